@@ -48,6 +48,7 @@ fn create_test_item(id: &str, source_name: &str) -> Item {
         link: format!("https://example.com/article/{id}"),
         summary: Some(format!("This is a benchmark summary for article {id}")),
         pub_date: Some(Utc::now()),
+        ..Default::default()
     }
 }
 
@@ -0,0 +1,434 @@
+use crate::config::{AlertChannel, AlertChannels, AlertRule, Config};
+use crate::error::{ClioError, ErrorContext};
+use crate::source::Item;
+use crate::writes;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Number of matching items listed in an alert before the rest are counted
+const LISTED_ITEMS: usize = 5;
+
+/// How long an item that was alerted on is remembered, so that feeds still
+/// carrying it do not trigger the alert again
+const REMEMBER_DAYS: i64 = 30;
+
+/// An item whose title contains an alert keyword, or that carries an alert tag
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AlertMatch {
+    pub source_name: String,
+    pub title: String,
+    pub link: String,
+    /// The keyword or tag found, as written in the rule
+    pub keyword: String,
+}
+
+/// One notification, grouping every match batched on a channel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alert {
+    pub channel: AlertChannel,
+    pub matches: Vec<AlertMatch>,
+}
+
+/// Alerts waiting for their channel's batching window, and items already
+/// alerted on
+///
+/// Kept between pulls in `~/.clio/alerts.json`.
+#[derive(Debug, Clone, Default)]
+pub struct AlertState {
+    path: PathBuf,
+    channels: BTreeMap<AlertChannel, ChannelState>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ChannelState {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_sent: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pending: Vec<AlertMatch>,
+    /// When each item was matched, by link
+    #[serde(default)]
+    seen: BTreeMap<String, DateTime<Utc>>,
+}
+
+/// Items in `items` whose titles match `rules`, grouped by channel
+///
+/// An item appears at most once per channel, under the first keyword found,
+/// or else the first tag it carries. Tags match ignoring case.
+pub fn matches(rules: &[AlertRule], items: &[Item]) -> BTreeMap<AlertChannel, Vec<AlertMatch>> {
+    let mut matches: BTreeMap<AlertChannel, Vec<AlertMatch>> = BTreeMap::new();
+    for item in items {
+        for rule in rules {
+            let Some(keyword) = matched(rule, item) else {
+                continue;
+            };
+            let channel = matches.entry(rule.channel).or_default();
+            if channel.iter().any(|found| found.link == item.link) {
+                continue;
+            }
+            channel.push(AlertMatch {
+                source_name: item.source_name.clone(),
+                title: item.title.clone(),
+                link: item.link.clone(),
+                keyword: keyword.to_string(),
+            });
+        }
+    }
+    matches
+}
+
+/// The first of `rule`'s keywords found in `item`'s title, or else the first
+/// of its tags the item carries
+pub fn matched<'a>(rule: &'a AlertRule, item: &Item) -> Option<&'a str> {
+    rule.keywords
+        .iter()
+        .find(|keyword| contains_word(&item.title, keyword))
+        .or_else(|| {
+            rule.tags
+                .iter()
+                .find(|tag| item.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+        })
+        .map(String::as_str)
+}
+
+/// Deliver `alert` on its channel
+pub fn send(alert: &Alert) -> Result<(), ClioError> {
+    match alert.channel {
+        AlertChannel::Terminal => {
+            eprintln!("{}", alert.summary());
+            for line in alert.body().lines() {
+                eprintln!("  {line}");
+            }
+            Ok(())
+        }
+        AlertChannel::Desktop => notify_desktop(&alert.summary(), &alert.body()),
+    }
+}
+
+impl Alert {
+    /// One line saying what matched
+    pub fn summary(&self) -> String {
+        let mut keywords: Vec<&str> = self.matches.iter().map(|m| m.keyword.as_str()).collect();
+        keywords.sort_unstable();
+        keywords.dedup();
+        match self.matches.len() {
+            1 => format!("New item matching {}", keywords.join(", ")),
+            count => format!("{count} new items matching {}", keywords.join(", ")),
+        }
+    }
+
+    /// The matching items, one per line, with the remainder counted
+    pub fn body(&self) -> String {
+        let mut lines: Vec<String> = self
+            .matches
+            .iter()
+            .take(LISTED_ITEMS)
+            .map(|m| format!("{}: {}", m.source_name, m.title))
+            .collect();
+        if self.matches.len() > LISTED_ITEMS {
+            lines.push(format!("and {} more", self.matches.len() - LISTED_ITEMS));
+        }
+        lines.join("\n")
+    }
+}
+
+impl AlertState {
+    /// The alert state in the clio configuration directory
+    pub fn open() -> Result<Self, ClioError> {
+        Self::at(Config::config_dir()?.join("alerts.json"))
+    }
+
+    /// Alert state stored at a specific path, empty if the file is missing
+    pub fn at(path: PathBuf) -> Result<Self, ClioError> {
+        let channels = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .clio_config_err(format!("Failed to parse alert state at {}", path.display()))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => {
+                return Err(ClioError::Config(format!(
+                    "Failed to read alert state at {}: {e}",
+                    path.display()
+                )));
+            }
+        };
+        Ok(Self { path, channels })
+    }
+
+    /// Queue `matches` not alerted on before, and take the alerts due at `now`
+    ///
+    /// A channel's queued matches are due once its batching window has passed
+    /// since its last alert, so a burst of matching items becomes one alert.
+    pub fn batch(
+        &mut self,
+        matches: BTreeMap<AlertChannel, Vec<AlertMatch>>,
+        windows: &AlertChannels,
+        now: DateTime<Utc>,
+    ) -> Vec<Alert> {
+        for (channel, matches) in matches {
+            let state = self.channels.entry(channel).or_default();
+            for found in matches {
+                if state.seen.insert(found.link.clone(), now).is_none() {
+                    state.pending.push(found);
+                }
+            }
+        }
+
+        let mut alerts = Vec::new();
+        for (&channel, state) in &mut self.channels {
+            state
+                .seen
+                .retain(|_, at| now - *at < Duration::days(REMEMBER_DAYS));
+
+            let window = Duration::minutes(windows.get(channel).batch_minutes as i64);
+            let due = state.last_sent.is_none_or(|sent| now - sent >= window);
+            if due && !state.pending.is_empty() {
+                state.last_sent = Some(now);
+                alerts.push(Alert {
+                    channel,
+                    matches: std::mem::take(&mut state.pending),
+                });
+            }
+        }
+        alerts
+    }
+
+    /// Write the state back to its file
+    ///
+    /// Nothing is written when filesystem writes are disabled.
+    pub fn save(&self) -> Result<(), ClioError> {
+        if writes::read_only().is_some() {
+            return Ok(());
+        }
+        let contents = serde_json::to_string_pretty(&self.channels)
+            .clio_config_err("Failed to serialize alert state")?;
+
+        if let Some(dir) = self.path.parent() {
+            Config::ensure_private_dir(dir)?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(contents.as_bytes()))
+            .clio_config_err(format!(
+                "Failed to write alert state at {}",
+                self.path.display()
+            ))
+    }
+}
+
+/// Whether `keyword` appears in `text` as whole words, ignoring case
+pub(crate) fn contains_word(text: &str, keyword: &str) -> bool {
+    let text = text.to_lowercase();
+    let keyword = keyword.trim().to_lowercase();
+    if keyword.is_empty() {
+        return false;
+    }
+    text.match_indices(&keyword).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + keyword.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+fn notify_desktop(summary: &str, body: &str) -> Result<(), ClioError> {
+    let mut command = if cfg!(target_os = "macos") {
+        let script = format!(
+            "display notification {} with title \"clio\" subtitle {}",
+            applescript_string(body),
+            applescript_string(summary)
+        );
+        let mut command = Command::new("osascript");
+        command.args(["-e", &script]);
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.args(["--app-name", "clio", summary, body]);
+        command
+    };
+
+    let program = command.get_program().to_string_lossy().into_owned();
+    let status = command.status().map_err(|e| {
+        ClioError::Notification(format!("Could not run {program}: {e}. Is it installed?"))
+    })?;
+    if !status.success() {
+        return Err(ClioError::Notification(format!(
+            "{program} failed ({status})"
+        )));
+    }
+    Ok(())
+}
+
+/// `text` as a quoted AppleScript string
+fn applescript_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn item(title: &str, link: &str) -> Item {
+        Item {
+            source_name: "Feed".to_string(),
+            title: title.to_string(),
+            link: link.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn rule(keywords: &[&str], channel: AlertChannel) -> AlertRule {
+        AlertRule {
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            tags: Vec::new(),
+            channel,
+        }
+    }
+
+    fn found(link: &str) -> AlertMatch {
+        AlertMatch {
+            source_name: "Feed".to_string(),
+            title: format!("CVE in {link}"),
+            link: link.to_string(),
+            keyword: "CVE".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_contains_word() {
+        assert!(contains_word("New CVE-2024-1234 in OpenSSL", "cve"));
+        assert!(contains_word(
+            "Security Advisory: patch now",
+            "security advisory"
+        ));
+        assert!(!contains_word("Cvent raises funding", "CVE"));
+        assert!(!contains_word("Anything at all", " "));
+    }
+
+    #[test]
+    fn test_matches_group_by_channel() {
+        let rules = [
+            rule(&["CVE", "security advisory"], AlertChannel::Desktop),
+            rule(&["openssl"], AlertChannel::Desktop),
+            rule(&["OpenSSL"], AlertChannel::Terminal),
+        ];
+        let items = [
+            item("OpenSSL CVE fixed", "https://example.com/1"),
+            item("Weekly notes", "https://example.com/2"),
+            item("Security advisory for OpenSSL", "https://example.com/3"),
+        ];
+
+        let matches = matches(&rules, &items);
+        let desktop = &matches[&AlertChannel::Desktop];
+        assert_eq!(desktop.len(), 2);
+        assert_eq!(desktop[0].keyword, "CVE");
+        assert_eq!(desktop[1].keyword, "security advisory");
+        assert_eq!(matches[&AlertChannel::Terminal].len(), 2);
+    }
+
+    #[test]
+    fn test_matches_tags() {
+        let rules = [AlertRule {
+            keywords: vec!["outage".to_string()],
+            tags: vec!["Critical".to_string()],
+            channel: AlertChannel::Terminal,
+        }];
+        let mut incident = item("API errors", "https://example.com/1");
+        incident.tags = vec!["incident".to_string(), "critical".to_string()];
+        let mut outage = item("Major outage", "https://example.com/2");
+        outage.tags = incident.tags.clone();
+        let items = [
+            incident,
+            outage,
+            item("Critical thinking", "https://example.com/3"),
+        ];
+
+        let found = &matches(&rules, &items)[&AlertChannel::Terminal];
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].keyword, "Critical");
+        assert_eq!(found[1].keyword, "outage");
+    }
+
+    #[test]
+    fn test_batch_groups_matches_into_one_alert() {
+        let mut state = AlertState::default();
+        let now = Utc::now();
+        let links: Vec<String> = (0..10)
+            .map(|i| format!("https://example.com/{i}"))
+            .collect();
+        let matches = BTreeMap::from([(
+            AlertChannel::Desktop,
+            links.iter().map(|link| found(link)).collect(),
+        )]);
+
+        let alerts = state.batch(matches.clone(), &AlertChannels::default(), now);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].matches.len(), 10);
+        assert_eq!(alerts[0].summary(), "10 new items matching CVE");
+        assert!(alerts[0].body().ends_with("and 5 more"));
+
+        // Items already alerted on are not alerted on again
+        let later = now + Duration::hours(1);
+        assert!(
+            state
+                .batch(matches, &AlertChannels::default(), later)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_batch_waits_for_window() {
+        let mut state = AlertState::default();
+        let mut windows = AlertChannels::default();
+        windows.desktop.batch_minutes = 30;
+        let now = Utc::now();
+        let batch = |link: &str| BTreeMap::from([(AlertChannel::Desktop, vec![found(link)])]);
+
+        assert_eq!(state.batch(batch("a"), &windows, now).len(), 1);
+        let soon = now + Duration::minutes(10);
+        assert!(state.batch(batch("b"), &windows, soon).is_empty());
+        assert!(state.batch(batch("c"), &windows, soon).is_empty());
+
+        let after = now + Duration::minutes(30);
+        let alerts = state.batch(BTreeMap::new(), &windows, after);
+        assert_eq!(alerts.len(), 1);
+        let links: Vec<&str> = alerts[0].matches.iter().map(|m| m.link.as_str()).collect();
+        assert_eq!(links, ["b", "c"]);
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("alerts.json");
+        let mut windows = AlertChannels::default();
+        windows.terminal.batch_minutes = 60;
+        let now = Utc::now();
+
+        let mut state = AlertState::at(path.clone()).unwrap();
+        let first = BTreeMap::from([(AlertChannel::Terminal, vec![found("a")])]);
+        assert_eq!(state.batch(first, &windows, now).len(), 1);
+        let second = BTreeMap::from([(AlertChannel::Terminal, vec![found("a"), found("b")])]);
+        assert!(state.batch(second, &windows, now).is_empty());
+        state.save().unwrap();
+
+        let mut state = AlertState::at(path).unwrap();
+        let alerts = state.batch(BTreeMap::new(), &windows, now + Duration::hours(1));
+        assert_eq!(alerts[0].matches, vec![found("b")]);
+    }
+
+    #[test]
+    fn test_applescript_string() {
+        assert_eq!(
+            applescript_string(r#"Say "hi" \ bye"#),
+            r#""Say \"hi\" \\ bye""#
+        );
+    }
+}
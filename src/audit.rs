@@ -0,0 +1,189 @@
+use crate::config::Config;
+use crate::error::{ClioError, ErrorContext};
+use crate::writes;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+
+/// One state-changing operation, as recorded in the audit log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub at: DateTime<Utc>,
+    /// The clio command that made the change, such as "purge"
+    pub command: String,
+    /// What changed, such as "mark read"
+    pub action: String,
+    /// Number of items or sources affected
+    pub rows: u64,
+    /// Context such as a source name or filter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// Links of the affected items, when known
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<String>,
+}
+
+impl AuditEntry {
+    /// Record that `command` performed `action` on `rows` items or sources, now
+    pub fn new(command: &str, action: &str, rows: usize) -> Self {
+        Self {
+            at: Utc::now(),
+            command: command.to_string(),
+            action: action.to_string(),
+            rows: rows as u64,
+            detail: None,
+            links: Vec::new(),
+        }
+    }
+
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Note the links of the affected items, so they can be searched for later
+    pub fn with_links(mut self, links: Vec<String>) -> Self {
+        self.links = links;
+        self
+    }
+}
+
+/// Append-only log of state changes, one JSON object per line
+///
+/// Lives at `~/.clio/audit.log` so that changes to the configuration and to
+/// stored items are recorded in one place.
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    /// The audit log in the clio configuration directory
+    ///
+    /// Nothing is created until the first entry is written.
+    pub fn open() -> Result<Self, ClioError> {
+        Ok(Self::at(Config::config_dir()?.join("audit.log")))
+    }
+
+    /// An audit log at a specific path
+    pub fn at(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Write `entry` at the end of the log
+    ///
+    /// Nothing is written when filesystem writes are disabled.
+    pub fn append(&self, entry: &AuditEntry) -> Result<(), ClioError> {
+        if writes::read_only().is_some() {
+            return Ok(());
+        }
+        let mut line =
+            serde_json::to_string(entry).clio_config_err("Failed to serialize audit entry")?;
+        line.push('\n');
+
+        if let Some(dir) = self.path.parent() {
+            Config::ensure_private_dir(dir)?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .mode(0o600)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .clio_config_err(format!(
+                "Failed to write audit log at {}",
+                self.path.display()
+            ))
+    }
+
+    /// The most recent `count` entries, oldest first
+    ///
+    /// Lines that cannot be parsed are skipped rather than hiding the rest of
+    /// the log.
+    pub fn tail(&self, count: usize) -> Result<Vec<AuditEntry>, ClioError> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(ClioError::Config(format!(
+                    "Failed to read audit log at {}: {e}",
+                    self.path.display()
+                )));
+            }
+        };
+
+        let entries: Vec<AuditEntry> = contents
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+        let skip = entries.len().saturating_sub(count);
+        Ok(entries.into_iter().skip(skip).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_append_and_tail() {
+        let dir = TempDir::new().unwrap();
+        let log = AuditLog::at(dir.path().join("audit.log"));
+        assert!(log.tail(10).unwrap().is_empty());
+
+        for rows in 1..=3 {
+            log.append(
+                &AuditEntry::new("purge", "delete", rows)
+                    .with_detail("older than 90 days")
+                    .with_links(vec![format!("https://example.com/{rows}")]),
+            )
+            .unwrap();
+        }
+
+        let entries = log.tail(2).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].rows, 2);
+        assert_eq!(entries[1].rows, 3);
+        assert_eq!(entries[1].command, "purge");
+        assert_eq!(entries[1].detail.as_deref(), Some("older than 90 days"));
+        assert_eq!(entries[1].links, vec!["https://example.com/3".to_string()]);
+    }
+
+    #[test]
+    fn test_append_creates_private_directory() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().unwrap();
+        let clio_dir = dir.path().join(".clio");
+        let log = AuditLog::at(clio_dir.join("audit.log"));
+        assert!(log.tail(10).unwrap().is_empty());
+        assert!(!clio_dir.exists());
+
+        log.append(&AuditEntry::new("purge", "delete", 1)).unwrap();
+        let mode = fs::metadata(&clio_dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+        assert_eq!(log.tail(10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_tail_skips_corrupt_lines() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("audit.log");
+        let log = AuditLog::at(path.clone());
+        log.append(&AuditEntry::new("list", "star", 1)).unwrap();
+        fs::write(&path, fs::read_to_string(&path).unwrap() + "{truncated\n").unwrap();
+        log.append(&AuditEntry::new("list", "archive", 2)).unwrap();
+
+        let actions: Vec<String> = log
+            .tail(10)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.action)
+            .collect();
+        assert_eq!(actions, vec!["star", "archive"]);
+    }
+}
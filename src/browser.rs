@@ -0,0 +1,14 @@
+use crate::error::ClioError;
+
+/// Open a link in the system's default browser
+///
+/// The `BROWSER` environment variable, when set, takes precedence over the
+/// platform default (`open` on macOS, `xdg-open` and friends on Linux).
+pub fn open(link: &str) -> Result<(), ClioError> {
+    webbrowser::open(link).map_err(|e| {
+        ClioError::Browser(format!(
+            "Could not launch a browser for {link}: {e}. \
+             Set BROWSER to your browser's command, or copy the link into one"
+        ))
+    })
+}
@@ -0,0 +1,245 @@
+use crate::config::Config;
+use crate::error::{ClioError, ErrorContext};
+use crate::writes;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Whether feed responses are being recorded or replayed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Fetch over the network and save every response
+    Record,
+    /// Answer every request from the saved responses, without the network
+    Replay,
+}
+
+/// Feed HTTP responses saved to a file, VCR style
+///
+/// Set `CLIO_RECORD=1` to record the responses of a run and `CLIO_REPLAY=1`
+/// to play them back later, so a pull can be reproduced exactly and offline.
+/// The cassette lives at `CLIO_CASSETTE`, or `~/.clio/cassette.json`.
+#[derive(Debug)]
+pub struct Cassette {
+    path: PathBuf,
+    mode: CassetteMode,
+    state: Mutex<State>,
+}
+
+/// One request and the response it received
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Interaction {
+    url: String,
+    /// HTTP status code
+    status: u16,
+    /// Response body, when it is valid UTF-8
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    /// Response body in base64, when it is not valid UTF-8
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body_base64: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CassetteFile {
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    interactions: Vec<Interaction>,
+    /// Number of responses already replayed for each URL
+    replayed: HashMap<String, usize>,
+}
+
+impl Cassette {
+    /// The cassette selected by `CLIO_RECORD` or `CLIO_REPLAY`, if either is set
+    pub fn from_env() -> Result<Option<Self>, ClioError> {
+        let mode = match (env_flag("CLIO_RECORD"), env_flag("CLIO_REPLAY")) {
+            (false, false) => return Ok(None),
+            (true, true) => {
+                return Err(ClioError::Config(
+                    "CLIO_RECORD and CLIO_REPLAY cannot be set at the same time".to_string(),
+                ));
+            }
+            (true, false) => CassetteMode::Record,
+            (false, true) => CassetteMode::Replay,
+        };
+
+        if mode == CassetteMode::Record {
+            writes::ensure_allowed("record a cassette")?;
+        }
+        let path = Self::default_path()?;
+        if mode == CassetteMode::Record && path.starts_with(Config::config_dir()?) {
+            Config::ensure_config_dir()?;
+        }
+
+        match mode {
+            CassetteMode::Record => Ok(Some(Self::record(path))),
+            CassetteMode::Replay => Self::replay(path).map(Some),
+        }
+    }
+
+    /// Where the cassette lives: `CLIO_CASSETTE`, or `~/.clio/cassette.json`
+    pub fn default_path() -> Result<PathBuf, ClioError> {
+        match env::var_os("CLIO_CASSETTE") {
+            Some(path) if !path.is_empty() => Ok(PathBuf::from(path)),
+            _ => Ok(Config::config_dir()?.join("cassette.json")),
+        }
+    }
+
+    /// Start a new recording, replacing the cassette at `path` on the first response
+    pub fn record(path: PathBuf) -> Self {
+        Self {
+            path,
+            mode: CassetteMode::Record,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Load the cassette at `path` for replay
+    pub fn replay(path: PathBuf) -> Result<Self, ClioError> {
+        let contents = fs::read_to_string(&path)
+            .clio_config_err(format!("Failed to read cassette at {}", path.display()))?;
+        let file: CassetteFile = serde_json::from_str(&contents)
+            .clio_parse_err(format!("Invalid cassette at {}", path.display()))?;
+
+        Ok(Self {
+            path,
+            mode: CassetteMode::Replay,
+            state: Mutex::new(State {
+                interactions: file.interactions,
+                replayed: HashMap::new(),
+            }),
+        })
+    }
+
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    /// The recorded status and body for the next request to `url`
+    ///
+    /// Responses to the same URL are replayed in the order they were recorded;
+    /// once they run out, the last one is repeated.
+    pub fn play(&self, url: &str) -> Result<(u16, Vec<u8>), ClioError> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let responses: Vec<&Interaction> = state
+            .interactions
+            .iter()
+            .filter(|interaction| interaction.url == url)
+            .collect();
+        let Some(&last) = responses.last() else {
+            return Err(ClioError::Network(format!(
+                "No response for {url} in cassette {}",
+                self.path.display()
+            )));
+        };
+
+        let played = state.replayed.get(url).copied().unwrap_or(0);
+        let interaction = responses.get(played).copied().unwrap_or(last).clone();
+        state.replayed.insert(url.to_string(), played + 1);
+
+        let body = match (&interaction.body, &interaction.body_base64) {
+            (_, Some(encoded)) => BASE64
+                .decode(encoded)
+                .clio_parse_err(format!("Invalid response body for {url} in cassette"))?,
+            (Some(body), None) => body.clone().into_bytes(),
+            (None, None) => Vec::new(),
+        };
+        Ok((interaction.status, body))
+    }
+
+    /// Save the response received for `url`
+    ///
+    /// The cassette file is rewritten after every response, so an interrupted
+    /// run still leaves everything recorded so far.
+    pub fn save(&self, url: &str, status: u16, body: &[u8]) -> Result<(), ClioError> {
+        let (body, body_base64) = match std::str::from_utf8(body) {
+            Ok(text) => (Some(text.to_string()), None),
+            Err(_) => (None, Some(BASE64.encode(body))),
+        };
+
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.interactions.push(Interaction {
+            url: url.to_string(),
+            status,
+            body,
+            body_base64,
+        });
+
+        let file = CassetteFile {
+            interactions: state.interactions.clone(),
+        };
+        let contents =
+            serde_json::to_string_pretty(&file).clio_config_err("Failed to serialize cassette")?;
+        fs::write(&self.path, contents).clio_config_err(format!(
+            "Failed to write cassette at {}",
+            self.path.display()
+        ))
+    }
+}
+
+/// Whether an on/off environment variable is set to something other than "0"
+fn env_flag(name: &str) -> bool {
+    env::var(name).is_ok_and(|value| !value.is_empty() && value != "0")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_record_then_replay() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("cassette.json");
+
+        let recorder = Cassette::record(path.clone());
+        recorder
+            .save("https://example.com/feed.xml", 200, b"<rss>first</rss>")
+            .unwrap();
+        recorder
+            .save("https://example.com/feed.xml", 200, b"<rss>second</rss>")
+            .unwrap();
+        recorder
+            .save("https://example.com/binary", 503, &[0xff, 0xfe])
+            .unwrap();
+
+        let player = Cassette::replay(path).unwrap();
+        assert_eq!(player.mode(), CassetteMode::Replay);
+        let play = |url| player.play(url).unwrap();
+        assert_eq!(
+            play("https://example.com/feed.xml"),
+            (200, b"<rss>first</rss>".to_vec())
+        );
+        assert_eq!(
+            play("https://example.com/feed.xml"),
+            (200, b"<rss>second</rss>".to_vec())
+        );
+        // The last response repeats once the recorded ones run out
+        assert_eq!(
+            play("https://example.com/feed.xml"),
+            (200, b"<rss>second</rss>".to_vec())
+        );
+        assert_eq!(play("https://example.com/binary"), (503, vec![0xff, 0xfe]));
+
+        let err = player.play("https://example.com/other").unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("No response for https://example.com/other")
+        );
+    }
+
+    #[test]
+    fn test_replay_missing_cassette() {
+        let dir = TempDir::new().unwrap();
+        let err = Cassette::replay(dir.path().join("missing.json")).unwrap_err();
+        assert!(err.to_string().contains("Failed to read cassette"));
+    }
+}
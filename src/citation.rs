@@ -0,0 +1,326 @@
+use crate::source::Item;
+use chrono::Datelike;
+use regex::Regex;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+static DOI: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)\b(10\.\d{4,9}/[^\s"'<>]+)"#).expect("valid DOI pattern"));
+
+/// The DOI of an item's paper, or the one its link or summary mentions
+///
+/// Journal feeds either link to `doi.org` or quote the DOI in the summary.
+/// Trailing punctuation from the surrounding sentence is dropped.
+pub fn doi(item: &Item) -> Option<String> {
+    if let Some(doi) = item.paper.as_ref().and_then(|paper| paper.doi.clone()) {
+        return Some(doi);
+    }
+    [Some(item.link.as_str()), item.summary.as_deref()]
+        .into_iter()
+        .flatten()
+        .find_map(|text| DOI.captures(text))
+        .map(|captures| {
+            captures[1]
+                .trim_end_matches(['.', ',', ';', ':', ')', ']'])
+                .to_string()
+        })
+}
+
+/// Render items as BibTeX entries, for citation managers
+///
+/// Items with a DOI become `@article` entries and the rest `@misc`; the
+/// source name is kept in `howpublished` so the feed stays traceable.
+/// Enriched items also carry their journal.
+pub fn to_bibtex(items: &[Item]) -> String {
+    let mut keys = CitationKeys::default();
+    let mut bibtex = String::new();
+    for item in items {
+        let doi = doi(item);
+        let kind = if doi.is_some() { "article" } else { "misc" };
+        let mut fields = vec![("title", format!("{{{}}}", escape_latex(&item.title)))];
+        if !item.authors.is_empty() {
+            let authors: Vec<String> = item.authors.iter().map(|a| bibtex_name(a)).collect();
+            fields.push(("author", escape_latex(&authors.join(" and "))));
+        }
+        if let Some(date) = item.pub_date {
+            fields.push(("year", date.year().to_string()));
+            fields.push(("month", MONTHS[date.month0() as usize].to_string()));
+        }
+        if let Some(journal) = item.paper.as_ref().and_then(|paper| paper.journal.as_ref()) {
+            fields.push(("journal", escape_latex(journal)));
+        }
+        fields.push(("url", item.link.clone()));
+        if let Some(doi) = doi {
+            fields.push(("doi", doi));
+        }
+        fields.push(("howpublished", escape_latex(&item.source_name)));
+
+        bibtex.push_str(&format!("@{kind}{{{},\n", keys.next(item)));
+        for (name, value) in fields {
+            // Month macros are bare; everything else is braced
+            if name == "month" {
+                bibtex.push_str(&format!("  {name} = {value},\n"));
+            } else {
+                bibtex.push_str(&format!("  {name} = {{{value}}},\n"));
+            }
+        }
+        bibtex.push_str("}\n\n");
+    }
+    bibtex
+}
+
+/// Render items as a CSL-JSON array, for Zotero, Pandoc and other CSL tools
+pub fn to_csl_json(items: &[Item]) -> String {
+    let mut keys = CitationKeys::default();
+    let entries: Vec<Value> = items
+        .iter()
+        .map(|item| {
+            let doi = doi(item);
+            let mut entry = json!({
+                "id": keys.next(item),
+                "type": if doi.is_some() { "article-journal" } else { "webpage" },
+                "title": item.title,
+                "URL": item.link,
+                "container-title": item
+                    .paper
+                    .as_ref()
+                    .and_then(|paper| paper.journal.as_ref())
+                    .unwrap_or(&item.source_name),
+            });
+            if let Some(text) = item
+                .paper
+                .as_ref()
+                .and_then(|paper| paper.abstract_text.as_ref())
+            {
+                entry["abstract"] = json!(text);
+            }
+            if !item.authors.is_empty() {
+                entry["author"] = item.authors.iter().map(|a| csl_name(a)).collect();
+            }
+            if let Some(date) = item.pub_date {
+                entry["issued"] =
+                    json!({ "date-parts": [[date.year(), date.month(), date.day()]] });
+            }
+            if let Some(doi) = doi {
+                entry["DOI"] = json!(doi);
+            }
+            entry
+        })
+        .collect();
+    serde_json::to_string_pretty(&entries).expect("JSON values always serialize")
+}
+
+/// Unique citation keys such as `evans2024wizard`
+#[derive(Default)]
+struct CitationKeys {
+    used: HashMap<String, usize>,
+}
+
+impl CitationKeys {
+    fn next(&mut self, item: &Item) -> String {
+        let author = item
+            .authors
+            .first()
+            .map(|name| family_name(name))
+            .unwrap_or(&item.source_name);
+        let year = item
+            .pub_date
+            .map(|date| date.year().to_string())
+            .unwrap_or_default();
+        let word = item
+            .title
+            .split_whitespace()
+            .map(key_part)
+            .find(|word| word.len() > 3)
+            .unwrap_or_default();
+        let mut key = format!("{}{year}{word}", key_part(author));
+        if key.is_empty() {
+            key = "item".to_string();
+        }
+
+        let count = self.used.entry(key.clone()).or_default();
+        *count += 1;
+        match *count {
+            1 => key,
+            // evans2024wizard, evans2024wizardb, evans2024wizardc, ...
+            n => format!("{key}{}", suffix(n)),
+        }
+    }
+}
+
+fn suffix(n: usize) -> String {
+    if n <= 26 {
+        char::from(b'a' + (n - 1) as u8).to_string()
+    } else {
+        n.to_string()
+    }
+}
+
+fn key_part(text: &str) -> String {
+    text.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+/// The family name of a writer given as "Given Family" or "Family, Given"
+fn family_name(name: &str) -> &str {
+    match name.split_once(',') {
+        Some((family, _)) => family.trim(),
+        None => name.split_whitespace().last().unwrap_or(name),
+    }
+}
+
+/// "Julia Evans" as BibTeX's unambiguous "Evans, Julia"
+fn bibtex_name(name: &str) -> String {
+    let name = name.trim();
+    if name.contains(',') {
+        return name.to_string();
+    }
+    match name.rsplit_once(' ') {
+        Some((given, family)) => format!("{family}, {}", given.trim()),
+        None => name.to_string(),
+    }
+}
+
+fn csl_name(name: &str) -> Value {
+    let name = name.trim();
+    let (family, given) = match name.split_once(',') {
+        Some((family, given)) => (family.trim(), given.trim()),
+        None => match name.rsplit_once(' ') {
+            Some((given, family)) => (family, given.trim()),
+            None => return json!({ "literal": name }),
+        },
+    };
+    json!({ "family": family, "given": given })
+}
+
+/// Escape the characters LaTeX treats specially
+fn escape_latex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str(r"\textbackslash{}"),
+            '~' => escaped.push_str(r"\textasciitilde{}"),
+            '^' => escaped.push_str(r"\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::Paper;
+    use chrono::{TimeZone, Utc};
+
+    fn paper() -> Item {
+        Item {
+            source_name: "arXiv cs.PL".to_string(),
+            title: "Ownership types & 100% safe borrowing".to_string(),
+            link: "https://arxiv.org/abs/2401.00001".to_string(),
+            summary: Some("Published as doi:10.1145/3591227.".to_string()),
+            authors: vec!["Julia Evans".to_string(), "Knuth, Donald E.".to_string()],
+            pub_date: Some(Utc.with_ymd_and_hms(2024, 3, 5, 9, 0, 0).unwrap()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_doi() {
+        assert_eq!(doi(&paper()).as_deref(), Some("10.1145/3591227"));
+
+        let item = Item {
+            link: "https://doi.org/10.1000/xyz-123".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(doi(&item).as_deref(), Some("10.1000/xyz-123"));
+        assert_eq!(doi(&Item::default()), None);
+    }
+
+    #[test]
+    fn test_to_bibtex() {
+        let blog_post = Item {
+            source_name: "Blog".to_string(),
+            title: "Notes".to_string(),
+            link: "https://example.com/notes".to_string(),
+            ..Default::default()
+        };
+        let bibtex = to_bibtex(&[paper(), paper(), blog_post]);
+        assert!(bibtex.starts_with(
+            "@article{evans2024ownership,\n  \
+             title = {{Ownership types \\& 100\\% safe borrowing}},\n  \
+             author = {Evans, Julia and Knuth, Donald E.},\n  \
+             year = {2024},\n  \
+             month = mar,\n  \
+             url = {https://arxiv.org/abs/2401.00001},\n  \
+             doi = {10.1145/3591227},\n  \
+             howpublished = {arXiv cs.PL},\n}\n\n"
+        ));
+        // Keys stay unique
+        assert!(bibtex.contains("@article{evans2024ownershipb,\n"));
+        assert!(bibtex.contains("@misc{blognotes,\n  title = {{Notes}},\n"));
+    }
+
+    #[test]
+    fn test_to_csl_json() {
+        let csl: Value = serde_json::from_str(&to_csl_json(&[paper()])).unwrap();
+        assert_eq!(
+            csl,
+            json!([{
+                "id": "evans2024ownership",
+                "type": "article-journal",
+                "title": "Ownership types & 100% safe borrowing",
+                "URL": "https://arxiv.org/abs/2401.00001",
+                "container-title": "arXiv cs.PL",
+                "author": [
+                    { "family": "Evans", "given": "Julia" },
+                    { "family": "Knuth", "given": "Donald E." }
+                ],
+                "issued": { "date-parts": [[2024, 3, 5]] },
+                "DOI": "10.1145/3591227"
+            }])
+        );
+    }
+
+    #[test]
+    fn test_enriched_citation() {
+        let item = Item {
+            paper: Some(Paper {
+                doi: Some("10.1000/journal.1".to_string()),
+                journal: Some("Journal of Borrowing".to_string()),
+                abstract_text: Some("We borrow.".to_string()),
+                ..Default::default()
+            }),
+            ..paper()
+        };
+        // The looked-up DOI wins over the one in the summary
+        assert_eq!(doi(&item).as_deref(), Some("10.1000/journal.1"));
+        assert!(
+            to_bibtex(std::slice::from_ref(&item))
+                .contains("  journal = {Journal of Borrowing},\n")
+        );
+
+        let csl: Value = serde_json::from_str(&to_csl_json(&[item])).unwrap();
+        assert_eq!(csl[0]["container-title"], "Journal of Borrowing");
+        assert_eq!(csl[0]["abstract"], "We borrow.");
+    }
+
+    #[test]
+    fn test_escape_latex() {
+        assert_eq!(
+            escape_latex(r"a_b {c} ~^\"),
+            r"a\_b \{c\} \textasciitilde{}\textasciicircum{}\textbackslash{}"
+        );
+    }
+}
@@ -1,4 +1,12 @@
-use clap::{Parser, Subcommand};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use clap::{ArgGroup, Parser, Subcommand};
+use clio::color::ColorChoice;
+use clio::filter::Filter;
+use clio::health::DEFAULT_MAX_PULL_AGE_HOURS;
+use clio::import::ImportFormat;
+use clio::opml::ConflictPolicy;
+use clio::rules::Replay;
+use std::path::PathBuf;
 
 /// A simple command-line feed aggregator
 ///
@@ -11,61 +19,2055 @@ pub struct Cli {
     #[command(subcommand)]
     pub command: Command,
 
+    /// Configuration file to use instead of ~/.clio/config.toml, such as
+    /// one per profile. Also read from CLIO_CONFIG
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
     /// Suppress all non-error output
     #[arg(long, short = 'q', global = true)]
     pub quiet: bool,
+
+    /// Screen-reader friendly output: labeled lines instead of symbols,
+    /// aligned columns, and the interactive list
+    #[arg(long, global = true)]
+    pub plain: bool,
+
+    /// Print machine-readable JSON to standard output, with progress and
+    /// other human output on standard error, for list, search, pull, stats,
+    /// status and source list
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Answer yes to confirmation prompts before destructive operations
+    #[arg(long, short = 'y', global = true)]
+    pub yes: bool,
+
+    /// Never write to the filesystem: no configuration, logs, or downloads.
+    /// Turned on automatically when the home directory is read-only
+    #[arg(long, global = true)]
+    pub no_write: bool,
+
+    /// When to color output; auto colors terminals unless NO_COLOR is set
+    #[arg(long, global = true, value_enum, value_name = "WHEN", default_value_t)]
+    pub color: ColorChoice,
 }
 
 #[derive(Subcommand, Debug)]
 pub enum Command {
+    /// Create ~/.clio/config.toml, or the file given with --config
+    ///
+    /// In a terminal, asks for the sites or feeds to follow (finding the feed
+    /// of a site from its home page) and where to store items. Otherwise, or
+    /// with --example, writes example sources to edit instead.
+    Init {
+        /// Replace an existing configuration
+        #[arg(long)]
+        force: bool,
+
+        /// Write the example configuration without asking anything
+        #[arg(long)]
+        example: bool,
+    },
+
     /// Fetch latest content from all configured sources
     ///
-    /// Fetches the latest content from all configured RSS and Atom feeds.
+    /// Fetches the latest content from all configured RSS and Atom feeds, or
+    /// only the sources given with --source.
     /// Sources are fetched in parallel with a 10-second timeout per source.
     /// Failed sources will be reported but won't stop other sources from being fetched.
-    Pull,
+    /// New items are stored, and --json lists only those.
+    /// Progress and the summary are written to stderr, keeping stdout for data.
+    Pull {
+        /// Fetch only sources with this name, or matching this pattern where
+        /// `*` matches anything and `?` one character (e.g. "Hacker*").
+        /// Repeat to select more sources
+        #[arg(long = "source", value_name = "NAME")]
+        sources: Vec<String>,
+
+        /// Record every HTTP request of this run (URL, status, timing, bytes,
+        /// cache hit or miss) to a JSON file
+        #[arg(long, value_name = "FILE")]
+        trace: Option<PathBuf>,
+    },
+
+    /// Poll sources continuously and print new items as they arrive
+    ///
+    /// Like `tail -f` for your feeds: sources are fetched every --interval
+    /// minutes and each item is printed once, unless it is already stored.
+    /// Items are written to stdout, one line each (one JSON object per line
+    /// with --json). Press Ctrl-C to stop.
+    /// With --health-port, also serves /healthz, which fails when polls stop
+    /// finishing within twice the interval, for container orchestrators.
+    Watch {
+        /// Watch only sources with this name, or matching this pattern where
+        /// `*` matches anything and `?` one character. Repeat to select more
+        #[arg(long = "source", value_name = "NAME")]
+        sources: Vec<String>,
+
+        /// Minutes between polls
+        #[arg(
+            long,
+            value_name = "MINUTES",
+            default_value_t = 5,
+            value_parser = clap::value_parser!(u32).range(1..)
+        )]
+        interval: u32,
+
+        /// Serve /healthz on this port, on all addresses
+        #[arg(long, value_name = "PORT")]
+        health_port: Option<u16>,
+
+        /// Fail /healthz when no source was fetched successfully within this many hours
+        #[arg(
+            long,
+            value_name = "HOURS",
+            default_value_t = DEFAULT_MAX_PULL_AGE_HOURS,
+            requires = "health_port"
+        )]
+        max_pull_age: u32,
+    },
 
     /// List fetched items in chronological order
     ///
     /// Displays all fetched items in reverse chronological order (newest first).
     /// Use arrow keys or j/k to navigate, q to quit.
+    /// Mark items with space, or press v to select a range, then act on them all:
+    /// r marks read, u unread, s stars, a archives, and o opens in the browser.
+    /// Press p to open the reader pane, which shows item thumbnails in
+    /// terminals supporting kitty, iTerm2, or sixel graphics.
+    /// With --plain, or when TERM is "dumb", items are printed as labeled lines instead.
     /// If no items are available, run 'clio pull' first to fetch content.
-    List,
+    List {
+        /// Only show starred items
+        #[arg(long)]
+        starred: bool,
+
+        /// Only show unread items
+        #[arg(long)]
+        unread: bool,
+
+        /// Only show items from this source; repeat for several sources
+        #[arg(long = "source", value_name = "NAME")]
+        sources: Vec<String>,
+
+        /// Only show items published since a date (2024-05-01) or within a
+        /// period (12h, 7d, 2w)
+        #[arg(long, value_name = "WHEN", value_parser = parse_since)]
+        since: Option<DateTime<Utc>>,
+
+        /// Show at most this many of the newest items
+        #[arg(long, short = 'n', value_name = "N")]
+        limit: Option<usize>,
+
+        /// Show the items stored by a date (2025-05-01) or a period ago (7d),
+        /// with the read state they had then; --unread shows what was unread
+        #[arg(long, value_name = "WHEN", value_parser = parse_since)]
+        as_of: Option<DateTime<Utc>>,
+
+        /// Only show items written by this author, across all sources
+        #[arg(long, value_name = "NAME")]
+        author: Option<String>,
+
+        /// Fold items with the same title (ignoring case and punctuation) into one row
+        #[arg(long)]
+        collapse_duplicates: bool,
+
+        /// Only fold duplicates published within this many hours of each other
+        #[arg(
+            long,
+            value_name = "HOURS",
+            default_value_t = 24,
+            requires = "collapse_duplicates"
+        )]
+        collapse_window: u32,
+    },
+
+    /// Show what's trending across your sources right now
+    ///
+    /// Fetches all active sources and ranks recent stories by points and
+    /// comments (from aggregator feeds such as hnrss.org or feeds with
+    /// slash:comments), multiplied by the number of sources carrying them.
+    Top {
+        /// Only consider items published within this many hours
+        #[arg(long, value_name = "HOURS", default_value_t = 24)]
+        hours: u32,
+
+        /// Number of stories to show
+        #[arg(long, short = 'n', value_name = "N", default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Show a feed's latest items without subscribing to it
+    ///
+    /// Fetches the feed at URL, or the first one a site's page advertises,
+    /// and prints its title, how often it posts and its newest items, with
+    /// warnings about anything that would trouble a subscription: a redirect,
+    /// entries left out, missing or future dates, repeated links. Nothing is
+    /// added to the configuration or stored.
+    Preview {
+        /// Feed or site URL; example.com is read as https://example.com
+        #[arg(value_name = "URL")]
+        url: String,
+
+        /// Number of items to show
+        #[arg(long, short = 'n', value_name = "N", default_value_t = 10)]
+        limit: usize,
+    },
+
+    /// Find the feeds a site advertises, and optionally subscribe to one
+    ///
+    /// Reads the page at URL and lists the RSS and Atom feeds it links to
+    /// with <link rel="alternate">. With --add, a feed is added to the
+    /// configuration; when the page lists several, pick one in the terminal
+    /// or give its number with --feed.
+    Discover {
+        /// Site URL; example.com is read as https://example.com
+        #[arg(value_name = "URL")]
+        url: String,
+
+        /// Add a feed found to the configuration
+        #[arg(long)]
+        add: bool,
+
+        /// Number of the feed to add, as listed
+        #[arg(long, value_name = "N", requires = "add")]
+        feed: Option<usize>,
+
+        /// Name for the added source instead of the feed's title
+        #[arg(long, value_name = "NAME", requires = "add")]
+        name: Option<String>,
+    },
 
     /// Open an item in your default browser
     ///
-    /// Opens the specified item in your system's default web browser.
-    /// Use the item ID shown in the 'list' command output.
+    /// Opens the specified item in your system's default web browser (or the
+    /// command in $BROWSER) and marks it read. Item IDs are the last column
+    /// of 'clio list' output when piped; an unambiguous prefix is enough.
+    /// With --unread, opens the newest unread items in tabs instead. Without
+    /// an ID, pick from recent items by typing part of a title or source.
     Open {
-        /// The ID of the item to open
+        /// The ID of the item to open, or the start of it
+        #[arg(value_name = "ITEM_ID")]
+        item_id: Option<String>,
+
+        /// Open the newest unread items, each in its own tab
+        #[arg(long, conflicts_with = "item_id")]
+        unread: bool,
+
+        /// Only open unread items from this source; repeat for several sources
+        #[arg(long = "source", value_name = "NAME", requires = "unread")]
+        sources: Vec<String>,
+
+        /// Open at most this many unread items
+        #[arg(long, short = 'n', value_name = "N", default_value_t = 10)]
+        limit: usize,
+    },
+
+    /// Copy an item's link to the clipboard
+    ///
+    /// Copies the bare URL, or with --markdown a `[title](url)` link, to paste
+    /// into chat or notes. Unlike 'clio open', the item stays unread.
+    Copy {
+        /// The ID of the item to copy, or the start of it
+        #[arg(value_name = "ITEM_ID")]
+        item_id: String,
+
+        /// Copy a Markdown link with the item's title instead of the bare URL
+        #[arg(long)]
+        markdown: bool,
+    },
+
+    /// Send an item to a read-later destination
+    ///
+    /// Destinations are [[read_later]] tables in the configuration: a file
+    /// collecting Markdown links, a webhook, or a command. Without --to, the
+    /// first one is used.
+    Save {
+        /// The ID of the item to save, or the start of it
+        #[arg(value_name = "ITEM_ID")]
+        item_id: String,
+
+        /// Name of the destination to send the item to
+        #[arg(long, value_name = "NAME")]
+        to: Option<String>,
+    },
+
+    /// Show everything stored about an item
+    ///
+    /// Prints the title, source, link, publication date, read state, tags and
+    /// the full summary, to read before deciding to open it.
+    Show {
+        /// The ID of the item to show, or the start of it
         #[arg(value_name = "ITEM_ID")]
         item_id: String,
     },
+
+    /// Open a random unread item, for serendipitous reading
+    ///
+    /// Marks the item read, like 'clio open'. With --print, shows it instead.
+    Random {
+        /// Only pick from sources matching tag:NAME or source:NAME (repeatable)
+        #[arg(long, value_name = "FILTER")]
+        filter: Vec<Filter>,
+
+        /// Print the item's details instead of opening it
+        #[arg(long)]
+        print: bool,
+    },
+
+    /// Search stored items by title and summary
+    ///
+    /// Matches items whose title or summary contains every word of the query,
+    /// ignoring case. Best matches come first: words in the title count for
+    /// more than in the summary, and newer items rank higher. Archived items
+    /// are left out.
+    Search {
+        /// Words to look for
+        #[arg(value_name = "QUERY", required = true)]
+        query: Vec<String>,
+
+        /// Only show unread items
+        #[arg(long)]
+        unread: bool,
+
+        /// Only show items from this source
+        #[arg(long, value_name = "NAME")]
+        source: Option<String>,
+
+        /// Only show items published since a date (2024-05-01) or within a
+        /// period (12h, 7d, 2w)
+        #[arg(long, value_name = "WHEN", value_parser = parse_since)]
+        since: Option<DateTime<Utc>>,
+
+        /// Search the sources of every profile (each .toml file next to the
+        /// configuration) at once, labeling results with their profiles
+        #[arg(long)]
+        all_profiles: bool,
+
+        /// Also search the yearly archive files old items were moved to
+        #[arg(long)]
+        include_archives: bool,
+    },
+
+    /// Show how many items are unread, in total and per source
+    ///
+    /// Archived items are not counted. With --total, only the number is
+    /// printed, for a shell prompt or tmux status bar.
+    Status {
+        /// Print only the total number of unread items
+        #[arg(long)]
+        total: bool,
+    },
+
+    /// Manage configured sources
+    #[command(visible_alias = "sources")]
+    Source {
+        #[command(subcommand)]
+        command: SourceCommand,
+    },
+
+    /// Download podcast episodes and other enclosures from configured sources
+    ///
+    /// Downloads run in parallel and are saved under DIR/<source>/. Interrupted
+    /// downloads resume on the next run, and enclosures that were already
+    /// downloaded are skipped. Local paths are recorded on the stored items.
+    /// Feeds that return the same body as on the last run are not parsed again.
+    FetchEnclosures {
+        /// Only download enclosures from this source
+        #[arg(long, value_name = "NAME")]
+        source: Option<String>,
+
+        /// Directory to save downloads in [default: ~/Podcasts]
+        #[arg(long, value_name = "DIR")]
+        dir: Option<PathBuf>,
+
+        /// Skip enclosures larger than this many megabytes
+        #[arg(long, value_name = "MB", default_value_t = 500)]
+        max_size: u64,
+
+        /// Number of downloads to run at the same time
+        #[arg(long, value_name = "N", default_value_t = 4)]
+        jobs: usize,
+    },
+
+    /// Import read and starred state from another feed reader
+    ///
+    /// Items are matched by URL. Items clio has not stored yet are added with
+    /// their state, and existing items are marked read or starred as needed.
+    /// Importing never marks an item unread or unstarred.
+    Import {
+        /// Reader that produced the export
+        #[arg(value_enum, value_name = "FORMAT")]
+        format: ImportFormat,
+
+        /// Path to the export (JSON for Miniflux/FreshRSS, cache.db for Newsboat)
+        #[arg(value_name = "PATH")]
+        path: PathBuf,
+    },
+
+    /// Export stored items in another format
+    ///
+    /// With --ics, items with a future publication date or a future date in
+    /// their title or summary become calendar events. Write the file somewhere
+    /// your calendar app can subscribe to and re-run export after each pull.
+    ///
+    /// With --bibtex or --csl, items become citations for reference managers,
+    /// for example `clio export --bibtex starred.bib --starred --filter tag:papers`.
+    ///
+    /// With --json, --csv or --markdown, items are dumped as they are stored,
+    /// for other tools: `clio export --json --unread --since 7d | jq`.
+    /// The JSON array is shaped like the `clio serve` API; use --output to
+    /// write it to a file.
+    #[command(group(ArgGroup::new("format").required(true).arg("json")))]
+    Export {
+        /// Write an iCalendar (.ics) file of upcoming items
+        #[arg(long, group = "format")]
+        ics: bool,
+
+        /// Write BibTeX entries, to FILE if given
+        #[arg(long, group = "format", value_name = "FILE", num_args = 0..=1)]
+        bibtex: Option<Option<PathBuf>>,
+
+        /// Write a CSL-JSON array, to FILE if given
+        #[arg(long, group = "format", value_name = "FILE", num_args = 0..=1)]
+        csl: Option<Option<PathBuf>>,
+
+        /// Write CSV with a header row, to FILE if given
+        #[arg(long, group = "format", value_name = "FILE", num_args = 0..=1)]
+        csv: Option<Option<PathBuf>>,
+
+        /// Write a Markdown list of links, to FILE if given
+        #[arg(long, group = "format", value_name = "FILE", num_args = 0..=1)]
+        markdown: Option<Option<PathBuf>>,
+
+        /// Only export starred items
+        #[arg(long)]
+        starred: bool,
+
+        /// Only export unread items
+        #[arg(long)]
+        unread: bool,
+
+        /// Only export items from this source
+        #[arg(long, value_name = "NAME")]
+        source: Option<String>,
+
+        /// Only export items published since a date (2024-05-01) or within a
+        /// period (12h, 7d, 2w)
+        #[arg(long, value_name = "WHEN", value_parser = parse_since)]
+        since: Option<DateTime<Utc>>,
+
+        /// Only export items from sources matching tag:NAME or source:NAME (repeatable)
+        #[arg(long, value_name = "FILTER")]
+        filter: Vec<Filter>,
+
+        /// Write to this file instead of standard output
+        #[arg(long, short = 'o', value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+
+    /// Summarize items stored since the last digest, grouped by source
+    ///
+    /// Each digest is recorded in storage, so the next one starts where it
+    /// left off; the first covers the last day. Prints to standard output
+    /// unless written to a file or emailed through the local sendmail, for
+    /// example from a daily cron job: `clio digest --email me@example.com`.
+    Digest {
+        /// Cover items stored since a date (2024-05-01) or within a period
+        /// (12h, 7d, 2w) instead of since the last digest
+        #[arg(long, value_name = "WHEN", value_parser = parse_since)]
+        since: Option<DateTime<Utc>>,
+
+        /// Write to this file instead of standard output
+        #[arg(long, short = 'o', value_name = "FILE", conflicts_with = "email")]
+        output: Option<PathBuf>,
+
+        /// Email the digest to this address instead of printing it
+        #[arg(long, value_name = "ADDRESS")]
+        email: Option<String>,
+    },
+
+    /// Mark items as read
+    ///
+    /// Item IDs can be abbreviated to any unambiguous prefix, as shown by
+    /// `clio list` and `clio search`.
+    #[command(visible_alias = "mark-read")]
+    Read {
+        /// IDs of the items to mark read
+        #[arg(
+            value_name = "ID",
+            required_unless_present = "all",
+            conflicts_with = "all"
+        )]
+        ids: Vec<String>,
+
+        /// Mark every stored item read
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Mark items as unread
+    ///
+    /// Item IDs can be abbreviated to any unambiguous prefix, as shown by
+    /// `clio list` and `clio search`.
+    #[command(visible_alias = "mark-unread")]
+    Unread {
+        /// IDs of the items to mark unread
+        #[arg(
+            value_name = "ID",
+            required_unless_present = "all",
+            conflicts_with = "all"
+        )]
+        ids: Vec<String>,
+
+        /// Mark every stored item unread
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Star items to keep a shortlist to revisit
+    ///
+    /// Starred items are kept by `clio purge`; list them with `clio list --starred`.
+    Star {
+        /// IDs of the items to star, or unambiguous prefixes of them
+        #[arg(value_name = "ID", required = true)]
+        ids: Vec<String>,
+    },
+
+    /// Remove the star from items
+    Unstar {
+        /// IDs of the items to unstar, or unambiguous prefixes of them
+        #[arg(value_name = "ID", required = true)]
+        ids: Vec<String>,
+    },
+
+    /// Mark every stored item as read
+    MarkAllRead {
+        /// Only mark items from sources matching tag:NAME or source:NAME (repeatable)
+        #[arg(long, value_name = "FILTER")]
+        filter: Vec<Filter>,
+    },
+
+    /// Delete old items from storage, or everything to start fresh
+    ///
+    /// Starred items are kept however old they are, unless --all is given.
+    Purge {
+        /// Delete items published more than this many days ago
+        #[arg(
+            long,
+            value_name = "DAYS",
+            default_value_t = 90,
+            conflicts_with = "all"
+        )]
+        older_than: u32,
+
+        /// Delete every stored item, starred ones included
+        #[arg(long)]
+        all: bool,
+
+        /// Also forget the fetch and digest history
+        #[arg(long, requires = "all")]
+        with_history: bool,
+
+        /// Also delete the configuration file
+        #[arg(long, requires = "all")]
+        with_config: bool,
+    },
+
+    /// Delete items by age, or all but the newest items of each source
+    ///
+    /// Items matching either rule are deleted, for example
+    /// `clio prune --older-than 30d --keep-starred`. Without a rule, applies
+    /// the `[[retention]]` rules of the configuration.
+    #[command(group(ArgGroup::new("rule").multiple(true)))]
+    Prune {
+        /// Delete items published before a date (2024-05-01) or longer ago
+        /// than a period (12h, 30d, 2w)
+        #[arg(long, value_name = "WHEN", value_parser = parse_since, group = "rule")]
+        older_than: Option<DateTime<Utc>>,
+
+        /// Keep only the newest N items of each source
+        #[arg(long, value_name = "N", group = "rule")]
+        keep_newest: Option<usize>,
+
+        /// Never delete starred items
+        #[arg(long, requires = "rule")]
+        keep_starred: bool,
+
+        /// Report how many items each rule would delete, deleting nothing
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Check that storage is reachable and pulls are succeeding
+    ///
+    /// Exits with a non-zero status when any check fails, for container
+    /// health checks and uptime monitors.
+    Health {
+        /// Fail when no source was fetched successfully within this many hours
+        #[arg(long, value_name = "HOURS", default_value_t = DEFAULT_MAX_PULL_AGE_HOURS)]
+        max_pull_age: u32,
+    },
+
+    /// Read stored items in a browser, through a local web page and JSON API
+    ///
+    /// Listens on 127.0.0.1 unless told otherwise. Besides the reading page,
+    /// serves /api/items, /healthz for uptime monitors and /opml.
+    Serve {
+        /// Port to listen on
+        #[arg(long, short = 'p', default_value_t = 8080)]
+        port: u16,
+
+        /// Address to listen on; anything but a loopback address exposes
+        /// your items to the network
+        #[arg(long, value_name = "ADDRESS", default_value = "127.0.0.1")]
+        bind: std::net::IpAddr,
+
+        /// Fail /healthz when no source was fetched successfully within this many hours
+        #[arg(long, value_name = "HOURS", default_value_t = DEFAULT_MAX_PULL_AGE_HOURS)]
+        max_pull_age: u32,
+    },
+
+    /// Show statistics about stored items, the fetch history, or your own usage
+    ///
+    /// Without a flag, summarizes stored items: how many each source has,
+    /// items per day, how much has been read, and the most and least active
+    /// sources.
+    #[command(group(ArgGroup::new("report")))]
+    Stats {
+        /// Show bytes downloaded per source, largest first, and how many
+        /// fetches returned a body that had not changed
+        #[arg(long, group = "report")]
+        bandwidth: bool,
+
+        /// Show your pulls, items read per week, and busiest reading hours
+        ///
+        /// Counted in ~/.clio/usage.log, which never leaves your machine.
+        #[arg(long, group = "report")]
+        me: bool,
+
+        /// Only count items or fetches from the last N days
+        #[arg(long, value_name = "N", default_value_t = 30)]
+        days: u32,
+    },
+
+    /// Share alert, author and retention rules, and try them on pulled items
+    Rules {
+        #[command(subcommand)]
+        command: RuleCommand,
+    },
+
+    /// Hide pulled items whose titles contain a keyword
+    ///
+    /// Mute rules are kept in the [[mutes]] tables of the configuration and
+    /// drop matching items before alerts, author rules or storage see them.
+    Mute {
+        #[command(subcommand)]
+        command: MuteCommand,
+    },
+
+    /// Simulate a pull under a proposed configuration and compare its item flow
+    ///
+    /// Both configurations replay the feed responses recorded in the cassette
+    /// (see CLIO_RECORD), so nothing is fetched. Only feeds and synthetic
+    /// sources are simulated.
+    DiffConfig {
+        /// The proposed configuration file
+        path: PathBuf,
+
+        /// Cassette to replay instead of CLIO_CASSETTE or ~/.clio/cassette.json
+        #[arg(long, value_name = "FILE")]
+        cassette: Option<PathBuf>,
+    },
+
+    /// Inspect the configuration file format
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+
+    /// Inspect the audit log of changes to items and sources
+    Audit {
+        #[command(subcommand)]
+        command: AuditCommand,
+    },
+
+    /// Manage Supabase credentials
+    Auth {
+        #[command(subcommand)]
+        command: AuthCommand,
+    },
+
+    /// Manage the storage schema
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SourceCommand {
+    /// List configured sources with their fetch health and stored item counts
+    ///
+    /// Health comes from the fetch history and stored items; without storage
+    /// credentials only the configuration is shown.
+    List,
+
+    /// Show a source's configuration, fetch health and recent errors
+    ///
+    /// Each error is listed with when it happened and its kind, such as DNS,
+    /// TLS, timeout, an HTTP status or parse, so a feed that sometimes fails
+    /// can be told apart from one that is gone.
+    Show {
+        /// Name of the source, as shown in the configuration
+        #[arg(value_name = "NAME")]
+        name: String,
+
+        /// Number of recent errors to show
+        #[arg(long, value_name = "N", default_value_t = 10)]
+        errors: usize,
+    },
+
+    /// Add an RSS or Atom feed to the configuration
+    Add {
+        /// Unique name shown next to the feed's items
+        #[arg(value_name = "NAME")]
+        name: String,
+
+        /// Feed URL (http or https)
+        #[arg(value_name = "URL")]
+        url: String,
+
+        /// Fetch the feed before saving, and only add it if it can be parsed
+        #[arg(long)]
+        fetch_now: bool,
+    },
+
+    /// Temporarily skip a source on pull without removing it
+    Pause {
+        /// Name of the source, as shown in the configuration
+        #[arg(value_name = "NAME")]
+        name: String,
+    },
+
+    /// Resume pulling a paused source
+    Resume {
+        /// Name of the source, as shown in the configuration
+        #[arg(value_name = "NAME")]
+        name: String,
+    },
+
+    /// Rename a source, moving its stored items and fetch history along
+    Rename {
+        /// Current name of the source, as shown in the configuration
+        #[arg(value_name = "OLD")]
+        old_name: String,
+
+        /// New name, not used by another source
+        #[arg(value_name = "NEW")]
+        new_name: String,
+    },
+
+    /// Remove a source from the configuration
+    ///
+    /// Items already stored from the source are kept unless --purge-items is given.
+    Remove {
+        /// Name of the source, as shown in the configuration
+        #[arg(value_name = "NAME")]
+        name: String,
+
+        /// Also delete the source's stored items, including starred ones
+        #[arg(long)]
+        purge_items: bool,
+    },
+
+    /// Suggest configuration updates from the fetch history
+    ///
+    /// Lists feeds whose latest successful fetch was redirected to another
+    /// address, which usually means the feed has moved. Temporary redirects
+    /// are listed too, so check a new address before applying it.
+    Audit {
+        /// Replace the URLs of redirected feeds in config.toml
+        #[arg(long)]
+        apply: bool,
+    },
+
+    /// Subscribe to the feeds of an OPML file exported by another reader
+    ///
+    /// Feeds are matched to configured sources by URL. Folders and categories
+    /// become tags. When a feed is already subscribed under another name or
+    /// tags, you are asked what to do unless --on-conflict is given.
+    Import {
+        /// Path to the OPML file
+        #[arg(value_name = "FILE")]
+        path: PathBuf,
+
+        /// How to resolve feeds that are already subscribed differently
+        #[arg(long, value_enum, value_name = "POLICY")]
+        on_conflict: Option<ConflictPolicy>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum RuleCommand {
+    /// Write the [[alerts]], [[authors]], [[mutes]] and [[retention]] rules
+    /// of the configuration to a rules file
+    Export {
+        /// Where to write the rules; printed to stdout when omitted
+        #[arg(value_name = "FILE")]
+        path: Option<PathBuf>,
+    },
+
+    /// Add the rules of a rules file to the configuration
+    ///
+    /// Rules already configured are skipped. The others go after the
+    /// configured rules of their kind.
+    Import {
+        /// Path to a rules file written by `clio rules export`
+        #[arg(value_name = "FILE")]
+        path: PathBuf,
+    },
+
+    /// Replay pulled items through the rules and show what each one matches
+    ///
+    /// Nothing is starred, tagged, alerted on or deleted.
+    Test {
+        /// Test the rules of this file instead of the configured ones
+        #[arg(value_name = "FILE")]
+        path: Option<PathBuf>,
+
+        /// Items to replay
+        #[arg(long, value_enum, value_name = "ITEMS", default_value_t)]
+        against: Replay,
+    },
+
+    /// Show how many pulled items each mute, author and alert rule matched
+    ///
+    /// Every pull records its matches in ~/.clio/rule_hits.log. Rules that
+    /// never match, and rules matching most items, are pointed out.
+    Stats {
+        /// Only count pulls from the last N days
+        #[arg(long, value_name = "N", default_value_t = 30)]
+        days: u32,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MuteCommand {
+    /// Drop pulled items whose titles contain a keyword
+    Add {
+        /// Word or phrase, matched as whole words ignoring case
+        keyword: String,
+    },
+
+    /// List the muted keywords
+    List,
+
+    /// Stop muting a keyword
+    Remove {
+        /// The muted keyword, ignoring case
+        keyword: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AuditCommand {
+    /// Show the most recent changes, oldest first
+    ///
+    /// Every command that changes stored items or the configuration appends
+    /// an entry to ~/.clio/audit.log, including the links of affected items,
+    /// so you can find out why an item disappeared or was marked read.
+    Tail {
+        /// Number of entries to show
+        #[arg(long, short = 'n', value_name = "N", default_value_t = 20)]
+        lines: usize,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Check the configuration, listing every error and warning
+    ///
+    /// Exits with a non-zero status when there are errors, for use in scripts.
+    Validate {
+        /// Also check that every active feed can be fetched
+        #[arg(long)]
+        network: bool,
+    },
+
+    /// Open config.toml in $VISUAL or $EDITOR, then check it
+    ///
+    /// The file is created from the example configuration if it does not
+    /// exist yet. After saving, problems are listed along with the sources
+    /// added and removed; in a terminal, you are offered to fix errors.
+    Edit,
+
+    /// Print the JSON Schema for config.toml
+    ///
+    /// Point your editor's TOML language server at the schema for completion
+    /// and validation, e.g. with taplo add this line to the top of config.toml:
+    ///
+    ///     #:schema ./config.schema.json
+    ///
+    /// after running: clio config schema > ~/.clio/config.schema.json
+    Schema,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AuthCommand {
+    /// Verify a new Supabase secret key before switching to it
+    ///
+    /// Sends a test request to SUPABASE_URL using the new key and, if it is
+    /// accepted, explains how to update your environment. Keep the old key in
+    /// SUPABASE_PREVIOUS_SECRET_KEY during the switch so long-running processes
+    /// keep working until they are restarted.
+    Rotate {
+        /// The new secret key (starts with 'sb_secret_')
+        #[arg(long, value_name = "KEY")]
+        new_key: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DbCommand {
+    /// Create the storage schema, or bring an existing one up to date
+    Init,
+
+    /// Delete everything stored and create the schema again, empty
+    ///
+    /// Items, including starred ones, and the fetch and digest history are
+    /// all lost. The configuration is left alone.
+    Reset,
+
+    /// Show the schema version and apply pending migrations
+    ///
+    /// Run this after upgrading clio: other commands only read and write
+    /// items, and never change the schema themselves.
+    Migrate {
+        /// Only show the schema version and the pending migrations
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Check stored items for damage left by older versions or manual edits
+    ///
+    /// Looks for items missing their link, source or title, text with
+    /// invalid UTF-8, unusable dates, and blank or repeated tags.
+    Verify {
+        /// Fix what was found; items without a link or source are deleted
+        #[arg(long)]
+        repair: bool,
+    },
+
+    /// Move old items out of storage into yearly archive files
+    ///
+    /// Items go to ~/.clio/archives/items-YEAR.db for the year they were
+    /// published, keeping storage small for everyday listing. Starred items
+    /// stay. Archived items are found with 'clio search --include-archives'.
+    /// Runs after each pull when cold_storage_after_days is set.
+    Rotate {
+        /// Move items published more than this many days ago, instead of
+        /// the cold_storage_after_days setting
+        #[arg(long, value_name = "DAYS")]
+        older_than: Option<u32>,
+    },
+}
+
+/// Parse `--since` as a date (2024-05-01), an RFC 3339 time, or a period
+/// before now in hours, days, or weeks (12h, 7d, 2w)
+fn parse_since(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_time(Default::default()).and_utc());
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+
+    let invalid = || {
+        format!("expected a date like 2024-05-01 or a period like 12h, 7d, or 2w, got '{value}'")
+    };
+    let split = value.len().checked_sub(1).ok_or_else(invalid)?;
+    let (count, unit) = value.split_at(split);
+    let count: i64 = count.parse().map_err(|_| invalid())?;
+    let period = match unit {
+        "h" => Duration::try_hours(count),
+        "d" => Duration::try_days(count),
+        "w" => Duration::try_weeks(count),
+        _ => None,
+    }
+    .ok_or_else(invalid)?;
+    Ok(Utc::now() - period)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_cli_parse_init() {
+        let cli = Cli::parse_from(["clio", "init"]);
+        assert!(matches!(
+            cli.command,
+            Command::Init {
+                force: false,
+                example: false
+            }
+        ));
+
+        let cli = Cli::parse_from(["clio", "init", "--force", "--example"]);
+        assert!(matches!(
+            cli.command,
+            Command::Init {
+                force: true,
+                example: true
+            }
+        ));
+    }
+
     #[test]
     fn test_cli_parse_pull() {
         let cli = Cli::parse_from(["clio", "pull"]);
-        assert!(matches!(cli.command, Command::Pull));
+        assert!(
+            matches!(cli.command, Command::Pull { trace: None, sources } if sources.is_empty())
+        );
+
+        let cli = Cli::parse_from(["clio", "pull", "--trace", "trace.json"]);
+        assert!(
+            matches!(cli.command, Command::Pull { trace: Some(path), .. } if path.as_os_str() == "trace.json")
+        );
+
+        let cli = Cli::parse_from([
+            "clio", "pull", "--source", "Hacker*", "--source", "Lobsters",
+        ]);
+        assert!(
+            matches!(cli.command, Command::Pull { sources, .. } if sources == ["Hacker*", "Lobsters"])
+        );
     }
 
     #[test]
     fn test_cli_parse_list() {
         let cli = Cli::parse_from(["clio", "list"]);
-        assert!(matches!(cli.command, Command::List));
+        assert!(matches!(
+            cli.command,
+            Command::List {
+                collapse_duplicates: false,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_cli_parse_list_filters() {
+        let cli = Cli::parse_from([
+            "clio",
+            "list",
+            "--unread",
+            "--source",
+            "Blog",
+            "--source",
+            "News",
+            "--since",
+            "2024-05-01",
+            "--limit",
+            "20",
+            "--as-of",
+            "2025-05-01",
+        ]);
+        match cli.command {
+            Command::List {
+                unread,
+                sources,
+                since,
+                limit,
+                as_of,
+                ..
+            } => {
+                assert!(unread);
+                assert_eq!(sources, ["Blog", "News"]);
+                assert_eq!(since.unwrap().to_rfc3339(), "2024-05-01T00:00:00+00:00");
+                assert_eq!(limit, Some(20));
+                assert_eq!(as_of.unwrap().to_rfc3339(), "2025-05-01T00:00:00+00:00");
+            }
+            _ => panic!("Expected List command"),
+        }
+
+        assert!(Cli::try_parse_from(["clio", "list", "--since", "1 week"]).is_err());
+        assert!(Cli::try_parse_from(["clio", "list", "--limit", "all"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_list_author() {
+        let cli = Cli::parse_from(["clio", "list", "--author", "Julia Evans"]);
+        match cli.command {
+            Command::List { author, .. } => assert_eq!(author.as_deref(), Some("Julia Evans")),
+            _ => panic!("Expected List command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_list_collapse_duplicates() {
+        let cli = Cli::parse_from(["clio", "list", "--collapse-duplicates"]);
+        match cli.command {
+            Command::List {
+                starred,
+                collapse_duplicates,
+                collapse_window,
+                ..
+            } => {
+                assert!(!starred);
+                assert!(collapse_duplicates);
+                assert_eq!(collapse_window, 24);
+            }
+            _ => panic!("Expected List command"),
+        }
+
+        // The window only makes sense when collapsing
+        assert!(Cli::try_parse_from(["clio", "list", "--collapse-window", "6"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_copy() {
+        let cli = Cli::parse_from(["clio", "copy", "item-123", "--markdown"]);
+        match cli.command {
+            Command::Copy { item_id, markdown } => {
+                assert_eq!(item_id, "item-123");
+                assert!(markdown);
+            }
+            _ => panic!("Expected Copy command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_save() {
+        let cli = Cli::parse_from(["clio", "save", "item-123", "--to", "notes"]);
+        match cli.command {
+            Command::Save { item_id, to } => {
+                assert_eq!(item_id, "item-123");
+                assert_eq!(to.as_deref(), Some("notes"));
+            }
+            _ => panic!("Expected Save command"),
+        }
     }
 
     #[test]
     fn test_cli_parse_open() {
         let cli = Cli::parse_from(["clio", "open", "item-123"]);
         match cli.command {
-            Command::Open { item_id } => assert_eq!(item_id, "item-123"),
+            Command::Open {
+                item_id, unread, ..
+            } => {
+                assert_eq!(item_id.as_deref(), Some("item-123"));
+                assert!(!unread);
+            }
+            _ => panic!("Expected Open command"),
+        }
+
+        let cli = Cli::parse_from(["clio", "open", "--unread", "--source", "HN", "-n", "5"]);
+        match cli.command {
+            Command::Open {
+                item_id,
+                unread,
+                sources,
+                limit,
+            } => {
+                assert!(item_id.is_none() && unread);
+                assert_eq!(sources, ["HN"]);
+                assert_eq!(limit, 5);
+            }
+            _ => panic!("Expected Open command"),
+        }
+        let cli = Cli::parse_from(["clio", "open"]);
+        match cli.command {
+            Command::Open {
+                item_id, unread, ..
+            } => assert!(item_id.is_none() && !unread),
             _ => panic!("Expected Open command"),
         }
+        assert!(Cli::try_parse_from(["clio", "open", "item-123", "--unread"]).is_err());
+        assert!(Cli::try_parse_from(["clio", "open", "--source", "HN"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_show() {
+        let cli = Cli::parse_from(["clio", "show", "3f2a"]);
+        match cli.command {
+            Command::Show { item_id } => assert_eq!(item_id, "3f2a"),
+            _ => panic!("Expected Show command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_random() {
+        let cli = Cli::parse_from(["clio", "random"]);
+        assert!(matches!(
+            cli.command,
+            Command::Random { ref filter, print: false } if filter.is_empty()
+        ));
+
+        let cli = Cli::parse_from(["clio", "random", "--filter", "tag:essays", "--print"]);
+        match cli.command {
+            Command::Random { filter, print } => {
+                assert_eq!(filter, vec![Filter::Tag("essays".to_string())]);
+                assert!(print);
+            }
+            _ => panic!("Expected Random command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_source_pause_resume() {
+        let cli = Cli::parse_from(["clio", "sources", "pause", "Hacker News"]);
+        match cli.command {
+            Command::Source {
+                command: SourceCommand::Pause { name },
+            } => assert_eq!(name, "Hacker News"),
+            _ => panic!("Expected Source Pause command"),
+        }
+
+        let cli = Cli::parse_from(["clio", "source", "resume", "Hacker News"]);
+        assert!(matches!(
+            cli.command,
+            Command::Source {
+                command: SourceCommand::Resume { .. }
+            }
+        ));
+    }
+
+    #[test]
+    fn test_cli_parse_source_rename() {
+        let cli = Cli::parse_from(["clio", "sources", "rename", "HN", "Hacker News"]);
+        match cli.command {
+            Command::Source {
+                command: SourceCommand::Rename { old_name, new_name },
+            } => {
+                assert_eq!(old_name, "HN");
+                assert_eq!(new_name, "Hacker News");
+            }
+            _ => panic!("Expected Source Rename command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_source_list() {
+        assert!(matches!(
+            Cli::parse_from(["clio", "sources", "list"]).command,
+            Command::Source {
+                command: SourceCommand::List
+            }
+        ));
+    }
+
+    #[test]
+    fn test_cli_parse_source_show() {
+        let cli = Cli::parse_from(["clio", "sources", "show", "Hacker News"]);
+        match cli.command {
+            Command::Source {
+                command: SourceCommand::Show { name, errors },
+            } => {
+                assert_eq!(name, "Hacker News");
+                assert_eq!(errors, 10);
+            }
+            _ => panic!("Expected Source Show command"),
+        }
+
+        let cli = Cli::parse_from(["clio", "sources", "show", "HN", "--errors", "3"]);
+        assert!(matches!(
+            cli.command,
+            Command::Source {
+                command: SourceCommand::Show { errors: 3, .. }
+            }
+        ));
+    }
+
+    #[test]
+    fn test_cli_parse_rules() {
+        let cli = Cli::parse_from(["clio", "rules", "export", "rules.toml"]);
+        match cli.command {
+            Command::Rules {
+                command: RuleCommand::Export { path },
+            } => assert_eq!(path, Some(PathBuf::from("rules.toml"))),
+            _ => panic!("Expected Rules Export command"),
+        }
+
+        let cli = Cli::parse_from(["clio", "rules", "test", "--against", "last-pull"]);
+        assert!(matches!(
+            cli.command,
+            Command::Rules {
+                command: RuleCommand::Test {
+                    path: None,
+                    against: Replay::LastPull
+                }
+            }
+        ));
+        assert!(Cli::try_parse_from(["clio", "rules", "import"]).is_err());
+        assert!(matches!(
+            Cli::parse_from(["clio", "rules", "stats"]).command,
+            Command::Rules {
+                command: RuleCommand::Stats { days: 30 }
+            }
+        ));
+    }
+
+    #[test]
+    fn test_cli_parse_source_add() {
+        let cli = Cli::parse_from([
+            "clio",
+            "source",
+            "add",
+            "Julia Evans",
+            "https://jvns.ca/atom.xml",
+            "--fetch-now",
+        ]);
+        match cli.command {
+            Command::Source {
+                command:
+                    SourceCommand::Add {
+                        name,
+                        url,
+                        fetch_now,
+                    },
+            } => {
+                assert_eq!(name, "Julia Evans");
+                assert_eq!(url, "https://jvns.ca/atom.xml");
+                assert!(fetch_now);
+            }
+            _ => panic!("Expected Source Add command"),
+        }
+
+        assert!(Cli::try_parse_from(["clio", "source", "add", "Name"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_watch() {
+        let cli = Cli::parse_from(["clio", "watch"]);
+        assert!(matches!(
+            cli.command,
+            Command::Watch { ref sources, interval: 5, health_port: None, .. } if sources.is_empty()
+        ));
+
+        let cli = Cli::parse_from(["clio", "watch", "--source", "HN*", "--interval", "1"]);
+        match cli.command {
+            Command::Watch {
+                sources, interval, ..
+            } => {
+                assert_eq!(sources, vec!["HN*"]);
+                assert_eq!(interval, 1);
+            }
+            _ => panic!("Expected Watch command"),
+        }
+
+        assert!(Cli::try_parse_from(["clio", "watch", "--interval", "0"]).is_err());
+
+        let cli = Cli::parse_from([
+            "clio",
+            "watch",
+            "--health-port",
+            "9090",
+            "--max-pull-age",
+            "2",
+        ]);
+        assert!(matches!(
+            cli.command,
+            Command::Watch {
+                health_port: Some(9090),
+                max_pull_age: 2,
+                ..
+            }
+        ));
+        assert!(Cli::try_parse_from(["clio", "watch", "--max-pull-age", "2"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_source_audit() {
+        assert!(matches!(
+            Cli::parse_from(["clio", "sources", "audit"]).command,
+            Command::Source {
+                command: SourceCommand::Audit { apply: false }
+            }
+        ));
+        assert!(matches!(
+            Cli::parse_from(["clio", "sources", "audit", "--apply"]).command,
+            Command::Source {
+                command: SourceCommand::Audit { apply: true }
+            }
+        ));
+    }
+
+    #[test]
+    fn test_cli_parse_source_import() {
+        let cli = Cli::parse_from([
+            "clio",
+            "sources",
+            "import",
+            "feeds.opml",
+            "--on-conflict",
+            "merge",
+        ]);
+        match cli.command {
+            Command::Source {
+                command: SourceCommand::Import { path, on_conflict },
+            } => {
+                assert_eq!(path, PathBuf::from("feeds.opml"));
+                assert_eq!(on_conflict, Some(ConflictPolicy::Merge));
+            }
+            _ => panic!("Expected Source Import command"),
+        }
+
+        assert!(
+            Cli::try_parse_from([
+                "clio",
+                "sources",
+                "import",
+                "a.opml",
+                "--on-conflict",
+                "skip"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_parse_fetch_enclosures() {
+        let cli = Cli::parse_from([
+            "clio",
+            "fetch-enclosures",
+            "--source",
+            "My Podcast",
+            "--dir",
+            "/tmp/pods",
+        ]);
+        match cli.command {
+            Command::FetchEnclosures {
+                source,
+                dir,
+                max_size,
+                jobs,
+            } => {
+                assert_eq!(source.as_deref(), Some("My Podcast"));
+                assert_eq!(dir, Some(PathBuf::from("/tmp/pods")));
+                assert_eq!(max_size, 500);
+                assert_eq!(jobs, 4);
+            }
+            _ => panic!("Expected FetchEnclosures command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_star() {
+        let cli = Cli::parse_from(["clio", "star", "6f1c", "a2b3"]);
+        assert!(matches!(cli.command, Command::Star { ids } if ids == ["6f1c", "a2b3"]));
+
+        let cli = Cli::parse_from(["clio", "unstar", "6f1c"]);
+        assert!(matches!(cli.command, Command::Unstar { ids } if ids == ["6f1c"]));
+        assert!(Cli::try_parse_from(["clio", "star"]).is_err());
+
+        let cli = Cli::parse_from(["clio", "list", "--starred"]);
+        assert!(matches!(cli.command, Command::List { starred: true, .. }));
+    }
+
+    #[test]
+    fn test_cli_parse_status() {
+        let cli = Cli::parse_from(["clio", "status"]);
+        assert!(matches!(cli.command, Command::Status { total: false }));
+
+        let cli = Cli::parse_from(["clio", "status", "--total"]);
+        assert!(matches!(cli.command, Command::Status { total: true }));
+    }
+
+    #[test]
+    fn test_cli_parse_read_unread() {
+        let cli = Cli::parse_from(["clio", "read", "6f1c", "a2b3"]);
+        match cli.command {
+            Command::Read { ids, all } => {
+                assert_eq!(ids, vec!["6f1c", "a2b3"]);
+                assert!(!all);
+            }
+            _ => panic!("Expected Read command"),
+        }
+
+        let cli = Cli::parse_from(["clio", "mark-unread", "--all"]);
+        assert!(matches!(cli.command, Command::Unread { ids, all: true } if ids.is_empty()));
+
+        assert!(Cli::try_parse_from(["clio", "read"]).is_err());
+        assert!(Cli::try_parse_from(["clio", "unread", "6f1c", "--all"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_import() {
+        let cli = Cli::parse_from(["clio", "import", "freshrss", "starred.json"]);
+        match cli.command {
+            Command::Import { format, path } => {
+                assert_eq!(format, ImportFormat::FreshRss);
+                assert_eq!(path, PathBuf::from("starred.json"));
+            }
+            _ => panic!("Expected Import command"),
+        }
+
+        assert!(Cli::try_parse_from(["clio", "import", "feedly", "export.json"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_top() {
+        let cli = Cli::parse_from(["clio", "top", "--hours", "48", "-n", "5"]);
+        match cli.command {
+            Command::Top { hours, limit } => {
+                assert_eq!(hours, 48);
+                assert_eq!(limit, 5);
+            }
+            _ => panic!("Expected Top command"),
+        }
+
+        match Cli::parse_from(["clio", "top"]).command {
+            Command::Top { hours, limit } => assert_eq!((hours, limit), (24, 20)),
+            _ => panic!("Expected Top command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_discover() {
+        match Cli::parse_from(["clio", "discover", "example.com"]).command {
+            Command::Discover {
+                url,
+                add,
+                feed,
+                name,
+            } => {
+                assert_eq!(url, "example.com");
+                assert!(!add && feed.is_none() && name.is_none());
+            }
+            _ => panic!("Expected Discover command"),
+        }
+
+        let cli = Cli::parse_from([
+            "clio",
+            "discover",
+            "example.com",
+            "--add",
+            "--feed",
+            "2",
+            "--name",
+            "Blog",
+        ]);
+        match cli.command {
+            Command::Discover {
+                add, feed, name, ..
+            } => {
+                assert!(add);
+                assert_eq!(feed, Some(2));
+                assert_eq!(name.as_deref(), Some("Blog"));
+            }
+            _ => panic!("Expected Discover command"),
+        }
+        assert!(Cli::try_parse_from(["clio", "discover", "example.com", "--feed", "1"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_preview() {
+        match Cli::parse_from(["clio", "preview", "example.com", "-n", "3"]).command {
+            Command::Preview { url, limit } => {
+                assert_eq!((url.as_str(), limit), ("example.com", 3))
+            }
+            _ => panic!("Expected Preview command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_search() {
+        let cli = Cli::parse_from([
+            "clio",
+            "search",
+            "rust",
+            "async",
+            "--unread",
+            "--source",
+            "Blog",
+            "--since",
+            "2024-05-01",
+            "--all-profiles",
+        ]);
+        match cli.command {
+            Command::Search {
+                query,
+                unread,
+                source,
+                since,
+                all_profiles,
+                include_archives,
+            } => {
+                assert_eq!(query, vec!["rust", "async"]);
+                assert!(unread && all_profiles && !include_archives);
+                assert_eq!(source.as_deref(), Some("Blog"));
+                assert_eq!(since.unwrap().to_rfc3339(), "2024-05-01T00:00:00+00:00");
+            }
+            _ => panic!("Expected Search command"),
+        }
+
+        assert!(Cli::try_parse_from(["clio", "search"]).is_err());
+        assert!(Cli::try_parse_from(["clio", "search", "rust", "--since", "soon"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let week_ago = parse_since("7d").unwrap();
+        let expected = Utc::now() - Duration::days(7);
+        assert!((expected - week_ago).num_seconds().abs() < 5);
+
+        assert!(parse_since("12h").unwrap() > week_ago);
+        assert!(parse_since("2w").unwrap() < week_ago);
+        assert_eq!(
+            parse_since("2024-05-01T12:00:00+02:00")
+                .unwrap()
+                .to_rfc3339(),
+            "2024-05-01T10:00:00+00:00"
+        );
+
+        for invalid in ["", "d", "7", "7y", "-", "2024-13-01"] {
+            assert!(parse_since(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_export() {
+        let cli = Cli::parse_from([
+            "clio",
+            "export",
+            "--ics",
+            "--filter",
+            "tag:events",
+            "-o",
+            "events.ics",
+        ]);
+        match cli.command {
+            Command::Export {
+                ics,
+                bibtex: None,
+                filter,
+                output,
+                ..
+            } => {
+                assert!(ics);
+                assert_eq!(filter, vec![Filter::Tag("events".to_string())]);
+                assert_eq!(output, Some(PathBuf::from("events.ics")));
+            }
+            _ => panic!("Expected Export command"),
+        }
+
+        let cli = Cli::parse_from([
+            "clio",
+            "export",
+            "--bibtex",
+            "starred.bib",
+            "--starred",
+            "--filter",
+            "tag:papers",
+        ]);
+        assert!(matches!(
+            cli.command,
+            Command::Export {
+                ics: false,
+                bibtex: Some(Some(_)),
+                csl: None,
+                starred: true,
+                ..
+            }
+        ));
+        let cli = Cli::parse_from(["clio", "export", "--csl"]);
+        assert!(matches!(
+            cli.command,
+            Command::Export {
+                csl: Some(None),
+                ..
+            }
+        ));
+        let cli = Cli::parse_from([
+            "clio",
+            "export",
+            "--csv",
+            "items.csv",
+            "--unread",
+            "--source",
+            "Blog",
+            "--since",
+            "7d",
+        ]);
+        match cli.command {
+            Command::Export {
+                csv,
+                unread,
+                source,
+                since,
+                ..
+            } => {
+                assert_eq!(csv, Some(Some(PathBuf::from("items.csv"))));
+                assert!(unread);
+                assert_eq!(source.as_deref(), Some("Blog"));
+                assert!(since.is_some());
+            }
+            _ => panic!("Expected Export command"),
+        }
+        assert!(Cli::try_parse_from(["clio", "export", "--json", "--markdown"]).is_err());
+
+        assert!(Cli::try_parse_from(["clio", "export"]).is_err());
+        assert!(Cli::try_parse_from(["clio", "export", "--ics", "--bibtex"]).is_err());
+        assert!(Cli::try_parse_from(["clio", "export", "--ics", "--filter", "events"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_digest() {
+        let cli = Cli::parse_from(["clio", "digest", "--email", "me@example.com"]);
+        match cli.command {
+            Command::Digest {
+                since,
+                output,
+                email,
+            } => {
+                assert_eq!(since, None);
+                assert_eq!(output, None);
+                assert_eq!(email.as_deref(), Some("me@example.com"));
+            }
+            _ => panic!("Expected Digest command"),
+        }
+
+        let cli = Cli::parse_from(["clio", "digest", "--since", "7d", "-o", "digest.md"]);
+        assert!(matches!(
+            cli.command,
+            Command::Digest {
+                since: Some(_),
+                output: Some(_),
+                email: None,
+            }
+        ));
+        assert!(
+            Cli::try_parse_from(["clio", "digest", "-o", "d.md", "--email", "me@example.com"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_parse_stats() {
+        let cli = Cli::parse_from(["clio", "stats", "--bandwidth", "--days", "7"]);
+        match cli.command {
+            Command::Stats {
+                bandwidth,
+                me,
+                days,
+            } => {
+                assert!(bandwidth && !me);
+                assert_eq!(days, 7);
+            }
+            _ => panic!("Expected Stats command"),
+        }
+
+        assert!(matches!(
+            Cli::parse_from(["clio", "stats", "--me"]).command,
+            Command::Stats { me: true, .. }
+        ));
+        assert!(matches!(
+            Cli::parse_from(["clio", "stats"]).command,
+            Command::Stats {
+                bandwidth: false,
+                me: false,
+                days: 30,
+            }
+        ));
+        assert!(Cli::try_parse_from(["clio", "stats", "--me", "--bandwidth"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_config_schema() {
+        let cli = Cli::parse_from(["clio", "config", "schema"]);
+        assert!(matches!(
+            cli.command,
+            Command::Config {
+                command: ConfigCommand::Schema
+            }
+        ));
+    }
+
+    #[test]
+    fn test_cli_parse_config_validate() {
+        let cli = Cli::parse_from(["clio", "config", "validate", "--network"]);
+        assert!(matches!(
+            cli.command,
+            Command::Config {
+                command: ConfigCommand::Validate { network: true }
+            }
+        ));
+    }
+
+    #[test]
+    fn test_cli_parse_config_edit() {
+        let cli = Cli::parse_from(["clio", "config", "edit"]);
+        assert!(matches!(
+            cli.command,
+            Command::Config {
+                command: ConfigCommand::Edit
+            }
+        ));
+    }
+
+    #[test]
+    fn test_cli_parse_auth_rotate() {
+        let cli = Cli::parse_from(["clio", "auth", "rotate", "--new-key", "sb_secret_new"]);
+        match cli.command {
+            Command::Auth {
+                command: AuthCommand::Rotate { new_key },
+            } => assert_eq!(new_key, "sb_secret_new"),
+            _ => panic!("Expected Auth Rotate command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_db_migrate() {
+        let cli = Cli::parse_from(["clio", "db", "migrate", "--dry-run"]);
+        assert!(matches!(
+            cli.command,
+            Command::Db {
+                command: DbCommand::Migrate { dry_run: true }
+            }
+        ));
+        assert!(Cli::try_parse_from(["clio", "db"]).is_err());
+
+        for (args, init) in [
+            (["clio", "db", "init"], true),
+            (["clio", "db", "reset"], false),
+        ] {
+            match Cli::parse_from(args).command {
+                Command::Db {
+                    command: DbCommand::Init,
+                } => assert!(init),
+                Command::Db {
+                    command: DbCommand::Reset,
+                } => assert!(!init),
+                _ => panic!("Expected Db command"),
+            }
+        }
+
+        let cli = Cli::parse_from(["clio", "db", "verify", "--repair"]);
+        assert!(matches!(
+            cli.command,
+            Command::Db {
+                command: DbCommand::Verify { repair: true }
+            }
+        ));
+
+        let cli = Cli::parse_from(["clio", "db", "rotate", "--older-than", "365"]);
+        assert!(matches!(
+            cli.command,
+            Command::Db {
+                command: DbCommand::Rotate {
+                    older_than: Some(365)
+                }
+            }
+        ));
+    }
+
+    #[test]
+    fn test_cli_parse_plain_flag() {
+        let cli = Cli::parse_from(["clio", "list", "--plain"]);
+        assert!(cli.plain);
+        assert!(!Cli::parse_from(["clio", "list"]).plain);
+    }
+
+    #[test]
+    fn test_cli_parse_json_flag() {
+        for args in [
+            ["clio", "--json", "list"],
+            ["clio", "pull", "--json"],
+            ["clio", "stats", "--json"],
+        ] {
+            assert!(Cli::parse_from(args).json);
+        }
+        let cli = Cli::parse_from(["clio", "source", "list", "--json"]);
+        assert!(cli.json);
+        assert!(matches!(
+            cli.command,
+            Command::Source {
+                command: SourceCommand::List
+            }
+        ));
+
+        // The global flag is also export's JSON format
+        let cli = Cli::parse_from(["clio", "export", "--json", "--unread"]);
+        assert!(cli.json);
+        assert!(Cli::try_parse_from(["clio", "export", "--json", "items.json"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_destructive_commands() {
+        let cli = Cli::parse_from(["clio", "sources", "remove", "Old Blog", "--yes"]);
+        assert!(cli.yes);
+        assert!(matches!(
+            cli.command,
+            Command::Source {
+                command: SourceCommand::Remove {
+                    purge_items: false,
+                    ..
+                }
+            }
+        ));
+
+        let cli = Cli::parse_from(["clio", "sources", "remove", "Old Blog", "--purge-items"]);
+        assert!(matches!(
+            cli.command,
+            Command::Source {
+                command: SourceCommand::Remove {
+                    purge_items: true,
+                    ..
+                }
+            }
+        ));
+
+        let cli = Cli::parse_from(["clio", "mark-all-read", "--filter", "source:News"]);
+        assert!(!cli.yes);
+        match cli.command {
+            Command::MarkAllRead { filter } => {
+                assert_eq!(filter, vec![Filter::Source("News".to_string())]);
+            }
+            _ => panic!("Expected MarkAllRead command"),
+        }
+
+        let cli = Cli::parse_from(["clio", "-y", "purge"]);
+        assert!(cli.yes);
+        assert!(matches!(
+            cli.command,
+            Command::Purge {
+                older_than: 90,
+                all: false,
+                ..
+            }
+        ));
+
+        let cli = Cli::parse_from(["clio", "purge", "--all", "--with-history"]);
+        assert!(matches!(
+            cli.command,
+            Command::Purge {
+                all: true,
+                with_history: true,
+                with_config: false,
+                ..
+            }
+        ));
+        assert!(Cli::try_parse_from(["clio", "purge", "--with-config"]).is_err());
+        assert!(Cli::try_parse_from(["clio", "purge", "--all", "--older-than", "7"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_prune() {
+        let cli = Cli::parse_from(["clio", "prune", "--older-than", "30d", "--keep-starred"]);
+        match cli.command {
+            Command::Prune {
+                older_than,
+                keep_newest,
+                keep_starred,
+                dry_run: false,
+            } => {
+                assert!(older_than.unwrap() < Utc::now() - Duration::days(29));
+                assert_eq!(keep_newest, None);
+                assert!(keep_starred);
+            }
+            _ => panic!("Expected Prune command"),
+        }
+
+        let cli = Cli::parse_from(["clio", "prune", "--keep-newest", "100"]);
+        assert!(matches!(
+            cli.command,
+            Command::Prune {
+                older_than: None,
+                keep_newest: Some(100),
+                keep_starred: false,
+                dry_run: false
+            }
+        ));
+
+        // Without a rule, the configured retention rules apply
+        let cli = Cli::parse_from(["clio", "prune", "--dry-run"]);
+        assert!(matches!(
+            cli.command,
+            Command::Prune {
+                older_than: None,
+                keep_newest: None,
+                dry_run: true,
+                ..
+            }
+        ));
+
+        // --keep-starred alone would delete everything else
+        assert!(Cli::try_parse_from(["clio", "prune", "--keep-starred"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_health() {
+        let cli = Cli::parse_from(["clio", "health"]);
+        assert!(matches!(cli.command, Command::Health { max_pull_age: 24 }));
+
+        let cli = Cli::parse_from(["clio", "health", "--max-pull-age", "2"]);
+        assert!(matches!(cli.command, Command::Health { max_pull_age: 2 }));
+    }
+
+    #[test]
+    fn test_cli_parse_serve() {
+        let cli = Cli::parse_from(["clio", "serve"]);
+        match cli.command {
+            Command::Serve {
+                port,
+                bind,
+                max_pull_age,
+            } => {
+                assert_eq!(port, 8080);
+                assert!(bind.is_loopback());
+                assert_eq!(max_pull_age, 24);
+            }
+            _ => panic!("Expected Serve command"),
+        }
+
+        let cli = Cli::parse_from(["clio", "serve", "-p", "3000", "--bind", "::1"]);
+        assert!(matches!(cli.command, Command::Serve { port: 3000, .. }));
+        assert!(Cli::try_parse_from(["clio", "serve", "--bind", "localhost:80"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_mute() {
+        let cli = Cli::parse_from(["clio", "mute", "add", "crypto"]);
+        assert!(matches!(
+            cli.command,
+            Command::Mute {
+                command: MuteCommand::Add { ref keyword }
+            } if keyword == "crypto"
+        ));
+        let cli = Cli::parse_from(["clio", "mute", "list"]);
+        assert!(matches!(
+            cli.command,
+            Command::Mute {
+                command: MuteCommand::List
+            }
+        ));
+        assert!(Cli::try_parse_from(["clio", "mute", "remove"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_diff_config() {
+        let cli = Cli::parse_from(["clio", "diff-config", "new.toml"]);
+        match cli.command {
+            Command::DiffConfig { path, cassette } => {
+                assert_eq!(path, PathBuf::from("new.toml"));
+                assert!(cassette.is_none());
+            }
+            _ => panic!("Expected DiffConfig command"),
+        }
+        assert!(Cli::try_parse_from(["clio", "diff-config"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_audit_tail() {
+        let cli = Cli::parse_from(["clio", "audit", "tail", "-n", "5"]);
+        assert!(matches!(
+            cli.command,
+            Command::Audit {
+                command: AuditCommand::Tail { lines: 5 }
+            }
+        ));
     }
 
     #[test]
@@ -74,6 +2076,21 @@ mod tests {
         assert!(cli.quiet);
     }
 
+    #[test]
+    fn test_cli_parse_no_write_flag() {
+        let cli = Cli::parse_from(["clio", "list", "--no-write"]);
+        assert!(cli.no_write);
+        assert!(!Cli::parse_from(["clio", "list"]).no_write);
+    }
+
+    #[test]
+    fn test_cli_parse_color() {
+        let cli = Cli::parse_from(["clio", "list", "--color", "never"]);
+        assert_eq!(cli.color, ColorChoice::Never);
+        assert_eq!(Cli::parse_from(["clio", "list"]).color, ColorChoice::Auto);
+        assert!(Cli::try_parse_from(["clio", "list", "--color", "sometimes"]).is_err());
+    }
+
     #[test]
     fn test_help_text() {
         let result = Cli::try_parse_from(["clio", "--help"]);
@@ -0,0 +1,16 @@
+use crate::error::ClioError;
+
+/// Put text on the system clipboard
+///
+/// On Linux the clipboard belongs to the process that set it, so the text is
+/// handed to the desktop's clipboard manager when clio exits; without one,
+/// it is gone once clio returns.
+pub fn copy(text: &str) -> Result<(), ClioError> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|e| {
+            ClioError::Clipboard(format!(
+                "Could not copy to the clipboard: {e}. Is a desktop session running?"
+            ))
+        })
+}
@@ -0,0 +1,444 @@
+use crate::config::Config;
+use crate::database::{Database, ItemQuery};
+use crate::error::{ClioError, ErrorContext};
+use crate::source::Item;
+use crate::writes;
+use chrono::{DateTime, Datelike, SecondsFormat, Utc};
+use rusqlite::{Connection, OpenFlags, params, params_from_iter};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Items moved per round by [`rotate`], so a large first rotation never
+/// holds the whole backlog at once
+const ROTATION_BATCH: usize = 1000;
+
+/// Yearly SQLite files (`items-2024.db`) holding old items moved out of
+/// storage, so that everyday listing only reads recent items
+///
+/// Each item goes to the file for the year it was published. The files can
+/// still be searched, and opened with any SQLite tool.
+#[derive(Debug, Clone)]
+pub struct ColdStorage {
+    dir: PathBuf,
+}
+
+impl ColdStorage {
+    /// The archive files in `~/.clio/archives`
+    pub fn open() -> Result<Self, ClioError> {
+        Ok(Self::in_dir(Config::config_dir()?.join("archives")))
+    }
+
+    /// The archive files in `dir`
+    pub fn in_dir(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The year of each archive file and its path, oldest first
+    pub fn years(&self) -> Result<Vec<(i32, PathBuf)>, ClioError> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(ClioError::Config(format!(
+                    "Failed to read archives in {}: {e}",
+                    self.dir.display()
+                )));
+            }
+        };
+        let mut years: Vec<(i32, PathBuf)> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter_map(|path| {
+                let year = path
+                    .file_name()?
+                    .to_str()?
+                    .strip_prefix("items-")?
+                    .strip_suffix(".db")?
+                    .parse()
+                    .ok()?;
+                Some((year, path))
+            })
+            .collect();
+        years.sort();
+        Ok(years)
+    }
+
+    /// Add `items` to the archive of the year each was published, returning
+    /// how many went to each year
+    ///
+    /// Items already archived are replaced, so storing the same items twice
+    /// is harmless. Undated items are skipped.
+    pub fn store(&self, items: &[Item]) -> Result<BTreeMap<i32, usize>, ClioError> {
+        let mut by_year: BTreeMap<i32, Vec<&Item>> = BTreeMap::new();
+        for item in items {
+            if let Some(date) = item.pub_date {
+                by_year.entry(date.year()).or_default().push(item);
+            }
+        }
+        if by_year.is_empty() {
+            return Ok(BTreeMap::new());
+        }
+
+        writes::ensure_allowed("write archive files")?;
+        fs::create_dir_all(&self.dir).clio_config_err(format!(
+            "Failed to create archive directory {}",
+            self.dir.display()
+        ))?;
+        let mut stored = BTreeMap::new();
+        for (year, items) in by_year {
+            let path = self.path(year);
+            let mut conn = open_archive(&path)?;
+            let transaction = conn
+                .transaction()
+                .clio_database_err(format!("Failed to write {}", path.display()))?;
+            for item in &items {
+                insert(&transaction, item)
+                    .clio_database_err(format!("Failed to write {}", path.display()))?;
+            }
+            transaction
+                .commit()
+                .clio_database_err(format!("Failed to write {}", path.display()))?;
+            stored.insert(year, items.len());
+        }
+        Ok(stored)
+    }
+
+    /// Archived items matching `query`, newest first
+    ///
+    /// The query's text, sources, unread and since parts apply; starred
+    /// items are never archived.
+    pub fn search(&self, query: &ItemQuery) -> Result<Vec<Item>, ClioError> {
+        if query.starred_only || query.sources.as_ref().is_some_and(Vec::is_empty) {
+            return Ok(Vec::new());
+        }
+        let (conditions, values) = conditions(query);
+        let mut sql = "SELECT id, source_name, title, link, summary, pub_date, is_read, \
+                       authors, tags FROM items"
+            .to_string();
+        if !conditions.is_empty() {
+            sql.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+        }
+
+        let mut items = Vec::new();
+        for (_, path) in self.years()?.into_iter().rev() {
+            let conn = Connection::open_with_flags(&path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+                .clio_database_err(format!("Failed to open {}", path.display()))?;
+            let mut statement = conn
+                .prepare(&sql)
+                .clio_database_err(format!("Invalid archive {}", path.display()))?;
+            let rows = statement
+                .query_map(params_from_iter(&values), read_item)
+                .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                .clio_database_err(format!("Failed to read {}", path.display()))?;
+            items.extend(rows);
+        }
+        items.sort_by_key(|item| std::cmp::Reverse(item.pub_date));
+        if let Some(limit) = query.limit {
+            items.truncate(limit);
+        }
+        Ok(items)
+    }
+
+    fn path(&self, year: i32) -> PathBuf {
+        self.dir.join(format!("items-{year}.db"))
+    }
+}
+
+/// Move stored items published before `before` into `cold`, returning how
+/// many went to each year's archive
+///
+/// Items are written to their archive before being deleted from storage,
+/// so an interrupted rotation leaves them in both rather than neither.
+pub fn rotate(
+    database: &Database,
+    cold: &ColdStorage,
+    before: DateTime<Utc>,
+) -> Result<BTreeMap<i32, usize>, ClioError> {
+    let mut moved: BTreeMap<i32, usize> = BTreeMap::new();
+    loop {
+        let items = database.cold_items(before, ROTATION_BATCH)?;
+        for (year, count) in cold.store(&items)? {
+            *moved.entry(year).or_default() += count;
+        }
+        let ids: Vec<String> = items.iter().map(|item| item.id.clone()).collect();
+        let deleted = database.delete_items(&ids)?;
+        // Without progress the same items would be selected again forever
+        if deleted.is_empty() && !items.is_empty() {
+            return Err(ClioError::Database(
+                "Archived items could not be deleted from storage".to_string(),
+            ));
+        }
+        if items.len() < ROTATION_BATCH {
+            return Ok(moved);
+        }
+    }
+}
+
+fn open_archive(path: &Path) -> Result<Connection, ClioError> {
+    let conn =
+        Connection::open(path).clio_database_err(format!("Failed to open {}", path.display()))?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS items (
+            id TEXT PRIMARY KEY,
+            source_name TEXT NOT NULL,
+            title TEXT NOT NULL,
+            link TEXT NOT NULL,
+            summary TEXT,
+            pub_date TEXT,
+            is_read INTEGER NOT NULL DEFAULT 0,
+            authors TEXT NOT NULL DEFAULT '[]',
+            tags TEXT NOT NULL DEFAULT '[]'
+        );
+        CREATE INDEX IF NOT EXISTS idx_items_pub_date ON items(pub_date DESC);",
+    )
+    .clio_database_err(format!("Failed to create archive {}", path.display()))?;
+    Ok(conn)
+}
+
+fn insert(conn: &Connection, item: &Item) -> rusqlite::Result<usize> {
+    conn.execute(
+        "INSERT OR REPLACE INTO items \
+         (id, source_name, title, link, summary, pub_date, is_read, authors, tags) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            item.id,
+            item.source_name,
+            item.title,
+            item.link,
+            item.summary,
+            item.pub_date.map(archive_date),
+            item.is_read,
+            serde_json::to_string(&item.authors).unwrap_or_default(),
+            serde_json::to_string(&item.tags).unwrap_or_default(),
+        ],
+    )
+}
+
+fn read_item(row: &rusqlite::Row) -> rusqlite::Result<Item> {
+    let list = |index: usize| -> rusqlite::Result<Vec<String>> {
+        let json: String = row.get(index)?;
+        Ok(serde_json::from_str(&json).unwrap_or_default())
+    };
+    Ok(Item {
+        id: row.get(0)?,
+        source_name: row.get(1)?,
+        title: row.get(2)?,
+        link: row.get(3)?,
+        summary: row.get(4)?,
+        pub_date: row
+            .get::<_, Option<String>>(5)?
+            .and_then(|date| DateTime::parse_from_rfc3339(&date).ok())
+            .map(|date| date.with_timezone(&Utc)),
+        is_read: row.get(6)?,
+        authors: list(7)?,
+        tags: list(8)?,
+        ..Default::default()
+    })
+}
+
+/// Dates are stored in UTC at a fixed precision, so that comparing them as
+/// text compares them in time
+fn archive_date(date: DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// SQL conditions for the parts of `query` archives support, and the values
+/// bound to them
+fn conditions(query: &ItemQuery) -> (Vec<String>, Vec<String>) {
+    let mut conditions = Vec::new();
+    let mut values = Vec::new();
+    if let Some(sources) = &query.sources {
+        conditions.push(format!(
+            "source_name IN ({})",
+            vec!["?"; sources.len()].join(", ")
+        ));
+        values.extend(sources.iter().cloned());
+    }
+    if query.unread_only {
+        conditions.push("NOT is_read".to_string());
+    }
+    if let Some(since) = query.since {
+        conditions.push("pub_date >= ?".to_string());
+        values.push(archive_date(since));
+    }
+    // LIKE ignores case for ASCII letters in SQLite
+    for word in query.text.iter().flat_map(|text| text.split_whitespace()) {
+        conditions.push(
+            "(title LIKE ? ESCAPE '\\' OR COALESCE(summary, '') LIKE ? ESCAPE '\\')".to_string(),
+        );
+        let pattern = format!(
+            "%{}%",
+            word.replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+        values.push(pattern.clone());
+        values.push(pattern);
+    }
+    (conditions, values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SupabaseConfig;
+    use crate::database::SupabaseClient;
+    use chrono::TimeZone;
+    use serde_json::{Value, json};
+    use std::sync::{Arc, Mutex};
+    use tempfile::TempDir;
+
+    fn item(id: &str, title: &str, year: i32) -> Item {
+        Item {
+            id: id.to_string(),
+            source_name: "Blog".to_string(),
+            title: title.to_string(),
+            link: format!("https://example.com/{id}"),
+            pub_date: Some(Utc.with_ymd_and_hms(year, 6, 1, 12, 0, 0).unwrap()),
+            tags: vec!["rust".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_store_and_search_yearly_archives() {
+        let dir = TempDir::new().unwrap();
+        let cold = ColdStorage::in_dir(dir.path().join("archives"));
+        assert!(cold.years().unwrap().is_empty());
+
+        let mut read = item("c", "Rust in 2024", 2024);
+        read.is_read = true;
+        let stored = cold
+            .store(&[
+                item("a", "Async Rust", 2023),
+                item("b", "Gardening", 2023),
+                read,
+                Item {
+                    pub_date: None,
+                    ..item("undated", "Rust", 2023)
+                },
+            ])
+            .unwrap();
+        assert_eq!(stored, BTreeMap::from([(2023, 2), (2024, 1)]));
+        // Storing again replaces rather than duplicates
+        cold.store(&[item("a", "Async Rust", 2023)]).unwrap();
+        let years: Vec<i32> = cold.years().unwrap().into_iter().map(|(y, _)| y).collect();
+        assert_eq!(years, [2023, 2024]);
+
+        let search = |query: ItemQuery| -> Vec<String> {
+            cold.search(&query)
+                .unwrap()
+                .into_iter()
+                .map(|item| item.id)
+                .collect()
+        };
+        let text = |text: &str| ItemQuery {
+            text: Some(text.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(search(text("RUST")), ["c", "a"]);
+        assert_eq!(
+            search(ItemQuery {
+                unread_only: true,
+                ..text("rust")
+            }),
+            ["a"]
+        );
+        assert_eq!(
+            search(ItemQuery {
+                sources: Some(vec!["Other".to_string()]),
+                ..text("rust")
+            }),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            search(ItemQuery {
+                since: Some(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()),
+                ..text("rust")
+            }),
+            ["c"]
+        );
+        assert!(search(text("100%")).is_empty());
+
+        let found = cold.search(&text("async")).unwrap();
+        assert_eq!(found[0].tags, ["rust"]);
+        assert_eq!(found[0].pub_date, item("a", "", 2023).pub_date);
+    }
+
+    /// Storage holding items, answering the rotation's selects and deletes
+    #[derive(Debug)]
+    struct Stored {
+        items: Mutex<Vec<Value>>,
+    }
+
+    impl SupabaseClient for Stored {
+        fn execute(&self, _query: &str) -> Result<(), ClioError> {
+            Ok(())
+        }
+
+        fn query(&self, query: &str) -> Result<Vec<Value>, ClioError> {
+            let mut items = self.items.lock().unwrap();
+            if query.starts_with("DELETE") {
+                let deleted: Vec<Value> = items
+                    .iter()
+                    .filter(|row| query.contains(&format!("'{}'", row["id"].as_str().unwrap())))
+                    .map(|row| json!({"link": row["link"]}))
+                    .collect();
+                items.retain(|row| !query.contains(&format!("'{}'", row["id"].as_str().unwrap())));
+                return Ok(deleted);
+            }
+            Ok(items.iter().take(ROTATION_BATCH).cloned().collect())
+        }
+
+        fn table_exists(&self, _table_name: &str) -> Result<bool, ClioError> {
+            Ok(true)
+        }
+
+        fn url(&self) -> &str {
+            "https://test.supabase.co"
+        }
+    }
+
+    #[test]
+    fn test_rotate_moves_items_out_of_storage() {
+        let rows: Vec<Value> = (0..ROTATION_BATCH + 1)
+            .map(|i| {
+                json!({
+                    "id": format!("item-{i}"),
+                    "source_name": "Blog",
+                    "title": format!("Post {i}"),
+                    "link": format!("https://example.com/{i}"),
+                    "pub_date": if i % 2 == 0 { "2022-03-01T00:00:00Z" } else { "2023-03-01T00:00:00Z" },
+                })
+            })
+            .collect();
+        let client = Arc::new(Stored {
+            items: Mutex::new(rows),
+        });
+        let config = SupabaseConfig {
+            url: "https://test.supabase.co".to_string(),
+            secret_key: "sb_secret_test123".to_string(),
+        };
+        let database = Database::with_client(config, client.clone());
+        let dir = TempDir::new().unwrap();
+        let cold = ColdStorage::in_dir(dir.path());
+
+        let moved = rotate(&database, &cold, Utc::now()).unwrap();
+        assert_eq!(
+            moved,
+            BTreeMap::from([(2022, ROTATION_BATCH / 2 + 1), (2023, ROTATION_BATCH / 2)])
+        );
+        assert!(client.items.lock().unwrap().is_empty());
+        let archived = cold
+            .search(&ItemQuery {
+                text: Some("Post 1000".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(archived.len(), 1);
+    }
+}
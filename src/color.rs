@@ -0,0 +1,146 @@
+use clap::ValueEnum;
+use crossterm::style::{Color, Stylize};
+use std::fmt::Display;
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+
+/// When to color terminal output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color output written to a terminal, unless NO_COLOR is set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// What a piece of output is, which decides its color
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tone {
+    /// Item titles and other headings
+    Title,
+    /// Source names
+    Source,
+    /// Secondary details such as links and progress counters
+    Dim,
+    Success,
+    Warning,
+    Error,
+}
+
+/// Where output is written, since either may be a terminal without the other
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+static CHOICE: OnceLock<ColorChoice> = OnceLock::new();
+
+/// Set when to color output for the rest of the process
+///
+/// The first choice made is kept; without one, output is colored as with
+/// [`ColorChoice::Auto`].
+pub fn init(choice: ColorChoice) {
+    let _ = CHOICE.set(choice);
+}
+
+/// Whether output written to `stream` is colored
+pub fn enabled(stream: Stream) -> bool {
+    let is_terminal = match stream {
+        Stream::Stdout => std::io::stdout().is_terminal(),
+        Stream::Stderr => std::io::stderr().is_terminal(),
+    };
+    should_color(
+        CHOICE.get().copied().unwrap_or_default(),
+        is_terminal,
+        |name| std::env::var(name).ok(),
+    )
+}
+
+/// `text` styled for `tone` when standard output is colored
+pub fn out(tone: Tone, text: impl Display) -> String {
+    paint(Stream::Stdout, tone, text)
+}
+
+/// `text` styled for `tone` when standard error is colored
+pub fn err(tone: Tone, text: impl Display) -> String {
+    paint(Stream::Stderr, tone, text)
+}
+
+/// `text` styled for `tone` when `stream` is colored, and as is otherwise
+pub fn paint(stream: Stream, tone: Tone, text: impl Display) -> String {
+    if !enabled(stream) {
+        return text.to_string();
+    }
+    let text = text.to_string();
+    match tone {
+        Tone::Title => text.bold().to_string(),
+        Tone::Source => text.with(Color::Cyan).to_string(),
+        Tone::Dim => text.dim().to_string(),
+        Tone::Success => text.with(Color::Green).to_string(),
+        Tone::Warning => text.with(Color::Yellow).bold().to_string(),
+        Tone::Error => text.with(Color::Red).bold().to_string(),
+    }
+}
+
+/// Whether to color a stream, given the choice made and the environment
+///
+/// `NO_COLOR` (when not empty) and `TERM=dumb` turn off automatic color,
+/// following <https://no-color.org>; `--color=always` still colors.
+fn should_color(
+    choice: ColorChoice,
+    is_terminal: bool,
+    var: impl Fn(&str) -> Option<String>,
+) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            is_terminal
+                && var("NO_COLOR").is_none_or(|value| value.is_empty())
+                && var("TERM").as_deref() != Some("dumb")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn test_should_color() {
+        assert!(should_color(ColorChoice::Auto, true, env(&[])));
+        assert!(!should_color(ColorChoice::Auto, false, env(&[])));
+        assert!(!should_color(
+            ColorChoice::Auto,
+            true,
+            env(&[("NO_COLOR", "1")])
+        ));
+        assert!(should_color(
+            ColorChoice::Auto,
+            true,
+            env(&[("NO_COLOR", "")])
+        ));
+        assert!(!should_color(
+            ColorChoice::Auto,
+            true,
+            env(&[("TERM", "dumb")])
+        ));
+
+        assert!(should_color(
+            ColorChoice::Always,
+            false,
+            env(&[("NO_COLOR", "1")])
+        ));
+        assert!(!should_color(ColorChoice::Never, true, env(&[])));
+    }
+}
@@ -1,29 +1,556 @@
+use crate::color::{self, Tone};
 use crate::error::{ClioError, ErrorContext};
+use crate::fetcher::DEFAULT_TIMEOUT_SECS;
+use crate::filter::Filter;
+use crate::http::DEFAULT_MAX_REDIRECTS;
+use crate::rules::RuleSet;
+use crate::writes;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::env;
-use std::fs;
-use std::os::unix::fs::PermissionsExt;
-use std::path::PathBuf;
+use std::fs::{self, DirBuilder};
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use toml_edit::{ArrayOfTables, DocumentMut, Item, Table, TableLike, Value};
 use url::Url;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// Environment variable holding RSS sources as a JSON array, replacing the
+/// ones in the configuration file
+const SOURCES_ENV: &str = "CLIO_SOURCES";
+
+/// Prefix of environment variables holding the sources of one other kind as a
+/// JSON array, such as `CLIO_SOURCES_TWITCH`
+const SOURCES_ENV_PREFIX: &str = "CLIO_SOURCES_";
+
+/// Sections of the configuration that `CLIO_<SECTION>` replaces with a JSON
+/// value, such as `CLIO_MUTES='[{"keyword": "crypto"}]'`
+const ENV_SECTIONS: [&str; 6] = [
+    "alerts",
+    "alert_channels",
+    "authors",
+    "mutes",
+    "retention",
+    "read_later",
+];
+
+/// Environment variable naming the configuration file, like `--config`
+const CONFIG_ENV: &str = "CLIO_CONFIG";
+
+/// Prefix of environment variables that override one setting each, such as
+/// `CLIO_SETTINGS_MAX_TITLE_LENGTH=200`
+const SETTINGS_ENV_PREFIX: &str = "CLIO_SETTINGS_";
+
+/// Clio configuration (`~/.clio/config.toml`)
+///
+/// Every part can also be given through the environment, for containers
+/// without a configuration file: `CLIO_SOURCES` holds the RSS sources as a
+/// JSON array, `CLIO_SOURCES_<KIND>` the sources of another kind,
+/// `CLIO_SETTINGS_<NAME>` sets one setting, and `CLIO_ALERTS`,
+/// `CLIO_ALERT_CHANNELS`, `CLIO_AUTHORS`, `CLIO_MUTES`, `CLIO_RETENTION` and
+/// `CLIO_READ_LATER` hold those sections as JSON. Environment values take
+/// precedence over the file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[schemars(title = "clio configuration")]
 pub struct Config {
+    /// Feeds to pull from
     pub sources: Sources,
+    /// Optional settings; omitted keys keep their defaults
+    #[serde(default)]
+    pub settings: Settings,
+    /// Keyword rules checked against the titles of pulled items
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alerts: Vec<AlertRule>,
+    /// How alerts are grouped on each channel
+    #[serde(default, skip_serializing_if = "AlertChannels::is_default")]
+    pub alert_channels: AlertChannels,
+    /// Writers whose items are starred or tagged, whichever source carries them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<AuthorRule>,
+    /// Keywords whose items are dropped as they are pulled, before anything
+    /// else sees them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mutes: Vec<MuteRule>,
+    /// How long `clio prune` keeps stored items; each source follows the
+    /// first rule that selects it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub retention: Vec<RetentionRule>,
+    /// Where `clio save` sends items to read later; the first is the default
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub read_later: Vec<ReadLaterDestination>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// Configured sources, grouped by kind
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct Sources {
+    /// RSS and Atom feeds
     #[serde(default)]
     pub rss: Vec<RssSource>,
+    /// Built-in sources that generate fake items, for demos and load testing
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub synthetic: Vec<SyntheticSource>,
+    /// Security advisories for packages you depend on
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub advisories: Vec<AdvisorySource>,
+    /// Incidents from the status pages of services you depend on
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub statuspage: Vec<StatusPageSource>,
+    /// Releases read from a project's changelog file
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changelog: Vec<ChangelogSource>,
+    /// Twitch channels, announced when they go live
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub twitch: Vec<TwitchSource>,
+    /// Official weather warnings for an area
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub weather: Vec<WeatherSource>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// An RSS or Atom feed
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[schemars(extend("anyOf" = [{"required": ["url"]}, {"required": ["urls"]}]))]
 pub struct RssSource {
+    /// Unique name shown next to the feed's items
+    pub name: String,
+    /// Feed URL (http or https)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    #[schemars(extend("format" = "uri"))]
+    pub url: String,
+    /// Feed URLs tried in order until one answers, in place of `url`, for
+    /// feeds with a flaky primary and reliable mirrors
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub urls: Vec<String>,
+    /// Paused sources stay in the configuration but are skipped on pull
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub paused: bool,
+    /// Free-form labels used to select sources, e.g. `--filter tag:events`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// How items already seen from this feed are recognized
+    #[serde(default, skip_serializing_if = "Dedup::is_link")]
+    pub dedup: Dedup,
+    /// Minimum minutes between fetches, replacing the interval the feed
+    /// declares with `<ttl>`, `sy:updatePeriod` or HTTP caching headers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poll_interval_minutes: Option<u64>,
+    /// Look up paper metadata for items citing a DOI or arXiv ID on pull
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub enrich: Vec<Enrichment>,
+    /// Fold runs of patch releases of one project into a single item, such
+    /// as "tokio 1.38.1–1.38.4", for feeds whose titles are release versions
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub collapse_releases: bool,
+    /// Maximum number of redirects followed for this feed, replacing
+    /// `settings.max_redirects`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_redirects: Option<usize>,
+}
+
+/// How a feed's items are matched against ones seen before
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Dedup {
+    /// The same link is the same item
+    #[default]
+    Link,
+    /// The same title and publication date is the same item, for feeds that
+    /// rewrite their GUIDs and permalinks on every request
+    Fingerprint,
+}
+
+impl Dedup {
+    fn is_link(&self) -> bool {
+        *self == Dedup::Link
+    }
+}
+
+/// Where paper metadata for a source's items is looked up
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Enrichment {
+    /// Crossref, for items whose link or summary contains a DOI
+    Doi,
+    /// The arXiv API, for items linking to or mentioning a preprint
+    Arxiv,
+}
+
+/// A source that makes up its items instead of fetching them
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SyntheticSource {
+    /// Unique name shown next to the generated items
+    pub name: String,
+    /// Number of items generated on each pull
+    #[serde(default = "default_synthetic_items")]
+    pub items: usize,
+    /// Simulated response time in milliseconds
+    #[serde(default)]
+    pub latency_ms: u64,
+    /// Fraction of pulls that fail, from 0.0 (never) to 1.0 (always)
+    #[serde(default)]
+    #[schemars(range(min = 0.0, max = 1.0))]
+    pub failure_rate: f64,
+}
+
+/// Security advisories from the OSV database, which also carries the RustSec
+/// and GitHub advisory databases
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct AdvisorySource {
+    /// Unique name shown next to the advisories
+    pub name: String,
+    /// Package ecosystem as OSV names it, such as "crates.io", "npm" or "PyPI"
+    #[serde(default = "default_ecosystem")]
+    pub ecosystem: String,
+    /// Packages to watch; with `lockfile`, only these of the locked packages
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub packages: Vec<String>,
+    /// Path to a `Cargo.lock`, so only advisories affecting its locked
+    /// versions appear
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lockfile: Option<PathBuf>,
+}
+
+/// Incident updates from a hosted status page
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct StatusPageSource {
+    /// Unique name shown next to the incidents
+    pub name: String,
+    /// Address of the status page, such as `https://www.githubstatus.com`
+    #[schemars(extend("format" = "uri"))]
+    pub url: String,
+    /// Service hosting the page, which decides the API used
+    #[serde(default)]
+    pub provider: StatusProvider,
+}
+
+/// A Markdown changelog, such as a raw `CHANGELOG.md`, whose version
+/// headings become items
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ChangelogSource {
+    /// Unique name shown next to the releases
     pub name: String,
+    /// URL of the raw Markdown file
+    #[schemars(extend("format" = "uri"))]
     pub url: String,
 }
 
+/// Twitch channels polled through the Helix API, using the app credentials
+/// in `TWITCH_CLIENT_ID` and `TWITCH_CLIENT_SECRET`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TwitchSource {
+    /// Unique name shown next to the streams
+    pub name: String,
+    /// Channel logins, as in `twitch.tv/<login>`
+    pub channels: Vec<String>,
+    /// Minutes a "went live" item lasts after the stream was last seen live;
+    /// with `archive_expired`, it is then archived
+    #[serde(default = "default_live_ttl_minutes")]
+    pub live_ttl_minutes: u32,
+}
+
+/// Weather warnings from a national alerting service's CAP feed
+///
+/// Each warning is tagged "weather" and its severity ("extreme", "severe",
+/// "moderate" or "minor"), so an alert rule with `tags = ["extreme", "severe"]`
+/// announces the dangerous ones.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct WeatherSource {
+    /// Unique name shown next to the warnings
+    pub name: String,
+    /// Service publishing the warnings
+    #[serde(default)]
+    pub provider: WeatherProvider,
+    /// For NOAA, a state or marine area code such as `CA`; for MeteoAlarm,
+    /// a country such as `germany`
+    pub area: String,
+}
+
+/// Hosted status page services
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum StatusProvider {
+    /// Atlassian Statuspage, read from `/api/v2/incidents.json`
+    #[default]
+    Statuspage,
+    /// Instatus, read from `/summary.json`; only ongoing incidents are listed
+    Instatus,
+}
+
+/// Weather warning services
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum WeatherProvider {
+    /// The US National Weather Service, at api.weather.gov
+    #[default]
+    Noaa,
+    /// European national weather services, at feeds.meteoalarm.org
+    Meteoalarm,
+}
+
+impl Sources {
+    /// Sources that are not paused
+    pub fn active(&self) -> impl Iterator<Item = &RssSource> {
+        self.rss.iter().filter(|source| !source.paused)
+    }
+
+    /// Names of the feeds whose items are matched by title and date rather
+    /// than link
+    pub fn fingerprinted(&self) -> HashSet<String> {
+        self.rss
+            .iter()
+            .filter(|source| source.dedup == Dedup::Fingerprint)
+            .map(|source| source.name.clone())
+            .collect()
+    }
+}
+
+fn default_synthetic_items() -> usize {
+    20
+}
+
+fn default_live_ttl_minutes() -> u32 {
+    120
+}
+
+fn default_ecosystem() -> String {
+    "crates.io".to_string()
+}
+
+/// Global settings applied to all sources
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Maximum number of characters kept from an item title
+    pub max_title_length: usize,
+    /// Maximum number of characters kept from an item summary
+    pub max_summary_length: usize,
+    /// Permit requests to loopback, link-local, and private network addresses
+    pub allow_private_networks: bool,
+    /// Seconds to wait for a source before giving up on it
+    pub timeout: u64,
+    /// Archive stored items once their feed-provided expiry date has passed
+    pub archive_expired: bool,
+    /// Show item thumbnails in the reader pane of `clio list`
+    pub image_previews: ImagePreviews,
+    /// Maximum number of redirects followed when fetching a feed; a redirect
+    /// back to an address already visited always fails
+    pub max_redirects: usize,
+    /// After each pull, move items published more than this many days ago
+    /// out of storage into yearly archive files in `~/.clio/archives`,
+    /// searched with `clio search --include-archives`; starred items stay
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cold_storage_after_days: Option<u32>,
+}
+
+/// Terminal graphics protocol used for thumbnails
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ImagePreviews {
+    /// Use whichever protocol the terminal supports, if any
+    #[default]
+    Auto,
+    /// Never show images
+    Off,
+    /// Kitty graphics protocol
+    Kitty,
+    /// iTerm2 inline images
+    Iterm2,
+    /// Sixel graphics
+    Sixel,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            max_title_length: 300,
+            max_summary_length: 2000,
+            allow_private_networks: false,
+            timeout: DEFAULT_TIMEOUT_SECS,
+            archive_expired: false,
+            image_previews: ImagePreviews::Auto,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            cold_storage_after_days: None,
+        }
+    }
+}
+
+/// Notify when a pulled item's title contains one of the keywords, or the
+/// item carries one of the tags
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct AlertRule {
+    /// Words or phrases to look for, matched as whole words ignoring case
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+    /// Item tags to look for, such as the "critical" of status page incidents
+    /// or the "severe" of weather warnings
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Where matching items are announced
+    #[serde(default)]
+    pub channel: AlertChannel,
+}
+
+/// Where alerts are delivered
+#[derive(
+    Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertChannel {
+    /// Desktop notification, via `notify-send` on Linux and `osascript` on macOS
+    #[default]
+    Desktop,
+    /// A message on stderr at the end of `clio pull`
+    Terminal,
+}
+
+/// Batching for each alert channel
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlertChannels {
+    pub desktop: ChannelSettings,
+    pub terminal: ChannelSettings,
+}
+
+/// Batching for one alert channel
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChannelSettings {
+    /// Minimum minutes between alerts; matches found sooner wait for the next
+    /// pull after the window ends. With 0, each pull sends at most one alert.
+    pub batch_minutes: u64,
+}
+
+impl AlertChannels {
+    /// Settings for `channel`
+    pub fn get(&self, channel: AlertChannel) -> &ChannelSettings {
+        match channel {
+            AlertChannel::Desktop => &self.desktop,
+            AlertChannel::Terminal => &self.terminal,
+        }
+    }
+
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Star or tag the items of one author as they are pulled
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct AuthorRule {
+    /// Author name as the feeds give it, ignoring case
+    pub name: String,
+    /// Star the author's items
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub star: bool,
+    /// Labels added to the author's items
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// Drop pulled items whose title contains a keyword
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MuteRule {
+    /// Word or phrase to look for, matched as whole words ignoring case
+    pub keyword: String,
+}
+
+/// A place `clio save` sends items to read later
+///
+/// Exactly one of `file`, `webhook` and `command` is set.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ReadLaterDestination {
+    /// Name to pick the destination with `clio save --to`
+    pub name: String,
+    /// Markdown file each saved item is appended to as a link
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    /// URL each saved item is POSTed to as JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<String>,
+    /// Program and arguments run for each saved item, with the item's link
+    /// as the last argument and the item as JSON on standard input
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<Vec<String>>,
+}
+
+/// How long the items of some sources are kept
+///
+/// A rule that sets neither limit keeps its sources' items forever.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RetentionRule {
+    /// Sources the rule covers, as `tag:NAME` or `source:NAME`; when omitted,
+    /// every source not covered by an earlier rule
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    /// Delete items published more than this many days ago
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_days: Option<u32>,
+    /// Delete all but this many of the newest items of each source
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_newest: Option<usize>,
+    /// Keep starred items whatever their age
+    #[serde(default = "default_keep_starred")]
+    pub keep_starred: bool,
+}
+
+fn default_keep_starred() -> bool {
+    true
+}
+
+/// Something wrong with a configuration, found by [`Config::problems`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub severity: Severity,
+    /// Name of the source concerned, if any
+    pub source: Option<String>,
+    pub message: String,
+}
+
+/// Whether a [`Problem`] stops clio from using the configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Problem {
+    pub fn error(source: Option<&str>, message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            source: source.map(str::to_string),
+            message,
+        }
+    }
+
+    pub fn warning(source: Option<&str>, message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            source: source.map(str::to_string),
+            message,
+        }
+    }
+}
+
+/// Sources added and removed between two configurations, by name
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
 #[derive(Clone)]
 pub struct SupabaseConfig {
     pub url: String,
@@ -31,94 +558,574 @@ pub struct SupabaseConfig {
 }
 
 impl Config {
-    pub fn load() -> Result<Self, ClioError> {
-        let config_path = Self::config_path()?;
+    /// Load and validate the configuration file at `path` with environment overrides
+    ///
+    /// Nothing is written: a missing file is an error pointing to `clio init`,
+    /// unless `CLIO_SOURCES` provides the sources.
+    pub fn load_strict(path: &Path) -> Result<Self, ClioError> {
+        let config = Self::load_unvalidated(path)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Load the configuration file at `path` with environment overrides,
+    /// without validating it
+    ///
+    /// For `clio config validate`, which reports every problem instead of the first.
+    pub fn load_unvalidated(path: &Path) -> Result<Self, ClioError> {
+        let config = if path.exists() {
+            Self::read_file(path)?
+        } else if Self::sources_from_env() {
+            Self::default()
+        } else {
+            return Err(config_missing_error(path));
+        };
+        config.with_env_overrides(env::vars())
+    }
+
+    /// Load and validate the configuration file at `path` alone, ignoring the
+    /// environment
+    ///
+    /// For changes that are saved back to the file, so that environment
+    /// overrides are not written into it.
+    pub fn load_file(path: &Path) -> Result<Self, ClioError> {
+        if !path.exists() {
+            return Err(config_missing_error(path));
+        }
+
+        let config = Self::read_file(path)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Whether `CLIO_SOURCES` or `CLIO_SOURCES_<KIND>` replaces sources in
+    /// the configuration file
+    pub fn sources_from_env() -> bool {
+        env::vars_os().any(|(name, _)| {
+            name.to_str()
+                .is_some_and(|name| name == SOURCES_ENV || name.starts_with(SOURCES_ENV_PREFIX))
+        })
+    }
 
-        if !config_path.exists() {
-            Self::init_with_example()?;
+    /// Where the configuration file lives
+    ///
+    /// `requested` comes from `--config`; without it, `CLIO_CONFIG` names the
+    /// file, and otherwise it is `~/.clio/config.toml`.
+    pub fn config_path(requested: Option<&Path>) -> Result<PathBuf, ClioError> {
+        if let Some(path) = requested {
+            return Ok(path.to_path_buf());
+        }
+        match env::var_os(CONFIG_ENV) {
+            Some(path) if !path.is_empty() => Ok(PathBuf::from(path)),
+            _ => Ok(Self::config_dir()?.join("config.toml")),
         }
+    }
+
+    /// The profiles found next to the configuration file at `config_path`:
+    /// every `.toml` file in its directory, named by its file name without
+    /// the extension, in name order
+    pub fn profiles(config_path: &Path) -> Result<Vec<(String, PathBuf)>, ClioError> {
+        let dir = match config_path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let entries = fs::read_dir(dir)
+            .clio_config_err(format!("Failed to read profiles in {}", dir.display()))?;
+        let mut profiles: Vec<(String, PathBuf)> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "toml"))
+            .filter_map(|path| Some((path.file_stem()?.to_str()?.to_string(), path)))
+            .collect();
+        profiles.sort();
+        Ok(profiles)
+    }
 
-        let contents = fs::read_to_string(&config_path).clio_config_err(format!(
+    /// Parse the configuration file at `path` as written, without environment
+    /// overrides or validation
+    pub fn read_file(path: &Path) -> Result<Self, ClioError> {
+        let contents = fs::read_to_string(path).clio_config_err(format!(
             "Failed to read configuration file at {}",
-            config_path.display()
+            path.display()
         ))?;
+        toml::from_str(&contents).clio_config_err("Failed to parse configuration file")
+    }
 
-        let config: Self =
-            toml::from_str(&contents).clio_config_err("Failed to parse configuration file")?;
+    /// Write the example configuration to `path`
+    ///
+    /// An existing configuration is only replaced when `force` is set.
+    pub fn init(path: &Path, force: bool) -> Result<(), ClioError> {
+        Self::check_init(path, force)?;
+        Self::write_config_file(path, include_str!("../data/example_config.toml"))
+    }
 
-        config.validate()?;
+    /// Check that `clio init` can write the configuration to `path`
+    ///
+    /// Fails when a configuration already exists, unless `force` is set, and
+    /// when it could not be written.
+    pub fn check_init(path: &Path, force: bool) -> Result<(), ClioError> {
+        if path.exists() && !force {
+            return Err(ClioError::Config(format!(
+                "Configuration already exists at {}; use --force to replace it",
+                path.display()
+            )));
+        }
+        writes::ensure_allowed("create the configuration")
+    }
 
-        Ok(config)
+    /// JSON Schema describing the configuration file, for editor validation
+    pub fn json_schema() -> String {
+        let schema = schemars::schema_for!(Config);
+        serde_json::to_string_pretty(&schema).expect("schema serializes to JSON") + "\n"
+    }
+
+    /// Write the configuration back to the configuration file at `path`
+    pub fn save(&self, path: &Path) -> Result<(), ClioError> {
+        let contents =
+            toml::to_string_pretty(self).clio_config_err("Failed to serialize configuration")?;
+        writes::ensure_allowed("save the configuration")?;
+        Self::write_config_file(path, &contents)
+    }
+
+    /// Add a feed, rejecting invalid URLs and names already in use
+    pub fn add_source(&mut self, source: RssSource) -> Result<(), ClioError> {
+        self.sources.rss.push(source);
+        let result = self.validate();
+        if result.is_err() {
+            self.sources.rss.pop();
+        }
+        result
+    }
+
+    /// Pause or resume the source with the given name
+    pub fn set_paused(&mut self, name: &str, paused: bool) -> Result<(), ClioError> {
+        let source = self
+            .sources
+            .rss
+            .iter_mut()
+            .find(|source| source.name == name)
+            .ok_or_else(|| ClioError::Config(format!("No source named '{name}'")))?;
+        source.paused = paused;
+        Ok(())
+    }
+
+    /// Replace the settings of the source with the given name, rejecting
+    /// invalid URLs and names already in use
+    pub fn update_source(&mut self, name: &str, source: RssSource) -> Result<(), ClioError> {
+        let index = self
+            .sources
+            .rss
+            .iter()
+            .position(|source| source.name == name)
+            .ok_or_else(|| ClioError::Config(format!("No source named '{name}'")))?;
+        let previous = std::mem::replace(&mut self.sources.rss[index], source);
+        let result = self.validate();
+        if result.is_err() {
+            self.sources.rss[index] = previous;
+        }
+        result
     }
 
+    /// Remove the source with the given name, returning it
+    pub fn remove_source(&mut self, name: &str) -> Result<RssSource, ClioError> {
+        let index = self
+            .sources
+            .rss
+            .iter()
+            .position(|source| source.name == name)
+            .ok_or_else(|| ClioError::Config(format!("No source named '{name}'")))?;
+        Ok(self.sources.rss.remove(index))
+    }
+
+    /// Fail on the first error [`problems`](Self::problems) finds, printing warnings
     pub fn validate(&self) -> Result<(), ClioError> {
-        if self.sources.rss.is_empty() {
-            eprintln!("Warning: No sources configured");
+        let problems = self.problems();
+        for problem in &problems {
+            if problem.severity == Severity::Warning {
+                eprintln!(
+                    "{} {}",
+                    color::err(Tone::Warning, "Warning:"),
+                    problem.message
+                );
+            }
+        }
+
+        match problems
+            .into_iter()
+            .find(|problem| problem.severity == Severity::Error)
+        {
+            Some(problem) => Err(ClioError::Config(problem.message)),
+            None => Ok(()),
+        }
+    }
+
+    /// Everything wrong with the configuration, in source order
+    pub fn problems(&self) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        if self.sources.rss.is_empty()
+            && self.sources.synthetic.is_empty()
+            && self.sources.advisories.is_empty()
+            && self.sources.statuspage.is_empty()
+            && self.sources.changelog.is_empty()
+            && self.sources.twitch.is_empty()
+            && self.sources.weather.is_empty()
+        {
+            problems.push(Problem::warning(None, "No sources configured".to_string()));
         }
 
         let mut seen_names = HashSet::new();
+        let mut seen_urls: HashMap<&str, &str> = HashMap::new();
         for source in &self.sources.rss {
-            if source.name.trim().is_empty() {
-                return Err(ClioError::Config("Source name cannot be empty".to_string()));
+            problems.extend(Self::name_problem(&source.name, &mut seen_names));
+
+            if !source.url.is_empty() && !source.urls.is_empty() {
+                problems.push(Problem::error(
+                    Some(&source.name),
+                    format!(
+                        "Source '{}' has both url and urls; list every address in urls",
+                        source.name
+                    ),
+                ));
+            }
+            let addresses = source.addresses();
+            if addresses.is_empty() {
+                problems.push(Problem::error(
+                    Some(&source.name),
+                    format!("Source '{}' has no url", source.name),
+                ));
+            }
+            for url in addresses {
+                if let Err(message) = Self::validate_url(url) {
+                    problems.push(Problem::error(Some(&source.name), message));
+                }
+
+                match seen_urls.get(url) {
+                    Some(other) => problems.push(Problem::warning(
+                        Some(&source.name),
+                        format!("Duplicate URL {url}, also used by '{other}'"),
+                    )),
+                    None => {
+                        seen_urls.insert(url, &source.name);
+                    }
+                }
+            }
+        }
+
+        for source in &self.sources.synthetic {
+            problems.extend(Self::name_problem(&source.name, &mut seen_names));
+
+            if !(0.0..=1.0).contains(&source.failure_rate) {
+                problems.push(Problem::error(
+                    Some(&source.name),
+                    format!(
+                        "Invalid failure_rate {} for source '{}': must be between 0.0 and 1.0",
+                        source.failure_rate, source.name
+                    ),
+                ));
             }
+        }
+
+        for source in &self.sources.advisories {
+            problems.extend(Self::name_problem(&source.name, &mut seen_names));
 
-            if !seen_names.insert(source.name.clone()) {
-                return Err(ClioError::Config(format!(
-                    "Duplicate source name: {}",
-                    source.name
-                )));
+            if source.packages.is_empty() && source.lockfile.is_none() {
+                problems.push(Problem::error(
+                    Some(&source.name),
+                    format!(
+                        "Advisory source '{}' needs packages or a lockfile to check",
+                        source.name
+                    ),
+                ));
+            }
+            if source.lockfile.is_some() && source.ecosystem != default_ecosystem() {
+                problems.push(Problem::error(
+                    Some(&source.name),
+                    format!(
+                        "Advisory source '{}' has a Cargo.lock but checks the {} ecosystem",
+                        source.name, source.ecosystem
+                    ),
+                ));
             }
+        }
 
-            Self::validate_url(&source.url)?;
+        let pages = self.sources.statuspage.iter().map(|s| (&s.name, &s.url));
+        let changelogs = self.sources.changelog.iter().map(|s| (&s.name, &s.url));
+        for (name, url) in pages.chain(changelogs) {
+            problems.extend(Self::name_problem(name, &mut seen_names));
+
+            if let Err(message) = Self::validate_url(url) {
+                problems.push(Problem::error(Some(name), message));
+            }
         }
 
-        Ok(())
+        for source in &self.sources.twitch {
+            problems.extend(Self::name_problem(&source.name, &mut seen_names));
+
+            if source.channels.is_empty() {
+                problems.push(Problem::error(
+                    Some(&source.name),
+                    format!("Twitch source '{}' has no channels", source.name),
+                ));
+            }
+            if source.live_ttl_minutes == 0 {
+                problems.push(Problem::error(
+                    Some(&source.name),
+                    format!(
+                        "Twitch source '{}' needs a live_ttl_minutes above 0",
+                        source.name
+                    ),
+                ));
+            }
+        }
+
+        for source in &self.sources.weather {
+            problems.extend(Self::name_problem(&source.name, &mut seen_names));
+
+            if source.area.trim().is_empty() {
+                problems.push(Problem::error(
+                    Some(&source.name),
+                    format!("Weather source '{}' has no area", source.name),
+                ));
+            }
+        }
+
+        for rule in &self.authors {
+            if rule.name.trim().is_empty() {
+                problems.push(Problem::error(
+                    None,
+                    "Author rule name cannot be empty".to_string(),
+                ));
+            } else if !rule.star && rule.tags.is_empty() {
+                problems.push(Problem::warning(
+                    None,
+                    format!(
+                        "Author rule for '{}' neither stars nor tags anything",
+                        rule.name
+                    ),
+                ));
+            }
+        }
+
+        let mut catch_all = false;
+        for rule in &self.retention {
+            match &rule.filter {
+                Some(filter) => {
+                    if let Err(e) = filter.parse::<Filter>() {
+                        problems.push(Problem::error(None, e.to_string()));
+                    } else if catch_all {
+                        problems.push(Problem::warning(
+                            None,
+                            format!(
+                                "Retention rule for {filter} never applies: \
+                                 an earlier rule covers every source"
+                            ),
+                        ));
+                    }
+                }
+                None => catch_all = true,
+            }
+        }
+
+        for rule in &self.alerts {
+            if rule.keywords.is_empty() && rule.tags.is_empty() {
+                problems.push(Problem::error(
+                    None,
+                    "Alert has no keywords or tags".to_string(),
+                ));
+            } else if rule
+                .keywords
+                .iter()
+                .any(|keyword| keyword.trim().is_empty())
+            {
+                problems.push(Problem::error(
+                    None,
+                    "Alert keywords cannot be empty".to_string(),
+                ));
+            }
+        }
+
+        for rule in &self.mutes {
+            if rule.keyword.trim().is_empty() {
+                problems.push(Problem::error(
+                    None,
+                    "Mute keywords cannot be empty".to_string(),
+                ));
+            }
+        }
+
+        let mut seen_destinations = HashSet::new();
+        for destination in &self.read_later {
+            let name = &destination.name;
+            if !seen_destinations.insert(name.as_str()) {
+                problems.push(Problem::error(
+                    None,
+                    format!("Duplicate read-later destination: {name}"),
+                ));
+            }
+            let targets = [
+                destination.file.is_some(),
+                destination.webhook.is_some(),
+                destination.command.is_some(),
+            ];
+            if targets.into_iter().filter(|&set| set).count() != 1 {
+                problems.push(Problem::error(
+                    None,
+                    format!(
+                        "Read-later destination '{name}' needs exactly one of file, webhook \
+                         and command"
+                    ),
+                ));
+            }
+            if let Some(Err(message)) = destination.webhook.as_deref().map(Self::validate_url) {
+                problems.push(Problem::error(None, message));
+            }
+            if destination.command.as_ref().is_some_and(Vec::is_empty) {
+                problems.push(Problem::error(
+                    None,
+                    format!("Read-later destination '{name}' has an empty command"),
+                ));
+            }
+        }
+
+        if self.settings.timeout == 0 {
+            problems.push(Problem::error(
+                None,
+                "timeout must be at least 1 second".to_string(),
+            ));
+        }
+
+        problems
     }
 
-    fn config_path() -> Result<PathBuf, ClioError> {
-        let home_dir = dirs::home_dir()
-            .ok_or_else(|| ClioError::Config("Could not determine home directory".to_string()))?;
-        Ok(home_dir.join(".clio").join("config.toml"))
+    /// Sources in `after` that are not in this configuration, and the reverse,
+    /// matched by name
+    pub fn source_changes(&self, after: &Config) -> SourceChanges {
+        let before = self.source_names();
+        let after = after.source_names();
+        SourceChanges {
+            added: after.difference(&before).cloned().collect(),
+            removed: before.difference(&after).cloned().collect(),
+        }
+    }
+
+    /// Names of every configured source, of any kind
+    pub fn source_names(&self) -> BTreeSet<String> {
+        let rss = self.sources.rss.iter().map(|source| source.name.clone());
+        let synthetic = self.sources.synthetic.iter().map(|s| s.name.clone());
+        let advisories = self.sources.advisories.iter().map(|s| s.name.clone());
+        let statuspage = self.sources.statuspage.iter().map(|s| s.name.clone());
+        let changelog = self.sources.changelog.iter().map(|s| s.name.clone());
+        let twitch = self.sources.twitch.iter().map(|s| s.name.clone());
+        let weather = self.sources.weather.iter().map(|s| s.name.clone());
+        rss.chain(synthetic)
+            .chain(advisories)
+            .chain(statuspage)
+            .chain(changelog)
+            .chain(twitch)
+            .chain(weather)
+            .collect()
+    }
+
+    /// An empty or repeated source name
+    fn name_problem(name: &str, seen: &mut HashSet<String>) -> Option<Problem> {
+        if name.trim().is_empty() {
+            Some(Problem::error(
+                None,
+                "Source name cannot be empty".to_string(),
+            ))
+        } else if !seen.insert(name.to_string()) {
+            Some(Problem::error(
+                Some(name),
+                format!("Duplicate source name: {name}"),
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Apply the `CLIO_SOURCES*`, `CLIO_SETTINGS_*` and section variables
+    /// from `vars`
+    ///
+    /// Setting values are read as JSON when they parse as JSON, and as plain
+    /// strings otherwise, so `true`, `200`, and `off` all work unquoted. Other
+    /// values must be JSON.
+    fn with_env_overrides(
+        self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self, ClioError> {
+        let mut config =
+            serde_json::to_value(&self).clio_config_err("Failed to read configuration")?;
+        for (name, value) in vars {
+            let (section, key) = if name == SOURCES_ENV {
+                ("sources", "rss".to_string())
+            } else if let Some(kind) = name.strip_prefix(SOURCES_ENV_PREFIX) {
+                ("sources", kind.to_ascii_lowercase())
+            } else if let Some(key) = name.strip_prefix(SETTINGS_ENV_PREFIX) {
+                ("settings", key.to_ascii_lowercase())
+            } else {
+                match name.strip_prefix("CLIO_").map(str::to_ascii_lowercase) {
+                    Some(section) if ENV_SECTIONS.contains(&section.as_str()) => {
+                        config[section] = serde_json::from_str(&value)
+                            .clio_config_err(format!("Invalid {name}"))?;
+                        Self::from_env_value(&config, &name)?;
+                    }
+                    _ => {}
+                }
+                continue;
+            };
+            let value = match serde_json::from_str(&value) {
+                Ok(value) => value,
+                Err(_) if section == "settings" => serde_json::Value::String(value),
+                Err(e) => return Err(ClioError::Config(format!("Invalid {name}: {e}"))),
+            };
+            config[section][key] = value;
+            Self::from_env_value(&config, &name)?;
+        }
+        Self::from_env_value(&config, "environment configuration")
+    }
+
+    /// The configuration `value` describes, blaming `name` if it is invalid
+    fn from_env_value(value: &serde_json::Value, name: &str) -> Result<Self, ClioError> {
+        serde_json::from_value(value.clone()).clio_config_err(format!("Invalid {name}"))
     }
 
-    fn config_dir() -> Result<PathBuf, ClioError> {
+    pub(crate) fn config_dir() -> Result<PathBuf, ClioError> {
         let home_dir = dirs::home_dir()
             .ok_or_else(|| ClioError::Config("Could not determine home directory".to_string()))?;
         Ok(home_dir.join(".clio"))
     }
 
-    fn validate_url(url_str: &str) -> Result<(), ClioError> {
-        let url = Url::parse(url_str)
-            .map_err(|e| ClioError::Config(format!("Invalid URL '{url_str}': {e}")))?;
+    fn validate_url(url_str: &str) -> Result<(), String> {
+        let url = Url::parse(url_str).map_err(|e| format!("Invalid URL '{url_str}': {e}"))?;
 
         match url.scheme() {
             "http" | "https" => Ok(()),
-            scheme => Err(ClioError::Config(format!(
+            scheme => Err(format!(
                 "Invalid URL scheme '{scheme}': only HTTP and HTTPS are supported"
-            ))),
+            )),
         }
     }
 
-    fn init_with_example() -> Result<(), ClioError> {
-        Self::ensure_config_dir()?;
-        let config_path = Self::config_path()?;
-
-        let example_config = include_str!("../data/example_config.toml");
+    /// Write `contents` to `config_path`, readable only by the user, creating
+    /// its directory if needed
+    fn write_config_file(config_path: &Path, contents: &str) -> Result<(), ClioError> {
+        if let Some(dir) = config_path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+        {
+            Self::ensure_private_dir(dir)?;
+        }
 
-        fs::write(&config_path, example_config).clio_config_err({
-            format!(
-                "Failed to write example configuration to {}",
-                config_path.display()
-            )
+        fs::write(config_path, contents).clio_config_err({
+            format!("Failed to write configuration to {}", config_path.display())
         })?;
 
-        let metadata = fs::metadata(&config_path).clio_config_err(format!(
+        let metadata = fs::metadata(config_path).clio_config_err(format!(
             "Failed to get metadata for {}",
             config_path.display()
         ))?;
         let mut permissions = metadata.permissions();
         permissions.set_mode(0o600);
-        fs::set_permissions(&config_path, permissions).clio_config_err(format!(
+        fs::set_permissions(config_path, permissions).clio_config_err(format!(
             "Failed to set permissions for {}",
             config_path.display()
         ))?;
@@ -126,7 +1133,18 @@ impl Config {
         Ok(())
     }
 
-    fn ensure_config_dir() -> Result<(), ClioError> {
+    /// Create `dir` and any missing parents, readable only by the user
+    ///
+    /// Existing directories are left as they are.
+    pub(crate) fn ensure_private_dir(dir: &Path) -> Result<(), ClioError> {
+        DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)
+            .clio_config_err(format!("Failed to create directory at {}", dir.display()))
+    }
+
+    pub(crate) fn ensure_config_dir() -> Result<(), ClioError> {
         let config_dir = Self::config_dir()?;
 
         if !config_dir.exists() {
@@ -152,239 +1170,1619 @@ impl Config {
     }
 }
 
-impl SupabaseConfig {
-    #[allow(dead_code)]
-    pub fn from_env() -> Result<Self, ClioError> {
-        let url = env::var("SUPABASE_URL").map_err(|_| {
-            ClioError::Config(
-                "Missing SUPABASE_URL environment variable. Please set it to your Supabase project URL.".to_string()
-            )
-        })?;
+/// The configuration file as written, for changes saved back to it
+///
+/// Changes are checked against the parsed [`Config`] and applied to the
+/// file's own text, so comments, ordering and formatting elsewhere in the file
+/// survive them.
+pub struct ConfigFile {
+    path: PathBuf,
+    config: Config,
+    document: DocumentMut,
+}
+
+impl ConfigFile {
+    /// Load and validate the configuration file at `path`, ignoring the environment
+    pub fn open(path: &Path) -> Result<Self, ClioError> {
+        let config = Config::load_file(path)?;
+        let document = fs::read_to_string(path)
+            .clio_config_err(format!(
+                "Failed to read configuration file at {}",
+                path.display()
+            ))?
+            .parse::<DocumentMut>()
+            .clio_config_err("Failed to parse configuration file")?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            config,
+            document,
+        })
+    }
+
+    /// The configuration with the changes made so far
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Add a feed after the last one, rejecting invalid URLs and names already in use
+    pub fn add_source(&mut self, source: RssSource) -> Result<(), ClioError> {
+        self.config.add_source(source.clone())?;
+        let table = source_table(&source)?;
+
+        let sources = self.document.entry("sources").or_insert_with(|| {
+            let mut sources = Table::new();
+            sources.set_implicit(true);
+            Item::Table(sources)
+        });
+        let Some(sources) = sources.as_table_like_mut() else {
+            return Err(file_layout_error("sources"));
+        };
+        match sources.get_mut("rss") {
+            Some(Item::ArrayOfTables(feeds)) => {
+                // Keep the new table with the others instead of at the end of the file
+                let position = feeds.iter().filter_map(Table::position).max();
+                let mut table = table;
+                if let Some(position) = position {
+                    table.set_position(position);
+                }
+                feeds.push(table);
+            }
+            Some(Item::Value(Value::Array(feeds))) => feeds.push(table.into_inline_table()),
+            Some(_) => return Err(file_layout_error("sources.rss")),
+            None => {
+                let mut feeds = ArrayOfTables::new();
+                feeds.push(table);
+                sources.insert("rss", Item::ArrayOfTables(feeds));
+            }
+        }
+        Ok(())
+    }
+
+    /// Pause or resume the source with the given name
+    pub fn set_paused(&mut self, name: &str, paused: bool) -> Result<(), ClioError> {
+        self.config.set_paused(name, paused)?;
+        let feed = self.feed(name)?;
+        if paused {
+            feed.insert("paused", toml_edit::value(true));
+        } else {
+            feed.remove("paused");
+        }
+        Ok(())
+    }
+
+    /// Replace the settings of the source with the given name, keeping its
+    /// place in the file and the comments around it
+    pub fn update_source(&mut self, name: &str, source: RssSource) -> Result<(), ClioError> {
+        self.config.update_source(name, source.clone())?;
+        let table = source_table(&source)?;
+        let feed = self.feed(name)?;
+
+        let stale: Vec<String> = feed
+            .iter()
+            .map(|(key, _)| key.to_string())
+            .filter(|key| !table.contains_key(key))
+            .collect();
+        for key in stale {
+            feed.remove(&key);
+        }
+        // Unchanged values keep their formatting and trailing comments
+        let bare = |item: &Item| {
+            item.as_value().map(|value| {
+                let mut value = value.clone();
+                value.decor_mut().clear();
+                value.to_string()
+            })
+        };
+        for (key, item) in table.iter() {
+            if feed.get(key).and_then(bare) != bare(item) {
+                feed.insert(key, item.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Give the source named `old_name` a new name, keeping the rest of its
+    /// settings and comments
+    pub fn rename_source(&mut self, old_name: &str, new_name: &str) -> Result<(), ClioError> {
+        let mut source = self
+            .config
+            .sources
+            .rss
+            .iter()
+            .find(|source| source.name == old_name)
+            .cloned()
+            .ok_or_else(|| ClioError::Config(format!("No source named '{old_name}'")))?;
+        source.name = new_name.to_string();
+        self.update_source(old_name, source)
+    }
+
+    /// Remove the source with the given name, along with the comments above it
+    pub fn remove_source(&mut self, name: &str) -> Result<RssSource, ClioError> {
+        let source = self.config.remove_source(name)?;
+        let index = self.feed_index(name)?;
+        match self.feeds_mut()? {
+            Item::ArrayOfTables(feeds) => feeds.remove(index),
+            Item::Value(Value::Array(feeds)) => {
+                feeds.remove(index);
+            }
+            _ => return Err(file_layout_error("sources.rss")),
+        }
+        Ok(source)
+    }
+
+    /// Append the rules of `rules` not configured yet, returning those added
+    ///
+    /// Each rule goes after the last one of its kind, so the order rules are
+    /// applied in is kept.
+    pub fn add_rules(&mut self, rules: &RuleSet) -> Result<RuleSet, ClioError> {
+        let added = rules.without(&RuleSet::from_config(&self.config));
+        let mut config = self.config.clone();
+        config.alerts.extend(added.alerts.iter().cloned());
+        config.authors.extend(added.authors.iter().cloned());
+        config.mutes.extend(added.mutes.iter().cloned());
+        config.retention.extend(added.retention.iter().cloned());
+        config.validate()?;
+
+        let tables: [(&str, Vec<Table>); 4] = [
+            (
+                "alerts",
+                added
+                    .alerts
+                    .iter()
+                    .map(toml_table)
+                    .collect::<Result<_, _>>()?,
+            ),
+            (
+                "authors",
+                added
+                    .authors
+                    .iter()
+                    .map(toml_table)
+                    .collect::<Result<_, _>>()?,
+            ),
+            (
+                "mutes",
+                added
+                    .mutes
+                    .iter()
+                    .map(toml_table)
+                    .collect::<Result<_, _>>()?,
+            ),
+            (
+                "retention",
+                added
+                    .retention
+                    .iter()
+                    .map(toml_table)
+                    .collect::<Result<_, _>>()?,
+            ),
+        ];
+        for (key, rules) in tables {
+            if rules.is_empty() {
+                continue;
+            }
+            match self.document.get_mut(key) {
+                Some(Item::ArrayOfTables(existing)) => {
+                    let position = existing.iter().filter_map(Table::position).max();
+                    for mut table in rules {
+                        if let Some(position) = position {
+                            table.set_position(position);
+                        }
+                        existing.push(table);
+                    }
+                }
+                Some(Item::Value(Value::Array(existing))) => {
+                    for table in rules {
+                        existing.push(table.into_inline_table());
+                    }
+                }
+                Some(_) => return Err(file_layout_error(key)),
+                None => {
+                    self.document
+                        .insert(key, Item::ArrayOfTables(rules.into_iter().collect()));
+                }
+            }
+        }
+        self.config = config;
+        Ok(added)
+    }
+
+    /// Remove the mute rule for `keyword`, ignoring case, along with the
+    /// comments above it
+    pub fn remove_mute(&mut self, keyword: &str) -> Result<MuteRule, ClioError> {
+        let is_keyword = |value: Option<&str>| {
+            value.is_some_and(|value| value.trim().eq_ignore_ascii_case(keyword.trim()))
+        };
+        let index = self
+            .config
+            .mutes
+            .iter()
+            .position(|rule| is_keyword(Some(&rule.keyword)))
+            .ok_or_else(|| ClioError::Config(format!("No mute rule for '{keyword}'")))?;
+
+        let mutes = self
+            .document
+            .get_mut("mutes")
+            .ok_or_else(|| file_layout_error("mutes"))?;
+        match mutes {
+            Item::ArrayOfTables(rules) => {
+                let found = rules
+                    .iter()
+                    .position(|rule| is_keyword(rule.get("keyword").and_then(Item::as_str)));
+                rules.remove(found.ok_or_else(|| file_layout_error("mutes"))?);
+            }
+            Item::Value(Value::Array(rules)) => {
+                let found = rules.iter().position(|rule| {
+                    is_keyword(
+                        rule.as_inline_table()
+                            .and_then(|rule| rule.get("keyword"))
+                            .and_then(Value::as_str),
+                    )
+                });
+                rules.remove(found.ok_or_else(|| file_layout_error("mutes"))?);
+            }
+            _ => return Err(file_layout_error("mutes")),
+        }
+        Ok(self.config.mutes.remove(index))
+    }
+
+    /// Write the file back with the changes made
+    pub fn save(&self) -> Result<(), ClioError> {
+        writes::ensure_allowed("save the configuration")?;
+        Config::write_config_file(&self.path, &self.document.to_string())
+    }
+
+    fn feeds_mut(&mut self) -> Result<&mut Item, ClioError> {
+        self.document
+            .get_mut("sources")
+            .and_then(|sources| sources.get_mut("rss"))
+            .ok_or_else(|| file_layout_error("sources.rss"))
+    }
+
+    /// Position of the named feed in `sources.rss`
+    fn feed_index(&mut self, name: &str) -> Result<usize, ClioError> {
+        let is_named =
+            |feed: &dyn TableLike| feed.get("name").and_then(|value| value.as_str()) == Some(name);
+        let index = match self.feeds_mut()? {
+            Item::ArrayOfTables(feeds) => feeds.iter().position(|feed| is_named(feed)),
+            Item::Value(Value::Array(feeds)) => feeds
+                .iter()
+                .position(|feed| feed.as_inline_table().is_some_and(|feed| is_named(feed))),
+            _ => None,
+        };
+        index.ok_or_else(|| file_layout_error("sources.rss"))
+    }
+
+    /// The named feed's table in the file
+    fn feed(&mut self, name: &str) -> Result<&mut dyn TableLike, ClioError> {
+        let index = self.feed_index(name)?;
+        let feed = match self.feeds_mut()? {
+            Item::ArrayOfTables(feeds) => {
+                feeds.get_mut(index).map(|feed| feed as &mut dyn TableLike)
+            }
+            Item::Value(Value::Array(feeds)) => feeds
+                .get_mut(index)
+                .and_then(Value::as_inline_table_mut)
+                .map(|feed| feed as &mut dyn TableLike),
+            _ => None,
+        };
+        feed.ok_or_else(|| file_layout_error("sources.rss"))
+    }
+}
+
+impl SupabaseConfig {
+    pub fn from_env() -> Result<Self, ClioError> {
+        let url = Self::url_from_env()?;
+
+        let secret_key = env::var("SUPABASE_SECRET_KEY").map_err(|_| {
+            ClioError::Config(
+                "Missing SUPABASE_SECRET_KEY environment variable. Please set it to your Supabase service role key (starts with 'sb_secret_').".to_string()
+            )
+        })?;
+
+        let config = Self { url, secret_key };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Build a configuration for `SUPABASE_URL` using a replacement secret key
+    pub fn rotated_from_env(new_secret_key: String) -> Result<Self, ClioError> {
+        let config = Self {
+            url: Self::url_from_env()?,
+            secret_key: new_secret_key,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Previous secret key still accepted during a key rotation grace window
+    ///
+    /// Read from `SUPABASE_PREVIOUS_SECRET_KEY`. Requests rejected with the
+    /// current key are retried with this one until the variable is unset.
+    pub fn previous_secret_key_from_env() -> Result<Option<String>, ClioError> {
+        match env::var("SUPABASE_PREVIOUS_SECRET_KEY") {
+            Ok(key) if key.is_empty() => Ok(None),
+            Ok(key) if key.starts_with("sb_secret_") => Ok(Some(key)),
+            Ok(_) => Err(ClioError::Config(
+                "SUPABASE_PREVIOUS_SECRET_KEY must start with 'sb_secret_'.".to_string(),
+            )),
+            Err(_) => Ok(None),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn secret_key(&self) -> &str {
+        &self.secret_key
+    }
+
+    fn url_from_env() -> Result<String, ClioError> {
+        env::var("SUPABASE_URL").map_err(|_| {
+            ClioError::Config(
+                "Missing SUPABASE_URL environment variable. Please set it to your Supabase project URL.".to_string()
+            )
+        })
+    }
+
+    fn validate(&self) -> Result<(), ClioError> {
+        // Validate URL format
+        let parsed_url = Url::parse(&self.url)
+            .map_err(|e| ClioError::Config(format!("Invalid SUPABASE_URL: {e}")))?;
+
+        if parsed_url.scheme() != "https" {
+            return Err(ClioError::Config(
+                "SUPABASE_URL must use HTTPS protocol".to_string(),
+            ));
+        }
+
+        // Validate secret key format
+        if self.secret_key.is_empty() {
+            return Err(ClioError::Config(
+                "SUPABASE_SECRET_KEY cannot be empty".to_string(),
+            ));
+        }
+
+        if !self.secret_key.starts_with("sb_secret_") {
+            return Err(ClioError::Config(
+                "SUPABASE_SECRET_KEY must start with 'sb_secret_'.".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for SupabaseConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SupabaseConfig")
+            .field("url", &self.url)
+            .field("secret_key", &"<REDACTED>")
+            .finish()
+    }
+}
+
+impl RssSource {
+    pub fn new(name: String, url: String) -> Self {
+        Self {
+            name,
+            url,
+            urls: Vec::new(),
+            paused: false,
+            tags: Vec::new(),
+            dedup: Dedup::Link,
+            poll_interval_minutes: None,
+            enrich: Vec::new(),
+            collapse_releases: false,
+            max_redirects: None,
+        }
+    }
+
+    /// The feed's addresses in the order they are tried: `url`, or each of `urls`
+    pub fn addresses(&self) -> Vec<&str> {
+        std::iter::once(self.url.as_str())
+            .chain(self.urls.iter().map(String::as_str))
+            .filter(|url| !url.is_empty())
+            .collect()
+    }
+
+    /// The address tried first, shown as the feed's location
+    pub fn primary_url(&self) -> &str {
+        self.addresses().first().copied().unwrap_or_default()
+    }
+
+    /// The most redirects followed when fetching this feed
+    pub fn max_redirects(&self, settings: &Settings) -> usize {
+        self.max_redirects.unwrap_or(settings.max_redirects)
+    }
+
+    /// The addresses tried after the primary fails
+    pub fn mirrors(&self) -> Vec<String> {
+        self.addresses()
+            .into_iter()
+            .skip(1)
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+/// A source as a TOML table, the way it is written under `[[sources.rss]]`
+fn source_table(source: &RssSource) -> Result<Table, ClioError> {
+    toml_table(source)
+}
+
+/// `value` as a table of the configuration file
+fn toml_table(value: &impl Serialize) -> Result<Table, ClioError> {
+    Ok(toml::to_string(value)
+        .clio_config_err("Failed to serialize configuration")?
+        .parse::<DocumentMut>()
+        .clio_config_err("Failed to serialize configuration")?
+        .as_table()
+        .clone())
+}
+
+/// The file parsed, but `key` is not laid out in a way it can be edited in place
+fn file_layout_error(key: &str) -> ClioError {
+    ClioError::Config(format!(
+        "Cannot update `{key}` in the configuration file; edit it with `clio config edit`"
+    ))
+}
+
+/// The error for a configuration file that does not exist yet
+fn config_missing_error(path: &Path) -> ClioError {
+    ClioError::Config(format!(
+        "No configuration found at {}. Run `clio init` to create one with example sources.",
+        path.display()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(test)]
+    use serial_test::serial;
+
+    #[test]
+    fn test_config_serialization() {
+        let config = Config {
+            sources: Sources {
+                rss: vec![
+                    RssSource::new(
+                        "Test Feed 1".to_string(),
+                        "https://example.com/feed1.xml".to_string(),
+                    ),
+                    RssSource::new(
+                        "Test Feed 2".to_string(),
+                        "https://example.com/feed2.xml".to_string(),
+                    ),
+                ],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let serialized = toml::to_string(&config).unwrap();
+        let deserialized: Config = toml::from_str(&serialized).unwrap();
+        assert_eq!(config, deserialized);
+    }
+
+    #[test]
+    fn test_config_validation_empty_name() {
+        let config = Config {
+            sources: Sources {
+                rss: vec![RssSource::new(
+                    "".to_string(),
+                    "https://example.com/feed.xml".to_string(),
+                )],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let result = config.validate();
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("empty"));
+    }
+
+    #[test]
+    fn test_config_validation_duplicate_names() {
+        let config = Config {
+            sources: Sources {
+                rss: vec![
+                    RssSource::new(
+                        "Duplicate".to_string(),
+                        "https://example.com/feed1.xml".to_string(),
+                    ),
+                    RssSource::new(
+                        "Duplicate".to_string(),
+                        "https://example.com/feed2.xml".to_string(),
+                    ),
+                ],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let result = config.validate();
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("Duplicate"));
+    }
+
+    #[test]
+    fn test_config_validation_invalid_url() {
+        let config = Config {
+            sources: Sources {
+                rss: vec![RssSource::new("Test".to_string(), "not-a-url".to_string())],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let result = config.validate();
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("Invalid URL"));
+    }
+
+    #[test]
+    fn test_config_validation_invalid_scheme() {
+        let config = Config {
+            sources: Sources {
+                rss: vec![RssSource::new(
+                    "Test".to_string(),
+                    "ftp://example.com/feed.xml".to_string(),
+                )],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let result = config.validate();
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("Invalid URL scheme")
+        );
+    }
+
+    #[test]
+    fn test_config_validation_valid() {
+        let config = Config {
+            sources: Sources {
+                rss: vec![
+                    RssSource::new(
+                        "Feed 1".to_string(),
+                        "https://example.com/feed1.xml".to_string(),
+                    ),
+                    RssSource::new(
+                        "Feed 2".to_string(),
+                        "http://example.com/feed2.xml".to_string(),
+                    ),
+                ],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_validation_empty_sources() {
+        let config = Config {
+            sources: Sources {
+                rss: vec![],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_problems() {
+        let config: Config = toml::from_str(
+            r#"
+            [[sources.rss]]
+            name = "A"
+            url = "https://example.com/feed.xml"
+
+            [[sources.rss]]
+            name = "A"
+            url = "ftp://example.com/feed.xml"
+
+            [[sources.rss]]
+            name = "B"
+            url = "https://example.com/feed.xml"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.problems(),
+            vec![
+                Problem::error(Some("A"), "Duplicate source name: A".to_string()),
+                Problem::error(
+                    Some("A"),
+                    "Invalid URL scheme 'ftp': only HTTP and HTTPS are supported".to_string()
+                ),
+                Problem::warning(
+                    Some("B"),
+                    "Duplicate URL https://example.com/feed.xml, also used by 'A'".to_string()
+                ),
+            ]
+        );
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("Duplicate source name: A"));
+
+        assert_eq!(
+            Config::default().problems(),
+            vec![Problem::warning(None, "No sources configured".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_parse_alerts() {
+        let config: Config = toml::from_str(
+            r#"
+            [sources]
+
+            [[alerts]]
+            keywords = ["CVE", "security advisory"]
+            channel = "terminal"
+
+            [[alerts]]
+            keywords = []
+
+            [[alerts]]
+            tags = ["critical"]
+
+            [alert_channels.desktop]
+            batch_minutes = 30
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.alerts[0].channel, AlertChannel::Terminal);
+        assert_eq!(config.alerts[1].channel, AlertChannel::Desktop);
+        assert_eq!(config.alerts[2].tags, ["critical"]);
+        assert_eq!(
+            config
+                .alert_channels
+                .get(AlertChannel::Desktop)
+                .batch_minutes,
+            30
+        );
+        assert_eq!(
+            config
+                .alert_channels
+                .get(AlertChannel::Terminal)
+                .batch_minutes,
+            0
+        );
+        assert!(config.problems().contains(&Problem::error(
+            None,
+            "Alert has no keywords or tags".to_string()
+        )));
+        assert_eq!(config.problems().len(), 2);
+    }
+
+    #[test]
+    fn test_parse_author_rules() {
+        let config: Config = toml::from_str(
+            r#"
+            [sources]
+
+            [[authors]]
+            name = "Julia Evans"
+            star = true
+            tags = ["favorites"]
+
+            [[authors]]
+            name = "Someone"
+            "#,
+        )
+        .unwrap();
+
+        assert!(config.authors[0].star);
+        assert_eq!(config.authors[0].tags, ["favorites"]);
+        assert!(config.problems().contains(&Problem::warning(
+            None,
+            "Author rule for 'Someone' neither stars nor tags anything".to_string()
+        )));
+    }
+
+    #[test]
+    fn test_retention_problems() {
+        let config: Config = toml::from_str(
+            r#"
+            [sources]
+
+            [[retention]]
+            filter = "tag:papers"
+
+            [[retention]]
+            keep_days = 30
+
+            [[retention]]
+            filter = "tag:news"
+            keep_days = 7
+
+            [[retention]]
+            filter = "folder:news"
+            "#,
+        )
+        .unwrap();
+
+        assert!(config.retention[0].keep_starred);
+        let problems = config.problems();
+        assert!(
+            problems.contains(&Problem::warning(
+                None,
+                "Retention rule for tag:news never applies: an earlier rule covers every source"
+                    .to_string()
+            ))
+        );
+        assert!(problems.iter().any(|problem| {
+            problem.severity == Severity::Error && problem.message.contains("folder:news")
+        }));
+    }
+
+    #[test]
+    fn test_read_later_problems() {
+        let config: Config = toml::from_str(
+            r#"
+            [sources]
+
+            [[read_later]]
+            name = "notes"
+            file = "/home/me/read-later.md"
+
+            [[read_later]]
+            name = "notes"
+            webhook = "ftp://example.com/save"
+
+            [[read_later]]
+            name = "both"
+            file = "/tmp/later.md"
+            command = ["later"]
+
+            [[read_later]]
+            name = "nothing"
+            command = []
+            "#,
+        )
+        .unwrap();
+
+        let errors: Vec<String> = config
+            .problems()
+            .into_iter()
+            .filter(|problem| problem.severity == Severity::Error)
+            .map(|problem| problem.message)
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                "Duplicate read-later destination: notes",
+                "Invalid URL scheme 'ftp': only HTTP and HTTPS are supported",
+                "Read-later destination 'both' needs exactly one of file, webhook and command",
+                "Read-later destination 'nothing' has an empty command",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_profiles() {
+        let dir = tempfile::TempDir::new().unwrap();
+        for file in [
+            "work.toml",
+            "config.toml",
+            "config.schema.json",
+            "audit.log",
+        ] {
+            fs::write(dir.path().join(file), "").unwrap();
+        }
+        fs::create_dir(dir.path().join("old.toml")).unwrap();
+
+        let profiles = Config::profiles(&dir.path().join("config.toml")).unwrap();
+        assert_eq!(
+            profiles,
+            [
+                ("config".to_string(), dir.path().join("config.toml")),
+                ("work".to_string(), dir.path().join("work.toml")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_source_changes() {
+        let before: Config = toml::from_str(
+            r#"
+            [[sources.rss]]
+            name = "Kept"
+            url = "https://example.com/kept.xml"
+
+            [[sources.rss]]
+            name = "Dropped"
+            url = "https://example.com/dropped.xml"
+            "#,
+        )
+        .unwrap();
+        let after: Config = toml::from_str(
+            r#"
+            [[sources.rss]]
+            name = "Kept"
+            url = "https://example.com/moved.xml"
+
+            [[sources.synthetic]]
+            name = "Demo"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            before.source_changes(&after),
+            SourceChanges {
+                added: vec!["Demo".to_string()],
+                removed: vec!["Dropped".to_string()],
+            }
+        );
+        assert_eq!(after.source_changes(&after), SourceChanges::default());
+    }
+
+    #[test]
+    fn test_url_validation() {
+        assert!(Config::validate_url("https://example.com/feed.xml").is_ok());
+        assert!(Config::validate_url("http://example.com/feed.xml").is_ok());
+        assert!(Config::validate_url("https://example.com:8080/feed.xml").is_ok());
+
+        assert!(Config::validate_url("ftp://example.com/feed.xml").is_err());
+        assert!(Config::validate_url("file:///etc/passwd").is_err());
+        assert!(Config::validate_url("not-a-url").is_err());
+        assert!(Config::validate_url("").is_err());
+    }
+
+    #[test]
+    fn test_parse_valid_toml() {
+        let toml_content = r#"
+[[sources.rss]]
+name = "Test Feed"
+url = "https://example.com/feed.xml"
+
+[[sources.rss]]
+name = "Another Feed"
+url = "http://example.org/rss"
+"#;
+
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.sources.rss.len(), 2);
+        assert_eq!(config.sources.rss[0].name, "Test Feed");
+        assert_eq!(config.sources.rss[1].name, "Another Feed");
+    }
+
+    #[test]
+    fn test_parse_synthetic_sources() {
+        let toml_content = r#"
+[[sources.rss]]
+name = "Feed"
+url = "https://example.com/feed.xml"
+
+[[sources.synthetic]]
+name = "Demo"
+
+[[sources.synthetic]]
+name = "Flaky"
+items = 500
+latency_ms = 250
+failure_rate = 0.2
+"#;
+
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.sources.synthetic[0].items, 20);
+        assert_eq!(config.sources.synthetic[0].latency_ms, 0);
+        assert_eq!(config.sources.synthetic[0].failure_rate, 0.0);
+        assert_eq!(config.sources.synthetic[1].items, 500);
+        assert_eq!(config.sources.synthetic[1].failure_rate, 0.2);
+
+        let mut invalid = config.clone();
+        invalid.sources.synthetic[1].failure_rate = 1.5;
+        let err = invalid.validate().unwrap_err();
+        assert!(err.to_string().contains("must be between 0.0 and 1.0"));
+
+        let mut duplicate = config;
+        duplicate.sources.synthetic[0].name = "Feed".to_string();
+        let err = duplicate.validate().unwrap_err();
+        assert!(err.to_string().contains("Duplicate source name: Feed"));
+    }
+
+    #[test]
+    fn test_parse_advisory_sources() {
+        let toml_content = r#"
+[[sources.advisories]]
+name = "Crate advisories"
+lockfile = "/home/me/project/Cargo.lock"
+
+[[sources.advisories]]
+name = "Frontend"
+ecosystem = "npm"
+packages = ["lodash", "left-pad"]
+"#;
+
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert!(config.validate().is_ok());
+        let crates = &config.sources.advisories[0];
+        assert_eq!(crates.ecosystem, "crates.io");
+        assert_eq!(
+            crates.lockfile.as_deref(),
+            Some(Path::new("/home/me/project/Cargo.lock"))
+        );
+        assert_eq!(
+            config.sources.advisories[1].packages,
+            ["lodash", "left-pad"]
+        );
+
+        let mut empty = config.clone();
+        empty.sources.advisories[1].packages.clear();
+        let err = empty.validate().unwrap_err();
+        assert!(err.to_string().contains("needs packages or a lockfile"));
+
+        let mut mismatched = config;
+        mismatched.sources.advisories[0].ecosystem = "PyPI".to_string();
+        let err = mismatched.validate().unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("has a Cargo.lock but checks the PyPI ecosystem")
+        );
+    }
+
+    #[test]
+    fn test_parse_statuspage_sources() {
+        let toml_content = r#"
+[[sources.statuspage]]
+name = "GitHub status"
+url = "https://www.githubstatus.com"
+
+[[sources.statuspage]]
+name = "Acme status"
+url = "https://status.acme.dev"
+provider = "instatus"
+"#;
+
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            config.sources.statuspage[0].provider,
+            StatusProvider::Statuspage
+        );
+        assert_eq!(
+            config.sources.statuspage[1].provider,
+            StatusProvider::Instatus
+        );
+
+        let mut invalid = config;
+        invalid.sources.statuspage[1].url = "status.acme.dev".to_string();
+        invalid.sources.changelog.push(ChangelogSource {
+            name: "GitHub status".to_string(),
+            url: "https://example.com/CHANGELOG.md".to_string(),
+        });
+        let err = invalid.validate().unwrap_err();
+        assert!(err.to_string().contains("Invalid URL 'status.acme.dev'"));
+        assert!(invalid.problems().contains(&Problem::error(
+            Some("GitHub status"),
+            "Duplicate source name: GitHub status".to_string()
+        )));
+    }
+
+    #[test]
+    fn test_parse_twitch_sources() {
+        let toml_content = r#"
+[[sources.twitch]]
+name = "Streams"
+channels = ["rustlang", "jonhoo"]
+
+[[sources.twitch]]
+name = "Nobody"
+channels = []
+live_ttl_minutes = 0
+"#;
+
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.sources.twitch[0].channels, ["rustlang", "jonhoo"]);
+        assert_eq!(config.sources.twitch[0].live_ttl_minutes, 120);
+        let problems = config.problems();
+        assert!(problems.contains(&Problem::error(
+            Some("Nobody"),
+            "Twitch source 'Nobody' has no channels".to_string()
+        )));
+        assert!(problems.contains(&Problem::error(
+            Some("Nobody"),
+            "Twitch source 'Nobody' needs a live_ttl_minutes above 0".to_string()
+        )));
+    }
+
+    #[test]
+    fn test_parse_mirror_urls() {
+        let toml_content = r#"
+[[sources.rss]]
+name = "Flaky"
+urls = ["https://primary.example.com/feed", "https://mirror.example.com/feed"]
+
+[[sources.rss]]
+name = "Both"
+url = "https://both.example.com/feed"
+urls = ["https://both-mirror.example.com/feed"]
+
+[[sources.rss]]
+name = "Neither"
+"#;
+
+        let config: Config = toml::from_str(toml_content).unwrap();
+        let flaky = &config.sources.rss[0];
+        assert_eq!(flaky.primary_url(), "https://primary.example.com/feed");
+        assert_eq!(flaky.mirrors(), ["https://mirror.example.com/feed"]);
+        assert_eq!(
+            config.problems(),
+            [
+                Problem::error(
+                    Some("Both"),
+                    "Source 'Both' has both url and urls; list every address in urls".to_string()
+                ),
+                Problem::error(Some("Neither"), "Source 'Neither' has no url".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_weather_sources() {
+        let toml_content = r#"
+[[sources.weather]]
+name = "California"
+area = "CA"
+
+[[sources.weather]]
+name = "Germany"
+provider = "meteoalarm"
+area = " "
+"#;
+
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.sources.weather[0].provider, WeatherProvider::Noaa);
+        assert_eq!(
+            config.sources.weather[1].provider,
+            WeatherProvider::Meteoalarm
+        );
+        assert_eq!(
+            config.problems(),
+            [Problem::error(
+                Some("Germany"),
+                "Weather source 'Germany' has no area".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_add_source() {
+        let mut config = Config {
+            sources: Sources {
+                rss: vec![RssSource::new(
+                    "Feed".to_string(),
+                    "https://example.com/feed.xml".to_string(),
+                )],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let err = config
+            .add_source(RssSource::new(
+                "Feed".to_string(),
+                "https://example.org/rss".to_string(),
+            ))
+            .unwrap_err();
+        assert!(err.to_string().contains("Duplicate source name: Feed"));
+
+        let err = config
+            .add_source(RssSource::new(
+                "Other".to_string(),
+                "ftp://example.org/rss".to_string(),
+            ))
+            .unwrap_err();
+        assert!(err.to_string().contains("Invalid URL scheme 'ftp'"));
+        assert_eq!(config.sources.rss.len(), 1);
+
+        config
+            .add_source(RssSource::new(
+                "Other".to_string(),
+                "https://example.org/rss".to_string(),
+            ))
+            .unwrap();
+        assert_eq!(config.sources.rss[1].name, "Other");
+    }
+
+    #[test]
+    fn test_parse_dedup() {
+        let toml_content = r#"
+[[sources.rss]]
+name = "Broken Feed"
+url = "https://example.com/feed.xml"
+dedup = "fingerprint"
+
+[[sources.rss]]
+name = "Good Feed"
+url = "https://example.org/rss"
+"#;
+
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.sources.rss[0].dedup, Dedup::Fingerprint);
+        assert_eq!(config.sources.rss[1].dedup, Dedup::Link);
+        assert!(
+            !toml::to_string(&config.sources.rss[1])
+                .unwrap()
+                .contains("dedup")
+        );
+    }
+
+    #[test]
+    fn test_parse_max_redirects() {
+        let toml_content = r#"
+[settings]
+max_redirects = 5
+
+[[sources.rss]]
+name = "Hopping Feed"
+url = "https://example.com/feed.xml"
+max_redirects = 1
 
-        let secret_key = env::var("SUPABASE_SECRET_KEY").map_err(|_| {
-            ClioError::Config(
-                "Missing SUPABASE_SECRET_KEY environment variable. Please set it to your Supabase service role key (starts with 'sb_secret_').".to_string()
-            )
-        })?;
+[[sources.rss]]
+name = "Good Feed"
+url = "https://example.org/rss"
+"#;
 
-        let config = Self { url, secret_key };
-        config.validate()?;
-        Ok(config)
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.sources.rss[0].max_redirects(&config.settings), 1);
+        assert_eq!(config.sources.rss[1].max_redirects(&config.settings), 5);
+        assert_eq!(
+            RssSource::new("Feed".to_string(), "https://example.com/a.xml".to_string())
+                .max_redirects(&Settings::default()),
+            DEFAULT_MAX_REDIRECTS
+        );
     }
 
-    #[allow(dead_code)]
-    pub fn url(&self) -> &str {
-        &self.url
-    }
+    #[test]
+    fn test_parse_enrich() {
+        let toml_content = r#"
+[[sources.rss]]
+name = "arXiv cs.PL"
+url = "https://rss.arxiv.org/rss/cs.PL"
+enrich = ["arxiv", "doi"]
+"#;
 
-    #[allow(dead_code)]
-    pub fn secret_key(&self) -> &str {
-        &self.secret_key
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(
+            config.sources.rss[0].enrich,
+            [Enrichment::Arxiv, Enrichment::Doi]
+        );
+        assert!(toml::from_str::<Config>(&toml_content.replace("\"doi\"", "\"isbn\"")).is_err());
     }
 
-    #[allow(dead_code)]
-    fn validate(&self) -> Result<(), ClioError> {
-        // Validate URL format
-        let parsed_url = Url::parse(&self.url)
-            .map_err(|e| ClioError::Config(format!("Invalid SUPABASE_URL: {e}")))?;
+    #[test]
+    fn test_config_file_edits_keep_comments() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(
+            &path,
+            r#"# My feeds
 
-        if parsed_url.scheme() != "https" {
-            return Err(ClioError::Config(
-                "SUPABASE_URL must use HTTPS protocol".to_string(),
-            ));
-        }
+[settings]
+max_title_length = 120 # narrow terminal
 
-        // Validate secret key format
-        if self.secret_key.is_empty() {
-            return Err(ClioError::Config(
-                "SUPABASE_SECRET_KEY cannot be empty".to_string(),
-            ));
-        }
+# Daily reading
+[[sources.rss]]
+name = "Blog"
+url = "https://blog.example.com/feed.xml"
 
-        if !self.secret_key.starts_with("sb_secret_") {
-            return Err(ClioError::Config(
-                "SUPABASE_SECRET_KEY must start with 'sb_secret_'.".to_string(),
-            ));
-        }
+# Too noisy, trying it out
+[[sources.rss]]
+name    = "Noisy"
+url     = "https://noisy.example.com/feed.xml"
+tags    = ["news"]
+"#,
+        )
+        .unwrap();
+
+        let mut file = ConfigFile::open(&path).unwrap();
+        file.set_paused("Blog", true).unwrap();
+        file.add_source(RssSource::new(
+            "Podcast".to_string(),
+            "https://podcast.example.com/feed.xml".to_string(),
+        ))
+        .unwrap();
+        file.remove_source("Noisy").unwrap();
+        assert!(
+            file.add_source(RssSource::new(
+                "Blog".to_string(),
+                "https://other.example.com/feed.xml".to_string(),
+            ))
+            .is_err()
+        );
+        file.save().unwrap();
 
-        Ok(())
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            r#"# My feeds
+
+[settings]
+max_title_length = 120 # narrow terminal
+
+# Daily reading
+[[sources.rss]]
+name = "Blog"
+url = "https://blog.example.com/feed.xml"
+paused = true
+
+[[sources.rss]]
+name = "Podcast"
+url = "https://podcast.example.com/feed.xml"
+"#
+        );
+        let saved = Config::load_file(&path).unwrap();
+        assert_eq!(saved.sources.rss.len(), 2);
+        assert_eq!(file.config().sources.rss, saved.sources.rss);
+
+        let mut file = ConfigFile::open(&path).unwrap();
+        file.set_paused("Blog", false).unwrap();
+        file.save().unwrap();
+        assert!(!fs::read_to_string(&path).unwrap().contains("paused"));
     }
-}
 
-impl std::fmt::Debug for SupabaseConfig {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("SupabaseConfig")
-            .field("url", &self.url)
-            .field("secret_key", &"<REDACTED>")
-            .finish()
+    #[test]
+    fn test_config_file_inline_sources() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(
+            &path,
+            "[sources]\nrss = [\n  { name = \"Blog\", url = \"https://blog.example.com/feed.xml\" }, # mine\n]\n",
+        )
+        .unwrap();
+
+        let mut file = ConfigFile::open(&path).unwrap();
+        file.set_paused("Blog", true).unwrap();
+        file.add_source(RssSource::new(
+            "News".to_string(),
+            "https://news.example.com/feed.xml".to_string(),
+        ))
+        .unwrap();
+        file.save().unwrap();
+
+        let saved = fs::read_to_string(&path).unwrap();
+        assert!(saved.contains("# mine"));
+        let saved = Config::load_file(&path).unwrap();
+        assert!(saved.sources.rss[0].paused);
+        assert_eq!(saved.sources.rss[1].name, "News");
     }
-}
 
-impl RssSource {
-    // Public for use in integration tests
-    #[allow(dead_code)]
-    pub fn new(name: String, url: String) -> Self {
-        Self { name, url }
+    #[test]
+    fn test_config_file_update_source() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(
+            &path,
+            "[[sources.rss]]\nname = \"Blog\" # short name\nurl = \"https://blog.example.com/feed.xml\"\ntags = [\"old\"]\n\n[[sources.rss]]\nname = \"News\"\nurl = \"https://news.example.com/feed.xml\"\n",
+        )
+        .unwrap();
+
+        let mut file = ConfigFile::open(&path).unwrap();
+        let mut blog = file.config().sources.rss[0].clone();
+        blog.tags = vec!["new".to_string()];
+        file.update_source("Blog", blog).unwrap();
+        let taken = RssSource::new(
+            "News".to_string(),
+            "https://blog.example.com/feed.xml".to_string(),
+        );
+        assert!(file.update_source("Blog", taken).is_err());
+        assert!(
+            file.update_source(
+                "Missing",
+                RssSource::new(
+                    "Missing".to_string(),
+                    "https://example.com/a.xml".to_string()
+                )
+            )
+            .is_err()
+        );
+        file.save().unwrap();
+
+        let saved = fs::read_to_string(&path).unwrap();
+        assert!(saved.contains("name = \"Blog\" # short name"));
+        assert!(saved.contains("tags = [\"new\"]"));
+        let saved = Config::load_file(&path).unwrap();
+        assert_eq!(saved.sources.rss[0].name, "Blog");
+        assert_eq!(saved.sources.rss[1].name, "News");
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    #[cfg(test)]
-    use serial_test::serial;
+    #[test]
+    fn test_config_file_add_rules() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(
+            &path,
+            "[[sources.rss]]\nname = \"Blog\"\nurl = \"https://blog.example.com/feed.xml\"\n\n# Outages\n[[alerts]]\nkeywords = [\"outage\"]\n\n[settings]\nmax_title_length = 100\n",
+        )
+        .unwrap();
+
+        let rules: RuleSet = toml::from_str(
+            "[[alerts]]\nkeywords = [\"outage\"]\n\n[[alerts]]\nkeywords = [\"rust\"]\n\n[[authors]]\nname = \"Julia Evans\"\nstar = true\n",
+        )
+        .unwrap();
+        let mut file = ConfigFile::open(&path).unwrap();
+        let added = file.add_rules(&rules).unwrap();
+        assert_eq!(added.alerts.len(), 1);
+        assert_eq!(added.authors.len(), 1);
+        file.save().unwrap();
+
+        let saved = fs::read_to_string(&path).unwrap();
+        assert!(saved.contains("# Outages\n[[alerts]]\nkeywords = [\"outage\"]\n"));
+        // New alerts go after the existing one, not after the settings
+        assert!(saved.find("\"rust\"").unwrap() < saved.find("[settings]").unwrap());
+        let saved = Config::load_file(&path).unwrap();
+        assert_eq!(saved.alerts.len(), 2);
+        assert_eq!(saved.alerts[1].keywords, ["rust"]);
+        assert_eq!(saved.authors[0].name, "Julia Evans");
+
+        let bad: RuleSet =
+            toml::from_str("[[retention]]\nfilter = \"nonsense\"\nkeep_days = 7\n").unwrap();
+        assert!(ConfigFile::open(&path).unwrap().add_rules(&bad).is_err());
+    }
 
     #[test]
-    fn test_config_serialization() {
-        let config = Config {
-            sources: Sources {
-                rss: vec![
-                    RssSource::new(
-                        "Test Feed 1".to_string(),
-                        "https://example.com/feed1.xml".to_string(),
-                    ),
-                    RssSource::new(
-                        "Test Feed 2".to_string(),
-                        "https://example.com/feed2.xml".to_string(),
-                    ),
-                ],
-            },
-        };
+    fn test_config_file_remove_mute() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(
+            &path,
+            "[sources]\nrss = []\n\n[[mutes]]\nkeyword = \"crypto\"\n\n# Too noisy\n[[mutes]]\nkeyword = \"NFT\"\n",
+        )
+        .unwrap();
+
+        let mut file = ConfigFile::open(&path).unwrap();
+        assert!(file.remove_mute("blockchain").is_err());
+        assert_eq!(file.remove_mute("nft").unwrap().keyword, "NFT");
+        file.save().unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "[sources]\nrss = []\n\n[[mutes]]\nkeyword = \"crypto\"\n"
+        );
+        assert_eq!(Config::load_file(&path).unwrap().mutes.len(), 1);
+    }
 
-        let serialized = toml::to_string(&config).unwrap();
-        let deserialized: Config = toml::from_str(&serialized).unwrap();
-        assert_eq!(config, deserialized);
+    #[test]
+    fn test_config_file_rename_source() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(
+            &path,
+            "# Daily\n[[sources.rss]]\nname = \"HN\"\nurl = \"https://news.ycombinator.com/rss\" # front page\n\n[[sources.rss]]\nname = \"Blog\"\nurl = \"https://blog.example.com/feed.xml\"\n",
+        )
+        .unwrap();
+
+        let mut file = ConfigFile::open(&path).unwrap();
+        assert!(file.rename_source("HN", "Blog").is_err());
+        assert!(file.rename_source("Missing", "Other").is_err());
+        file.rename_source("HN", "Hacker News").unwrap();
+        file.save().unwrap();
+
+        let saved = fs::read_to_string(&path).unwrap();
+        assert!(saved.starts_with("# Daily\n[[sources.rss]]\nname = \"Hacker News\"\n"));
+        assert!(saved.contains("url = \"https://news.ycombinator.com/rss\" # front page"));
     }
 
     #[test]
-    fn test_config_validation_empty_name() {
-        let config = Config {
+    fn test_set_paused() {
+        let mut config = Config {
             sources: Sources {
                 rss: vec![RssSource::new(
-                    "".to_string(),
+                    "Feed".to_string(),
                     "https://example.com/feed.xml".to_string(),
                 )],
+                ..Default::default()
             },
+            ..Default::default()
         };
 
-        let result = config.validate();
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("empty"));
-    }
+        config.set_paused("Feed", true).unwrap();
+        assert!(config.sources.rss[0].paused);
+        assert_eq!(config.sources.active().count(), 0);
 
-    #[test]
-    fn test_config_validation_duplicate_names() {
-        let config = Config {
-            sources: Sources {
-                rss: vec![
-                    RssSource::new(
-                        "Duplicate".to_string(),
-                        "https://example.com/feed1.xml".to_string(),
-                    ),
-                    RssSource::new(
-                        "Duplicate".to_string(),
-                        "https://example.com/feed2.xml".to_string(),
-                    ),
-                ],
-            },
-        };
+        config.set_paused("Feed", false).unwrap();
+        assert_eq!(config.sources.active().count(), 1);
 
-        let result = config.validate();
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("Duplicate"));
+        let err = config.set_paused("Missing", true).unwrap_err();
+        assert!(err.to_string().contains("No source named 'Missing'"));
     }
 
     #[test]
-    fn test_config_validation_invalid_url() {
-        let config = Config {
-            sources: Sources {
-                rss: vec![RssSource::new("Test".to_string(), "not-a-url".to_string())],
-            },
-        };
-
-        let result = config.validate();
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("Invalid URL"));
+    fn test_paused_serialization() {
+        let mut source = RssSource::new("Feed".to_string(), "https://example.com".to_string());
+        assert!(!toml::to_string(&source).unwrap().contains("paused"));
+
+        source.paused = true;
+        let serialized = toml::to_string(&source).unwrap();
+        assert!(serialized.contains("paused = true"));
+        assert_eq!(toml::from_str::<RssSource>(&serialized).unwrap(), source);
     }
 
     #[test]
-    fn test_config_validation_invalid_scheme() {
-        let config = Config {
-            sources: Sources {
-                rss: vec![RssSource::new(
-                    "Test".to_string(),
-                    "ftp://example.com/feed.xml".to_string(),
-                )],
-            },
-        };
+    fn test_parse_settings() {
+        let toml_content = r#"
+[settings]
+max_title_length = 80
+archive_expired = true
+image_previews = "sixel"
 
-        let result = config.validate();
-        assert!(result.is_err());
-        assert!(
-            result
-                .unwrap_err()
-                .to_string()
-                .contains("Invalid URL scheme")
+[[sources.rss]]
+name = "Test Feed"
+url = "https://example.com/feed.xml"
+"#;
+
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.settings.max_title_length, 80);
+        assert!(config.settings.archive_expired);
+        assert_eq!(config.settings.image_previews, ImagePreviews::Sixel);
+        assert_eq!(
+            config.settings.max_summary_length,
+            Settings::default().max_summary_length
         );
     }
 
     #[test]
-    fn test_config_validation_valid() {
-        let config = Config {
-            sources: Sources {
-                rss: vec![
-                    RssSource::new(
-                        "Feed 1".to_string(),
-                        "https://example.com/feed1.xml".to_string(),
-                    ),
-                    RssSource::new(
-                        "Feed 2".to_string(),
-                        "http://example.com/feed2.xml".to_string(),
-                    ),
-                ],
-            },
-        };
+    fn test_parse_rejects_unknown_fields() {
+        let toml_content = r#"
+[[sources.rss]]
+name = "Test Feed"
+ulr = "https://example.com/feed.xml"
+"#;
+        let err = toml::from_str::<Config>(toml_content).unwrap_err();
+        assert!(err.to_string().contains("unknown field `ulr`"));
 
-        assert!(config.validate().is_ok());
+        let toml_content = "[sources]\n[settings]\nmax_title_lenght = 80\n";
+        let err = toml::from_str::<Config>(toml_content).unwrap_err();
+        assert!(err.to_string().contains("unknown field `max_title_lenght`"));
     }
 
     #[test]
-    fn test_config_validation_empty_sources() {
-        let config = Config {
-            sources: Sources { rss: vec![] },
-        };
-
-        assert!(config.validate().is_ok());
+    fn test_json_schema_is_published() {
+        // data/config.schema.json is generated from these types; regenerate it
+        // with `clio config schema > data/config.schema.json` after changing them
+        assert_eq!(
+            Config::json_schema(),
+            include_str!("../data/config.schema.json")
+        );
     }
 
     #[test]
-    fn test_url_validation() {
-        assert!(Config::validate_url("https://example.com/feed.xml").is_ok());
-        assert!(Config::validate_url("http://example.com/feed.xml").is_ok());
-        assert!(Config::validate_url("https://example.com:8080/feed.xml").is_ok());
-
-        assert!(Config::validate_url("ftp://example.com/feed.xml").is_err());
-        assert!(Config::validate_url("file:///etc/passwd").is_err());
-        assert!(Config::validate_url("not-a-url").is_err());
-        assert!(Config::validate_url("").is_err());
+    fn test_json_schema_describes_sources() {
+        let schema: serde_json::Value = serde_json::from_str(&Config::json_schema()).unwrap();
+        let source = &schema["$defs"]["RssSource"];
+        assert_eq!(source["required"], serde_json::json!(["name"]));
+        assert_eq!(
+            source["anyOf"],
+            serde_json::json!([{"required": ["url"]}, {"required": ["urls"]}])
+        );
+        assert_eq!(source["additionalProperties"], false);
+        assert_eq!(source["properties"]["url"]["format"], "uri");
+        assert_eq!(
+            schema["$defs"]["Settings"]["properties"]["max_title_length"]["default"],
+            300
+        );
     }
 
     #[test]
-    fn test_parse_valid_toml() {
-        let toml_content = r#"
-[[sources.rss]]
-name = "Test Feed"
-url = "https://example.com/feed.xml"
-
-[[sources.rss]]
-name = "Another Feed"
-url = "http://example.org/rss"
-"#;
+    fn test_env_overrides() {
+        let vars = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect::<Vec<_>>()
+        };
+        let config: Config = toml::from_str(
+            r#"
+            [[sources.rss]]
+            name = "File Feed"
+            url = "https://example.com/file.xml"
+
+            [settings]
+            max_title_length = 100
+            archive_expired = true
+            "#,
+        )
+        .unwrap();
+
+        let config = config
+            .with_env_overrides(vars(&[
+                (
+                    "CLIO_SOURCES",
+                    r#"[{"name": "HN", "url": "https://news.ycombinator.com/rss", "tags": ["tech"]}]"#,
+                ),
+                ("CLIO_SETTINGS_MAX_TITLE_LENGTH", "200"),
+                ("CLIO_SETTINGS_IMAGE_PREVIEWS", "off"),
+                ("CLIO_RECORD", "1"),
+            ]))
+            .unwrap();
+        assert_eq!(config.sources.rss.len(), 1);
+        assert_eq!(config.sources.rss[0].name, "HN");
+        assert_eq!(config.sources.rss[0].tags, vec!["tech"]);
+        assert_eq!(config.settings.max_title_length, 200);
+        assert_eq!(config.settings.image_previews, ImagePreviews::Off);
+        // Values not overridden keep what the file says
+        assert!(config.settings.archive_expired);
+
+        let config = Config::default()
+            .with_env_overrides(vars(&[("CLIO_SETTINGS_TIMEOUT", "20")]))
+            .unwrap();
+        assert_eq!(config.settings.timeout, 20);
+
+        let err = Config::default()
+            .with_env_overrides(vars(&[("CLIO_SETTINGS_TIMEOUTS", "20")]))
+            .unwrap_err();
+        assert!(err.to_string().contains("unknown field `timeouts`"));
+
+        let err = Config::default()
+            .with_env_overrides(vars(&[(
+                "CLIO_SOURCES",
+                r#"[{"url": "https://hn.example"}]"#,
+            )]))
+            .unwrap_err();
+        assert!(err.to_string().contains("Invalid CLIO_SOURCES"));
+    }
 
-        let config: Config = toml::from_str(toml_content).unwrap();
-        assert_eq!(config.sources.rss.len(), 2);
-        assert_eq!(config.sources.rss[0].name, "Test Feed");
-        assert_eq!(config.sources.rss[1].name, "Another Feed");
+    #[test]
+    fn test_env_overrides_every_section() {
+        let vars = [
+            (
+                "CLIO_SOURCES_TWITCH",
+                r#"[{"name": "Streams", "channels": ["rustlang"]}]"#,
+            ),
+            (
+                "CLIO_SOURCES_STATUSPAGE",
+                r#"[{"name": "GitHub", "url": "https://www.githubstatus.com"}]"#,
+            ),
+            ("CLIO_ALERTS", r#"[{"keywords": ["outage"]}]"#),
+            ("CLIO_AUTHORS", r#"[{"name": "Julia Evans", "star": true}]"#),
+            ("CLIO_MUTES", r#"[{"keyword": "crypto"}]"#),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+        let config = Config::default().with_env_overrides(vars).unwrap();
+        assert_eq!(config.sources.twitch[0].channels, vec!["rustlang"]);
+        assert_eq!(config.sources.statuspage[0].name, "GitHub");
+        assert_eq!(config.alerts[0].keywords, vec!["outage"]);
+        assert_eq!(config.authors[0].name, "Julia Evans");
+        assert_eq!(config.mutes[0].keyword, "crypto");
+        // The RSS sources are left alone
+        assert!(config.sources.rss.is_empty());
+
+        for (name, value, reason) in [
+            (
+                "CLIO_SOURCES_TWITCH",
+                "rustlang",
+                "Invalid CLIO_SOURCES_TWITCH",
+            ),
+            ("CLIO_SOURCES_PODCASTS", "[]", "unknown field `podcasts`"),
+            (
+                "CLIO_MUTES",
+                r#"[{"word": "crypto"}]"#,
+                "Invalid CLIO_MUTES",
+            ),
+        ] {
+            let err = Config::default()
+                .with_env_overrides([(name.to_string(), value.to_string())])
+                .unwrap_err();
+            assert!(err.to_string().contains(reason), "{name}: {err}");
+        }
     }
 
     #[test]
@@ -410,7 +2808,7 @@ url = "https://example.com/feed.xml"
     fn test_parse_missing_required_fields() {
         let toml_content = r#"
 [[sources.rss]]
-name = "Test Feed"
+url = "https://example.com/feed.xml"
 "#;
 
         let result: Result<Config, _> = toml::from_str(toml_content);
@@ -468,7 +2866,10 @@ name = "Test Feed"
         let result = SupabaseConfig::from_env();
         assert!(result.is_err());
         let err = result.unwrap_err();
-        assert!(err.to_string().contains("Configuration error: SUPABASE_URL must use HTTPS protocol"));
+        assert!(
+            err.to_string()
+                .contains("Configuration error: SUPABASE_URL must use HTTPS protocol")
+        );
 
         // Cleanup
         unsafe {
@@ -590,6 +2991,58 @@ name = "Test Feed"
         }
     }
 
+    #[test]
+    #[serial]
+    fn test_supabase_config_rotated_from_env() {
+        unsafe {
+            env::set_var("SUPABASE_URL", "https://test.supabase.co");
+            env::set_var("SUPABASE_SECRET_KEY", "sb_secret_old123");
+        }
+
+        let config = SupabaseConfig::rotated_from_env("sb_secret_new456".to_string()).unwrap();
+        assert_eq!(config.url(), "https://test.supabase.co");
+        assert_eq!(config.secret_key(), "sb_secret_new456");
+
+        let result = SupabaseConfig::rotated_from_env("not_a_secret_key".to_string());
+        assert!(result.unwrap_err().to_string().contains("sb_secret_"));
+
+        // Cleanup
+        unsafe {
+            env::remove_var("SUPABASE_URL");
+            env::remove_var("SUPABASE_SECRET_KEY");
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_supabase_previous_secret_key_from_env() {
+        unsafe {
+            env::remove_var("SUPABASE_PREVIOUS_SECRET_KEY");
+        }
+        assert_eq!(
+            SupabaseConfig::previous_secret_key_from_env().unwrap(),
+            None
+        );
+
+        unsafe {
+            env::set_var("SUPABASE_PREVIOUS_SECRET_KEY", "sb_secret_old123");
+        }
+        assert_eq!(
+            SupabaseConfig::previous_secret_key_from_env().unwrap(),
+            Some("sb_secret_old123".to_string())
+        );
+
+        unsafe {
+            env::set_var("SUPABASE_PREVIOUS_SECRET_KEY", "invalid");
+        }
+        assert!(SupabaseConfig::previous_secret_key_from_env().is_err());
+
+        // Cleanup
+        unsafe {
+            env::remove_var("SUPABASE_PREVIOUS_SECRET_KEY");
+        }
+    }
+
     #[test]
     #[serial]
     fn test_supabase_config_secret_key_not_logged() {
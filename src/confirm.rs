@@ -0,0 +1,76 @@
+use crate::error::ClioError;
+use dialoguer::{Confirm, FuzzySelect, Select};
+use std::io::{self, IsTerminal};
+
+/// Ask the user to confirm a destructive operation
+///
+/// `assume_yes` (the `--yes` flag) skips the question. When stdin or stdout
+/// is not a terminal there is nobody to ask, so the operation is refused
+/// rather than carried out unconfirmed.
+pub fn confirm(prompt: &str, assume_yes: bool) -> Result<bool, ClioError> {
+    if assume_yes {
+        return Ok(true);
+    }
+
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Err(ClioError::Config(format!(
+            "{prompt} Not running in a terminal; pass --yes to confirm"
+        )));
+    }
+
+    Confirm::new()
+        .with_prompt(prompt)
+        .default(false)
+        .interact()
+        .map_err(|e| ClioError::Config(format!("Failed to read confirmation: {e}")))
+}
+
+/// Ask the user to pick one of `choices`, returning its index
+///
+/// When stdin or stdout is not a terminal there is nobody to ask, so this
+/// fails, pointing to `flag` for making the choice up front.
+pub fn choose(prompt: &str, choices: &[&str], flag: &str) -> Result<usize, ClioError> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Err(ClioError::Config(format!(
+            "{prompt} Not running in a terminal; pass {flag} to choose"
+        )));
+    }
+
+    Select::new()
+        .with_prompt(prompt)
+        .items(choices)
+        .default(0)
+        .interact()
+        .map_err(|e| ClioError::Config(format!("Failed to read choice: {e}")))
+}
+
+/// Ask the user to pick one of `choices` by typing part of it, returning
+/// its index, or `None` when they back out with Esc
+///
+/// When stdin or stdout is not a terminal there is nobody to ask, so this
+/// fails, pointing to `flag` for making the choice up front.
+pub fn pick(prompt: &str, choices: &[String], flag: &str) -> Result<Option<usize>, ClioError> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Err(ClioError::Config(format!(
+            "{prompt} Not running in a terminal; pass {flag} to choose"
+        )));
+    }
+
+    FuzzySelect::new()
+        .with_prompt(prompt)
+        .items(choices)
+        .default(0)
+        .max_length(15)
+        .interact_opt()
+        .map_err(|e| ClioError::Config(format!("Failed to read choice: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confirm_assume_yes() {
+        assert!(confirm("Delete everything?", true).unwrap());
+    }
+}
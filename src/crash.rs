@@ -0,0 +1,185 @@
+use crate::audit::AuditLog;
+use crate::config::Config;
+use crate::error::{ClioError, ErrorContext};
+use crate::redact::redact;
+use crate::writes;
+use chrono::Utc;
+use std::backtrace::Backtrace;
+use std::fmt::Write as _;
+use std::fs::{self, DirBuilder, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+use std::panic::{self, PanicHookInfo};
+use std::path::{Path, PathBuf};
+
+/// Number of audit log entries included in a crash report
+const RECENT_CHANGES: usize = 20;
+
+/// Replace the default panic output with a crash report
+///
+/// On a panic, a redacted diagnostic report is written to `~/.clio/crash/`
+/// and a short message pointing to it is printed instead of a backtrace. If
+/// the report cannot be written, or filesystem writes are disabled, the
+/// default panic output is shown instead.
+pub fn install() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let written = Config::config_dir()
+            .and_then(|dir| write_report(&dir.join("crash"), &report(&message(info))));
+        match written {
+            Ok(path) => eprintln!(
+                "clio crashed unexpectedly. A diagnostic report was written to {}\n\
+                 Please attach it when reporting this bug; secrets are removed, \
+                 but feel free to review it first.",
+                path.display()
+            ),
+            Err(_) => default_hook(info),
+        }
+    }));
+}
+
+/// The panic message and where it happened
+fn message(info: &PanicHookInfo) -> String {
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "(no message)".to_string());
+    let location = info
+        .location()
+        .map(|location| location.to_string())
+        .unwrap_or_else(|| "(unknown)".to_string());
+    format!("{message}\nat {location}")
+}
+
+/// Diagnostic report for a panic, with secrets removed
+fn report(panic: &str) -> String {
+    let mut report = String::new();
+    let _ = writeln!(report, "clio {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(
+        report,
+        "OS: {} {}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    let _ = writeln!(report, "Time: {}", Utc::now().to_rfc3339());
+    let _ = writeln!(report, "\n## Panic\n{panic}");
+    let _ = writeln!(report, "\n## Backtrace\n{}", Backtrace::force_capture());
+    let _ = writeln!(report, "## Configuration\n{}", config_shape());
+    let _ = writeln!(report, "## Recent changes\n{}", recent_changes());
+    redact(&report)
+}
+
+/// Save a report in `dir`, returning its path
+fn write_report(dir: &Path, report: &str) -> Result<PathBuf, ClioError> {
+    writes::ensure_allowed("write a crash report")?;
+    DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)
+        .clio_config_err(format!("Failed to create {}", dir.display()))?;
+
+    let name = format!(
+        "crash-{}-{}.txt",
+        Utc::now().format("%Y%m%dT%H%M%SZ"),
+        std::process::id()
+    );
+    let path = dir.join(name);
+    OpenOptions::new()
+        .create_new(true)
+        .write(true)
+        .mode(0o600)
+        .open(&path)
+        .and_then(|mut file| file.write_all(report.as_bytes()))
+        .clio_config_err(format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Counts of configured sources and the settings in use, without names or URLs
+fn config_shape() -> String {
+    // The hook is installed before arguments are parsed, so only CLIO_CONFIG is seen
+    let config = Config::config_path(None)
+        .and_then(|path| fs::read_to_string(path).clio_config_err("Failed to read"))
+        .and_then(|contents| Ok(toml::from_str::<Config>(&contents)?));
+    let config = match config {
+        Ok(config) => config,
+        Err(e) => return format!("unavailable ({e})\n"),
+    };
+
+    let paused = config.sources.rss.len() - config.sources.active().count();
+    let tagged = config
+        .sources
+        .rss
+        .iter()
+        .filter(|source| !source.tags.is_empty())
+        .count();
+    let settings = toml::to_string(&config.settings).unwrap_or_default();
+    format!(
+        "rss sources: {} ({paused} paused, {tagged} tagged)\nsynthetic sources: {}\n\
+         advisory sources: {}\nstatus page sources: {}\n\
+         changelog sources: {}\ntwitch sources: {}\nweather sources: {}\n{settings}",
+        config.sources.rss.len(),
+        config.sources.synthetic.len(),
+        config.sources.advisories.len(),
+        config.sources.statuspage.len(),
+        config.sources.changelog.len(),
+        config.sources.twitch.len(),
+        config.sources.weather.len()
+    )
+}
+
+/// The last audit log entries, without the links of affected items
+fn recent_changes() -> String {
+    let entries = match AuditLog::open().and_then(|log| log.tail(RECENT_CHANGES)) {
+        Ok(entries) => entries,
+        Err(e) => return format!("unavailable ({e})\n"),
+    };
+    if entries.is_empty() {
+        return "none\n".to_string();
+    }
+
+    entries
+        .iter()
+        .map(|entry| {
+            format!(
+                "{} {}: {} ({})\n",
+                entry.at.to_rfc3339(),
+                entry.command,
+                entry.action,
+                entry.rows
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_report_is_redacted() {
+        let report = report("request failed with key sb_secret_abc123\nat src/main.rs:1:1");
+        assert!(report.starts_with(&format!("clio {}", env!("CARGO_PKG_VERSION"))));
+        assert!(report.contains("## Panic\nrequest failed with key"));
+        assert!(report.contains("## Configuration"));
+        assert!(!report.contains("sb_secret_abc123"));
+    }
+
+    #[test]
+    fn test_write_report() {
+        let dir = TempDir::new().unwrap();
+        let crash_dir = dir.path().join("crash");
+
+        let path = write_report(&crash_dir, "clio 0.1.0\n").unwrap();
+        assert!(path.starts_with(&crash_dir));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "clio 0.1.0\n");
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        let mode = fs::metadata(&crash_dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+    }
+}
@@ -0,0 +1,166 @@
+use crate::error::ClioError;
+use crate::sanitize::truncate;
+use crate::source::Item;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Longest summary shown under an item, in characters
+const SUMMARY_CHARS: usize = 280;
+
+/// Items added since the last digest, grouped by source
+#[derive(Debug, Clone, PartialEq)]
+pub struct Digest {
+    /// Start of the period the digest covers
+    since: DateTime<Utc>,
+    /// Sources in alphabetical order, each with its items in the order given
+    sections: Vec<(String, Vec<Item>)>,
+}
+
+impl Digest {
+    /// Group `items`, best passed newest first, by source
+    pub fn new(items: Vec<Item>, since: DateTime<Utc>) -> Self {
+        let mut sections: BTreeMap<String, Vec<Item>> = BTreeMap::new();
+        for item in items {
+            sections
+                .entry(item.source_name.clone())
+                .or_default()
+                .push(item);
+        }
+        Self {
+            since,
+            sections: sections.into_iter().collect(),
+        }
+    }
+
+    pub fn item_count(&self) -> usize {
+        self.sections.iter().map(|(_, items)| items.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    /// One line saying what the digest holds, used as the email subject
+    pub fn subject(&self) -> String {
+        let items = match self.item_count() {
+            1 => "1 new item".to_string(),
+            n => format!("{n} new items"),
+        };
+        match self.sections.len() {
+            1 => format!("clio digest: {items} from {}", self.sections[0].0),
+            n => format!("clio digest: {items} from {n} sources"),
+        }
+    }
+
+    /// The digest as Markdown, which also reads well as plain text
+    pub fn render(&self) -> String {
+        let mut text = format!(
+            "# {} since {}\n",
+            self.subject().trim_start_matches("clio digest: "),
+            self.since.format("%Y-%m-%d %H:%M UTC")
+        );
+        for (source, items) in &self.sections {
+            text.push_str(&format!("\n## {source} ({})\n\n", items.len()));
+            for item in items {
+                text.push_str(&format!("- {}\n  {}\n", item.title, item.link));
+                if let Some(summary) = item.summary.as_deref().filter(|s| !s.is_empty()) {
+                    text.push_str(&format!("  {}\n", truncate(summary, SUMMARY_CHARS)));
+                }
+            }
+        }
+        text
+    }
+}
+
+/// Mail `digest` to `to` through the local `sendmail`
+pub fn send_email(digest: &Digest, to: &str) -> Result<(), ClioError> {
+    if to.contains(['\r', '\n']) {
+        return Err(ClioError::Notification(format!(
+            "Invalid email address {to:?}"
+        )));
+    }
+    let message = format!(
+        "To: {to}\nSubject: {}\nContent-Type: text/plain; charset=utf-8\n\n{}",
+        digest.subject().replace(['\r', '\n'], " "),
+        digest.render()
+    );
+
+    let mut child = Command::new("sendmail")
+        .args(["-t", "-i"])
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            ClioError::Notification(format!("Could not run sendmail: {e}. Is it installed?"))
+        })?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(message.as_bytes())
+            .map_err(|e| ClioError::Notification(format!("Failed to write to sendmail: {e}")))?;
+    }
+    let status = child
+        .wait()
+        .map_err(|e| ClioError::Notification(format!("sendmail did not finish: {e}")))?;
+    if !status.success() {
+        return Err(ClioError::Notification(format!(
+            "sendmail failed ({status})"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn item(source: &str, title: &str, summary: Option<&str>) -> Item {
+        Item {
+            source_name: source.to_string(),
+            title: title.to_string(),
+            link: format!("https://example.com/{}", title.replace(' ', "-")),
+            summary: summary.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_digest_groups_by_source() {
+        let since = Utc.with_ymd_and_hms(2024, 5, 1, 8, 0, 0).unwrap();
+        let digest = Digest::new(
+            vec![
+                item("Lobsters", "Newer post", Some("Short summary")),
+                item("Hacker News", "Launch", None),
+                item("Lobsters", "Older post", Some(&"x".repeat(400))),
+            ],
+            since,
+        );
+
+        assert_eq!(digest.item_count(), 3);
+        assert_eq!(digest.subject(), "clio digest: 3 new items from 2 sources");
+        let text = digest.render();
+        assert!(text.starts_with("# 3 new items from 2 sources since 2024-05-01 08:00 UTC\n"));
+        assert!(text.contains(
+            "## Lobsters (2)\n\n\
+             - Newer post\n  https://example.com/Newer-post\n  Short summary\n\
+             - Older post\n"
+        ));
+        assert!(text.find("## Hacker News").unwrap() < text.find("## Lobsters").unwrap());
+        assert!(text.contains(&format!("  {}…\n", "x".repeat(SUMMARY_CHARS - 1))));
+    }
+
+    #[test]
+    fn test_digest_subject_for_one_source() {
+        let digest = Digest::new(vec![item("Lobsters", "Post", None)], Utc::now());
+        assert_eq!(digest.subject(), "clio digest: 1 new item from Lobsters");
+        assert!(Digest::new(Vec::new(), Utc::now()).is_empty());
+    }
+
+    #[test]
+    fn test_email_rejects_header_injection() {
+        let digest = Digest::new(vec![item("Lobsters", "Post", None)], Utc::now());
+        let err = send_email(&digest, "me@example.com\nBcc: x@example.com").unwrap_err();
+        assert!(err.to_string().contains("Invalid email address"));
+    }
+}
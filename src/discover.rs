@@ -0,0 +1,321 @@
+use crate::error::{ClioError, ErrorContext};
+use crate::http::FeedClient;
+use crate::source::{CLOCK_SKEW_TOLERANCE, Item};
+use atom_syndication::Feed;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use rss::Channel;
+use std::collections::HashSet;
+use std::sync::LazyLock;
+use url::Url;
+
+/// Media types of the feeds a page can advertise that clio can read
+const FEED_TYPES: [&str; 2] = ["application/rss+xml", "application/atom+xml"];
+
+static LINK_TAG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<link\b[^>]*>").expect("valid link tag pattern"));
+
+static ATTRIBUTE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?s)([\w-]+)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#)
+        .expect("valid attribute pattern")
+});
+
+/// A feed found by [`discover`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredFeed {
+    pub url: String,
+    /// The feed's title, or the one the page gives it
+    pub title: Option<String>,
+}
+
+impl DiscoveredFeed {
+    /// Suggested source name: the feed's title, or its host
+    pub fn default_name(&self) -> String {
+        self.title.clone().unwrap_or_else(|| {
+            Url::parse(&self.url)
+                .ok()
+                .and_then(|url| url.host_str().map(str::to_string))
+                .unwrap_or_else(|| self.url.clone())
+        })
+    }
+}
+
+/// Find the feeds behind a site or feed URL
+///
+/// A URL that is itself an RSS or Atom feed is returned as is. Otherwise the
+/// page is read as HTML, and the feeds it advertises with
+/// `<link rel="alternate">` are returned in page order.
+pub async fn discover(client: &FeedClient, url: &str) -> Result<Vec<DiscoveredFeed>, ClioError> {
+    let page_url = Url::parse(url).clio_network_err(format!("Invalid URL {url}"))?;
+    if let Some(guard) = client.guard() {
+        guard.check_url(&page_url)?;
+    }
+
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .clio_network_err(format!("Failed to load {url}"))?;
+    if !response.status().is_success() {
+        return Err(ClioError::Network(format!(
+            "HTTP {} from {url}",
+            response.status()
+        )));
+    }
+    // Redirects decide where relative links point
+    let page_url = response.url().clone();
+    let body = response
+        .bytes()
+        .await
+        .clio_network_err("Failed to read response body")?;
+
+    if let Some(title) = feed_title(&body) {
+        return Ok(vec![DiscoveredFeed {
+            url: page_url.to_string(),
+            title,
+        }]);
+    }
+    Ok(advertised_feeds(&String::from_utf8_lossy(&body), &page_url))
+}
+
+/// The title of `body` when it is an RSS or Atom feed
+fn feed_title(body: &[u8]) -> Option<Option<String>> {
+    if let Ok(channel) = Channel::read_from(body) {
+        return Some(non_empty(channel.title()));
+    }
+    Feed::read_from(body)
+        .ok()
+        .map(|feed| non_empty(feed.title().as_str()))
+}
+
+/// Feeds linked from an HTML page, resolved against `base`
+fn advertised_feeds(html: &str, base: &Url) -> Vec<DiscoveredFeed> {
+    let mut feeds: Vec<DiscoveredFeed> = Vec::new();
+    for tag in LINK_TAG.find_iter(html) {
+        let attribute = |name: &str| {
+            ATTRIBUTE
+                .captures_iter(tag.as_str())
+                .find(|captures| captures[1].eq_ignore_ascii_case(name))
+                .and_then(|captures| captures.iter().skip(2).flatten().next())
+                .map(|value| {
+                    html_escape::decode_html_entities(value.as_str())
+                        .trim()
+                        .to_string()
+                })
+        };
+
+        let is_alternate = attribute("rel").is_some_and(|rel| {
+            rel.split_ascii_whitespace()
+                .any(|rel| rel.eq_ignore_ascii_case("alternate"))
+        });
+        let is_feed = attribute("type").is_some_and(|kind| {
+            FEED_TYPES
+                .iter()
+                .any(|feed_type| kind.eq_ignore_ascii_case(feed_type))
+        });
+        let Some(url) = attribute("href").and_then(|href| base.join(&href).ok()) else {
+            continue;
+        };
+        if !is_alternate || !is_feed || feeds.iter().any(|feed| feed.url == url.as_str()) {
+            continue;
+        }
+
+        feeds.push(DiscoveredFeed {
+            url: url.to_string(),
+            title: attribute("title").as_deref().and_then(non_empty),
+        });
+    }
+    feeds
+}
+
+/// Problems worth knowing about before subscribing to the feed at `url`,
+/// judged from one fetch of it
+///
+/// `final_url` is where the fetch ended up after redirects and `skipped` the
+/// number of entries left out for lacking a title or link.
+pub fn feed_warnings(
+    url: &str,
+    final_url: Option<&str>,
+    skipped: usize,
+    items: &[Item],
+    now: DateTime<Utc>,
+) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if url.starts_with("http://") {
+        warnings.push("The feed is served over plain http".to_string());
+    }
+    if let Some(final_url) = final_url.filter(|final_url| *final_url != url) {
+        warnings.push(format!(
+            "The feed redirects to {final_url}; subscribe to that address instead"
+        ));
+    }
+    if skipped > 0 {
+        warnings.push(format!(
+            "{skipped} entries were left out for lacking a title or link"
+        ));
+    }
+    if items.is_empty() {
+        warnings.push("The feed has no items yet".to_string());
+        return warnings;
+    }
+
+    let undated = items.iter().filter(|item| item.pub_date.is_none()).count();
+    if undated > 0 {
+        warnings.push(format!(
+            "{undated} of {} items have no date and will sort by when they were fetched",
+            items.len()
+        ));
+    }
+    let future = items
+        .iter()
+        .filter(|item| {
+            item.pub_date
+                .is_some_and(|date| date - now > CLOCK_SKEW_TOLERANCE)
+        })
+        .count();
+    if future > 0 {
+        warnings.push(format!("{future} items are dated in the future"));
+    }
+    let mut links = HashSet::new();
+    let repeated = items
+        .iter()
+        .filter(|item| !links.insert(item.link.as_str()))
+        .count();
+    if repeated > 0 {
+        warnings.push(format!("{repeated} items repeat the link of another"));
+    }
+    let unlinked = items
+        .iter()
+        .filter(|item| !item.link.starts_with("http://") && !item.link.starts_with("https://"))
+        .count();
+    if unlinked > 0 {
+        warnings.push(format!(
+            "{unlinked} items link to something other than a web page"
+        ));
+    }
+    warnings
+}
+
+fn non_empty(text: &str) -> Option<String> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_advertised_feeds() {
+        let html = r#"<html><head>
+            <link rel="stylesheet" href="/style.css">
+            <link rel="alternate" type="application/rss+xml" title="Posts &amp; notes" href="/feed.xml">
+            <LINK REL='alternate' TYPE='application/atom+xml' HREF='https://cdn.example.com/atom'>
+            <link rel="alternate" type="application/rss+xml" href="/feed.xml">
+            <link rel="alternate" type="text/html" hreflang="fr" href="/fr/">
+        </head></html>"#;
+        let base = Url::parse("https://example.com/blog/").unwrap();
+
+        assert_eq!(
+            advertised_feeds(html, &base),
+            vec![
+                DiscoveredFeed {
+                    url: "https://example.com/feed.xml".to_string(),
+                    title: Some("Posts & notes".to_string()),
+                },
+                DiscoveredFeed {
+                    url: "https://cdn.example.com/atom".to_string(),
+                    title: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_feed_warnings() {
+        let now = Utc::now();
+        let item = |link: &str, days_ahead: Option<i64>| Item {
+            title: "Post".to_string(),
+            link: link.to_string(),
+            pub_date: days_ahead.map(|days| now + chrono::Duration::days(days)),
+            ..Default::default()
+        };
+
+        let items = vec![
+            item("https://example.com/a", Some(-1)),
+            item("https://example.com/b", Some(-2)),
+        ];
+        assert!(feed_warnings("https://example.com/feed", None, 0, &items, now).is_empty());
+
+        let items = vec![
+            item("https://example.com/a", Some(-1)),
+            item("https://example.com/a", None),
+            item("mailto:editor@example.com", Some(30)),
+        ];
+        let warnings = feed_warnings(
+            "http://example.com/feed",
+            Some("https://example.com/feed"),
+            2,
+            &items,
+            now,
+        );
+        assert_eq!(
+            warnings,
+            [
+                "The feed is served over plain http",
+                "The feed redirects to https://example.com/feed; subscribe to that address instead",
+                "2 entries were left out for lacking a title or link",
+                "1 of 3 items have no date and will sort by when they were fetched",
+                "1 items are dated in the future",
+                "1 items repeat the link of another",
+                "1 items link to something other than a web page",
+            ]
+        );
+
+        assert_eq!(
+            feed_warnings("https://example.com/feed", None, 0, &[], now),
+            ["The feed has no items yet"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_discover_feed_url() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/feed.xml")
+            .with_body(
+                r#"<?xml version="1.0"?><rss version="2.0"><channel>
+                <title>Example Blog</title><link>https://example.com</link>
+                <description>Posts</description></channel></rss>"#,
+            )
+            .create_async()
+            .await;
+
+        let url = format!("{}/feed.xml", server.url());
+        let feeds = discover(&FeedClient::default(), &url).await.unwrap();
+        assert_eq!(
+            feeds,
+            vec![DiscoveredFeed {
+                url,
+                title: Some("Example Blog".to_string()),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_discover_from_page() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/")
+            .with_header("content-type", "text/html")
+            .with_body(r#"<link rel="alternate" type="application/atom+xml" href="atom.xml">"#)
+            .create_async()
+            .await;
+
+        let feeds = discover(&FeedClient::default(), &format!("{}/", server.url()))
+            .await
+            .unwrap();
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].url, format!("{}/atom.xml", server.url()));
+    }
+}
@@ -0,0 +1,784 @@
+use crate::Item;
+use crate::source::{Enclosure, Severity, WeatherAlert};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Separator between the fields of `--plain` output lines
+pub const PLAIN_SEPARATOR: &str = " | ";
+
+/// One row of list output: an item plus any duplicates folded into it
+#[derive(Debug, Clone)]
+pub struct ListRow {
+    pub item: Item,
+    /// Number of items represented by this row (1 when nothing was folded)
+    pub count: usize,
+}
+
+impl ListRow {
+    /// Title annotated with a weather warning's severity and the number of
+    /// folded duplicates, if any
+    pub fn title(&self) -> String {
+        let title = match &self.item.alert {
+            Some(alert) => format!("[{}] {}", alert_label(alert), self.item.title),
+            None => self.item.title.clone(),
+        };
+        match self.count {
+            1 => title,
+            n => format!("{title} (×{n})"),
+        }
+    }
+
+    /// Length and size of the item's media file, such as "42 min, 38 MB",
+    /// when the feed gave either
+    pub fn media(&self) -> Option<String> {
+        self.item.enclosure.as_ref().and_then(media_info)
+    }
+
+    /// The row as labeled fields on one line, for screen readers
+    ///
+    /// For example "UNREAD | Hacker News | Title | 3 hours ago".
+    pub fn plain_line(&self, now: DateTime<Utc>) -> String {
+        let mut state = if self.item.is_read { "READ" } else { "UNREAD" }.to_string();
+        if self.item.is_starred {
+            state.push_str(", STARRED");
+        }
+        if let Some(alert) = &self.item.alert {
+            state.push_str(&format!(", {}", alert_label(alert)));
+        }
+        let title = match self.count {
+            1 => self.item.title.clone(),
+            n => format!("{} ({n} copies)", self.item.title),
+        };
+        let age = relative_time(&self.item, now).unwrap_or_else(|| "undated".to_string());
+
+        let mut fields = vec![state, self.item.source_name.clone(), title, age];
+        fields.extend(self.media());
+        fields.join(PLAIN_SEPARATOR)
+    }
+}
+
+/// Fold items whose normalized titles match and whose dates fall within `window`
+///
+/// This is a cheap title-only pass for syndicated wire stories that several
+/// sources publish under different URLs. The first item seen for a title is
+/// kept, so pass items newest first to show the latest copy. Undated items
+/// only fold into other undated items.
+pub fn collapse_duplicates(items: Vec<Item>, window: Duration) -> Vec<ListRow> {
+    let mut rows: Vec<ListRow> = Vec::with_capacity(items.len());
+    let mut by_title: HashMap<String, Vec<usize>> = HashMap::new();
+
+    for item in items {
+        let key = normalize_title(&item.title);
+        let candidates = by_title.entry(key).or_default();
+
+        let existing =
+            candidates
+                .iter()
+                .copied()
+                .find(|&i| match (rows[i].item.pub_date, item.pub_date) {
+                    (Some(kept), Some(date)) => (kept - date).abs() <= window,
+                    (None, None) => true,
+                    _ => false,
+                });
+
+        match existing {
+            Some(i) => rows[i].count += 1,
+            None => {
+                candidates.push(rows.len());
+                rows.push(ListRow { item, count: 1 });
+            }
+        }
+    }
+
+    rows
+}
+
+/// A story and its reach across sources, for the trending view
+#[derive(Debug, Clone)]
+pub struct TrendingRow {
+    /// The copy with the most engagement
+    pub item: Item,
+    /// Distinct sources that carried the story
+    pub sources: Vec<String>,
+    /// Points summed over every copy
+    pub score: u32,
+    /// Comments summed over every copy
+    pub comments: u32,
+}
+
+impl TrendingRow {
+    /// Ranking weight: engagement, multiplied by the number of sources carrying the story
+    pub fn heat(&self) -> u64 {
+        (u64::from(self.score) + u64::from(self.comments) + 1) * self.sources.len() as u64
+    }
+}
+
+/// Rank items published within `window` before `now` by engagement and reach
+///
+/// Copies of a story are matched by link or normalized title, so an article
+/// submitted to several aggregators counts once with their scores combined.
+pub fn trending(items: Vec<Item>, now: DateTime<Utc>, window: Duration) -> Vec<TrendingRow> {
+    let mut rows: Vec<TrendingRow> = Vec::new();
+    let mut by_title: HashMap<String, usize> = HashMap::new();
+    let mut by_link: HashMap<String, usize> = HashMap::new();
+
+    let recent = items.into_iter().filter(|item| {
+        item.effective_date(now)
+            .is_some_and(|date| date <= now && now - date <= window)
+    });
+
+    for item in recent {
+        let title = normalize_title(&item.title);
+        let existing = by_link
+            .get(&item.link)
+            .or_else(|| by_title.get(&title))
+            .copied();
+
+        let index = match existing {
+            Some(i) => {
+                let row = &mut rows[i];
+                if !row.sources.contains(&item.source_name) {
+                    row.sources.push(item.source_name.clone());
+                }
+                row.score += item.score.unwrap_or(0);
+                row.comments += item.comments.unwrap_or(0);
+                let engagement = |item: &Item| item.score.unwrap_or(0) + item.comments.unwrap_or(0);
+                if engagement(&item) > engagement(&row.item) {
+                    row.item = item.clone();
+                }
+                i
+            }
+            None => {
+                rows.push(TrendingRow {
+                    sources: vec![item.source_name.clone()],
+                    score: item.score.unwrap_or(0),
+                    comments: item.comments.unwrap_or(0),
+                    item: item.clone(),
+                });
+                rows.len() - 1
+            }
+        };
+        by_link.entry(item.link).or_insert(index);
+        by_title.entry(title).or_insert(index);
+    }
+
+    rows.sort_by_key(|row| std::cmp::Reverse(row.heat()));
+    rows
+}
+
+/// Sort items newest first by their effective date, undated items last
+///
+/// Near-future dates from servers with fast clocks sort as if published
+/// `now` rather than above everything else.
+pub fn sort_newest_first(items: &mut [Item], now: DateTime<Utc>) {
+    items.sort_by_key(|item| std::cmp::Reverse(item.effective_date(now)));
+}
+
+/// Short label for a weather warning, such as "SEVERE ALERT"
+pub fn alert_label(alert: &WeatherAlert) -> String {
+    match alert.severity {
+        Severity::Unknown => "ALERT".to_string(),
+        severity => format!("{} ALERT", severity.as_str().to_uppercase()),
+    }
+}
+
+/// Describe an item's date relative to `now`, such as "5 minutes ago"
+pub fn relative_time(item: &Item, now: DateTime<Utc>) -> Option<String> {
+    item.effective_date(now)
+        .map(|date| relative_date(date, now))
+}
+
+/// Describe a date relative to `now`, such as "5 minutes ago" or "in 2 days"
+pub fn relative_date(date: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let (elapsed, future) = if date > now {
+        (date - now, true)
+    } else {
+        (now - date, false)
+    };
+
+    let amount = match (
+        elapsed.num_days(),
+        elapsed.num_hours(),
+        elapsed.num_minutes(),
+    ) {
+        (0, 0, 0) => return "just now".to_string(),
+        (0, 0, 1) => "1 minute".to_string(),
+        (0, 0, m) => format!("{m} minutes"),
+        (0, 1, _) => "1 hour".to_string(),
+        (0, h, _) => format!("{h} hours"),
+        (1, _, _) => "1 day".to_string(),
+        (d, _, _) => format!("{d} days"),
+    };
+
+    if future {
+        format!("in {amount}")
+    } else {
+        format!("{amount} ago")
+    }
+}
+
+/// Short description of an enclosure's length and size, such as "42 min, 38 MB"
+pub fn media_info(enclosure: &Enclosure) -> Option<String> {
+    let parts: Vec<String> = [
+        enclosure.duration.map(|d| format_duration(d.as_secs())),
+        enclosure.length.map(format_size),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    (!parts.is_empty()).then(|| parts.join(", "))
+}
+
+/// Human-readable byte count, such as "38 MB"
+pub fn format_size(bytes: u64) -> String {
+    const KB: f64 = 1024.0;
+    const MB: f64 = KB * 1024.0;
+    const GB: f64 = MB * 1024.0;
+
+    let bytes = bytes as f64;
+    if bytes >= GB {
+        format!("{:.1} GB", bytes / GB)
+    } else if bytes >= MB {
+        format!("{:.0} MB", bytes / MB)
+    } else {
+        format!("{:.0} KB", (bytes / KB).max(1.0))
+    }
+}
+
+/// Everything stored about an item, as labeled lines of plain text
+///
+/// The summary, and the abstract of an enriched paper, follow the labels,
+/// wrapped to `width` columns.
+pub fn item_details(item: &Item, now: DateTime<Utc>, width: usize) -> String {
+    let mut fields: Vec<(&str, String)> = vec![
+        ("Source", item.source_name.clone()),
+        ("Link", item.link.clone()),
+    ];
+    let published = match item.pub_date {
+        Some(date) => format!(
+            "{} ({})",
+            date.format("%Y-%m-%d %H:%M UTC"),
+            relative_time(item, now).unwrap_or_default()
+        ),
+        None => "undated".to_string(),
+    };
+    fields.push(("Published", published));
+    let mut state = if item.is_read { "read" } else { "unread" }.to_string();
+    if item.is_starred {
+        state.push_str(", starred");
+    }
+    fields.push(("State", state));
+    if let Some(alert) = &item.alert {
+        fields.push((
+            "Warning",
+            format!("{} ({})", alert.event, alert.severity.as_str()),
+        ));
+        if !alert.area.is_empty() {
+            fields.push(("Area", alert.area.clone()));
+        }
+        if let Some(onset) = alert.onset {
+            fields.push(("Onset", onset.format("%Y-%m-%d %H:%M UTC").to_string()));
+        }
+    }
+    if let Some(expires_at) = item.expires_at {
+        fields.push((
+            "Expires",
+            expires_at.format("%Y-%m-%d %H:%M UTC").to_string(),
+        ));
+    }
+    if !item.authors.is_empty() {
+        fields.push(("Authors", item.authors.join(", ")));
+    }
+    if !item.tags.is_empty() {
+        fields.push(("Tags", item.tags.join(", ")));
+    }
+    if let Some(paper) = &item.paper {
+        fields.extend(
+            [
+                ("Journal", &paper.journal),
+                ("DOI", &paper.doi),
+                ("arXiv", &paper.arxiv_id),
+            ]
+            .into_iter()
+            .filter_map(|(label, value)| Some((label, value.clone()?))),
+        );
+    }
+    if let Some(enclosure) = &item.enclosure {
+        let media = match media_info(enclosure) {
+            Some(info) => format!("{} ({info})", enclosure.url),
+            None => enclosure.url.clone(),
+        };
+        fields.push(("Media", media));
+    }
+    if let Some(score) = item.score {
+        fields.push(("Score", score.to_string()));
+    }
+    if let Some(comments) = item.comments {
+        fields.push(("Comments", comments.to_string()));
+    }
+    fields.push(("ID", item.id.clone()));
+
+    let label_width = fields
+        .iter()
+        .map(|(label, _)| label.len())
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let mut text = format!("{}\n\n", item.title);
+    for (label, value) in fields {
+        text.push_str(&format!("{:label_width$} {value}\n", format!("{label}:")));
+    }
+    let abstract_text = item.paper.as_ref().and_then(|p| p.abstract_text.as_ref());
+    for (heading, body) in [("", item.summary.as_ref()), ("Abstract", abstract_text)] {
+        let Some(body) = body.filter(|body| !body.trim().is_empty()) else {
+            continue;
+        };
+        text.push('\n');
+        if !heading.is_empty() {
+            text.push_str(&format!("{heading}:\n"));
+        }
+        for line in wrap(body, width) {
+            text.push_str(&line);
+            text.push('\n');
+        }
+    }
+    text
+}
+
+/// Horizontal bar for a terminal chart, `width` cells long at `max`
+///
+/// Uses eighth-block characters so small differences stay visible.
+pub fn bar(value: u64, max: u64, width: usize) -> String {
+    const EIGHTHS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+    if max == 0 {
+        return String::new();
+    }
+    let eighths = (value.min(max) as u128 * width as u128 * 8 / max as u128) as usize;
+    let mut bar = "█".repeat(eighths / 8);
+    if !eighths.is_multiple_of(8) {
+        bar.push(EIGHTHS[eighths % 8]);
+    }
+    bar
+}
+
+/// Cut text to fit within `width` terminal columns
+pub fn truncate_columns(text: &str, width: usize) -> String {
+    let mut used = 0;
+    text.chars()
+        .take_while(|c| {
+            used += c.width().unwrap_or(0);
+            used <= width
+        })
+        .collect()
+}
+
+/// Break text into lines of at most `width` columns, at spaces where possible
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.width() + 1 + word.width() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+        // Split words that are wider than a whole line
+        while line.width() > width && width > 0 {
+            let head = truncate_columns(&line, width);
+            if head.is_empty() {
+                break;
+            }
+            line = line[head.len()..].to_string();
+            lines.push(head);
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+fn format_duration(seconds: u64) -> String {
+    let minutes = (seconds + 30) / 60;
+    match (minutes / 60, minutes % 60) {
+        (0, 0) => format!("{seconds} s"),
+        (0, m) => format!("{m} min"),
+        (h, 0) => format!("{h} h"),
+        (h, m) => format!("{h} h {m} min"),
+    }
+}
+
+/// Normalize a title for duplicate detection
+///
+/// Case, punctuation, and whitespace differences are ignored.
+pub(crate) fn normalize_title(title: &str) -> String {
+    title
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_lowercase().next().unwrap_or(c)
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_bar() {
+        assert_eq!(bar(10, 10, 4), "████");
+        assert_eq!(bar(5, 10, 4), "██");
+        assert_eq!(bar(1, 16, 2), "▏");
+        assert_eq!(bar(0, 10, 4), "");
+        assert_eq!(bar(20, 10, 4), "████");
+        assert_eq!(bar(3, 0, 4), "");
+    }
+
+    fn item(title: &str, source: &str, hour: Option<u32>) -> Item {
+        Item {
+            id: format!("{source}-{title}"),
+            source_name: source.to_string(),
+            title: title.to_string(),
+            link: format!("https://{source}.example.com/{title}"),
+            summary: None,
+            pub_date: hour.map(|h| Utc.with_ymd_and_hms(2024, 5, 1, h, 0, 0).unwrap()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_normalize_title() {
+        assert_eq!(
+            normalize_title("  Markets RALLY: Stocks up!  "),
+            "markets rally stocks up"
+        );
+        assert_eq!(
+            normalize_title("Markets rally — stocks up"),
+            "markets rally stocks up"
+        );
+    }
+
+    #[test]
+    fn test_collapse_within_window() {
+        let rows = collapse_duplicates(
+            vec![
+                item("Quake hits region", "ap", Some(12)),
+                item("Other story", "ap", Some(11)),
+                item("Quake Hits Region!", "reuters", Some(10)),
+                item("quake hits region", "bbc", Some(9)),
+            ],
+            Duration::hours(6),
+        );
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].count, 3);
+        assert_eq!(rows[0].item.source_name, "ap");
+        assert_eq!(rows[0].title(), "Quake hits region (×3)");
+        assert_eq!(rows[1].title(), "Other story");
+    }
+
+    #[test]
+    fn test_collapse_respects_window() {
+        let rows = collapse_duplicates(
+            vec![
+                item("Weekly roundup", "a", Some(20)),
+                item("Weekly roundup", "b", Some(1)),
+            ],
+            Duration::hours(6),
+        );
+        assert_eq!(rows.len(), 2);
+        assert!(rows.iter().all(|row| row.count == 1));
+    }
+
+    #[test]
+    fn test_plain_line() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let mut row = ListRow {
+            item: Item {
+                source_name: "Hacker News".to_string(),
+                title: "Show HN: Clio".to_string(),
+                pub_date: Some(now - Duration::hours(3)),
+                ..Default::default()
+            },
+            count: 1,
+        };
+        assert_eq!(
+            row.plain_line(now),
+            "UNREAD | Hacker News | Show HN: Clio | 3 hours ago"
+        );
+
+        row.item.is_read = true;
+        row.item.is_starred = true;
+        row.item.pub_date = None;
+        row.count = 2;
+        assert_eq!(
+            row.plain_line(now),
+            "READ, STARRED | Hacker News | Show HN: Clio (2 copies) | undated"
+        );
+
+        row.item.enclosure = Some(Enclosure {
+            url: "https://example.com/episode.mp3".to_string(),
+            length: Some(39_845_888),
+            duration: Some(std::time::Duration::from_secs(42 * 60)),
+            ..Default::default()
+        });
+        assert!(row.plain_line(now).ends_with(" | undated | 42 min, 38 MB"));
+    }
+
+    #[test]
+    fn test_relative_time() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let at = |minutes: i64| Item {
+            pub_date: Some(now + Duration::minutes(minutes)),
+            ..Default::default()
+        };
+
+        assert_eq!(relative_time(&at(0), now).as_deref(), Some("just now"));
+        assert_eq!(relative_time(&at(-1), now).as_deref(), Some("1 minute ago"));
+        assert_eq!(
+            relative_time(&at(-45), now).as_deref(),
+            Some("45 minutes ago")
+        );
+        assert_eq!(
+            relative_time(&at(-150), now).as_deref(),
+            Some("2 hours ago")
+        );
+        assert_eq!(
+            relative_time(&at(-3 * 1440), now).as_deref(),
+            Some("3 days ago")
+        );
+        // Clock skew: a few minutes ahead is "just now", not "-3 minutes ago"
+        assert_eq!(relative_time(&at(3), now).as_deref(), Some("just now"));
+        assert_eq!(
+            relative_time(&at(2 * 1440), now).as_deref(),
+            Some("in 2 days")
+        );
+        assert_eq!(relative_time(&Item::default(), now), None);
+    }
+
+    #[test]
+    fn test_sort_newest_first_tolerates_skew() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let mut items = vec![
+            item("undated", "a", None),
+            item("older", "a", Some(11)),
+            Item {
+                title: "skewed".to_string(),
+                pub_date: Some(now + Duration::minutes(5)),
+                ..Default::default()
+            },
+            Item {
+                title: "scheduled".to_string(),
+                pub_date: Some(now + Duration::days(1)),
+                ..Default::default()
+            },
+            item("newest", "a", Some(12)),
+        ];
+
+        sort_newest_first(&mut items, now);
+        let titles: Vec<&str> = items.iter().map(|item| item.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            ["scheduled", "skewed", "newest", "older", "undated"]
+        );
+    }
+
+    #[test]
+    fn test_trending_ranks_by_engagement_and_reach() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let story = |title: &str, source: &str, link: &str, score: u32, comments: u32| Item {
+            title: title.to_string(),
+            source_name: source.to_string(),
+            link: link.to_string(),
+            pub_date: Some(now - Duration::hours(2)),
+            score: Some(score),
+            comments: Some(comments),
+            ..Default::default()
+        };
+
+        let items = vec![
+            story(
+                "Rust 2.0 announced",
+                "hn",
+                "https://a.example/rust",
+                300,
+                100,
+            ),
+            story(
+                "Rust 2.0 Announced!",
+                "reddit",
+                "https://reddit.example/r/1",
+                200,
+                50,
+            ),
+            story(
+                "Lobsters thread",
+                "lobsters",
+                "https://a.example/rust",
+                20,
+                5,
+            ),
+            story("Popular but alone", "hn", "https://b.example/x", 900, 400),
+            story("Quiet blog post", "blog", "https://c.example/y", 0, 0),
+            Item {
+                pub_date: Some(now - Duration::hours(30)),
+                ..story("Yesterday's news", "hn", "https://d.example/z", 5000, 10)
+            },
+        ];
+
+        let rows = trending(items, now, Duration::hours(24));
+        assert_eq!(rows.len(), 3);
+
+        assert_eq!(rows[0].item.title, "Rust 2.0 announced");
+        assert_eq!(rows[0].sources, ["hn", "reddit", "lobsters"]);
+        assert_eq!((rows[0].score, rows[0].comments), (520, 155));
+        assert_eq!(rows[0].heat(), 676 * 3);
+
+        assert_eq!(rows[1].item.title, "Popular but alone");
+        assert_eq!(rows[2].item.title, "Quiet blog post");
+        assert_eq!(rows[2].heat(), 1);
+    }
+
+    #[test]
+    fn test_media_info() {
+        let enclosure = Enclosure {
+            length: Some(39_845_888),
+            duration: Some(std::time::Duration::from_secs(42 * 60 + 10)),
+            ..Default::default()
+        };
+        assert_eq!(media_info(&enclosure).as_deref(), Some("42 min, 38 MB"));
+
+        let enclosure = Enclosure {
+            duration: Some(std::time::Duration::from_secs(3900)),
+            ..Default::default()
+        };
+        assert_eq!(media_info(&enclosure).as_deref(), Some("1 h 5 min"));
+
+        let enclosure = Enclosure {
+            length: Some(1_288_490_189),
+            ..Default::default()
+        };
+        assert_eq!(media_info(&enclosure).as_deref(), Some("1.2 GB"));
+
+        assert_eq!(media_info(&Enclosure::default()), None);
+    }
+
+    #[test]
+    fn test_collapse_undated_items() {
+        let rows = collapse_duplicates(
+            vec![
+                item("Same", "a", None),
+                item("Same", "b", None),
+                item("Same", "c", Some(5)),
+            ],
+            Duration::hours(24),
+        );
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].count, 2);
+        assert_eq!(rows[1].count, 1);
+    }
+
+    #[test]
+    fn test_truncate_to_columns() {
+        assert_eq!(truncate_columns("hello", 3), "hel");
+        assert_eq!(truncate_columns("日本語", 4), "日本");
+        assert_eq!(truncate_columns("short", 80), "short");
+    }
+
+    #[test]
+    fn test_wrap_to_width() {
+        assert_eq!(
+            wrap("the quick brown fox jumps", 10),
+            vec!["the quick", "brown fox", "jumps"]
+        );
+        assert_eq!(wrap("abcdefghij", 4), vec!["abcd", "efgh", "ij"]);
+        assert!(wrap("", 10).is_empty());
+    }
+
+    #[test]
+    fn test_item_details() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let item = Item {
+            id: "3f2a".to_string(),
+            source_name: "Julia Evans".to_string(),
+            title: "How DNS works".to_string(),
+            link: "https://jvns.ca/dns".to_string(),
+            summary: Some("A long summary that wraps across lines".to_string()),
+            tags: vec!["dns".to_string(), "networking".to_string()],
+            pub_date: Some(Utc.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap()),
+            is_starred: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            item_details(&item, now, 20),
+            "How DNS works\n\n\
+             Source:    Julia Evans\n\
+             Link:      https://jvns.ca/dns\n\
+             Published: 2024-05-01 09:00 UTC (3 hours ago)\n\
+             State:     unread, starred\n\
+             Tags:      dns, networking\n\
+             ID:        3f2a\n\
+             \n\
+             A long summary that\n\
+             wraps across lines\n"
+        );
+
+        let undated = Item {
+            is_read: true,
+            ..Default::default()
+        };
+        let details = item_details(&undated, now, 20);
+        assert!(details.contains("Published: undated\nState:     read\n"));
+        assert!(details.ends_with("ID:        \n"));
+    }
+
+    #[test]
+    fn test_weather_alert_rendering() {
+        let now = Utc.with_ymd_and_hms(2024, 1, 10, 12, 0, 0).unwrap();
+        let item = Item {
+            id: "9b1e".to_string(),
+            source_name: "California".to_string(),
+            title: "Blizzard Warning issued January 10".to_string(),
+            pub_date: Some(Utc.with_ymd_and_hms(2024, 1, 10, 11, 0, 0).unwrap()),
+            expires_at: Some(Utc.with_ymd_and_hms(2024, 1, 12, 12, 0, 0).unwrap()),
+            alert: Some(WeatherAlert {
+                event: "Blizzard Warning".to_string(),
+                severity: Severity::Extreme,
+                area: "Northern Sierra Nevada".to_string(),
+                onset: Some(Utc.with_ymd_and_hms(2024, 1, 11, 6, 0, 0).unwrap()),
+            }),
+            ..Default::default()
+        };
+
+        let row = ListRow { item, count: 1 };
+        assert_eq!(
+            row.title(),
+            "[EXTREME ALERT] Blizzard Warning issued January 10"
+        );
+        assert!(
+            row.plain_line(now)
+                .starts_with("UNREAD, EXTREME ALERT | California | Blizzard Warning")
+        );
+        let details = item_details(&row.item, now, 40);
+        assert!(details.contains(
+            "Warning:   Blizzard Warning (extreme)\n\
+             Area:      Northern Sierra Nevada\n\
+             Onset:     2024-01-11 06:00 UTC\n\
+             Expires:   2024-01-12 12:00 UTC\n"
+        ));
+    }
+}
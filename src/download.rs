@@ -0,0 +1,446 @@
+use crate::error::{ClioError, ErrorContext};
+use crate::http::RequestGuard;
+use crate::source::{Enclosure, Item};
+use crate::writes;
+use futures::stream::{self, StreamExt};
+use reqwest::{Client, StatusCode, header};
+use std::path::{Path, PathBuf};
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use url::Url;
+
+/// Default cap on the size of a single download (500 MB)
+pub const DEFAULT_MAX_BYTES: u64 = 500 * 1024 * 1024;
+
+/// Default number of downloads run at the same time
+pub const DEFAULT_JOBS: usize = 4;
+
+/// Maximum number of characters kept from an item title in a file name
+const MAX_FILE_STEM_LENGTH: usize = 100;
+
+/// Downloads item enclosures (podcast episodes and other media) to disk
+///
+/// Files are written to `<dir>/<source name>/<item title>.<ext>`. Partial
+/// downloads are kept as `.part` files and resumed with an HTTP range request
+/// on the next run; enclosures whose file already exists are skipped.
+#[derive(Debug, Clone)]
+pub struct Downloader {
+    client: Client,
+    dir: PathBuf,
+    guard: RequestGuard,
+    max_bytes: u64,
+    jobs: usize,
+}
+
+/// What happened to a single enclosure
+#[derive(Debug)]
+pub struct DownloadResult {
+    /// Link of the item the enclosure belongs to
+    pub item_link: String,
+    pub enclosure: Enclosure,
+    pub outcome: Result<DownloadOutcome, ClioError>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DownloadOutcome {
+    /// The file was downloaded (or a partial download completed)
+    Downloaded { path: PathBuf, bytes: u64 },
+    /// The file was already downloaded by an earlier run
+    AlreadyPresent { path: PathBuf },
+}
+
+impl DownloadOutcome {
+    /// Local path of the downloaded file
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Downloaded { path, .. } | Self::AlreadyPresent { path } => path,
+        }
+    }
+}
+
+impl Downloader {
+    /// Create a downloader writing into `dir`, with requests restricted by `guard`
+    pub fn new(dir: PathBuf, guard: RequestGuard) -> Self {
+        Self {
+            client: guard
+                .apply(Client::builder().user_agent("Clio/0.1.0"))
+                .build()
+                .unwrap_or_default(),
+            dir,
+            guard,
+            max_bytes: DEFAULT_MAX_BYTES,
+            jobs: DEFAULT_JOBS,
+        }
+    }
+
+    /// Refuse enclosures larger than `max_bytes`
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Run up to `jobs` downloads in parallel
+    pub fn with_jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs.max(1);
+        self
+    }
+
+    /// Download the enclosures of all items that have one
+    pub async fn download_all(&self, items: &[Item]) -> Vec<DownloadResult> {
+        stream::iter(
+            items
+                .iter()
+                .filter_map(|item| item.enclosure.as_ref().map(|enclosure| (item, enclosure))),
+        )
+        .map(|(item, enclosure)| async move {
+            DownloadResult {
+                item_link: item.link.clone(),
+                enclosure: enclosure.clone(),
+                outcome: self
+                    .download(enclosure, &self.local_path(item, enclosure))
+                    .await,
+            }
+        })
+        .buffer_unordered(self.jobs)
+        .collect()
+        .await
+    }
+
+    /// Path an item's enclosure is saved to
+    pub fn local_path(&self, item: &Item, enclosure: &Enclosure) -> PathBuf {
+        let stem = safe_file_name(&item.title);
+        let stem = stem.chars().take(MAX_FILE_STEM_LENGTH).collect::<String>();
+        let file_name = match extension(enclosure) {
+            Some(ext) => format!("{stem}.{ext}"),
+            None => stem,
+        };
+
+        self.dir
+            .join(safe_file_name(&item.source_name))
+            .join(file_name)
+    }
+
+    async fn download(
+        &self,
+        enclosure: &Enclosure,
+        path: &Path,
+    ) -> Result<DownloadOutcome, ClioError> {
+        let url = enclosure.url.as_str();
+        if fs::try_exists(path).await.unwrap_or(false) {
+            return Ok(DownloadOutcome::AlreadyPresent {
+                path: path.to_path_buf(),
+            });
+        }
+
+        // Skip oversized files without a request when the feed declares their size
+        if enclosure
+            .length
+            .is_some_and(|length| length > self.max_bytes)
+        {
+            return Err(self.too_large(url));
+        }
+
+        let parsed = Url::parse(url).clio_network_err(format!("Invalid enclosure URL {url}"))?;
+        self.guard.check_url(&parsed)?;
+
+        let part = part_path(path);
+        let mut offset = fs::metadata(&part).await.map(|m| m.len()).unwrap_or(0);
+
+        let mut request = self.client.get(url);
+        if offset > 0 {
+            request = request.header(header::RANGE, format!("bytes={offset}-"));
+        }
+        let mut response = request
+            .send()
+            .await
+            .clio_network_err(format!("Failed to download {url}"))?;
+
+        match response.status() {
+            StatusCode::PARTIAL_CONTENT => {}
+            // The partial file already holds the whole body
+            StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => {
+                return finish(&part, path, offset).await;
+            }
+            // Server ignored the range request; start over
+            status if status.is_success() => offset = 0,
+            status => {
+                return Err(ClioError::Network(format!("HTTP {status} from {url}")));
+            }
+        }
+
+        if let Some(length) = response.content_length()
+            && offset + length > self.max_bytes
+        {
+            return Err(self.too_large(url));
+        }
+
+        writes::ensure_allowed("download enclosures")?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .clio_config_err(format!("Failed to create {}", parent.display()))?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(offset > 0)
+            .truncate(offset == 0)
+            .open(&part)
+            .await
+            .clio_config_err(format!("Failed to open {}", part.display()))?;
+
+        let mut bytes = offset;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .clio_network_err(format!("Download of {url} interrupted"))?
+        {
+            bytes += chunk.len() as u64;
+            if bytes > self.max_bytes {
+                drop(file);
+                let _ = fs::remove_file(&part).await;
+                return Err(self.too_large(url));
+            }
+            file.write_all(&chunk)
+                .await
+                .clio_config_err(format!("Failed to write {}", part.display()))?;
+        }
+        file.flush()
+            .await
+            .clio_config_err(format!("Failed to write {}", part.display()))?;
+
+        finish(&part, path, bytes).await
+    }
+
+    fn too_large(&self, url: &str) -> ClioError {
+        ClioError::Network(format!(
+            "Enclosure {url} exceeds the {} MB size limit",
+            self.max_bytes / (1024 * 1024)
+        ))
+    }
+}
+
+async fn finish(part: &Path, path: &Path, bytes: u64) -> Result<DownloadOutcome, ClioError> {
+    fs::rename(part, path)
+        .await
+        .clio_config_err(format!("Failed to move download to {}", path.display()))?;
+    Ok(DownloadOutcome::Downloaded {
+        path: path.to_path_buf(),
+        bytes,
+    })
+}
+
+fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    path.with_file_name(name)
+}
+
+/// Replace anything that is not safe in a file name, including path separators
+fn safe_file_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let cleaned = cleaned.trim().trim_start_matches('.').trim();
+
+    if cleaned.is_empty() {
+        "untitled".to_string()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+/// File extension taken from the enclosure URL, falling back to its MIME type
+fn extension(enclosure: &Enclosure) -> Option<String> {
+    let from_url = Url::parse(&enclosure.url).ok().and_then(|url| {
+        let segment = url.path_segments()?.next_back()?.to_string();
+        let (_, ext) = segment.rsplit_once('.')?;
+        (!ext.is_empty() && ext.len() <= 5 && ext.chars().all(|c| c.is_ascii_alphanumeric()))
+            .then(|| ext.to_ascii_lowercase())
+    });
+
+    from_url.or_else(|| {
+        let subtype = enclosure.mime_type.as_deref()?.split('/').nth(1)?;
+        let ext = match subtype {
+            "mpeg" => "mp3",
+            "mp4" | "x-m4a" => "m4a",
+            "ogg" => "ogg",
+            "opus" => "opus",
+            "pdf" => "pdf",
+            _ => return None,
+        };
+        Some(ext.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn episode(title: &str, url: &str) -> Item {
+        Item {
+            source_name: "My Podcast".to_string(),
+            title: title.to_string(),
+            link: format!("https://example.com/{title}"),
+            enclosure: Some(Enclosure {
+                url: url.to_string(),
+                mime_type: Some("audio/mpeg".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn downloader(dir: &TempDir) -> Downloader {
+        Downloader::new(dir.path().to_path_buf(), RequestGuard::new(true))
+    }
+
+    #[test]
+    fn test_local_path() {
+        let dir = TempDir::new().unwrap();
+        let downloader = downloader(&dir);
+
+        let item = episode(
+            "Ep 1: ../../etc/passwd",
+            "https://cdn.example.com/a/ep1.MP3?x=1",
+        );
+        let path = downloader.local_path(&item, item.enclosure.as_ref().unwrap());
+        assert_eq!(
+            path,
+            dir.path()
+                .join("My Podcast")
+                .join("Ep 1_ .._.._etc_passwd.mp3")
+        );
+
+        let item = episode("Ep 2", "https://cdn.example.com/stream");
+        let path = downloader.local_path(&item, item.enclosure.as_ref().unwrap());
+        assert_eq!(path.file_name().unwrap(), "Ep 2.mp3");
+    }
+
+    #[test]
+    fn test_safe_file_name() {
+        assert_eq!(safe_file_name("a/b\\c"), "a_b_c");
+        assert_eq!(safe_file_name(".."), "untitled");
+        assert_eq!(safe_file_name("  "), "untitled");
+    }
+
+    #[tokio::test]
+    async fn test_download_all_and_skip_existing() {
+        let mut server = mockito::Server::new_async().await;
+        let body = vec![7u8; 1024];
+        let mock = server
+            .mock("GET", "/ep1.mp3")
+            .with_status(200)
+            .with_body(&body)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let dir = TempDir::new().unwrap();
+        let downloader = downloader(&dir);
+        let items = vec![
+            episode("Ep 1", &format!("{}/ep1.mp3", server.url())),
+            Item::default(),
+        ];
+
+        let results = downloader.download_all(&items).await;
+        assert_eq!(results.len(), 1);
+        let outcome = results[0].outcome.as_ref().unwrap();
+        assert!(matches!(
+            outcome,
+            DownloadOutcome::Downloaded { bytes: 1024, .. }
+        ));
+        assert_eq!(std::fs::read(outcome.path()).unwrap(), body);
+
+        let results = downloader.download_all(&items).await;
+        assert!(matches!(
+            results[0].outcome,
+            Ok(DownloadOutcome::AlreadyPresent { .. })
+        ));
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_download_resumes_partial_file() {
+        let mut server = mockito::Server::new_async().await;
+        let _m = server
+            .mock("GET", "/ep.mp3")
+            .match_header("range", "bytes=5-")
+            .with_status(206)
+            .with_body("world")
+            .create_async()
+            .await;
+
+        let dir = TempDir::new().unwrap();
+        let downloader = downloader(&dir);
+        let item = episode("Ep", &format!("{}/ep.mp3", server.url()));
+        let path = downloader.local_path(&item, item.enclosure.as_ref().unwrap());
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(part_path(&path), "hello").unwrap();
+
+        let results = downloader.download_all(&[item]).await;
+        let outcome = results[0].outcome.as_ref().unwrap();
+        assert!(matches!(
+            outcome,
+            DownloadOutcome::Downloaded { bytes: 10, .. }
+        ));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "helloworld");
+        assert!(!part_path(&path).exists());
+    }
+
+    #[tokio::test]
+    async fn test_download_enforces_size_cap() {
+        let mut server = mockito::Server::new_async().await;
+        let _m = server
+            .mock("GET", "/big.mp3")
+            .with_status(200)
+            .with_body(vec![0u8; 2048])
+            .create_async()
+            .await;
+
+        let dir = TempDir::new().unwrap();
+        let downloader = downloader(&dir).with_max_bytes(1024);
+        let item = episode("Big", &format!("{}/big.mp3", server.url()));
+        let path = downloader.local_path(&item, item.enclosure.as_ref().unwrap());
+
+        let results = downloader.download_all(&[item]).await;
+        let err = results[0].outcome.as_ref().unwrap_err();
+        assert!(err.to_string().contains("size limit"));
+        assert!(!path.exists());
+        assert!(!part_path(&path).exists());
+    }
+
+    #[tokio::test]
+    async fn test_download_skips_declared_oversized_enclosure() {
+        let dir = TempDir::new().unwrap();
+        let downloader = downloader(&dir).with_max_bytes(1024);
+        let mut item = episode("Big", "http://unused.invalid/big.mp3");
+        if let Some(enclosure) = item.enclosure.as_mut() {
+            enclosure.length = Some(4096);
+        }
+
+        let results = downloader.download_all(&[item]).await;
+        let err = results[0].outcome.as_ref().unwrap_err();
+        assert!(err.to_string().contains("size limit"));
+    }
+
+    #[tokio::test]
+    async fn test_download_respects_guard() {
+        let dir = TempDir::new().unwrap();
+        let downloader = Downloader::new(dir.path().to_path_buf(), RequestGuard::default());
+        let item = episode("Ep", "http://127.0.0.1:9/ep.mp3");
+
+        let results = downloader.download_all(&[item]).await;
+        let err = results[0].outcome.as_ref().unwrap_err();
+        assert!(err.to_string().contains("private network address"));
+    }
+}
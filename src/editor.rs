@@ -0,0 +1,70 @@
+use crate::error::ClioError;
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+/// Editor used when neither `VISUAL` nor `EDITOR` is set
+const DEFAULT_EDITOR: &str = "vi";
+
+/// Open `path` in the user's editor and wait for it to exit
+///
+/// The editor is taken from `VISUAL`, then `EDITOR`, and run through the
+/// shell so that values with arguments such as `code --wait` work.
+pub fn edit(path: &Path) -> Result<(), ClioError> {
+    let editor = editor(env::var("VISUAL").ok(), env::var("EDITOR").ok());
+    let status = command(&editor, path)
+        .status()
+        .map_err(|e| ClioError::Config(format!("Could not run editor '{editor}': {e}")))?;
+    if !status.success() {
+        return Err(ClioError::Config(format!(
+            "Editor '{editor}' exited with {status}"
+        )));
+    }
+    Ok(())
+}
+
+fn editor(visual: Option<String>, editor: Option<String>) -> String {
+    [visual, editor]
+        .into_iter()
+        .flatten()
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string())
+}
+
+/// `editor` run on `path` by the shell, with the path passed as an argument
+/// rather than spliced into the script
+fn command(editor: &str, path: &Path) -> Command {
+    let mut command = Command::new("sh");
+    command
+        .arg("-c")
+        .arg(format!("{editor} \"$1\""))
+        .arg("sh")
+        .arg(path);
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_editor_precedence() {
+        let some = |editor: &str| Some(editor.to_string());
+        assert_eq!(editor(some("code --wait"), some("nano")), "code --wait");
+        assert_eq!(editor(some(" "), some("nano")), "nano");
+        assert_eq!(editor(None, None), "vi");
+    }
+
+    #[test]
+    fn test_command_with_arguments_and_spaces() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("my config.toml");
+        fs::write(&path, "name = \"old\"\n").unwrap();
+
+        let status = command("sed -i s/old/new/", &path).status().unwrap();
+        assert!(status.success());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "name = \"new\"\n");
+    }
+}
@@ -0,0 +1,451 @@
+use crate::citation;
+use crate::config::{Enrichment, Sources};
+use crate::error::{ClioError, ErrorContext};
+use crate::http::FeedClient;
+use crate::sanitize::{TextLimits, clean_text};
+use crate::source::{Item, Paper};
+use atom_syndication::Feed;
+use futures::stream::{self, StreamExt};
+use html_escape::decode_html_entities;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+const CROSSREF_URL: &str = "https://api.crossref.org/works/";
+
+const ARXIV_URL: &str = "https://export.arxiv.org/api/query";
+
+/// Lookups in flight at once; both APIs ask clients not to hammer them
+const CONCURRENT_LOOKUPS: usize = 4;
+
+static ARXIV_ID: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(?:arxiv\.org/(?:abs|pdf)/|\barxiv:\s*)(\d{4}\.\d{4,5})(?:v\d+)?\b")
+        .expect("valid arXiv ID pattern")
+});
+
+static MARKUP: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<[^>]*>").expect("valid markup pattern"));
+
+/// The arXiv preprint an item's link or summary points to, if any
+pub fn arxiv_id(item: &Item) -> Option<String> {
+    [Some(item.link.as_str()), item.summary.as_deref()]
+        .into_iter()
+        .flatten()
+        .find_map(|text| ARXIV_ID.captures(text))
+        .map(|captures| captures[1].to_string())
+}
+
+/// Looks up paper metadata for items of sources with `enrich` set
+///
+/// Authors from the lookup replace the ones the feed gave, since journal and
+/// preprint feeds often list them incompletely or not at all.
+#[derive(Debug, Clone)]
+pub struct Enricher {
+    client: FeedClient,
+    limits: TextLimits,
+    crossref_url: String,
+    arxiv_url: String,
+}
+
+/// An identifier to look up, in the order a source's `enrich` lists them
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Lookup {
+    Doi(String),
+    Arxiv(String),
+}
+
+/// What a lookup found
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Found {
+    paper: Paper,
+    authors: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct CrossrefResponse {
+    message: CrossrefWork,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct CrossrefWork {
+    #[serde(default)]
+    author: Vec<CrossrefAuthor>,
+    #[serde(default)]
+    container_title: Vec<String>,
+    #[serde(rename = "abstract")]
+    abstract_text: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CrossrefAuthor {
+    given: Option<String>,
+    family: Option<String>,
+    /// Name of an organization credited as author
+    name: Option<String>,
+}
+
+impl Enricher {
+    pub fn new(client: FeedClient) -> Self {
+        Self {
+            client,
+            limits: TextLimits::default(),
+            crossref_url: CROSSREF_URL.to_string(),
+            arxiv_url: ARXIV_URL.to_string(),
+        }
+    }
+
+    /// Truncate abstracts like item summaries
+    pub fn with_limits(mut self, limits: TextLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Fill in [`Item::paper`] for every item whose source asks for it
+    ///
+    /// Each identifier is looked up once however many items cite it. Returns
+    /// how many items were enriched, and the lookups that failed.
+    pub async fn enrich(&self, sources: &Sources, items: &mut [Item]) -> (usize, Vec<ClioError>) {
+        let mut wanted: HashMap<Lookup, Vec<usize>> = HashMap::new();
+        for (index, item) in items.iter().enumerate() {
+            let Some(source) = sources.rss.iter().find(|s| s.name == item.source_name) else {
+                continue;
+            };
+            if let Some(lookup) = lookup_for(item, &source.enrich) {
+                wanted.entry(lookup).or_default().push(index);
+            }
+        }
+
+        let results: Vec<_> = stream::iter(wanted)
+            .map(|(lookup, indexes)| async move { (self.lookup(&lookup).await, indexes) })
+            .buffer_unordered(CONCURRENT_LOOKUPS)
+            .collect()
+            .await;
+
+        let mut enriched = 0;
+        let mut failures = Vec::new();
+        for (result, indexes) in results {
+            match result {
+                Ok(found) => {
+                    for index in indexes {
+                        let item = &mut items[index];
+                        if !found.authors.is_empty() {
+                            item.authors = found.authors.clone();
+                        }
+                        item.paper = Some(found.paper.clone());
+                        enriched += 1;
+                    }
+                }
+                Err(e) => failures.push(e),
+            }
+        }
+        (enriched, failures)
+    }
+
+    async fn lookup(&self, lookup: &Lookup) -> Result<Found, ClioError> {
+        match lookup {
+            Lookup::Doi(doi) => self.crossref(doi).await,
+            Lookup::Arxiv(id) => self.arxiv(id).await,
+        }
+    }
+
+    async fn crossref(&self, doi: &str) -> Result<Found, ClioError> {
+        let url = format!("{}{doi}", self.crossref_url);
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .clio_network_err(format!("Failed to look up DOI {doi}"))?;
+        if !response.status().is_success() {
+            return Err(ClioError::Network(format!(
+                "HTTP {} looking up DOI {doi}",
+                response.status()
+            )));
+        }
+        let work = response
+            .json::<CrossrefResponse>()
+            .await
+            .clio_parse_err(format!("Invalid Crossref record for DOI {doi}"))?
+            .message;
+
+        let authors = work
+            .author
+            .into_iter()
+            .filter_map(|author| match (author.given, author.family, author.name) {
+                (Some(given), Some(family), _) => Some(format!("{given} {family}")),
+                (None, Some(family), _) => Some(family),
+                (_, None, name) => name,
+            })
+            .map(|name| clean_text(&name))
+            .collect();
+        Ok(Found {
+            paper: Paper {
+                doi: Some(doi.to_string()),
+                journal: work.container_title.first().map(|title| clean_text(title)),
+                abstract_text: work.abstract_text.map(|text| self.plain_text(&text)),
+                ..Default::default()
+            },
+            authors,
+        })
+    }
+
+    async fn arxiv(&self, id: &str) -> Result<Found, ClioError> {
+        let response = self
+            .client
+            .get(&self.arxiv_url)
+            .query(&[("id_list", id)])
+            .send()
+            .await
+            .clio_network_err(format!("Failed to look up arXiv {id}"))?;
+        if !response.status().is_success() {
+            return Err(ClioError::Network(format!(
+                "HTTP {} looking up arXiv {id}",
+                response.status()
+            )));
+        }
+        let body = response
+            .bytes()
+            .await
+            .clio_network_err("Failed to read arXiv response")?;
+        let feed =
+            Feed::read_from(&body[..]).clio_parse_err(format!("Invalid arXiv record for {id}"))?;
+
+        // Unknown IDs come back as a single entry describing the error
+        let Some(entry) = feed
+            .entries()
+            .first()
+            .filter(|entry| !entry.id().contains("/api/errors"))
+        else {
+            return Err(ClioError::Parse(format!("arXiv has no preprint {id}")));
+        };
+        let extension = |name: &str| {
+            entry
+                .extensions()
+                .get("arxiv")
+                .and_then(|extensions| extensions.get(name))
+                .and_then(|values| values.first())
+                .and_then(|value| value.value())
+                .map(clean_text)
+        };
+
+        Ok(Found {
+            paper: Paper {
+                doi: extension("doi"),
+                arxiv_id: Some(id.to_string()),
+                journal: extension("journal_ref"),
+                abstract_text: entry.summary().map(|text| self.plain_text(text.as_str())),
+            },
+            authors: entry
+                .authors()
+                .iter()
+                .map(|person| clean_text(&person.name))
+                .collect(),
+        })
+    }
+
+    /// Abstract text without JATS or HTML markup
+    fn plain_text(&self, text: &str) -> String {
+        self.limits
+            .summary(&decode_html_entities(&MARKUP.replace_all(text, " ")))
+    }
+}
+
+/// The first identifier an item carries among the kinds a source enriches
+fn lookup_for(item: &Item, kinds: &[Enrichment]) -> Option<Lookup> {
+    kinds.iter().find_map(|kind| match kind {
+        Enrichment::Doi => citation::doi(item).map(Lookup::Doi),
+        Enrichment::Arxiv => arxiv_id(item).map(Lookup::Arxiv),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RssSource;
+
+    fn sources(enrich: Vec<Enrichment>) -> Sources {
+        Sources {
+            rss: vec![RssSource {
+                enrich,
+                ..RssSource::new("Papers".to_string(), "https://example.com/feed".to_string())
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn item(link: &str, summary: Option<&str>) -> Item {
+        Item {
+            source_name: "Papers".to_string(),
+            title: "A paper".to_string(),
+            link: link.to_string(),
+            summary: summary.map(str::to_string),
+            authors: vec!["J. Doe et al.".to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn enricher(server: &mockito::Server) -> Enricher {
+        Enricher {
+            crossref_url: format!("{}/works/", server.url()),
+            arxiv_url: format!("{}/api/query", server.url()),
+            ..Enricher::new(FeedClient::default())
+        }
+    }
+
+    #[test]
+    fn test_arxiv_id() {
+        let id = |link: &str, summary: Option<&str>| arxiv_id(&item(link, summary));
+        assert_eq!(
+            id("https://arxiv.org/abs/2401.01234v2", None).as_deref(),
+            Some("2401.01234")
+        );
+        assert_eq!(
+            id(
+                "https://example.com/post",
+                Some("Preprint: arXiv:2312.9876.")
+            )
+            .as_deref(),
+            Some("2312.9876")
+        );
+        assert_eq!(id("https://example.com/1234.5678", None), None);
+    }
+
+    #[test]
+    fn test_lookup_follows_source_order() {
+        let both = item(
+            "https://arxiv.org/abs/2401.01234",
+            Some("Published as 10.1145/3591227"),
+        );
+        assert_eq!(
+            lookup_for(&both, &[Enrichment::Doi, Enrichment::Arxiv]),
+            Some(Lookup::Doi("10.1145/3591227".to_string()))
+        );
+        assert_eq!(
+            lookup_for(&both, &[Enrichment::Arxiv]),
+            Some(Lookup::Arxiv("2401.01234".to_string()))
+        );
+        assert_eq!(lookup_for(&both, &[]), None);
+    }
+
+    #[tokio::test]
+    async fn test_enrich_from_crossref() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/works/10.1145/3591227")
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"status": "ok", "message": {
+                    "author": [
+                        {"given": "Ada", "family": "Lovelace"},
+                        {"name": "The Analytical Society"}
+                    ],
+                    "container-title": ["Proceedings of the ACM on Programming Languages"],
+                    "abstract": "<jats:p>Borrowing &amp; ownership,\n  revisited.</jats:p>"
+                }}"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let mut items = vec![
+            item("https://doi.org/10.1145/3591227", None),
+            item("https://example.com/again", Some("doi:10.1145/3591227")),
+            item("https://example.com/unrelated", None),
+        ];
+        let (enriched, failures) = enricher(&server)
+            .enrich(&sources(vec![Enrichment::Doi]), &mut items)
+            .await;
+
+        mock.assert_async().await;
+        assert_eq!((enriched, failures.len()), (2, 0));
+        assert_eq!(items[0].authors, ["Ada Lovelace", "The Analytical Society"]);
+        assert_eq!(
+            items[1].paper,
+            Some(Paper {
+                doi: Some("10.1145/3591227".to_string()),
+                arxiv_id: None,
+                journal: Some("Proceedings of the ACM on Programming Languages".to_string()),
+                abstract_text: Some("Borrowing & ownership, revisited.".to_string()),
+            })
+        );
+        assert_eq!(items[2].paper, None);
+        assert_eq!(items[2].authors, ["J. Doe et al."]);
+    }
+
+    #[tokio::test]
+    async fn test_enrich_from_arxiv() {
+        let mut server = mockito::Server::new_async().await;
+        let _found = server
+            .mock("GET", "/api/query")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "id_list".to_string(),
+                "2401.01234".to_string(),
+            ))
+            .with_body(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+                <feed xmlns="http://www.w3.org/2005/Atom" xmlns:arxiv="http://arxiv.org/schemas/atom">
+                  <title>arXiv Query</title><id>http://arxiv.org/api/q</id><updated>2024-01-03T00:00:00Z</updated>
+                  <entry>
+                    <id>http://arxiv.org/abs/2401.01234v1</id>
+                    <title>Fast things</title>
+                    <updated>2024-01-03T00:00:00Z</updated>
+                    <summary>  We make things
+                      fast.</summary>
+                    <author><name>Grace Hopper</name></author>
+                    <author><name>Alan Turing</name></author>
+                    <arxiv:journal_ref>J. Fast 12 (2024)</arxiv:journal_ref>
+                    <arxiv:doi>10.1000/fast.12</arxiv:doi>
+                  </entry>
+                </feed>"#,
+            )
+            .create_async()
+            .await;
+        let _missing = server
+            .mock("GET", "/api/query")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "id_list".to_string(),
+                "9999.99999".to_string(),
+            ))
+            .with_body(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+                <feed xmlns="http://www.w3.org/2005/Atom">
+                  <title>arXiv Query</title><id>http://arxiv.org/api/q</id><updated>2024-01-03T00:00:00Z</updated>
+                  <entry>
+                    <id>http://arxiv.org/api/errors#incorrect_id_format_for_9999.99999</id>
+                    <title>Error</title><updated>2024-01-03T00:00:00Z</updated>
+                  </entry>
+                </feed>"#,
+            )
+            .create_async()
+            .await;
+
+        let mut items = vec![
+            item("https://arxiv.org/abs/2401.01234v1", None),
+            item("https://arxiv.org/abs/9999.99999", None),
+        ];
+        let (enriched, failures) = enricher(&server)
+            .enrich(&sources(vec![Enrichment::Arxiv]), &mut items)
+            .await;
+
+        assert_eq!(enriched, 1);
+        assert_eq!(failures.len(), 1);
+        assert!(
+            failures[0]
+                .to_string()
+                .contains("arXiv has no preprint 9999.99999")
+        );
+        assert_eq!(items[0].authors, ["Grace Hopper", "Alan Turing"]);
+        assert_eq!(
+            items[0].paper,
+            Some(Paper {
+                doi: Some("10.1000/fast.12".to_string()),
+                arxiv_id: Some("2401.01234".to_string()),
+                journal: Some("J. Fast 12 (2024)".to_string()),
+                abstract_text: Some("We make things fast.".to_string()),
+            })
+        );
+        assert_eq!(items[1].paper, None);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::redact::redact;
 use std::fmt::Display;
 
 /// Extension trait for adding context to errors
@@ -5,13 +6,10 @@ pub trait ErrorContext<T> {
     /// Add a simple string context to the error with a specific error variant
     fn clio_config_err(self, msg: impl Display) -> std::result::Result<T, ClioError>;
 
-    #[allow(dead_code)]
     fn clio_network_err(self, msg: impl Display) -> std::result::Result<T, ClioError>;
 
-    #[allow(dead_code)]
     fn clio_parse_err(self, msg: impl Display) -> std::result::Result<T, ClioError>;
 
-    #[allow(dead_code)]
     fn clio_database_err(self, msg: impl Display) -> std::result::Result<T, ClioError>;
 }
 
@@ -33,19 +31,118 @@ impl<T, E: Display> ErrorContext<T> for std::result::Result<T, E> {
     }
 }
 
-#[derive(Debug, thiserror::Error)]
+/// Errors produced by clio
+///
+/// Messages are passed through [`redact`] when displayed or debug-printed, so
+/// secrets embedded in URLs or upstream error text never reach the terminal.
+#[derive(thiserror::Error)]
 pub enum ClioError {
-    #[error("Configuration error: {0}")]
+    #[error("Configuration error: {}", redact(.0))]
     Config(String),
 
-    #[error("Network error: {0}")]
+    #[error("Network error: {}", redact(.0))]
     Network(String),
 
-    #[error("Parse error: {0}")]
+    /// A request that failed in a way its [`ErrorKind`] tells apart, such as
+    /// a timeout or an error status
+    #[error("Network error: {}", redact(.message))]
+    Request { kind: ErrorKind, message: String },
+
+    #[error("Redirect loop: {}", redact(.0))]
+    RedirectLoop(String),
+
+    #[error("Parse error: {}", redact(.0))]
     Parse(String),
 
-    #[error("Database error: {0}")]
+    #[error("Database error: {}", redact(.0))]
     Database(String),
+
+    #[error("Browser error: {}", redact(.0))]
+    Browser(String),
+
+    #[error("Notification error: {}", redact(.0))]
+    Notification(String),
+
+    #[error("Clipboard error: {}", redact(.0))]
+    Clipboard(String),
+
+    #[error("Read-later error: {}", redact(.0))]
+    ReadLater(String),
+}
+
+/// What went wrong with a failed fetch, for telling flaky feeds' problems apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    Dns,
+    Tls,
+    Timeout,
+    /// The server answered with an error status
+    Http(u16),
+    RedirectLoop,
+    Parse,
+    /// Any other network failure, such as a refused connection
+    Network,
+    Other,
+}
+
+impl ClioError {
+    /// A request that failed with `kind`
+    pub fn request(kind: ErrorKind, message: impl Into<String>) -> Self {
+        ClioError::Request {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    /// What kind of failure this is, as recorded in the fetch history
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            ClioError::Request { kind, .. } => *kind,
+            ClioError::Network(_) => ErrorKind::Network,
+            ClioError::RedirectLoop(_) => ErrorKind::RedirectLoop,
+            ClioError::Parse(_) => ErrorKind::Parse,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Dns => write!(f, "DNS"),
+            Self::Tls => write!(f, "TLS"),
+            Self::Timeout => write!(f, "timeout"),
+            Self::Http(status) => write!(f, "HTTP {status}"),
+            Self::RedirectLoop => write!(f, "redirect loop"),
+            Self::Parse => write!(f, "parse"),
+            Self::Network => write!(f, "network"),
+            Self::Other => write!(f, "other"),
+        }
+    }
+}
+
+impl std::fmt::Debug for ClioError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (variant, message) = match self {
+            ClioError::Request { kind, message } => {
+                return f
+                    .debug_struct("Request")
+                    .field("kind", kind)
+                    .field("message", &redact(message))
+                    .finish();
+            }
+            ClioError::Config(msg) => ("Config", msg),
+            ClioError::Network(msg) => ("Network", msg),
+            ClioError::RedirectLoop(msg) => ("RedirectLoop", msg),
+            ClioError::Parse(msg) => ("Parse", msg),
+            ClioError::Database(msg) => ("Database", msg),
+            ClioError::Browser(msg) => ("Browser", msg),
+            ClioError::Notification(msg) => ("Notification", msg),
+            ClioError::Clipboard(msg) => ("Clipboard", msg),
+            ClioError::ReadLater(msg) => ("ReadLater", msg),
+        };
+        f.debug_tuple(variant).field(&redact(message)).finish()
+    }
 }
 
 impl From<toml::de::Error> for ClioError {
@@ -56,7 +153,13 @@ impl From<toml::de::Error> for ClioError {
 
 impl From<reqwest::Error> for ClioError {
     fn from(err: reqwest::Error) -> Self {
-        ClioError::Network(err.to_string())
+        if err.is_timeout() {
+            ClioError::request(ErrorKind::Timeout, err.to_string())
+        } else if let Some(status) = err.status() {
+            ClioError::request(ErrorKind::Http(status.as_u16()), err.to_string())
+        } else {
+            ClioError::Network(err.to_string())
+        }
     }
 }
 
@@ -77,3 +180,43 @@ impl From<chrono::ParseError> for ClioError {
         ClioError::Parse(format!("Date parsing error: {err}"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_display_redacts_secrets() {
+        let err = ClioError::Network(
+            "error sending request for url (https://example.com/feed?token=abc123)".to_string(),
+        );
+        assert_eq!(
+            err.to_string(),
+            "Network error: error sending request for url (https://example.com/feed?token=<REDACTED>)"
+        );
+    }
+
+    #[test]
+    fn test_error_debug_redacts_secrets() {
+        let err = ClioError::Database("rejected key sb_secret_supersecret123".to_string());
+        let debug = format!("{err:?}");
+        assert_eq!(debug, "Database(\"rejected key sb_secret_<REDACTED>\")");
+    }
+
+    #[test]
+    fn test_browser_error_display() {
+        let err = ClioError::Browser("Could not launch a browser".to_string());
+        assert_eq!(err.to_string(), "Browser error: Could not launch a browser");
+        assert_eq!(
+            format!("{err:?}"),
+            "Browser(\"Could not launch a browser\")"
+        );
+    }
+
+    #[test]
+    fn test_error_context_redacts_secrets() {
+        let result: Result<(), &str> = Err("Authorization: Bearer abc.def");
+        let err = result.clio_network_err("Request failed").unwrap_err();
+        assert!(!err.to_string().contains("abc.def"));
+    }
+}
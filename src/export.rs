@@ -0,0 +1,179 @@
+use crate::serve::ItemJson;
+use crate::source::Item;
+
+/// Columns of CSV exports, in order
+const CSV_HEADER: [&str; 10] = [
+    "id",
+    "source",
+    "title",
+    "link",
+    "published",
+    "read",
+    "starred",
+    "authors",
+    "tags",
+    "summary",
+];
+
+/// Render items as a JSON array, shaped like the `clio serve` API
+pub fn to_json(items: &[Item]) -> String {
+    let items: Vec<ItemJson> = items.iter().cloned().map(ItemJson::from).collect();
+    serde_json::to_string_pretty(&items).expect("items serialize to JSON")
+}
+
+/// Render items as CSV (RFC 4180) with a header row
+///
+/// Authors and tags are joined with "; ", and dates are RFC 3339.
+pub fn to_csv(items: &[Item]) -> String {
+    let mut csv = CSV_HEADER.join(",") + "\r\n";
+    for item in items {
+        let fields = [
+            item.id.clone(),
+            item.source_name.clone(),
+            item.title.clone(),
+            item.link.clone(),
+            item.pub_date
+                .map(|date| date.to_rfc3339())
+                .unwrap_or_default(),
+            item.is_read.to_string(),
+            item.is_starred.to_string(),
+            item.authors.join("; "),
+            item.tags.join("; "),
+            item.summary.clone().unwrap_or_default(),
+        ];
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+/// Render items as a Markdown list of links, one item per line
+///
+/// For example `- [Title](https://example.com/post) · Source · 2024-05-01`.
+pub fn to_markdown(items: &[Item]) -> String {
+    items
+        .iter()
+        .map(|item| {
+            let mut line = format!(
+                "- {} · {}",
+                markdown_link(item),
+                escape_markdown(&item.source_name)
+            );
+            if let Some(date) = item.pub_date {
+                line.push_str(&format!(" · {}", date.format("%Y-%m-%d")));
+            }
+            line + "\n"
+        })
+        .collect()
+}
+
+/// An item as a Markdown link, `[Title](https://example.com/post)`
+pub fn markdown_link(item: &Item) -> String {
+    format!(
+        "[{}]({})",
+        escape_markdown(&item.title),
+        item.link.replace('(', "%28").replace(')', "%29")
+    )
+}
+
+/// A CSV field, quoted when it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Backslash-escape characters Markdown would read as formatting
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '[' | ']' | '*' | '_' | '`' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use serde_json::Value;
+
+    fn items() -> Vec<Item> {
+        vec![
+            Item {
+                id: "a1".to_string(),
+                source_name: "Julia Evans".to_string(),
+                title: "Quotes, \"commas\", and [brackets]".to_string(),
+                link: "https://example.com/post_(1)".to_string(),
+                summary: Some("Line one\nline two".to_string()),
+                authors: vec!["Julia Evans".to_string()],
+                tags: vec!["dns".to_string(), "favorites".to_string()],
+                pub_date: Some(Utc.with_ymd_and_hms(2024, 5, 1, 8, 30, 0).unwrap()),
+                is_starred: true,
+                ..Default::default()
+            },
+            Item {
+                id: "b2".to_string(),
+                source_name: "Lobsters".to_string(),
+                title: "Plain".to_string(),
+                link: "https://example.com/plain".to_string(),
+                is_read: true,
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn test_to_json() {
+        let json: Value = serde_json::from_str(&to_json(&items())).unwrap();
+        assert_eq!(json[0]["source"], "Julia Evans");
+        assert_eq!(json[0]["published"], "2024-05-01T08:30:00Z");
+        assert_eq!(json[0]["tags"], serde_json::json!(["dns", "favorites"]));
+        assert_eq!(json[1]["read"], true);
+        assert_eq!(to_json(&[]), "[]");
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let csv = to_csv(&items());
+        let lines: Vec<&str> = csv.split("\r\n").collect();
+        assert_eq!(
+            lines[0],
+            "id,source,title,link,published,read,starred,authors,tags,summary"
+        );
+        assert_eq!(
+            lines[1],
+            "a1,Julia Evans,\"Quotes, \"\"commas\"\", and [brackets]\",https://example.com/post_(1),\
+             2024-05-01T08:30:00+00:00,false,true,Julia Evans,dns; favorites,\"Line one\nline two\""
+        );
+        assert_eq!(
+            lines[2],
+            "b2,Lobsters,Plain,https://example.com/plain,,true,false,,,"
+        );
+        assert_eq!(to_csv(&[]).lines().count(), 1);
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        assert_eq!(
+            to_markdown(&items()),
+            "- [Quotes, \"commas\", and \\[brackets\\]](https://example.com/post_%281%29) \
+             · Julia Evans · 2024-05-01\n\
+             - [Plain](https://example.com/plain) · Lobsters\n"
+        );
+    }
+
+    #[test]
+    fn test_markdown_link() {
+        assert_eq!(
+            markdown_link(&items()[1]),
+            "[Plain](https://example.com/plain)"
+        );
+    }
+}
@@ -1,12 +1,23 @@
+use crate::color::{self, Tone};
+use crate::error::ErrorKind;
+use crate::http::{ConnectionStats, FeedClient};
 use crate::{ClioError, Item, Source};
 use futures::future::join_all;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::timeout;
 
+/// Seconds a fetcher waits for each source, unless configured otherwise with
+/// `timeout`
+pub const DEFAULT_TIMEOUT_SECS: u64 = 10;
+
 /// Fetcher handles parallel content fetching from multiple sources
 pub struct Fetcher {
     timeout_duration: Duration,
+    /// Client shared by the sources, whose connection reuse is reported
+    client: Option<FeedClient>,
+    /// Leave out progress and the summary on stderr
+    quiet: bool,
 }
 
 impl Default for Fetcher {
@@ -19,7 +30,9 @@ impl Fetcher {
     /// Create a new fetcher with default timeout
     pub fn new() -> Self {
         Self {
-            timeout_duration: Duration::from_secs(10),
+            timeout_duration: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            client: None,
+            quiet: false,
         }
     }
 
@@ -27,9 +40,24 @@ impl Fetcher {
     pub fn with_timeout(timeout_secs: u64) -> Self {
         Self {
             timeout_duration: Duration::from_secs(timeout_secs),
+            client: None,
+            quiet: false,
         }
     }
 
+    /// Report how the sources' shared client reused connections during each fetch
+    pub fn with_client(mut self, client: FeedClient) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Fetch without reporting progress or a summary, for callers that
+    /// report results themselves
+    pub fn quiet(mut self) -> Self {
+        self.quiet = true;
+        self
+    }
+
     /// Fetch content from all sources in parallel
     pub async fn fetch_all(&self, sources: Vec<Arc<dyn Source>>) -> (Vec<Item>, FetchStats) {
         let num_sources = sources.len();
@@ -40,7 +68,11 @@ impl Fetcher {
         }
 
         // Show initial progress
-        println!("Fetching content from {num_sources} sources...");
+        let quiet = self.quiet;
+        if !quiet {
+            eprintln!("Fetching content from {num_sources} sources...");
+        }
+        let connections_before = self.client.as_ref().map(FeedClient::stats);
 
         // Create concurrent fetch async_tasks
         let async_tasks: Vec<_> = sources
@@ -52,10 +84,17 @@ impl Fetcher {
                 tokio::spawn(async move {
                     // Show progress for this source
                     let source_name = source.name().to_string();
-                    println!("  [{}/{}] Fetching {}", index + 1, num_sources, source_name);
+                    if !quiet {
+                        let counter = format!("[{}/{}]", index + 1, num_sources);
+                        eprintln!(
+                            "  {} Fetching {}",
+                            color::err(Tone::Dim, counter),
+                            color::err(Tone::Source, &source_name)
+                        );
+                    }
 
                     // Use fetch_one to handle timeout logic
-                    match fetcher.fetch_one(source).await {
+                    let result = match fetcher.fetch_one(Arc::clone(&source)).await {
                         Ok(items) => FetchResult::Success {
                             source_name: source_name.clone(),
                             items,
@@ -63,8 +102,10 @@ impl Fetcher {
                         Err(e) => FetchResult::Error {
                             source_name: source_name.clone(),
                             error: e.to_string(),
+                            kind: e.kind(),
                         },
-                    }
+                    };
+                    (result, source)
                 })
             })
             .collect();
@@ -76,10 +117,11 @@ impl Fetcher {
         let mut feed_items = Vec::new();
         for result in results {
             match result {
-                Ok(fetch_result) => {
+                Ok((fetch_result, source)) => {
                     if let FetchResult::Success { ref items, .. } = fetch_result {
                         feed_items.extend(items.clone());
                     }
+                    stats.record_fetch(&fetch_result, source.as_ref());
                     stats.process_result(&fetch_result);
                 }
                 Err(e) => {
@@ -91,8 +133,14 @@ impl Fetcher {
             }
         }
 
-        println!(); // Empty line after progress
-        stats.display_summary();
+        if let (Some(client), Some(before)) = (&self.client, connections_before) {
+            stats.connections = Some(client.stats().since(&before));
+        }
+
+        if !quiet {
+            eprintln!(); // Empty line after progress
+            stats.display_summary();
+        }
 
         (feed_items, stats)
     }
@@ -102,11 +150,14 @@ impl Fetcher {
         timeout(self.timeout_duration, source.fetch())
             .await
             .map_err(|_| {
-                ClioError::Network(format!(
-                    "Request to {} timed out after {:?}",
-                    source.name(),
-                    self.timeout_duration
-                ))
+                ClioError::request(
+                    ErrorKind::Timeout,
+                    format!(
+                        "Request to {} timed out after {:?}",
+                        source.name(),
+                        self.timeout_duration
+                    ),
+                )
             })?
     }
 }
@@ -121,9 +172,31 @@ pub enum FetchResult {
     Error {
         source_name: String,
         error: String,
+        kind: ErrorKind,
     },
 }
 
+/// What fetching one source downloaded and produced, as kept in the fetch history
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceFetch {
+    pub source_name: String,
+    /// Response body bytes received
+    pub bytes: u64,
+    pub items: usize,
+    pub succeeded: bool,
+    /// Why the fetch failed
+    pub error: Option<String>,
+    pub error_kind: Option<ErrorKind>,
+    /// Fallback address that answered because the source's URL failed
+    pub mirror: Option<String>,
+    /// The body was the same as last time, so it was not parsed
+    pub not_modified: bool,
+    /// Hash of the last body parsed, for skipping it next time
+    pub body_hash: Option<String>,
+    /// Where the request ended up after following redirects, if elsewhere
+    pub final_url: Option<String>,
+}
+
 /// Statistics from a fetch operation
 #[derive(Debug, Clone, Default)]
 pub struct FetchStats {
@@ -132,6 +205,10 @@ pub struct FetchStats {
     pub failed_sources: usize,
     pub total_items: usize,
     pub errors: Vec<(String, String)>, // (source_name, error_message)
+    /// One entry per source fetched
+    pub fetches: Vec<SourceFetch>,
+    /// Requests and new connections made by the shared client, when known
+    pub connections: Option<ConnectionStats>,
 }
 
 impl FetchStats {
@@ -143,9 +220,49 @@ impl FetchStats {
             failed_sources: 0,
             total_items: 0,
             errors: Vec::new(),
+            fetches: Vec::new(),
+            connections: None,
         }
     }
 
+    /// Record the bytes a source's fetch downloaded along with its outcome,
+    /// the mirror it fell back to, if any, and whether its body had changed
+    pub fn record_fetch(&mut self, result: &FetchResult, source: &dyn Source) {
+        let (source_name, items, error, error_kind) = match result {
+            FetchResult::Success { source_name, items } => (source_name, items.len(), None, None),
+            FetchResult::Error {
+                source_name,
+                error,
+                kind,
+            } => (source_name, 0, Some(error.clone()), Some(*kind)),
+        };
+        self.fetches.push(SourceFetch {
+            source_name: source_name.clone(),
+            bytes: source.bytes_received(),
+            items,
+            succeeded: error.is_none(),
+            error,
+            error_kind,
+            mirror: source.mirror_used(),
+            not_modified: source.not_modified(),
+            body_hash: source.body_hash(),
+            final_url: source.final_url(),
+        });
+    }
+
+    /// Number of sources whose body had not changed since their last fetch
+    pub fn not_modified(&self) -> usize {
+        self.fetches
+            .iter()
+            .filter(|fetch| fetch.not_modified)
+            .count()
+    }
+
+    /// Total response body bytes received across all sources
+    pub fn total_bytes(&self) -> u64 {
+        self.fetches.iter().map(|fetch| fetch.bytes).sum()
+    }
+
     /// Process a fetch result and update statistics
     pub fn process_result(&mut self, result: &FetchResult) {
         match result {
@@ -153,7 +270,9 @@ impl FetchStats {
                 self.successful_sources += 1;
                 self.total_items += items.len();
             }
-            FetchResult::Error { source_name, error } => {
+            FetchResult::Error {
+                source_name, error, ..
+            } => {
                 self.failed_sources += 1;
                 self.errors.push((source_name.clone(), error.clone()));
             }
@@ -162,15 +281,51 @@ impl FetchStats {
 
     /// Display summary of fetch operation
     pub fn display_summary(&self) {
-        println!(
-            "Fetched {} items from {} of {} sources",
-            self.total_items, self.successful_sources, self.num_sources
+        let tone = if self.failed_sources == 0 {
+            Tone::Success
+        } else {
+            Tone::Warning
+        };
+        eprintln!(
+            "{}",
+            color::err(
+                tone,
+                format!(
+                    "Fetched {} items from {} of {} sources",
+                    self.total_items, self.successful_sources, self.num_sources
+                )
+            )
         );
+        if let Some(connections) = self.connections.filter(|c| c.requests > 0) {
+            eprintln!(
+                "Reused connections for {} of {} requests ({} new connections)",
+                connections.reused(),
+                connections.requests,
+                connections.connections
+            );
+        }
+
+        let not_modified = self.not_modified();
+        if not_modified > 0 {
+            eprintln!("{not_modified} source(s) not modified since their last fetch");
+        }
+
+        let mirrors: Vec<_> = self
+            .fetches
+            .iter()
+            .filter_map(|fetch| Some((&fetch.source_name, fetch.mirror.as_ref()?)))
+            .collect();
+        if !mirrors.is_empty() {
+            eprintln!("\n{}", color::err(Tone::Warning, "Fetched from a mirror:"));
+            for (source, mirror) in mirrors {
+                eprintln!("  - {}: {mirror}", color::err(Tone::Source, source));
+            }
+        }
 
         if !self.errors.is_empty() {
-            eprintln!("\nFailed sources:");
+            eprintln!("\n{}", color::err(Tone::Error, "Failed sources:"));
             for (source, error) in &self.errors {
-                eprintln!("  - {source}: {error}");
+                eprintln!("  - {}: {error}", color::err(Tone::Source, source));
             }
         }
     }
@@ -225,6 +380,7 @@ mod tests {
             link: format!("https://example.com/{id}"),
             summary: Some(format!("Summary for article {id}")),
             pub_date: Some(Utc::now()),
+            ..Default::default()
         }
     }
 
@@ -330,7 +486,7 @@ mod tests {
         let result = fetcher.fetch_one(source).await;
 
         assert!(result.is_err());
-        assert!(matches!(result.unwrap_err(), ClioError::Network(_)));
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::Timeout);
     }
 
     #[tokio::test]
@@ -415,6 +571,114 @@ mod tests {
         assert_eq!(stats.failed_sources, 1);
         assert_eq!(stats.total_items, 5);
         assert_eq!(items.len(), 5);
+
+        assert_eq!(stats.fetches.len(), 3);
+        let failed = stats.fetches.iter().find(|f| f.source_name == "Source3");
+        assert!(failed.is_some_and(|f| !f.succeeded && f.items == 0));
+    }
+
+    /// A source that has already been fetched, reporting what it received
+    #[derive(Debug)]
+    struct Fetched {
+        bytes: u64,
+        mirror: Option<&'static str>,
+        body_hash: Option<&'static str>,
+        not_modified: bool,
+    }
+
+    #[async_trait]
+    impl Source for Fetched {
+        fn name(&self) -> &str {
+            "Fetched"
+        }
+
+        fn url(&self) -> &str {
+            "https://example.com/feed"
+        }
+
+        async fn fetch(&self) -> Result<Vec<Item>, ClioError> {
+            Ok(Vec::new())
+        }
+
+        fn bytes_received(&self) -> u64 {
+            self.bytes
+        }
+
+        fn mirror_used(&self) -> Option<String> {
+            self.mirror.map(str::to_string)
+        }
+
+        fn body_hash(&self) -> Option<String> {
+            self.body_hash.map(str::to_string)
+        }
+
+        fn not_modified(&self) -> bool {
+            self.not_modified
+        }
+    }
+
+    #[test]
+    fn test_record_fetch_bytes() {
+        let mut stats = FetchStats::new(3);
+        stats.record_fetch(
+            &FetchResult::Success {
+                source_name: "Big".to_string(),
+                items: vec![create_test_item("1", "Big")],
+            },
+            &Fetched {
+                bytes: 50_000,
+                mirror: Some("https://mirror.example.com/big.xml"),
+                body_hash: Some("9f2c6a1e0b7d4e53"),
+                not_modified: false,
+            },
+        );
+        stats.record_fetch(
+            &FetchResult::Error {
+                source_name: "Broken".to_string(),
+                error: "Invalid feed".to_string(),
+                kind: ErrorKind::Parse,
+            },
+            &Fetched {
+                bytes: 1_200,
+                mirror: None,
+                body_hash: None,
+                not_modified: false,
+            },
+        );
+        stats.record_fetch(
+            &FetchResult::Success {
+                source_name: "Static".to_string(),
+                items: Vec::new(),
+            },
+            &Fetched {
+                bytes: 800,
+                mirror: None,
+                body_hash: Some("04b1d3f5a7c9e2a0"),
+                not_modified: true,
+            },
+        );
+
+        assert_eq!(
+            stats.fetches[0],
+            SourceFetch {
+                source_name: "Big".to_string(),
+                bytes: 50_000,
+                items: 1,
+                succeeded: true,
+                error: None,
+                error_kind: None,
+                mirror: Some("https://mirror.example.com/big.xml".to_string()),
+                not_modified: false,
+                body_hash: Some("9f2c6a1e0b7d4e53".to_string()),
+                final_url: None,
+            }
+        );
+        assert!(!stats.fetches[1].succeeded);
+        assert_eq!(stats.fetches[1].error.as_deref(), Some("Invalid feed"));
+        assert_eq!(stats.fetches[1].error_kind, Some(ErrorKind::Parse));
+        assert!(stats.fetches[2].not_modified);
+        assert_eq!(stats.not_modified(), 1);
+        assert_eq!(stats.total_bytes(), 52_000);
     }
 
     #[tokio::test]
@@ -427,6 +691,7 @@ mod tests {
         let error = FetchResult::Error {
             source_name: "Failed".to_string(),
             error: "Network error".to_string(),
+            kind: ErrorKind::Network,
         };
 
         let mut stats = FetchStats::new(2);
@@ -0,0 +1,188 @@
+use crate::config::{RssSource, Sources};
+use crate::error::ClioError;
+use std::fmt;
+use std::str::FromStr;
+
+/// A condition selecting configured sources, written as `tag:NAME` or `source:NAME`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    /// Sources carrying this tag
+    Tag(String),
+    /// The source with this name
+    Source(String),
+}
+
+impl Filter {
+    /// Whether a source satisfies this filter
+    pub fn matches(&self, source: &RssSource) -> bool {
+        match self {
+            Filter::Tag(tag) => source.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)),
+            Filter::Source(name) => source.name == *name,
+        }
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Filter::Tag(tag) => write!(f, "tag:{tag}"),
+            Filter::Source(name) => write!(f, "source:{name}"),
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = ClioError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            ClioError::Config(format!(
+                "Invalid filter '{s}' (expected tag:NAME or source:NAME)"
+            ))
+        };
+
+        let (kind, value) = s.split_once(':').ok_or_else(invalid)?;
+        let value = value.trim();
+        if value.is_empty() {
+            return Err(invalid());
+        }
+
+        match kind.trim() {
+            "tag" => Ok(Filter::Tag(value.to_string())),
+            "source" => Ok(Filter::Source(value.to_string())),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Names of the active sources matching every filter
+///
+/// Returns `None` when there are no filters, meaning every source is selected.
+pub fn matching_sources(sources: &Sources, filters: &[Filter]) -> Option<Vec<String>> {
+    if filters.is_empty() {
+        return None;
+    }
+
+    Some(
+        sources
+            .active()
+            .filter(|source| filters.iter().all(|filter| filter.matches(source)))
+            .map(|source| source.name.clone())
+            .collect(),
+    )
+}
+
+/// Whether a source name matches a pattern, where `*` stands for any run of
+/// characters and `?` for exactly one
+///
+/// A pattern without wildcards matches only that exact name.
+pub fn name_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Where the last `*` was seen, and how much of the name it has taken so far
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                // Let the last `*` take one more character and try again
+                Some((star_p, star_n)) => {
+                    star = Some((star_p, star_n + 1));
+                    p = star_p + 1;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(name: &str, tags: &[&str]) -> RssSource {
+        RssSource {
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..RssSource::new(name.to_string(), format!("https://{name}.example.com/feed"))
+        }
+    }
+
+    #[test]
+    fn test_parse_filter() {
+        assert_eq!(
+            "tag:events".parse::<Filter>().unwrap(),
+            Filter::Tag("events".to_string())
+        );
+        assert_eq!(
+            "source:My Blog".parse::<Filter>().unwrap(),
+            Filter::Source("My Blog".to_string())
+        );
+        assert!("events".parse::<Filter>().is_err());
+        assert!("tag:".parse::<Filter>().is_err());
+        assert!("author:me".parse::<Filter>().is_err());
+
+        let filter = Filter::Source("My Blog".to_string());
+        assert_eq!(filter.to_string().parse::<Filter>().unwrap(), filter);
+    }
+
+    #[test]
+    fn test_matching_sources() {
+        let mut paused = source("paused", &["events"]);
+        paused.paused = true;
+        let sources = Sources {
+            rss: vec![
+                source("meetups", &["events", "local"]),
+                source("releases", &["Events"]),
+                source("news", &[]),
+                paused,
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(matching_sources(&sources, &[]), None);
+        assert_eq!(
+            matching_sources(&sources, &[Filter::Tag("events".to_string())]),
+            Some(vec!["meetups".to_string(), "releases".to_string()])
+        );
+        assert_eq!(
+            matching_sources(
+                &sources,
+                &[
+                    Filter::Tag("events".to_string()),
+                    Filter::Tag("local".to_string())
+                ]
+            ),
+            Some(vec!["meetups".to_string()])
+        );
+        assert_eq!(
+            matching_sources(&sources, &[Filter::Source("missing".to_string())]),
+            Some(Vec::new())
+        );
+    }
+
+    #[test]
+    fn test_name_matches() {
+        assert!(name_matches("Hacker*", "Hacker News"));
+        assert!(name_matches("Hacker*", "Hacker"));
+        assert!(name_matches("*News", "Hacker News"));
+        assert!(name_matches("*a*e*", "Lobste.rs and Hacker News"));
+        assert!(name_matches("Blog ?", "Blog 2"));
+        assert!(name_matches("Rust Blog", "Rust Blog"));
+
+        assert!(!name_matches("Hacker*", "hacker news"));
+        assert!(!name_matches("Blog ?", "Blog 12"));
+        assert!(!name_matches("Rust", "Rust Blog"));
+        assert!(!name_matches("*News", "News feed"));
+    }
+}
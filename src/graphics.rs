@@ -0,0 +1,419 @@
+use crate::config::ImagePreviews;
+use crate::error::{ClioError, ErrorContext};
+use crate::http::RequestGuard;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use image::imageops::FilterType;
+use image::{ImageFormat, RgbaImage};
+use reqwest::Client;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::io::Cursor;
+use std::time::Duration;
+use url::Url;
+
+/// Largest thumbnail downloaded for a preview (5 MB)
+const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Largest base64 payload sent in one kitty graphics escape
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+/// Levels per channel in the sixel palette (6 × 6 × 6 = 216 colors)
+const SIXEL_LEVELS: u32 = 6;
+
+/// Cell size assumed when the terminal does not report its pixel size
+const DEFAULT_CELL_SIZE: (u32, u32) = (8, 16);
+
+/// Terminal protocols for drawing images inline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphicsProtocol {
+    /// Kitty graphics protocol, also spoken by Ghostty and WezTerm
+    Kitty,
+    /// iTerm2 inline images (OSC 1337)
+    Iterm2,
+    /// DEC sixel graphics
+    Sixel,
+}
+
+impl GraphicsProtocol {
+    /// The protocol to use under a setting, detecting the terminal for `auto`
+    pub fn select(setting: ImagePreviews) -> Option<Self> {
+        match setting {
+            ImagePreviews::Auto => Self::detect(|name| std::env::var(name).ok()),
+            ImagePreviews::Off => None,
+            ImagePreviews::Kitty => Some(Self::Kitty),
+            ImagePreviews::Iterm2 => Some(Self::Iterm2),
+            ImagePreviews::Sixel => Some(Self::Sixel),
+        }
+    }
+
+    /// Escape sequence drawing an image file at the cursor, scaled to fit
+    /// within `columns` × `rows` cells of `cell` pixels each
+    pub fn encode(
+        self,
+        data: &[u8],
+        columns: u16,
+        rows: u16,
+        cell: (u32, u32),
+    ) -> Result<String, ClioError> {
+        let max_width = u32::from(columns) * cell.0;
+        let max_height = u32::from(rows) * cell.1;
+        if max_width == 0 || max_height == 0 {
+            return Ok(String::new());
+        }
+
+        let mut image = image::load_from_memory(data).clio_parse_err("Unsupported image")?;
+        // Shrink to fit, but leave small images at their own size
+        if image.width() > max_width || image.height() > max_height {
+            image = image.resize(max_width, max_height, FilterType::Triangle);
+        }
+        let image = image.to_rgba8();
+
+        match self {
+            Self::Kitty => Ok(kitty(&png(&image)?)),
+            Self::Iterm2 => Ok(iterm2(&png(&image)?, image.width(), image.height())),
+            Self::Sixel => Ok(sixel(&image)),
+        }
+    }
+
+    /// Escape sequence removing previously drawn images
+    ///
+    /// Kitty keeps images above the text layer, so clearing the screen does
+    /// not remove them; the other protocols draw into the cells themselves.
+    pub fn clear(self) -> &'static str {
+        match self {
+            Self::Kitty => "\x1b_Ga=d,d=A,q=2\x1b\\",
+            Self::Iterm2 | Self::Sixel => "",
+        }
+    }
+
+    /// Guess the terminal's image support from its environment
+    fn detect(var: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        let term = var("TERM").unwrap_or_default();
+        let program = var("TERM_PROGRAM").unwrap_or_default();
+
+        // Multiplexers swallow graphics escapes unless specially configured
+        if var("TMUX").is_some() || term.starts_with("screen") || term.starts_with("tmux") {
+            return None;
+        }
+
+        if var("KITTY_WINDOW_ID").is_some()
+            || term == "xterm-kitty"
+            || term == "xterm-ghostty"
+            || program == "ghostty"
+        {
+            Some(Self::Kitty)
+        } else if matches!(program.as_str(), "iTerm.app" | "WezTerm")
+            || var("LC_TERMINAL").as_deref() == Some("iTerm2")
+        {
+            Some(Self::Iterm2)
+        } else if term.contains("sixel")
+            || term.starts_with("foot")
+            || term.starts_with("mlterm")
+            || term.starts_with("contour")
+        {
+            Some(Self::Sixel)
+        } else {
+            None
+        }
+    }
+}
+
+/// Pixel size of one terminal cell, as reported by the terminal
+pub fn cell_size() -> (u32, u32) {
+    match crossterm::terminal::window_size() {
+        Ok(size) if size.width > 0 && size.height > 0 && size.columns > 0 && size.rows > 0 => (
+            u32::from(size.width / size.columns),
+            u32::from(size.height / size.rows),
+        ),
+        _ => DEFAULT_CELL_SIZE,
+    }
+}
+
+/// Downloads thumbnails for the reader pane
+#[derive(Debug, Clone)]
+pub struct ImageFetcher {
+    client: Client,
+    guard: RequestGuard,
+}
+
+impl ImageFetcher {
+    /// Create a fetcher with requests restricted by `guard`
+    pub fn new(guard: RequestGuard) -> Self {
+        Self {
+            client: guard
+                .apply(
+                    Client::builder()
+                        .user_agent("Clio/0.1.0")
+                        .timeout(Duration::from_secs(10)),
+                )
+                .build()
+                .unwrap_or_default(),
+            guard,
+        }
+    }
+
+    /// Download an image, refusing anything over 5 MB
+    pub async fn fetch(&self, url: &str) -> Result<Vec<u8>, ClioError> {
+        let parsed = Url::parse(url).clio_network_err(format!("Invalid image URL {url}"))?;
+        self.guard.check_url(&parsed)?;
+
+        let mut response = self
+            .client
+            .get(parsed)
+            .send()
+            .await
+            .clio_network_err(format!("Failed to download {url}"))?;
+        if !response.status().is_success() {
+            return Err(ClioError::Network(format!(
+                "HTTP {} from {url}",
+                response.status()
+            )));
+        }
+
+        let too_large = || ClioError::Network(format!("Image at {url} is larger than 5 MB"));
+        if response
+            .content_length()
+            .is_some_and(|length| length > MAX_IMAGE_BYTES as u64)
+        {
+            return Err(too_large());
+        }
+
+        let mut data = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .clio_network_err(format!("Failed to download {url}"))?
+        {
+            data.extend_from_slice(&chunk);
+            if data.len() > MAX_IMAGE_BYTES {
+                return Err(too_large());
+            }
+        }
+        Ok(data)
+    }
+}
+
+fn png(image: &RgbaImage) -> Result<Vec<u8>, ClioError> {
+    let mut data = Cursor::new(Vec::new());
+    image
+        .write_to(&mut data, ImageFormat::Png)
+        .clio_parse_err("Failed to encode image")?;
+    Ok(data.into_inner())
+}
+
+/// Transmit and display a PNG in chunks, leaving the cursor where it was
+fn kitty(png: &[u8]) -> String {
+    let payload = BASE64.encode(png);
+    let chunks: Vec<&[u8]> = payload.as_bytes().chunks(KITTY_CHUNK_SIZE).collect();
+
+    let mut out = String::with_capacity(payload.len() + chunks.len() * 16);
+    for (index, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(index + 1 < chunks.len());
+        let chunk = std::str::from_utf8(chunk).expect("base64 is ASCII");
+        if index == 0 {
+            let _ = write!(out, "\x1b_Ga=T,f=100,C=1,q=2,m={more};{chunk}\x1b\\");
+        } else {
+            let _ = write!(out, "\x1b_Gm={more};{chunk}\x1b\\");
+        }
+    }
+    out
+}
+
+fn iterm2(png: &[u8], width: u32, height: u32) -> String {
+    format!(
+        "\x1b]1337;File=inline=1;size={};width={width}px;height={height}px;preserveAspectRatio=1:{}\x07",
+        png.len(),
+        BASE64.encode(png)
+    )
+}
+
+/// Encode an image as sixels using a fixed 216-color palette
+///
+/// Pixels that are mostly transparent are left unpainted.
+fn sixel(image: &RgbaImage) -> String {
+    let (width, height) = image.dimensions();
+    let mut out = format!("\x1bPq\"1;1;{width};{height}");
+
+    let mut defined = [false; (SIXEL_LEVELS * SIXEL_LEVELS * SIXEL_LEVELS) as usize];
+    for band in (0..height).step_by(6) {
+        // Sixel characters per palette color across this band of six rows
+        let mut layers: BTreeMap<u32, Vec<u8>> = BTreeMap::new();
+        for y in band..(band + 6).min(height) {
+            for x in 0..width {
+                let [r, g, b, a] = image.get_pixel(x, y).0;
+                if a < 128 {
+                    continue;
+                }
+                let color = palette_index(r, g, b);
+                let layer = layers
+                    .entry(color)
+                    .or_insert_with(|| vec![0; width as usize]);
+                layer[x as usize] |= 1 << (y - band);
+            }
+        }
+
+        for (&color, layer) in &layers {
+            if !defined[color as usize] {
+                defined[color as usize] = true;
+                let level = |c: u32| c * 100 / (SIXEL_LEVELS - 1);
+                let _ = write!(
+                    out,
+                    "#{color};2;{};{};{}",
+                    level(color / (SIXEL_LEVELS * SIXEL_LEVELS)),
+                    level(color / SIXEL_LEVELS % SIXEL_LEVELS),
+                    level(color % SIXEL_LEVELS)
+                );
+            }
+            let _ = write!(out, "#{color}");
+            push_sixel_run(&mut out, layer);
+            out.push('$');
+        }
+        out.push('-');
+    }
+
+    out.push_str("\x1b\\");
+    out
+}
+
+fn palette_index(r: u8, g: u8, b: u8) -> u32 {
+    let level = |c: u8| (u32::from(c) * (SIXEL_LEVELS - 1) + 127) / 255;
+    level(r) * SIXEL_LEVELS * SIXEL_LEVELS + level(g) * SIXEL_LEVELS + level(b)
+}
+
+/// Append sixel characters, run-length encoding repeats
+fn push_sixel_run(out: &mut String, bits: &[u8]) {
+    let mut rest = bits;
+    while let Some(&first) = rest.first() {
+        let run = rest.iter().take_while(|&&b| b == first).count();
+        let c = char::from(b'?' + first);
+        if run > 3 {
+            let _ = write!(out, "!{run}{c}");
+        } else {
+            out.extend(std::iter::repeat_n(c, run));
+        }
+        rest = &rest[run..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn png_of(width: u32, height: u32, pixel: impl Fn(u32, u32) -> Rgba<u8>) -> Vec<u8> {
+        png(&RgbaImage::from_fn(width, height, pixel)).unwrap()
+    }
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: Vec<(String, String)> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.iter().find(|(k, _)| k == name).map(|(_, v)| v.clone())
+    }
+
+    #[test]
+    fn test_detect_protocol() {
+        let detect = |vars: &[(&str, &str)]| GraphicsProtocol::detect(env(vars));
+
+        assert_eq!(
+            detect(&[("TERM", "xterm-kitty")]),
+            Some(GraphicsProtocol::Kitty)
+        );
+        assert_eq!(
+            detect(&[("KITTY_WINDOW_ID", "1"), ("TERM", "xterm-256color")]),
+            Some(GraphicsProtocol::Kitty)
+        );
+        assert_eq!(
+            detect(&[("TERM_PROGRAM", "iTerm.app")]),
+            Some(GraphicsProtocol::Iterm2)
+        );
+        assert_eq!(detect(&[("TERM", "foot")]), Some(GraphicsProtocol::Sixel));
+        assert_eq!(detect(&[("TERM", "xterm-256color")]), None);
+        assert_eq!(
+            detect(&[("TERM", "xterm-kitty"), ("TMUX", "/tmp/tmux")]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_select_honors_setting() {
+        assert_eq!(GraphicsProtocol::select(ImagePreviews::Off), None);
+        assert_eq!(
+            GraphicsProtocol::select(ImagePreviews::Sixel),
+            Some(GraphicsProtocol::Sixel)
+        );
+    }
+
+    #[test]
+    fn test_encode_kitty_chunks_payload() {
+        // Noise compresses badly, forcing several chunks
+        let data = png_of(64, 64, |x, y| {
+            let v = (x * 7919 + y * 104_729) as u8;
+            Rgba([v, v.wrapping_mul(3), v.wrapping_mul(7), 255])
+        });
+        let out = GraphicsProtocol::Kitty
+            .encode(&data, 10, 5, (8, 16))
+            .unwrap();
+
+        let escapes: Vec<&str> = out.split_terminator("\x1b\\").collect();
+        assert!(escapes.len() > 1);
+        assert!(escapes[0].starts_with("\x1b_Ga=T,f=100,C=1,q=2,m=1;"));
+        assert!(
+            escapes[1..escapes.len() - 1]
+                .iter()
+                .all(|e| e.starts_with("\x1b_Gm=1;"))
+        );
+        assert!(escapes.last().unwrap().starts_with("\x1b_Gm=0;"));
+
+        let payload: String = escapes
+            .iter()
+            .map(|e| e.split_once(';').unwrap().1)
+            .collect();
+        let decoded = image::load_from_memory(&BASE64.decode(payload).unwrap()).unwrap();
+        // Fits in the 80 × 80 pixels available, so it is not enlarged
+        assert_eq!((decoded.width(), decoded.height()), (64, 64));
+    }
+
+    #[test]
+    fn test_encode_iterm2_scales_to_fit() {
+        let data = png_of(400, 100, |_, _| Rgba([0, 0, 255, 255]));
+        let out = GraphicsProtocol::Iterm2
+            .encode(&data, 10, 10, (10, 20))
+            .unwrap();
+        assert!(out.starts_with("\x1b]1337;File=inline=1;size="));
+        assert!(out.contains(";width=100px;height=25px;"));
+        assert!(out.ends_with('\x07'));
+    }
+
+    #[test]
+    fn test_encode_sixel() {
+        let data = png_of(5, 2, |x, _| {
+            if x == 4 {
+                Rgba([0, 0, 0, 0])
+            } else {
+                Rgba([255, 0, 0, 255])
+            }
+        });
+        let out = GraphicsProtocol::Sixel.encode(&data, 1, 1, (5, 6)).unwrap();
+        // Four red columns with the top two rows set, then an unpainted column
+        assert_eq!(out, "\x1bPq\"1;1;5;2#180;2;100;0;0#180!4B?$-\x1b\\");
+    }
+
+    #[test]
+    fn test_encode_rejects_garbage() {
+        assert!(
+            GraphicsProtocol::Sixel
+                .encode(b"not an image", 10, 10, (8, 16))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_palette_index() {
+        assert_eq!(palette_index(0, 0, 0), 0);
+        assert_eq!(palette_index(255, 255, 255), 215);
+        assert_eq!(palette_index(0, 255, 0), 30);
+    }
+}
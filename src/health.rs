@@ -0,0 +1,212 @@
+use crate::database::Database;
+use crate::display::relative_date;
+use crate::error::ClioError;
+use chrono::{DateTime, Duration, Utc};
+use std::sync::{Arc, Mutex};
+
+/// Default longest time since the last successful pull before clio is unhealthy
+pub const DEFAULT_MAX_PULL_AGE_HOURS: u32 = 24;
+
+/// Outcome of one health check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    /// What was checked, such as "storage"
+    pub name: &'static str,
+    pub healthy: bool,
+    pub detail: String,
+}
+
+/// Whether clio is working, for `clio health` and uptime monitors
+///
+/// Storage must be reachable and some source must have been fetched
+/// successfully within the allowed age. Under `clio watch`, its poll loop
+/// must also be running.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthReport {
+    pub checks: Vec<Check>,
+}
+
+impl HealthReport {
+    /// Run every check against `database`, as of `now`
+    pub fn check(
+        database: Result<Database, ClioError>,
+        max_pull_age: Duration,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let last_pull = database.and_then(|database| {
+            database.verify_connection()?;
+            database.last_successful_fetch()
+        });
+        Self::from_last_pull(last_pull, max_pull_age, now)
+    }
+
+    /// Also check that the loop beating `heartbeat` is still running
+    pub fn with_scheduler(mut self, heartbeat: &Heartbeat, now: DateTime<Utc>) -> Self {
+        self.checks.push(heartbeat.check(now));
+        self
+    }
+
+    /// Whether every check passed
+    pub fn healthy(&self) -> bool {
+        self.checks.iter().all(|check| check.healthy)
+    }
+
+    fn from_last_pull(
+        last_pull: Result<Option<DateTime<Utc>>, ClioError>,
+        max_pull_age: Duration,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let (storage, pull) = match last_pull {
+            Err(e) => (
+                Check {
+                    name: "storage",
+                    healthy: false,
+                    detail: e.to_string(),
+                },
+                Check {
+                    name: "last pull",
+                    healthy: false,
+                    detail: "unknown while storage is unreachable".to_string(),
+                },
+            ),
+            Ok(last_pull) => (
+                Check {
+                    name: "storage",
+                    healthy: true,
+                    detail: "reachable".to_string(),
+                },
+                pull_check(last_pull, max_pull_age, now),
+            ),
+        };
+        Self {
+            checks: vec![storage, pull],
+        }
+    }
+}
+
+/// When a polling loop last finished a round, shared with its health check
+///
+/// A loop stuck on a fetch or stopped by a panic stops beating, which the
+/// check reports once `max_silence` has passed.
+#[derive(Debug, Clone)]
+pub struct Heartbeat {
+    last: Arc<Mutex<DateTime<Utc>>>,
+    max_silence: Duration,
+}
+
+impl Heartbeat {
+    /// A heartbeat that last beat at `now`
+    pub fn new(max_silence: Duration, now: DateTime<Utc>) -> Self {
+        Self {
+            last: Arc::new(Mutex::new(now)),
+            max_silence,
+        }
+    }
+
+    /// Record that the loop finished a round at `now`
+    pub fn beat(&self, now: DateTime<Utc>) {
+        *self.last.lock().unwrap_or_else(|e| e.into_inner()) = now;
+    }
+
+    fn check(&self, now: DateTime<Utc>) -> Check {
+        let last = *self.last.lock().unwrap_or_else(|e| e.into_inner());
+        let healthy = now - last <= self.max_silence;
+        let mut detail = format!("last poll finished {}", relative_date(last, now));
+        if !healthy {
+            detail.push_str(&format!(
+                ", over the {}-minute limit",
+                self.max_silence.num_minutes()
+            ));
+        }
+        Check {
+            name: "scheduler",
+            healthy,
+            detail,
+        }
+    }
+}
+
+fn pull_check(last_pull: Option<DateTime<Utc>>, max_age: Duration, now: DateTime<Utc>) -> Check {
+    let (healthy, detail) = match last_pull {
+        None => (false, "no successful pull recorded".to_string()),
+        Some(at) => {
+            let healthy = now - at <= max_age;
+            let mut detail = format!("succeeded {}", relative_date(at, now));
+            if !healthy {
+                detail.push_str(&format!(", over the {}-hour limit", max_age.num_hours()));
+            }
+            (healthy, detail)
+        }
+    };
+    Check {
+        name: "last pull",
+        healthy,
+        detail,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_healthy() {
+        let now = Utc::now();
+        let report = HealthReport::from_last_pull(
+            Ok(Some(now - Duration::hours(2))),
+            Duration::hours(24),
+            now,
+        );
+        assert!(report.healthy());
+        assert_eq!(report.checks[0].detail, "reachable");
+        assert_eq!(report.checks[1].detail, "succeeded 2 hours ago");
+    }
+
+    #[test]
+    fn test_stale_or_missing_pull() {
+        let now = Utc::now();
+        let report = HealthReport::from_last_pull(
+            Ok(Some(now - Duration::hours(30))),
+            Duration::hours(24),
+            now,
+        );
+        assert!(!report.healthy());
+        assert!(report.checks[0].healthy);
+        assert_eq!(
+            report.checks[1].detail,
+            "succeeded 1 day ago, over the 24-hour limit"
+        );
+
+        let report = HealthReport::from_last_pull(Ok(None), Duration::hours(24), now);
+        assert!(!report.healthy());
+        assert_eq!(report.checks[1].detail, "no successful pull recorded");
+    }
+
+    #[test]
+    fn test_scheduler() {
+        let start = Utc::now() - Duration::hours(1);
+        let heartbeat = Heartbeat::new(Duration::minutes(10), start);
+        let healthy = HealthReport::from_last_pull(Ok(Some(start)), Duration::hours(24), start)
+            .with_scheduler(&heartbeat, start + Duration::minutes(5));
+        assert!(healthy.healthy());
+        assert_eq!(healthy.checks[2].name, "scheduler");
+
+        let now = start + Duration::minutes(30);
+        let stuck = HealthReport::from_last_pull(Ok(Some(start)), Duration::hours(24), now)
+            .with_scheduler(&heartbeat, now);
+        assert!(!stuck.healthy());
+        assert!(stuck.checks[2].detail.ends_with("over the 10-minute limit"));
+
+        heartbeat.beat(now);
+        assert!(heartbeat.check(now + Duration::minutes(1)).healthy);
+    }
+
+    #[test]
+    fn test_storage_unreachable() {
+        let error = ClioError::Database("connection refused".to_string());
+        let report = HealthReport::from_last_pull(Err(error), Duration::hours(24), Utc::now());
+        assert!(!report.checks[0].healthy);
+        assert!(report.checks[0].detail.contains("connection refused"));
+        assert!(!report.checks[1].healthy);
+    }
+}
@@ -0,0 +1,644 @@
+use crate::error::{ClioError, ErrorKind};
+use crate::trace::Trace;
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use reqwest::{Client, ClientBuilder, RequestBuilder, redirect};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use url::{Host, Url};
+
+/// Maximum number of redirects followed for one request, unless configured
+/// otherwise with `max_redirects`
+pub const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+/// Maximum number of simultaneous requests to one host from a [`FeedClient`]
+///
+/// Feeds hosted on the same CDN then share a few warm connections instead of
+/// each opening its own.
+pub const MAX_CONNECTIONS_PER_HOST: usize = 6;
+
+/// How long an unused connection is kept open for the next request
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Shared guard that keeps outbound requests away from private networks
+///
+/// Requests whose host is, or resolves to, a loopback, link-local, or private
+/// (RFC 1918 / unique local) address are rejected unless private networks are
+/// explicitly allowed. The check runs both on the initial URL and on every
+/// DNS resolution and redirect performed by the HTTP client.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RequestGuard {
+    allow_private_networks: bool,
+}
+
+impl RequestGuard {
+    /// Create a guard, optionally permitting requests to private networks
+    pub fn new(allow_private_networks: bool) -> Self {
+        Self {
+            allow_private_networks,
+        }
+    }
+
+    /// Apply the guard to an HTTP client builder
+    pub fn apply(&self, builder: ClientBuilder) -> ClientBuilder {
+        let redirects = Arc::new(RedirectLimits::new(DEFAULT_MAX_REDIRECTS));
+        self.apply_counting(builder, None, redirects)
+    }
+
+    /// Apply the guard, counting every DNS resolution in `lookups`
+    fn apply_counting(
+        &self,
+        builder: ClientBuilder,
+        lookups: Option<Arc<AtomicU64>>,
+        redirects: Arc<RedirectLimits>,
+    ) -> ClientBuilder {
+        let guard = *self;
+        builder
+            .dns_resolver(Arc::new(GuardedResolver { guard, lookups }))
+            .redirect(redirect_policy(Some(guard), redirects))
+    }
+
+    /// Reject URLs whose host is a literal private network address
+    ///
+    /// Hostnames are checked when they are resolved by a guarded client.
+    pub fn check_url(&self, url: &Url) -> Result<(), ClioError> {
+        let ip = match url.host() {
+            Some(Host::Ipv4(ip)) => IpAddr::V4(ip),
+            Some(Host::Ipv6(ip)) => IpAddr::V6(ip),
+            _ => return Ok(()),
+        };
+
+        self.check_ip(url.host_str().unwrap_or_default(), ip)
+    }
+
+    fn check_ip(&self, host: &str, ip: IpAddr) -> Result<(), ClioError> {
+        if self.allow_private_networks || !is_private_address(ip) {
+            return Ok(());
+        }
+
+        Err(ClioError::Network(format!(
+            "Blocked request to {host}: {ip} is a private network address \
+             (set allow_private_networks = true to permit)"
+        )))
+    }
+}
+
+/// Describe a failed request to `address`, telling redirect loops, timeouts,
+/// DNS and TLS failures apart from other network errors
+///
+/// The causes are included, since reqwest only names the URL that failed
+/// and leaves out whether it was DNS, TLS, or the connection.
+pub fn request_error(address: &str, err: reqwest::Error) -> ClioError {
+    describe_request_error(address, format!("Failed to pull feed from {address}"), err)
+}
+
+/// Describe a failed request as [`request_error`] does, after `context`
+/// rather than the feed address
+pub fn request_failed(context: impl std::fmt::Display, err: reqwest::Error) -> ClioError {
+    let address = err.url().map(|url| url.to_string()).unwrap_or_default();
+    describe_request_error(&address, context.to_string(), err)
+}
+
+/// Whether an address is loopback, link-local, private, or otherwise non-public
+pub fn is_private_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+        }
+        IpAddr::V6(ip) => {
+            if let Some(mapped) = ip.to_ipv4_mapped() {
+                return is_private_address(IpAddr::V4(mapped));
+            }
+            let first = ip.segments()[0];
+            ip.is_loopback()
+                || ip.is_unspecified()
+                || (first & 0xfe00) == 0xfc00 // unique local fc00::/7
+                || (first & 0xffc0) == 0xfe80 // link-local fe80::/10
+        }
+    }
+}
+
+fn describe_request_error(address: &str, context: String, err: reqwest::Error) -> ClioError {
+    let mut kind = err.is_timeout().then_some(ErrorKind::Timeout);
+    let mut message = err.to_string();
+    let mut source = std::error::Error::source(&err);
+    while let Some(e) = source {
+        if let Some(RedirectRefused::Loop(url)) = e.downcast_ref() {
+            return ClioError::RedirectLoop(format!("{address} redirects back to {url}"));
+        }
+        if e.is::<LookupFailed>() {
+            kind = kind.or(Some(ErrorKind::Dns));
+        }
+        if e.is::<native_tls::Error>() {
+            kind = kind.or(Some(ErrorKind::Tls));
+        }
+        let cause = e.to_string();
+        if !message.contains(&cause) {
+            message = format!("{message}: {cause}");
+        }
+        source = e.source();
+    }
+    let message = format!("{context}: {message}");
+    match kind {
+        Some(kind) => ClioError::request(kind, message),
+        None => ClioError::Network(message),
+    }
+}
+
+/// HTTP client shared by every feed in a pull, so connections are reused
+///
+/// Servers that support HTTP/2 are spoken to over it, which lets requests to
+/// the same host share one connection. Idle connections stay open between
+/// requests, and each host gets at most [`MAX_CONNECTIONS_PER_HOST`] requests
+/// at a time, so a pull with many feeds on one CDN queues for warm
+/// connections instead of paying for a TLS handshake per feed.
+#[derive(Debug, Clone)]
+pub struct FeedClient {
+    client: Client,
+    guard: Option<RequestGuard>,
+    requests: Arc<AtomicU64>,
+    /// DNS lookups, one per new connection to a named host
+    connections: Arc<AtomicU64>,
+    hosts: Arc<Mutex<HashMap<String, Arc<Semaphore>>>>,
+    /// Records every feed request, when tracing
+    trace: Option<Arc<Trace>>,
+    redirects: Arc<RedirectLimits>,
+}
+
+/// Requests sent by a [`FeedClient`] and connections opened for them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConnectionStats {
+    pub requests: u64,
+    /// New connections, each with its own handshake; requests to IP
+    /// addresses are not counted
+    pub connections: u64,
+}
+
+impl FeedClient {
+    /// A shared client whose requests are restricted by `guard`
+    pub fn new(guard: RequestGuard) -> Self {
+        Self::build(Some(guard))
+    }
+
+    /// Record every feed request made through this client in `trace`
+    pub fn with_trace(mut self, trace: Arc<Trace>) -> Self {
+        self.trace = Some(trace);
+        self
+    }
+
+    /// Follow at most `max` redirects for requests to `url`
+    pub fn limit_redirects(&self, url: &Url, max: usize) {
+        self.redirects
+            .feeds
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(url.to_string(), max);
+    }
+
+    /// Start a GET request, counting it in the connection stats
+    pub fn get(&self, url: &str) -> RequestBuilder {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.client.get(url)
+    }
+
+    /// Start a POST request, counting it in the connection stats
+    pub fn post(&self, url: &str) -> RequestBuilder {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.client.post(url)
+    }
+
+    /// The guard restricting this client's requests, if any
+    pub fn guard(&self) -> Option<&RequestGuard> {
+        self.guard.as_ref()
+    }
+
+    /// The trace recording this client's requests, if any
+    pub fn trace(&self) -> Option<&Arc<Trace>> {
+        self.trace.as_ref()
+    }
+
+    /// Wait for a free connection slot for `url`'s host
+    ///
+    /// Hold the permit until the response body has been read.
+    pub async fn slot(&self, url: &Url) -> OwnedSemaphorePermit {
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        let semaphore = self
+            .hosts
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(host)
+            .or_insert_with(|| Arc::new(Semaphore::new(MAX_CONNECTIONS_PER_HOST)))
+            .clone();
+        semaphore
+            .acquire_owned()
+            .await
+            .expect("connection slots are never closed")
+    }
+
+    /// Requests and new connections so far
+    pub fn stats(&self) -> ConnectionStats {
+        ConnectionStats {
+            requests: self.requests.load(Ordering::Relaxed),
+            connections: self.connections.load(Ordering::Relaxed),
+        }
+    }
+
+    fn build(guard: Option<RequestGuard>) -> Self {
+        let connections = Arc::new(AtomicU64::new(0));
+        let builder = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .user_agent("Clio/0.1.0")
+            .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+            .pool_max_idle_per_host(MAX_CONNECTIONS_PER_HOST)
+            .tcp_keepalive(POOL_IDLE_TIMEOUT)
+            .http2_adaptive_window(true);
+        let redirects = Arc::new(RedirectLimits::new(DEFAULT_MAX_REDIRECTS));
+        let builder = match &guard {
+            Some(guard) => {
+                guard.apply_counting(builder, Some(connections.clone()), redirects.clone())
+            }
+            None => builder.redirect(redirect_policy(None, redirects.clone())),
+        };
+
+        Self {
+            client: builder.build().unwrap_or_default(),
+            guard,
+            requests: Arc::new(AtomicU64::new(0)),
+            connections,
+            hosts: Arc::new(Mutex::new(HashMap::new())),
+            trace: None,
+            redirects,
+        }
+    }
+}
+
+/// An unguarded client, for feeds that may be on private networks
+impl Default for FeedClient {
+    fn default() -> Self {
+        Self::build(None)
+    }
+}
+
+impl ConnectionStats {
+    /// Requests that went over a connection opened for an earlier one
+    pub fn reused(&self) -> u64 {
+        self.requests.saturating_sub(self.connections)
+    }
+
+    /// Activity between `earlier` and these stats
+    pub fn since(&self, earlier: &ConnectionStats) -> ConnectionStats {
+        ConnectionStats {
+            requests: self.requests.saturating_sub(earlier.requests),
+            connections: self.connections.saturating_sub(earlier.connections),
+        }
+    }
+}
+
+/// How many redirects a client follows, by the original URL of a request
+#[derive(Debug)]
+struct RedirectLimits {
+    default: usize,
+    /// Limits configured for single feeds
+    feeds: Mutex<HashMap<String, usize>>,
+}
+
+impl RedirectLimits {
+    fn new(default: usize) -> Self {
+        Self {
+            default,
+            feeds: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn max_for(&self, url: &Url) -> usize {
+        let feeds = self.feeds.lock().unwrap_or_else(|e| e.into_inner());
+        match feeds.get(url.as_str()) {
+            Some(&max) => max,
+            None => self.default,
+        }
+    }
+}
+
+/// A host name that could not be resolved, told apart from addresses the
+/// guard refused
+#[derive(Debug, thiserror::Error)]
+#[error(transparent)]
+struct LookupFailed(std::io::Error);
+
+/// Why a client refused to follow a redirect
+#[derive(Debug, thiserror::Error)]
+enum RedirectRefused {
+    #[error("redirect loop back to {0}")]
+    Loop(Url),
+    #[error("more than {0} redirects")]
+    TooMany(usize),
+}
+
+/// Follow redirects up to the limit for the request, refusing loops and,
+/// with a guard, private addresses
+fn redirect_policy(guard: Option<RequestGuard>, limits: Arc<RedirectLimits>) -> redirect::Policy {
+    redirect::Policy::custom(move |attempt| {
+        let previous = attempt.previous();
+        let max = limits.max_for(&previous[0]);
+        if previous.contains(attempt.url()) {
+            let url = attempt.url().clone();
+            attempt.error(RedirectRefused::Loop(url))
+        } else if previous.len() > max {
+            attempt.error(RedirectRefused::TooMany(max))
+        } else if let Some(Err(e)) = guard.map(|guard| guard.check_url(attempt.url())) {
+            attempt.error(e)
+        } else {
+            attempt.follow()
+        }
+    })
+}
+
+/// DNS resolver that drops private addresses from resolution results
+struct GuardedResolver {
+    guard: RequestGuard,
+    /// Counts lookups, which the HTTP client only makes to open a connection
+    lookups: Option<Arc<AtomicU64>>,
+}
+
+impl Resolve for GuardedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let guard = self.guard;
+        if let Some(lookups) = &self.lookups {
+            lookups.fetch_add(1, Ordering::Relaxed);
+        }
+        Box::pin(async move {
+            let host = name.as_str();
+            let resolved: Vec<SocketAddr> = tokio::net::lookup_host((host, 0))
+                .await
+                .map_err(LookupFailed)?
+                .collect();
+
+            let mut last_error = None;
+            let mut allowed = Vec::with_capacity(resolved.len());
+            for addr in resolved {
+                match guard.check_ip(host, addr.ip()) {
+                    Ok(()) => allowed.push(addr),
+                    Err(e) => last_error = Some(e),
+                }
+            }
+
+            match last_error {
+                Some(e) if allowed.is_empty() => Err(e.into()),
+                _ => Ok(Box::new(allowed.into_iter()) as Addrs),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::Client;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_private_addresses() {
+        for addr in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "0.0.0.0",
+            "::1",
+            "fe80::1",
+            "fd00::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(is_private_address(ip(addr)), "{addr} should be private");
+        }
+    }
+
+    #[test]
+    fn test_public_addresses() {
+        for addr in ["93.184.216.34", "1.1.1.1", "172.32.0.1", "2606:4700::1111"] {
+            assert!(!is_private_address(ip(addr)), "{addr} should be public");
+        }
+    }
+
+    #[test]
+    fn test_check_url_literal_hosts() {
+        let guard = RequestGuard::default();
+        let blocked = Url::parse("http://169.254.169.254/latest/meta-data").unwrap();
+        let public = Url::parse("https://93.184.216.34/feed").unwrap();
+        let named = Url::parse("https://example.com/feed").unwrap();
+
+        let err = guard.check_url(&blocked).unwrap_err();
+        assert!(matches!(err, ClioError::Network(_)));
+        assert!(err.to_string().contains("allow_private_networks"));
+        assert!(guard.check_url(&public).is_ok());
+        assert!(guard.check_url(&named).is_ok());
+        assert!(RequestGuard::new(true).check_url(&blocked).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_guarded_client_blocks_resolved_loopback() {
+        let mut server = mockito::Server::new_async().await;
+        let _m = server.mock("GET", "/feed").with_status(200).create();
+        let port = server.socket_address().port();
+        let url = format!("http://localhost:{port}/feed");
+
+        let blocked = RequestGuard::default()
+            .apply(Client::builder())
+            .build()
+            .unwrap();
+        let err = blocked.get(&url).send().await.unwrap_err();
+        assert!(format!("{err:?}").contains("Blocked request to localhost"));
+
+        let allowed = RequestGuard::new(true)
+            .apply(Client::builder())
+            .build()
+            .unwrap();
+        let response = allowed.get(&url).send().await.unwrap();
+        assert!(response.status().is_success());
+    }
+
+    #[tokio::test]
+    async fn test_guarded_client_blocks_redirect_to_private_address() {
+        let mut server = mockito::Server::new_async().await;
+        let _m = server
+            .mock("GET", "/redirect")
+            .with_status(302)
+            .with_header("location", "http://127.0.0.1:1/internal")
+            .create();
+        let url = format!("{}/redirect", server.url());
+
+        // IP literals bypass the resolver, so only the redirect policy applies here
+        let client = RequestGuard::default()
+            .apply(Client::builder())
+            .build()
+            .unwrap();
+        let err = client.get(&url).send().await.unwrap_err();
+        assert!(err.is_redirect());
+    }
+
+    #[tokio::test]
+    async fn test_feed_client_redirect_limits_and_loops() {
+        let mut server = mockito::Server::new_async().await;
+        let _hop = server
+            .mock("GET", "/old")
+            .with_status(301)
+            .with_header("location", "/new")
+            .create();
+        let _feed = server.mock("GET", "/new").with_status(200).create();
+        let _a = server
+            .mock("GET", "/a")
+            .with_status(302)
+            .with_header("location", "/b")
+            .create();
+        let _b = server
+            .mock("GET", "/b")
+            .with_status(302)
+            .with_header("location", "/a")
+            .create();
+
+        let client = FeedClient::default();
+        let old = Url::parse(&format!("{}/old", server.url())).unwrap();
+        let response = client.get(old.as_str()).send().await.unwrap();
+        assert_eq!(response.url().path(), "/new");
+
+        client.limit_redirects(&old, 0);
+        let err = client.get(old.as_str()).send().await.unwrap_err();
+        assert!(err.is_redirect());
+        let err = request_error(old.as_str(), err);
+        assert!(matches!(err, ClioError::Network(_)));
+        assert!(err.to_string().contains("more than 0 redirects"));
+
+        let looping = format!("{}/a", server.url());
+        let err = client.get(&looping).send().await.unwrap_err();
+        let err = request_error(&looping, err);
+        assert!(matches!(err, ClioError::RedirectLoop(_)));
+        assert_eq!(
+            err.to_string(),
+            format!("Redirect loop: {looping} redirects back to {looping}")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_request_error_kinds() {
+        let client = FeedClient::new(RequestGuard::new(true));
+        let kind = |address: String| {
+            let client = client.clone();
+            async move {
+                let err = client
+                    .get(&address)
+                    .timeout(Duration::from_millis(500))
+                    .send()
+                    .await
+                    .unwrap_err();
+                request_error(&address, err).kind()
+            }
+        };
+
+        assert_eq!(
+            kind("http://nope.invalid/feed".to_string()).await,
+            ErrorKind::Dns
+        );
+
+        // Accepts connections but never answers, TLS handshakes included
+        let silent = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = silent.local_addr().unwrap().port();
+        assert_eq!(
+            kind(format!("http://127.0.0.1:{port}/feed")).await,
+            ErrorKind::Timeout
+        );
+
+        let mut server = mockito::Server::new_async().await;
+        let plain = server.host_with_port();
+        let _m = server.mock("GET", "/feed").with_status(200).create();
+        assert_eq!(kind(format!("https://{plain}/feed")).await, ErrorKind::Tls);
+
+        drop(silent);
+        assert_eq!(
+            kind(format!("http://127.0.0.1:{port}/feed")).await,
+            ErrorKind::Network
+        );
+    }
+
+    /// Serve "ok" over keep-alive HTTP/1.1, counting accepted connections
+    async fn keep_alive_server() -> (u16, Arc<AtomicU64>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let accepted = Arc::new(AtomicU64::new(0));
+        let counter = accepted.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::Relaxed);
+                tokio::spawn(async move {
+                    let mut request = Vec::new();
+                    let mut buf = [0; 1024];
+                    while let Ok(n) = stream.read(&mut buf).await {
+                        if n == 0 {
+                            break;
+                        }
+                        request.extend_from_slice(&buf[..n]);
+                        while let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                            request.drain(..end + 4);
+                            let response = b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok";
+                            if stream.write_all(response).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                });
+            }
+        });
+        (port, accepted)
+    }
+
+    #[tokio::test]
+    async fn test_feed_client_reuses_connections() {
+        let (port, accepted) = keep_alive_server().await;
+        let url = format!("http://localhost:{port}/feed");
+
+        let client = FeedClient::new(RequestGuard::new(true));
+        let before = client.stats();
+        for _ in 0..3 {
+            let response = client.get(&url).send().await.unwrap();
+            assert_eq!(response.bytes().await.unwrap(), "ok");
+        }
+
+        let stats = client.stats().since(&before);
+        assert_eq!(stats.requests, 3);
+        assert_eq!(stats.connections, accepted.load(Ordering::Relaxed));
+        assert_eq!(stats.connections, 1);
+        assert_eq!(stats.reused(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_feed_client_limits_connections_per_host() {
+        let client = FeedClient::default();
+        let url = Url::parse("https://cdn.example.com/a.xml").unwrap();
+        let other = Url::parse("https://other.example.com/b.xml").unwrap();
+
+        let mut permits = Vec::new();
+        for _ in 0..MAX_CONNECTIONS_PER_HOST {
+            permits.push(client.slot(&url).await);
+        }
+        let waiting = tokio::time::timeout(Duration::from_millis(50), client.slot(&url));
+        assert!(waiting.await.is_err());
+        // Other hosts are not held up
+        let _ = client.slot(&other).await;
+
+        permits.pop();
+        let _ = client.slot(&url).await;
+    }
+}
@@ -0,0 +1,227 @@
+use crate::source::Item;
+use chrono::{DateTime, NaiveDate, Utc};
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Maximum line length in octets before folding (RFC 5545 §3.1)
+const MAX_LINE_OCTETS: usize = 75;
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+static ISO_DATE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(\d{4})-(\d{2})-(\d{2})\b").expect("valid date pattern"));
+
+static MONTH_DAY_YEAR: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b(jan|feb|mar|apr|may|jun|jul|aug|sep|oct|nov|dec)[a-z]*\.?\s+(\d{1,2})(?:st|nd|rd|th)?,?\s+(\d{4})\b")
+        .expect("valid date pattern")
+});
+
+static DAY_MONTH_YEAR: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b(\d{1,2})(?:st|nd|rd|th)?\s+(jan|feb|mar|apr|may|jun|jul|aug|sep|oct|nov|dec)[a-z]*\.?,?\s+(\d{4})\b")
+        .expect("valid date pattern")
+});
+
+/// When an upcoming item takes place
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventStart {
+    /// A publication date set in the future
+    At(DateTime<Utc>),
+    /// A date mentioned in the title or summary; exported as an all-day event
+    On(NaiveDate),
+}
+
+/// Find when an item takes place, if that is after `now`
+///
+/// Event and release feeds either date items in the future or mention the
+/// date in the text; a future publication date wins over dates in the text.
+pub fn event_start(item: &Item, now: DateTime<Utc>) -> Option<EventStart> {
+    if let Some(pub_date) = item.pub_date
+        && pub_date > now
+    {
+        return Some(EventStart::At(pub_date));
+    }
+
+    let today = now.date_naive();
+    [Some(item.title.as_str()), item.summary.as_deref()]
+        .into_iter()
+        .flatten()
+        .flat_map(mentioned_dates)
+        .find(|date| *date >= today)
+        .map(EventStart::On)
+}
+
+/// Render the upcoming items as an iCalendar document
+pub fn to_ics(items: &[Item], now: DateTime<Utc>) -> String {
+    let stamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//clio//clio export//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "X-WR-CALNAME:clio".to_string(),
+    ];
+
+    for item in items {
+        let Some(start) = event_start(item, now) else {
+            continue;
+        };
+
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}", escape_text(&item.link)));
+        lines.push(format!("DTSTAMP:{stamp}"));
+        lines.push(match start {
+            EventStart::At(at) => format!("DTSTART:{}", at.format("%Y%m%dT%H%M%SZ")),
+            EventStart::On(date) => format!("DTSTART;VALUE=DATE:{}", date.format("%Y%m%d")),
+        });
+        lines.push(format!("SUMMARY:{}", escape_text(&item.title)));
+        lines.push(format!("URL:{}", item.link));
+        let description = match &item.summary {
+            Some(summary) => format!("{}\n\n{summary}", item.source_name),
+            None => item.source_name.clone(),
+        };
+        lines.push(format!("DESCRIPTION:{}", escape_text(&description)));
+        lines.push("END:VEVENT".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+fn mentioned_dates(text: &str) -> impl Iterator<Item = NaiveDate> + '_ {
+    let iso = ISO_DATE.captures_iter(text).filter_map(|c| {
+        NaiveDate::from_ymd_opt(c[1].parse().ok()?, c[2].parse().ok()?, c[3].parse().ok()?)
+    });
+    let month_day = MONTH_DAY_YEAR
+        .captures_iter(text)
+        .filter_map(|c| date_from_parts(&c[3], &c[1], &c[2]));
+    let day_month = DAY_MONTH_YEAR
+        .captures_iter(text)
+        .filter_map(|c| date_from_parts(&c[3], &c[2], &c[1]));
+
+    iso.chain(month_day).chain(day_month)
+}
+
+fn date_from_parts(year: &str, month: &str, day: &str) -> Option<NaiveDate> {
+    let month = MONTHS.iter().position(|m| month.eq_ignore_ascii_case(m))?;
+    NaiveDate::from_ymd_opt(year.parse().ok()?, month as u32 + 1, day.parse().ok()?)
+}
+
+/// Escape a TEXT value (RFC 5545 §3.3.11)
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Fold a content line at 75 octets and terminate it with CRLF
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 8);
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 3, 1, 12, 0, 0).unwrap()
+    }
+
+    fn item(title: &str, summary: Option<&str>, pub_date: Option<DateTime<Utc>>) -> Item {
+        Item {
+            source_name: "Events".to_string(),
+            title: title.to_string(),
+            link: format!("https://example.com/{}", title.len()),
+            summary: summary.map(str::to_string),
+            pub_date,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_event_start_from_future_pub_date() {
+        let at = Utc.with_ymd_and_hms(2025, 4, 2, 18, 30, 0).unwrap();
+        let item = item("Meetup on 2025-05-01", None, Some(at));
+        assert_eq!(event_start(&item, now()), Some(EventStart::At(at)));
+    }
+
+    #[test]
+    fn test_event_start_from_text() {
+        let past = Some(Utc.with_ymd_and_hms(2025, 2, 1, 0, 0, 0).unwrap());
+        let date = |y, m, d| Some(EventStart::On(NaiveDate::from_ymd_opt(y, m, d).unwrap()));
+
+        assert_eq!(
+            event_start(&item("Release 2.0 ships 2025-04-15", None, past), now()),
+            date(2025, 4, 15)
+        );
+        assert_eq!(
+            event_start(
+                &item("Conference", Some("Join us on June 10th, 2025"), past),
+                now()
+            ),
+            date(2025, 6, 10)
+        );
+        assert_eq!(
+            event_start(&item("Doors open 3 Sept 2025", None, past), now()),
+            date(2025, 9, 3)
+        );
+        // Past dates and impossible dates are not events
+        assert_eq!(
+            event_start(&item("Recap of 2024-12-01", None, past), now()),
+            None
+        );
+        assert_eq!(
+            event_start(&item("Due 2025-02-30", None, past), now()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_to_ics() {
+        let at = Utc.with_ymd_and_hms(2025, 4, 2, 18, 30, 0).unwrap();
+        let items = vec![
+            item("Launch; party, v2", Some("Line one\nLine two"), Some(at)),
+            item(
+                "Old news",
+                None,
+                Some(Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()),
+            ),
+        ];
+
+        let ics = to_ics(&items, now());
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+        assert!(ics.contains("DTSTART:20250402T183000Z\r\n"));
+        assert!(ics.contains("DTSTAMP:20250301T120000Z\r\n"));
+        assert!(ics.contains("SUMMARY:Launch\\; party\\, v2\r\n"));
+        assert!(ics.contains("DESCRIPTION:Events\\n\\nLine one\\nLine two\r\n"));
+    }
+
+    #[test]
+    fn test_fold_long_lines() {
+        let folded = fold(&format!("SUMMARY:{}", "é".repeat(60)));
+        for line in folded.split("\r\n").filter(|l| !l.is_empty()) {
+            assert!(line.len() <= MAX_LINE_OCTETS);
+        }
+        assert_eq!(
+            folded.replace("\r\n ", ""),
+            format!("SUMMARY:{}\r\n", "é".repeat(60))
+        );
+    }
+}
@@ -0,0 +1,308 @@
+use crate::error::{ClioError, ErrorContext};
+use crate::sanitize::{TextLimits, strip_control};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// Google Reader state tag marking an item as read (used by FreshRSS exports)
+const READ_STATE: &str = "user/-/state/com.google/read";
+
+/// Google Reader state tag marking an item as starred (used by FreshRSS exports)
+const STARRED_STATE: &str = "user/-/state/com.google/starred";
+
+/// Reader applications whose exports can be imported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ImportFormat {
+    /// Miniflux entries JSON, as returned by `GET /v1/entries`
+    Miniflux,
+    /// FreshRSS (Google Reader API) JSON export
+    #[value(name = "freshrss")]
+    FreshRss,
+    /// Newsboat `cache.db`
+    Newsboat,
+}
+
+/// An item and its reading state, as recorded by another reader
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedItem {
+    /// Feed title in the originating reader
+    pub source_name: String,
+    pub title: String,
+    /// URL of the article; used to match items already stored by clio
+    pub link: String,
+    pub pub_date: Option<DateTime<Utc>>,
+    pub is_read: bool,
+    pub is_starred: bool,
+}
+
+/// Read every item with a usable link from a reader export
+///
+/// Titles and feed names are sanitized with the given limits, since exports
+/// are as untrusted as the feeds they were built from.
+pub fn read_export(
+    format: ImportFormat,
+    path: &Path,
+    limits: &TextLimits,
+) -> Result<Vec<ImportedItem>, ClioError> {
+    let items = match format {
+        ImportFormat::Miniflux => parse_miniflux(&read_file(path)?)?,
+        ImportFormat::FreshRss => parse_freshrss(&read_file(path)?)?,
+        ImportFormat::Newsboat => read_newsboat(path)?,
+    };
+
+    Ok(items
+        .into_iter()
+        .map(|item| ImportedItem {
+            source_name: limits.title(&item.source_name),
+            title: limits.title(&item.title),
+            link: strip_control(item.link.trim()),
+            ..item
+        })
+        .filter(|item| is_web_link(&item.link))
+        .collect())
+}
+
+fn read_file(path: &Path) -> Result<String, ClioError> {
+    fs::read_to_string(path).clio_config_err(format!("Failed to read {}", path.display()))
+}
+
+fn is_web_link(link: &str) -> bool {
+    url::Url::parse(link).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+#[derive(Deserialize)]
+struct MinifluxExport {
+    entries: Vec<MinifluxEntry>,
+}
+
+#[derive(Deserialize)]
+struct MinifluxEntry {
+    url: String,
+    #[serde(default)]
+    title: String,
+    status: String,
+    #[serde(default)]
+    starred: bool,
+    published_at: Option<DateTime<Utc>>,
+    feed: Option<MinifluxFeed>,
+}
+
+#[derive(Deserialize)]
+struct MinifluxFeed {
+    title: String,
+}
+
+fn parse_miniflux(json: &str) -> Result<Vec<ImportedItem>, ClioError> {
+    let export: MinifluxExport =
+        serde_json::from_str(json).clio_parse_err("Invalid Miniflux export")?;
+
+    Ok(export
+        .entries
+        .into_iter()
+        .map(|entry| ImportedItem {
+            source_name: entry.feed.map(|feed| feed.title).unwrap_or_default(),
+            title: entry.title,
+            link: entry.url,
+            pub_date: entry.published_at,
+            // Removed entries were read (and dismissed) in Miniflux
+            is_read: entry.status != "unread",
+            is_starred: entry.starred,
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct FreshRssExport {
+    items: Vec<FreshRssItem>,
+}
+
+#[derive(Deserialize)]
+struct FreshRssItem {
+    #[serde(default)]
+    title: String,
+    published: Option<i64>,
+    #[serde(default)]
+    alternate: Vec<FreshRssLink>,
+    #[serde(default)]
+    categories: Vec<String>,
+    origin: Option<FreshRssOrigin>,
+}
+
+#[derive(Deserialize)]
+struct FreshRssLink {
+    href: String,
+}
+
+#[derive(Deserialize)]
+struct FreshRssOrigin {
+    #[serde(default)]
+    title: String,
+}
+
+fn parse_freshrss(json: &str) -> Result<Vec<ImportedItem>, ClioError> {
+    let export: FreshRssExport =
+        serde_json::from_str(json).clio_parse_err("Invalid FreshRSS export")?;
+
+    Ok(export
+        .items
+        .into_iter()
+        .filter_map(|item| {
+            let link = item.alternate.into_iter().next()?.href;
+            let has_state = |state: &str| item.categories.iter().any(|c| c == state);
+            Some(ImportedItem {
+                source_name: item.origin.map(|origin| origin.title).unwrap_or_default(),
+                is_read: has_state(READ_STATE),
+                is_starred: has_state(STARRED_STATE),
+                title: item.title,
+                link,
+                pub_date: item
+                    .published
+                    .and_then(|secs| DateTime::from_timestamp(secs, 0)),
+            })
+        })
+        .collect())
+}
+
+/// Read items from a Newsboat cache database
+///
+/// Newsboat has no star; any item carrying flags is treated as starred.
+fn read_newsboat(path: &Path) -> Result<Vec<ImportedItem>, ClioError> {
+    use rusqlite::{Connection, OpenFlags};
+
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .clio_parse_err(format!("Failed to open Newsboat cache {}", path.display()))?;
+
+    let mut statement = conn
+        .prepare(
+            "SELECT COALESCE(f.title, i.feedurl), i.title, i.url, i.pubDate, i.unread, i.flags
+             FROM rss_item i LEFT JOIN rss_feed f ON f.rssurl = i.feedurl
+             WHERE i.deleted = 0",
+        )
+        .clio_parse_err("Invalid Newsboat cache")?;
+
+    statement
+        .query_map([], |row| {
+            let flags: Option<String> = row.get(5)?;
+            Ok(ImportedItem {
+                source_name: row.get::<_, Option<String>>(0)?.unwrap_or_default(),
+                title: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                link: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                pub_date: row
+                    .get::<_, Option<i64>>(3)?
+                    .and_then(|secs| DateTime::from_timestamp(secs, 0)),
+                is_read: row.get::<_, i64>(4)? == 0,
+                is_starred: flags.is_some_and(|flags| !flags.trim().is_empty()),
+            })
+        })
+        .and_then(|rows| rows.collect())
+        .clio_parse_err("Failed to read Newsboat items")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn write_temp(contents: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_parse_miniflux() {
+        let json = r#"{
+            "total": 3,
+            "entries": [
+                {"url": "https://example.com/a", "title": "A", "status": "read", "starred": true,
+                 "published_at": "2024-01-02T03:04:05Z", "feed": {"title": "Example"}},
+                {"url": "https://example.com/b", "title": "B", "status": "unread", "starred": false},
+                {"url": "https://example.com/c", "title": "C", "status": "removed"}
+            ]
+        }"#;
+
+        let items = parse_miniflux(json).unwrap();
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].source_name, "Example");
+        assert!(items[0].is_read && items[0].is_starred);
+        assert!(items[0].pub_date.is_some());
+        assert!(!items[1].is_read && !items[1].is_starred);
+        assert!(items[2].is_read);
+    }
+
+    #[test]
+    fn test_parse_freshrss() {
+        let json = r#"{
+            "id": "user/-/state/com.google/starred",
+            "items": [
+                {"title": "Starred", "published": 1700000000,
+                 "alternate": [{"href": "https://example.com/s", "type": "text/html"}],
+                 "categories": ["user/-/state/com.google/read", "user/-/state/com.google/starred"],
+                 "origin": {"title": "Blog"}},
+                {"title": "Unread", "alternate": [{"href": "https://example.com/u"}],
+                 "categories": ["user/-/label/Tech"]},
+                {"title": "No link", "categories": []}
+            ]
+        }"#;
+
+        let items = parse_freshrss(json).unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].link, "https://example.com/s");
+        assert_eq!(items[0].source_name, "Blog");
+        assert!(items[0].is_read && items[0].is_starred);
+        assert_eq!(items[0].pub_date.unwrap().timestamp(), 1_700_000_000);
+        assert!(!items[1].is_read && !items[1].is_starred);
+    }
+
+    #[test]
+    fn test_read_newsboat_cache() {
+        let file = NamedTempFile::new().unwrap();
+        let conn = rusqlite::Connection::open(file.path()).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE rss_feed (rssurl TEXT PRIMARY KEY, url TEXT, title TEXT);
+             CREATE TABLE rss_item (id INTEGER PRIMARY KEY, guid TEXT, title TEXT, url TEXT,
+                 feedurl TEXT, pubDate INTEGER, unread INTEGER, flags TEXT, deleted INTEGER);
+             INSERT INTO rss_feed VALUES ('https://example.com/rss', 'https://example.com', 'Example');
+             INSERT INTO rss_item VALUES (1, 'a', 'Read', 'https://example.com/a',
+                 'https://example.com/rss', 1700000000, 0, NULL, 0);
+             INSERT INTO rss_item VALUES (2, 'b', 'Flagged', 'https://example.com/b',
+                 'https://example.com/rss', 1700000000, 1, 's', 0);
+             INSERT INTO rss_item VALUES (3, 'c', 'Deleted', 'https://example.com/c',
+                 'https://example.com/rss', 1700000000, 0, NULL, 1);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let items = read_newsboat(file.path()).unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].source_name, "Example");
+        assert!(items[0].is_read && !items[0].is_starred);
+        assert!(!items[1].is_read && items[1].is_starred);
+    }
+
+    #[test]
+    fn test_read_export_sanitizes_and_skips_invalid_links() {
+        let file = write_temp(
+            r#"{"entries": [
+                {"url": "https://example.com/a", "title": "\u001b[31mRed\u001b[0m title", "status": "read"},
+                {"url": "javascript:alert(1)", "title": "Bad", "status": "read"}
+            ]}"#,
+        );
+
+        let items =
+            read_export(ImportFormat::Miniflux, file.path(), &TextLimits::default()).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].title, "Red title");
+    }
+
+    #[test]
+    fn test_read_export_invalid_json() {
+        let file = write_temp("not json");
+        let err =
+            read_export(ImportFormat::FreshRss, file.path(), &TextLimits::default()).unwrap_err();
+        assert!(matches!(err, ClioError::Parse(_)));
+    }
+}
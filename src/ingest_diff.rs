@@ -0,0 +1,264 @@
+use crate::config::Config;
+use crate::error::ClioError;
+use crate::fetcher::FetchStats;
+use crate::rules::{self, PulledItem, RuleSet};
+use crate::source::Item;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// What a pull under one configuration brought in
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Simulation {
+    /// Items each source brings in past the mute rules, or why the source
+    /// could not be pulled
+    pub sources: BTreeMap<String, Result<usize, String>>,
+    /// Matches by rule, for the rules that act on pulled items
+    pub rules: BTreeMap<String, usize>,
+}
+
+/// How the item flow of a pull changes between two configurations
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct IngestDiff {
+    pub items_before: usize,
+    pub items_after: usize,
+    pub sources: Vec<SourceFlow>,
+    pub rules: Vec<RuleFlow>,
+}
+
+/// Items one source brings in before and after the change
+///
+/// `None` means the source is not configured on that side.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SourceFlow {
+    pub source: String,
+    pub before: Option<usize>,
+    pub after: Option<usize>,
+    /// Why the source could not be simulated, such as a feed body missing
+    /// from the cassette
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Items one rule matches before and after the change
+///
+/// `None` means the rule is not configured on that side.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RuleFlow {
+    /// The rule, by [`crate::rules::RuleReport::key`]
+    pub rule: String,
+    pub before: Option<usize>,
+    pub after: Option<usize>,
+}
+
+impl Simulation {
+    /// Count what `config` would do with the `items` a pull fetched
+    pub fn new(
+        config: &Config,
+        items: &[Item],
+        stats: &FetchStats,
+        now: DateTime<Utc>,
+    ) -> Result<Self, ClioError> {
+        let mut kept = items.to_vec();
+        rules::apply_mute_rules(&config.mutes, &mut kept);
+        let sources = stats
+            .fetches
+            .iter()
+            .map(|fetch| {
+                let flow = match &fetch.error {
+                    Some(error) if !fetch.succeeded => Err(error.clone()),
+                    _ => Ok(kept
+                        .iter()
+                        .filter(|item| item.source_name == fetch.source_name)
+                        .count()),
+                };
+                (fetch.source_name.clone(), flow)
+            })
+            .collect();
+
+        let pulled: Vec<PulledItem> = items.iter().map(PulledItem::from).collect();
+        let rules = RuleSet::from_config(config)
+            .test(config, &pulled, now)?
+            .into_iter()
+            .filter(|report| report.kind.applies_on_pull())
+            .map(|report| (report.key(), report.matches.len()))
+            .collect();
+        Ok(Self { sources, rules })
+    }
+
+    /// Items brought in by the sources that could be pulled
+    pub fn items(&self) -> usize {
+        self.sources
+            .values()
+            .filter_map(|flow| flow.as_ref().ok())
+            .sum()
+    }
+}
+
+impl IngestDiff {
+    /// Compare a pull under the current configuration with one under the
+    /// proposed configuration
+    pub fn between(before: &Simulation, after: &Simulation) -> Self {
+        let names: BTreeSet<&String> = before.sources.keys().chain(after.sources.keys()).collect();
+        let sources = names
+            .into_iter()
+            .map(|name| {
+                let (was, is) = (before.sources.get(name), after.sources.get(name));
+                let error = [is, was]
+                    .into_iter()
+                    .flatten()
+                    .find_map(|flow| flow.as_ref().err().cloned());
+                let count = |flow: Option<&Result<usize, String>>| {
+                    flow.map(|flow| flow.as_ref().copied().unwrap_or(0))
+                };
+                SourceFlow {
+                    source: name.clone(),
+                    before: count(was),
+                    after: count(is),
+                    error,
+                }
+            })
+            .collect();
+
+        let keys: BTreeSet<&String> = before.rules.keys().chain(after.rules.keys()).collect();
+        let rules = keys
+            .into_iter()
+            .map(|key| RuleFlow {
+                rule: key.clone(),
+                before: before.rules.get(key).copied(),
+                after: after.rules.get(key).copied(),
+            })
+            .collect();
+
+        Self {
+            items_before: before.items(),
+            items_after: after.items(),
+            sources,
+            rules,
+        }
+    }
+
+    /// The sources whose flow changes, or that could not be simulated
+    pub fn changed_sources(&self) -> impl Iterator<Item = &SourceFlow> {
+        self.sources
+            .iter()
+            .filter(|flow| flow.before != flow.after || flow.error.is_some())
+    }
+
+    /// The rules whose matches change
+    pub fn changed_rules(&self) -> impl Iterator<Item = &RuleFlow> {
+        self.rules.iter().filter(|flow| flow.before != flow.after)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::fetcher::{FetchResult, FetchStats};
+    use crate::source::synthetic::SyntheticSource;
+
+    fn simulation(sources: &[(&str, Result<usize, &str>)], rules: &[(&str, usize)]) -> Simulation {
+        Simulation {
+            sources: sources
+                .iter()
+                .map(|(name, flow)| (name.to_string(), flow.map_err(str::to_string)))
+                .collect(),
+            rules: rules.iter().map(|(key, n)| (key.to_string(), *n)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_ingest_diff_between() {
+        let before = simulation(
+            &[("Blog", Ok(10)), ("News", Ok(30)), ("Old", Ok(5))],
+            &[("alerts: outage", 2), ("alerts: the", 20)],
+        );
+        let after = simulation(
+            &[
+                ("Blog", Ok(10)),
+                ("News", Ok(30)),
+                ("New", Err("No response")),
+            ],
+            &[("alerts: outage", 2), ("alerts: rust", 4)],
+        );
+
+        let diff = IngestDiff::between(&before, &after);
+        assert_eq!((diff.items_before, diff.items_after), (45, 40));
+        assert_eq!(diff.sources.len(), 4);
+        assert_eq!(
+            diff.changed_sources().collect::<Vec<_>>(),
+            vec![
+                &SourceFlow {
+                    source: "New".to_string(),
+                    before: None,
+                    after: Some(0),
+                    error: Some("No response".to_string()),
+                },
+                &SourceFlow {
+                    source: "Old".to_string(),
+                    before: Some(5),
+                    after: None,
+                    error: None,
+                },
+            ]
+        );
+        assert_eq!(
+            diff.changed_rules()
+                .map(|flow| (flow.rule.as_str(), flow.before, flow.after))
+                .collect::<Vec<_>>(),
+            vec![
+                ("alerts: rust", None, Some(4)),
+                ("alerts: the", Some(20), None)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_simulation_counts_sources_and_rules() {
+        let config: Config = toml::from_str(
+            r#"
+            [[alerts]]
+            keywords = ["outage"]
+
+            [[mutes]]
+            keyword = "clear"
+
+            [[sources.synthetic]]
+            name = "Fake"
+            "#,
+        )
+        .unwrap();
+        let source = SyntheticSource::from(&config.sources.synthetic[0]);
+        let item = |title: &str| Item {
+            source_name: "Status".to_string(),
+            title: title.to_string(),
+            link: format!("https://example.com/{title}"),
+            ..Default::default()
+        };
+        let items = vec![item("Outage in us-east"), item("All clear")];
+        let mut stats = FetchStats::new(2);
+        stats.record_fetch(
+            &FetchResult::Success {
+                source_name: "Status".to_string(),
+                items: items.clone(),
+            },
+            &source,
+        );
+        stats.record_fetch(
+            &FetchResult::Error {
+                source_name: "Blog".to_string(),
+                error: "No response".to_string(),
+                kind: ErrorKind::Network,
+            },
+            &source,
+        );
+
+        let simulation = Simulation::new(&config, &items, &stats, Utc::now()).unwrap();
+        assert_eq!(simulation.sources["Status"], Ok(1));
+        assert_eq!(simulation.sources["Blog"], Err("No response".to_string()));
+        assert_eq!(simulation.items(), 1);
+        assert_eq!(simulation.rules["alerts: outage"], 1);
+        assert_eq!(simulation.rules["mutes: clear"], 1);
+    }
+}
@@ -1,11 +1,55 @@
+pub mod alerts;
+pub mod audit;
+pub mod browser;
+pub mod cassette;
+pub mod citation;
+pub mod clipboard;
+pub mod cold_storage;
+pub mod color;
 pub mod config;
+pub mod confirm;
+pub mod crash;
 pub mod database;
+pub mod digest;
+pub mod discover;
+pub mod display;
+pub mod download;
+pub mod editor;
+pub mod enrich;
 pub mod error;
+pub mod export;
 pub mod fetcher;
+pub mod filter;
+pub mod graphics;
+pub mod health;
+pub mod http;
+pub mod ics;
+pub mod import;
+pub mod ingest_diff;
+pub mod opml;
+pub mod pull;
+pub mod query_log;
+pub mod read_later;
+pub mod redact;
+pub mod releases;
+pub mod retention;
+pub mod rule_stats;
+pub mod rules;
+pub mod sanitize;
+pub mod search;
+pub mod serve;
+pub mod setup;
 pub mod source;
+#[cfg(feature = "test-util")]
+pub mod testing;
+pub mod trace;
+pub mod tui;
+pub mod usage;
+pub mod watch;
+pub mod writes;
 
 // Re-export commonly used types
 pub use config::Config;
 pub use error::ClioError;
-pub use fetcher::{FetchResult, FetchStats, Fetcher};
+pub use fetcher::{FetchResult, FetchStats, Fetcher, SourceFetch};
 pub use source::{Item, Source};
@@ -1,49 +1,3697 @@
 mod cli;
-mod config;
-mod error;
 
 use anyhow::Result;
 use clap::Parser;
-use cli::{Cli, Command};
+use cli::{
+    AuditCommand, AuthCommand, Cli, Command, ConfigCommand, DbCommand, MuteCommand, RuleCommand,
+    SourceCommand,
+};
+use clio::audit::{AuditEntry, AuditLog};
+use clio::color::{self, Tone};
+use clio::database::{ItemChange, ItemQuery, Retention};
+use clio::filter::Filter;
+use clio::import::ImportFormat;
+use clio::usage::{Activity, UsageLog};
+use clio::writes::{self, ReadOnly};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
-#[tokio::main]
-async fn main() -> Result<()> {
+fn main() -> ExitCode {
+    clio::crash::install();
+    // Parsing exits for --help and --version before anything else is set up
     let cli = Cli::parse();
-    run(cli).await
+    color::init(cli.color);
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{} {e:?}", color::err(Tone::Error, "Error:"));
+            ExitCode::FAILURE
+        }
+    }
 }
 
-async fn run(cli: Cli) -> Result<()> {
+/// Run a command, setting up only what it needs
+///
+/// Configuration and storage are loaded by the commands that use them, and
+/// the async runtime is only started for commands that do I/O through it.
+fn run(cli: Cli) -> Result<()> {
+    if cli.no_write {
+        writes::disable(ReadOnly::Requested);
+    } else if writes::home_is_read_only() {
+        writes::disable(ReadOnly::Home);
+    }
+
+    let config_path = clio::config::Config::config_path(cli.config.as_deref())?;
+    let config = config_path.as_path();
+    let plain = cli.plain;
+    let json = cli.json;
+    let yes = cli.yes;
     match cli.command {
-        Command::Pull => execute_pull().await,
-        Command::List => execute_list().await,
-        Command::Open { item_id } => execute_open(&item_id).await,
+        Command::Init { force, example } => execute_init(config, force, example),
+        Command::Pull { sources, trace } => block_on(execute_pull(config, sources, trace, json)),
+        Command::Watch {
+            sources,
+            interval,
+            health_port,
+            max_pull_age,
+        } => block_on(execute_watch(
+            config,
+            sources,
+            interval,
+            health_port.map(|port| (port, max_pull_age)),
+            plain,
+            json,
+        )),
+        Command::List {
+            starred,
+            unread,
+            sources,
+            since,
+            limit,
+            as_of,
+            author,
+            collapse_duplicates,
+            collapse_window,
+        } => {
+            let query = ItemQuery {
+                sources: (!sources.is_empty()).then_some(sources),
+                unread_only: unread,
+                starred_only: starred,
+                hide_archived: true,
+                since,
+                author,
+                as_of,
+                limit,
+                ..Default::default()
+            };
+            block_on(execute_list(
+                config,
+                query,
+                collapse_duplicates.then_some(collapse_window),
+                plain,
+                json,
+            ))
+        }
+        Command::Preview { url, limit } => {
+            block_on(execute_preview(config, url, limit, plain, json))
+        }
+        Command::Discover {
+            url,
+            add,
+            feed,
+            name,
+        } => block_on(execute_discover(config, url, add, feed, name, plain, json)),
+        Command::Top { hours, limit } => block_on(execute_top(config, hours, limit, plain)),
+        Command::Open {
+            item_id: Some(item_id),
+            ..
+        } => block_on(execute_open(item_id)),
+        Command::Open {
+            unread: true,
+            sources,
+            limit,
+            ..
+        } => block_on(execute_open_unread(sources, limit)),
+        Command::Open { .. } => block_on(execute_open_pick()),
+        Command::Copy { item_id, markdown } => block_on(execute_copy(item_id, markdown)),
+        Command::Save { item_id, to } => block_on(execute_save(config, item_id, to)),
+        Command::Show { item_id } => block_on(execute_show(item_id)),
+        Command::Random { filter, print } => block_on(execute_random(config, filter, print)),
+        Command::Search {
+            query,
+            unread,
+            source,
+            since,
+            all_profiles,
+            include_archives,
+        } => {
+            let query = ItemQuery {
+                sources: source.map(|name| vec![name]),
+                unread_only: unread,
+                hide_archived: true,
+                since,
+                text: Some(query.join(" ")),
+                ..Default::default()
+            };
+            block_on(execute_search(
+                config,
+                query,
+                all_profiles,
+                include_archives,
+                plain,
+                json,
+            ))
+        }
+        Command::Status { total } => block_on(execute_status(total, plain, json)),
+        Command::Source { command } => block_on(execute_source(config, command, yes, plain, json)),
+        Command::FetchEnclosures {
+            source,
+            dir,
+            max_size,
+            jobs,
+        } => block_on(execute_fetch_enclosures(
+            config, source, dir, max_size, jobs, plain,
+        )),
+        Command::Import { format, path } => block_on(execute_import(config, format, path)),
+        Command::Export {
+            ics,
+            bibtex,
+            csl,
+            csv,
+            markdown,
+            starred,
+            unread,
+            source,
+            since,
+            filter,
+            output,
+        } => {
+            // A --json given before the subcommand escapes its format group
+            let others = [&bibtex, &csl, &csv, &markdown];
+            if json && (ics || others.iter().any(|file| file.is_some())) {
+                anyhow::bail!("Choose one export format");
+            }
+            let (format, file) = [
+                (ExportFormat::Bibtex, bibtex),
+                (ExportFormat::Csl, csl),
+                (ExportFormat::Json, json.then_some(None)),
+                (ExportFormat::Csv, csv),
+                (ExportFormat::Markdown, markdown),
+            ]
+            .into_iter()
+            .find_map(|(format, file)| Some((format, file?)))
+            .unwrap_or((ExportFormat::Ics, None));
+            if file.is_some() && output.is_some() {
+                anyhow::bail!("Give the output file either after the format or with --output");
+            }
+            let query = ItemQuery {
+                starred_only: starred,
+                unread_only: unread,
+                since,
+                ..Default::default()
+            };
+            block_on(execute_export(
+                config,
+                format,
+                filter,
+                source,
+                query,
+                file.or(output),
+            ))
+        }
+        Command::Digest {
+            since,
+            output,
+            email,
+        } => block_on(execute_digest(since, output, email)),
+        Command::Read { ids, all } => block_on(execute_read(ids, all, true, yes)),
+        Command::Unread { ids, all } => block_on(execute_read(ids, all, false, yes)),
+        Command::Star { ids } => block_on(execute_star(ids, true)),
+        Command::Unstar { ids } => block_on(execute_star(ids, false)),
+        Command::MarkAllRead { filter } => block_on(execute_mark_all_read(config, filter, yes)),
+        Command::Purge {
+            all: true,
+            with_history,
+            with_config,
+            ..
+        } => block_on(execute_purge_all(config, with_history, with_config, yes)),
+        Command::Purge { older_than, .. } => block_on(execute_purge(older_than, yes)),
+        Command::Prune {
+            older_than: None,
+            keep_newest: None,
+            dry_run,
+            ..
+        } => block_on(execute_retention(config, dry_run, yes)),
+        Command::Prune {
+            older_than,
+            keep_newest,
+            keep_starred,
+            dry_run,
+        } => block_on(execute_prune(
+            Retention {
+                older_than,
+                keep_newest,
+                keep_starred,
+                ..Default::default()
+            },
+            dry_run,
+            yes,
+        )),
+        Command::Health { max_pull_age } => block_on(execute_health(max_pull_age, plain)),
+        Command::Serve {
+            port,
+            bind,
+            max_pull_age,
+        } => block_on(execute_serve(config, bind, port, max_pull_age)),
+        Command::Stats { me: true, .. } => execute_stats_me(plain, json),
+        Command::Stats {
+            bandwidth: true,
+            days,
+            ..
+        } => block_on(execute_stats_bandwidth(days, plain, json)),
+        Command::Stats { days, .. } => block_on(execute_stats_items(days, plain, json)),
+        Command::Rules { command } => execute_rules(config, command, plain, json),
+        Command::Mute { command } => execute_mute(config, command, json),
+        Command::DiffConfig { path, cassette } => {
+            block_on(execute_diff_config(config, &path, cassette, plain, json))
+        }
+        Command::Config {
+            command: ConfigCommand::Schema,
+        } => execute_config_schema(),
+        Command::Config {
+            command: ConfigCommand::Validate { network },
+        } => execute_config_validate(config, network, plain),
+        Command::Config {
+            command: ConfigCommand::Edit,
+        } => execute_config_edit(config, plain),
+        Command::Audit {
+            command: AuditCommand::Tail { lines },
+        } => execute_audit_tail(lines, plain),
+        Command::Auth {
+            command: AuthCommand::Rotate { new_key },
+        } => block_on(execute_auth_rotate(new_key)),
+        Command::Db {
+            command: DbCommand::Init,
+        } => block_on(execute_db_init()),
+        Command::Db {
+            command: DbCommand::Reset,
+        } => block_on(execute_db_reset(yes)),
+        Command::Db {
+            command: DbCommand::Migrate { dry_run },
+        } => block_on(execute_db_migrate(dry_run, json)),
+        Command::Db {
+            command: DbCommand::Verify { repair },
+        } => block_on(execute_db_verify(repair, yes, plain, json)),
+        Command::Db {
+            command: DbCommand::Rotate { older_than },
+        } => block_on(execute_db_rotate(config, older_than)),
+    }
+}
+
+/// Run a command's future on a Tokio runtime started just for it
+fn block_on<T>(command: impl Future<Output = Result<T>>) -> Result<T> {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(command)
+}
+
+fn execute_init(config_path: &Path, force: bool, example: bool) -> Result<()> {
+    use clio::config::Config;
+    use std::io::{self, IsTerminal};
+
+    // Fail before asking anything if the configuration cannot be written
+    Config::check_init(config_path, force)?;
+    let interactive = !example && io::stdin().is_terminal() && io::stdout().is_terminal();
+    let config = if interactive {
+        clio::setup::run()?
+    } else {
+        None
+    };
+
+    match config {
+        Some(config) => {
+            config.save(config_path)?;
+            eprintln!("Created {}", config_path.display());
+            eprintln!("Run `clio pull` to fetch your feeds");
+        }
+        None => {
+            Config::init(config_path, true)?;
+            eprintln!("Created {}", config_path.display());
+            eprintln!("Run `clio config edit` to add your sources, then `clio pull`");
+        }
+    }
+    Ok(())
+}
+
+async fn execute_pull(
+    config_path: &Path,
+    selected: Vec<String>,
+    trace_path: Option<PathBuf>,
+    json: bool,
+) -> Result<()> {
+    use clio::config::Config;
+    use clio::database::Database;
+    use clio::http::{FeedClient, RequestGuard};
+    use clio::pull::{Pull, Pulled};
+    use clio::rules::LastPull;
+    use clio::serve::ItemJson;
+    use clio::trace::Trace;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    if trace_path.is_some() {
+        writes::ensure_allowed("write the trace")?;
+    }
+    let config = Config::load_strict(config_path)?;
+
+    let active: Vec<_> = config.sources.active().collect();
+    let synthetic = &config.sources.synthetic;
+    let advisories = &config.sources.advisories;
+    let statuspage = &config.sources.statuspage;
+    let changelog = &config.sources.changelog;
+    let twitch = &config.sources.twitch;
+    let weather = &config.sources.weather;
+    // A selection is listed once the sources are built
+    if selected.is_empty() {
+        eprintln!(
+            "Fetching content from {} configured sources...",
+            active.len()
+                + synthetic.len()
+                + advisories.len()
+                + statuspage.len()
+                + changelog.len()
+                + twitch.len()
+                + weather.len()
+        );
+        for source in &active {
+            match source.addresses().as_slice() {
+                [] | [_] => eprintln!("  - {} ({})", source.name, source.primary_url()),
+                [primary, mirrors @ ..] => {
+                    eprintln!("  - {} ({primary}, {} mirrors)", source.name, mirrors.len())
+                }
+            }
+        }
+        for source in synthetic {
+            eprintln!("  - {} (synthetic, {} items)", source.name, source.items);
+        }
+        for source in advisories {
+            eprintln!("  - {} ({} advisories)", source.name, source.ecosystem);
+        }
+        for source in statuspage {
+            eprintln!("  - {} ({}, status page)", source.name, source.url);
+        }
+        for source in changelog {
+            eprintln!("  - {} ({}, changelog)", source.name, source.url);
+        }
+        for source in twitch {
+            eprintln!(
+                "  - {} (Twitch, {} channels)",
+                source.name,
+                source.channels.len()
+            );
+        }
+        for source in weather {
+            eprintln!("  - {} (weather warnings for {})", source.name, source.area);
+        }
+    }
+
+    let paused = config.sources.rss.len() - active.len();
+    if paused > 0 && selected.is_empty() {
+        eprintln!("Skipping {paused} paused source(s)");
+    }
+    if config.settings.archive_expired {
+        eprintln!("Items past their feed's expiration date will be archived");
+    }
+
+    let trace = trace_path.as_ref().map(|_| Arc::new(Trace::new()));
+    let client = FeedClient::new(RequestGuard::new(config.settings.allow_private_networks));
+    let client = match &trace {
+        Some(trace) => client.with_trace(trace.clone()),
+        None => client,
+    };
+    let mut sources = clio::source::from_config(&config, &client)?;
+    if !selected.is_empty() {
+        let configured = sources.len();
+        sources = clio::source::select(sources, &selected)?;
+        eprintln!(
+            "Fetching {} of {configured} active sources, matching {}:",
+            sources.len(),
+            selected.join(", ")
+        );
+        for source in &sources {
+            eprintln!("  - {} ({})", source.name(), source.url());
+        }
+    }
+    let mut pull = Pull::new(&config, client);
+    let storing = match tokio::task::spawn_blocking(Database::new).await? {
+        Ok(database) => {
+            pull = pull.with_store(Arc::new(database));
+            true
+        }
+        Err(e) => {
+            eprintln!(
+                "{} Storage unavailable, so pulled items were not stored: {e}",
+                warning_label()
+            );
+            false
+        }
+    };
+    let Pulled {
+        last_pull,
+        items,
+        stats,
+        enriched,
+        enrich_failures,
+        muted,
+        followed,
+    } = pull.run(sources).await?;
+    for e in enrich_failures {
+        eprintln!("{} {e}", warning_label());
+    }
+    if enriched > 0 {
+        eprintln!("Looked up paper metadata for {enriched} item(s)");
+    }
+    if let Err(e) = LastPull::path().and_then(|path| last_pull.save(&path)) {
+        eprintln!("{} {e}", warning_label());
+    }
+    record_rule_hits(&config, &last_pull);
+    if muted > 0 {
+        eprintln!("Muted {muted} item(s)");
+    }
+    if followed > 0 {
+        eprintln!("Author rules starred or tagged {followed} item(s)");
+    }
+    if storing {
+        eprintln!("Stored {} new item(s)", items.len());
+    }
+    send_alerts(&config, &items);
+
+    if let (Some(path), Some(trace)) = (&trace_path, &trace) {
+        trace.save(path)?;
+        eprintln!(
+            "Wrote a trace of {} requests to {}",
+            trace.requests().len(),
+            path.display()
+        );
+    }
+    if let Some(days) = config.settings.cold_storage_after_days
+        && let Err(e) = rotate_cold_storage(days).await
+    {
+        eprintln!("{} Old items were not archived: {e}", warning_label());
+    }
+    record_usage(Activity::Pull, 1);
+
+    if json {
+        let errors: Vec<_> = stats
+            .errors
+            .iter()
+            .map(|(source, error)| json!({"source": source, "error": error}))
+            .collect();
+        let items: Vec<ItemJson> = items.into_iter().map(ItemJson::from).collect();
+        print_json(&json!({
+            "sources": stats.num_sources,
+            "succeeded": stats.successful_sources,
+            "failed": stats.failed_sources,
+            "items": items,
+            "errors": errors,
+        }))?;
+    }
+    Ok(())
+}
+
+/// `health` is the port to serve /healthz on and its maximum pull age in hours
+async fn execute_watch(
+    config_path: &Path,
+    selected: Vec<String>,
+    interval: u32,
+    health: Option<(u16, u32)>,
+    plain: bool,
+    json: bool,
+) -> Result<()> {
+    use clio::config::Config;
+    use clio::database::Database;
+    use clio::display::PLAIN_SEPARATOR;
+    use clio::fetcher::Fetcher;
+    use clio::health::Heartbeat;
+    use clio::http::{FeedClient, RequestGuard};
+    use clio::rules;
+    use clio::serve::{HealthServer, ItemJson};
+    use clio::watch::Seen;
+    use std::time::Duration;
+
+    let config = Config::load_strict(config_path)?;
+    let client = FeedClient::new(RequestGuard::new(config.settings.allow_private_networks));
+    let mut sources = clio::source::from_config(&config, &client)?;
+    if !selected.is_empty() {
+        sources = clio::source::select(sources, &selected)?;
+    }
+    if sources.is_empty() {
+        anyhow::bail!("No active sources to watch");
+    }
+
+    let database = match tokio::task::spawn_blocking(Database::new).await? {
+        Ok(database) => Some(database),
+        Err(e) => {
+            eprintln!(
+                "{} Storage unavailable, so stored items may be shown again: {e}",
+                warning_label()
+            );
+            None
+        }
+    };
+    eprintln!(
+        "Watching {} sources every {interval} minute(s); press Ctrl-C to stop",
+        sources.len()
+    );
+
+    // A poll may take up to an interval on top of the wait before it
+    let heartbeat = Heartbeat::new(
+        chrono::Duration::minutes(i64::from(interval) * 2),
+        chrono::Utc::now(),
+    );
+    let health_server = match health {
+        Some((port, max_pull_age)) => {
+            let listener = tokio::net::TcpListener::bind(("0.0.0.0", port)).await?;
+            eprintln!(
+                "Serving health checks at http://{}/healthz",
+                listener.local_addr()?
+            );
+            let server = HealthServer::new(database.clone(), heartbeat.clone())
+                .with_max_pull_age(chrono::Duration::hours(i64::from(max_pull_age)));
+            Some(tokio::spawn(async move {
+                if let Err(e) = server.run(listener).await {
+                    eprintln!("{} {e}", warning_label());
+                }
+            }))
+        }
+        None => None,
+    };
+
+    let fetcher = Fetcher::with_timeout(config.settings.timeout)
+        .with_client(client)
+        .quiet();
+    let mut seen = Seen::new().with_fingerprints(config.sources.fingerprinted());
+    let stop = tokio::signal::ctrl_c();
+    tokio::pin!(stop);
+    loop {
+        let now = chrono::Utc::now();
+        let due: Vec<_> = sources
+            .iter()
+            .filter(|source| source.is_due(now))
+            .cloned()
+            .collect();
+        let (items, stats) = tokio::select! {
+            fetched = fetcher.fetch_all(due) => fetched,
+            _ = &mut stop => break,
+        };
+        heartbeat.beat(chrono::Utc::now());
+        for (source, error) in &stats.errors {
+            eprintln!("{} {source}: {error}", warning_label());
+        }
+
+        let mut items = seen.unseen(items);
+        rules::apply_mute_rules(&config.mutes, &mut items);
+        if let Some(database) = database.clone().filter(|_| !items.is_empty()) {
+            let fingerprinted = config.sources.fingerprinted();
+            let unseen = items.clone();
+            let unstored = tokio::task::spawn_blocking(move || {
+                let links: Vec<String> = unseen.iter().map(|item| item.link.clone()).collect();
+                let stored = database.stored_links(&links)?;
+                let unseen = unseen
+                    .into_iter()
+                    .filter(|item| !stored.contains(&item.link))
+                    .collect();
+                clio::pull::drop_stored_fingerprints(&database, &fingerprinted, unseen)
+            })
+            .await?;
+            match unstored {
+                Ok(unstored) => items = unstored,
+                Err(e) => eprintln!("{} {e}", warning_label()),
+            }
+        }
+
+        // Oldest first, so the newest item ends up at the bottom
+        items.sort_by_key(|item| item.effective_date(now));
+        for item in items {
+            if json {
+                println!("{}", serde_json::to_string(&ItemJson::from(item))?);
+            } else if plain {
+                let fields = [item.source_name, item.title, item.link];
+                println!("{}", fields.join(PLAIN_SEPARATOR));
+            } else {
+                println!("[{}] {}", item.source_name, item.title);
+                println!("  {}", item.link);
+            }
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(u64::from(interval) * 60)) => {}
+            _ = &mut stop => break,
+        }
+    }
+    if let Some(health_server) = health_server {
+        health_server.abort();
+    }
+    Ok(())
+}
+
+async fn execute_list(
+    config_path: &Path,
+    query: ItemQuery,
+    collapse_window: Option<u32>,
+    plain: bool,
+    json: bool,
+) -> Result<()> {
+    use clio::ClioError;
+    use clio::browser;
+    use clio::config::Config;
+    use clio::database::{Database, PAGE_SIZE};
+    use clio::display::{ListRow, collapse_duplicates, relative_time, sort_newest_first};
+    use clio::graphics::{GraphicsProtocol, ImageFetcher};
+    use clio::http::RequestGuard;
+    use clio::serve::ItemJson;
+    use clio::tui::{self, Action, FeedPreview, PREVIEW_ITEMS, Previews, Subscriber};
+    use std::io::IsTerminal;
+
+    type Pages = Box<dyn Iterator<Item = Result<Vec<ListRow>, ClioError>> + Send>;
+
+    let database = tokio::task::spawn_blocking(Database::new).await??;
+    let pages: Pages = match collapse_window {
+        // Folding duplicates compares every item with the others, so they
+        // are read at once
+        Some(hours) => {
+            let database = database.clone();
+            let rows = tokio::task::spawn_blocking(move || {
+                let mut items = database.items(&query)?;
+                sort_newest_first(&mut items, chrono::Utc::now());
+                let window = chrono::Duration::hours(i64::from(hours));
+                Ok::<_, ClioError>(collapse_duplicates(items, window))
+            })
+            .await??;
+            Box::new(std::iter::once(Ok(rows)))
+        }
+        // Otherwise rows are read a page at a time and shown as they come,
+        // however large the backlog
+        None => Box::new(database.item_pages(query, PAGE_SIZE).map(|page| {
+            page.map(|items| {
+                items
+                    .into_iter()
+                    .map(|item| ListRow { item, count: 1 })
+                    .collect()
+            })
+        })),
+    };
+
+    if json {
+        return tokio::task::spawn_blocking(move || {
+            print_json_pages(pages.map(|page| {
+                page.map(|rows| {
+                    rows.into_iter()
+                        .map(|row| ItemJson::from(row.item))
+                        .collect()
+                })
+            }))
+        })
+        .await?;
+    }
+
+    // Linear output for screen readers and terminals that cannot draw the
+    // list, and plain output when piped, so the list can be searched or saved
+    let dumb_terminal = std::env::var("TERM").is_ok_and(|term| term == "dumb");
+    if plain || dumb_terminal || !std::io::stdout().is_terminal() {
+        let labeled = plain || dumb_terminal;
+        return tokio::task::spawn_blocking(move || {
+            let now = chrono::Utc::now();
+            for page in pages {
+                for row in page? {
+                    if labeled {
+                        println!("{}", row.plain_line(now));
+                        continue;
+                    }
+                    let mut age = relative_time(&row.item, now).unwrap_or_default();
+                    if let Some(media) = row.media() {
+                        age.push_str(&format!(" · {media}"));
+                    }
+                    println!(
+                        "{}\t{}\t{age}\t{}\t{}",
+                        color::out(Tone::Title, row.title()),
+                        color::out(Tone::Source, &row.item.source_name),
+                        color::out(Tone::Dim, &row.item.link),
+                        row.item.id
+                    );
+                }
+            }
+            Ok(())
+        })
+        .await?;
+    }
+
+    let config = Config::load_strict(config_path)?;
+    let protocol = GraphicsProtocol::select(config.settings.image_previews);
+    let fetcher = ImageFetcher::new(RequestGuard::new(config.settings.allow_private_networks));
+    let runtime = tokio::runtime::Handle::current();
+    let config_path = config_path.to_path_buf();
+
+    tokio::task::spawn_blocking(move || {
+        let subscriber = {
+            let runtime = runtime.clone();
+            Subscriber::new(
+                move |url| {
+                    let (feed, items, _) =
+                        runtime.block_on(discover_feed(&config.settings, url))?;
+                    Ok(FeedPreview {
+                        name: feed.default_name(),
+                        url: feed.url,
+                        titles: items
+                            .into_iter()
+                            .take(PREVIEW_ITEMS)
+                            .map(|item| item.title)
+                            .collect(),
+                    })
+                },
+                move |preview| {
+                    use clio::config::{ConfigFile, RssSource};
+
+                    let mut file = ConfigFile::open(&config_path)?;
+                    file.add_source(RssSource::new(preview.name.clone(), preview.url.clone()))?;
+                    file.save()?;
+                    audit(AuditEntry::new("list", "add source", 1).with_detail(&preview.name));
+                    Ok(())
+                },
+            )
+        };
+        let previews = match protocol {
+            Some(protocol) => Previews::new(protocol, move |url| {
+                runtime.block_on(fetcher.fetch(url)).ok()
+            }),
+            None => Previews::disabled(),
+        };
+        tui::run(pages, previews, subscriber, |action, items| {
+            let ids: Vec<String> = items.iter().map(|item| item.id.clone()).collect();
+            let change = match action {
+                Action::Change(change) => change,
+                Action::Open => {
+                    for item in items {
+                        browser::open(&item.link)?;
+                    }
+                    ItemChange::MarkRead
+                }
+            };
+            database.update_items(&ids, change)?;
+            if change == ItemChange::MarkRead {
+                record_usage(Activity::Read, ids.len() as u64);
+            }
+
+            let links = items.iter().map(|item| item.link.clone()).collect();
+            audit(AuditEntry::new("list", change_name(change), ids.len()).with_links(links));
+            Ok(())
+        })
+    })
+    .await??;
+    Ok(())
+}
+
+/// The first feed behind a site or feed URL, with its items
+async fn discover_feed(
+    settings: &clio::config::Settings,
+    url: &str,
+) -> Result<(clio::discover::DiscoveredFeed, Vec<clio::Item>, Vec<String>), clio::ClioError> {
+    use clio::Source;
+    use clio::discover::{discover, feed_warnings};
+    use clio::http::{FeedClient, RequestGuard};
+    use clio::sanitize::TextLimits;
+
+    let client = FeedClient::new(RequestGuard::new(settings.allow_private_networks));
+    let url = site_url(url);
+    let Some(feed) = discover(&client, &url).await?.into_iter().next() else {
+        return Err(clio::ClioError::Network(format!("No feed found at {url}")));
+    };
+
+    let source = clio::source::rss::RssSource::new(feed.default_name(), feed.url.clone())
+        .with_limits(TextLimits::from(settings))
+        .with_max_redirects(settings.max_redirects)
+        .with_client(client);
+    let items = source.fetch().await?;
+    let warnings = feed_warnings(
+        &feed.url,
+        source.final_url().as_deref(),
+        source.skipped(),
+        &items,
+        chrono::Utc::now(),
+    );
+    Ok((feed, items, warnings))
+}
+
+/// `url`, with https:// added when it has no scheme, since typing
+/// example.com is enough
+fn site_url(url: &str) -> String {
+    if url.contains("://") {
+        url.to_string()
+    } else {
+        format!("https://{url}")
+    }
+}
+
+/// The settings in the configuration file, or the defaults when there is
+/// none, for commands that need no configuration but follow it
+fn settings_or_default(config_path: &Path) -> Result<clio::config::Settings> {
+    use clio::config::{Config, Settings};
+
+    Ok(if config_path.exists() {
+        Config::load_strict(config_path)?.settings
+    } else {
+        Settings::default()
+    })
+}
+
+async fn execute_discover(
+    config_path: &Path,
+    url: String,
+    add: bool,
+    feed: Option<usize>,
+    name: Option<String>,
+    plain: bool,
+    json: bool,
+) -> Result<()> {
+    use clio::config::{ConfigFile, RssSource};
+    use clio::confirm::choose;
+    use clio::discover::discover;
+    use clio::display::PLAIN_SEPARATOR;
+    use clio::http::{FeedClient, RequestGuard};
+
+    let settings = settings_or_default(config_path)?;
+    let client = FeedClient::new(RequestGuard::new(settings.allow_private_networks));
+    let url = site_url(&url);
+    let feeds = discover(&client, &url).await?;
+    if feeds.is_empty() {
+        return Err(clio::ClioError::Network(format!("No feed found at {url}")).into());
+    }
+
+    if json {
+        let feeds: Vec<_> = feeds
+            .iter()
+            .map(|feed| serde_json::json!({"title": feed.title, "url": feed.url}))
+            .collect();
+        print_json(&feeds)?;
+    } else {
+        for (number, feed) in feeds.iter().enumerate().map(|(i, feed)| (i + 1, feed)) {
+            if plain {
+                println!(
+                    "FEED{PLAIN_SEPARATOR}{number}{PLAIN_SEPARATOR}{}{PLAIN_SEPARATOR}{}",
+                    feed.default_name(),
+                    feed.url
+                );
+            } else {
+                println!("{number}. {}", color::out(Tone::Title, feed.default_name()));
+                println!("   {}", color::out(Tone::Dim, &feed.url));
+            }
+        }
+    }
+    if !add {
+        return Ok(());
+    }
+
+    let index = match feed {
+        Some(number) if (1..=feeds.len()).contains(&number) => number - 1,
+        Some(number) => {
+            anyhow::bail!("No feed number {number}; the page lists {}", feeds.len())
+        }
+        None if feeds.len() == 1 => 0,
+        None => {
+            let labels: Vec<String> = feeds
+                .iter()
+                .map(|feed| format!("{} ({})", feed.default_name(), feed.url))
+                .collect();
+            let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+            choose("Add which feed?", &labels, "--feed N")?
+        }
+    };
+    let chosen = &feeds[index];
+    let name = name.unwrap_or_else(|| chosen.default_name());
+    let mut file = ConfigFile::open(config_path)?;
+    file.add_source(RssSource::new(name.clone(), chosen.url.clone()))?;
+    file.save()?;
+    audit(AuditEntry::new("discover", "add source", 1).with_detail(&name));
+    eprintln!("Added source '{name}' ({})", chosen.url);
+    Ok(())
+}
+
+async fn execute_preview(
+    config_path: &Path,
+    url: String,
+    limit: usize,
+    plain: bool,
+    json: bool,
+) -> Result<()> {
+    use clio::display::{PLAIN_SEPARATOR, relative_time, sort_newest_first};
+    use clio::serve::ItemJson;
+
+    let settings = settings_or_default(config_path)?;
+    let (feed, mut items, warnings) = discover_feed(&settings, &url).await?;
+    let now = chrono::Utc::now();
+    sort_newest_first(&mut items, now);
+    let total = items.len();
+    let pace = posting_pace(&items, now);
+    items.truncate(limit);
+
+    if json {
+        let items: Vec<ItemJson> = items.into_iter().map(ItemJson::from).collect();
+        return print_json(&serde_json::json!({
+            "title": feed.title,
+            "url": feed.url,
+            "total": total,
+            "warnings": warnings,
+            "items": items,
+        }));
+    }
+    for warning in &warnings {
+        eprintln!("{} {warning}", warning_label());
+    }
+    if plain {
+        println!(
+            "FEED{PLAIN_SEPARATOR}{}{PLAIN_SEPARATOR}{}{PLAIN_SEPARATOR}{total} items",
+            feed.default_name(),
+            feed.url
+        );
+        for item in &items {
+            let age = relative_time(item, now).unwrap_or_default();
+            println!(
+                "ITEM{PLAIN_SEPARATOR}{}{PLAIN_SEPARATOR}{age}{PLAIN_SEPARATOR}{}",
+                item.title, item.link
+            );
+        }
+        return Ok(());
+    }
+
+    println!("{}", color::out(Tone::Title, feed.default_name()));
+    println!("{}", color::out(Tone::Dim, &feed.url));
+    match pace {
+        Some(pace) => println!("{total} items, {pace}"),
+        None => println!("{total} items"),
+    }
+    println!();
+    for item in &items {
+        let title = color::out(Tone::Title, &item.title);
+        match relative_time(item, now) {
+            Some(age) => println!("  - {title} ({age})"),
+            None => println!("  - {title}"),
+        }
+        println!("    {}", color::out(Tone::Dim, &item.link));
+    }
+    Ok(())
+}
+
+/// How often a feed posts, judged from the dates of its items
+fn posting_pace(items: &[clio::Item], now: chrono::DateTime<chrono::Utc>) -> Option<String> {
+    let oldest = items.iter().filter_map(|item| item.pub_date).min()?;
+    let days = (now - oldest).num_days();
+    if days < 1 {
+        return None;
+    }
+    let per_week = items.len() as f64 * 7.0 / days as f64;
+    Some(format!(
+        "about {per_week:.1} a week over the last {days} days"
+    ))
+}
+
+async fn execute_top(config_path: &Path, hours: u32, limit: usize, plain: bool) -> Result<()> {
+    use clio::config::Config;
+    use clio::display::{PLAIN_SEPARATOR, trending};
+    use clio::fetcher::Fetcher;
+    use clio::http::{FeedClient, RequestGuard};
+
+    let config = Config::load_strict(config_path)?;
+    let client = FeedClient::new(RequestGuard::new(config.settings.allow_private_networks));
+    let sources = clio::source::from_config(&config, &client)?;
+    let (items, _) = Fetcher::with_timeout(config.settings.timeout)
+        .with_client(client)
+        .fetch_all(sources)
+        .await;
+
+    let window = chrono::Duration::hours(i64::from(hours));
+    let rows = trending(items, chrono::Utc::now(), window);
+    if rows.is_empty() {
+        eprintln!("Nothing published in the last {hours}h");
+        return Ok(());
+    }
+
+    for (rank, row) in rows.iter().take(limit).enumerate() {
+        let mut details = vec![row.sources.join(", ")];
+        if row.score > 0 {
+            details.push(format!("{} points", row.score));
+        }
+        if row.comments > 0 {
+            details.push(format!("{} comments", row.comments));
+        }
+
+        if plain {
+            let mut fields = vec![format!("RANK {}", rank + 1), row.item.title.clone()];
+            fields.extend(details);
+            fields.push(row.item.link.clone());
+            println!("{}", fields.join(PLAIN_SEPARATOR));
+        } else {
+            println!(
+                "{:>3}. {}",
+                rank + 1,
+                color::out(Tone::Title, &row.item.title)
+            );
+            println!("     {}", details.join(" · "));
+            println!("     {}", color::out(Tone::Dim, &row.item.link));
+        }
+    }
+    Ok(())
+}
+
+async fn execute_open(item_id: String) -> Result<()> {
+    use clio::database::Database;
+
+    let (database, item) = tokio::task::spawn_blocking(move || {
+        let database = Database::new()?;
+        let item = database.find_item(&item_id)?;
+        Ok::<_, clio::ClioError>((database, item))
+    })
+    .await??;
+
+    eprintln!("Opening {}", item.link);
+    open_and_mark_read(database, vec![item], "open").await
+}
+
+async fn execute_open_unread(sources: Vec<String>, limit: usize) -> Result<()> {
+    use clio::database::Database;
+
+    let query = ItemQuery {
+        sources: (!sources.is_empty()).then_some(sources),
+        unread_only: true,
+        hide_archived: true,
+        limit: Some(limit),
+        ..Default::default()
+    };
+    let (database, items) = tokio::task::spawn_blocking(move || {
+        let database = Database::new()?;
+        let items = database.items(&query)?;
+        Ok::<_, clio::ClioError>((database, items))
+    })
+    .await??;
+
+    if items.is_empty() {
+        eprintln!("No unread items");
+        return Ok(());
+    }
+    eprintln!("Opening {} unread items:", items.len());
+    for item in &items {
+        eprintln!("  - {} ({})", item.title, item.source_name);
+    }
+    open_and_mark_read(database, items, "open").await
+}
+
+/// How many recent items `clio open` offers to pick from
+const PICK_CANDIDATES: usize = 500;
+
+async fn execute_open_pick() -> Result<()> {
+    use clio::confirm::pick;
+    use clio::database::Database;
+    use clio::display::relative_time;
+
+    let query = ItemQuery {
+        hide_archived: true,
+        limit: Some(PICK_CANDIDATES),
+        ..Default::default()
+    };
+    let (database, mut items) = tokio::task::spawn_blocking(move || {
+        let database = Database::new()?;
+        let items = database.items(&query)?;
+        Ok::<_, clio::ClioError>((database, items))
+    })
+    .await??;
+
+    if items.is_empty() {
+        eprintln!("No items to open");
+        return Ok(());
+    }
+    let now = chrono::Utc::now();
+    let labels: Vec<String> = items
+        .iter()
+        .map(|item| match relative_time(item, now) {
+            Some(age) => format!("{} · {} · {age}", item.title, item.source_name),
+            None => format!("{} · {}", item.title, item.source_name),
+        })
+        .collect();
+    let Some(index) = pick("Open which item?", &labels, "an ITEM_ID")? else {
+        return Ok(());
+    };
+
+    let item = items.swap_remove(index);
+    eprintln!("Opening {}", item.link);
+    open_and_mark_read(database, vec![item], "open").await
+}
+
+async fn execute_copy(item_id: String, markdown: bool) -> Result<()> {
+    use clio::database::Database;
+
+    let item = tokio::task::spawn_blocking(move || Database::new()?.find_item(&item_id)).await??;
+    let text = if markdown {
+        clio::export::markdown_link(&item)
+    } else {
+        item.link
+    };
+    clio::clipboard::copy(&text)?;
+    eprintln!("Copied {text}");
+    Ok(())
+}
+
+async fn execute_save(config_path: &Path, item_id: String, to: Option<String>) -> Result<()> {
+    use clio::config::Config;
+    use clio::database::Database;
+    use clio::http::{FeedClient, RequestGuard};
+    use clio::read_later;
+
+    let config = Config::load_strict(config_path)?;
+    let destination = read_later::destination(&config.read_later, to.as_deref())?;
+    let item = tokio::task::spawn_blocking(move || Database::new()?.find_item(&item_id)).await??;
+
+    let client = FeedClient::new(RequestGuard::new(config.settings.allow_private_networks));
+    read_later::save(destination, &item, &client).await?;
+    audit(
+        AuditEntry::new("save", "save for later", 1)
+            .with_detail(&destination.name)
+            .with_links(vec![item.link.clone()]),
+    );
+    eprintln!("Saved '{}' to {}", item.title, destination.name);
+    Ok(())
+}
+
+async fn execute_show(item_id: String) -> Result<()> {
+    use clio::database::Database;
+    use clio::display::item_details;
+
+    let item = tokio::task::spawn_blocking(move || Database::new()?.find_item(&item_id)).await??;
+    print!(
+        "{}",
+        item_details(&item, chrono::Utc::now(), details_width())
+    );
+    Ok(())
+}
+
+async fn execute_random(config_path: &Path, filters: Vec<Filter>, print: bool) -> Result<()> {
+    use clio::config::Config;
+    use clio::database::Database;
+    use clio::display::item_details;
+    use clio::filter::matching_sources;
+
+    let config = Config::load_strict(config_path)?;
+    let query = ItemQuery {
+        sources: matching_sources(&config.sources, &filters),
+        unread_only: true,
+        hide_archived: true,
+        ..Default::default()
+    };
+    let (database, item) = tokio::task::spawn_blocking(move || {
+        let database = Database::new()?;
+        let item = database.random_item(&query)?;
+        Ok::<_, clio::ClioError>((database, item))
+    })
+    .await??;
+
+    let Some(item) = item else {
+        eprintln!("No unread items");
+        return Ok(());
+    };
+    if print {
+        print!(
+            "{}",
+            item_details(&item, chrono::Utc::now(), details_width())
+        );
+        return Ok(());
+    }
+    eprintln!("Opening {}\n  {}", item.title, item.link);
+    open_and_mark_read(database, vec![item], "random").await
+}
+
+async fn execute_search(
+    config_path: &Path,
+    query: ItemQuery,
+    all_profiles: bool,
+    include_archives: bool,
+    plain: bool,
+    json: bool,
+) -> Result<()> {
+    use clio::database::Database;
+    use clio::serve::ItemJson;
+
+    let text = query.text.clone().unwrap_or_default();
+    if all_profiles {
+        return execute_search_profiles(config_path, &text, query, include_archives, plain, json)
+            .await;
+    }
+    let archives = include_archives.then(|| query.clone());
+    let (hits, archived) = tokio::try_join!(
+        async {
+            Ok::<_, anyhow::Error>(
+                tokio::task::spawn_blocking(move || Database::new()?.search(&query)).await??,
+            )
+        },
+        async {
+            match archives {
+                Some(query) => search_archives(query).await,
+                None => Ok(Vec::new()),
+            }
+        }
+    )?;
+    // Stored items come first among equal scores
+    let mut results: Vec<_> = hits
+        .into_iter()
+        .map(|hit| (hit, false))
+        .chain(archived.into_iter().map(|hit| (hit, true)))
+        .collect();
+    results.sort_by(|(a, _), (b, _)| b.score.total_cmp(&a.score));
+
+    if results.is_empty() {
+        eprintln!("No items match '{text}'");
+    }
+    if json && include_archives {
+        let mut values = Vec::new();
+        for (hit, archived) in results {
+            let mut value = serde_json::to_value(ItemJson::from(hit.item))?;
+            value["archived"] = serde_json::json!(archived);
+            values.push(value);
+        }
+        return print_json(&values);
+    }
+    if json {
+        let items: Vec<ItemJson> = results
+            .into_iter()
+            .map(|(hit, _)| ItemJson::from(hit.item))
+            .collect();
+        return print_json(&items);
+    }
+    let now = chrono::Utc::now();
+    for (hit, archived) in results {
+        print_search_result(hit.item, archived.then_some("archive"), now, plain);
+    }
+    Ok(())
+}
+
+/// Items in the yearly archive files matching `query`, scored like stored ones
+async fn search_archives(query: ItemQuery) -> Result<Vec<clio::search::SearchHit>> {
+    use clio::cold_storage::ColdStorage;
+
+    let text = query.text.clone().unwrap_or_default();
+    let hits = tokio::task::spawn_blocking(move || {
+        let items = ColdStorage::open()?.search(&query)?;
+        Ok::<_, clio::ClioError>(clio::search::rank(items, &text, chrono::Utc::now()))
+    })
+    .await??;
+    Ok(hits)
+}
+
+/// Search the sources of every profile concurrently, merging the results
+/// by score
+///
+/// Archived items are labeled with the "archive" profile.
+async fn execute_search_profiles(
+    config_path: &Path,
+    text: &str,
+    query: ItemQuery,
+    include_archives: bool,
+    plain: bool,
+    json: bool,
+) -> Result<()> {
+    use clio::config::Config;
+    use clio::database::Database;
+    use clio::search::merge;
+    use clio::serve::ItemJson;
+
+    let profiles = Config::profiles(config_path)?;
+    if profiles.is_empty() {
+        anyhow::bail!("No profiles found next to {}", config_path.display());
+    }
+    let archives = include_archives.then(|| query.clone());
+    let mut searches = Vec::new();
+    for (name, path) in profiles {
+        let names = Config::read_file(&path)
+            .map_err(|e| anyhow::anyhow!("Profile '{name}': {e}"))?
+            .source_names();
+        let sources = match &query.sources {
+            Some(only) => only
+                .iter()
+                .filter(|n| names.contains(*n))
+                .cloned()
+                .collect(),
+            None => names.into_iter().collect(),
+        };
+        let query = ItemQuery {
+            sources: Some(sources),
+            ..query.clone()
+        };
+        searches.push(async move {
+            let hits =
+                tokio::task::spawn_blocking(move || Database::new()?.search(&query)).await??;
+            Ok::<_, anyhow::Error>((name, hits))
+        });
+    }
+    let (mut results, archived) =
+        tokio::try_join!(futures::future::try_join_all(searches), async {
+            match archives {
+                Some(query) => search_archives(query).await,
+                None => Ok(Vec::new()),
+            }
+        })?;
+    if include_archives {
+        results.push(("archive".to_string(), archived));
+    }
+    let hits = merge(results);
+
+    if hits.is_empty() {
+        eprintln!("No items match '{text}' in any profile");
+    }
+    if json {
+        let mut results = Vec::new();
+        for hit in hits {
+            let mut value = serde_json::to_value(ItemJson::from(hit.hit.item))?;
+            value["profiles"] = serde_json::json!(hit.profiles);
+            value["score"] = serde_json::json!(hit.hit.score);
+            results.push(value);
+        }
+        return print_json(&results);
+    }
+    let now = chrono::Utc::now();
+    for hit in hits {
+        print_search_result(hit.hit.item, Some(&hit.profiles.join(", ")), now, plain);
+    }
+    Ok(())
+}
+
+/// Print one search result, with a label such as its profiles when
+/// searching several
+fn print_search_result(
+    item: clio::Item,
+    label: Option<&str>,
+    now: chrono::DateTime<chrono::Utc>,
+    plain: bool,
+) {
+    use clio::display::{ListRow, PLAIN_SEPARATOR, relative_time};
+
+    let row = ListRow { item, count: 1 };
+    if plain {
+        match label {
+            Some(label) => println!("{}{PLAIN_SEPARATOR}{label}", row.plain_line(now)),
+            None => println!("{}", row.plain_line(now)),
+        }
+        return;
+    }
+    let mut age = relative_time(&row.item, now).unwrap_or_default();
+    if let Some(media) = row.media() {
+        age.push_str(&format!(" · {media}"));
+    }
+    let line = format!(
+        "{}\t{}\t{age}\t{}\t{}",
+        color::out(Tone::Title, row.title()),
+        color::out(Tone::Source, &row.item.source_name),
+        color::out(Tone::Dim, &row.item.link),
+        row.item.id
+    );
+    match label {
+        Some(label) => println!("{}\t{line}", color::out(Tone::Dim, format!("[{label}]"))),
+        None => println!("{line}"),
+    }
+}
+
+async fn execute_source(
+    config_path: &Path,
+    command: SourceCommand,
+    yes: bool,
+    plain: bool,
+    json: bool,
+) -> Result<()> {
+    use clio::Source;
+    use clio::config::{Config, ConfigFile, RssSource};
+    use clio::confirm::{self, confirm};
+    use clio::database::Database;
+    use clio::display::PLAIN_SEPARATOR;
+    use clio::http::RequestGuard;
+    use clio::sanitize::TextLimits;
+
+    // Changes are saved back to the file, so they start from the file alone
+    let open = || {
+        if Config::sources_from_env() {
+            eprintln!(
+                "{} CLIO_SOURCES or CLIO_SOURCES_<KIND> is set and replaces sources saved in the file",
+                warning_label()
+            );
+        }
+        ConfigFile::open(config_path)
+    };
+    match command {
+        SourceCommand::List => {
+            let config = Config::load_strict(config_path)?;
+            execute_source_list(&config, plain, json).await?
+        }
+        SourceCommand::Show { name, errors } => {
+            let config = Config::load_strict(config_path)?;
+            execute_source_show(&config, name, errors, plain, json).await?
+        }
+        SourceCommand::Add {
+            name,
+            url,
+            fetch_now,
+        } => {
+            let mut file = open()?;
+            file.add_source(RssSource::new(name.clone(), url.clone()))?;
+            if fetch_now {
+                let settings = &file.config().settings;
+                let feed = clio::source::rss::RssSource::new(name.clone(), url)
+                    .with_limits(TextLimits::from(settings))
+                    .with_max_redirects(settings.max_redirects)
+                    .with_guard(RequestGuard::new(settings.allow_private_networks));
+                let items = feed
+                    .fetch()
+                    .await
+                    .map_err(|e| anyhow::anyhow!("Not adding source '{name}': {e}"))?;
+                eprintln!("Fetched {} items from '{name}'", items.len());
+            }
+            file.save()?;
+            audit(AuditEntry::new("sources add", "add source", 1).with_detail(&name));
+            eprintln!("Added source '{name}'");
+        }
+        SourceCommand::Pause { name } => {
+            let mut file = open()?;
+            file.set_paused(&name, true)?;
+            file.save()?;
+            audit(AuditEntry::new("sources pause", "pause source", 1).with_detail(&name));
+            eprintln!("Paused source '{name}'");
+        }
+        SourceCommand::Resume { name } => {
+            let mut file = open()?;
+            file.set_paused(&name, false)?;
+            file.save()?;
+            audit(AuditEntry::new("sources resume", "resume source", 1).with_detail(&name));
+            eprintln!("Resumed source '{name}'");
+        }
+        SourceCommand::Rename { old_name, new_name } => {
+            let mut file = open()?;
+            file.rename_source(&old_name, &new_name)?;
+            let filter = format!("source:{old_name}");
+            if file
+                .config()
+                .retention
+                .iter()
+                .any(|rule| rule.filter.as_deref() == Some(filter.as_str()))
+            {
+                eprintln!(
+                    "{} [[retention]] rules with filter = \"{filter}\" no longer match; \
+                     update them to source:{new_name}",
+                    warning_label()
+                );
+            }
+
+            // Move stored items first, so a storage failure leaves the
+            // configuration untouched; without storage, there is nothing to move
+            let database = match tokio::task::spawn_blocking(Database::new).await? {
+                Ok(database) => Some(database),
+                Err(e) => {
+                    eprintln!(
+                        "{} Storage unavailable, so stored items keep the name '{old_name}': {e}",
+                        warning_label()
+                    );
+                    None
+                }
+            };
+            let links = match database.clone() {
+                Some(database) => {
+                    let (old, new) = (old_name.clone(), new_name.clone());
+                    tokio::task::spawn_blocking(move || database.rename_source(&old, &new))
+                        .await??
+                }
+                None => Vec::new(),
+            };
+            if let Err(e) = file.save() {
+                if let Some(database) = database {
+                    let (old, new) = (old_name.clone(), new_name.clone());
+                    tokio::task::spawn_blocking(move || database.rename_source(&new, &old))
+                        .await??;
+                }
+                return Err(e.into());
+            }
+
+            let detail = format!("{old_name} -> {new_name}");
+            let moved = links.len();
+            audit(AuditEntry::new("sources rename", "rename source", 1).with_detail(&detail));
+            if moved > 0 {
+                audit(
+                    AuditEntry::new("sources rename", "move items", moved)
+                        .with_detail(&detail)
+                        .with_links(links),
+                );
+            }
+            if database.is_some() {
+                eprintln!("Renamed '{old_name}' to '{new_name}' and moved {moved} stored items");
+            } else {
+                eprintln!("Renamed '{old_name}' to '{new_name}'");
+            }
+        }
+        SourceCommand::Remove { name, purge_items } => {
+            let mut file = open()?;
+            file.remove_source(&name)?;
+            let prompt = if purge_items {
+                format!("Remove source '{name}' and delete its stored items?")
+            } else {
+                format!("Remove source '{name}'?")
+            };
+            if !confirm(&prompt, yes)? {
+                eprintln!("Cancelled");
+                return Ok(());
+            }
+
+            // Delete items first, so a storage failure leaves the source configured
+            if purge_items {
+                let source_name = name.clone();
+                let links = tokio::task::spawn_blocking(move || {
+                    Database::new()?.delete_source_items(&source_name)
+                })
+                .await??;
+                eprintln!("Deleted {} items from '{name}'", links.len());
+                audit(
+                    AuditEntry::new("sources remove", "delete", links.len())
+                        .with_detail(&name)
+                        .with_links(links),
+                );
+            }
+            file.save()?;
+            audit(AuditEntry::new("sources remove", "remove source", 1).with_detail(&name));
+            eprintln!("Removed source '{name}'");
+        }
+        SourceCommand::Audit { apply } => {
+            let config = Config::load_strict(config_path)?;
+            let final_urls =
+                tokio::task::spawn_blocking(|| Database::new()?.final_urls()).await??;
+            let moved: Vec<(&RssSource, &String)> = config
+                .sources
+                .rss
+                .iter()
+                .filter_map(|source| Some((source, final_urls.get(&source.name)?)))
+                .filter(|(source, final_url)| source.primary_url() != final_url.as_str())
+                .collect();
+
+            if json {
+                let moved: Vec<_> = moved
+                    .iter()
+                    .map(|(source, final_url)| {
+                        serde_json::json!({
+                            "source": source.name,
+                            "url": source.primary_url(),
+                            "final_url": final_url,
+                        })
+                    })
+                    .collect();
+                print_json(&moved)?;
+            } else if moved.is_empty() {
+                eprintln!("No configuration updates to suggest");
+            }
+            for (source, final_url) in moved.iter().filter(|_| !json) {
+                if plain {
+                    let fields = ["REDIRECTED", &source.name, source.primary_url(), final_url];
+                    println!("{}", fields.join(PLAIN_SEPARATOR));
+                } else {
+                    println!("'{}' redirects to {final_url}", source.name);
+                    println!("  url = \"{final_url}\"  # was {}", source.primary_url());
+                }
+            }
+
+            if apply && !moved.is_empty() {
+                let mut file = open()?;
+                for (source, final_url) in &moved {
+                    let mut updated = (*source).clone();
+                    match updated.urls.first_mut() {
+                        Some(primary) if updated.url.is_empty() => *primary = final_url.to_string(),
+                        _ => updated.url = final_url.to_string(),
+                    }
+                    file.update_source(&source.name, updated)?;
+                }
+                file.save()?;
+                audit(AuditEntry::new(
+                    "sources audit",
+                    "update source",
+                    moved.len(),
+                ));
+                eprintln!("Updated the URLs of {} sources", moved.len());
+            }
+        }
+        SourceCommand::Import { path, on_conflict } => {
+            use clio::opml::{self, ConflictPolicy, Incoming};
+
+            let mut file = open()?;
+            let imported = opml::read(&path)?;
+            if imported.is_empty() {
+                eprintln!("No feeds found in {}", path.display());
+                return Ok(());
+            }
+            let incoming = opml::match_existing(&file.config().sources, imported);
+
+            let (mut added, mut updated, mut kept, mut known) = (0, 0, 0, 0);
+            for incoming in incoming {
+                match incoming {
+                    Incoming::New(source) => {
+                        let name = source.name.clone();
+                        match file.add_source(source) {
+                            Ok(()) => added += 1,
+                            Err(e) => eprintln!("{} Not adding '{name}': {e}", warning_label()),
+                        }
+                    }
+                    Incoming::Known(_) => known += 1,
+                    Incoming::Conflict { existing, imported } => {
+                        let policy = match on_conflict {
+                            Some(policy) => policy,
+                            None => {
+                                let describe = |source: &RssSource| match source.tags.as_slice() {
+                                    [] => format!("'{}'", source.name),
+                                    tags => format!("'{}' [{}]", source.name, tags.join(", ")),
+                                };
+                                let prompt = format!(
+                                    "{} is subscribed as {}, the import has {}.",
+                                    existing.primary_url(),
+                                    describe(&existing),
+                                    describe(&imported)
+                                );
+                                let choices = [
+                                    "Keep the existing source",
+                                    "Replace its name and tags",
+                                    "Merge the tags",
+                                ];
+                                let policies = [
+                                    ConflictPolicy::Keep,
+                                    ConflictPolicy::Replace,
+                                    ConflictPolicy::Merge,
+                                ];
+                                policies[confirm::choose(&prompt, &choices, "--on-conflict")?]
+                            }
+                        };
+                        let resolved = policy.apply(&existing, &imported);
+                        if resolved == existing {
+                            kept += 1;
+                            continue;
+                        }
+                        match file.update_source(&existing.name, resolved) {
+                            Ok(()) => updated += 1,
+                            Err(e) => eprintln!(
+                                "{} Not updating '{}': {e}",
+                                warning_label(),
+                                existing.name
+                            ),
+                        }
+                    }
+                }
+            }
+
+            if added + updated > 0 {
+                file.save()?;
+            }
+            let detail = path.display().to_string();
+            if added > 0 {
+                audit(AuditEntry::new("sources import", "add source", added).with_detail(&detail));
+            }
+            if updated > 0 {
+                audit(
+                    AuditEntry::new("sources import", "update source", updated)
+                        .with_detail(&detail),
+                );
+            }
+            eprintln!(
+                "Added {added} sources, updated {updated}, kept {kept} unchanged; {known} already subscribed"
+            );
+        }
+    }
+    Ok(())
+}
+
+fn execute_rules(config_path: &Path, command: RuleCommand, plain: bool, json: bool) -> Result<()> {
+    use clio::config::{Config, ConfigFile};
+    use clio::display::{PLAIN_SEPARATOR, relative_date};
+    use clio::rules::{LastPull, Replay, RuleSet};
+    use serde_json::json;
+
+    match command {
+        RuleCommand::Export { path } => {
+            let rules = RuleSet::from_config(&Config::load_strict(config_path)?);
+            let contents = rules.to_toml()?;
+            match path {
+                Some(path) => {
+                    writes::ensure_allowed("write the rules")?;
+                    std::fs::write(&path, contents)?;
+                    eprintln!("Wrote {} rules to {}", rules.len(), path.display());
+                }
+                None => print!("{contents}"),
+            }
+        }
+        RuleCommand::Import { path } => {
+            let rules = RuleSet::read(&path)?;
+            let mut file = ConfigFile::open(config_path)?;
+            let added = file.add_rules(&rules)?;
+            if !added.is_empty() {
+                file.save()?;
+                audit(
+                    AuditEntry::new("rules import", "add rule", added.len())
+                        .with_detail(path.display().to_string()),
+                );
+            }
+            eprintln!(
+                "Added {} rules; {} already configured",
+                added.len(),
+                rules.len() - added.len()
+            );
+        }
+        RuleCommand::Test { path, against } => {
+            let config = Config::load_strict(config_path)?;
+            let rules = match path {
+                Some(path) => RuleSet::read(&path)?,
+                None => RuleSet::from_config(&config),
+            };
+            let pulled = match against {
+                Replay::LastPull => LastPull::load(&LastPull::path()?)?.ok_or_else(|| {
+                    anyhow::anyhow!("Nothing has been pulled yet; run `clio pull` first")
+                })?,
+            };
+            let now = chrono::Utc::now();
+            let reports = rules.test(&config, &pulled.items, now)?;
+
+            if json {
+                return print_json(&json!({
+                    "pulled_at": pulled.pulled_at,
+                    "items": pulled.items.len(),
+                    "rules": reports,
+                }));
+            }
+            eprintln!(
+                "Replaying {} items pulled {}",
+                pulled.items.len(),
+                relative_date(pulled.pulled_at, now)
+            );
+            if reports.is_empty() {
+                eprintln!("No rules to test");
+            }
+            for report in reports {
+                if plain {
+                    let count = report.matches.len().to_string();
+                    let fields = ["RULE", &report.rule, &report.description, &count];
+                    println!("{}", fields.join(PLAIN_SEPARATOR));
+                    for item in &report.matches {
+                        let fields = [
+                            "MATCH",
+                            &report.rule,
+                            &item.source_name,
+                            &item.title,
+                            &item.link,
+                        ];
+                        println!("{}", fields.join(PLAIN_SEPARATOR));
+                    }
+                    continue;
+                }
+                println!(
+                    "{} ({}): {} matches",
+                    report.rule,
+                    report.description,
+                    report.matches.len()
+                );
+                for item in &report.matches {
+                    println!("  {} · {}", item.source_name, item.title);
+                    println!("    {}", item.link);
+                }
+            }
+        }
+        RuleCommand::Stats { days } => {
+            use clio::rule_stats::{RuleHitLog, RuleStats};
+
+            let config = Config::load_strict(config_path)?;
+            let now = chrono::Utc::now();
+            let rules = RuleSet::from_config(&config).test(&config, &[], now)?;
+            let pulls = RuleHitLog::open()?.pulls()?;
+            let since = now - chrono::Duration::days(days.into());
+            let stats = RuleStats::collect(&rules, &pulls, since);
+
+            if json {
+                return print_json(&stats);
+            }
+            if stats.is_empty() {
+                eprintln!("No author or alert rules configured");
+            }
+            for rule in stats {
+                let verdict = if rule.never_matched() {
+                    "never matched"
+                } else if rule.matches_most() {
+                    "matches most items"
+                } else {
+                    ""
+                };
+                let last = rule
+                    .last_match
+                    .map(|at| relative_date(at, now))
+                    .unwrap_or_else(|| "never".to_string());
+                if plain {
+                    let mut fields = vec![
+                        rule.rule,
+                        rule.description,
+                        rule.matches.to_string(),
+                        rule.items.to_string(),
+                        rule.pulls.to_string(),
+                        last,
+                    ];
+                    if !verdict.is_empty() {
+                        fields.push(verdict.to_string());
+                    }
+                    println!("{}", fields.join(PLAIN_SEPARATOR));
+                    continue;
+                }
+                println!("{} ({})", rule.rule, rule.description);
+                if rule.pulls == 0 {
+                    println!("  not checked by any pull in the last {days} days");
+                    continue;
+                }
+                let mut details = vec![
+                    format!(
+                        "{} of {} items in {} pulls",
+                        rule.matches, rule.items, rule.pulls
+                    ),
+                    format!("last matched {last}"),
+                ];
+                if !verdict.is_empty() {
+                    details.push(verdict.to_string());
+                }
+                println!("  {}", details.join(" · "));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn execute_mute(config_path: &Path, command: MuteCommand, json: bool) -> Result<()> {
+    use clio::config::{Config, ConfigFile, MuteRule};
+    use clio::rules::RuleSet;
+
+    match command {
+        MuteCommand::Add { keyword } => {
+            let mut file = ConfigFile::open(config_path)?;
+            let muted = file
+                .config()
+                .mutes
+                .iter()
+                .any(|rule| rule.keyword.trim().eq_ignore_ascii_case(keyword.trim()));
+            if muted {
+                eprintln!("'{keyword}' is already muted");
+                return Ok(());
+            }
+            let rules = RuleSet {
+                mutes: vec![MuteRule {
+                    keyword: keyword.clone(),
+                }],
+                ..Default::default()
+            };
+            file.add_rules(&rules)?;
+            file.save()?;
+            audit(AuditEntry::new("mute add", "add mute", 1).with_detail(keyword.clone()));
+            eprintln!("Muted '{keyword}'; matching items are dropped from now on");
+        }
+        MuteCommand::List => {
+            let config = Config::load_strict(config_path)?;
+            if json {
+                return print_json(&config.mutes);
+            }
+            if config.mutes.is_empty() {
+                eprintln!("No keywords muted");
+            }
+            for rule in &config.mutes {
+                println!("{}", rule.keyword);
+            }
+        }
+        MuteCommand::Remove { keyword } => {
+            let mut file = ConfigFile::open(config_path)?;
+            let removed = file.remove_mute(&keyword)?;
+            file.save()?;
+            audit(
+                AuditEntry::new("mute remove", "remove mute", 1)
+                    .with_detail(removed.keyword.clone()),
+            );
+            eprintln!("Unmuted '{}'", removed.keyword);
+        }
     }
+    Ok(())
 }
 
-async fn execute_pull() -> Result<()> {
-    use config::Config;
+async fn execute_diff_config(
+    config_path: &Path,
+    proposed_path: &Path,
+    cassette: Option<PathBuf>,
+    plain: bool,
+    json: bool,
+) -> Result<()> {
+    use clio::cassette::Cassette;
+    use clio::config::Config;
+    use clio::display::PLAIN_SEPARATOR;
+    use clio::fetcher::Fetcher;
+    use clio::http::{FeedClient, RequestGuard};
+    use clio::ingest_diff::{IngestDiff, Simulation};
+    use std::sync::Arc;
 
-    let config = Config::load()?;
+    let current = Config::load_strict(config_path)?;
+    let proposed = Config::load_strict(proposed_path)?;
+    let cassette = match cassette {
+        Some(path) => path,
+        None => Cassette::default_path()?,
+    };
+    if !cassette.exists() {
+        anyhow::bail!(
+            "No recorded feeds at {}; run `CLIO_RECORD=1 clio pull` first",
+            cassette.display()
+        );
+    }
+    eprintln!("Replaying feed responses from {}", cassette.display());
+
+    let now = chrono::Utc::now();
+    let mut simulations = Vec::new();
+    for config in [&current, &proposed] {
+        let client = FeedClient::new(RequestGuard::new(config.settings.allow_private_networks));
+        let replay = Arc::new(Cassette::replay(cassette.clone())?);
+        let sources = clio::source::replayed(config, &client, replay);
+        let (items, stats) = Fetcher::new().quiet().fetch_all(sources).await;
+        simulations.push(Simulation::new(config, &items, &stats, now)?);
+    }
+    let diff = IngestDiff::between(&simulations[0], &simulations[1]);
+
+    if json {
+        return print_json(&diff);
+    }
+    let count = |n: Option<usize>| n.map_or("-".to_string(), |n| n.to_string());
+    if plain {
+        let (before, after) = (diff.items_before.to_string(), diff.items_after.to_string());
+        println!("{}", ["ITEMS", &before, &after].join(PLAIN_SEPARATOR));
+        for flow in diff.changed_sources() {
+            let (before, after) = (count(flow.before), count(flow.after));
+            let error = flow.error.as_deref().unwrap_or_default();
+            let fields = ["SOURCE", &flow.source, &before, &after, error];
+            println!("{}", fields.join(PLAIN_SEPARATOR));
+        }
+        for flow in diff.changed_rules() {
+            let (before, after) = (count(flow.before), count(flow.after));
+            let fields = ["RULE", &flow.rule, &before, &after];
+            println!("{}", fields.join(PLAIN_SEPARATOR));
+        }
+        return Ok(());
+    }
 
+    let change = diff.items_after as i64 - diff.items_before as i64;
     println!(
-        "Fetching content from {} configured sources...",
-        config.sources.rss.len()
+        "Items per pull: {} -> {} ({change:+})",
+        diff.items_before, diff.items_after
     );
-    for source in &config.sources.rss {
-        println!("  - {} ({})", source.name, source.url);
+    let sources: Vec<_> = diff.changed_sources().collect();
+    if !sources.is_empty() {
+        println!("\nSources:");
+    }
+    for flow in sources {
+        let line = match (flow.before, flow.after) {
+            (None, Some(after)) => format!("  + {}: {after} items", flow.source),
+            (Some(before), None) => format!("  - {}: {before} items", flow.source),
+            (before, after) => format!(
+                "    {}: {} -> {} items",
+                flow.source,
+                count(before),
+                count(after)
+            ),
+        };
+        match &flow.error {
+            Some(error) => println!("{line} (not simulated: {error})"),
+            None => println!("{line}"),
+        }
+    }
+    let rules: Vec<_> = diff.changed_rules().collect();
+    if !rules.is_empty() {
+        println!("\nRules:");
+    }
+    for flow in rules {
+        match (flow.before, flow.after) {
+            (None, Some(after)) => println!("  + {}: {after} matches", flow.rule),
+            (Some(before), None) => println!("  - {}: {before} matches", flow.rule),
+            (before, after) => println!(
+                "    {}: {} -> {} matches",
+                flow.rule,
+                count(before),
+                count(after)
+            ),
+        }
+    }
+    if diff.changed_sources().next().is_none() && diff.changed_rules().next().is_none() {
+        eprintln!("The proposed configuration pulls the same items");
+    }
+    Ok(())
+}
+
+async fn execute_source_list(config: &clio::config::Config, plain: bool, json: bool) -> Result<()> {
+    use clio::database::Database;
+    use clio::display::PLAIN_SEPARATOR;
+    use serde_json::json;
+
+    let sources = source_locations(config);
+    if sources.is_empty() {
+        eprintln!("No sources configured");
+        return if json { print_json(&sources) } else { Ok(()) };
+    }
+
+    let health = match tokio::task::spawn_blocking(|| Database::new()?.source_health()).await? {
+        Ok(health) => Some(health),
+        Err(e) => {
+            eprintln!("{} Source health unavailable: {e}", warning_label());
+            None
+        }
+    };
+
+    if json {
+        let sources: Vec<_> = sources
+            .into_iter()
+            .map(|(name, location, paused)| {
+                let health = health.as_ref().map(|health| health.get(name).cloned());
+                json!({
+                    "name": name,
+                    "location": location,
+                    "paused": paused,
+                    "health": health.map(Option::unwrap_or_default),
+                })
+            })
+            .collect();
+        return print_json(&sources);
+    }
+
+    let now = chrono::Utc::now();
+    for (name, location, paused) in sources {
+        let details = health
+            .as_ref()
+            .map(|health| health.get(name).cloned().unwrap_or_default());
+        let details = source_details(paused, details.as_ref(), now);
+
+        if plain {
+            let mut fields = vec![name.to_string(), location];
+            fields.extend(details);
+            println!("{}", fields.join(PLAIN_SEPARATOR));
+        } else {
+            let mut lines = vec![format!("{name} ({location})")];
+            if !details.is_empty() {
+                lines.push(format!("  {}", details.join(" · ")));
+            }
+            for line in lines {
+                // Paused sources are greyed out, and still marked "paused"
+                // for terminals without color
+                if paused {
+                    println!("{}", color::out(Tone::Dim, line));
+                } else {
+                    println!("{line}");
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn execute_source_show(
+    config: &clio::config::Config,
+    name: String,
+    errors: usize,
+    plain: bool,
+    json: bool,
+) -> Result<()> {
+    use clio::database::Database;
+    use clio::display::{PLAIN_SEPARATOR, relative_date};
+    use serde_json::json;
+
+    let Some((name, location, paused)) = source_locations(config)
+        .into_iter()
+        .find(|(source, _, _)| *source == name)
+    else {
+        return Err(clio::ClioError::Config(format!("No source named '{name}'")).into());
+    };
+
+    let source_name = name.to_string();
+    let history = tokio::task::spawn_blocking(move || {
+        let database = Database::new()?;
+        let health = database.source_health()?;
+        let errors = database.fetch_errors(&source_name, errors)?;
+        Ok::<_, clio::ClioError>((
+            health.get(&source_name).cloned().unwrap_or_default(),
+            errors,
+        ))
+    })
+    .await?;
+    let (health, errors) = match history {
+        Ok((health, errors)) => (Some(health), errors),
+        Err(e) => {
+            eprintln!("{} Fetch history unavailable: {e}", warning_label());
+            (None, Vec::new())
+        }
+    };
+
+    if json {
+        return print_json(&json!({
+            "name": name,
+            "location": location,
+            "paused": paused,
+            "health": health,
+            "errors": errors,
+        }));
+    }
+
+    let now = chrono::Utc::now();
+    let details = source_details(paused, health.as_ref(), now);
+    if plain {
+        let mut fields = vec![name.to_string(), location];
+        fields.extend(details);
+        println!("{}", fields.join(PLAIN_SEPARATOR));
+        for error in &errors {
+            println!(
+                "{}",
+                [
+                    "ERROR",
+                    &error.fetched_at.to_rfc3339(),
+                    &error.kind,
+                    &error.message,
+                ]
+                .join(PLAIN_SEPARATOR)
+            );
+        }
+        return Ok(());
+    }
+
+    println!("{name} ({location})");
+    if !details.is_empty() {
+        println!("  {}", details.join(" · "));
+    }
+    if health.is_some() {
+        if errors.is_empty() {
+            println!("\nNo errors recorded");
+        } else {
+            println!("\nRecent errors:");
+            for error in &errors {
+                println!(
+                    "  {:<14} {:<13} {}",
+                    relative_date(error.fetched_at, now),
+                    error.kind,
+                    error.message
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Name, where items come from, and whether paused, for every configured source
+fn source_locations(config: &clio::config::Config) -> Vec<(&str, String, bool)> {
+    config
+        .sources
+        .rss
+        .iter()
+        .map(|source| {
+            let location = source.addresses().join(", then ");
+            (source.name.as_str(), location, source.paused)
+        })
+        .chain(config.sources.synthetic.iter().map(|source| {
+            let location = format!("synthetic, {} items", source.items);
+            (source.name.as_str(), location, false)
+        }))
+        .chain(config.sources.advisories.iter().map(|source| {
+            let location = format!("{} advisories", source.ecosystem);
+            (source.name.as_str(), location, false)
+        }))
+        .chain(config.sources.statuspage.iter().map(|source| {
+            let location = format!("{} (status page)", source.url);
+            (source.name.as_str(), location, false)
+        }))
+        .chain(config.sources.changelog.iter().map(|source| {
+            let location = format!("{} (changelog)", source.url);
+            (source.name.as_str(), location, false)
+        }))
+        .chain(config.sources.twitch.iter().map(|source| {
+            let location = format!("Twitch: {}", source.channels.join(", "));
+            (source.name.as_str(), location, false)
+        }))
+        .chain(config.sources.weather.iter().map(|source| {
+            let location = format!("weather warnings for {}", source.area);
+            (source.name.as_str(), location, false)
+        }))
+        .collect()
+}
+
+/// Whether a source is paused and, when its health is known, how it has been doing
+fn source_details(
+    paused: bool,
+    health: Option<&clio::database::SourceHealth>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Vec<String> {
+    use clio::display::relative_date;
+
+    let mut details = Vec::new();
+    if paused {
+        details.push("paused".to_string());
+    }
+    if let Some(source) = health {
+        details.push(match source.last_success {
+            Some(at) => format!("last fetched {}", relative_date(at, now)),
+            None => "never fetched".to_string(),
+        });
+        details.push(format!("{} items, {} unread", source.items, source.unread));
+        if let (Some(error), Some(at)) = (&source.last_error, source.last_failure) {
+            let label = if source.is_failing() {
+                "failing since"
+            } else {
+                "last error"
+            };
+            details.push(format!("{label} {}: {error}", relative_date(at, now)));
+        }
+    }
+    details
+}
+
+async fn execute_status(total_only: bool, plain: bool, json: bool) -> Result<()> {
+    use clio::database::Database;
+    use clio::display::PLAIN_SEPARATOR;
+    use serde_json::json;
+
+    let counts = tokio::task::spawn_blocking(|| Database::new()?.unread_counts()).await??;
+    let total: u64 = counts.iter().map(|(_, unread)| unread).sum();
+
+    if json {
+        let sources: Vec<_> = counts
+            .iter()
+            .map(|(source, unread)| json!({"source": source, "unread": unread}))
+            .collect();
+        print_json(&json!({"unread": total, "sources": sources}))?;
+    } else if total_only {
+        println!("{total}");
+    } else if plain {
+        println!("total{PLAIN_SEPARATOR}{total}");
+        for (source, unread) in &counts {
+            println!("{source}{PLAIN_SEPARATOR}{unread}");
+        }
+    } else if total == 0 {
+        println!("No unread items");
+    } else {
+        println!("{total} unread");
+        let width = counts
+            .iter()
+            .map(|(_, unread)| unread.to_string().len())
+            .max()
+            .unwrap_or_default();
+        for (source, unread) in &counts {
+            println!("  {unread:>width$}  {source}");
+        }
     }
-    println!("\nNote: Full pull command implementation coming in Stage 6");
     Ok(())
 }
 
-async fn execute_list() -> Result<()> {
-    println!("Listing items...");
-    println!("Note: List command implementation coming in Stage 8");
+async fn execute_read(ids: Vec<String>, all: bool, read: bool, yes: bool) -> Result<()> {
+    use clio::confirm::confirm;
+    use clio::database::Database;
+
+    let (command, change) = if read {
+        ("read", ItemChange::MarkRead)
+    } else {
+        ("unread", ItemChange::MarkUnread)
+    };
+
+    let links = if all {
+        if !confirm(&format!("Mark all items {command}?"), yes)? {
+            eprintln!("Cancelled");
+            return Ok(());
+        }
+        tokio::task::spawn_blocking(move || {
+            let database = Database::new()?;
+            if read {
+                database.mark_all_read(None)
+            } else {
+                database.mark_all_unread(None)
+            }
+        })
+        .await??
+    } else {
+        let items =
+            tokio::task::spawn_blocking(move || Database::new()?.set_read(&ids, read)).await??;
+        items.into_iter().map(|item| item.link).collect()
+    };
+
+    let count = links.len();
+    audit(AuditEntry::new(command, change_name(change), count).with_links(links));
+    if read {
+        record_usage(Activity::Read, count as u64);
+    }
+    eprintln!("Marked {count} items {command}");
     Ok(())
 }
 
-async fn execute_open(item_id: &str) -> Result<()> {
-    println!("Opening item {item_id}...");
-    println!("Note: Open command implementation coming in Stage 9");
+async fn execute_star(ids: Vec<String>, starred: bool) -> Result<()> {
+    use clio::database::Database;
+
+    let (command, change) = if starred {
+        ("star", ItemChange::Star)
+    } else {
+        ("unstar", ItemChange::Unstar)
+    };
+    let items =
+        tokio::task::spawn_blocking(move || Database::new()?.set_starred(&ids, starred)).await??;
+
+    let count = items.len();
+    let links = items.into_iter().map(|item| item.link).collect();
+    audit(AuditEntry::new(command, change_name(change), count).with_links(links));
+    let verb = if starred { "Starred" } else { "Unstarred" };
+    eprintln!("{verb} {count} items");
     Ok(())
 }
+
+async fn execute_mark_all_read(config_path: &Path, filters: Vec<Filter>, yes: bool) -> Result<()> {
+    use clio::config::Config;
+    use clio::confirm::confirm;
+    use clio::database::Database;
+    use clio::filter::matching_sources;
+
+    let config = Config::load_strict(config_path)?;
+    let sources = matching_sources(&config.sources, &filters);
+    let prompt = match &sources {
+        Some(names) => format!("Mark all items from {} source(s) read?", names.len()),
+        None => "Mark all items read?".to_string(),
+    };
+    if !confirm(&prompt, yes)? {
+        eprintln!("Cancelled");
+        return Ok(());
+    }
+
+    let links =
+        tokio::task::spawn_blocking(move || Database::new()?.mark_all_read(sources.as_deref()))
+            .await??;
+
+    let count = links.len();
+    let mut entry = AuditEntry::new("mark-all-read", change_name(ItemChange::MarkRead), count);
+    if !filters.is_empty() {
+        let filters: Vec<String> = filters.iter().map(ToString::to_string).collect();
+        entry = entry.with_detail(filters.join(" "));
+    }
+    audit(entry.with_links(links));
+    eprintln!("Marked {count} items read");
+    Ok(())
+}
+
+async fn execute_purge(older_than: u32, yes: bool) -> Result<()> {
+    use clio::confirm::confirm;
+    use clio::database::Database;
+
+    let prompt =
+        format!("Delete items published more than {older_than} days ago? Starred items are kept.");
+    if !confirm(&prompt, yes)? {
+        eprintln!("Cancelled");
+        return Ok(());
+    }
+
+    let before = chrono::Utc::now() - chrono::Duration::days(i64::from(older_than));
+    let links = tokio::task::spawn_blocking(move || Database::new()?.purge(before)).await??;
+
+    let count = links.len();
+    audit(
+        AuditEntry::new("purge", "delete", count)
+            .with_detail(format!("older than {older_than} days"))
+            .with_links(links),
+    );
+    eprintln!("Deleted {count} items older than {older_than} days");
+    Ok(())
+}
+
+async fn execute_purge_all(
+    config_path: &Path,
+    with_history: bool,
+    with_config: bool,
+    yes: bool,
+) -> Result<()> {
+    use clio::confirm::confirm;
+    use clio::database::Database;
+
+    let mut wiped = vec!["every stored item, including starred ones".to_string()];
+    if with_history {
+        wiped.push("the fetch and digest history".to_string());
+    }
+    if with_config {
+        wiped.push(config_path.display().to_string());
+    }
+    let prompt = format!("Delete {}? This cannot be undone.", wiped.join(" and "));
+    if !confirm(&prompt, yes)? {
+        eprintln!("Cancelled");
+        return Ok(());
+    }
+    if with_config {
+        writes::ensure_allowed("delete the configuration")?;
+    }
+
+    let links = tokio::task::spawn_blocking(move || {
+        let database = Database::new()?;
+        let links = database.purge_all()?;
+        if with_history {
+            database.clear_history()?;
+        }
+        Ok::<_, clio::ClioError>(links)
+    })
+    .await??;
+    let count = links.len();
+    audit(
+        AuditEntry::new("purge", "delete", count)
+            .with_detail("all items")
+            .with_links(links),
+    );
+    eprintln!("Deleted {count} items");
+    if with_history {
+        audit(AuditEntry::new("purge", "clear history", 0));
+        eprintln!("Cleared the fetch and digest history");
+    }
+
+    if with_config {
+        match std::fs::remove_file(config_path) {
+            Ok(()) => {
+                audit(
+                    AuditEntry::new("purge", "delete config", 1)
+                        .with_detail(config_path.display().to_string()),
+                );
+                eprintln!("Deleted {}", config_path.display());
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => anyhow::bail!("Failed to delete {}: {e}", config_path.display()),
+        }
+    }
+    Ok(())
+}
+
+async fn execute_prune(retention: Retention, dry_run: bool, yes: bool) -> Result<()> {
+    use clio::confirm::confirm;
+    use clio::database::Database;
+
+    let mut rules = Vec::new();
+    if let Some(before) = retention.older_than {
+        rules.push(format!(
+            "published before {}",
+            before.format("%Y-%m-%d %H:%M UTC")
+        ));
+    }
+    if let Some(keep) = retention.keep_newest {
+        rules.push(format!("beyond the newest {keep} of each source"));
+    }
+    let rules = rules.join(" or ");
+    if dry_run {
+        let links =
+            tokio::task::spawn_blocking(move || Database::new()?.prunable(&retention)).await??;
+        println!("{} items would be deleted: {rules}", links.len());
+        return Ok(());
+    }
+    let starred = if retention.keep_starred {
+        "Starred items are kept."
+    } else {
+        "Starred items are deleted too."
+    };
+    if !confirm(&format!("Delete items {rules}? {starred}"), yes)? {
+        eprintln!("Cancelled");
+        return Ok(());
+    }
+
+    let links = tokio::task::spawn_blocking(move || Database::new()?.prune(&retention)).await??;
+
+    let count = links.len();
+    audit(
+        AuditEntry::new("prune", "delete", count)
+            .with_detail(rules.clone())
+            .with_links(links),
+    );
+    eprintln!("Deleted {count} items {rules}");
+    Ok(())
+}
+
+/// Apply the configured `[[retention]]` rules, or report what they would delete
+async fn execute_retention(config_path: &Path, dry_run: bool, yes: bool) -> Result<()> {
+    use clio::config::Config;
+    use clio::confirm::confirm;
+    use clio::database::Database;
+    use clio::retention::policies;
+
+    let config = Config::load_strict(config_path)?;
+    let policies = policies(&config, chrono::Utc::now())?;
+    if policies.is_empty() {
+        anyhow::bail!(
+            "No retention rules configured; add [[retention]] rules to the configuration, \
+             or pass --older-than or --keep-newest"
+        );
+    }
+
+    if !dry_run
+        && !confirm(
+            &format!(
+                "Apply {} retention rule(s)? Run with --dry-run to see what each deletes.",
+                policies.len()
+            ),
+            yes,
+        )?
+    {
+        eprintln!("Cancelled");
+        return Ok(());
+    }
+
+    let mut total = 0;
+    for policy in policies {
+        let Some(retention) = policy.retention else {
+            let verb = if dry_run {
+                "would be deleted"
+            } else {
+                "deleted"
+            };
+            println!("{} (keep {}): 0 items {verb}", policy.label, policy.keep);
+            continue;
+        };
+        let links = tokio::task::spawn_blocking(move || {
+            let database = Database::new()?;
+            if dry_run {
+                database.prunable(&retention)
+            } else {
+                database.prune(&retention)
+            }
+        })
+        .await??;
+
+        let count = links.len();
+        total += count;
+        if dry_run {
+            println!(
+                "{} (keep {}): {count} items would be deleted",
+                policy.label, policy.keep
+            );
+            continue;
+        }
+        audit(
+            AuditEntry::new("prune", "delete", count)
+                .with_detail(format!("{}: keep {}", policy.label, policy.keep))
+                .with_links(links),
+        );
+        println!(
+            "{} (keep {}): {count} items deleted",
+            policy.label, policy.keep
+        );
+    }
+
+    if dry_run {
+        eprintln!("{total} items would be deleted; nothing was changed");
+    } else {
+        eprintln!("Deleted {total} items");
+    }
+    Ok(())
+}
+
+async fn execute_fetch_enclosures(
+    config_path: &Path,
+    source: Option<String>,
+    dir: Option<PathBuf>,
+    max_size: u64,
+    jobs: usize,
+    plain: bool,
+) -> Result<()> {
+    use clio::config::Config;
+    use clio::database::Database;
+    use clio::display::media_info;
+    use clio::download::Downloader;
+    use clio::fetcher::Fetcher;
+    use clio::http::{FeedClient, RequestGuard};
+
+    writes::ensure_allowed("download enclosures")?;
+    let config = Config::load_strict(config_path)?;
+    let guard = RequestGuard::new(config.settings.allow_private_networks);
+    let client = FeedClient::new(guard);
+    let mut sources = clio::source::from_config(&config, &client)?;
+    if let Some(name) = &source {
+        sources.retain(|s| s.name() == name);
+        if sources.is_empty() {
+            anyhow::bail!("No active source named '{name}'");
+        }
+    }
+
+    let dir = match dir {
+        Some(dir) => dir,
+        None => dirs::home_dir()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine home directory"))?
+            .join("Podcasts"),
+    };
+
+    // Fail before downloading anything if paths cannot be recorded
+    let database = Database::new()?;
+    let (database, hashes) = tokio::task::spawn_blocking(move || {
+        let hashes = database.body_hashes()?;
+        Ok::<_, clio::ClioError>((database, hashes))
+    })
+    .await??;
+    // Feeds whose body is unchanged since the last run have nothing new to download
+    clio::pull::skip_unchanged(&sources, &hashes);
+
+    let (items, stats) = Fetcher::with_timeout(config.settings.timeout)
+        .with_client(client)
+        .fetch_all(sources)
+        .await;
+    let fetches = stats.fetches;
+    let database = tokio::task::spawn_blocking(move || {
+        database.record_fetches(&fetches)?;
+        Ok::<_, clio::ClioError>(database)
+    })
+    .await??;
+
+    let downloader = Downloader::new(dir, guard)
+        .with_max_bytes(max_size * 1024 * 1024)
+        .with_jobs(jobs);
+
+    let mut recorded = Vec::new();
+    for result in downloader.download_all(&items).await {
+        match result.outcome {
+            Ok(outcome) => {
+                let path = outcome.path().display();
+                match (media_info(&result.enclosure), plain) {
+                    (Some(info), true) => println!("OK | {path} | {info}"),
+                    (None, true) => println!("OK | {path}"),
+                    (Some(info), false) => {
+                        println!("  {} {path} ({info})", color::out(Tone::Success, "✓"))
+                    }
+                    (None, false) => println!("  {} {path}", color::out(Tone::Success, "✓")),
+                }
+                recorded.push((result.item_link, outcome.path().display().to_string()));
+            }
+            Err(e) if plain => eprintln!("FAILED | {} | {e}", result.enclosure.url),
+            Err(e) => eprintln!(
+                "  {} {}: {e}",
+                color::err(Tone::Error, "✗"),
+                result.enclosure.url
+            ),
+        }
+    }
+
+    let downloaded = recorded.len();
+    let links = recorded.iter().map(|(link, _)| link.clone()).collect();
+    tokio::task::spawn_blocking(move || database.record_enclosure_paths(&recorded)).await??;
+    audit(
+        AuditEntry::new("fetch-enclosures", "record enclosure path", downloaded).with_links(links),
+    );
+    eprintln!("{downloaded} enclosures available locally");
+    Ok(())
+}
+
+async fn execute_import(config_path: &Path, format: ImportFormat, path: PathBuf) -> Result<()> {
+    use clio::config::Config;
+    use clio::database::Database;
+    use clio::import::read_export;
+    use clio::sanitize::TextLimits;
+
+    let config = Config::load_strict(config_path)?;
+    let items = read_export(format, &path, &TextLimits::from(&config.settings))?;
+    let read = items.iter().filter(|item| item.is_read).count();
+    let starred = items.iter().filter(|item| item.is_starred).count();
+
+    let total = items.len();
+    let links = items.iter().map(|item| item.link.clone()).collect();
+    tokio::task::spawn_blocking(move || {
+        let database = Database::new()?;
+        database.init_schema()?;
+        database.import_items(&items)
+    })
+    .await??;
+
+    audit(
+        AuditEntry::new("import", "import", total)
+            .with_detail(path.display().to_string())
+            .with_links(links),
+    );
+    eprintln!("Imported {total} items ({read} read, {starred} starred)");
+    Ok(())
+}
+
+/// File formats `clio export` writes
+#[derive(Debug, Clone, Copy)]
+enum ExportFormat {
+    Ics,
+    Bibtex,
+    Csl,
+    Json,
+    Csv,
+    Markdown,
+}
+
+async fn execute_export(
+    config_path: &Path,
+    format: ExportFormat,
+    filters: Vec<Filter>,
+    source: Option<String>,
+    query: ItemQuery,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    use clio::citation;
+    use clio::config::Config;
+    use clio::database::Database;
+    use clio::export;
+    use clio::filter::matching_sources;
+    use clio::ics;
+
+    if let Some(path) = &output {
+        writes::ensure_allowed(&format!("write {}", path.display()))?;
+    }
+    let config = Config::load_strict(config_path)?;
+    let sources = match (matching_sources(&config.sources, &filters), source) {
+        (Some(matching), Some(name)) => Some(matching.into_iter().filter(|s| *s == name).collect()),
+        (matching, name) => matching.or(name.map(|name| vec![name])),
+    };
+    let query = ItemQuery { sources, ..query };
+
+    let items = tokio::task::spawn_blocking(move || Database::new()?.items(&query)).await??;
+
+    let now = chrono::Utc::now();
+    let count = format!("{} items", items.len());
+    let (document, exported) = match format {
+        ExportFormat::Ics => {
+            let events = items
+                .iter()
+                .filter(|item| ics::event_start(item, now).is_some())
+                .count();
+            (ics::to_ics(&items, now), format!("{events} upcoming items"))
+        }
+        ExportFormat::Bibtex => (
+            citation::to_bibtex(&items),
+            format!("{} citations", items.len()),
+        ),
+        ExportFormat::Csl => (
+            citation::to_csl_json(&items) + "\n",
+            format!("{} citations", items.len()),
+        ),
+        ExportFormat::Json => (export::to_json(&items) + "\n", count),
+        ExportFormat::Csv => (export::to_csv(&items), count),
+        ExportFormat::Markdown => (export::to_markdown(&items), count),
+    };
+    match output {
+        Some(path) => {
+            std::fs::write(&path, document)?;
+            eprintln!("Exported {exported} to {}", path.display());
+        }
+        None => print!("{document}"),
+    }
+    Ok(())
+}
+
+async fn execute_digest(
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    output: Option<PathBuf>,
+    email: Option<String>,
+) -> Result<()> {
+    use clio::database::Database;
+    use clio::digest::{self, Digest};
+    use clio::display::sort_newest_first;
+
+    if let Some(path) = &output {
+        writes::ensure_allowed(&format!("write {}", path.display()))?;
+    }
+    let now = chrono::Utc::now();
+    let (database, since, mut items) = tokio::task::spawn_blocking(move || {
+        let database = Database::new()?;
+        let since = match since {
+            Some(since) => since,
+            None => database
+                .last_digest()?
+                .unwrap_or(now - chrono::Duration::days(1)),
+        };
+        let query = ItemQuery {
+            hide_archived: true,
+            added_since: Some(since),
+            ..Default::default()
+        };
+        let items = database.items(&query)?;
+        Ok::<_, clio::ClioError>((database, since, items))
+    })
+    .await??;
+
+    sort_newest_first(&mut items, now);
+    let digest = Digest::new(items, since);
+    if digest.is_empty() {
+        eprintln!("No new items since {}", since.format("%Y-%m-%d %H:%M UTC"));
+    } else {
+        match (&output, &email) {
+            (Some(path), _) => {
+                std::fs::write(path, digest.render())?;
+                eprintln!(
+                    "Wrote a digest of {} items to {}",
+                    digest.item_count(),
+                    path.display()
+                );
+            }
+            (None, Some(address)) => {
+                digest::send_email(&digest, address)?;
+                eprintln!(
+                    "Emailed a digest of {} items to {address}",
+                    digest.item_count()
+                );
+            }
+            (None, None) => print!("{}", digest.render()),
+        }
+    }
+
+    let count = digest.item_count();
+    tokio::task::spawn_blocking(move || database.record_digest(now, count)).await??;
+    Ok(())
+}
+
+async fn execute_serve(
+    config_path: &Path,
+    bind: std::net::IpAddr,
+    port: u16,
+    max_pull_age: u32,
+) -> Result<()> {
+    use clio::audit::AuditLog;
+    use clio::config::Config;
+    use clio::database::Database;
+    use clio::serve::Server;
+
+    let config = Config::load_strict(config_path)?;
+    let database = tokio::task::spawn_blocking(Database::new).await??;
+    let listener = tokio::net::TcpListener::bind((bind, port)).await?;
+    let address = listener.local_addr()?;
+
+    if !bind.is_loopback() {
+        eprintln!(
+            "{} listening on {bind}, so anyone who can reach this machine can read your items",
+            warning_label()
+        );
+    }
+    eprintln!("Serving your items at http://{address}/ (Ctrl-C to stop)");
+    Server::new(database, config)
+        .with_config_path(config_path.to_path_buf())
+        .with_audit_log(AuditLog::open()?)
+        .with_max_pull_age(chrono::Duration::hours(i64::from(max_pull_age)))
+        .run(listener)
+        .await?;
+    Ok(())
+}
+
+async fn execute_health(max_pull_age: u32, plain: bool) -> Result<()> {
+    use clio::database::Database;
+    use clio::display::PLAIN_SEPARATOR;
+    use clio::health::HealthReport;
+
+    let max_pull_age = chrono::Duration::hours(i64::from(max_pull_age));
+    let report = tokio::task::spawn_blocking(move || {
+        HealthReport::check(Database::new(), max_pull_age, chrono::Utc::now())
+    })
+    .await?;
+
+    for check in &report.checks {
+        if plain {
+            let status = if check.healthy { "OK" } else { "FAILED" };
+            println!(
+                "{}",
+                [status, check.name, &check.detail].join(PLAIN_SEPARATOR)
+            );
+        } else {
+            let mark = if check.healthy {
+                color::out(Tone::Success, "✓")
+            } else {
+                color::out(Tone::Error, "✗")
+            };
+            println!("  {mark} {}: {}", check.name, check.detail);
+        }
+    }
+    if !report.healthy() {
+        anyhow::bail!("clio is unhealthy");
+    }
+    Ok(())
+}
+
+async fn execute_stats_items(days: u32, plain: bool, json: bool) -> Result<()> {
+    use clio::database::Database;
+    use clio::display::{PLAIN_SEPARATOR, bar};
+    use serde_json::json;
+
+    const BAR_WIDTH: usize = 30;
+    /// Sources named as the most and the least active
+    const NAMED: usize = 3;
+
+    let now = chrono::Utc::now();
+    let since = now - chrono::Duration::days(i64::from(days));
+    let (sources, per_day, backlog) = tokio::task::spawn_blocking(move || {
+        let database = Database::new()?;
+        let sources = database.item_counts_by_source(since)?;
+        let per_day = database.items_per_day(since, now)?;
+        let backlog = database.backlog_per_day(since, now)?;
+        Ok::<_, clio::ClioError>((sources, per_day, backlog))
+    })
+    .await??;
+
+    let items: u64 = sources.iter().map(|source| source.items).sum();
+    let read: u64 = sources.iter().map(|source| source.read).sum();
+    if json {
+        let per_day: Vec<_> = per_day
+            .iter()
+            .map(|(day, count)| json!({"day": day, "items": count}))
+            .collect();
+        let backlog: Vec<_> = backlog
+            .iter()
+            .map(|(day, count)| json!({"day": day, "unread": count}))
+            .collect();
+        return print_json(&json!({
+            "items": items,
+            "read": read,
+            "days": days,
+            "sources": sources,
+            "per_day": per_day,
+            "backlog": backlog,
+        }));
+    }
+    if items == 0 {
+        eprintln!("No items stored");
+        return Ok(());
+    }
+    let read_percent = read * 100 / items;
+
+    if plain {
+        let total = [
+            items.to_string(),
+            read.to_string(),
+            format!("{read_percent}%"),
+        ];
+        println!("TOTAL{PLAIN_SEPARATOR}{}", total.join(PLAIN_SEPARATOR));
+        for source in &sources {
+            let fields = [
+                source.source_name.clone(),
+                source.items.to_string(),
+                source.read.to_string(),
+                source.recent.to_string(),
+            ];
+            println!("SOURCE{PLAIN_SEPARATOR}{}", fields.join(PLAIN_SEPARATOR));
+        }
+        for (day, count) in &per_day {
+            println!("DAY{PLAIN_SEPARATOR}{day}{PLAIN_SEPARATOR}{count}");
+        }
+        for (day, count) in &backlog {
+            println!("BACKLOG{PLAIN_SEPARATOR}{day}{PLAIN_SEPARATOR}{count}");
+        }
+        return Ok(());
+    }
+
+    println!("{items} items stored, {read} read ({read_percent}%)");
+
+    println!("\nItems per source (published in the last {days} days):");
+    let width = sources
+        .iter()
+        .map(|source| source.source_name.chars().count())
+        .max()
+        .unwrap_or(0);
+    for source in &sources {
+        println!(
+            "  {:<width$}  {:>6} items  {:>3}% read  {:>5} recent",
+            source.source_name,
+            source.items,
+            source.read * 100 / source.items.max(1),
+            source.recent,
+        );
+    }
+
+    println!("\nItems per day (UTC):");
+    let most = per_day.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    for (day, count) in &per_day {
+        println!(
+            "  {}  {:<BAR_WIDTH$} {count}",
+            day.format("%b %d"),
+            bar(*count, most, BAR_WIDTH)
+        );
+    }
+
+    println!("\nUnread at the end of each day (UTC):");
+    let most = backlog.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    for (day, count) in &backlog {
+        println!(
+            "  {}  {:<BAR_WIDTH$} {count}",
+            day.format("%b %d"),
+            bar(*count, most, BAR_WIDTH)
+        );
+    }
+
+    // Sources are ordered busiest first
+    if sources.len() > 1 {
+        let named = NAMED.min(sources.len() / 2);
+        let describe = |source: &clio::database::SourceItemCounts| {
+            format!("{} ({})", source.source_name, source.recent)
+        };
+        let most: Vec<String> = sources.iter().take(named).map(describe).collect();
+        let least: Vec<String> = sources.iter().rev().take(named).map(describe).collect();
+        println!("\nMost active: {}", most.join(", "));
+        println!("Least active: {}", least.join(", "));
+    }
+    Ok(())
+}
+
+async fn execute_stats_bandwidth(days: u32, plain: bool, json: bool) -> Result<()> {
+    use clio::database::Database;
+    use clio::display::{PLAIN_SEPARATOR, format_size};
+    use serde_json::json;
+
+    let since = chrono::Utc::now() - chrono::Duration::days(i64::from(days));
+    let usage =
+        tokio::task::spawn_blocking(move || Database::new()?.bandwidth_by_source(since)).await??;
+
+    let total: u64 = usage.iter().map(|source| source.bytes).sum();
+    if json {
+        return print_json(&json!({"days": days, "bytes": total, "sources": usage}));
+    }
+    if total == 0 {
+        println!("No fetches recorded in the last {days} days");
+        return Ok(());
+    }
+
+    println!("Downloaded {} in the last {days} days:", format_size(total));
+    let width = usage
+        .iter()
+        .map(|source| source.source_name.chars().count())
+        .max()
+        .unwrap_or(0);
+    for source in &usage {
+        let per_fetch = source.bytes / source.fetches.max(1);
+        if plain {
+            let fields = [
+                source.source_name.clone(),
+                format_size(source.bytes),
+                format!("{}%", source.bytes * 100 / total),
+                format!("{} fetches", source.fetches),
+                format!("{} not modified", source.not_modified),
+                format!("{} each", format_size(per_fetch)),
+            ];
+            println!("{}", fields.join(PLAIN_SEPARATOR));
+            continue;
+        }
+        let not_modified = match source.not_modified {
+            0 => String::new(),
+            n => format!(" ({n} not modified)"),
+        };
+        println!(
+            "  {:<width$}  {:>8}  {:>3}%  {} fetches{not_modified}, {} each",
+            source.source_name,
+            format_size(source.bytes),
+            source.bytes * 100 / total,
+            source.fetches,
+            format_size(per_fetch),
+        );
+    }
+    Ok(())
+}
+
+fn execute_stats_me(plain: bool, json: bool) -> Result<()> {
+    use clio::display::{PLAIN_SEPARATOR, bar};
+    use clio::usage::{UsageSummary, WEEKS};
+
+    const BAR_WIDTH: usize = 30;
+
+    let events = UsageLog::open()?.events()?;
+    if json {
+        return print_json(&UsageSummary::new(&events, chrono::Local::now()));
+    }
+    if events.is_empty() {
+        println!("No usage recorded yet");
+        return Ok(());
+    }
+    let summary = UsageSummary::new(&events, chrono::Local::now());
+
+    println!(
+        "{} pulls ({} in the last 7 days), {} items read",
+        summary.pulls, summary.recent_pulls, summary.items_read
+    );
+
+    println!("\nItems read per week (last {WEEKS} weeks):");
+    let most = summary.reads_per_week.iter().map(|(_, count)| *count).max();
+    for (week, count) in &summary.reads_per_week {
+        let label = week.format("%b %d");
+        if plain {
+            println!("WEEK OF {label}{PLAIN_SEPARATOR}{count} read");
+        } else {
+            println!(
+                "  {label}  {:<BAR_WIDTH$} {count}",
+                bar(*count, most.unwrap_or(0), BAR_WIDTH)
+            );
+        }
+    }
+
+    println!("\nBusiest reading hours:");
+    let most = summary.reads_by_hour.iter().copied().max().unwrap_or(0);
+    for (hour, count) in summary.reads_by_hour.iter().enumerate() {
+        if plain {
+            if *count > 0 {
+                println!("HOUR {hour:02}:00{PLAIN_SEPARATOR}{count} read");
+            }
+        } else {
+            println!(
+                "  {hour:02}:00  {:<BAR_WIDTH$} {count}",
+                bar(*count, most, BAR_WIDTH)
+            );
+        }
+    }
+    Ok(())
+}
+
+fn execute_config_schema() -> Result<()> {
+    print!("{}", clio::config::Config::json_schema());
+    Ok(())
+}
+
+fn execute_config_validate(config_path: &Path, network: bool, plain: bool) -> Result<()> {
+    use clio::config::Config;
+
+    let config = Config::load_unvalidated(config_path)?;
+    let mut problems = config.problems();
+    if network {
+        problems.extend(block_on(unreachable_sources(&config))?);
+    }
+
+    let (errors, warnings) = print_problems(&problems, plain);
+    if errors > 0 {
+        anyhow::bail!("Configuration has {errors} error(s) and {warnings} warning(s)");
+    }
+    let sources = config.sources.rss.len()
+        + config.sources.synthetic.len()
+        + config.sources.advisories.len()
+        + config.sources.statuspage.len()
+        + config.sources.changelog.len()
+        + config.sources.twitch.len()
+        + config.sources.weather.len();
+    eprintln!("Configuration is valid: {sources} source(s), {warnings} warning(s)");
+    Ok(())
+}
+
+fn execute_config_edit(config_path: &Path, plain: bool) -> Result<()> {
+    use clio::config::{Config, Problem, Severity};
+    use clio::confirm::confirm;
+    use clio::editor;
+    use std::io::{self, IsTerminal};
+
+    writes::ensure_allowed("edit the configuration")?;
+    if !config_path.exists() {
+        Config::init(config_path, false)?;
+        eprintln!(
+            "Created {} from the example configuration",
+            config_path.display()
+        );
+    }
+    let before = Config::read_file(config_path).ok();
+
+    let after = loop {
+        editor::edit(config_path)?;
+        let (after, problems) = match Config::read_file(config_path) {
+            Ok(after) => {
+                let problems = after.problems();
+                (Some(after), problems)
+            }
+            Err(e) => (None, vec![Problem::error(None, e.to_string())]),
+        };
+        let (errors, warnings) = print_problems(&problems, plain);
+        if let Some(after) = after
+            && !problems.iter().any(|p| p.severity == Severity::Error)
+        {
+            break after;
+        }
+
+        let interactive = io::stdin().is_terminal() && io::stdout().is_terminal();
+        if !interactive || !confirm("Edit again to fix the errors?", false)? {
+            anyhow::bail!(
+                "Configuration saved with {errors} error(s) and {warnings} warning(s); \
+                 run `clio config edit` to fix it"
+            );
+        }
+    };
+
+    let changes = before.unwrap_or_default().source_changes(&after);
+    for name in &changes.added {
+        eprintln!("  + {name}");
+    }
+    for name in &changes.removed {
+        eprintln!("  - {name}");
+    }
+    let changed = changes.added.len() + changes.removed.len();
+    audit(AuditEntry::new(
+        "config edit",
+        "edit configuration",
+        changed,
+    ));
+    eprintln!(
+        "Configuration saved: {} source(s) added, {} removed",
+        changes.added.len(),
+        changes.removed.len()
+    );
+    Ok(())
+}
+
+/// Print `problems` one per line, returning the number of errors and warnings
+fn print_problems(problems: &[clio::config::Problem], plain: bool) -> (usize, usize) {
+    use clio::config::Severity;
+    use clio::display::PLAIN_SEPARATOR;
+
+    for problem in problems {
+        let source = problem.source.as_deref();
+        if plain {
+            let severity = match problem.severity {
+                Severity::Error => "ERROR",
+                Severity::Warning => "WARNING",
+            };
+            let fields = [severity, source.unwrap_or("-"), &problem.message];
+            println!("{}", fields.join(PLAIN_SEPARATOR));
+        } else {
+            let mark = match problem.severity {
+                Severity::Error => color::out(Tone::Error, "✗"),
+                Severity::Warning => color::out(Tone::Warning, "!"),
+            };
+            match source {
+                Some(source) => println!("  {mark} {source}: {}", problem.message),
+                None => println!("  {mark} {}", problem.message),
+            }
+        }
+    }
+
+    let errors = problems
+        .iter()
+        .filter(|problem| problem.severity == Severity::Error)
+        .count();
+    (errors, problems.len() - errors)
+}
+
+/// A problem for each active feed that cannot be fetched and parsed
+async fn unreachable_sources(config: &clio::config::Config) -> Result<Vec<clio::config::Problem>> {
+    use clio::config::Problem;
+    use clio::fetcher::Fetcher;
+    use clio::http::{FeedClient, RequestGuard};
+    use clio::source::rss::RssSource;
+    use std::sync::Arc;
+
+    let client = FeedClient::new(RequestGuard::new(config.settings.allow_private_networks));
+    let fetcher = Fetcher::with_timeout(config.settings.timeout);
+    let checks = config.sources.active().map(|source| {
+        let feed = RssSource::new(source.name.clone(), source.primary_url().to_string())
+            .with_mirrors(source.mirrors())
+            .with_max_redirects(source.max_redirects(&config.settings))
+            .with_client(client.clone());
+        let fetcher = &fetcher;
+        async move {
+            let error = fetcher.fetch_one(Arc::new(feed)).await.err()?;
+            Some(Problem::error(Some(&source.name), error.to_string()))
+        }
+    });
+    Ok(futures::future::join_all(checks)
+        .await
+        .into_iter()
+        .flatten()
+        .collect())
+}
+
+async fn execute_db_init() -> Result<()> {
+    use clio::database::{Database, MIGRATIONS};
+
+    let latest = MIGRATIONS.last().map_or(0, |migration| migration.version);
+    let version = tokio::task::spawn_blocking(|| {
+        let database = Database::new()?;
+        let version = database.schema_version()?;
+        database.init_schema()?;
+        Ok::<_, clio::ClioError>(version)
+    })
+    .await??;
+
+    if version == latest {
+        eprintln!("The schema is up to date at version {latest}");
+    } else {
+        audit(AuditEntry::new(
+            "db init",
+            "migrate",
+            (latest - version) as usize,
+        ));
+        eprintln!("Initialized the schema at version {latest}");
+    }
+    Ok(())
+}
+
+async fn execute_db_reset(yes: bool) -> Result<()> {
+    use clio::confirm::confirm;
+    use clio::database::Database;
+
+    let prompt = "Delete every stored item, including starred ones, and the fetch and digest \
+                  history, then recreate the schema? This cannot be undone.";
+    if !confirm(prompt, yes)? {
+        eprintln!("Cancelled");
+        return Ok(());
+    }
+
+    tokio::task::spawn_blocking(|| Database::new()?.reset()).await??;
+    audit(AuditEntry::new("db reset", "delete", 0).with_detail("dropped and recreated the schema"));
+    eprintln!("Reset the schema; storage is empty");
+    Ok(())
+}
+
+async fn execute_db_verify(repair: bool, yes: bool, plain: bool, json: bool) -> Result<()> {
+    use clio::confirm::confirm;
+    use clio::database::Database;
+    use clio::display::PLAIN_SEPARATOR;
+
+    let (database, reports) = tokio::task::spawn_blocking(|| {
+        let database = Database::new()?;
+        let reports = database.verify()?;
+        Ok::<_, clio::ClioError>((database, reports))
+    })
+    .await??;
+    let damaged: Vec<_> = reports.into_iter().filter(|r| r.count > 0).collect();
+
+    if json {
+        print_json(&damaged)?;
+    } else {
+        for report in &damaged {
+            if plain {
+                let count = report.count.to_string();
+                let fields = [report.anomaly.to_string(), count, report.examples.join(" ")];
+                println!("{}", fields.join(PLAIN_SEPARATOR));
+                continue;
+            }
+            println!("{}: {} item(s)", report.anomaly, report.count);
+            for link in &report.examples {
+                println!("  - {link}");
+            }
+        }
+    }
+    if damaged.is_empty() {
+        eprintln!("No damaged items found");
+        return Ok(());
+    }
+    if !repair {
+        eprintln!("Run `clio db verify --repair` to fix them");
+        return Ok(());
+    }
+
+    let total: u64 = damaged.iter().map(|report| report.count).sum();
+    let deleted: u64 = damaged
+        .iter()
+        .filter(|report| report.anomaly.deletes())
+        .map(|report| report.count)
+        .sum();
+    let prompt = if deleted > 0 {
+        format!("Repair {total} item(s)? {deleted} without a link or source will be deleted.")
+    } else {
+        format!("Repair {total} item(s)?")
+    };
+    if !confirm(&prompt, yes)? {
+        eprintln!("Cancelled");
+        return Ok(());
+    }
+
+    for report in damaged {
+        let anomaly = report.anomaly;
+        let database = database.clone();
+        let links = tokio::task::spawn_blocking(move || database.repair(anomaly)).await??;
+        let action = if anomaly.deletes() {
+            "delete"
+        } else {
+            "repair"
+        };
+        eprintln!("Fixed {} item(s): {anomaly}", links.len());
+        audit(
+            AuditEntry::new("db verify", action, links.len())
+                .with_detail(anomaly.to_string())
+                .with_links(links),
+        );
+    }
+    Ok(())
+}
+
+async fn execute_db_rotate(config_path: &Path, older_than: Option<u32>) -> Result<()> {
+    use clio::config::Config;
+
+    let days = match older_than {
+        Some(days) => days,
+        None => Config::load_strict(config_path)?
+            .settings
+            .cold_storage_after_days
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No cold_storage_after_days setting; set it or pass --older-than DAYS"
+                )
+            })?,
+    };
+    rotate_cold_storage(days).await
+}
+
+/// Move stored items published more than `days` ago into the yearly
+/// archive files
+async fn rotate_cold_storage(days: u32) -> Result<()> {
+    use clio::cold_storage::{ColdStorage, rotate};
+    use clio::database::Database;
+
+    let before = chrono::Utc::now() - chrono::Duration::days(i64::from(days));
+    let cold = ColdStorage::open()?;
+    let dir = cold.dir().to_path_buf();
+    let moved =
+        tokio::task::spawn_blocking(move || rotate(&Database::new()?, &cold, before)).await??;
+
+    let total: usize = moved.values().sum();
+    if total == 0 {
+        eprintln!("No unstarred items published more than {days} days ago to archive");
+        return Ok(());
+    }
+    let files = moved
+        .iter()
+        .map(|(year, count)| format!("{count} to items-{year}.db"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    audit(AuditEntry::new("db rotate", "move to archive", total).with_detail(&files));
+    eprintln!(
+        "Moved {total} items published more than {days} days ago into {}: {files}",
+        dir.display()
+    );
+    Ok(())
+}
+
+async fn execute_db_migrate(dry_run: bool, json: bool) -> Result<()> {
+    use clio::database::{Database, MIGRATIONS};
+    use serde_json::json;
+
+    let latest = MIGRATIONS.last().map_or(0, |migration| migration.version);
+    let (version, pending) = tokio::task::spawn_blocking(|| {
+        let database = Database::new()?;
+        Ok::<_, clio::ClioError>((database.schema_version()?, database.pending_migrations()?))
+    })
+    .await??;
+
+    eprintln!("Schema version {version} of {latest}");
+    if pending.is_empty() {
+        eprintln!("The schema is up to date");
+    } else {
+        eprintln!("Pending migrations:");
+        for migration in &pending {
+            eprintln!("  {}. {}", migration.version, migration.description);
+        }
+    }
+
+    let applied = if dry_run || pending.is_empty() {
+        Vec::new()
+    } else {
+        let applied = tokio::task::spawn_blocking(|| Database::new()?.migrate()).await??;
+        audit(AuditEntry::new("db migrate", "migrate", applied.len()));
+        eprintln!(
+            "Applied {} migrations; the schema is at version {latest}",
+            applied.len()
+        );
+        applied
+    };
+
+    if json {
+        let pending: Vec<_> = pending
+            .iter()
+            .map(|m| json!({"version": m.version, "description": m.description}))
+            .collect();
+        let applied: Vec<u32> = applied.iter().map(|migration| migration.version).collect();
+        print_json(&json!({
+            "version": version,
+            "latest": latest,
+            "pending": pending,
+            "applied": applied,
+        }))?;
+    }
+    Ok(())
+}
+
+async fn execute_auth_rotate(new_key: String) -> Result<()> {
+    use clio::config::SupabaseConfig;
+    use clio::database::Database;
+
+    let config = SupabaseConfig::rotated_from_env(new_key)?;
+    let url = config.url().to_string();
+
+    // The Supabase client blocks on its own runtime, so keep it off the async executor
+    tokio::task::spawn_blocking(move || Database::connect(config)?.verify_connection()).await??;
+
+    println!("New secret key verified against {url}");
+    println!();
+    println!("To complete the rotation:");
+    println!("  1. Set SUPABASE_SECRET_KEY to the new key wherever clio runs");
+    println!("     (shell profile, keyring, service unit, or container secrets).");
+    println!("  2. Set SUPABASE_PREVIOUS_SECRET_KEY to the old key so running processes");
+    println!("     fall back to it if the new key is rejected during the switch.");
+    println!("  3. Revoke the old key in the Supabase dashboard, then unset");
+    println!("     SUPABASE_PREVIOUS_SECRET_KEY.");
+    Ok(())
+}
+
+fn execute_audit_tail(lines: usize, plain: bool) -> Result<()> {
+    use clio::display::PLAIN_SEPARATOR;
+
+    let entries = AuditLog::open()?.tail(lines)?;
+    if entries.is_empty() {
+        eprintln!("No changes recorded yet");
+        return Ok(());
+    }
+
+    for entry in entries {
+        let at = entry
+            .at
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string();
+        let mut fields = vec![
+            at,
+            entry.command,
+            format!("{} ({})", entry.action, entry.rows),
+        ];
+        fields.extend(entry.detail);
+
+        if plain {
+            fields.extend(entry.links);
+            println!("{}", fields.join(PLAIN_SEPARATOR));
+        } else {
+            println!("{}", fields.join("  "));
+            for link in entry.links {
+                println!("    {link}");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Open `items` in the browser, in order, and mark them read, auditing them
+/// under `command`
+///
+/// When the browser fails, the items opened before it are still marked read.
+async fn open_and_mark_read(
+    database: clio::database::Database,
+    items: Vec<clio::Item>,
+    command: &str,
+) -> Result<()> {
+    let mut opened = Vec::new();
+    let mut failure = None;
+    for item in items {
+        match clio::browser::open(&item.link) {
+            Ok(()) => opened.push(item),
+            Err(e) => {
+                failure = Some(e);
+                break;
+            }
+        }
+    }
+
+    if !opened.is_empty() {
+        let ids: Vec<String> = opened.iter().map(|item| item.id.clone()).collect();
+        tokio::task::spawn_blocking(move || database.update_items(&ids, ItemChange::MarkRead))
+            .await??;
+        record_usage(Activity::Read, opened.len() as u64);
+        let links = opened.into_iter().map(|item| item.link).collect::<Vec<_>>();
+        audit(
+            AuditEntry::new(command, change_name(ItemChange::MarkRead), links.len())
+                .with_links(links),
+        );
+    }
+    match failure {
+        Some(e) => Err(e.into()),
+        None => Ok(()),
+    }
+}
+
+/// Columns to wrap item details to: the terminal's width up to 100, for
+/// readable lines, or 80 when not writing to a terminal
+fn details_width() -> usize {
+    use std::io::IsTerminal;
+
+    match crossterm::terminal::size() {
+        Ok((columns, _)) if std::io::stdout().is_terminal() => usize::from(columns).min(100),
+        _ => 80,
+    }
+}
+
+/// Print pages of values as one pretty-printed JSON array, like
+/// [`print_json`] would print them all at once, without holding them all
+/// in memory
+fn print_json_pages<T: serde::Serialize>(
+    pages: impl Iterator<Item = Result<Vec<T>, clio::ClioError>>,
+) -> Result<()> {
+    use std::io::Write;
+
+    let mut out = std::io::stdout().lock();
+    let mut first = true;
+    for page in pages {
+        for value in page? {
+            let json = serde_json::to_string_pretty(&value)?;
+            let open = if first { "[" } else { "," };
+            write!(out, "{open}\n  {}", json.replace('\n', "\n  "))?;
+            first = false;
+        }
+    }
+    writeln!(out, "{}", if first { "[]" } else { "\n]" })?;
+    Ok(())
+}
+
+/// "Warning:", colored when standard error is
+fn warning_label() -> String {
+    color::err(Tone::Warning, "Warning:")
+}
+
+/// Print a value as pretty JSON to standard output, for --json
+fn print_json(value: &impl serde::Serialize) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Record a change in the audit log
+///
+/// Failing to write the log only warns, since the change itself was made.
+fn audit(entry: AuditEntry) {
+    if let Err(e) = AuditLog::open().and_then(|log| log.append(&entry)) {
+        eprintln!("{} {e}", warning_label());
+    }
+}
+
+/// Announce pulled items matching the configured alerts
+///
+/// Alerts are a side effect of pulling, so failures are warnings.
+fn send_alerts(config: &clio::config::Config, items: &[clio::Item]) {
+    use clio::alerts::{self, AlertState};
+
+    if config.alerts.is_empty() {
+        return;
+    }
+    let result = AlertState::open().and_then(|mut state| {
+        let matches = alerts::matches(&config.alerts, items);
+        for alert in state.batch(matches, &config.alert_channels, chrono::Utc::now()) {
+            if let Err(e) = alerts::send(&alert) {
+                eprintln!("{} {e}", warning_label());
+            }
+        }
+        state.save()
+    });
+    if let Err(e) = result {
+        eprintln!("{} {e}", warning_label());
+    }
+}
+
+/// Count what each rule matched in a pull, for `clio rules stats`
+fn record_rule_hits(config: &clio::config::Config, pulled: &clio::rules::LastPull) {
+    use clio::rule_stats::{PullHits, RuleHitLog};
+    use clio::rules::RuleSet;
+
+    let rules = RuleSet::from_config(config);
+    if rules.alerts.is_empty() && rules.authors.is_empty() && rules.mutes.is_empty() {
+        return;
+    }
+    let recorded = rules
+        .test(config, &pulled.items, pulled.pulled_at)
+        .map(|reports| PullHits::new(pulled.pulled_at, pulled.items.len(), &reports))
+        .and_then(|hits| RuleHitLog::open()?.record(&hits));
+    if let Err(e) = recorded {
+        eprintln!("{} {e}", warning_label());
+    }
+}
+
+/// Count an activity for `clio stats --me`
+///
+/// Usage stats are a nicety, so failing to record them is silently ignored.
+fn record_usage(activity: Activity, count: u64) {
+    let _ = UsageLog::open().and_then(|log| log.record(activity, count));
+}
+
+fn change_name(change: ItemChange) -> &'static str {
+    match change {
+        ItemChange::MarkRead => "mark read",
+        ItemChange::MarkUnread => "mark unread",
+        ItemChange::Star => "star",
+        ItemChange::Unstar => "unstar",
+        ItemChange::Archive => "archive",
+    }
+}
@@ -0,0 +1,423 @@
+use crate::config::{RssSource, Sources};
+use crate::error::{ClioError, ErrorContext};
+use crate::sanitize::strip_control;
+use crate::source::weather;
+use clap::ValueEnum;
+use html_escape::encode_double_quoted_attribute;
+use quick_xml::Reader;
+use quick_xml::encoding::Decoder;
+use quick_xml::events::{BytesStart, Event};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use url::Url;
+
+/// Render the configured subscriptions as an OPML 2.0 document
+///
+/// Every source read from a feed is included: RSS and Atom feeds, and the
+/// CAP Atom feeds of weather warnings. Paused sources are included, since
+/// they are still subscriptions; other readers have no notion of pausing, so
+/// the flag is not exported.
+pub fn export(sources: &Sources) -> String {
+    let feeds = sources
+        .rss
+        .iter()
+        .map(|source| (source.name.as_str(), source.primary_url().to_string()));
+    let warnings = sources
+        .weather
+        .iter()
+        .map(|source| (source.name.as_str(), weather::feed_url(source)));
+    let outlines: String = feeds
+        .chain(warnings)
+        .map(|(name, url)| {
+            format!(
+                "    <outline type=\"rss\" text=\"{name}\" title=\"{name}\" xmlUrl=\"{url}\"/>\n",
+                name = encode_double_quoted_attribute(name),
+                url = encode_double_quoted_attribute(&url),
+            )
+        })
+        .collect();
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <opml version=\"2.0\">\n  \
+         <head>\n    <title>clio subscriptions</title>\n  </head>\n  \
+         <body>\n{outlines}  </body>\n\
+         </opml>\n"
+    )
+}
+
+/// Read the feeds of an OPML file, as exported by most feed readers
+pub fn read(path: &Path) -> Result<Vec<RssSource>, ClioError> {
+    let document =
+        fs::read_to_string(path).clio_config_err(format!("Failed to read {}", path.display()))?;
+    import(&document)
+}
+
+/// Parse the feeds of an OPML document
+///
+/// Every outline with an HTTP or HTTPS `xmlUrl` becomes a source named after
+/// its title, tagged with the folders it is nested in and its `category`
+/// entries. Other outlines are skipped.
+pub fn import(document: &str) -> Result<Vec<RssSource>, ClioError> {
+    let mut reader = Reader::from_str(document);
+    // Names of the open outlines that are folders rather than feeds
+    let mut folders: Vec<Option<String>> = Vec::new();
+    let mut sources = Vec::new();
+
+    loop {
+        let decoder = reader.decoder();
+        let event = reader
+            .read_event()
+            .map_err(|e| ClioError::Parse(format!("Invalid OPML: {e}")))?;
+        match event {
+            Event::Start(outline) if outline.local_name().as_ref() == b"outline" => {
+                let folder = match feed(&outline, &folders, decoder)? {
+                    Some(source) => {
+                        sources.push(source);
+                        None
+                    }
+                    None => attribute(&outline, "text", decoder)?
+                        .or(attribute(&outline, "title", decoder)?),
+                };
+                folders.push(folder);
+            }
+            Event::Empty(outline) if outline.local_name().as_ref() == b"outline" => {
+                sources.extend(feed(&outline, &folders, decoder)?);
+            }
+            Event::End(end) if end.local_name().as_ref() == b"outline" => {
+                folders.pop();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(sources)
+}
+
+/// What to do with an imported feed that is already subscribed under a
+/// different name or tags
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ConflictPolicy {
+    /// Leave the existing source as it is
+    Keep,
+    /// Take the imported name and tags
+    Replace,
+    /// Keep the existing name and add the imported tags
+    Merge,
+}
+
+impl ConflictPolicy {
+    /// The existing source once this policy has been applied to it
+    ///
+    /// Settings the import does not carry, such as pausing, are kept.
+    pub fn apply(self, existing: &RssSource, imported: &RssSource) -> RssSource {
+        match self {
+            ConflictPolicy::Keep => existing.clone(),
+            ConflictPolicy::Replace => RssSource {
+                name: imported.name.clone(),
+                tags: imported.tags.clone(),
+                ..existing.clone()
+            },
+            ConflictPolicy::Merge => {
+                let mut tags = existing.tags.clone();
+                for tag in &imported.tags {
+                    if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                        tags.push(tag.clone());
+                    }
+                }
+                RssSource {
+                    tags,
+                    ..existing.clone()
+                }
+            }
+        }
+    }
+}
+
+/// How an imported feed relates to the configured sources
+#[derive(Debug, Clone, PartialEq)]
+pub enum Incoming {
+    /// Not subscribed yet; renamed if its name was taken
+    New(RssSource),
+    /// Already subscribed with the same name and tags, or listed twice
+    Known(String),
+    /// Already subscribed at the same URL, under another name or tags
+    Conflict {
+        existing: RssSource,
+        imported: RssSource,
+    },
+}
+
+/// Match imported feeds to the configured sources by URL
+pub fn match_existing(sources: &Sources, imported: Vec<RssSource>) -> Vec<Incoming> {
+    let mut names: HashSet<String> = sources.rss.iter().map(|s| s.name.clone()).collect();
+    let mut seen = HashSet::new();
+
+    imported
+        .into_iter()
+        .map(|mut source| {
+            let url = source.primary_url().to_string();
+            if !seen.insert(url.clone()) {
+                return Incoming::Known(source.name);
+            }
+            let Some(existing) = sources
+                .rss
+                .iter()
+                .find(|existing| existing.addresses().contains(&url.as_str()))
+            else {
+                source.name = unique_name(&source.name, &names);
+                names.insert(source.name.clone());
+                return Incoming::New(source);
+            };
+
+            let same_tags = existing.tags.len() == source.tags.len()
+                && source
+                    .tags
+                    .iter()
+                    .all(|tag| existing.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)));
+            if existing.name == source.name && same_tags {
+                Incoming::Known(source.name)
+            } else {
+                Incoming::Conflict {
+                    existing: existing.clone(),
+                    imported: source,
+                }
+            }
+        })
+        .collect()
+}
+
+/// A source for an outline with a feed URL
+fn feed(
+    outline: &BytesStart,
+    folders: &[Option<String>],
+    decoder: Decoder,
+) -> Result<Option<RssSource>, ClioError> {
+    let Some(url) = attribute(outline, "xmlUrl", decoder)?
+        .filter(|url| Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https")))
+    else {
+        return Ok(None);
+    };
+    let name = attribute(outline, "title", decoder)?
+        .or(attribute(outline, "text", decoder)?)
+        .unwrap_or_else(|| url.clone());
+
+    let mut tags: Vec<String> = folders.iter().flatten().cloned().collect();
+    let categories = attribute(outline, "category", decoder)?.unwrap_or_default();
+    for category in categories.split(',') {
+        // Categories are slash-delimited paths such as "/Tech/Rust"
+        for tag in category.split('/').map(str::trim).filter(|t| !t.is_empty()) {
+            if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                tags.push(tag.to_string());
+            }
+        }
+    }
+
+    Ok(Some(RssSource {
+        tags,
+        ..RssSource::new(name, url)
+    }))
+}
+
+/// An outline attribute, unescaped, trimmed and without control characters
+fn attribute(
+    outline: &BytesStart,
+    name: &str,
+    decoder: Decoder,
+) -> Result<Option<String>, ClioError> {
+    let Some(attribute) = outline
+        .try_get_attribute(name)
+        .map_err(|e| ClioError::Parse(format!("Invalid OPML outline: {e}")))?
+    else {
+        return Ok(None);
+    };
+    let value = attribute
+        .decode_and_unescape_value(decoder)
+        .map_err(|e| ClioError::Parse(format!("Invalid OPML outline: {e}")))?;
+    let value = strip_control(value.trim());
+    Ok((!value.is_empty()).then_some(value))
+}
+
+/// `name`, or `name (2)`, `name (3)`... when it is taken
+fn unique_name(name: &str, taken: &HashSet<String>) -> String {
+    (1..)
+        .map(|n| match n {
+            1 => name.to_string(),
+            n => format!("{name} ({n})"),
+        })
+        .find(|candidate| !taken.contains(candidate))
+        .expect("some numbered name is free")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{RssSource, WeatherProvider, WeatherSource};
+
+    #[test]
+    fn test_export_sources() {
+        let mut paused = RssSource::new(
+            "Paused & quiet".to_string(),
+            "https://example.com/b.xml?x=1&y=\"2\"".to_string(),
+        );
+        paused.paused = true;
+        let sources = Sources {
+            rss: vec![
+                RssSource::new("Blog".to_string(), "https://example.com/a.xml".to_string()),
+                paused,
+            ],
+            weather: vec![WeatherSource {
+                name: "Storms".to_string(),
+                provider: WeatherProvider::Noaa,
+                area: "CA".to_string(),
+            }],
+            ..Default::default()
+        };
+
+        let opml = export(&sources);
+        assert!(
+            opml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<opml version=\"2.0\">")
+        );
+        assert!(opml.contains(
+            "<outline type=\"rss\" text=\"Blog\" title=\"Blog\" xmlUrl=\"https://example.com/a.xml\"/>"
+        ));
+        assert!(opml.contains("text=\"Paused &amp; quiet\""));
+        assert!(opml.contains("xmlUrl=\"https://example.com/b.xml?x=1&amp;y=&quot;2&quot;\""));
+        assert!(opml.contains(
+            "text=\"Storms\" title=\"Storms\" xmlUrl=\"https://api.weather.gov/alerts/active.atom?area=CA\""
+        ));
+        assert!(opml.trim_end().ends_with("</opml>"));
+    }
+
+    const READER_EXPORT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<opml version="1.0">
+  <head><title>Subscriptions</title></head>
+  <body>
+    <outline text="Tech" title="Tech">
+      <outline type="rss" text="Rust Blog" title="Rust Blog" xmlUrl="https://blog.rust-lang.org/feed.xml"/>
+      <outline text="Podcasts">
+        <outline type="rss" text="Talk &amp; Show" xmlUrl="https://example.com/show.xml" category="/Audio,Tech"/>
+      </outline>
+    </outline>
+    <outline type="rss" xmlUrl="https://example.com/untitled.xml"/>
+    <outline type="rss" text="Local" xmlUrl="file:///tmp/feed.xml"/>
+    <outline text="Empty folder"></outline>
+  </body>
+</opml>"#;
+
+    fn source(name: &str, url: &str, tags: &[&str]) -> RssSource {
+        RssSource {
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..RssSource::new(name.to_string(), url.to_string())
+        }
+    }
+
+    #[test]
+    fn test_import_reader_export() {
+        let sources = import(READER_EXPORT).unwrap();
+        assert_eq!(
+            sources,
+            vec![
+                source(
+                    "Rust Blog",
+                    "https://blog.rust-lang.org/feed.xml",
+                    &["Tech"]
+                ),
+                source(
+                    "Talk & Show",
+                    "https://example.com/show.xml",
+                    &["Tech", "Podcasts", "Audio"]
+                ),
+                source(
+                    "https://example.com/untitled.xml",
+                    "https://example.com/untitled.xml",
+                    &[]
+                ),
+            ]
+        );
+
+        assert!(import("<opml><body><outline></body></opml>").is_err());
+    }
+
+    #[test]
+    fn test_import_roundtrips_export() {
+        let sources = Sources {
+            rss: vec![source("Blog", "https://example.com/a.xml?x=1&y=2", &[])],
+            ..Default::default()
+        };
+        assert_eq!(import(&export(&sources)).unwrap(), sources.rss);
+    }
+
+    #[test]
+    fn test_match_existing() {
+        let sources = Sources {
+            rss: vec![
+                source("Rust", "https://blog.rust-lang.org/feed.xml", &["tech"]),
+                source("Show", "https://example.com/show.xml", &[]),
+                source("Blog", "https://example.com/blog.xml", &[]),
+            ],
+            ..Default::default()
+        };
+        let incoming = match_existing(
+            &sources,
+            vec![
+                source("Rust", "https://blog.rust-lang.org/feed.xml", &["Tech"]),
+                source("Talk Show", "https://example.com/show.xml", &[]),
+                source("Blog", "https://other.example.com/blog.xml", &[]),
+                source("Blog", "https://third.example.com/blog.xml", &[]),
+                source("Again", "https://other.example.com/blog.xml", &[]),
+            ],
+        );
+
+        assert_eq!(
+            incoming,
+            vec![
+                Incoming::Known("Rust".to_string()),
+                Incoming::Conflict {
+                    existing: source("Show", "https://example.com/show.xml", &[]),
+                    imported: source("Talk Show", "https://example.com/show.xml", &[]),
+                },
+                Incoming::New(source(
+                    "Blog (2)",
+                    "https://other.example.com/blog.xml",
+                    &[]
+                )),
+                Incoming::New(source(
+                    "Blog (3)",
+                    "https://third.example.com/blog.xml",
+                    &[]
+                )),
+                Incoming::Known("Again".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_conflict_policies() {
+        let mut existing = source("Show", "https://example.com/show.xml", &["audio", "tech"]);
+        existing.paused = true;
+        let imported = source(
+            "Talk Show",
+            "https://example.com/show.xml",
+            &["Tech", "Talk"],
+        );
+
+        assert_eq!(ConflictPolicy::Keep.apply(&existing, &imported), existing);
+
+        let replaced = ConflictPolicy::Replace.apply(&existing, &imported);
+        assert_eq!(replaced.name, "Talk Show");
+        assert_eq!(replaced.tags, vec!["Tech", "Talk"]);
+        assert!(replaced.paused);
+
+        let merged = ConflictPolicy::Merge.apply(&existing, &imported);
+        assert_eq!(merged.name, "Show");
+        assert_eq!(merged.tags, vec!["audio", "tech", "Talk"]);
+    }
+
+    #[test]
+    fn test_export_empty() {
+        let opml = export(&Sources::default());
+        assert!(opml.contains("<body>\n  </body>"));
+    }
+}
@@ -0,0 +1,219 @@
+use crate::config::Config;
+use crate::database::Database;
+use crate::enrich::Enricher;
+use crate::error::ClioError;
+use crate::fetcher::{FetchStats, Fetcher, SourceFetch};
+use crate::http::FeedClient;
+use crate::rules::{self, LastPull};
+use crate::sanitize::TextLimits;
+use crate::source::{Item, Source};
+use chrono::Utc;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::sync::Arc;
+
+/// Storage a pull writes its items to
+///
+/// [`Database`] is the real store; tests can keep items in memory instead.
+/// Methods block, so a pull calls them on the blocking pool.
+pub trait PullStore: Send + Sync + Debug {
+    /// Insert the items whose links are not stored yet, returning the links
+    /// inserted
+    fn store_items(&self, items: &[Item]) -> Result<Vec<String>, ClioError>;
+
+    /// Append how each source's fetch went to the fetch history
+    fn record_fetches(&self, fetches: &[SourceFetch]) -> Result<(), ClioError>;
+
+    /// Body hash of each source's latest successful fetch, keyed by source
+    fn body_hashes(&self) -> Result<HashMap<String, String>, ClioError>;
+
+    /// Leave out the items whose fingerprint matches one stored for the source
+    fn drop_stored_fingerprints(
+        &self,
+        source_name: &str,
+        items: Vec<Item>,
+    ) -> Result<Vec<Item>, ClioError>;
+
+    /// Archive the stored items whose expiry date has passed
+    fn archive_expired(&self) -> Result<(), ClioError>;
+}
+
+impl PullStore for Database {
+    fn store_items(&self, items: &[Item]) -> Result<Vec<String>, ClioError> {
+        Database::store_items(self, items)
+    }
+
+    fn record_fetches(&self, fetches: &[SourceFetch]) -> Result<(), ClioError> {
+        Database::record_fetches(self, fetches)
+    }
+
+    fn body_hashes(&self) -> Result<HashMap<String, String>, ClioError> {
+        Database::body_hashes(self)
+    }
+
+    fn drop_stored_fingerprints(
+        &self,
+        source_name: &str,
+        items: Vec<Item>,
+    ) -> Result<Vec<Item>, ClioError> {
+        Database::drop_stored_fingerprints(self, source_name, items)
+    }
+
+    fn archive_expired(&self) -> Result<(), ClioError> {
+        Database::archive_expired(self)
+    }
+}
+
+/// What a pull fetched and kept
+#[derive(Debug)]
+pub struct Pulled {
+    /// Every item fetched, before mute and author rules, for `clio rules test`
+    pub last_pull: LastPull,
+    /// Items left after the rules; with a store, only those it did not hold
+    pub items: Vec<Item>,
+    pub stats: FetchStats,
+    /// Items whose paper metadata was looked up
+    pub enriched: usize,
+    /// Lookups that failed; their items are kept without the metadata
+    pub enrich_failures: Vec<ClioError>,
+    pub muted: usize,
+    /// Items starred or tagged by author rules
+    pub followed: usize,
+}
+
+/// The steps of `clio pull`: fetch the sources, look up paper metadata,
+/// apply mute and author rules, and store the new items along with how
+/// each fetch went, archiving expired items when the settings ask to
+pub struct Pull<'a> {
+    config: &'a Config,
+    client: FeedClient,
+    fetcher: Fetcher,
+    enricher: Enricher,
+    store: Option<Arc<dyn PullStore>>,
+}
+
+impl<'a> Pull<'a> {
+    /// A pull sending its requests through `client`, the one the sources
+    /// were built with
+    pub fn new(config: &'a Config, client: FeedClient) -> Self {
+        Self {
+            config,
+            fetcher: Fetcher::with_timeout(config.settings.timeout).with_client(client.clone()),
+            enricher: Enricher::new(client.clone()).with_limits(TextLimits::from(&config.settings)),
+            client,
+            store: None,
+        }
+    }
+
+    /// Fetch with `fetcher`, such as one with a shorter timeout or one that
+    /// reports nothing
+    pub fn with_fetcher(mut self, fetcher: Fetcher) -> Self {
+        self.fetcher = fetcher.with_client(self.client.clone());
+        self
+    }
+
+    /// Store pulled items in `store`, keeping only the new ones in the result
+    pub fn with_store(mut self, store: Arc<dyn PullStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Pull `sources`, built from the configuration with
+    /// [`source::from_config`](crate::source::from_config)
+    ///
+    /// Sources that fail are reported in the stats; only storage failures
+    /// fail the pull.
+    pub async fn run(self, sources: Vec<Arc<dyn Source>>) -> Result<Pulled, ClioError> {
+        if let Some(store) = self.store.clone() {
+            let hashes = blocking(move || store.body_hashes()).await?;
+            skip_unchanged(&sources, &hashes);
+        }
+        let (mut items, stats) = self.fetcher.fetch_all(sources).await;
+        let (enriched, enrich_failures) =
+            self.enricher.enrich(&self.config.sources, &mut items).await;
+        // Kept as pulled, before mute and author rules, for `clio rules test`
+        let last_pull = LastPull::new(&items, Utc::now());
+        let muted = rules::apply_mute_rules(&self.config.mutes, &mut items);
+        let followed = rules::apply_author_rules(&self.config.authors, &mut items);
+
+        if let Some(store) = &self.store {
+            let (store, pulled, fetches) = (store.clone(), items, stats.fetches.clone());
+            let fingerprinted = self.config.sources.fingerprinted();
+            let archive_expired = self.config.settings.archive_expired;
+            items = blocking(move || {
+                // Recorded even when nothing is new, since health checks,
+                // bandwidth stats and skipping unchanged feeds read it
+                store.record_fetches(&fetches)?;
+                // Feeds that rewrite their links would never conflict on insert
+                let pulled = drop_stored_fingerprints(store.as_ref(), &fingerprinted, pulled)?;
+                let inserted: HashSet<String> = store.store_items(&pulled)?.into_iter().collect();
+                // After storing, so items that arrive already expired go too
+                if archive_expired {
+                    store.archive_expired()?;
+                }
+                Ok(pulled
+                    .into_iter()
+                    .filter(|item| inserted.contains(&item.link))
+                    .collect())
+            })
+            .await?;
+        }
+
+        Ok(Pulled {
+            last_pull,
+            items,
+            stats,
+            enriched,
+            enrich_failures,
+            muted,
+            followed,
+        })
+    }
+}
+
+/// Have each source skip parsing a body that hashes the same as in its
+/// latest successful fetch, given `hashes` from the fetch history
+///
+/// Such fetches return no items and count as not modified.
+pub fn skip_unchanged(sources: &[Arc<dyn Source>], hashes: &HashMap<String, String>) {
+    for source in sources {
+        if let Some(hash) = hashes.get(source.name()) {
+            source.skip_unchanged(hash.clone());
+        }
+    }
+}
+
+/// Leave out the items of `fingerprinted` sources that `store` already holds
+/// under another link
+///
+/// Other items are kept, in order; their links tell whether they are stored.
+pub fn drop_stored_fingerprints(
+    store: &dyn PullStore,
+    fingerprinted: &HashSet<String>,
+    mut items: Vec<Item>,
+) -> Result<Vec<Item>, ClioError> {
+    let mut kept = HashSet::new();
+    for source_name in fingerprinted {
+        let candidates: Vec<Item> = items
+            .iter()
+            .filter(|item| &item.source_name == source_name)
+            .cloned()
+            .collect();
+        if candidates.is_empty() {
+            continue;
+        }
+        let new = store.drop_stored_fingerprints(source_name, candidates)?;
+        kept.extend(new.into_iter().map(|item| item.id));
+    }
+    items.retain(|item| !fingerprinted.contains(&item.source_name) || kept.contains(&item.id));
+    Ok(items)
+}
+
+/// Run `work` on the blocking pool, where storage clients run
+async fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, ClioError> + Send + 'static,
+) -> Result<T, ClioError> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|e| ClioError::Database(format!("Storage task failed: {e}")))?
+}
@@ -0,0 +1,128 @@
+use crate::color::{self, Tone};
+use crate::database::SupabaseClient;
+use crate::error::ClioError;
+use serde_json::Value;
+use std::env;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Environment variable holding the time, in milliseconds, above which
+/// storage queries are logged
+///
+/// Set `CLIO_SLOW_QUERY_MS=200` to see which queries a new filter or a
+/// growing table made slow; `0` logs every query.
+const SLOW_QUERY_ENV: &str = "CLIO_SLOW_QUERY_MS";
+
+/// Longest query text shown in a log line
+const MAX_QUERY_LENGTH: usize = 300;
+
+/// A storage client that logs queries slower than a threshold to standard
+/// error, with a hint for seeing how the database ran them
+#[derive(Debug)]
+pub struct SlowQueryLog {
+    inner: Arc<dyn SupabaseClient>,
+    threshold: Duration,
+}
+
+impl SlowQueryLog {
+    pub fn new(inner: Arc<dyn SupabaseClient>, threshold: Duration) -> Self {
+        Self { inner, threshold }
+    }
+
+    /// The threshold set in `CLIO_SLOW_QUERY_MS`, or `None` when unset
+    pub fn threshold_from_env() -> Result<Option<Duration>, ClioError> {
+        match env::var(SLOW_QUERY_ENV) {
+            Ok(value) if !value.trim().is_empty() => value
+                .trim()
+                .parse()
+                .map(|ms| Some(Duration::from_millis(ms)))
+                .map_err(|_| {
+                    ClioError::Config(format!(
+                        "Invalid {SLOW_QUERY_ENV} '{value}': expected milliseconds"
+                    ))
+                }),
+            _ => Ok(None),
+        }
+    }
+
+    fn timed<T>(&self, query: &str, run: impl FnOnce() -> T) -> T {
+        let started = Instant::now();
+        let result = run();
+        let elapsed = started.elapsed();
+        if elapsed >= self.threshold {
+            eprintln!("{}", report(query, elapsed));
+        }
+        result
+    }
+}
+
+impl SupabaseClient for SlowQueryLog {
+    fn execute(&self, query: &str) -> Result<(), ClioError> {
+        self.timed(query, || self.inner.execute(query))
+    }
+
+    fn query(&self, query: &str) -> Result<Vec<Value>, ClioError> {
+        self.timed(query, || self.inner.query(query))
+    }
+
+    fn table_exists(&self, table_name: &str) -> Result<bool, ClioError> {
+        self.inner.table_exists(table_name)
+    }
+
+    fn url(&self) -> &str {
+        self.inner.url()
+    }
+}
+
+/// The log line for `query`, which took `elapsed`
+///
+/// Reads get an `EXPLAIN` to paste into the SQL editor; writes get one
+/// that leaves out `ANALYZE`, since that would run them again.
+fn report(query: &str, elapsed: Duration) -> String {
+    let query = query.split_whitespace().collect::<Vec<_>>().join(" ");
+    let shown = match query.char_indices().nth(MAX_QUERY_LENGTH) {
+        Some((end, _)) => format!("{}…", &query[..end]),
+        None => query.clone(),
+    };
+    let explain = if query.starts_with("SELECT") || query.starts_with("WITH") {
+        "EXPLAIN (ANALYZE, BUFFERS)"
+    } else {
+        "EXPLAIN"
+    };
+    format!(
+        "{} {} ms: {shown}\n  {}",
+        color::err(Tone::Warning, "Slow query"),
+        elapsed.as_millis(),
+        color::err(
+            Tone::Dim,
+            format!(
+                "See its plan with {explain} <query>; a sequential scan on items may need an index"
+            )
+        )
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let line = report(
+            "SELECT id FROM items\n    WHERE source_name IN ('HN')",
+            Duration::from_millis(1250),
+        );
+        assert!(line.contains("1250 ms: SELECT id FROM items WHERE source_name IN ('HN')"));
+        assert!(line.contains("EXPLAIN (ANALYZE, BUFFERS) <query>"));
+
+        let line = report(
+            &format!(
+                "UPDATE items SET is_read = TRUE WHERE id IN ({})",
+                "'a', ".repeat(200)
+            ),
+            Duration::from_millis(300),
+        );
+        assert!(line.contains("…"));
+        assert!(line.contains("EXPLAIN <query>"));
+    }
+}
@@ -0,0 +1,203 @@
+use crate::config::ReadLaterDestination;
+use crate::error::ClioError;
+use crate::export;
+use crate::http::FeedClient;
+use crate::serve::ItemJson;
+use crate::source::Item;
+use crate::writes;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// The destination named `name`, or the first one configured
+pub fn destination<'a>(
+    destinations: &'a [ReadLaterDestination],
+    name: Option<&str>,
+) -> Result<&'a ReadLaterDestination, ClioError> {
+    match name {
+        Some(name) => destinations
+            .iter()
+            .find(|destination| destination.name == name)
+            .ok_or_else(|| ClioError::Config(format!("No read-later destination named '{name}'"))),
+        None => destinations.first().ok_or_else(|| {
+            ClioError::Config(
+                "No read-later destination configured; add a [[read_later]] table".to_string(),
+            )
+        }),
+    }
+}
+
+/// Send `item` to `destination`
+///
+/// Files get the item appended as a Markdown link, unless writes are turned
+/// off; webhooks are POSTed the item as JSON, and commands get the link as
+/// their last argument and the item as JSON on standard input.
+pub async fn save(
+    destination: &ReadLaterDestination,
+    item: &Item,
+    client: &FeedClient,
+) -> Result<(), ClioError> {
+    if let Some(path) = &destination.file {
+        writes::ensure_allowed(&format!("save to {}", path.display()))?;
+        let line = export::to_markdown(std::slice::from_ref(item));
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(|e| {
+                ClioError::ReadLater(format!("Failed to write to {}: {e}", path.display()))
+            })
+    } else if let Some(url) = &destination.webhook {
+        let response = client
+            .post(url)
+            .json(&ItemJson::from(item.clone()))
+            .send()
+            .await
+            .map_err(|e| ClioError::ReadLater(format!("Failed to reach {url}: {e}")))?;
+        if !response.status().is_success() {
+            return Err(ClioError::ReadLater(format!(
+                "HTTP {} from {url}",
+                response.status()
+            )));
+        }
+        Ok(())
+    } else if let Some((program, args)) =
+        destination.command.as_deref().and_then(<[_]>::split_first)
+    {
+        run_command(program, args, item)
+    } else {
+        Err(ClioError::Config(format!(
+            "Read-later destination '{}' needs exactly one of file, webhook and command",
+            destination.name
+        )))
+    }
+}
+
+fn run_command(program: &str, args: &[String], item: &Item) -> Result<(), ClioError> {
+    let json = serde_json::to_string(&ItemJson::from(item.clone()))
+        .map_err(|e| ClioError::ReadLater(format!("Failed to serialize item: {e}")))?;
+    let mut child = Command::new(program)
+        .args(args)
+        .arg(&item.link)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| ClioError::ReadLater(format!("Could not run {program}: {e}")))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A command that only wants the link may exit without reading
+        let _ = stdin.write_all(json.as_bytes());
+    }
+    let status = child
+        .wait()
+        .map_err(|e| ClioError::ReadLater(format!("{program} did not finish: {e}")))?;
+    if !status.success() {
+        return Err(ClioError::ReadLater(format!("{program} failed ({status})")));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::RequestGuard;
+    use tempfile::TempDir;
+
+    fn item() -> Item {
+        Item {
+            id: "0c6b1a52".to_string(),
+            source_name: "Blog".to_string(),
+            title: "A post".to_string(),
+            link: "https://example.com/post".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn destination(name: &str) -> ReadLaterDestination {
+        ReadLaterDestination {
+            name: name.to_string(),
+            file: None,
+            webhook: None,
+            command: None,
+        }
+    }
+
+    #[test]
+    fn test_destination_by_name_or_first() {
+        let destinations = [destination("notes"), destination("hook")];
+        assert_eq!(
+            super::destination(&destinations, None).unwrap().name,
+            "notes"
+        );
+        assert_eq!(
+            super::destination(&destinations, Some("hook"))
+                .unwrap()
+                .name,
+            "hook"
+        );
+        assert!(super::destination(&destinations, Some("other")).is_err());
+        assert!(super::destination(&[], None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_save_to_file_and_command() {
+        let dir = TempDir::new().unwrap();
+        let client = FeedClient::new(RequestGuard::new(true));
+        let notes = dir.path().join("later.md");
+        let file = ReadLaterDestination {
+            file: Some(notes.clone()),
+            ..destination("notes")
+        };
+        save(&file, &item(), &client).await.unwrap();
+        save(&file, &item(), &client).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&notes).unwrap(),
+            "- [A post](https://example.com/post) · Blog\n".repeat(2)
+        );
+
+        let out = dir.path().join("out");
+        let command = ReadLaterDestination {
+            command: Some(vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                format!("cat > {0}.json && echo \"$0\" > {0}.link", out.display()),
+            ]),
+            ..destination("script")
+        };
+        save(&command, &item(), &client).await.unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(out.with_extension("json")).unwrap())
+                .unwrap();
+        assert_eq!(json["title"], "A post");
+        assert_eq!(
+            std::fs::read_to_string(out.with_extension("link")).unwrap(),
+            "https://example.com/post\n"
+        );
+
+        let failing = ReadLaterDestination {
+            command: Some(vec!["false".to_string()]),
+            ..destination("broken")
+        };
+        assert!(save(&failing, &item(), &client).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_save_to_webhook() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/save")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "id": "0c6b1a52",
+                "link": "https://example.com/post",
+            })))
+            .with_status(201)
+            .create_async()
+            .await;
+        let webhook = ReadLaterDestination {
+            webhook: Some(format!("{}/save", server.url())),
+            ..destination("hook")
+        };
+
+        let client = FeedClient::new(RequestGuard::new(true));
+        save(&webhook, &item(), &client).await.unwrap();
+        mock.assert_async().await;
+    }
+}
@@ -0,0 +1,224 @@
+use crate::sanitize::TextLimits;
+use crate::source::Item;
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// A `major.minor.patch` version in a release title, with an optional
+/// pre-release suffix such as `-rc.1`
+static VERSION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\bv?((\d+)\.(\d+)\.(\d+))\b(-[0-9a-z][0-9a-z.]*)?")
+        .expect("valid version pattern")
+});
+
+/// A stable release parsed from an item title
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Release {
+    /// Text before the version, lowercased, such as "tokio" for "tokio-v1.38.1"
+    project: String,
+    major: u64,
+    minor: u64,
+    /// The version as written, without a leading `v`
+    version: String,
+    /// Byte range of `version` in the title
+    span: (usize, usize),
+}
+
+/// Releases of one project folded into the newest of them
+struct Run {
+    item: Item,
+    release: Option<Release>,
+    oldest: String,
+    notes: Vec<String>,
+}
+
+/// Fold consecutive patch releases of the same project into one item
+///
+/// Items are taken in feed order, newest first. A stable release joins the
+/// last kept release of its project when both share a major and minor
+/// version; the kept item's title then names the range, as in
+/// "tokio 1.38.1–1.38.4", and its summary lists each release's notes.
+/// Pre-releases and items without a version are passed through.
+pub fn collapse_patch_releases(items: Vec<Item>, limits: &TextLimits) -> Vec<Item> {
+    let mut runs: Vec<Run> = Vec::with_capacity(items.len());
+    let mut latest: HashMap<String, usize> = HashMap::new();
+
+    for item in items {
+        let release = parse_release(&item.title);
+        let notes = note(&item, release.as_ref());
+        if let Some(release) = &release {
+            let same_minor = latest.get(&release.project).copied().filter(|&i| {
+                runs[i]
+                    .release
+                    .as_ref()
+                    .is_some_and(|kept| (kept.major, kept.minor) == (release.major, release.minor))
+            });
+            if let Some(i) = same_minor {
+                let run = &mut runs[i];
+                run.oldest = release.version.clone();
+                run.notes.extend(notes);
+                continue;
+            }
+            latest.insert(release.project.clone(), runs.len());
+        }
+        runs.push(Run {
+            oldest: release
+                .as_ref()
+                .map(|release| release.version.clone())
+                .unwrap_or_default(),
+            notes: notes.into_iter().collect(),
+            item,
+            release,
+        });
+    }
+
+    runs.into_iter().map(|run| run.finish(limits)).collect()
+}
+
+impl Run {
+    fn finish(self, limits: &TextLimits) -> Item {
+        let Run {
+            mut item,
+            release,
+            oldest,
+            notes,
+        } = self;
+        let Some(release) = release.filter(|release| release.version != oldest) else {
+            return item;
+        };
+
+        let (start, end) = release.span;
+        item.title = limits.title(&format!(
+            "{}{oldest}–{}{}",
+            &item.title[..start],
+            release.version,
+            &item.title[end..]
+        ));
+        item.summary = (!notes.is_empty()).then(|| limits.summary(&notes.join(" · ")));
+        item
+    }
+}
+
+/// The stable release a title names, if any
+fn parse_release(title: &str) -> Option<Release> {
+    let captures = VERSION.captures(title)?;
+    if captures.get(5).is_some() {
+        return None;
+    }
+    let version = captures.get(1)?;
+    let project = title[..captures.get(0)?.start()]
+        .trim_end_matches(|c: char| c.is_whitespace() || matches!(c, '-' | '_' | '@' | ':'))
+        .to_lowercase();
+
+    Some(Release {
+        project,
+        major: captures[2].parse().ok()?,
+        minor: captures[3].parse().ok()?,
+        version: version.as_str().to_string(),
+        span: (version.start(), version.end()),
+    })
+}
+
+/// A release's summary labeled with its version, for the folded item
+fn note(item: &Item, release: Option<&Release>) -> Option<String> {
+    let summary = item.summary.as_deref().filter(|s| !s.is_empty())?;
+    Some(match release {
+        Some(release) => format!("{}: {summary}", release.version),
+        None => summary.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(title: &str, summary: Option<&str>) -> Item {
+        Item {
+            source_name: "Releases".to_string(),
+            title: title.to_string(),
+            link: format!("https://example.com/{title}"),
+            summary: summary.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn titles(items: &[Item]) -> Vec<&str> {
+        items.iter().map(|item| item.title.as_str()).collect()
+    }
+
+    #[test]
+    fn test_parse_release() {
+        let parsed = parse_release("tokio-v1.38.4").unwrap();
+        assert_eq!(parsed.project, "tokio");
+        assert_eq!((parsed.major, parsed.minor), (1, 38));
+        assert_eq!(parsed.version, "1.38.4");
+        assert_eq!(&"tokio-v1.38.4"[parsed.span.0..parsed.span.1], "1.38.4");
+
+        assert_eq!(parse_release("v0.2.1").unwrap().project, "");
+        assert_eq!(
+            parse_release("@scope/pkg@2.0.1").unwrap().project,
+            "@scope/pkg"
+        );
+        assert_eq!(parse_release("serde 1.0.0-rc.1"), None);
+        assert_eq!(parse_release("Release notes for May"), None);
+    }
+
+    #[test]
+    fn test_collapse_patch_releases() {
+        let items = vec![
+            release("tokio 1.38.4", Some("Fix a deadlock")),
+            release("tokio 1.38.3", None),
+            release("tokio 1.38.2", Some("Fix a leak")),
+            release("tokio 1.38.1", None),
+            release("tokio 1.37.2", None),
+            release("tokio 1.37.1", None),
+        ];
+        let collapsed = collapse_patch_releases(items, &TextLimits::default());
+
+        assert_eq!(
+            titles(&collapsed),
+            ["tokio 1.38.1–1.38.4", "tokio 1.37.1–1.37.2"]
+        );
+        assert_eq!(collapsed[0].link, "https://example.com/tokio 1.38.4");
+        assert_eq!(
+            collapsed[0].summary.as_deref(),
+            Some("1.38.4: Fix a deadlock · 1.38.2: Fix a leak")
+        );
+        assert_eq!(collapsed[1].summary, None);
+    }
+
+    #[test]
+    fn test_collapse_keeps_projects_apart() {
+        let items = vec![
+            release("tokio-v1.38.2", None),
+            release("tokio-macros-v2.3.1", None),
+            release("tokio-v1.38.1", None),
+            release("tokio 1.39.0-rc.1", None),
+            release("tokio-macros-v2.3.0", None),
+            release("Roadmap update", Some("Plans")),
+        ];
+        let collapsed = collapse_patch_releases(items, &TextLimits::default());
+
+        assert_eq!(
+            titles(&collapsed),
+            [
+                "tokio-v1.38.1–1.38.2",
+                "tokio-macros-v2.3.0–2.3.1",
+                "tokio 1.39.0-rc.1",
+                "Roadmap update",
+            ]
+        );
+        assert_eq!(collapsed[3].summary.as_deref(), Some("Plans"));
+    }
+
+    #[test]
+    fn test_new_minor_starts_a_run() {
+        let items = vec![
+            release("v1.39.0", None),
+            release("v1.38.1", None),
+            release("v1.38.0", None),
+        ];
+        let collapsed = collapse_patch_releases(items, &TextLimits::default());
+        assert_eq!(titles(&collapsed), ["v1.39.0", "v1.38.0–1.38.1"]);
+    }
+}
@@ -0,0 +1,192 @@
+use crate::config::{Config, RetentionRule};
+use crate::database::Retention;
+use crate::error::ClioError;
+use crate::filter::Filter;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashSet;
+
+/// A configured retention rule applied to the configured sources, for `clio prune`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Policy {
+    /// The rule's filter, or "other sources" for a rule without one
+    pub label: String,
+    /// What the rule keeps, such as "last 7 days" or "forever"
+    pub keep: String,
+    /// Items to delete, or `None` when the rule keeps everything it covers
+    pub retention: Option<Retention>,
+}
+
+/// Resolve the `[[retention]]` rules of `config` as of `now`
+///
+/// Each source follows the first rule that selects it, so a rule keeping
+/// `tag:papers` forever protects those sources from a later catch-all rule.
+/// Sources no longer configured are only covered by a rule without a filter.
+pub fn policies(config: &Config, now: DateTime<Utc>) -> Result<Vec<Policy>, ClioError> {
+    let mut claimed: Vec<String> = Vec::new();
+    let mut policies = Vec::new();
+    for rule in &config.retention {
+        let (label, sources, except_sources) = match &rule.filter {
+            Some(filter) => {
+                let filter: Filter = filter.parse()?;
+                let sources: Vec<String> = selected_sources(config, &filter)
+                    .into_iter()
+                    .filter(|name| !claimed.contains(name))
+                    .collect();
+                claimed.extend(sources.iter().cloned());
+                (filter.to_string(), Some(sources), Vec::new())
+            }
+            None => {
+                let except = std::mem::take(&mut claimed);
+                // Nothing is left for later rules
+                claimed = all_sources(config);
+                claimed.extend(except.iter().cloned());
+                ("other sources".to_string(), None, except)
+            }
+        };
+
+        let limited = rule.keep_days.is_some() || rule.keep_newest.is_some();
+        policies.push(Policy {
+            label,
+            keep: describe(rule),
+            retention: limited.then(|| Retention {
+                older_than: rule
+                    .keep_days
+                    .map(|days| now - Duration::days(i64::from(days))),
+                keep_newest: rule.keep_newest,
+                keep_starred: rule.keep_starred,
+                sources,
+                except_sources,
+            }),
+        });
+    }
+    Ok(policies)
+}
+
+/// Names of the configured sources `filter` selects, paused ones included
+fn selected_sources(config: &Config, filter: &Filter) -> Vec<String> {
+    let rss = config
+        .sources
+        .rss
+        .iter()
+        .filter(|source| filter.matches(source))
+        .map(|source| source.name.clone());
+    let others = config
+        .sources
+        .synthetic
+        .iter()
+        .map(|source| &source.name)
+        .chain(config.sources.advisories.iter().map(|source| &source.name))
+        .chain(config.sources.statuspage.iter().map(|source| &source.name))
+        .chain(config.sources.changelog.iter().map(|source| &source.name))
+        .chain(config.sources.twitch.iter().map(|source| &source.name))
+        .chain(config.sources.weather.iter().map(|source| &source.name))
+        .filter(|&source| matches!(filter, Filter::Source(name) if name == source))
+        .cloned();
+    let mut seen = HashSet::new();
+    rss.chain(others)
+        .filter(|name| seen.insert(name.clone()))
+        .collect()
+}
+
+fn all_sources(config: &Config) -> Vec<String> {
+    let rss = config.sources.rss.iter().map(|source| source.name.clone());
+    let synthetic = config.sources.synthetic.iter().map(|s| s.name.clone());
+    let advisories = config.sources.advisories.iter().map(|s| s.name.clone());
+    let statuspage = config.sources.statuspage.iter().map(|s| s.name.clone());
+    let changelog = config.sources.changelog.iter().map(|s| s.name.clone());
+    let twitch = config.sources.twitch.iter().map(|s| s.name.clone());
+    let weather = config.sources.weather.iter().map(|s| s.name.clone());
+    rss.chain(synthetic)
+        .chain(advisories)
+        .chain(statuspage)
+        .chain(changelog)
+        .chain(twitch)
+        .chain(weather)
+        .collect()
+}
+
+fn describe(rule: &RetentionRule) -> String {
+    let mut limits = Vec::new();
+    if let Some(days) = rule.keep_days {
+        limits.push(format!("last {days} days"));
+    }
+    if let Some(newest) = rule.keep_newest {
+        limits.push(format!("newest {newest} per source"));
+    }
+    if limits.is_empty() {
+        return "forever".to_string();
+    }
+    let mut keep = limits.join(" and ");
+    if rule.keep_starred {
+        keep.push_str(", plus starred");
+    }
+    keep
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policies() {
+        let config: Config = toml::from_str(
+            r#"
+            [[sources.rss]]
+            name = "Wire"
+            url = "https://example.com/wire.xml"
+            tags = ["news"]
+
+            [[sources.rss]]
+            name = "arXiv"
+            url = "https://example.com/arxiv.xml"
+            tags = ["papers", "news"]
+
+            [[sources.rss]]
+            name = "Blog"
+            url = "https://example.com/blog.xml"
+
+            [[retention]]
+            filter = "tag:papers"
+
+            [[retention]]
+            filter = "tag:news"
+            keep_days = 7
+            keep_starred = false
+
+            [[retention]]
+            keep_newest = 100
+            "#,
+        )
+        .unwrap();
+        let now = Utc::now();
+
+        let policies = policies(&config, now).unwrap();
+        assert_eq!(policies.len(), 3);
+
+        assert_eq!(
+            (policies[0].label.as_str(), policies[0].keep.as_str()),
+            ("tag:papers", "forever")
+        );
+        assert_eq!(policies[0].retention, None);
+
+        let news = policies[1].retention.as_ref().unwrap();
+        assert_eq!(policies[1].keep, "last 7 days");
+        assert_eq!(news.sources, Some(vec!["Wire".to_string()]));
+        assert_eq!(news.older_than, Some(now - Duration::days(7)));
+        assert!(!news.keep_starred);
+
+        let other = policies[2].retention.as_ref().unwrap();
+        assert_eq!(policies[2].label, "other sources");
+        assert_eq!(policies[2].keep, "newest 100 per source, plus starred");
+        assert_eq!(other.sources, None);
+        assert_eq!(other.except_sources, ["arXiv", "Wire"]);
+        assert_eq!(other.keep_newest, Some(100));
+    }
+
+    #[test]
+    fn test_invalid_filter() {
+        let config: Config =
+            toml::from_str("[sources]\n[[retention]]\nfilter = \"news\"\nkeep_days = 1\n").unwrap();
+        assert!(policies(&config, Utc::now()).is_err());
+    }
+}
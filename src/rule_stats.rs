@@ -0,0 +1,239 @@
+use crate::config::Config;
+use crate::error::{ClioError, ErrorContext};
+use crate::rules::RuleReport;
+use crate::writes;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+
+/// How many items each rule matched in one pull
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PullHits {
+    pub at: DateTime<Utc>,
+    /// Items pulled
+    pub items: u64,
+    /// Matches by [`RuleReport::key`]
+    pub hits: BTreeMap<String, u64>,
+}
+
+/// Local record of rule matches, one JSON object per pull
+///
+/// Lives at `~/.clio/rule_hits.log` and is read by `clio rules stats`.
+#[derive(Debug, Clone)]
+pub struct RuleHitLog {
+    path: PathBuf,
+}
+
+/// How one rule has done over the pulls recorded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RuleStats {
+    /// The rule's table and position, such as `[[alerts]] #2`
+    pub rule: String,
+    pub description: String,
+    /// Pulls recorded while the rule was configured
+    pub pulls: u64,
+    /// Items those pulls brought in
+    pub items: u64,
+    pub matches: u64,
+    pub last_match: Option<DateTime<Utc>>,
+}
+
+impl PullHits {
+    /// The matches of the rules in `reports` that act on pulled items
+    pub fn new(at: DateTime<Utc>, items: usize, reports: &[RuleReport]) -> Self {
+        Self {
+            at,
+            items: items as u64,
+            hits: reports
+                .iter()
+                .filter(|report| report.kind.applies_on_pull())
+                .map(|report| (report.key(), report.matches.len() as u64))
+                .collect(),
+        }
+    }
+}
+
+impl RuleHitLog {
+    /// The rule hit log in the clio configuration directory
+    ///
+    /// Nothing is created until the first pull is recorded.
+    pub fn open() -> Result<Self, ClioError> {
+        Ok(Self::at(Config::config_dir()?.join("rule_hits.log")))
+    }
+
+    /// A rule hit log at a specific path
+    pub fn at(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Append the matches of one pull
+    ///
+    /// Nothing is recorded when filesystem writes are disabled.
+    pub fn record(&self, hits: &PullHits) -> Result<(), ClioError> {
+        if writes::read_only().is_some() {
+            return Ok(());
+        }
+        let mut line =
+            serde_json::to_string(hits).clio_config_err("Failed to serialize rule hits")?;
+        line.push('\n');
+
+        if let Some(dir) = self.path.parent() {
+            Config::ensure_private_dir(dir)?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .mode(0o600)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .clio_config_err(format!(
+                "Failed to write rule hit log at {}",
+                self.path.display()
+            ))
+    }
+
+    /// Every recorded pull, skipping lines that cannot be parsed
+    pub fn pulls(&self) -> Result<Vec<PullHits>, ClioError> {
+        match fs::read_to_string(&self.path) {
+            Ok(contents) => Ok(contents
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(ClioError::Config(format!(
+                "Failed to read rule hit log at {}: {e}",
+                self.path.display()
+            ))),
+        }
+    }
+}
+
+impl RuleStats {
+    /// Total up `pulls` made since `since` for each rule in `rules`
+    ///
+    /// A rule only counts the pulls that recorded it, so one added last week
+    /// is not blamed for the pulls before.
+    pub fn collect(rules: &[RuleReport], pulls: &[PullHits], since: DateTime<Utc>) -> Vec<Self> {
+        let pulls: Vec<&PullHits> = pulls.iter().filter(|pull| pull.at >= since).collect();
+        rules
+            .iter()
+            .filter(|rule| rule.kind.applies_on_pull())
+            .map(|rule| {
+                let key = rule.key();
+                let mut stats = Self {
+                    rule: rule.rule.clone(),
+                    description: rule.description.clone(),
+                    pulls: 0,
+                    items: 0,
+                    matches: 0,
+                    last_match: None,
+                };
+                for pull in &pulls {
+                    let Some(&matches) = pull.hits.get(&key) else {
+                        continue;
+                    };
+                    stats.pulls += 1;
+                    stats.items += pull.items;
+                    stats.matches += matches;
+                    if matches > 0 {
+                        stats.last_match = stats.last_match.max(Some(pull.at));
+                    }
+                }
+                stats
+            })
+            .collect()
+    }
+
+    /// The rule was checked but never matched anything
+    pub fn never_matched(&self) -> bool {
+        self.pulls > 0 && self.matches == 0
+    }
+
+    /// The rule matched more than half of the items it was checked against,
+    /// which usually means it is broader than intended
+    pub fn matches_most(&self) -> bool {
+        self.items > 0 && self.matches * 2 > self.items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{PulledItem, RuleKind};
+    use chrono::Duration;
+
+    fn report(kind: RuleKind, description: &str, matches: usize) -> RuleReport {
+        let item = PulledItem {
+            source_name: "Blog".to_string(),
+            title: "Hello".to_string(),
+            link: "https://example.com/hello".to_string(),
+            authors: Vec::new(),
+            tags: Vec::new(),
+            pub_date: None,
+        };
+        RuleReport {
+            kind,
+            rule: format!("[[{kind}]] #1"),
+            description: description.to_string(),
+            matches: vec![item; matches],
+        }
+    }
+
+    #[test]
+    fn test_rule_hit_log_round_trip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let log = RuleHitLog::at(dir.path().join("rule_hits.log"));
+        assert!(log.pulls().unwrap().is_empty());
+
+        let hits = PullHits::new(
+            Utc::now(),
+            10,
+            &[
+                report(RuleKind::Alerts, "outage", 2),
+                report(RuleKind::Retention, "other sources, keep last 7 days", 5),
+            ],
+        );
+        assert_eq!(hits.hits.len(), 1);
+        log.record(&hits).unwrap();
+        log.record(&hits).unwrap();
+        assert_eq!(log.pulls().unwrap(), vec![hits.clone(), hits]);
+    }
+
+    #[test]
+    fn test_rule_stats_collect() {
+        let now = Utc::now();
+        let pull = |days_ago: i64, items: u64, hits: &[(&str, u64)]| PullHits {
+            at: now - Duration::days(days_ago),
+            items,
+            hits: hits.iter().map(|(k, n)| (k.to_string(), *n)).collect(),
+        };
+        let pulls = [
+            pull(60, 100, &[("alerts: outage", 50)]),
+            pull(2, 10, &[("alerts: outage", 1), ("alerts: the", 9)]),
+            pull(1, 10, &[("alerts: outage", 0), ("alerts: the", 8)]),
+        ];
+        let rules = [
+            report(RuleKind::Alerts, "outage", 0),
+            report(RuleKind::Alerts, "the", 0),
+            report(RuleKind::Authors, "Julia Evans", 0),
+        ];
+
+        let stats = RuleStats::collect(&rules, &pulls, now - Duration::days(30));
+        assert_eq!(
+            stats
+                .iter()
+                .map(|s| (s.pulls, s.items, s.matches))
+                .collect::<Vec<_>>(),
+            vec![(2, 20, 1), (2, 20, 17), (0, 0, 0)]
+        );
+        assert_eq!(stats[0].last_match, Some(now - Duration::days(2)));
+        assert!(!stats[0].never_matched() && !stats[0].matches_most());
+        assert!(stats[1].matches_most());
+        // Rules never checked have no verdict
+        assert!(!stats[2].never_matched());
+    }
+}
@@ -0,0 +1,566 @@
+use crate::alerts;
+use crate::config::{AlertRule, AuthorRule, Config, MuteRule, RetentionRule};
+use crate::error::{ClioError, ErrorContext};
+use crate::retention;
+use crate::source::Item;
+use crate::writes;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+/// The alert, author, mute and retention rules of a configuration, as kept
+/// in a rules file for `clio rules export` and `clio rules import`
+///
+/// The file uses the same `[[alerts]]`, `[[authors]]`, `[[mutes]]` and
+/// `[[retention]]` tables as config.toml.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleSet {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alerts: Vec<AlertRule>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<AuthorRule>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mutes: Vec<MuteRule>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub retention: Vec<RetentionRule>,
+}
+
+/// Items `clio rules test` replays through the rules
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Replay {
+    /// The items of the most recent `clio pull`
+    #[default]
+    LastPull,
+}
+
+/// The table of the configuration a rule comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleKind {
+    Mutes,
+    Authors,
+    Alerts,
+    Retention,
+}
+
+/// What one rule would have done to a set of pulled items
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RuleReport {
+    pub kind: RuleKind,
+    /// The rule's table and position, such as `[[alerts]] #2`
+    pub rule: String,
+    /// What the rule looks for, or keeps
+    pub description: String,
+    pub matches: Vec<PulledItem>,
+}
+
+/// An item as it was pulled, before author rules starred or tagged it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PulledItem {
+    pub source_name: String,
+    pub title: String,
+    pub link: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pub_date: Option<DateTime<Utc>>,
+}
+
+/// The items of the most recent `clio pull`, kept in `~/.clio/last_pull.json`
+/// so rules can be tried against them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LastPull {
+    pub pulled_at: DateTime<Utc>,
+    pub items: Vec<PulledItem>,
+}
+
+/// Drop the pulled items whose titles contain a keyword of `rules`,
+/// returning how many were dropped
+pub fn apply_mute_rules(rules: &[MuteRule], items: &mut Vec<Item>) -> usize {
+    let before = items.len();
+    items.retain(|item| !rules.iter().any(|rule| mutes(rule, &item.title)));
+    before - items.len()
+}
+
+/// Star and tag pulled items according to `rules`, returning how many changed
+///
+/// An item matches a rule when any of its authors has the rule's name,
+/// ignoring case and surrounding whitespace.
+pub fn apply_author_rules(rules: &[AuthorRule], items: &mut [Item]) -> usize {
+    let mut changed = 0;
+    for item in items {
+        let before = (item.is_starred, item.tags.len());
+        let matching: Vec<&AuthorRule> = rules
+            .iter()
+            .filter(|rule| wrote(rule, &item.authors))
+            .collect();
+        for rule in matching {
+            item.is_starred |= rule.star;
+            for tag in &rule.tags {
+                if !item.tags.contains(tag) {
+                    item.tags.push(tag.clone());
+                }
+            }
+        }
+        if (item.is_starred, item.tags.len()) != before {
+            changed += 1;
+        }
+    }
+    changed
+}
+
+impl RuleSet {
+    /// The rules configured in `config`
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            alerts: config.alerts.clone(),
+            authors: config.authors.clone(),
+            mutes: config.mutes.clone(),
+            retention: config.retention.clone(),
+        }
+    }
+
+    /// Read a rules file
+    pub fn read(path: &Path) -> Result<Self, ClioError> {
+        let contents = fs::read_to_string(path)
+            .clio_config_err(format!("Failed to read rules from {}", path.display()))?;
+        toml::from_str(&contents)
+            .clio_config_err(format!("Failed to parse rules in {}", path.display()))
+    }
+
+    /// The rules as the contents of a rules file
+    pub fn to_toml(&self) -> Result<String, ClioError> {
+        toml::to_string_pretty(self).clio_config_err("Failed to serialize rules")
+    }
+
+    /// Number of rules of every kind
+    pub fn len(&self) -> usize {
+        self.alerts.len() + self.authors.len() + self.mutes.len() + self.retention.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The rules not already in `existing`
+    pub fn without(&self, existing: &RuleSet) -> Self {
+        Self {
+            alerts: missing(&self.alerts, &existing.alerts),
+            authors: missing(&self.authors, &existing.authors),
+            mutes: missing(&self.mutes, &existing.mutes),
+            retention: missing(&self.retention, &existing.retention),
+        }
+    }
+
+    /// Replay `items` through every rule, in the order `clio pull` and
+    /// `clio prune` apply them
+    ///
+    /// Muted items are left out of every later rule, and alert rules see the
+    /// tags author rules add. A retention rule matches
+    /// the items of the sources it covers that are older than its
+    /// `keep_days`, as of `now`; its `keep_newest` counts stored items, so
+    /// it is not replayed.
+    pub fn test(
+        &self,
+        config: &Config,
+        items: &[PulledItem],
+        now: DateTime<Utc>,
+    ) -> Result<Vec<RuleReport>, ClioError> {
+        let mut reports = Vec::new();
+        for (index, rule) in self.mutes.iter().enumerate() {
+            reports.push(RuleReport {
+                kind: RuleKind::Mutes,
+                rule: format!("[[mutes]] #{}", index + 1),
+                description: rule.keyword.clone(),
+                matches: items
+                    .iter()
+                    .filter(|item| mutes(rule, &item.title))
+                    .cloned()
+                    .collect(),
+            });
+        }
+        let items: Vec<PulledItem> = items
+            .iter()
+            .filter(|item| !self.mutes.iter().any(|rule| mutes(rule, &item.title)))
+            .cloned()
+            .collect();
+        let mut pulled: Vec<Item> = items.iter().map(PulledItem::to_item).collect();
+
+        for (index, rule) in self.authors.iter().enumerate() {
+            reports.push(RuleReport {
+                kind: RuleKind::Authors,
+                rule: format!("[[authors]] #{}", index + 1),
+                description: rule.name.clone(),
+                matches: items
+                    .iter()
+                    .filter(|item| wrote(rule, &item.authors))
+                    .cloned()
+                    .collect(),
+            });
+        }
+        apply_author_rules(&self.authors, &mut pulled);
+
+        for (index, rule) in self.alerts.iter().enumerate() {
+            let mut looks_for = rule.keywords.clone();
+            looks_for.extend(rule.tags.iter().map(|tag| format!("tag:{tag}")));
+            reports.push(RuleReport {
+                kind: RuleKind::Alerts,
+                rule: format!("[[alerts]] #{}", index + 1),
+                description: looks_for.join(", "),
+                matches: items
+                    .iter()
+                    .zip(&pulled)
+                    .filter(|(_, item)| alerts::matched(rule, item).is_some())
+                    .map(|(item, _)| item.clone())
+                    .collect(),
+            });
+        }
+
+        let config = Config {
+            retention: self.retention.clone(),
+            ..config.clone()
+        };
+        for (index, policy) in retention::policies(&config, now)?.into_iter().enumerate() {
+            let matches = match &policy.retention {
+                Some(retention) => items
+                    .iter()
+                    .filter(|item| {
+                        let covered = match &retention.sources {
+                            Some(sources) => sources.contains(&item.source_name),
+                            None => !retention.except_sources.contains(&item.source_name),
+                        };
+                        let expired = retention
+                            .older_than
+                            .zip(item.pub_date)
+                            .is_some_and(|(before, published)| published < before);
+                        covered && expired
+                    })
+                    .cloned()
+                    .collect(),
+                None => Vec::new(),
+            };
+            reports.push(RuleReport {
+                kind: RuleKind::Retention,
+                rule: format!("[[retention]] #{}", index + 1),
+                description: format!("{}, keep {}", policy.label, policy.keep),
+                matches,
+            });
+        }
+        Ok(reports)
+    }
+}
+
+impl RuleKind {
+    /// Whether the rules act on items as they are pulled, rather than on
+    /// stored items
+    pub fn applies_on_pull(self) -> bool {
+        !matches!(self, Self::Retention)
+    }
+}
+
+impl std::fmt::Display for RuleKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Mutes => write!(f, "mutes"),
+            Self::Authors => write!(f, "authors"),
+            Self::Alerts => write!(f, "alerts"),
+            Self::Retention => write!(f, "retention"),
+        }
+    }
+}
+
+impl RuleReport {
+    /// Identifies the rule by what it does rather than its position, so its
+    /// history survives rules being added or reordered
+    pub fn key(&self) -> String {
+        format!("{}: {}", self.kind, self.description)
+    }
+}
+
+impl PulledItem {
+    fn to_item(&self) -> Item {
+        Item {
+            source_name: self.source_name.clone(),
+            title: self.title.clone(),
+            link: self.link.clone(),
+            authors: self.authors.clone(),
+            tags: self.tags.clone(),
+            pub_date: self.pub_date,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&Item> for PulledItem {
+    fn from(item: &Item) -> Self {
+        Self {
+            source_name: item.source_name.clone(),
+            title: item.title.clone(),
+            link: item.link.clone(),
+            authors: item.authors.clone(),
+            tags: item.tags.clone(),
+            pub_date: item.pub_date,
+        }
+    }
+}
+
+impl LastPull {
+    pub fn new(items: &[Item], pulled_at: DateTime<Utc>) -> Self {
+        Self {
+            pulled_at,
+            items: items.iter().map(PulledItem::from).collect(),
+        }
+    }
+
+    /// Where the last pull is kept
+    pub fn path() -> Result<PathBuf, ClioError> {
+        Ok(Config::config_dir()?.join("last_pull.json"))
+    }
+
+    /// The last pull saved at `path`, or `None` if nothing has been pulled yet
+    pub fn load(path: &Path) -> Result<Option<Self>, ClioError> {
+        match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents).clio_config_err(format!(
+                "Failed to parse the last pull at {}",
+                path.display()
+            )),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(ClioError::Config(format!(
+                "Failed to read the last pull at {}: {e}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Write the pull to `path`, replacing the one before
+    ///
+    /// Nothing is written when filesystem writes are disabled.
+    pub fn save(&self, path: &Path) -> Result<(), ClioError> {
+        if writes::read_only().is_some() {
+            return Ok(());
+        }
+        let contents =
+            serde_json::to_string(self).clio_config_err("Failed to serialize the last pull")?;
+
+        if let Some(dir) = path.parent() {
+            Config::ensure_private_dir(dir)?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)
+            .and_then(|mut file| file.write_all(contents.as_bytes()))
+            .clio_config_err(format!(
+                "Failed to write the last pull at {}",
+                path.display()
+            ))
+    }
+}
+
+fn missing<T: Clone + PartialEq>(rules: &[T], existing: &[T]) -> Vec<T> {
+    let mut missing: Vec<T> = Vec::new();
+    for rule in rules {
+        if !existing.contains(rule) && !missing.contains(rule) {
+            missing.push(rule.clone());
+        }
+    }
+    missing
+}
+
+fn mutes(rule: &MuteRule, title: &str) -> bool {
+    alerts::contains_word(title, &rule.keyword)
+}
+
+fn wrote(rule: &AuthorRule, authors: &[String]) -> bool {
+    let name = rule.name.trim().to_lowercase();
+    !name.is_empty()
+        && authors
+            .iter()
+            .any(|author| author.trim().to_lowercase() == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(authors: &[&str]) -> Item {
+        Item {
+            authors: authors.iter().map(|a| a.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn pulled(source_name: &str, title: &str, authors: &[&str], days_old: i64) -> PulledItem {
+        PulledItem {
+            source_name: source_name.to_string(),
+            title: title.to_string(),
+            link: format!("https://example.com/{}", title.replace(' ', "-")),
+            authors: authors.iter().map(|a| a.to_string()).collect(),
+            tags: Vec::new(),
+            pub_date: Some(Utc::now() - chrono::Duration::days(days_old)),
+        }
+    }
+
+    #[test]
+    fn test_rule_set_test_replays_items() {
+        let config: Config = toml::from_str(
+            r#"
+[[sources.rss]]
+name = "Blog"
+url = "https://blog.example.com/feed.xml"
+tags = ["news"]
+
+[[sources.rss]]
+name = "Papers"
+url = "https://papers.example.com/feed.xml"
+"#,
+        )
+        .unwrap();
+        let rules: RuleSet = toml::from_str(
+            r#"
+[[authors]]
+name = "Julia Evans"
+tags = ["favorites"]
+
+[[alerts]]
+keywords = ["outage"]
+
+[[alerts]]
+tags = ["favorites"]
+
+[[mutes]]
+keyword = "outage"
+
+[[retention]]
+filter = "tag:news"
+keep_days = 7
+
+[[retention]]
+"#,
+        )
+        .unwrap();
+        let items = [
+            pulled("Blog", "Major outage today", &[], 1),
+            pulled("Blog", "Old news", &["Julia Evans"], 30),
+            pulled("Papers", "Ancient paper", &[], 365),
+        ];
+
+        let reports = rules.test(&config, &items, Utc::now()).unwrap();
+        let matched: Vec<(&str, Vec<&str>)> = reports
+            .iter()
+            .map(|report| {
+                let titles = report.matches.iter().map(|i| i.title.as_str()).collect();
+                (report.rule.as_str(), titles)
+            })
+            .collect();
+        assert_eq!(
+            matched,
+            vec![
+                ("[[mutes]] #1", vec!["Major outage today"]),
+                ("[[authors]] #1", vec!["Old news"]),
+                // Muted items never reach the other rules
+                ("[[alerts]] #1", vec![]),
+                // Alerts see the tags author rules add
+                ("[[alerts]] #2", vec!["Old news"]),
+                ("[[retention]] #1", vec!["Old news"]),
+                ("[[retention]] #2", vec![]),
+            ]
+        );
+        assert_eq!(
+            reports[4].description,
+            "tag:news, keep last 7 days, plus starred"
+        );
+        assert_eq!(reports[2].description, "outage");
+    }
+
+    #[test]
+    fn test_rule_set_without_existing() {
+        let rules: RuleSet = toml::from_str(
+            "[[alerts]]\nkeywords = [\"a\"]\n\n[[alerts]]\nkeywords = [\"b\"]\n\n[[alerts]]\nkeywords = [\"b\"]\n",
+        )
+        .unwrap();
+        let existing: RuleSet = toml::from_str("[[alerts]]\nkeywords = [\"a\"]\n").unwrap();
+        let missing = rules.without(&existing);
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing.alerts[0].keywords, ["b"]);
+
+        let round_trip: RuleSet = toml::from_str(&rules.to_toml().unwrap()).unwrap();
+        assert_eq!(round_trip, rules);
+    }
+
+    #[test]
+    fn test_last_pull_round_trip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("last_pull.json");
+        assert_eq!(LastPull::load(&path).unwrap(), None);
+
+        let items = [Item {
+            source_name: "Blog".to_string(),
+            title: "Hello".to_string(),
+            link: "https://example.com/hello".to_string(),
+            authors: vec!["Julia Evans".to_string()],
+            is_starred: true,
+            ..Default::default()
+        }];
+        let pull = LastPull::new(&items, Utc::now());
+        pull.save(&path).unwrap();
+        assert_eq!(LastPull::load(&path).unwrap(), Some(pull));
+    }
+
+    #[test]
+    fn test_apply_author_rules() {
+        let rules = [
+            AuthorRule {
+                name: "JULIA EVANS ".to_string(),
+                star: true,
+                tags: vec!["favorites".to_string()],
+            },
+            AuthorRule {
+                name: "Kamal Marhubi".to_string(),
+                star: false,
+                tags: vec!["favorites".to_string(), "systems".to_string()],
+            },
+        ];
+        let mut items = [
+            item(&["Julia Evans", "Kamal Marhubi"]),
+            item(&["Kamal Marhubi"]),
+            item(&["Someone Else"]),
+            item(&[]),
+        ];
+
+        assert_eq!(apply_author_rules(&rules, &mut items), 2);
+        assert!(items[0].is_starred);
+        assert_eq!(items[0].tags, ["favorites", "systems"]);
+        assert!(!items[1].is_starred);
+        assert_eq!(items[1].tags, ["favorites", "systems"]);
+        assert_eq!(items[2], item(&["Someone Else"]));
+    }
+
+    #[test]
+    fn test_apply_mute_rules() {
+        let rules = [MuteRule {
+            keyword: "Crypto ".to_string(),
+        }];
+        let titled = |title: &str| Item {
+            title: title.to_string(),
+            ..Default::default()
+        };
+        let mut items = vec![
+            titled("Crypto winter is here"),
+            titled("Cryptography basics"),
+            titled("Why I quit CRYPTO"),
+        ];
+
+        assert_eq!(apply_mute_rules(&rules, &mut items), 2);
+        assert_eq!(items, [titled("Cryptography basics")]);
+    }
+}
@@ -0,0 +1,158 @@
+use crate::config::Settings;
+
+/// Maximum lengths (in characters) for text fields stored from feeds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextLimits {
+    pub max_title_length: usize,
+    pub max_summary_length: usize,
+}
+
+impl Default for TextLimits {
+    fn default() -> Self {
+        Self::from(&Settings::default())
+    }
+}
+
+impl From<&Settings> for TextLimits {
+    fn from(settings: &Settings) -> Self {
+        Self {
+            max_title_length: settings.max_title_length,
+            max_summary_length: settings.max_summary_length,
+        }
+    }
+}
+
+impl TextLimits {
+    /// Sanitize and truncate an item title
+    pub fn title(&self, text: &str) -> String {
+        truncate(&clean_text(text), self.max_title_length)
+    }
+
+    /// Sanitize and truncate an item summary
+    pub fn summary(&self, text: &str) -> String {
+        truncate(&clean_text(text), self.max_summary_length)
+    }
+}
+
+/// Strip terminal escape sequences and control characters, then collapse whitespace
+pub fn clean_text(text: &str) -> String {
+    strip_control(text)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Remove ANSI escape sequences and control characters from text
+///
+/// Newlines and tabs are kept as whitespace; every other control character
+/// (including C1 controls such as the single-byte CSI) is dropped.
+pub fn strip_control(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\u{1b}' => match chars.next() {
+                // CSI: parameters and intermediates, terminated by a final byte
+                Some('[') => {
+                    for c in chars.by_ref() {
+                        if ('\u{40}'..='\u{7e}').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                // OSC/DCS/APC/PM/SOS: terminated by BEL or ST (ESC \)
+                Some(']' | 'P' | '_' | '^' | 'X') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\u{07}' {
+                            break;
+                        }
+                        if c == '\u{1b}' && chars.peek() == Some(&'\\') {
+                            chars.next();
+                            break;
+                        }
+                    }
+                }
+                // Two-character escape sequence, or a lone trailing ESC
+                _ => {}
+            },
+            '\n' | '\t' => output.push(c),
+            c if c.is_control() => {}
+            c => output.push(c),
+        }
+    }
+
+    output
+}
+
+/// Truncate text to at most `max_chars` characters, ending with an ellipsis if shortened
+pub fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+
+    if max_chars == 0 {
+        return String::new();
+    }
+
+    let kept: String = text.chars().take(max_chars - 1).collect();
+    format!("{}…", kept.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_csi_sequences() {
+        assert_eq!(strip_control("\u{1b}[31mred\u{1b}[0m text"), "red text");
+        assert_eq!(strip_control("\u{1b}[2J\u{1b}[Hcleared"), "cleared");
+    }
+
+    #[test]
+    fn test_strip_osc_sequences() {
+        assert_eq!(strip_control("\u{1b}]0;evil title\u{07}safe"), "safe");
+        assert_eq!(
+            strip_control("\u{1b}]8;;https://evil.example\u{1b}\\link\u{1b}]8;;\u{1b}\\"),
+            "link"
+        );
+    }
+
+    #[test]
+    fn test_strip_control_characters() {
+        assert_eq!(
+            strip_control("bell\u{07} back\u{08}space"),
+            "bell backspace"
+        );
+        assert_eq!(strip_control("c1\u{9b}31m csi"), "c131m csi");
+        assert_eq!(
+            strip_control("keeps\nnewlines\tand tabs"),
+            "keeps\nnewlines\tand tabs"
+        );
+        assert_eq!(strip_control("trailing escape\u{1b}"), "trailing escape");
+    }
+
+    #[test]
+    fn test_clean_text_collapses_whitespace() {
+        assert_eq!(clean_text("  a \u{1b}[1m b\n\nc  "), "a b c");
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("exactly10!", 10), "exactly10!");
+        assert_eq!(truncate("this is too long", 8), "this is…");
+        assert_eq!(truncate("ünïcödé text", 6), "ünïcö…");
+        assert_eq!(truncate("anything", 0), "");
+    }
+
+    #[test]
+    fn test_text_limits() {
+        let limits = TextLimits {
+            max_title_length: 5,
+            max_summary_length: 10,
+        };
+        assert_eq!(limits.title("\u{1b}[31mHello world"), "Hell…");
+        assert_eq!(limits.summary("Hello world"), "Hello wor…");
+    }
+}
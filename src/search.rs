@@ -0,0 +1,154 @@
+use crate::source::Item;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// Score for each query word found in an item's title
+const TITLE_WORD: f64 = 2.0;
+
+/// Score for each query word found only in an item's summary
+const SUMMARY_WORD: f64 = 1.0;
+
+/// Extra score when the whole query appears in the title as typed
+const TITLE_PHRASE: f64 = 2.0;
+
+/// Days after which the recency bonus has halved
+const RECENCY_HALF_LIFE_DAYS: f64 = 7.0;
+
+/// A stored item matching a search, with how well it matches
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub item: Item,
+    /// Higher is better; comparable between searches for the same text
+    pub score: f64,
+}
+
+/// A search hit found in one or more profiles
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileHit {
+    pub hit: SearchHit,
+    /// Profiles whose sources the item belongs to, in the order searched
+    pub profiles: Vec<String>,
+}
+
+/// Score `items` found for `text`, best first
+///
+/// Each word of `text` in the title scores more than one only in the
+/// summary, the whole text in the title scores extra, and newer items get a
+/// bonus that fades over weeks. Equal scores keep the order of `items`.
+pub fn rank(items: Vec<Item>, text: &str, now: DateTime<Utc>) -> Vec<SearchHit> {
+    let words: Vec<String> = text.split_whitespace().map(str::to_lowercase).collect();
+    let phrase = words.join(" ");
+    let mut hits: Vec<SearchHit> = items
+        .into_iter()
+        .map(|item| SearchHit {
+            score: score(&item, &words, &phrase, now),
+            item,
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits
+}
+
+/// Merge the hits of each profile into one list, best first
+///
+/// An item found in several profiles appears once, labeled with each.
+pub fn merge(results: Vec<(String, Vec<SearchHit>)>) -> Vec<ProfileHit> {
+    let mut merged: Vec<ProfileHit> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for (profile, hits) in results {
+        for hit in hits {
+            match positions.get(&hit.item.id) {
+                Some(&position) => merged[position].profiles.push(profile.clone()),
+                None => {
+                    positions.insert(hit.item.id.clone(), merged.len());
+                    merged.push(ProfileHit {
+                        hit,
+                        profiles: vec![profile.clone()],
+                    });
+                }
+            }
+        }
+    }
+    merged.sort_by(|a, b| b.hit.score.total_cmp(&a.hit.score));
+    merged
+}
+
+fn score(item: &Item, words: &[String], phrase: &str, now: DateTime<Utc>) -> f64 {
+    let title = item.title.to_lowercase();
+    let summary = item.summary.as_deref().unwrap_or_default().to_lowercase();
+    let mut score: f64 = words
+        .iter()
+        .map(|word| {
+            if title.contains(word.as_str()) {
+                TITLE_WORD
+            } else if summary.contains(word.as_str()) {
+                SUMMARY_WORD
+            } else {
+                0.0
+            }
+        })
+        .sum();
+    if words.len() > 1 && title.contains(phrase) {
+        score += TITLE_PHRASE;
+    }
+    if let Some(date) = item.effective_date(now) {
+        let days = (now - date).num_minutes().max(0) as f64 / (24.0 * 60.0);
+        score += 0.5_f64.powf(days / RECENCY_HALF_LIFE_DAYS);
+    }
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn item(id: &str, title: &str, summary: &str, days_old: i64, now: DateTime<Utc>) -> Item {
+        Item {
+            id: id.to_string(),
+            title: title.to_string(),
+            summary: Some(summary.to_string()),
+            pub_date: Some(now - Duration::days(days_old)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_rank() {
+        let now = Utc::now();
+        let items = vec![
+            item("summary", "Weekly notes", "async rust tips", 0, now),
+            item("title", "Rust and async", "", 30, now),
+            item("phrase", "Async Rust in practice", "", 30, now),
+        ];
+        let hits = rank(items, "async rust", now);
+        let ids: Vec<&str> = hits.iter().map(|hit| hit.item.id.as_str()).collect();
+        assert_eq!(ids, ["phrase", "title", "summary"]);
+
+        // Among equal matches, newer items rank higher
+        let items = vec![
+            item("old", "Rust", "", 60, now),
+            item("new", "Rust", "", 1, now),
+        ];
+        let hits = rank(items, "rust", now);
+        assert_eq!(hits[0].item.id, "new");
+        assert!(hits[0].score > hits[1].score);
+    }
+
+    #[test]
+    fn test_merge_labels_profiles() {
+        let now = Utc::now();
+        let hit = |id: &str, score: f64| SearchHit {
+            item: item(id, id, "", 0, now),
+            score,
+        };
+        let merged = merge(vec![
+            ("work".to_string(), vec![hit("a", 3.0), hit("shared", 1.0)]),
+            ("home".to_string(), vec![hit("shared", 1.0), hit("b", 4.0)]),
+        ]);
+        let ids: Vec<&str> = merged.iter().map(|hit| hit.hit.item.id.as_str()).collect();
+        assert_eq!(ids, ["b", "a", "shared"]);
+        assert_eq!(merged[2].profiles, ["work", "home"]);
+        assert_eq!(merged[0].profiles, ["home"]);
+    }
+}
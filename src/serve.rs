@@ -0,0 +1,859 @@
+use crate::audit::{AuditEntry, AuditLog};
+use crate::color::{self, Tone};
+use crate::config::Config;
+use crate::database::{Database, ItemQuery};
+use crate::display::relative_time;
+use crate::error::ClioError;
+use crate::filter::{Filter, matching_sources};
+use crate::health::{DEFAULT_MAX_PULL_AGE_HOURS, HealthReport, Heartbeat};
+use crate::opml;
+use crate::source::{Item, Paper, WeatherAlert};
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::middleware::{self, Next};
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::{DateTime, Duration, Utc};
+use html_escape::{encode_double_quoted_attribute, encode_text};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use url::Url;
+
+/// Items shown when a request does not ask for a number
+const DEFAULT_LIMIT: usize = 100;
+
+/// Host names a server bound to a loopback address answers to
+const LOOPBACK_HOSTS: [&str; 3] = ["localhost", "127.0.0.1", "[::1]"];
+
+/// A local web interface and JSON API over the stored items, for `clio serve`
+///
+/// Routes:
+/// - `GET /`: the reading page, taking the same parameters as `/api/items`
+/// - `GET /api/items?unread=true&starred=true&filter=tag:NAME&q=TEXT&limit=N`
+/// - `GET /api/items/{id}`
+/// - `POST /api/items/{id}/{read,unread,star,unstar}`
+/// - `GET /healthz`: the checks of `clio health`, failing with 503
+/// - `GET /opml`: the subscriptions as OPML, re-read from the configuration
+///   file on each request when it is known
+#[derive(Debug, Clone)]
+pub struct Server {
+    database: Database,
+    config: Arc<Config>,
+    /// File `config` was loaded from, read again for each `/opml` request so
+    /// that sources added since the server started are listed
+    config_path: Option<PathBuf>,
+    max_pull_age: Duration,
+    /// Where item changes are recorded, if anywhere
+    audit_log: Option<AuditLog>,
+    /// Only answer requests addressed to a loopback host name
+    loopback_only: bool,
+}
+
+/// Only `GET /healthz`, for `clio watch`, adding a check that its poll loop
+/// is still running to those of `clio health`
+///
+/// Answers any host, since the checks reveal nothing about the items.
+#[derive(Debug, Clone)]
+pub struct HealthServer {
+    /// Storage opened by the watch, if it could be; otherwise opened again on
+    /// each request, so the storage check reports why it fails
+    database: Option<Database>,
+    heartbeat: Heartbeat,
+    max_pull_age: Duration,
+}
+
+/// Item as returned by the JSON API
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ItemJson {
+    pub id: String,
+    pub source: String,
+    pub title: String,
+    pub link: String,
+    pub summary: Option<String>,
+    pub authors: Vec<String>,
+    pub tags: Vec<String>,
+    pub published: Option<DateTime<Utc>>,
+    pub thumbnail: Option<String>,
+    pub enclosure: Option<String>,
+    pub read: bool,
+    pub starred: bool,
+    /// Journal, abstract and identifiers, for enriched items
+    pub paper: Option<Paper>,
+    /// Severity, area and onset, for weather warnings
+    pub alert: Option<WeatherAlert>,
+    /// When the item stops applying, such as the end of a warning
+    pub expires: Option<DateTime<Utc>>,
+}
+
+/// Item selection given in the query string
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct ItemParams {
+    unread: bool,
+    starred: bool,
+    /// tag:NAME or source:NAME
+    filter: Option<String>,
+    q: Option<String>,
+    limit: Option<usize>,
+}
+
+/// A state change requested for one item
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Read,
+    Unread,
+    Star,
+    Unstar,
+}
+
+/// An error answered with its status and a JSON `{"error": ...}` body
+#[derive(Debug)]
+struct ServeError {
+    status: StatusCode,
+    message: String,
+}
+
+impl Server {
+    pub fn new(database: Database, config: Config) -> Self {
+        Self {
+            database,
+            config: Arc::new(config),
+            config_path: None,
+            max_pull_age: Duration::hours(i64::from(DEFAULT_MAX_PULL_AGE_HOURS)),
+            audit_log: None,
+            loopback_only: true,
+        }
+    }
+
+    /// Longest time since the last successful pull before `/healthz` fails
+    pub fn with_max_pull_age(mut self, max_pull_age: Duration) -> Self {
+        self.max_pull_age = max_pull_age;
+        self
+    }
+
+    /// Export the subscriptions in the configuration file at `path` from
+    /// `/opml`, as it is when requested
+    pub fn with_config_path(mut self, path: PathBuf) -> Self {
+        self.config_path = Some(path);
+        self
+    }
+
+    /// Record items marked read or starred from the browser in `audit_log`
+    pub fn with_audit_log(mut self, audit_log: AuditLog) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
+
+    /// Serve requests on `listener` until interrupted
+    ///
+    /// On a loopback address, requests naming any other host are refused, so
+    /// that web pages cannot reach the server through DNS rebinding.
+    pub async fn run(mut self, listener: TcpListener) -> Result<(), ClioError> {
+        let address = listener
+            .local_addr()
+            .map_err(|e| ClioError::Network(format!("Failed to read server address: {e}")))?;
+        self.loopback_only = address.ip().is_loopback();
+
+        axum::serve(listener, self.router())
+            .with_graceful_shutdown(async {
+                let _ = tokio::signal::ctrl_c().await;
+            })
+            .await
+            .map_err(|e| ClioError::Network(format!("Server failed: {e}")))
+    }
+
+    fn router(self) -> Router {
+        Router::new()
+            .route("/", get(page))
+            .route("/items/{id}/{action}", post(page_action))
+            .route("/api/items", get(list_items))
+            .route("/api/items/{id}", get(show_item))
+            .route("/api/items/{id}/{action}", post(item_action))
+            .route("/healthz", get(healthz))
+            .route("/opml", get(subscriptions))
+            .layer(middleware::from_fn_with_state(self.clone(), guard))
+            .with_state(self)
+    }
+
+    /// Load items on the blocking pool, where the database client runs
+    async fn blocking<T: Send + 'static>(
+        &self,
+        load: impl FnOnce(Database) -> Result<T, ClioError> + Send + 'static,
+    ) -> Result<T, ServeError> {
+        let database = self.database.clone();
+        tokio::task::spawn_blocking(move || load(database))
+            .await
+            .map_err(|e| ServeError::internal(e.to_string()))?
+            .map_err(ServeError::from)
+    }
+
+    async fn items(&self, params: ItemParams) -> Result<Vec<Item>, ServeError> {
+        let filters = match params.filter.as_deref().map(str::parse::<Filter>) {
+            Some(Ok(filter)) => vec![filter],
+            Some(Err(e)) => return Err(ServeError::new(StatusCode::BAD_REQUEST, e.to_string())),
+            None => Vec::new(),
+        };
+        let query = ItemQuery {
+            sources: matching_sources(&self.config.sources, &filters),
+            unread_only: params.unread,
+            starred_only: params.starred,
+            hide_archived: true,
+            text: params.q.filter(|text| !text.trim().is_empty()),
+            limit: Some(params.limit.unwrap_or(DEFAULT_LIMIT)),
+            ..Default::default()
+        };
+        self.blocking(move |database| database.items(&query)).await
+    }
+
+    async fn apply(&self, id: String, action: Action) -> Result<Item, ServeError> {
+        let ids = vec![id];
+        let mut item = self
+            .blocking(move |database| match action {
+                Action::Read | Action::Unread => database.set_read(&ids, action == Action::Read),
+                Action::Star | Action::Unstar => database.set_starred(&ids, action == Action::Star),
+            })
+            .await?
+            .pop()
+            .ok_or_else(|| ServeError::internal("No item changed".to_string()))?;
+
+        match action {
+            Action::Read | Action::Unread => item.is_read = action == Action::Read,
+            Action::Star | Action::Unstar => item.is_starred = action == Action::Star,
+        }
+        if let Some(audit_log) = &self.audit_log {
+            let entry =
+                AuditEntry::new("serve", action.change(), 1).with_links(vec![item.link.clone()]);
+            if let Err(e) = audit_log.append(&entry) {
+                eprintln!("{} {e}", color::err(Tone::Warning, "Warning:"));
+            }
+        }
+        Ok(item)
+    }
+}
+
+impl HealthServer {
+    pub fn new(database: Option<Database>, heartbeat: Heartbeat) -> Self {
+        Self {
+            database,
+            heartbeat,
+            max_pull_age: Duration::hours(i64::from(DEFAULT_MAX_PULL_AGE_HOURS)),
+        }
+    }
+
+    /// Longest time since the last successful pull before `/healthz` fails
+    pub fn with_max_pull_age(mut self, max_pull_age: Duration) -> Self {
+        self.max_pull_age = max_pull_age;
+        self
+    }
+
+    /// Serve requests on `listener` until the task running it is dropped
+    pub async fn run(self, listener: TcpListener) -> Result<(), ClioError> {
+        let router = Router::new()
+            .route("/healthz", get(watch_healthz))
+            .with_state(self);
+        axum::serve(listener, router)
+            .await
+            .map_err(|e| ClioError::Network(format!("Health server failed: {e}")))
+    }
+}
+
+impl From<Item> for ItemJson {
+    fn from(item: Item) -> Self {
+        Self {
+            id: item.id,
+            source: item.source_name,
+            title: item.title,
+            link: item.link,
+            summary: item.summary,
+            authors: item.authors,
+            tags: item.tags,
+            published: item.pub_date,
+            thumbnail: item.thumbnail,
+            enclosure: item.enclosure.map(|enclosure| enclosure.url),
+            read: item.is_read,
+            starred: item.is_starred,
+            paper: item.paper,
+            alert: item.alert,
+            expires: item.expires_at,
+        }
+    }
+}
+
+impl Action {
+    fn parse(action: &str) -> Result<Self, ServeError> {
+        match action {
+            "read" => Ok(Action::Read),
+            "unread" => Ok(Action::Unread),
+            "star" => Ok(Action::Star),
+            "unstar" => Ok(Action::Unstar),
+            _ => Err(ServeError::new(
+                StatusCode::NOT_FOUND,
+                format!("Unknown action '{action}'; expected read, unread, star or unstar"),
+            )),
+        }
+    }
+
+    /// Name of the change in the audit log, as the CLI commands record it
+    fn change(self) -> &'static str {
+        match self {
+            Action::Read => "mark read",
+            Action::Unread => "mark unread",
+            Action::Star => "star",
+            Action::Unstar => "unstar",
+        }
+    }
+}
+
+impl ServeError {
+    fn new(status: StatusCode, message: String) -> Self {
+        Self { status, message }
+    }
+
+    fn internal(message: String) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, message)
+    }
+}
+
+impl From<ClioError> for ServeError {
+    fn from(e: ClioError) -> Self {
+        let message = e.to_string();
+        // Unknown, malformed and ambiguous ids, as reported by Database::find_item
+        let status = if ["No item with ID", "Invalid item ID", "is ambiguous"]
+            .iter()
+            .any(|reason| message.contains(reason))
+        {
+            StatusCode::NOT_FOUND
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        };
+        Self::new(status, message)
+    }
+}
+
+impl IntoResponse for ServeError {
+    fn into_response(self) -> Response {
+        (self.status, Json(json!({ "error": self.message }))).into_response()
+    }
+}
+
+/// Refuse requests that did not come from a page of this server
+///
+/// Checks the `Host` header when bound to a loopback address, and the
+/// `Origin` header of form posts, which browsers send cross-site without asking.
+async fn guard(State(server): State<Server>, request: Request, next: Next) -> Response {
+    let headers = request.headers();
+    let host = headers
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok());
+    if server.loopback_only && !host.is_some_and(is_loopback_host) {
+        return ServeError::new(StatusCode::FORBIDDEN, "Unexpected Host header".to_string())
+            .into_response();
+    }
+    if request.method() != axum::http::Method::GET && !same_origin(headers) {
+        return ServeError::new(StatusCode::FORBIDDEN, "Cross-origin request".to_string())
+            .into_response();
+    }
+    next.run(request).await
+}
+
+async fn page(
+    State(server): State<Server>,
+    Query(params): Query<ItemParams>,
+) -> Result<Html<String>, ServeError> {
+    let items = server.items(params.clone()).await?;
+    Ok(Html(render_page(&items, &params, Utc::now())))
+}
+
+/// Apply an action from a button on the reading page, then go back to it
+async fn page_action(
+    State(server): State<Server>,
+    Path((id, action)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Result<Redirect, ServeError> {
+    server.apply(id, Action::parse(&action)?).await?;
+    Ok(Redirect::to(&return_path(&headers)))
+}
+
+async fn list_items(
+    State(server): State<Server>,
+    Query(params): Query<ItemParams>,
+) -> Result<Json<Vec<ItemJson>>, ServeError> {
+    let items = server.items(params).await?;
+    Ok(Json(items.into_iter().map(ItemJson::from).collect()))
+}
+
+async fn show_item(
+    State(server): State<Server>,
+    Path(id): Path<String>,
+) -> Result<Json<ItemJson>, ServeError> {
+    let item = server
+        .blocking(move |database| database.find_item(&id))
+        .await?;
+    Ok(Json(item.into()))
+}
+
+async fn item_action(
+    State(server): State<Server>,
+    Path((id, action)): Path<(String, String)>,
+) -> Result<Json<ItemJson>, ServeError> {
+    let item = server.apply(id, Action::parse(&action)?).await?;
+    Ok(Json(item.into()))
+}
+
+async fn healthz(State(server): State<Server>) -> Response {
+    let max_pull_age = server.max_pull_age;
+    let database = server.database.clone();
+    let report = tokio::task::spawn_blocking(move || {
+        HealthReport::check(Ok(database), max_pull_age, Utc::now())
+    })
+    .await;
+    match report {
+        Ok(report) => health_response(&report),
+        Err(_) => ServeError::internal("Health check failed".to_string()).into_response(),
+    }
+}
+
+async fn watch_healthz(State(server): State<HealthServer>) -> Response {
+    let HealthServer {
+        database,
+        heartbeat,
+        max_pull_age,
+    } = server;
+    let report = tokio::task::spawn_blocking(move || {
+        let database = database.map_or_else(Database::new, Ok);
+        let now = Utc::now();
+        HealthReport::check(database, max_pull_age, now).with_scheduler(&heartbeat, now)
+    })
+    .await;
+    match report {
+        Ok(report) => health_response(&report),
+        Err(_) => ServeError::internal("Health check failed".to_string()).into_response(),
+    }
+}
+
+/// The checks of `report` as JSON, answered with 503 when any failed
+fn health_response(report: &HealthReport) -> Response {
+    let status = if report.healthy() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    let checks: Vec<_> = report
+        .checks
+        .iter()
+        .map(
+            |check| json!({ "name": check.name, "healthy": check.healthy, "detail": check.detail }),
+        )
+        .collect();
+    let body = json!({ "healthy": report.healthy(), "checks": checks });
+    (status, Json(body)).into_response()
+}
+
+async fn subscriptions(State(server): State<Server>) -> Result<impl IntoResponse, ServeError> {
+    let sources = match server.config_path {
+        Some(path) => {
+            tokio::task::spawn_blocking(move || Config::load_strict(&path))
+                .await
+                .map_err(|e| ServeError::internal(e.to_string()))??
+                .sources
+        }
+        None => server.config.sources.clone(),
+    };
+    Ok((
+        [(header::CONTENT_TYPE, "text/x-opml; charset=utf-8")],
+        opml::export(&sources),
+    ))
+}
+
+fn is_loopback_host(host: &str) -> bool {
+    let name = match host.rsplit_once(':') {
+        // Keep IPv6 literals such as [::1] whole
+        Some((name, port)) if !port.contains(']') => name,
+        _ => host,
+    };
+    LOOPBACK_HOSTS
+        .iter()
+        .any(|loopback| name.eq_ignore_ascii_case(loopback))
+}
+
+/// Whether a request carries no `Origin`, or one naming the host it was sent to
+fn same_origin(headers: &HeaderMap) -> bool {
+    let Some(origin) = headers.get(header::ORIGIN) else {
+        return true;
+    };
+    let origin = origin
+        .to_str()
+        .ok()
+        .and_then(|origin| Url::parse(origin).ok());
+    let host = headers
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok());
+    match (origin, host) {
+        (Some(origin), Some(host)) => {
+            let origin_host = match (origin.host_str(), origin.port()) {
+                (Some(name), Some(port)) => format!("{name}:{port}"),
+                (Some(name), None) => name.to_string(),
+                _ => return false,
+            };
+            origin_host.eq_ignore_ascii_case(host)
+        }
+        _ => false,
+    }
+}
+
+/// Where to go after a page action: the page it came from, without its host
+fn return_path(headers: &HeaderMap) -> String {
+    headers
+        .get(header::REFERER)
+        .and_then(|referer| referer.to_str().ok())
+        .and_then(|referer| Url::parse(referer).ok())
+        .map(|referer| match referer.query() {
+            Some(query) => format!("{}?{query}", referer.path()),
+            None => referer.path().to_string(),
+        })
+        .unwrap_or_else(|| "/".to_string())
+}
+
+fn render_page(items: &[Item], params: &ItemParams, now: DateTime<Utc>) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>clio</title>\n<style>\n\
+         body { font-family: system-ui, sans-serif; max-width: 46rem; margin: 0 auto; padding: 1rem; line-height: 1.5; }\n\
+         nav a { margin-right: 1rem; }\n\
+         article { border-bottom: 1px solid #ddd; padding: 0.75rem 0; }\n\
+         article.read h2 { font-weight: normal; }\n\
+         h2 { font-size: 1.1rem; margin: 0; }\n\
+         .meta { color: #666; font-size: 0.85rem; margin: 0.25rem 0; }\n\
+         form { display: inline; }\n\
+         </style>\n</head>\n<body>\n",
+    );
+
+    let query = params.q.as_deref().unwrap_or_default();
+    html.push_str(&format!(
+        "<nav><a href=\"/\">All</a><a href=\"/?unread=true\">Unread</a>\
+         <a href=\"/?starred=true\">Starred</a>\
+         <form method=\"get\" action=\"/\"><input type=\"search\" name=\"q\" value=\"{}\" \
+         placeholder=\"Search\"></form></nav>\n",
+        encode_double_quoted_attribute(query)
+    ));
+
+    if items.is_empty() {
+        html.push_str("<p>No items.</p>\n");
+    }
+    for item in items {
+        html.push_str(&render_item(item, now));
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn render_item(item: &Item, now: DateTime<Utc>) -> String {
+    let title = encode_text(&item.title);
+    // Only web links are clickable; a feed could carry javascript: URLs
+    let heading = if item.link.starts_with("https://") || item.link.starts_with("http://") {
+        format!(
+            "<a href=\"{}\" rel=\"noreferrer\">{title}</a>",
+            encode_double_quoted_attribute(&item.link)
+        )
+    } else {
+        title.to_string()
+    };
+
+    let mut meta = vec![item.source_name.clone()];
+    if !item.authors.is_empty() {
+        meta.push(item.authors.join(", "));
+    }
+    meta.extend(item.paper.as_ref().and_then(|paper| paper.journal.clone()));
+    meta.extend(relative_time(item, now));
+    let meta = encode_text(&meta.join(" · ")).to_string();
+
+    // An enriched item's abstract says more than the feed's summary
+    let summary = item
+        .paper
+        .as_ref()
+        .and_then(|paper| paper.abstract_text.as_deref())
+        .or(item.summary.as_deref())
+        .map(|summary| format!("<p>{}</p>\n", encode_text(summary)))
+        .unwrap_or_default();
+
+    let id = encode_double_quoted_attribute(&item.id);
+    let read = if item.is_read {
+        ("unread", "Mark unread")
+    } else {
+        ("read", "Mark read")
+    };
+    let star = if item.is_starred {
+        ("unstar", "Unstar")
+    } else {
+        ("star", "Star")
+    };
+    let buttons: String = [read, star]
+        .iter()
+        .map(|(action, label)| {
+            format!(
+                "<form method=\"post\" action=\"/items/{id}/{action}\"><button>{label}</button></form>\n"
+            )
+        })
+        .collect();
+
+    let class = if item.is_read { " class=\"read\"" } else { "" };
+    format!(
+        "<article{class}>\n<h2>{heading}</h2>\n<p class=\"meta\">{meta}</p>\n{summary}{buttons}</article>\n"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SupabaseConfig;
+    use crate::database::SupabaseClient;
+    use serde_json::Value;
+    use std::sync::Mutex;
+
+    /// Storage holding one unread item
+    #[derive(Debug, Default)]
+    struct OneItem {
+        queries: Mutex<Vec<String>>,
+    }
+
+    impl SupabaseClient for OneItem {
+        fn execute(&self, query: &str) -> Result<(), ClioError> {
+            self.queries.lock().unwrap().push(query.to_string());
+            Ok(())
+        }
+
+        fn query(&self, query: &str) -> Result<Vec<Value>, ClioError> {
+            self.queries.lock().unwrap().push(query.to_string());
+            if query.contains("LIKE 'ffff") {
+                return Ok(Vec::new());
+            }
+            Ok(vec![json!({
+                "id": "0c6b1a52-3d0e-4a7c-9a55-7f2b8f6f1d10",
+                "source_name": "Blog",
+                "title": "Fish & <chips>",
+                "link": "javascript:alert(1)",
+                "summary": "A <b>bold</b> claim",
+                "pub_date": null,
+                "thumbnail_url": null,
+                "is_read": false,
+                "is_starred": false,
+                "authors": ["Julia Evans"],
+                "tags": null
+            })])
+        }
+
+        fn table_exists(&self, _table_name: &str) -> Result<bool, ClioError> {
+            Ok(true)
+        }
+
+        fn url(&self) -> &str {
+            "https://test.supabase.co"
+        }
+    }
+
+    async fn start() -> (String, Arc<OneItem>) {
+        let storage = Arc::new(OneItem::default());
+        let database = Database::with_client(
+            SupabaseConfig {
+                url: "https://test.supabase.co".to_string(),
+                secret_key: "sb_secret_test123".to_string(),
+            },
+            storage.clone(),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = Server::new(database, Config::default());
+        tokio::spawn(server.run(listener));
+        (url, storage)
+    }
+
+    #[tokio::test]
+    async fn test_page_escapes_items() {
+        let (url, _) = start().await;
+        let page = reqwest::get(format!("{url}/?unread=true"))
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+
+        assert!(page.contains("<h2>Fish &amp; &lt;chips&gt;</h2>"));
+        assert!(page.contains("<p>A &lt;b&gt;bold&lt;/b&gt; claim</p>"));
+        assert!(page.contains("Blog · Julia Evans"));
+        assert!(!page.contains("javascript:"));
+        assert!(page.contains("action=\"/items/0c6b1a52-3d0e-4a7c-9a55-7f2b8f6f1d10/read\""));
+    }
+
+    #[tokio::test]
+    async fn test_api_items_and_actions() {
+        let (url, storage) = start().await;
+        let client = reqwest::Client::new();
+
+        let items: Value = client
+            .get(format!("{url}/api/items?starred=true&limit=5"))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(items[0]["title"], "Fish & <chips>");
+        assert_eq!(items[0]["authors"], json!(["Julia Evans"]));
+        let queries = storage.queries.lock().unwrap().join("\n");
+        assert!(queries.contains("COALESCE(is_starred, FALSE)"));
+        assert!(queries.contains("LIMIT 5"));
+
+        let response = client
+            .post(format!("{url}/api/items/0c6b1a52/star"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 200);
+        let item: Value = response.json().await.unwrap();
+        assert_eq!(item["starred"], true);
+
+        let response = client
+            .post(format!("{url}/api/items/ffff/read"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 404);
+
+        let response = client
+            .get(format!("{url}/api/items?filter=news"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_guard() {
+        let (url, _) = start().await;
+        let client = reqwest::Client::new();
+
+        let response = client
+            .get(format!("{url}/api/items"))
+            .header("Host", "attacker.example")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 403);
+
+        let response = client
+            .post(format!("{url}/items/0c6b1a52/read"))
+            .header("Origin", "https://attacker.example")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 403);
+    }
+
+    #[tokio::test]
+    async fn test_opml() {
+        let (url, _) = start().await;
+        let response = reqwest::get(format!("{url}/opml")).await.unwrap();
+        assert_eq!(response.status().as_u16(), 200);
+        assert!(
+            response
+                .text()
+                .await
+                .unwrap()
+                .contains("<opml version=\"2.0\">")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_opml_rereads_config() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        let blog =
+            "[[sources.rss]]\nname = \"Blog\"\nurl = \"https://blog.example.com/feed.xml\"\n";
+        std::fs::write(&path, blog).unwrap();
+        let database = Database::with_client(
+            SupabaseConfig {
+                url: "https://test.supabase.co".to_string(),
+                secret_key: "sb_secret_test123".to_string(),
+            },
+            Arc::new(OneItem::default()),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = Server::new(database, Config::load_strict(&path).unwrap())
+            .with_config_path(path.clone());
+        tokio::spawn(server.run(listener));
+
+        let opml = || async {
+            reqwest::get(format!("{url}/opml"))
+                .await
+                .unwrap()
+                .text()
+                .await
+                .unwrap()
+        };
+        assert!(opml().await.contains("text=\"Blog\""));
+
+        let weather =
+            "[[sources.weather]]\nname = \"Storms\"\nprovider = \"noaa\"\narea = \"CA\"\n";
+        std::fs::write(&path, format!("{blog}{weather}")).unwrap();
+        let opml = opml().await;
+        assert!(opml.contains("text=\"Blog\""));
+        assert!(opml.contains("xmlUrl=\"https://api.weather.gov/alerts/active.atom?area=CA\""));
+    }
+
+    #[tokio::test]
+    async fn test_watch_healthz_checks_scheduler() {
+        let database = Database::with_client(
+            SupabaseConfig {
+                url: "https://test.supabase.co".to_string(),
+                secret_key: "sb_secret_test123".to_string(),
+            },
+            Arc::new(OneItem::default()),
+        );
+        let stuck = Heartbeat::new(Duration::minutes(10), Utc::now() - Duration::hours(1));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(HealthServer::new(Some(database), stuck.clone()).run(listener));
+
+        let response = reqwest::get(format!("{url}/healthz")).await.unwrap();
+        assert_eq!(response.status().as_u16(), 503);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["checks"][0]["healthy"], true);
+        assert_eq!(body["checks"][2]["name"], "scheduler");
+        assert_eq!(body["checks"][2]["healthy"], false);
+
+        stuck.beat(Utc::now());
+        let body: Value = reqwest::get(format!("{url}/healthz"))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(body["checks"][2]["healthy"], true);
+    }
+
+    #[test]
+    fn test_is_loopback_host() {
+        assert!(is_loopback_host("localhost:8080"));
+        assert!(is_loopback_host("127.0.0.1"));
+        assert!(is_loopback_host("[::1]:8080"));
+        assert!(!is_loopback_host("clio.example:8080"));
+        assert!(!is_loopback_host("127.0.0.1.example"));
+    }
+
+    #[test]
+    fn test_return_path() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(return_path(&headers), "/");
+        headers.insert(
+            header::REFERER,
+            "https://elsewhere.example/?unread=true".parse().unwrap(),
+        );
+        assert_eq!(return_path(&headers), "/?unread=true");
+    }
+}
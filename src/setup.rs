@@ -0,0 +1,169 @@
+use crate::config::{Config, RssSource, SupabaseConfig};
+use crate::database::Database;
+use crate::discover::{DiscoveredFeed, discover};
+use crate::error::ClioError;
+use crate::http::{FeedClient, RequestGuard};
+use dialoguer::{Confirm, Input, Select};
+
+/// Ask for feeds and storage to create a configuration, for `clio init`
+///
+/// Each site or feed URL entered is looked up with [`discover`], so a blog's
+/// home page is enough. Returns `None` when the user would rather start from
+/// the example configuration. Must run in a terminal, outside an async runtime.
+pub fn run() -> Result<Option<Config>, ClioError> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| ClioError::Config(format!("Failed to start runtime: {e}")))?;
+    let client = FeedClient::new(RequestGuard::new(false));
+
+    eprintln!("Let's set up clio. Enter the sites or feeds to follow, then an empty line.");
+    let mut config = Config::default();
+    loop {
+        let url: String = Input::new()
+            .with_prompt("Site or feed URL")
+            .allow_empty(true)
+            .interact_text()
+            .map_err(answer_err)?;
+        let url = url.trim();
+        if url.is_empty() {
+            break;
+        }
+
+        let feeds = match runtime.block_on(discover(&client, url)) {
+            Ok(feeds) => feeds,
+            Err(e) => {
+                eprintln!("  Could not load {url}: {e}");
+                continue;
+            }
+        };
+        let Some(feed) = choose_feed(feeds)? else {
+            eprintln!("  No feed found at {url}");
+            continue;
+        };
+
+        let name: String = Input::new()
+            .with_prompt("Name")
+            .default(feed.default_name())
+            .interact_text()
+            .map_err(answer_err)?;
+        match config.add_source(RssSource::new(name.trim().to_string(), feed.url)) {
+            Ok(()) => eprintln!("  Added '{}'", name.trim()),
+            Err(e) => eprintln!("  Not added: {e}"),
+        }
+    }
+
+    if config.sources.rss.is_empty()
+        && Confirm::new()
+            .with_prompt("No feeds added. Start with the example feeds instead?")
+            .default(true)
+            .interact()
+            .map_err(answer_err)?
+    {
+        return Ok(None);
+    }
+
+    choose_storage()?;
+    Ok(Some(config))
+}
+
+/// The feed to add, asking when a page advertises several
+fn choose_feed(mut feeds: Vec<DiscoveredFeed>) -> Result<Option<DiscoveredFeed>, ClioError> {
+    if feeds.len() <= 1 {
+        return Ok(feeds.pop());
+    }
+
+    let labels: Vec<String> = feeds.iter().map(feed_label).collect();
+    let index = Select::new()
+        .with_prompt("Several feeds found; which one?")
+        .items(&labels)
+        .default(0)
+        .interact()
+        .map_err(answer_err)?;
+    Ok(Some(feeds.swap_remove(index)))
+}
+
+/// Ask where pulled items go, checking Supabase credentials when wanted
+///
+/// Credentials are only ever read from the environment, so nothing here
+/// ends up in the configuration file.
+fn choose_storage() -> Result<(), ClioError> {
+    let choice = Select::new()
+        .with_prompt("Where should pulled items be stored?")
+        .items(&["Supabase", "Nowhere yet (fetch only)"])
+        .default(0)
+        .interact()
+        .map_err(answer_err)?;
+    if choice != 0 {
+        return Ok(());
+    }
+
+    let supabase = match SupabaseConfig::from_env() {
+        Ok(supabase) => supabase,
+        Err(_) => {
+            eprintln!(
+                "  Set SUPABASE_URL and SUPABASE_SECRET_KEY (sb_secret_...) wherever clio runs; \
+                 credentials are read from the environment, never from the configuration file."
+            );
+            return Ok(());
+        }
+    };
+    let verify = Confirm::new()
+        .with_prompt(format!(
+            "Verify the Supabase credentials for {}?",
+            supabase.url()
+        ))
+        .default(true)
+        .interact()
+        .map_err(answer_err)?;
+    if verify {
+        match Database::connect(supabase).and_then(|database| database.verify_connection()) {
+            Ok(()) => eprintln!("  ✓ Connected to Supabase"),
+            Err(e) => eprintln!("  ✗ {e}"),
+        }
+    }
+    Ok(())
+}
+
+fn feed_label(feed: &DiscoveredFeed) -> String {
+    match &feed.title {
+        Some(title) => format!("{title} ({})", feed.url),
+        None => feed.url.clone(),
+    }
+}
+
+fn answer_err(e: dialoguer::Error) -> ClioError {
+    ClioError::Config(format!("Failed to read answer: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_name() {
+        let mut feed = DiscoveredFeed {
+            url: "https://blog.example.com/feed.xml".to_string(),
+            title: Some("Example Blog".to_string()),
+        };
+        assert_eq!(feed.default_name(), "Example Blog");
+        assert_eq!(
+            feed_label(&feed),
+            "Example Blog (https://blog.example.com/feed.xml)"
+        );
+
+        feed.title = None;
+        assert_eq!(feed.default_name(), "blog.example.com");
+        assert_eq!(feed_label(&feed), "https://blog.example.com/feed.xml");
+    }
+
+    #[test]
+    fn test_choose_single_feed() {
+        let feed = DiscoveredFeed {
+            url: "https://example.com/feed.xml".to_string(),
+            title: None,
+        };
+        assert_eq!(choose_feed(Vec::new()).unwrap(), None);
+        assert_eq!(choose_feed(vec![feed.clone()]).unwrap(), Some(feed));
+    }
+}
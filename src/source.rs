@@ -1,12 +1,32 @@
+use crate::cassette::Cassette;
+use crate::config::Config;
+use crate::display::normalize_title;
 use crate::error::ClioError;
+use crate::filter::name_matches;
+use crate::http::FeedClient;
+use crate::sanitize::TextLimits;
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Timelike, Utc, Weekday};
+use serde::Serialize;
 use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
 
+pub mod advisories;
+pub mod changelog;
 pub mod rss;
+pub mod statuspage;
+pub mod synthetic;
+pub mod twitch;
+pub mod weather;
+
+/// How far ahead of the local clock a publication date may be and still count as "now"
+///
+/// Feed servers with fast clocks stamp fresh items a few minutes in the future.
+pub const CLOCK_SKEW_TOLERANCE: chrono::Duration = chrono::Duration::minutes(10);
 
 /// Represents a single content item from any source
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Item {
     /// Unique identifier for the session
     pub id: String,
@@ -18,8 +38,178 @@ pub struct Item {
     pub link: String,
     /// Article summary/description
     pub summary: Option<String>,
+    /// Names of the writers, in feed order
+    pub authors: Vec<String>,
+    /// Labels given by author rules
+    pub tags: Vec<String>,
     /// Publication date
     pub pub_date: Option<DateTime<Utc>>,
+    /// When the feed says the item stops being relevant (RSS `<expirationDate>`)
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Attached media file, such as a podcast episode
+    pub enclosure: Option<Enclosure>,
+    /// Preview image URL (`media:thumbnail`, an image enclosure, or `itunes:image`)
+    pub thumbnail: Option<String>,
+    /// Points or upvotes, as reported by aggregator feeds
+    pub score: Option<u32>,
+    /// Number of comments, as reported by aggregator feeds
+    pub comments: Option<u32>,
+    /// Read state, for items loaded from storage
+    pub is_read: bool,
+    /// Starred state, for items loaded from storage
+    pub is_starred: bool,
+    /// Publication details looked up for sources with `enrich` set
+    pub paper: Option<Paper>,
+    /// Severity, area and timing of an official weather warning
+    pub alert: Option<WeatherAlert>,
+}
+
+/// What Crossref or arXiv know about the paper an item is about
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Paper {
+    pub doi: Option<String>,
+    /// Identifier such as `2401.01234`, without a version
+    pub arxiv_id: Option<String>,
+    /// Journal or proceedings the paper appeared in
+    pub journal: Option<String>,
+    #[serde(rename = "abstract")]
+    pub abstract_text: Option<String>,
+}
+
+/// What a CAP (Common Alerting Protocol) weather warning says about itself
+///
+/// The warning's end is the item's `expires_at`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct WeatherAlert {
+    /// Kind of warning, such as "Winter Storm Warning"
+    pub event: String,
+    pub severity: Severity,
+    /// Places covered, such as "Marin; San Francisco"
+    pub area: String,
+    /// When the weather is expected to begin, or the warning takes effect
+    pub onset: Option<DateTime<Utc>>,
+}
+
+/// CAP severity of a warning, most dangerous first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Extraordinary threat to life or property
+    Extreme,
+    /// Significant threat to life or property
+    Severe,
+    /// Possible threat to life or property
+    Moderate,
+    /// Minimal to no known threat
+    Minor,
+    #[default]
+    Unknown,
+}
+
+impl Severity {
+    /// Parse a CAP `<severity>` value, or a stored one, ignoring case
+    pub fn parse(value: &str) -> Self {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "extreme" => Self::Extreme,
+            "severe" => Self::Severe,
+            "moderate" => Self::Moderate,
+            "minor" => Self::Minor,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Lowercase name, as stored and used for tags
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Extreme => "extreme",
+            Self::Severe => "severe",
+            Self::Moderate => "moderate",
+            Self::Minor => "minor",
+            Self::Unknown => "unknown",
+        }
+    }
+
+    /// Whether the warning is about a significant threat to life or property
+    pub fn is_severe(self) -> bool {
+        self <= Self::Severe
+    }
+}
+
+/// Media file attached to an item (RSS `<enclosure>` or Atom `rel="enclosure"`)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Enclosure {
+    pub url: String,
+    /// MIME type declared by the feed
+    pub mime_type: Option<String>,
+    /// File size in bytes, as declared by the feed
+    pub length: Option<u64>,
+    /// Playing time, from `itunes:duration`
+    pub duration: Option<Duration>,
+}
+
+impl Item {
+    /// Whether the item's feed-provided expiry has passed
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Identity of the item by normalized title and publication date
+    ///
+    /// Used instead of the link for feeds whose links change on every request.
+    pub fn fingerprint(&self) -> String {
+        let date = self
+            .pub_date
+            .map(|date| date.to_rfc3339())
+            .unwrap_or_default();
+        format!("{}|{date}", normalize_title(&self.title))
+    }
+
+    /// Publication date to display and sort by
+    ///
+    /// Dates less than [`CLOCK_SKEW_TOLERANCE`] ahead of `now` are treated as
+    /// `now`; dates further out are kept, since those are deliberate.
+    pub fn effective_date(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.pub_date.map(|date| {
+            if date > now && date - now <= CLOCK_SKEW_TOLERANCE {
+                now
+            } else {
+                date
+            }
+        })
+    }
+}
+
+/// When a feed says it is worth polling (RSS `skipHours`/`skipDays`, `ttl`,
+/// `sy:updatePeriod`, HTTP `Cache-Control`)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeedSchedule {
+    /// Hours of the day (UTC) during which the feed does not update
+    pub skip_hours: Vec<u32>,
+    /// Days of the week (UTC) on which the feed does not update
+    pub skip_days: Vec<Weekday>,
+    /// Expected time between updates
+    pub update_interval: Option<Duration>,
+}
+
+impl FeedSchedule {
+    /// Whether a feed last fetched at `fetched_at` is worth fetching again at `now`
+    ///
+    /// Skip lists covering every hour or every day are ignored, since a feed
+    /// that claims never to update is misconfigured rather than dead.
+    pub fn is_due(&self, fetched_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        let skips_hour = self.skip_hours.len() < 24 && self.skip_hours.contains(&now.hour());
+        let skips_day = self.skip_days.len() < 7 && self.skip_days.contains(&now.weekday());
+        if skips_hour || skips_day {
+            return false;
+        }
+
+        match self.update_interval {
+            Some(interval) => (now - fetched_at)
+                .to_std()
+                .is_ok_and(|since| since >= interval),
+            None => true,
+        }
+    }
 }
 
 /// Trait for all content sources
@@ -33,6 +223,178 @@ pub trait Source: Send + Sync + Debug {
 
     /// Fetch all items from this source
     async fn fetch(&self) -> Result<Vec<Item>, ClioError>;
+
+    /// Response body bytes received by all fetches so far, for bandwidth accounting
+    fn bytes_received(&self) -> u64 {
+        0
+    }
+
+    /// The fallback address that answered the last fetch, when the source's
+    /// own URL failed
+    fn mirror_used(&self) -> Option<String> {
+        None
+    }
+
+    /// Where the last fetch ended up after following redirects, when that is
+    /// not the address requested
+    fn final_url(&self) -> Option<String> {
+        None
+    }
+
+    /// Hash of the last response body parsed, kept so later runs can skip
+    /// bodies that have not changed
+    fn body_hash(&self) -> Option<String> {
+        None
+    }
+
+    /// Whether the last fetch returned the same body as the one before it,
+    /// so nothing was parsed and no items were returned
+    fn not_modified(&self) -> bool {
+        false
+    }
+
+    /// Skip parsing the next response when its body hashes to `body_hash`,
+    /// the [`Source::body_hash`] of an earlier run
+    fn skip_unchanged(&self, _body_hash: String) {}
+
+    /// Whether the source is worth fetching at `now`, given the update schedule
+    /// its feed declared when it was last fetched
+    fn is_due(&self, _now: DateTime<Utc>) -> bool {
+        true
+    }
+}
+
+/// Build the sources defined in a configuration, applying its global settings
+///
+/// Feeds send their requests through `client`, so they share its connections.
+/// They record to or replay from a cassette when `CLIO_RECORD` or
+/// `CLIO_REPLAY` is set (see [`Cassette`]).
+pub fn from_config(
+    config: &Config,
+    client: &FeedClient,
+) -> Result<Vec<Arc<dyn Source>>, ClioError> {
+    let limits = TextLimits::from(&config.settings);
+    let cassette = Cassette::from_env()?.map(Arc::new);
+
+    Ok(config
+        .sources
+        .active()
+        .map(|source| {
+            let feed = feed(config, source, client, limits);
+            let feed = match &cassette {
+                Some(cassette) => feed.with_cassette(cassette.clone()),
+                None => feed,
+            };
+            Arc::new(feed) as Arc<dyn Source>
+        })
+        .chain(
+            config.sources.synthetic.iter().map(|source| {
+                Arc::new(synthetic::SyntheticSource::from(source)) as Arc<dyn Source>
+            }),
+        )
+        .chain(config.sources.advisories.iter().map(|source| {
+            let advisories = advisories::AdvisorySource::from(source)
+                .with_limits(limits)
+                .with_client(client.clone());
+            Arc::new(advisories) as Arc<dyn Source>
+        }))
+        .chain(config.sources.statuspage.iter().map(|source| {
+            let page = statuspage::StatusPageSource::from(source)
+                .with_limits(limits)
+                .with_client(client.clone());
+            Arc::new(page) as Arc<dyn Source>
+        }))
+        .chain(config.sources.changelog.iter().map(|source| {
+            let changelog = changelog::ChangelogSource::from(source)
+                .with_limits(limits)
+                .with_client(client.clone());
+            Arc::new(changelog) as Arc<dyn Source>
+        }))
+        .chain(config.sources.twitch.iter().map(|source| {
+            let twitch = twitch::TwitchSource::from(source)
+                .with_limits(limits)
+                .with_client(client.clone());
+            Arc::new(twitch) as Arc<dyn Source>
+        }))
+        .chain(config.sources.weather.iter().map(|source| {
+            let weather = weather::WeatherSource::from(source)
+                .with_limits(limits)
+                .with_client(client.clone());
+            Arc::new(weather) as Arc<dyn Source>
+        }))
+        .collect())
+}
+
+/// Build the sources of a configuration that can be pulled offline: its feeds,
+/// answered from `cassette`, and its synthetic sources
+///
+/// Sources that call other APIs are left out.
+pub fn replayed(
+    config: &Config,
+    client: &FeedClient,
+    cassette: Arc<Cassette>,
+) -> Vec<Arc<dyn Source>> {
+    let limits = TextLimits::from(&config.settings);
+    config
+        .sources
+        .active()
+        .map(|source| {
+            let feed = feed(config, source, client, limits).with_cassette(cassette.clone());
+            Arc::new(feed) as Arc<dyn Source>
+        })
+        .chain(
+            config.sources.synthetic.iter().map(|source| {
+                Arc::new(synthetic::SyntheticSource::from(source)) as Arc<dyn Source>
+            }),
+        )
+        .collect()
+}
+
+/// Keep the sources whose names match any of `patterns` (see [`name_matches`])
+///
+/// Fails when a pattern matches none of the sources, naming it, so that a
+/// typo is not mistaken for a source with nothing new.
+pub fn select(
+    sources: Vec<Arc<dyn Source>>,
+    patterns: &[String],
+) -> Result<Vec<Arc<dyn Source>>, ClioError> {
+    if let Some(unmatched) = patterns.iter().find(|pattern| {
+        !sources
+            .iter()
+            .any(|source| name_matches(pattern, source.name()))
+    }) {
+        return Err(ClioError::Config(format!(
+            "No active source matches '{unmatched}'"
+        )));
+    }
+    Ok(sources
+        .into_iter()
+        .filter(|source| {
+            patterns
+                .iter()
+                .any(|pattern| name_matches(pattern, source.name()))
+        })
+        .collect())
+}
+
+/// A configured feed with the global settings applied
+fn feed(
+    config: &Config,
+    source: &crate::config::RssSource,
+    client: &FeedClient,
+    limits: TextLimits,
+) -> rss::RssSource {
+    let feed = rss::RssSource::new(source.name.clone(), source.primary_url().to_string())
+        .with_mirrors(source.mirrors())
+        .with_limits(limits)
+        .with_client(client.clone())
+        .with_dedup(source.dedup)
+        .with_collapsed_releases(source.collapse_releases)
+        .with_max_redirects(source.max_redirects(&config.settings));
+    match source.poll_interval_minutes {
+        Some(minutes) => feed.with_poll_interval(Duration::from_secs(minutes * 60)),
+        None => feed,
+    }
 }
 
 #[cfg(test)]
@@ -76,6 +438,7 @@ mod tests {
             link: "https://example.com/article".to_string(),
             summary: Some("Test summary".to_string()),
             pub_date: Some(Utc::now()),
+            ..Default::default()
         }];
 
         let source = MockSource {
@@ -115,6 +478,7 @@ mod tests {
             link: "https://example.com".to_string(),
             summary: None,
             pub_date: None,
+            ..Default::default()
         };
 
         let item2 = item1.clone();
@@ -126,4 +490,186 @@ mod tests {
         };
         assert_ne!(item1, item3);
     }
+
+    #[test]
+    fn test_item_fingerprint() {
+        let date = Utc::now();
+        let item = Item {
+            title: "Release notes: v2.0!".to_string(),
+            link: "https://example.com/post?rev=1".to_string(),
+            pub_date: Some(date),
+            ..Default::default()
+        };
+        let rewritten = Item {
+            title: "release notes v2 0".to_string(),
+            link: "https://example.com/post?rev=2".to_string(),
+            ..item.clone()
+        };
+        assert_eq!(item.fingerprint(), rewritten.fingerprint());
+
+        let later = Item {
+            pub_date: Some(date + chrono::Duration::days(1)),
+            ..item.clone()
+        };
+        assert_ne!(item.fingerprint(), later.fingerprint());
+    }
+
+    #[test]
+    fn test_item_is_expired() {
+        let now = Utc::now();
+        let item = Item {
+            expires_at: Some(now - chrono::Duration::hours(1)),
+            ..Default::default()
+        };
+        assert!(item.is_expired(now));
+
+        let item = Item {
+            expires_at: Some(now + chrono::Duration::hours(1)),
+            ..Default::default()
+        };
+        assert!(!item.is_expired(now));
+        assert!(!Item::default().is_expired(now));
+    }
+
+    #[test]
+    fn test_item_effective_date() {
+        let now = Utc::now();
+        let item = |offset: chrono::Duration| Item {
+            pub_date: Some(now + offset),
+            ..Default::default()
+        };
+
+        let skewed = item(chrono::Duration::minutes(3));
+        assert_eq!(skewed.effective_date(now), Some(now));
+
+        let past = item(-chrono::Duration::minutes(3));
+        assert_eq!(past.effective_date(now), past.pub_date);
+
+        let scheduled = item(chrono::Duration::days(2));
+        assert_eq!(scheduled.effective_date(now), scheduled.pub_date);
+
+        assert_eq!(Item::default().effective_date(now), None);
+    }
+
+    #[test]
+    fn test_feed_schedule_is_due() {
+        use chrono::TimeZone;
+
+        // Wednesday
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 3, 30, 0).unwrap();
+        let fetched_at = now - chrono::Duration::hours(2);
+
+        assert!(FeedSchedule::default().is_due(fetched_at, now));
+
+        let night = FeedSchedule {
+            skip_hours: vec![1, 2, 3, 4],
+            ..Default::default()
+        };
+        assert!(!night.is_due(fetched_at, now));
+        assert!(night.is_due(fetched_at, now + chrono::Duration::hours(2)));
+
+        let weekdays_only = FeedSchedule {
+            skip_days: vec![Weekday::Sat, Weekday::Sun],
+            ..Default::default()
+        };
+        assert!(weekdays_only.is_due(fetched_at, now));
+        assert!(!weekdays_only.is_due(fetched_at, now + chrono::Duration::days(3)));
+
+        let daily = FeedSchedule {
+            update_interval: Some(Duration::from_secs(24 * 60 * 60)),
+            ..Default::default()
+        };
+        assert!(!daily.is_due(fetched_at, now));
+        assert!(daily.is_due(fetched_at, now + chrono::Duration::days(1)));
+
+        let never = FeedSchedule {
+            skip_hours: (0..24).collect(),
+            ..Default::default()
+        };
+        assert!(never.is_due(fetched_at, now));
+    }
+
+    #[test]
+    fn test_select_sources_by_name_or_glob() {
+        let config = Config {
+            sources: crate::config::Sources {
+                rss: ["Hacker News", "Hacker Newsletter", "Lobsters"]
+                    .into_iter()
+                    .map(|name| {
+                        crate::config::RssSource::new(
+                            name.to_string(),
+                            format!("https://example.com/{}.xml", name.len()),
+                        )
+                    })
+                    .collect(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let sources = || from_config(&config, &FeedClient::default()).unwrap();
+        let names = |sources: Vec<Arc<dyn Source>>| {
+            sources
+                .iter()
+                .map(|source| source.name().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let selected = select(sources(), &["Hacker*".to_string()]).unwrap();
+        assert_eq!(names(selected), ["Hacker News", "Hacker Newsletter"]);
+
+        let selected = select(
+            sources(),
+            &["Lobsters".to_string(), "Hacker News".to_string()],
+        )
+        .unwrap();
+        assert_eq!(names(selected), ["Hacker News", "Lobsters"]);
+
+        let err = select(sources(), &["Lobsters".to_string(), "Reddit*".to_string()])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("No active source matches 'Reddit*'"));
+    }
+
+    #[test]
+    fn test_from_config() {
+        let config = Config {
+            sources: crate::config::Sources {
+                rss: vec![
+                    crate::config::RssSource::new(
+                        "Feed 1".to_string(),
+                        "https://example.com/feed1.xml".to_string(),
+                    ),
+                    crate::config::RssSource::new(
+                        "Feed 2".to_string(),
+                        "https://example.com/feed2.xml".to_string(),
+                    ),
+                ],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let client = FeedClient::default();
+        let sources = from_config(&config, &client).unwrap();
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0].name(), "Feed 1");
+        assert_eq!(sources[1].url(), "https://example.com/feed2.xml");
+
+        let mut paused = config.clone();
+        paused.set_paused("Feed 1", true).unwrap();
+        let sources = from_config(&paused, &client).unwrap();
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].name(), "Feed 2");
+
+        let mut synthetic = config.clone();
+        synthetic.sources.synthetic = vec![crate::config::SyntheticSource {
+            name: "Demo".to_string(),
+            items: 20,
+            latency_ms: 0,
+            failure_rate: 0.0,
+        }];
+        let sources = from_config(&synthetic, &client).unwrap();
+        assert_eq!(sources.len(), 3);
+        assert_eq!(sources[2].url(), "synthetic://demo");
+    }
 }
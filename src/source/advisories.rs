@@ -0,0 +1,484 @@
+use crate::config;
+use crate::error::{ClioError, ErrorContext, ErrorKind};
+use crate::http::{self, FeedClient};
+use crate::sanitize::TextLimits;
+use crate::source::{Item, Source};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use url::Url;
+use uuid::Uuid;
+
+const OSV_URL: &str = "https://api.osv.dev/v1";
+
+/// Most queries OSV accepts in one batch
+const QUERY_BATCH_SIZE: usize = 1000;
+
+/// Advisory records fetched at once
+const CONCURRENT_LOOKUPS: usize = 4;
+
+/// Tag on every advisory item
+const ADVISORY_TAG: &str = "advisory";
+
+/// Source of security advisories for a set of packages, from OSV
+///
+/// Advisories come in starred and tagged "advisory", plus their
+/// severity when the database gives one, so they stand out from feed items.
+/// With a lockfile, only advisories affecting the locked versions appear.
+#[derive(Debug, Clone)]
+pub struct AdvisorySource {
+    name: String,
+    ecosystem: String,
+    packages: Vec<String>,
+    lockfile: Option<PathBuf>,
+    client: FeedClient,
+    limits: TextLimits,
+    api_url: String,
+}
+
+/// A package to check, at its locked version if known
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Package {
+    name: String,
+    version: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CargoLock {
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+}
+
+#[derive(Deserialize)]
+struct LockedPackage {
+    name: String,
+    version: String,
+    /// Registry or git URL; path dependencies have none
+    source: Option<String>,
+}
+
+#[derive(Serialize)]
+struct QueryBatch<'a> {
+    queries: Vec<Query<'a>>,
+}
+
+#[derive(Serialize)]
+struct Query<'a> {
+    package: QueryPackage<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct QueryPackage<'a> {
+    name: &'a str,
+    ecosystem: &'a str,
+}
+
+#[derive(Deserialize)]
+struct BatchResponse {
+    #[serde(default)]
+    results: Vec<BatchResult>,
+}
+
+#[derive(Deserialize)]
+struct BatchResult {
+    #[serde(default)]
+    vulns: Vec<VulnId>,
+}
+
+#[derive(Deserialize)]
+struct VulnId {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct Vuln {
+    id: String,
+    summary: Option<String>,
+    details: Option<String>,
+    published: Option<DateTime<Utc>>,
+    withdrawn: Option<DateTime<Utc>>,
+    #[serde(default)]
+    database_specific: Value,
+}
+
+#[async_trait]
+impl Source for AdvisorySource {
+    async fn fetch(&self) -> Result<Vec<Item>, ClioError> {
+        let packages = self.packages_to_check()?;
+        if packages.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Which of the packages each advisory affects, by index
+        let mut affected: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (batch, chunk) in packages.chunks(QUERY_BATCH_SIZE).enumerate() {
+            let results = self.query_batch(chunk).await?;
+            for (index, result) in (batch * QUERY_BATCH_SIZE..).zip(results) {
+                for vuln in result.vulns {
+                    affected.entry(vuln.id).or_default().push(index);
+                }
+            }
+        }
+
+        let vulns: Vec<(Vuln, Vec<usize>)> = stream::iter(affected)
+            .map(
+                |(id, indexes)| async move { Ok::<_, ClioError>((self.vuln(&id).await?, indexes)) },
+            )
+            .buffer_unordered(CONCURRENT_LOOKUPS)
+            .try_collect()
+            .await?;
+
+        let mut items: Vec<Item> = vulns
+            .into_iter()
+            .filter(|(vuln, _)| vuln.withdrawn.is_none())
+            .map(|(vuln, indexes)| {
+                let affected: Vec<&Package> = indexes.iter().map(|&i| &packages[i]).collect();
+                self.item(vuln, &affected)
+            })
+            .collect();
+        items.sort_by_key(|item| std::cmp::Reverse(item.pub_date));
+        Ok(items)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn url(&self) -> &str {
+        &self.api_url
+    }
+}
+
+impl AdvisorySource {
+    /// Send requests through a client shared with other sources
+    pub fn with_client(mut self, client: FeedClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Use custom maximum lengths for stored text fields
+    pub fn with_limits(mut self, limits: TextLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// The configured packages, or the locked ones among them
+    fn packages_to_check(&self) -> Result<Vec<Package>, ClioError> {
+        let Some(path) = &self.lockfile else {
+            return Ok(self
+                .packages
+                .iter()
+                .map(|name| Package {
+                    name: name.clone(),
+                    version: None,
+                })
+                .collect());
+        };
+
+        let contents = std::fs::read_to_string(path)
+            .clio_config_err(format!("Failed to read {}", path.display()))?;
+        let lock: CargoLock =
+            toml::from_str(&contents).clio_parse_err(format!("Invalid {}", path.display()))?;
+        Ok(lock
+            .package
+            .into_iter()
+            .filter(|locked| {
+                locked
+                    .source
+                    .as_deref()
+                    .is_some_and(|source| source.starts_with("registry+"))
+            })
+            .filter(|locked| self.packages.is_empty() || self.packages.contains(&locked.name))
+            .map(|locked| Package {
+                name: locked.name,
+                version: Some(locked.version),
+            })
+            .collect())
+    }
+
+    /// Ids of the advisories affecting each package, in the order given
+    async fn query_batch(&self, packages: &[Package]) -> Result<Vec<BatchResult>, ClioError> {
+        let body = QueryBatch {
+            queries: packages
+                .iter()
+                .map(|package| Query {
+                    package: QueryPackage {
+                        name: &package.name,
+                        ecosystem: &self.ecosystem,
+                    },
+                    version: package.version.as_deref(),
+                })
+                .collect(),
+        };
+        let url = format!("{}/querybatch", self.api_url);
+        self.check_url(&url)?;
+        let response = self
+            .client
+            .post(&url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| {
+                http::request_failed(format!("Failed to query advisories for {}", self.name), e)
+            })?;
+        if !response.status().is_success() {
+            return Err(ClioError::request(
+                ErrorKind::Http(response.status().as_u16()),
+                format!(
+                    "HTTP {} querying advisories for {}",
+                    response.status(),
+                    self.name
+                ),
+            ));
+        }
+        let batch: BatchResponse = response
+            .json()
+            .await
+            .clio_parse_err("Invalid OSV query response")?;
+        Ok(batch.results)
+    }
+
+    async fn vuln(&self, id: &str) -> Result<Vuln, ClioError> {
+        let url = format!("{}/vulns/{id}", self.api_url);
+        self.check_url(&url)?;
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| http::request_failed(format!("Failed to load advisory {id}"), e))?;
+        if !response.status().is_success() {
+            return Err(ClioError::request(
+                ErrorKind::Http(response.status().as_u16()),
+                format!("HTTP {} loading advisory {id}", response.status()),
+            ));
+        }
+        response
+            .json()
+            .await
+            .clio_parse_err(format!("Invalid advisory {id}"))
+    }
+
+    fn check_url(&self, url: &str) -> Result<(), ClioError> {
+        let url = Url::parse(url).clio_network_err(format!("Invalid URL {url}"))?;
+        match self.client.guard() {
+            Some(guard) => guard.check_url(&url),
+            None => Ok(()),
+        }
+    }
+
+    fn item(&self, vuln: Vuln, packages: &[&Package]) -> Item {
+        let names: Vec<String> = packages
+            .iter()
+            .map(|package| match &package.version {
+                Some(version) => format!("{} {version}", package.name),
+                None => package.name.clone(),
+            })
+            .collect();
+        let headline = vuln
+            .summary
+            .as_deref()
+            .or_else(|| vuln.details.as_deref().and_then(|d| d.lines().next()))
+            .unwrap_or("Security advisory");
+
+        let mut tags = vec![ADVISORY_TAG.to_string()];
+        if let Some(severity) = vuln.database_specific["severity"].as_str() {
+            tags.push(severity.to_lowercase());
+        }
+        // RustSec marks unmaintained and unsound crates as informational
+        if let Some(kind) = vuln.database_specific["informational"].as_str() {
+            tags.push(kind.to_lowercase());
+        }
+
+        Item {
+            id: Uuid::new_v4().to_string(),
+            source_name: self.name.clone(),
+            title: self
+                .limits
+                .title(&format!("{} ({}): {headline}", vuln.id, names.join(", "))),
+            link: format!("https://osv.dev/vulnerability/{}", vuln.id),
+            summary: vuln.details.map(|details| self.limits.summary(&details)),
+            pub_date: vuln.published,
+            tags,
+            is_starred: true,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&config::AdvisorySource> for AdvisorySource {
+    fn from(source: &config::AdvisorySource) -> Self {
+        Self {
+            name: source.name.clone(),
+            ecosystem: source.ecosystem.clone(),
+            packages: source.packages.clone(),
+            lockfile: source.lockfile.clone(),
+            client: FeedClient::default(),
+            limits: TextLimits::default(),
+            api_url: OSV_URL.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Matcher;
+    use serde_json::json;
+    use std::io::Write;
+
+    const CARGO_LOCK: &str = r#"
+version = 3
+
+[[package]]
+name = "my-app"
+version = "0.1.0"
+
+[[package]]
+name = "smallvec"
+version = "1.6.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "tokio"
+version = "1.38.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+
+    fn source(server: &mockito::Server, config: config::AdvisorySource) -> AdvisorySource {
+        AdvisorySource {
+            api_url: server.url(),
+            ..AdvisorySource::from(&config)
+        }
+    }
+
+    fn config(packages: &[&str], lockfile: Option<PathBuf>) -> config::AdvisorySource {
+        config::AdvisorySource {
+            name: "Advisories".to_string(),
+            ecosystem: "crates.io".to_string(),
+            packages: packages.iter().map(|p| p.to_string()).collect(),
+            lockfile,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_advisories_for_lockfile() {
+        let mut lockfile = tempfile::NamedTempFile::new().unwrap();
+        lockfile.write_all(CARGO_LOCK.as_bytes()).unwrap();
+
+        let mut server = mockito::Server::new_async().await;
+        let query = server
+            .mock("POST", "/querybatch")
+            .match_body(Matcher::Json(json!({"queries": [
+                {"package": {"name": "smallvec", "ecosystem": "crates.io"}, "version": "1.6.0"},
+                {"package": {"name": "tokio", "ecosystem": "crates.io"}, "version": "1.38.0"}
+            ]})))
+            .with_body(
+                json!({"results": [
+                    {"vulns": [{"id": "RUSTSEC-2021-0003", "modified": "2023-06-13T13:10:24Z"}]},
+                    {}
+                ]})
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let _vuln = server
+            .mock("GET", "/vulns/RUSTSEC-2021-0003")
+            .with_body(
+                json!({
+                    "id": "RUSTSEC-2021-0003",
+                    "summary": "Buffer overflow in SmallVec::insert_many",
+                    "details": "A bug in insert_many\ncan overflow the buffer.",
+                    "published": "2021-01-08T12:00:00Z",
+                    "modified": "2023-06-13T13:10:24Z",
+                    "database_specific": {"severity": "CRITICAL"}
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let items = source(&server, config(&[], Some(lockfile.path().to_path_buf())))
+            .fetch()
+            .await
+            .unwrap();
+
+        query.assert_async().await;
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            items[0].title,
+            "RUSTSEC-2021-0003 (smallvec 1.6.0): Buffer overflow in SmallVec::insert_many"
+        );
+        assert_eq!(
+            items[0].link,
+            "https://osv.dev/vulnerability/RUSTSEC-2021-0003"
+        );
+        assert_eq!(
+            items[0].summary.as_deref(),
+            Some("A bug in insert_many can overflow the buffer.")
+        );
+        assert_eq!(items[0].tags, ["advisory", "critical"]);
+        assert!(items[0].is_starred);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_skips_withdrawn_advisories() {
+        let mut server = mockito::Server::new_async().await;
+        let _query = server
+            .mock("POST", "/querybatch")
+            .match_body(Matcher::Json(json!({"queries": [
+                {"package": {"name": "tokio", "ecosystem": "crates.io"}}
+            ]})))
+            .with_body(
+                json!({"results": [{"vulns": [{"id": "GHSA-1"}, {"id": "GHSA-2"}]}]}).to_string(),
+            )
+            .create_async()
+            .await;
+        let _withdrawn = server
+            .mock("GET", "/vulns/GHSA-1")
+            .with_body(
+                json!({"id": "GHSA-1", "summary": "Retracted", "withdrawn": "2024-01-02T00:00:00Z"})
+                    .to_string(),
+            )
+            .create_async()
+            .await;
+        let _current = server
+            .mock("GET", "/vulns/GHSA-2")
+            .with_body(json!({"id": "GHSA-2", "details": "First line\nSecond line"}).to_string())
+            .create_async()
+            .await;
+
+        let items = source(&server, config(&["tokio"], None))
+            .fetch()
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].title, "GHSA-2 (tokio): First line");
+        assert_eq!(items[0].tags, ["advisory"]);
+    }
+
+    #[test]
+    fn test_packages_from_lockfile() {
+        let mut lockfile = tempfile::NamedTempFile::new().unwrap();
+        lockfile.write_all(CARGO_LOCK.as_bytes()).unwrap();
+        let source = AdvisorySource::from(&config(&["tokio"], Some(lockfile.path().to_path_buf())));
+
+        assert_eq!(
+            source.packages_to_check().unwrap(),
+            [Package {
+                name: "tokio".to_string(),
+                version: Some("1.38.0".to_string()),
+            }]
+        );
+
+        let missing =
+            AdvisorySource::from(&config(&[], Some(PathBuf::from("/nonexistent/Cargo.lock"))));
+        assert!(missing.packages_to_check().is_err());
+    }
+}
@@ -0,0 +1,294 @@
+use crate::config;
+use crate::error::{ClioError, ErrorContext, ErrorKind};
+use crate::http::{self, FeedClient};
+use crate::sanitize::TextLimits;
+use crate::source::{Item, Source};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use regex::Regex;
+use std::sync::LazyLock;
+use url::Url;
+use uuid::Uuid;
+
+/// Newest releases kept from a changelog; older sections are history
+/// nobody is waiting to read
+const MAX_RELEASES: usize = 50;
+
+/// A version such as `1.2.0`, `v0.9` or `2.0.0-beta.1` in a heading
+static VERSION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\bv?\d+\.\d+(?:\.\d+)?(?:-[0-9a-z][0-9a-z.]*)?\b")
+        .expect("valid version pattern")
+});
+
+/// An ISO date such as `2024-05-01`, or one written out like `May 1, 2024`
+static DATE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b(\d{4}-\d{2}-\d{2})\b|\b([A-Z][a-z]+ \d{1,2},? \d{4})\b")
+        .expect("valid date pattern")
+});
+
+/// Source of releases from a Markdown changelog fetched over HTTP
+///
+/// Every top-level version heading, such as `## [1.2.0] - 2024-05-01` in the
+/// Keep a Changelog layout, becomes an item titled with the version, whose
+/// summary is the section below it. For projects with no feed and no
+/// release page.
+#[derive(Debug, Clone)]
+pub struct ChangelogSource {
+    name: String,
+    url: String,
+    client: FeedClient,
+    limits: TextLimits,
+}
+
+/// A version's section of a changelog
+#[derive(Debug, Clone, PartialEq)]
+struct Release {
+    version: String,
+    date: Option<DateTime<Utc>>,
+    body: String,
+}
+
+#[async_trait]
+impl Source for ChangelogSource {
+    async fn fetch(&self) -> Result<Vec<Item>, ClioError> {
+        let url = Url::parse(&self.url).clio_network_err(format!("Invalid URL {}", self.url))?;
+        if let Some(guard) = self.client.guard() {
+            guard.check_url(&url)?;
+        }
+        let response = self.client.get(&self.url).send().await.map_err(|e| {
+            http::request_failed(format!("Failed to fetch changelog {}", self.name), e)
+        })?;
+        if !response.status().is_success() {
+            return Err(ClioError::request(
+                ErrorKind::Http(response.status().as_u16()),
+                format!("HTTP {} from {}", response.status(), self.url),
+            ));
+        }
+        let text = response
+            .text()
+            .await
+            .clio_network_err(format!("Failed to read changelog {}", self.name))?;
+
+        Ok(parse_releases(&text)
+            .into_iter()
+            .take(MAX_RELEASES)
+            .map(|release| self.item(release))
+            .collect())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl ChangelogSource {
+    /// Send requests through a client shared with other sources
+    pub fn with_client(mut self, client: FeedClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Use custom maximum lengths for stored text fields
+    pub fn with_limits(mut self, limits: TextLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    fn item(&self, release: Release) -> Item {
+        let anchor: String = release
+            .version
+            .chars()
+            .filter(|c| c.is_alphanumeric() || *c == '-')
+            .collect();
+        Item {
+            id: Uuid::new_v4().to_string(),
+            source_name: self.name.clone(),
+            title: self.limits.title(&release.version),
+            link: format!("{}#{}", self.url, anchor.to_lowercase()),
+            summary: Some(self.limits.summary(&release.body)).filter(|s| !s.is_empty()),
+            pub_date: release.date,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&config::ChangelogSource> for ChangelogSource {
+    fn from(source: &config::ChangelogSource) -> Self {
+        Self {
+            name: source.name.clone(),
+            url: source.url.clone(),
+            client: FeedClient::default(),
+            limits: TextLimits::default(),
+        }
+    }
+}
+
+/// The releases of a Markdown changelog, in file order
+///
+/// Releases are the headings with a version at the shallowest level any
+/// version heading uses, so `# Changelog` above `## 1.0.0` is not one, and
+/// `### Added` below it stays part of its section. Headings in fenced code
+/// blocks are ignored.
+fn parse_releases(text: &str) -> Vec<Release> {
+    let mut in_fence = false;
+    let headings: Vec<(usize, usize, &str)> = text
+        .lines()
+        .enumerate()
+        .filter_map(|(number, line)| {
+            if line.trim_start().starts_with("```") {
+                in_fence = !in_fence;
+            }
+            if in_fence {
+                return None;
+            }
+            let level = line.bytes().take_while(|&b| b == b'#').count();
+            let heading = line[level..].strip_prefix(' ')?;
+            (1..=6)
+                .contains(&level)
+                .then_some((number, level, heading.trim()))
+        })
+        .collect();
+    let Some(level) = headings
+        .iter()
+        .filter(|(_, _, heading)| VERSION.is_match(heading))
+        .map(|&(_, level, _)| level)
+        .min()
+    else {
+        return Vec::new();
+    };
+
+    let lines: Vec<&str> = text.lines().collect();
+    let sections: Vec<(usize, &str)> = headings
+        .iter()
+        .filter(|&&(_, l, _)| l <= level)
+        .map(|&(number, _, heading)| (number, heading))
+        .collect();
+    sections
+        .iter()
+        .enumerate()
+        .filter_map(|(i, &(start, heading))| {
+            let version = VERSION.find(heading)?.as_str().to_string();
+            let end = sections.get(i + 1).map_or(lines.len(), |&(end, _)| end);
+            Some(Release {
+                version,
+                date: parse_date(heading),
+                body: lines[start + 1..end].join("\n").trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// The release date written in a heading, at midnight UTC
+fn parse_date(heading: &str) -> Option<DateTime<Utc>> {
+    let captures = DATE.captures(heading)?;
+    let date = match (captures.get(1), captures.get(2)) {
+        (Some(iso), _) => NaiveDate::parse_from_str(iso.as_str(), "%Y-%m-%d").ok()?,
+        (_, Some(written)) => {
+            let written = written.as_str().replace(',', "");
+            NaiveDate::parse_from_str(&written, "%B %d %Y")
+                .or_else(|_| NaiveDate::parse_from_str(&written, "%b %d %Y"))
+                .ok()?
+        }
+        _ => return None,
+    };
+    Some(date.and_hms_opt(0, 0, 0)?.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const CHANGELOG: &str = "\
+# Changelog
+
+All notable changes to this project are documented here.
+
+## [Unreleased]
+
+- Work in progress
+
+## [1.2.0] - 2024-05-01
+
+### Added
+
+- A `--json` flag
+
+```markdown
+## 9.9.9 is not a release
+```
+
+## v1.1.0 (March 3, 2024)
+
+- Fixed a crash
+
+## 1.0.0
+
+First release.
+";
+
+    #[test]
+    fn test_parse_releases() {
+        let releases = parse_releases(CHANGELOG);
+
+        let versions: Vec<&str> = releases.iter().map(|r| r.version.as_str()).collect();
+        assert_eq!(versions, ["1.2.0", "v1.1.0", "1.0.0"]);
+        assert_eq!(
+            releases[0].date,
+            Some(Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap())
+        );
+        assert!(
+            releases[0]
+                .body
+                .starts_with("### Added\n\n- A `--json` flag")
+        );
+        assert!(releases[0].body.contains("9.9.9 is not a release"));
+        assert_eq!(
+            releases[1].date,
+            Some(Utc.with_ymd_and_hms(2024, 3, 3, 0, 0, 0).unwrap())
+        );
+        assert_eq!(releases[2].date, None);
+        assert_eq!(releases[2].body, "First release.");
+    }
+
+    #[test]
+    fn test_parse_releases_at_top_level() {
+        let releases = parse_releases("# 2.0.0\n\n## Breaking\n\n- Renamed 1.x APIs\n\n# 1.0.0\n");
+        assert_eq!(releases.len(), 2);
+        assert_eq!(releases[0].body, "## Breaking\n\n- Renamed 1.x APIs");
+        assert!(parse_releases("# Notes\n\nNothing versioned here.").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_changelog() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/CHANGELOG.md")
+            .with_header("content-type", "text/plain; charset=utf-8")
+            .with_body(CHANGELOG)
+            .create_async()
+            .await;
+        let url = format!("{}/CHANGELOG.md", server.url());
+        let source = ChangelogSource::from(&config::ChangelogSource {
+            name: "Project".to_string(),
+            url: url.clone(),
+        });
+
+        let items = source.fetch().await.unwrap();
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].title, "1.2.0");
+        assert_eq!(items[0].link, format!("{url}#120"));
+        assert!(
+            items[0]
+                .summary
+                .as_deref()
+                .unwrap()
+                .starts_with("### Added - A `--json` flag")
+        );
+        assert_eq!(items[1].link, format!("{url}#v110"));
+    }
+}
@@ -1,60 +1,117 @@
-use crate::error::{ClioError, ErrorContext};
-use crate::source::{Item, Source};
+use crate::cassette::{Cassette, CassetteMode};
+use crate::config::Dedup;
+use crate::error::{ClioError, ErrorContext, ErrorKind};
+use crate::http::{self, DEFAULT_MAX_REDIRECTS, FeedClient, RequestGuard};
+use crate::releases::collapse_patch_releases;
+use crate::sanitize::{TextLimits, strip_control};
+use crate::source::{Enclosure, FeedSchedule, Item, Source};
+use crate::trace::CacheStatus;
 use async_trait::async_trait;
 use atom_syndication::Feed as AtomFeed;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Utc, Weekday};
 use html_escape::decode_html_entities;
-use reqwest::Client;
+use quick_xml::Reader;
+use quick_xml::events::Event;
+use regex::Regex;
+use reqwest::StatusCode;
+use reqwest::header::CACHE_CONTROL;
 use rss::Channel;
+use rss::extension::syndication::UpdatePeriod;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
 use std::time::Duration;
+use url::Url;
 use uuid::Uuid;
 
+/// Score line in aggregator descriptions, as written by hnrss.org ("Points: 42")
+static POINTS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\bpoints:\s*(\d+)").expect("valid points pattern"));
+
+/// Comment count line in aggregator descriptions ("# Comments: 7")
+static COMMENT_COUNT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)#\s*comments:\s*(\d+)").expect("valid comment count pattern")
+});
+
 /// RSS/Atom feed source implementation
 #[derive(Debug, Clone)]
 pub struct RssSource {
     name: String,
     url: String,
-    client: Client,
+    /// Addresses tried in order when `url` fails
+    mirrors: Vec<String>,
+    /// The mirror that answered the last fetch, shared between clones
+    mirror_used: Arc<Mutex<Option<String>>>,
+    /// Where the last fetch was redirected to, shared between clones
+    final_url: Arc<Mutex<Option<String>>>,
+    max_redirects: usize,
+    client: FeedClient,
+    limits: TextLimits,
+    /// Response body bytes received, shared between clones
+    received: Arc<AtomicU64>,
+    /// Shared between clones, like `received`
+    last_fetch: Arc<Mutex<Option<LastFetch>>>,
+    /// Hash of the last body parsed; an identical body is not parsed again
+    body_hash: Arc<Mutex<Option<String>>>,
+    /// Whether the last fetch returned the body hashed in `body_hash`
+    not_modified: Arc<AtomicBool>,
+    /// Entries of the last fetch left out for lacking a title or link,
+    /// shared between clones
+    skipped: Arc<AtomicUsize>,
+    /// Records responses to, or replays them from, a file instead of the network
+    cassette: Option<Arc<Cassette>>,
+    dedup: Dedup,
+    /// Minimum time between fetches, overriding what the feed declares
+    poll_interval: Option<Duration>,
+    collapse_releases: bool,
+}
+
+/// A feed's HTTP response, from the network or a cassette
+#[derive(Debug)]
+struct FeedResponse {
+    status: u16,
+    body: Vec<u8>,
+    /// `Cache-Control` max-age
+    max_age: Option<Duration>,
+    cache: Option<CacheStatus>,
+    /// The address that answered, when the request was redirected
+    redirected_to: Option<String>,
+}
+
+/// When a feed was last fetched and the schedule it declared then
+#[derive(Debug)]
+struct LastFetch {
+    at: DateTime<Utc>,
+    schedule: FeedSchedule,
 }
 
 #[async_trait]
 impl Source for RssSource {
     async fn fetch(&self) -> Result<Vec<Item>, ClioError> {
-        let response = self
-            .client
-            .get(&self.url)
-            .send()
-            .await
-            .clio_network_err(format!("Failed to pull feed from {}", self.url))?;
-
-        if !response.status().is_success() {
-            return Err(ClioError::Network(format!(
-                "HTTP {} from {}",
-                response.status(),
-                self.url
-            )));
-        }
+        *self.mirror_used.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        *self.final_url.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        self.not_modified.store(false, Ordering::Relaxed);
+        self.skipped.store(0, Ordering::Relaxed);
+        let primary = match self.fetch_from(&self.url).await {
+            Err(e) if !self.mirrors.is_empty() => e,
+            result => return result,
+        };
 
-        let content = response
-            .bytes()
-            .await
-            .clio_network_err("Failed to read response body")?;
-
-        // Try parsing as RSS first
-        if let Ok(items) = self.parse_rss(&content) {
-            return Ok(items);
-        }
-
-        // Try parsing as Atom
-        if let Ok(content_str) = std::str::from_utf8(&content) {
-            if let Ok(items) = self.parse_atom(content_str) {
-                return Ok(items);
+        let mut errors = vec![primary.to_string()];
+        for mirror in &self.mirrors {
+            match self.fetch_from(mirror).await {
+                Ok(items) => {
+                    *self.mirror_used.lock().unwrap_or_else(|e| e.into_inner()) =
+                        Some(mirror.clone());
+                    return Ok(items);
+                }
+                Err(e) => errors.push(e.to_string()),
             }
         }
-
-        Err(ClioError::Parse(format!(
-            "Failed to parse feed from {} as RSS or Atom",
-            self.url
+        Err(ClioError::Network(format!(
+            "Every address of '{}' failed: {}",
+            self.name,
+            errors.join("; ")
         )))
     }
 
@@ -65,55 +122,461 @@ impl Source for RssSource {
     fn url(&self) -> &str {
         &self.url
     }
+
+    fn bytes_received(&self) -> u64 {
+        self.received.load(Ordering::Relaxed)
+    }
+
+    fn mirror_used(&self) -> Option<String> {
+        self.mirror_used
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn final_url(&self) -> Option<String> {
+        self.final_url
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn body_hash(&self) -> Option<String> {
+        self.body_hash
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn not_modified(&self) -> bool {
+        self.not_modified.load(Ordering::Relaxed)
+    }
+
+    fn skip_unchanged(&self, body_hash: String) {
+        *self.body_hash.lock().unwrap_or_else(|e| e.into_inner()) = Some(body_hash);
+    }
+
+    fn is_due(&self, now: DateTime<Utc>) -> bool {
+        match &*self.last_fetch.lock().unwrap_or_else(|e| e.into_inner()) {
+            Some(last) => last.schedule.is_due(last.at, now),
+            None => true,
+        }
+    }
 }
 
 impl RssSource {
     /// Create a new RSS/Atom feed source
     pub fn new(name: String, url: String) -> Self {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(10))
-            .user_agent("Clio/0.1.0")
-            .build()
-            .unwrap_or_default();
+        Self {
+            name,
+            url,
+            mirrors: Vec::new(),
+            mirror_used: Arc::new(Mutex::new(None)),
+            final_url: Arc::new(Mutex::new(None)),
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            client: FeedClient::default(),
+            limits: TextLimits::default(),
+            received: Arc::new(AtomicU64::new(0)),
+            last_fetch: Arc::new(Mutex::new(None)),
+            body_hash: Arc::new(Mutex::new(None)),
+            not_modified: Arc::new(AtomicBool::new(false)),
+            skipped: Arc::new(AtomicUsize::new(0)),
+            cassette: None,
+            dedup: Dedup::Link,
+            poll_interval: None,
+            collapse_releases: false,
+        }
+    }
+
+    /// Fall back to these addresses, in order, when the feed's URL fails
+    pub fn with_mirrors(mut self, mirrors: Vec<String>) -> Self {
+        self.mirrors = mirrors;
+        self
+    }
+
+    /// Restrict requests from this source with a network guard
+    pub fn with_guard(self, guard: RequestGuard) -> Self {
+        self.with_client(FeedClient::new(guard))
+    }
+
+    /// Send requests through a client shared with other sources
+    pub fn with_client(mut self, client: FeedClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Follow at most `max` redirects when fetching the feed
+    pub fn with_max_redirects(mut self, max: usize) -> Self {
+        self.max_redirects = max;
+        self
+    }
+
+    /// Choose how repeated items within a fetch are recognized
+    pub fn with_dedup(mut self, dedup: Dedup) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
+    /// Poll no more often than every `interval`, whatever the feed declares
+    ///
+    /// A zero interval makes the source due on every poll.
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = Some(interval);
+        self
+    }
+
+    /// Fold runs of patch releases into one item (see [`collapse_patch_releases`])
+    pub fn with_collapsed_releases(mut self, collapse: bool) -> Self {
+        self.collapse_releases = collapse;
+        self
+    }
+
+    /// Record responses to, or replay them from, a cassette
+    pub fn with_cassette(mut self, cassette: Arc<Cassette>) -> Self {
+        self.cassette = Some(cassette);
+        self
+    }
+
+    /// Use custom maximum lengths for stored text fields
+    pub fn with_limits(mut self, limits: TextLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// How many entries the last fetch left out for lacking a title or link
+    pub fn skipped(&self) -> usize {
+        self.skipped.load(Ordering::Relaxed)
+    }
+
+    /// Fetch and parse the feed at one of its addresses
+    async fn fetch_from(&self, url: &str) -> Result<Vec<Item>, ClioError> {
+        let span = self.client.trace().map(|trace| trace.start(url));
+        let response = self.respond(url).await;
+        if let Some(span) = span {
+            match &response {
+                Ok(response) => {
+                    span.finish(response.status, response.body.len() as u64, response.cache)
+                }
+                Err(e) => span.fail(e.to_string()),
+            }
+        }
+        let FeedResponse {
+            status,
+            body: content,
+            max_age,
+            redirected_to,
+            ..
+        } = response?;
+
+        if !StatusCode::from_u16(status).is_ok_and(|status| status.is_success()) {
+            return Err(ClioError::request(
+                ErrorKind::Http(status),
+                format!("HTTP {status} from {url}"),
+            ));
+        }
+        *self.final_url.lock().unwrap_or_else(|e| e.into_inner()) = redirected_to;
+        self.received
+            .fetch_add(content.len() as u64, Ordering::Relaxed);
+
+        // Hosts without validators answer 200 with the same body every time
+        let hash = body_hash(&content);
+        let mut last_hash = self.body_hash.lock().unwrap_or_else(|e| e.into_inner());
+        if last_hash.as_ref() == Some(&hash) {
+            self.not_modified.store(true, Ordering::Relaxed);
+            return Ok(Vec::new());
+        }
+
+        // Try parsing as RSS first
+        if let Ok(channel) = Channel::read_from(&content[..]) {
+            self.record_fetch(Self::schedule(&channel), max_age);
+            *last_hash = Some(hash);
+            return Ok(self.finish(self.channel_items(&channel, &content)));
+        }
+
+        // Try parsing as Atom
+        if let Ok(content_str) = std::str::from_utf8(&content)
+            && let Ok(items) = self.parse_atom(content_str)
+        {
+            self.record_fetch(FeedSchedule::default(), max_age);
+            *last_hash = Some(hash);
+            return Ok(self.finish(items));
+        }
+
+        Err(ClioError::Parse(format!(
+            "Failed to parse feed from {url} as RSS or Atom"
+        )))
+    }
+
+    /// Apply the per-feed passes over a fetch's parsed items
+    fn finish(&self, items: Vec<Item>) -> Vec<Item> {
+        let items = self.dedup(items);
+        if self.collapse_releases {
+            collapse_patch_releases(items, &self.limits)
+        } else {
+            items
+        }
+    }
+
+    /// Drop items repeated within one fetch, keeping the first copy
+    ///
+    /// Feeds deduplicated by fingerprint can list one story under several
+    /// rewritten links; feeds deduplicated by link are passed through.
+    fn dedup(&self, items: Vec<Item>) -> Vec<Item> {
+        match self.dedup {
+            Dedup::Link => items,
+            Dedup::Fingerprint => {
+                let mut seen = HashSet::new();
+                items
+                    .into_iter()
+                    .filter(|item| seen.insert(item.fingerprint()))
+                    .collect()
+            }
+        }
+    }
+
+    /// The feed's response, replayed from the cassette or requested over the network
+    async fn respond(&self, url: &str) -> Result<FeedResponse, ClioError> {
+        match self.cassette.as_deref() {
+            Some(cassette) if cassette.mode() == CassetteMode::Replay => {
+                let (status, body) = cassette.play(url)?;
+                Ok(FeedResponse {
+                    status,
+                    body,
+                    max_age: None,
+                    cache: Some(CacheStatus::Replay),
+                    redirected_to: None,
+                })
+            }
+            cassette => {
+                let response = self.get(url).await?;
+                if let Some(cassette) = cassette {
+                    cassette.save(url, response.status, &response.body)?;
+                }
+                Ok(response)
+            }
+        }
+    }
+
+    /// Request the feed over the network
+    async fn get(&self, address: &str) -> Result<FeedResponse, ClioError> {
+        let url = Url::parse(address).clio_network_err(format!("Invalid URL {address}"))?;
+        if let Some(guard) = self.client.guard() {
+            guard.check_url(&url)?;
+        }
+
+        let _slot = self.client.slot(&url).await;
+        self.client.limit_redirects(&url, self.max_redirects);
+        let response = self
+            .client
+            .get(address)
+            .send()
+            .await
+            .map_err(|e| http::request_error(address, e))?;
+        let status = response.status().as_u16();
+        let redirected_to = (*response.url() != url).then(|| response.url().to_string());
+        let max_age = response
+            .headers()
+            .get(CACHE_CONTROL)
+            .and_then(|value| value.to_str().ok())
+            .and_then(Self::max_age);
+        let cache = CacheStatus::from_headers(response.headers());
+        let body = response
+            .bytes()
+            .await
+            .clio_network_err("Failed to read response body")?;
+        Ok(FeedResponse {
+            status,
+            body: body.to_vec(),
+            max_age,
+            cache,
+            redirected_to,
+        })
+    }
+
+    /// How long a response may be cached, from a `Cache-Control` header value
+    ///
+    /// Responses marked `no-cache` or `no-store` give no hint.
+    fn max_age(cache_control: &str) -> Option<Duration> {
+        let mut max_age = None;
+        for directive in cache_control.split(',') {
+            let directive = directive.trim().to_ascii_lowercase();
+            if directive == "no-cache" || directive == "no-store" {
+                return None;
+            }
+            if let Some(seconds) = directive.strip_prefix("max-age=") {
+                max_age = seconds
+                    .trim_matches('"')
+                    .parse()
+                    .ok()
+                    .map(Duration::from_secs);
+            }
+        }
+        max_age.filter(|max_age| !max_age.is_zero())
+    }
 
-        Self { name, url, client }
+    /// Remember this fetch, with the longer of the feed's declared update
+    /// interval and the response's max-age, unless an interval is configured
+    fn record_fetch(&self, mut schedule: FeedSchedule, max_age: Option<Duration>) {
+        schedule.update_interval = match self.poll_interval {
+            Some(interval) => Some(interval),
+            None => schedule.update_interval.max(max_age),
+        };
+        let last = LastFetch {
+            at: Utc::now(),
+            schedule,
+        };
+        *self.last_fetch.lock().unwrap_or_else(|e| e.into_inner()) = Some(last);
+    }
+
+    /// Read the update schedule an RSS channel declares
+    ///
+    /// `skipHours` are GMT hours and `skipDays` English day names; values that
+    /// fail to parse are ignored. When both `ttl` (minutes) and `sy:updatePeriod`
+    /// are given, the longer interval wins.
+    fn schedule(channel: &Channel) -> FeedSchedule {
+        let ttl = channel
+            .ttl()
+            .and_then(|ttl| ttl.trim().parse::<u64>().ok())
+            .filter(|&minutes| minutes > 0)
+            .map(|minutes| Duration::from_secs(minutes * 60));
+        let syndication = channel.syndication_ext().and_then(|sy| {
+            let period = match sy.period() {
+                UpdatePeriod::Hourly => 60 * 60,
+                UpdatePeriod::Daily => 24 * 60 * 60,
+                UpdatePeriod::Weekly => 7 * 24 * 60 * 60,
+                UpdatePeriod::Monthly => 30 * 24 * 60 * 60,
+                UpdatePeriod::Yearly => 365 * 24 * 60 * 60,
+            };
+            let frequency = u64::from(sy.frequency());
+            (frequency > 0).then(|| Duration::from_secs(period / frequency))
+        });
+        let update_interval = ttl.max(syndication);
+
+        FeedSchedule {
+            skip_hours: channel
+                .skip_hours()
+                .iter()
+                .filter_map(|hour| hour.trim().parse().ok())
+                .filter(|&hour| hour < 24)
+                .collect(),
+            skip_days: channel
+                .skip_days()
+                .iter()
+                .filter_map(|day| day.trim().parse::<Weekday>().ok())
+                .collect(),
+            update_interval,
+        }
     }
 
     /// Parse RSS feed content
+    #[cfg(test)]
     fn parse_rss(&self, content: &[u8]) -> Result<Vec<Item>, ClioError> {
         let channel = Channel::read_from(content)?;
+        Ok(self.channel_items(&channel, content))
+    }
+
+    /// Build items from a parsed RSS channel and the raw document it came from
+    fn channel_items(&self, channel: &Channel, content: &[u8]) -> Vec<Item> {
         let mut items = Vec::new();
 
-        for rss_item in channel.items() {
+        // Only trust expiry dates when every item was accounted for
+        let expirations = Some(item_expirations(content))
+            .filter(|expirations| expirations.len() == channel.items().len())
+            .unwrap_or_default();
+
+        for (index, rss_item) in channel.items().iter().enumerate() {
             // Skip items without title or link
             let title = match rss_item.title() {
                 Some(t) if !t.trim().is_empty() => decode_html_entities(t).to_string(),
-                _ => continue,
+                _ => {
+                    self.skipped.fetch_add(1, Ordering::Relaxed);
+                    continue;
+                }
             };
 
             let link = match rss_item.link() {
-                Some(l) if !l.trim().is_empty() => l.to_string(),
-                _ => continue,
+                Some(l) if !l.trim().is_empty() => strip_control(l),
+                _ => {
+                    self.skipped.fetch_add(1, Ordering::Relaxed);
+                    continue;
+                }
             };
 
             let summary = rss_item
                 .description()
-                .map(|d| decode_html_entities(d).to_string())
-                .map(|s| Self::normalize_whitespace(&s));
+                .map(|d| self.limits.summary(&decode_html_entities(d)));
+
+            let description = rss_item.description().unwrap_or_default();
+            let score = Self::count(&POINTS, description);
+            let comments = rss_item
+                .extensions()
+                .get("slash")
+                .and_then(|slash| slash.get("comments"))
+                .and_then(|values| values.first())
+                .and_then(|comments| comments.value())
+                .and_then(|value| value.trim().parse().ok())
+                .or_else(|| Self::count(&COMMENT_COUNT, description));
 
             let pub_date = rss_item.pub_date().and_then(|d| Self::parse_date(d).ok());
 
+            let creators = rss_item
+                .dublin_core_ext()
+                .map(|dc| dc.creators())
+                .unwrap_or_default();
+            let authors = Self::author_names(
+                rss_item
+                    .author()
+                    .into_iter()
+                    .chain(creators.iter().map(String::as_str)),
+            );
+
+            let expires_at = expirations
+                .get(index)
+                .and_then(Option::as_deref)
+                .and_then(|d| Self::parse_date(d).ok());
+
+            let duration = rss_item
+                .itunes_ext()
+                .and_then(|ext| ext.duration())
+                .and_then(Self::parse_duration);
+            let enclosure = rss_item
+                .enclosure()
+                .and_then(|e| Self::enclosure(e.url(), Some(e.mime_type()), Some(e.length())))
+                .map(|enclosure| Enclosure {
+                    duration,
+                    ..enclosure
+                });
+
+            let thumbnail = rss_item
+                .extensions()
+                .get("media")
+                .and_then(|media| Self::media_thumbnail(media, |e| &e.attrs, |e| &e.children))
+                .or_else(|| enclosure.as_ref().and_then(Self::image_enclosure))
+                .or_else(|| {
+                    rss_item
+                        .itunes_ext()
+                        .and_then(|ext| ext.image())
+                        .and_then(Self::image_url)
+                });
+
             items.push(Item {
                 id: Uuid::new_v4().to_string(),
                 source_name: self.name.clone(),
-                title: Self::normalize_whitespace(&title),
+                title: self.limits.title(&title),
                 link,
                 summary,
+                authors,
                 pub_date,
+                expires_at,
+                enclosure,
+                thumbnail,
+                score,
+                comments,
+                ..Default::default()
             });
         }
 
-        Ok(items)
+        items
     }
 
     /// Parse Atom feed content
@@ -125,6 +588,7 @@ impl RssSource {
             // Skip entries without title
             let title = entry.title().value.trim();
             if title.is_empty() {
+                self.skipped.fetch_add(1, Ordering::Relaxed);
                 continue;
             }
 
@@ -137,8 +601,11 @@ impl RssSource {
                 .map(|l| l.href().to_string());
 
             let link = match link {
-                Some(l) if !l.trim().is_empty() => l,
-                _ => continue,
+                Some(l) if !l.trim().is_empty() => strip_control(&l),
+                _ => {
+                    self.skipped.fetch_add(1, Ordering::Relaxed);
+                    continue;
+                }
             };
 
             let summary = entry
@@ -150,7 +617,27 @@ impl RssSource {
                         .and_then(|c| c.value())
                         .map(|v| decode_html_entities(v).to_string())
                 })
-                .map(|s| Self::normalize_whitespace(&s));
+                .map(|s| self.limits.summary(&s));
+
+            let enclosure = entry
+                .links()
+                .iter()
+                .find(|l| l.rel() == "enclosure")
+                .and_then(|l| Self::enclosure(l.href(), l.mime_type(), l.length()));
+
+            let thumbnail = entry
+                .extensions()
+                .get("media")
+                .and_then(|media| Self::media_thumbnail(media, |e| &e.attrs, |e| &e.children))
+                .or_else(|| enclosure.as_ref().and_then(Self::image_enclosure));
+
+            // Entries without authors inherit the feed's
+            let people = if entry.authors().is_empty() {
+                feed.authors()
+            } else {
+                entry.authors()
+            };
+            let authors = Self::author_names(people.iter().map(|person| person.name()));
 
             let pub_date = entry
                 .published()
@@ -160,16 +647,122 @@ impl RssSource {
             items.push(Item {
                 id: Uuid::new_v4().to_string(),
                 source_name: self.name.clone(),
-                title: Self::normalize_whitespace(decode_html_entities(title).as_ref()),
+                title: self.limits.title(&decode_html_entities(title)),
                 link,
                 summary,
+                authors,
                 pub_date,
+                expires_at: None,
+                enclosure,
+                thumbnail,
+                ..Default::default()
             });
         }
 
         Ok(items)
     }
 
+    /// Distinct author names, taking the name from RSS's `email (Name)` form
+    fn author_names<'a>(authors: impl Iterator<Item = &'a str>) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for author in authors {
+            let author = decode_html_entities(author.trim());
+            let name = match author.split_once('(') {
+                Some((email, name)) if email.contains('@') => {
+                    name.trim_end().trim_end_matches(')').trim()
+                }
+                _ => author.as_ref(),
+            };
+            let name = strip_control(name);
+            if !name.is_empty() && !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// Build an enclosure, ignoring entries without a usable URL
+    fn enclosure(url: &str, mime_type: Option<&str>, length: Option<&str>) -> Option<Enclosure> {
+        let url = strip_control(url.trim());
+        if url.is_empty() {
+            return None;
+        }
+
+        Some(Enclosure {
+            url,
+            mime_type: mime_type
+                .map(|m| strip_control(m.trim()))
+                .filter(|m| !m.is_empty()),
+            // Feeds often declare a placeholder length of 0
+            length: length
+                .and_then(|l| l.trim().parse().ok())
+                .filter(|&l| l > 0),
+            duration: None,
+        })
+    }
+
+    /// Find a preview image among Media RSS elements (`media:thumbnail`, or
+    /// `media:content` that is an image), looking inside `media:group` too
+    ///
+    /// RSS and Atom extensions are distinct types with the same shape, so the
+    /// accessors are passed in.
+    fn media_thumbnail<E>(
+        media: &BTreeMap<String, Vec<E>>,
+        attrs: impl Fn(&E) -> &BTreeMap<String, String> + Copy,
+        children: impl Fn(&E) -> &BTreeMap<String, Vec<E>> + Copy,
+    ) -> Option<String> {
+        let groups = media.get("group").into_iter().flatten().map(children);
+        std::iter::once(media).chain(groups).find_map(|elements| {
+            let thumbnails = elements.get("thumbnail").into_iter().flatten().map(attrs);
+            let images = elements
+                .get("content")
+                .into_iter()
+                .flatten()
+                .map(attrs)
+                .filter(|attrs| {
+                    attrs.get("medium").is_some_and(|m| m == "image")
+                        || attrs.get("type").is_some_and(|t| t.starts_with("image/"))
+                });
+            thumbnails
+                .chain(images)
+                .find_map(|attrs| attrs.get("url").and_then(|url| Self::image_url(url)))
+        })
+    }
+
+    /// Use an enclosure as the thumbnail when it is itself an image
+    fn image_enclosure(enclosure: &Enclosure) -> Option<String> {
+        enclosure
+            .mime_type
+            .as_deref()
+            .is_some_and(|m| m.starts_with("image/"))
+            .then(|| enclosure.url.clone())
+    }
+
+    fn image_url(url: &str) -> Option<String> {
+        Some(strip_control(url.trim())).filter(|url| !url.is_empty())
+    }
+
+    /// Read a count captured by `pattern`, such as the points in "Points: 42"
+    fn count(pattern: &Regex, text: &str) -> Option<u32> {
+        pattern.captures(text)?[1].parse().ok()
+    }
+
+    /// Parse an `itunes:duration` value: seconds, `MM:SS`, or `HH:MM:SS`
+    fn parse_duration(value: &str) -> Option<Duration> {
+        let parts: Vec<&str> = value.trim().split(':').collect();
+        if parts.len() > 3 {
+            return None;
+        }
+
+        let mut seconds = 0u64;
+        for part in parts {
+            // Some feeds add fractional seconds
+            let whole = part.split('.').next()?;
+            seconds = seconds * 60 + whole.parse::<u64>().ok()?;
+        }
+        (seconds > 0).then(|| Duration::from_secs(seconds))
+    }
+
     /// Parse various date formats commonly used in feeds
     fn parse_date(date_str: &str) -> Result<DateTime<Utc>, ClioError> {
         // Try RFC 2822 format (common in RSS)
@@ -205,16 +798,69 @@ impl RssSource {
             "Unable to parse date: {date_str}"
         )))
     }
+}
+
+/// Collect each RSS item's `<expirationDate>`, in document order
+///
+/// The rss crate drops elements outside the RSS 2.0 spec, so this scans the
+/// raw XML for the RSS 0.93 expiry hint still used by deal and event feeds.
+fn item_expirations(content: &[u8]) -> Vec<Option<String>> {
+    let mut reader = Reader::from_reader(content);
+    let mut buf = Vec::new();
+    let mut expirations = Vec::new();
+    let mut current = None;
+    let mut in_item = false;
+    let mut in_expiry = false;
 
-    /// Normalize whitespace in text
-    fn normalize_whitespace(text: &str) -> String {
-        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => match e.local_name().as_ref() {
+                b"item" => {
+                    in_item = true;
+                    current = None;
+                }
+                b"expirationDate" if in_item => in_expiry = true,
+                _ => {}
+            },
+            Ok(Event::Empty(e)) if e.local_name().as_ref() == b"item" => expirations.push(None),
+            Ok(Event::Text(text)) if in_expiry => {
+                current = text.unescape().ok().map(|t| t.trim().to_string());
+            }
+            Ok(Event::End(e)) => match e.local_name().as_ref() {
+                b"item" => {
+                    expirations.push(current.take());
+                    in_item = false;
+                }
+                b"expirationDate" => in_expiry = false,
+                _ => {}
+            },
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
     }
+
+    expirations
+}
+
+/// A stable 64-bit FNV-1a hash of a response body, in hex
+///
+/// It only has to tell one body of a feed from the next, and unlike the
+/// standard library's hasher it stays the same across Rust releases, so it
+/// can be stored between runs.
+fn body_hash(body: &[u8]) -> String {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    let hash = body.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    });
+    format!("{hash:016x}")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sanitize::clean_text;
     use chrono::Datelike;
 
     fn create_test_source(url: &str) -> RssSource {
@@ -223,15 +869,9 @@ mod tests {
 
     #[test]
     fn test_normalize_whitespace() {
-        assert_eq!(
-            RssSource::normalize_whitespace("  hello   world  "),
-            "hello world"
-        );
-        assert_eq!(
-            RssSource::normalize_whitespace("line\nbreak\ttab"),
-            "line break tab"
-        );
-        assert_eq!(RssSource::normalize_whitespace("single"), "single");
+        assert_eq!(clean_text("  hello   world  "), "hello world");
+        assert_eq!(clean_text("line\nbreak\ttab"), "line break tab");
+        assert_eq!(clean_text("single"), "single");
     }
 
     #[test]
@@ -302,6 +942,7 @@ mod tests {
         assert_eq!(items[0].link, "https://example.com/article1");
         assert_eq!(items[0].summary, Some("Article description".to_string()));
         assert!(items[0].pub_date.is_some());
+        assert_eq!(source.bytes_received(), rss_content.len() as u64);
     }
 
     #[tokio::test]
@@ -403,6 +1044,132 @@ mod tests {
         let items = result.unwrap();
         assert_eq!(items.len(), 1);
         assert_eq!(items[0].title, "Valid Article");
+        assert_eq!(source.skipped(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_pull_records_and_replays_cassette() {
+        let rss_content = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0">
+  <channel>
+    <title>Test Feed</title>
+    <item>
+      <title>Recorded Article</title>
+      <link>https://example.com/recorded</link>
+    </item>
+  </channel>
+</rss>"#;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("cassette.json");
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/feed.xml")
+            .with_status(200)
+            .with_body(rss_content)
+            .expect(1)
+            .create();
+        let url = format!("{}/feed.xml", server.url());
+
+        let recorder =
+            create_test_source(&url).with_cassette(Arc::new(Cassette::record(path.clone())));
+        let recorded = recorder.fetch().await.unwrap();
+        mock.assert();
+
+        // Replay never touches the server, which would now answer 503
+        let _down = server.mock("GET", "/feed.xml").with_status(503).create();
+        let player =
+            create_test_source(&url).with_cassette(Arc::new(Cassette::replay(path).unwrap()));
+        let replayed = player.fetch().await.unwrap();
+        assert_eq!(replayed.len(), 1);
+        assert_eq!(replayed[0].title, recorded[0].title);
+        assert_eq!(replayed[0].link, "https://example.com/recorded");
+        assert_eq!(player.bytes_received(), rss_content.len() as u64);
+    }
+
+    #[tokio::test]
+    async fn test_pull_dedup_by_fingerprint() {
+        let rss_content = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0">
+  <channel>
+    <title>Test Feed</title>
+    <item>
+      <title>Weekly update</title>
+      <link>https://example.com/update?rev=8812</link>
+      <pubDate>Wed, 01 May 2024 12:00:00 +0000</pubDate>
+    </item>
+    <item>
+      <title>Weekly Update</title>
+      <link>https://example.com/update?rev=8813</link>
+      <pubDate>Wed, 01 May 2024 12:00:00 +0000</pubDate>
+    </item>
+  </channel>
+</rss>"#;
+
+        let mut server = mockito::Server::new_async().await;
+        let _m = server
+            .mock("GET", "/feed.xml")
+            .with_status(200)
+            .with_body(rss_content)
+            .create();
+        let url = format!("{}/feed.xml", server.url());
+
+        let items = create_test_source(&url).fetch().await.unwrap();
+        assert_eq!(items.len(), 2);
+
+        let items = create_test_source(&url)
+            .with_dedup(Dedup::Fingerprint)
+            .fetch()
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].link, "https://example.com/update?rev=8812");
+    }
+
+    #[tokio::test]
+    async fn test_pull_collapses_releases() {
+        let atom_content = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Release notes from tokio</title>
+  <id>tag:github.com,2008:https://github.com/tokio-rs/tokio/releases</id>
+  <updated>2024-07-05T00:00:00Z</updated>
+  <entry>
+    <id>tag:github.com,2008:Repository/1/tokio-1.38.1</id>
+    <title>Tokio v1.38.1</title>
+    <updated>2024-07-05T00:00:00Z</updated>
+    <link rel="alternate" href="https://github.com/tokio-rs/tokio/releases/tag/tokio-1.38.1"/>
+    <content type="html">Fixes a timer regression</content>
+  </entry>
+  <entry>
+    <id>tag:github.com,2008:Repository/1/tokio-1.38.0</id>
+    <title>Tokio v1.38.0</title>
+    <updated>2024-05-30T00:00:00Z</updated>
+    <link rel="alternate" href="https://github.com/tokio-rs/tokio/releases/tag/tokio-1.38.0"/>
+  </entry>
+</feed>"#;
+
+        let mut server = mockito::Server::new_async().await;
+        let _m = server
+            .mock("GET", "/releases.atom")
+            .with_status(200)
+            .with_body(atom_content)
+            .create();
+        let url = format!("{}/releases.atom", server.url());
+
+        let items = create_test_source(&url).fetch().await.unwrap();
+        assert_eq!(items.len(), 2);
+
+        let items = create_test_source(&url)
+            .with_collapsed_releases(true)
+            .fetch()
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].title, "Tokio v1.38.0–1.38.1");
+        assert_eq!(
+            items[0].link,
+            "https://github.com/tokio-rs/tokio/releases/tag/tokio-1.38.1"
+        );
     }
 
     #[tokio::test]
@@ -413,7 +1180,105 @@ mod tests {
         let result = source.fetch().await;
 
         assert!(result.is_err());
-        assert!(matches!(result.unwrap_err(), ClioError::Network(_)));
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::Http(404));
+    }
+
+    #[tokio::test]
+    async fn test_pull_falls_back_to_mirrors() {
+        let rss_content = r#"<?xml version="1.0"?>
+<rss version="2.0"><channel><title>Mirror</title>
+<item><title>Mirrored</title><link>https://example.com/m</link></item>
+</channel></rss>"#;
+        let mut server = mockito::Server::new_async().await;
+        let _primary = server.mock("GET", "/primary.xml").with_status(503).create();
+        let _broken = server
+            .mock("GET", "/broken.xml")
+            .with_body("not a feed")
+            .create();
+        let _mirror = server
+            .mock("GET", "/mirror.xml")
+            .with_body(rss_content)
+            .create();
+        let mirror = format!("{}/mirror.xml", server.url());
+        let source = create_test_source(&format!("{}/primary.xml", server.url()))
+            .with_mirrors(vec![format!("{}/broken.xml", server.url()), mirror.clone()]);
+
+        let items = source.fetch().await.unwrap();
+        assert_eq!(items[0].title, "Mirrored");
+        assert_eq!(source.mirror_used(), Some(mirror));
+
+        let source = create_test_source(&format!("{}/primary.xml", server.url()))
+            .with_mirrors(vec![format!("{}/broken.xml", server.url())]);
+        let err = source.fetch().await.unwrap_err().to_string();
+        assert!(err.contains("Every address of 'Test Source' failed"));
+        assert!(err.contains("HTTP 503"));
+        assert!(err.contains("as RSS or Atom"));
+        assert_eq!(source.mirror_used(), None);
+    }
+
+    #[tokio::test]
+    async fn test_identical_body_is_not_modified() {
+        let rss_content = r#"<?xml version="1.0"?>
+<rss version="2.0"><channel><title>Static</title>
+<item><title>Same as ever</title><link>https://example.com/same</link></item>
+</channel></rss>"#;
+        let mut server = mockito::Server::new_async().await;
+        let _m = server
+            .mock("GET", "/static.xml")
+            .with_body(rss_content)
+            .expect(3)
+            .create();
+        let source = create_test_source(&format!("{}/static.xml", server.url()));
+
+        assert_eq!(source.fetch().await.unwrap().len(), 1);
+        assert!(!source.not_modified());
+        let hash = source.body_hash().unwrap();
+        assert_eq!(hash, body_hash(rss_content.as_bytes()));
+
+        assert!(source.fetch().await.unwrap().is_empty());
+        assert!(source.not_modified());
+        assert_eq!(source.bytes_received(), 2 * rss_content.len() as u64);
+
+        // A hash from an earlier run is skipped the same way
+        let source = create_test_source(&format!("{}/static.xml", server.url()));
+        source.skip_unchanged(hash);
+        assert!(source.fetch().await.unwrap().is_empty());
+        assert!(source.not_modified());
+    }
+
+    #[tokio::test]
+    async fn test_redirected_fetch_records_final_url() {
+        let rss_content = r#"<?xml version="1.0"?>
+<rss version="2.0"><channel><title>Moved</title>
+<item><title>Still here</title><link>https://example.com/here</link></item>
+</channel></rss>"#;
+        let mut server = mockito::Server::new_async().await;
+        let _old = server
+            .mock("GET", "/old.xml")
+            .with_status(301)
+            .with_header("location", "/new.xml")
+            .create();
+        let _new = server
+            .mock("GET", "/new.xml")
+            .with_body(rss_content)
+            .create();
+
+        let source = create_test_source(&format!("{}/old.xml", server.url()));
+        assert_eq!(source.fetch().await.unwrap().len(), 1);
+        assert_eq!(
+            source.final_url(),
+            Some(format!("{}/new.xml", server.url()))
+        );
+
+        let direct = create_test_source(&format!("{}/new.xml", server.url()));
+        direct.fetch().await.unwrap();
+        assert_eq!(direct.final_url(), None);
+
+        let limited =
+            create_test_source(&format!("{}/old.xml", server.url())).with_max_redirects(0);
+        let err = limited.fetch().await.unwrap_err();
+        assert!(err.to_string().contains("more than 0 redirects"));
+        assert_eq!(limited.final_url(), None);
     }
 
     #[tokio::test]
@@ -456,4 +1321,451 @@ mod tests {
         let items = result.unwrap();
         assert_eq!(items.len(), 0);
     }
+
+    #[tokio::test]
+    async fn test_pull_strips_escapes_and_truncates() {
+        let rss_content = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0">
+  <channel>
+    <title>Hostile Feed</title>
+    <item>
+      <title>&#27;[2J&#27;]0;pwned&#7;Totally normal title</title>
+      <link>https://example.com/article</link>
+      <description>&#27;[31mRed&#27;[0m summary that goes on and on</description>
+    </item>
+  </channel>
+</rss>"#;
+
+        let mut server = mockito::Server::new_async().await;
+        let _m = server
+            .mock("GET", "/hostile.xml")
+            .with_status(200)
+            .with_body(rss_content)
+            .create();
+        let source =
+            create_test_source(&format!("{}/hostile.xml", server.url())).with_limits(TextLimits {
+                max_title_length: 100,
+                max_summary_length: 12,
+            });
+        let items = source.fetch().await.unwrap();
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].title, "Totally normal title");
+        assert_eq!(items[0].summary, Some("Red summary…".to_string()));
+    }
+
+    #[test]
+    fn test_parse_authors() {
+        let source = create_test_source("https://example.com/feed");
+
+        let rss = br#"<?xml version="1.0"?>
+<rss version="2.0" xmlns:dc="http://purl.org/dc/elements/1.1/">
+<channel><title>Blog</title><link>https://example.com</link><description>d</description>
+  <item>
+    <title>Post</title><link>https://example.com/1</link>
+    <author>julia@example.com (Julia Evans)</author>
+    <dc:creator>Julia Evans</dc:creator>
+    <dc:creator>Kamal Marhubi</dc:creator>
+  </item>
+  <item><title>Anonymous</title><link>https://example.com/2</link></item>
+</channel></rss>"#;
+        let items = source.parse_rss(rss).unwrap();
+        assert_eq!(items[0].authors, ["Julia Evans", "Kamal Marhubi"]);
+        assert!(items[1].authors.is_empty());
+
+        let atom = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Papers</title><id>urn:papers</id><updated>2024-01-01T00:00:00Z</updated>
+  <author><name>Editors</name></author>
+  <entry>
+    <title>Paper</title><id>urn:1</id><updated>2024-01-01T00:00:00Z</updated>
+    <link href="https://example.com/paper"/>
+    <author><name>Ada Lovelace</name></author>
+    <author><name>Charles Babbage</name></author>
+  </entry>
+  <entry>
+    <title>Note</title><id>urn:2</id><updated>2024-01-01T00:00:00Z</updated>
+    <link href="https://example.com/note"/>
+  </entry>
+</feed>"#;
+        let items = source.parse_atom(atom).unwrap();
+        assert_eq!(items[0].authors, ["Ada Lovelace", "Charles Babbage"]);
+        assert_eq!(items[1].authors, ["Editors"]);
+    }
+
+    #[test]
+    fn test_parse_enclosures() {
+        let source = create_test_source("https://example.com/feed");
+
+        let rss = br#"<?xml version="1.0"?>
+<rss version="2.0"><channel><title>Pod</title><link>https://example.com</link><description>d</description>
+  <item>
+    <title>Episode 1</title>
+    <link>https://example.com/ep1</link>
+    <enclosure url="https://cdn.example.com/ep1.mp3" length="1000" type="audio/mpeg"/>
+  </item>
+  <item><title>Notes</title><link>https://example.com/notes</link></item>
+</channel></rss>"#;
+        let items = source.parse_rss(rss).unwrap();
+        assert_eq!(
+            items[0].enclosure,
+            Some(Enclosure {
+                url: "https://cdn.example.com/ep1.mp3".to_string(),
+                mime_type: Some("audio/mpeg".to_string()),
+                length: Some(1000),
+                duration: None,
+            })
+        );
+        assert_eq!(items[1].enclosure, None);
+
+        let atom = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Pod</title><id>urn:pod</id><updated>2024-01-01T00:00:00Z</updated>
+  <entry>
+    <title>Episode 2</title><id>urn:ep2</id><updated>2024-01-01T00:00:00Z</updated>
+    <link rel="alternate" href="https://example.com/ep2"/>
+    <link rel="enclosure" href="https://cdn.example.com/ep2.ogg" type="audio/ogg"/>
+  </entry>
+</feed>"#;
+        let items = source.parse_atom(atom).unwrap();
+        assert_eq!(items[0].link, "https://example.com/ep2");
+        let enclosure = items[0].enclosure.as_ref().unwrap();
+        assert_eq!(enclosure.url, "https://cdn.example.com/ep2.ogg");
+        assert_eq!(enclosure.mime_type.as_deref(), Some("audio/ogg"));
+    }
+
+    #[test]
+    fn test_parse_thumbnails() {
+        let source = create_test_source("https://example.com/feed");
+
+        let rss = br#"<?xml version="1.0"?>
+<rss version="2.0" xmlns:media="http://search.yahoo.com/mrss/" xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd">
+<channel><title>Pics</title><link>https://example.com</link><description>d</description>
+  <item>
+    <title>Thumbnail</title><link>https://example.com/1</link>
+    <media:content url="https://cdn.example.com/1.mp4" medium="video"/>
+    <media:thumbnail url="https://cdn.example.com/1.jpg"/>
+  </item>
+  <item>
+    <title>Grouped</title><link>https://example.com/2</link>
+    <media:group><media:content url="https://cdn.example.com/2.png" type="image/png"/></media:group>
+  </item>
+  <item>
+    <title>Enclosure</title><link>https://example.com/3</link>
+    <enclosure url="https://cdn.example.com/3.webp" length="0" type="image/webp"/>
+  </item>
+  <item>
+    <title>Episode</title><link>https://example.com/4</link>
+    <itunes:image href="https://cdn.example.com/4.jpg"/>
+    <enclosure url="https://cdn.example.com/4.mp3" length="0" type="audio/mpeg"/>
+  </item>
+  <item><title>Plain</title><link>https://example.com/5</link></item>
+</channel></rss>"#;
+        let thumbnails: Vec<Option<String>> = source
+            .parse_rss(rss)
+            .unwrap()
+            .into_iter()
+            .map(|item| item.thumbnail)
+            .collect();
+        assert_eq!(
+            thumbnails,
+            vec![
+                Some("https://cdn.example.com/1.jpg".to_string()),
+                Some("https://cdn.example.com/2.png".to_string()),
+                Some("https://cdn.example.com/3.webp".to_string()),
+                Some("https://cdn.example.com/4.jpg".to_string()),
+                None,
+            ]
+        );
+
+        let atom = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom" xmlns:media="http://search.yahoo.com/mrss/">
+  <title>Videos</title><id>urn:videos</id><updated>2024-01-01T00:00:00Z</updated>
+  <entry>
+    <title>Clip</title><id>urn:clip</id><updated>2024-01-01T00:00:00Z</updated>
+    <link rel="alternate" href="https://example.com/clip"/>
+    <media:group><media:thumbnail url="https://i.example.com/clip.jpg" width="480"/></media:group>
+  </entry>
+</feed>"#;
+        let items = source.parse_atom(atom).unwrap();
+        assert_eq!(
+            items[0].thumbnail.as_deref(),
+            Some("https://i.example.com/clip.jpg")
+        );
+    }
+
+    #[test]
+    fn test_parse_itunes_duration() {
+        let source = create_test_source("https://example.com/feed");
+        let rss = br#"<?xml version="1.0"?>
+<rss version="2.0" xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd">
+<channel><title>Pod</title><link>https://example.com</link><description>d</description>
+  <item>
+    <title>Episode 1</title>
+    <link>https://example.com/ep1</link>
+    <itunes:duration>42:10</itunes:duration>
+    <enclosure url="https://cdn.example.com/ep1.mp3" length="39845888" type="audio/mpeg"/>
+  </item>
+</channel></rss>"#;
+        let items = source.parse_rss(rss).unwrap();
+        let enclosure = items[0].enclosure.as_ref().unwrap();
+        assert_eq!(enclosure.duration, Some(Duration::from_secs(42 * 60 + 10)));
+        assert_eq!(enclosure.length, Some(39_845_888));
+    }
+
+    #[test]
+    fn test_parse_aggregator_counts() {
+        let source = create_test_source("https://example.com/feed");
+        let rss = br#"<?xml version="1.0"?>
+<rss version="2.0" xmlns:slash="http://purl.org/rss/1.0/modules/slash/">
+<channel><title>HN</title><link>https://news.ycombinator.com</link><description>d</description>
+  <item>
+    <title>Show HN: Clio</title>
+    <link>https://example.com/clio</link>
+    <description><![CDATA[<p>Article URL: https://example.com/clio</p>
+<p>Points: 128</p>
+<p># Comments: 42</p>]]></description>
+  </item>
+  <item>
+    <title>Slashdot story</title>
+    <link>https://example.com/story</link>
+    <slash:comments>17</slash:comments>
+  </item>
+  <item><title>Plain</title><link>https://example.com/plain</link></item>
+</channel></rss>"#;
+        let items = source.parse_rss(rss).unwrap();
+        assert_eq!((items[0].score, items[0].comments), (Some(128), Some(42)));
+        assert_eq!((items[1].score, items[1].comments), (None, Some(17)));
+        assert_eq!((items[2].score, items[2].comments), (None, None));
+    }
+
+    #[test]
+    fn test_parse_schedule() {
+        let rss = br#"<?xml version="1.0"?>
+<rss version="2.0" xmlns:sy="http://purl.org/rss/1.0/modules/syndication/">
+<channel><title>Office news</title><link>https://example.com</link><description>d</description>
+  <sy:updatePeriod>daily</sy:updatePeriod>
+  <sy:updateFrequency>2</sy:updateFrequency>
+  <skipHours><hour>0</hour><hour>23</hour><hour>24</hour><hour>noon</hour></skipHours>
+  <skipDays><day>Saturday</day><day>Sunday</day><day>Someday</day></skipDays>
+</channel></rss>"#;
+        let channel = Channel::read_from(&rss[..]).unwrap();
+        assert_eq!(
+            RssSource::schedule(&channel),
+            FeedSchedule {
+                skip_hours: vec![0, 23],
+                skip_days: vec![Weekday::Sat, Weekday::Sun],
+                update_interval: Some(Duration::from_secs(12 * 60 * 60)),
+            }
+        );
+
+        let plain = br#"<rss version="2.0"><channel><title>t</title><link>https://example.com</link><description>d</description></channel></rss>"#;
+        let channel = Channel::read_from(&plain[..]).unwrap();
+        assert_eq!(RssSource::schedule(&channel), FeedSchedule::default());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_records_schedule() {
+        let rss = r#"<?xml version="1.0"?>
+<rss version="2.0" xmlns:sy="http://purl.org/rss/1.0/modules/syndication/">
+<channel><title>Weekly</title><link>https://example.com</link><description>d</description>
+  <sy:updatePeriod>weekly</sy:updatePeriod>
+</channel></rss>"#;
+
+        let mut server = mockito::Server::new_async().await;
+        let _m = server
+            .mock("GET", "/feed.xml")
+            .with_status(200)
+            .with_body(rss)
+            .create();
+        let source = create_test_source(&format!("{}/feed.xml", server.url()));
+
+        assert!(source.is_due(Utc::now()));
+        source.fetch().await.unwrap();
+        assert!(!source.is_due(Utc::now()));
+        assert!(source.is_due(Utc::now() + chrono::Duration::days(8)));
+    }
+
+    #[test]
+    fn test_parse_ttl() {
+        let rss = br#"<?xml version="1.0"?>
+<rss version="2.0" xmlns:sy="http://purl.org/rss/1.0/modules/syndication/">
+<channel><title>t</title><link>https://example.com</link><description>d</description>
+  <ttl>60</ttl>
+</channel></rss>"#;
+        let channel = Channel::read_from(&rss[..]).unwrap();
+        assert_eq!(
+            RssSource::schedule(&channel).update_interval,
+            Some(Duration::from_secs(60 * 60))
+        );
+
+        // The longer of ttl and sy:updatePeriod wins
+        let both = br#"<?xml version="1.0"?>
+<rss version="2.0" xmlns:sy="http://purl.org/rss/1.0/modules/syndication/">
+<channel><title>t</title><link>https://example.com</link><description>d</description>
+  <ttl>30</ttl>
+  <sy:updatePeriod>hourly</sy:updatePeriod>
+  <sy:updateFrequency>4</sy:updateFrequency>
+</channel></rss>"#;
+        let channel = Channel::read_from(&both[..]).unwrap();
+        assert_eq!(
+            RssSource::schedule(&channel).update_interval,
+            Some(Duration::from_secs(30 * 60))
+        );
+
+        let invalid = br#"<rss version="2.0"><channel><title>t</title><link>https://example.com</link><description>d</description><ttl>soon</ttl></channel></rss>"#;
+        let channel = Channel::read_from(&invalid[..]).unwrap();
+        assert_eq!(RssSource::schedule(&channel).update_interval, None);
+    }
+
+    #[test]
+    fn test_max_age() {
+        assert_eq!(
+            RssSource::max_age("public, max-age=3600"),
+            Some(Duration::from_secs(3600))
+        );
+        assert_eq!(
+            RssSource::max_age("Max-Age=\"600\""),
+            Some(Duration::from_secs(600))
+        );
+        assert_eq!(RssSource::max_age("max-age=0"), None);
+        assert_eq!(RssSource::max_age("no-cache, max-age=3600"), None);
+        assert_eq!(RssSource::max_age("private"), None);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_uses_cache_control() {
+        let atom = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Atom</title><id>urn:uuid:feed</id><updated>2024-05-01T00:00:00Z</updated>
+</feed>"#;
+
+        let mut server = mockito::Server::new_async().await;
+        let _m = server
+            .mock("GET", "/atom.xml")
+            .with_status(200)
+            .with_header("cache-control", "public, max-age=7200")
+            .with_body(atom)
+            .create();
+        let source = create_test_source(&format!("{}/atom.xml", server.url()));
+
+        source.fetch().await.unwrap();
+        assert!(!source.is_due(Utc::now() + chrono::Duration::hours(1)));
+        assert!(source.is_due(Utc::now() + chrono::Duration::hours(3)));
+    }
+
+    #[tokio::test]
+    async fn test_poll_interval_overrides_feed() {
+        let rss = r#"<?xml version="1.0"?>
+<rss version="2.0"><channel><title>t</title><link>https://example.com</link><description>d</description>
+  <ttl>1440</ttl>
+</channel></rss>"#;
+
+        let mut server = mockito::Server::new_async().await;
+        let _m = server
+            .mock("GET", "/feed.xml")
+            .with_status(200)
+            .with_header("cache-control", "max-age=86400")
+            .with_body(rss)
+            .create();
+        let url = format!("{}/feed.xml", server.url());
+
+        let declared = create_test_source(&url);
+        declared.fetch().await.unwrap();
+        assert!(!declared.is_due(Utc::now() + chrono::Duration::hours(2)));
+
+        let overridden = create_test_source(&url).with_poll_interval(Duration::from_secs(60 * 60));
+        overridden.fetch().await.unwrap();
+        assert!(!overridden.is_due(Utc::now()));
+        assert!(overridden.is_due(Utc::now() + chrono::Duration::hours(2)));
+
+        let always = create_test_source(&url).with_poll_interval(Duration::ZERO);
+        always.fetch().await.unwrap();
+        assert!(always.is_due(Utc::now()));
+    }
+
+    #[test]
+    fn test_parse_duration_formats() {
+        assert_eq!(
+            RssSource::parse_duration("2520"),
+            Some(Duration::from_secs(2520))
+        );
+        assert_eq!(
+            RssSource::parse_duration("1:02:03"),
+            Some(Duration::from_secs(3723))
+        );
+        assert_eq!(
+            RssSource::parse_duration("05:30.5"),
+            Some(Duration::from_secs(330))
+        );
+        assert_eq!(RssSource::parse_duration("0"), None);
+        assert_eq!(RssSource::parse_duration("about an hour"), None);
+        assert_eq!(RssSource::parse_duration("1:2:3:4"), None);
+    }
+
+    #[tokio::test]
+    async fn test_pull_records_expiration_dates() {
+        let rss_content = r#"<?xml version="1.0"?>
+<rss version="2.0">
+  <channel>
+    <title>Deals</title>
+    <link>https://example.com</link>
+    <description>Deals</description>
+    <item>
+      <title>Flash sale</title>
+      <link>https://example.com/sale</link>
+      <expirationDate>Tue, 10 Jun 2025 04:00:00 GMT</expirationDate>
+    </item>
+    <item>
+      <title>Evergreen</title>
+      <link>https://example.com/evergreen</link>
+    </item>
+  </channel>
+</rss>"#;
+
+        let mut server = mockito::Server::new_async().await;
+        let _m = server
+            .mock("GET", "/deals.xml")
+            .with_status(200)
+            .with_body(rss_content)
+            .create();
+        let items = create_test_source(&format!("{}/deals.xml", server.url()))
+            .fetch()
+            .await
+            .unwrap();
+
+        assert_eq!(items.len(), 2);
+        let expires_at = items[0].expires_at.unwrap();
+        assert_eq!(expires_at.to_rfc3339(), "2025-06-10T04:00:00+00:00");
+        assert!(items[0].is_expired(Utc::now()));
+        assert_eq!(items[1].expires_at, None);
+    }
+
+    #[test]
+    fn test_item_expirations_ignores_channel_level_elements() {
+        let xml = br#"<rss><channel><expirationDate>x</expirationDate>
+            <item><title>a</title></item>
+            <item><expirationDate> 2030-01-01T00:00:00Z </expirationDate></item>
+            <item/></channel></rss>"#;
+        assert_eq!(
+            item_expirations(xml),
+            vec![None, Some("2030-01-01T00:00:00Z".to_string()), None]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_guarded_source_blocks_private_address() {
+        let mut server = mockito::Server::new_async().await;
+        let _m = server.mock("GET", "/feed.xml").with_status(200).create();
+        let url = format!("{}/feed.xml", server.url());
+
+        let blocked = create_test_source(&url).with_guard(RequestGuard::default());
+        let err = blocked.fetch().await.unwrap_err();
+        assert!(matches!(err, ClioError::Network(_)));
+        assert!(err.to_string().contains("private network address"));
+
+        let allowed = create_test_source(&url).with_guard(RequestGuard::new(true));
+        let err = allowed.fetch().await.unwrap_err();
+        assert!(matches!(err, ClioError::Parse(_)));
+    }
 }
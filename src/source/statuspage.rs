@@ -0,0 +1,410 @@
+use crate::config::{self, StatusProvider};
+use crate::error::{ClioError, ErrorContext, ErrorKind};
+use crate::http::{self, FeedClient};
+use crate::sanitize::TextLimits;
+use crate::source::{Item, Source};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use url::Url;
+use uuid::Uuid;
+
+/// Tag on every incident item
+const INCIDENT_TAG: &str = "incident";
+
+/// Tag on scheduled maintenance items, in place of "incident"
+const MAINTENANCE_TAG: &str = "maintenance";
+
+/// Source of incident updates from a hosted status page
+///
+/// Every update to an incident becomes an item, tagged "incident" (or
+/// "maintenance"), its status such as "resolved", and its severity as
+/// "critical", "major" or "minor", so alert rules can match on severity.
+#[derive(Debug, Clone)]
+pub struct StatusPageSource {
+    name: String,
+    url: String,
+    provider: StatusProvider,
+    client: FeedClient,
+    limits: TextLimits,
+}
+
+/// Atlassian Statuspage `/api/v2/incidents.json`
+#[derive(Deserialize)]
+struct Incidents {
+    #[serde(default)]
+    incidents: Vec<Incident>,
+}
+
+#[derive(Deserialize)]
+struct Incident {
+    id: String,
+    name: String,
+    /// "none", "minor", "major", "critical" or "maintenance"
+    impact: Option<String>,
+    #[serde(default)]
+    incident_updates: Vec<IncidentUpdate>,
+}
+
+#[derive(Deserialize)]
+struct IncidentUpdate {
+    id: String,
+    status: String,
+    #[serde(default)]
+    body: String,
+    created_at: Option<DateTime<Utc>>,
+    display_at: Option<DateTime<Utc>>,
+}
+
+/// Instatus `/summary.json`
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Summary {
+    #[serde(default)]
+    active_incidents: Vec<ActiveIncident>,
+    #[serde(default)]
+    active_maintenances: Vec<ActiveIncident>,
+}
+
+#[derive(Deserialize)]
+struct ActiveIncident {
+    name: String,
+    /// Such as "INVESTIGATING" or, for maintenance, "INPROGRESS"
+    status: String,
+    /// Such as "MAJOROUTAGE"; absent for maintenance
+    impact: Option<String>,
+    #[serde(alias = "start")]
+    started: Option<DateTime<Utc>>,
+    url: String,
+}
+
+#[async_trait]
+impl Source for StatusPageSource {
+    async fn fetch(&self) -> Result<Vec<Item>, ClioError> {
+        let mut items = match self.provider {
+            StatusProvider::Statuspage => {
+                let page: Incidents = self.get_json("api/v2/incidents.json").await?;
+                page.incidents
+                    .into_iter()
+                    .flat_map(|incident| self.incident_items(incident))
+                    .collect()
+            }
+            StatusProvider::Instatus => {
+                let summary: Summary = self.get_json("summary.json").await?;
+                let incidents = summary
+                    .active_incidents
+                    .into_iter()
+                    .map(|incident| self.active_item(incident, INCIDENT_TAG));
+                let maintenances = summary
+                    .active_maintenances
+                    .into_iter()
+                    .map(|maintenance| self.active_item(maintenance, MAINTENANCE_TAG));
+                incidents.chain(maintenances).collect::<Vec<_>>()
+            }
+        };
+        items.sort_by_key(|item| std::cmp::Reverse(item.pub_date));
+        Ok(items)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl StatusPageSource {
+    /// Send requests through a client shared with other sources
+    pub fn with_client(mut self, client: FeedClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Use custom maximum lengths for stored text fields
+    pub fn with_limits(mut self, limits: TextLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Load `path` under the page's address as JSON
+    async fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T, ClioError> {
+        let url = format!("{}/{path}", self.url.trim_end_matches('/'));
+        let parsed = Url::parse(&url).clio_network_err(format!("Invalid URL {url}"))?;
+        if let Some(guard) = self.client.guard() {
+            guard.check_url(&parsed)?;
+        }
+        let response = self.client.get(&url).send().await.map_err(|e| {
+            http::request_failed(format!("Failed to fetch status page {}", self.name), e)
+        })?;
+        if !response.status().is_success() {
+            return Err(ClioError::request(
+                ErrorKind::Http(response.status().as_u16()),
+                format!(
+                    "HTTP {} fetching status page {}",
+                    response.status(),
+                    self.name
+                ),
+            ));
+        }
+        response
+            .json()
+            .await
+            .clio_parse_err(format!("Invalid status page response from {}", self.name))
+    }
+
+    /// One item per update of a Statuspage incident
+    fn incident_items(&self, incident: Incident) -> Vec<Item> {
+        let impact = incident.impact.as_deref().unwrap_or_default();
+        let kind = if impact == "maintenance" {
+            MAINTENANCE_TAG
+        } else {
+            INCIDENT_TAG
+        };
+        let link = format!(
+            "{}/incidents/{}",
+            self.url.trim_end_matches('/'),
+            incident.id
+        );
+
+        incident
+            .incident_updates
+            .into_iter()
+            .map(|update| {
+                let status = status_label(&update.status);
+                Item {
+                    id: Uuid::new_v4().to_string(),
+                    source_name: self.name.clone(),
+                    title: self.limits.title(&format!(
+                        "{}: {}",
+                        capitalize(&status),
+                        incident.name
+                    )),
+                    link: format!("{link}#{}", update.id),
+                    summary: Some(self.limits.summary(&update.body)).filter(|s| !s.is_empty()),
+                    pub_date: update.display_at.or(update.created_at),
+                    tags: tags(kind, &status, impact),
+                    ..Default::default()
+                }
+            })
+            .collect()
+    }
+
+    /// An ongoing Instatus incident or maintenance, at its current status
+    ///
+    /// Instatus lists no individual updates, so the status goes in the link
+    /// to make each change of status a new item.
+    fn active_item(&self, incident: ActiveIncident, kind: &str) -> Item {
+        let status = status_label(&incident.status);
+        Item {
+            id: Uuid::new_v4().to_string(),
+            source_name: self.name.clone(),
+            title: self
+                .limits
+                .title(&format!("{}: {}", capitalize(&status), incident.name)),
+            link: format!("{}#{}", incident.url, status.replace(' ', "-")),
+            pub_date: incident.started,
+            tags: tags(
+                kind,
+                &status,
+                incident.impact.as_deref().unwrap_or_default(),
+            ),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&config::StatusPageSource> for StatusPageSource {
+    fn from(source: &config::StatusPageSource) -> Self {
+        Self {
+            name: source.name.clone(),
+            url: source.url.clone(),
+            provider: source.provider,
+            client: FeedClient::default(),
+            limits: TextLimits::default(),
+        }
+    }
+}
+
+/// Tags for an incident update: its kind, status (as one word, such as
+/// "in-progress") and severity
+fn tags(kind: &str, status: &str, impact: &str) -> Vec<String> {
+    let mut tags = vec![kind.to_string(), status.replace(' ', "-")];
+    tags.extend(severity(impact).map(str::to_string));
+    tags
+}
+
+/// The severity tag for a Statuspage or Instatus impact
+fn severity(impact: &str) -> Option<&'static str> {
+    match impact.to_ascii_lowercase().as_str() {
+        "critical" | "majoroutage" => Some("critical"),
+        "major" | "partialoutage" => Some("major"),
+        "minor" | "degradedperformance" => Some("minor"),
+        _ => None,
+    }
+}
+
+/// A status such as "in_progress" or "INPROGRESS" as lowercase words
+fn status_label(status: &str) -> String {
+    match status.to_ascii_lowercase().as_str() {
+        "inprogress" => "in progress".to_string(),
+        "notstartedyet" => "scheduled".to_string(),
+        status => status.replace('_', " "),
+    }
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn source(server: &mockito::Server, provider: StatusProvider) -> StatusPageSource {
+        StatusPageSource::from(&config::StatusPageSource {
+            name: "GitHub".to_string(),
+            url: format!("{}/", server.url()),
+            provider,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_fetch_statuspage_incident_updates() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/api/v2/incidents.json")
+            .with_body(
+                json!({
+                    "page": {"id": "kctbh9vrtdwd", "name": "GitHub"},
+                    "incidents": [
+                        {
+                            "id": "abc123",
+                            "name": "Disruption with Actions",
+                            "status": "resolved",
+                            "impact": "critical",
+                            "incident_updates": [
+                                {
+                                    "id": "u2",
+                                    "status": "resolved",
+                                    "body": "This incident has been resolved.",
+                                    "created_at": "2024-05-01T10:00:00Z",
+                                    "display_at": "2024-05-01T10:00:00Z"
+                                },
+                                {
+                                    "id": "u1",
+                                    "status": "investigating",
+                                    "body": "We are  investigating\nreports.",
+                                    "created_at": "2024-05-01T09:00:00Z"
+                                }
+                            ]
+                        },
+                        {
+                            "id": "def456",
+                            "name": "Database upgrade",
+                            "impact": "maintenance",
+                            "incident_updates": [{
+                                "id": "u3",
+                                "status": "in_progress",
+                                "body": "",
+                                "created_at": "2024-04-30T02:00:00Z"
+                            }]
+                        }
+                    ]
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let items = source(&server, StatusProvider::Statuspage)
+            .fetch()
+            .await
+            .unwrap();
+
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].title, "Resolved: Disruption with Actions");
+        assert_eq!(
+            items[0].link,
+            format!("{}/incidents/abc123#u2", server.url())
+        );
+        assert_eq!(items[0].tags, ["incident", "resolved", "critical"]);
+        assert_eq!(items[1].title, "Investigating: Disruption with Actions");
+        assert_eq!(
+            items[1].summary.as_deref(),
+            Some("We are investigating reports.")
+        );
+        assert_eq!(items[2].title, "In progress: Database upgrade");
+        assert_eq!(items[2].tags, ["maintenance", "in-progress"]);
+        assert_eq!(items[2].summary, None);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_instatus_active_incidents() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/summary.json")
+            .with_body(
+                json!({
+                    "page": {"name": "Acme", "url": "https://status.acme.dev", "status": "HASISSUES"},
+                    "activeIncidents": [{
+                        "id": "cl4a",
+                        "name": "Slow dashboard",
+                        "started": "2024-05-01T08:00:00Z",
+                        "status": "IDENTIFIED",
+                        "impact": "DEGRADEDPERFORMANCE",
+                        "url": "https://status.acme.dev/incident/cl4a"
+                    }],
+                    "activeMaintenances": [{
+                        "id": "cl4b",
+                        "name": "Network work",
+                        "start": "2024-05-02T01:00:00Z",
+                        "status": "NOTSTARTEDYET",
+                        "duration": "60",
+                        "url": "https://status.acme.dev/maintenance/cl4b"
+                    }]
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let items = source(&server, StatusProvider::Instatus)
+            .fetch()
+            .await
+            .unwrap();
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].title, "Scheduled: Network work");
+        assert_eq!(items[0].tags, ["maintenance", "scheduled"]);
+        assert_eq!(items[1].title, "Identified: Slow dashboard");
+        assert_eq!(
+            items[1].link,
+            "https://status.acme.dev/incident/cl4a#identified"
+        );
+        assert_eq!(items[1].tags, ["incident", "identified", "minor"]);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_reports_http_errors() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/api/v2/incidents.json")
+            .with_status(404)
+            .create_async()
+            .await;
+
+        let err = source(&server, StatusProvider::Statuspage)
+            .fetch()
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("HTTP 404"));
+    }
+}
@@ -0,0 +1,176 @@
+use crate::config;
+use crate::error::ClioError;
+use crate::source::{Item, Source};
+use async_trait::async_trait;
+use chrono::Utc;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Words that synthetic titles are made from
+const WORDS: &[&str] = &[
+    "async",
+    "benchmark",
+    "cache",
+    "compiler",
+    "database",
+    "editor",
+    "feed",
+    "kernel",
+    "latency",
+    "memory",
+    "network",
+    "parser",
+    "protocol",
+    "release",
+    "runtime",
+    "schema",
+    "storage",
+    "terminal",
+    "thread",
+    "workflow",
+];
+
+/// Source that generates fake items, for demos and load testing
+///
+/// Every pull waits for the configured latency, then fails with the
+/// configured probability or returns a fresh batch of items with unique links.
+#[derive(Debug, Clone)]
+pub struct SyntheticSource {
+    name: String,
+    url: String,
+    items: usize,
+    latency: Duration,
+    failure_rate: f64,
+}
+
+#[async_trait]
+impl Source for SyntheticSource {
+    async fn fetch(&self) -> Result<Vec<Item>, ClioError> {
+        if !self.latency.is_zero() {
+            tokio::time::sleep(self.latency).await;
+        }
+
+        if unit_random() < self.failure_rate {
+            return Err(ClioError::Network(format!(
+                "Simulated failure for {}",
+                self.name
+            )));
+        }
+
+        let now = Utc::now();
+        let batch = Uuid::new_v4().simple().to_string();
+        let slug = slug(&self.name);
+
+        Ok((0..self.items)
+            .map(|index| {
+                let title = (0..4)
+                    .map(|_| WORDS[random() as usize % WORDS.len()])
+                    .collect::<Vec<_>>()
+                    .join(" ");
+
+                Item {
+                    id: Uuid::new_v4().to_string(),
+                    source_name: self.name.clone(),
+                    title: capitalize(&title),
+                    link: format!("https://example.com/synthetic/{slug}/{batch}-{index}"),
+                    summary: Some(format!("Synthetic item {} of {}", index + 1, self.items)),
+                    pub_date: Some(now - chrono::Duration::minutes(index as i64 * 7)),
+                    score: Some((random() % 500) as u32),
+                    comments: Some((random() % 100) as u32),
+                    ..Default::default()
+                }
+            })
+            .collect())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl From<&config::SyntheticSource> for SyntheticSource {
+    fn from(source: &config::SyntheticSource) -> Self {
+        Self {
+            name: source.name.clone(),
+            url: format!("synthetic://{}", slug(&source.name)),
+            items: source.items,
+            latency: Duration::from_millis(source.latency_ms),
+            failure_rate: source.failure_rate,
+        }
+    }
+}
+
+fn random() -> u64 {
+    Uuid::new_v4().as_u64_pair().0
+}
+
+/// Uniformly distributed in `[0, 1)`
+fn unit_random() -> f64 {
+    (random() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+fn slug(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn source(items: usize, failure_rate: f64) -> SyntheticSource {
+        SyntheticSource::from(&config::SyntheticSource {
+            name: "Load Test".to_string(),
+            items,
+            latency_ms: 0,
+            failure_rate,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_generates_items() {
+        let source = source(25, 0.0);
+        assert_eq!(source.url(), "synthetic://load-test");
+
+        let first = source.fetch().await.unwrap();
+        let second = source.fetch().await.unwrap();
+        assert_eq!(first.len(), 25);
+        assert!(first.iter().all(|item| item.source_name == "Load Test"));
+        assert!(first.iter().all(|item| !item.title.is_empty()));
+
+        // Links stay unique across pulls, so every pull stores new items
+        let links: HashSet<_> = first.iter().chain(&second).map(|item| &item.link).collect();
+        assert_eq!(links.len(), 50);
+    }
+
+    #[tokio::test]
+    async fn test_failure_rate() {
+        let error = source(5, 1.0).fetch().await.unwrap_err();
+        assert!(matches!(error, ClioError::Network(message) if message.contains("Load Test")));
+    }
+
+    #[test]
+    fn test_unit_random_range() {
+        assert!(
+            (0..1000)
+                .map(|_| unit_random())
+                .all(|x| (0.0..1.0).contains(&x))
+        );
+    }
+}
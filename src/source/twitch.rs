@@ -0,0 +1,317 @@
+use crate::config;
+use crate::error::{ClioError, ErrorContext, ErrorKind};
+use crate::http::{self, FeedClient};
+use crate::sanitize::TextLimits;
+use crate::source::{Item, Source};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use std::env;
+use url::Url;
+use uuid::Uuid;
+
+const API_URL: &str = "https://api.twitch.tv/helix";
+
+const AUTH_URL: &str = "https://id.twitch.tv/oauth2";
+
+/// Most channels Helix looks up in one request
+const CHANNELS_PER_REQUEST: usize = 100;
+
+/// Tag on every stream item
+const LIVE_TAG: &str = "live";
+
+/// Source of "went live" items for Twitch channels
+///
+/// Each stream becomes one item, linked to the channel, that expires
+/// `live_ttl` after it was last seen live so ended streams do not pile up.
+#[derive(Debug, Clone)]
+pub struct TwitchSource {
+    name: String,
+    channels: Vec<String>,
+    live_ttl: Duration,
+    /// App client ID and secret, from `TWITCH_CLIENT_ID` and `TWITCH_CLIENT_SECRET`
+    credentials: Option<(String, String)>,
+    client: FeedClient,
+    limits: TextLimits,
+    api_url: String,
+    auth_url: String,
+}
+
+#[derive(Deserialize)]
+struct Token {
+    access_token: String,
+}
+
+#[derive(Deserialize)]
+struct Streams {
+    #[serde(default)]
+    data: Vec<Stream>,
+}
+
+#[derive(Deserialize)]
+struct Stream {
+    id: String,
+    user_login: String,
+    user_name: String,
+    #[serde(default)]
+    game_name: String,
+    #[serde(default)]
+    title: String,
+    started_at: Option<DateTime<Utc>>,
+    /// URL template with `{width}` and `{height}` placeholders
+    #[serde(default)]
+    thumbnail_url: String,
+}
+
+#[async_trait]
+impl Source for TwitchSource {
+    async fn fetch(&self) -> Result<Vec<Item>, ClioError> {
+        let Some((client_id, client_secret)) = &self.credentials else {
+            return Err(ClioError::Config(format!(
+                "Twitch source '{}' needs TWITCH_CLIENT_ID and TWITCH_CLIENT_SECRET \
+                 environment variables, from an application registered at dev.twitch.tv",
+                self.name
+            )));
+        };
+        let token = self.token(client_id, client_secret).await?;
+
+        let now = Utc::now();
+        let mut items = Vec::new();
+        for channels in self.channels.chunks(CHANNELS_PER_REQUEST) {
+            let logins: Vec<(&str, &str)> = channels
+                .iter()
+                .map(|channel| ("user_login", channel.as_str()))
+                .collect();
+            let url = format!("{}/streams", self.api_url);
+            self.check_url(&url)?;
+            let response = self
+                .client
+                .get(&url)
+                .query(&logins)
+                .header("Client-Id", client_id)
+                .bearer_auth(&token)
+                .send()
+                .await
+                .map_err(|e| {
+                    http::request_failed(format!("Failed to fetch streams for {}", self.name), e)
+                })?;
+            if !response.status().is_success() {
+                return Err(ClioError::request(
+                    ErrorKind::Http(response.status().as_u16()),
+                    format!(
+                        "HTTP {} fetching streams for {}",
+                        response.status(),
+                        self.name
+                    ),
+                ));
+            }
+            let streams: Streams = response
+                .json()
+                .await
+                .clio_parse_err("Invalid Twitch streams response")?;
+            items.extend(
+                streams
+                    .data
+                    .into_iter()
+                    .map(|stream| self.item(stream, now)),
+            );
+        }
+        Ok(items)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn url(&self) -> &str {
+        &self.api_url
+    }
+}
+
+impl TwitchSource {
+    /// Send requests through a client shared with other sources
+    pub fn with_client(mut self, client: FeedClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Use custom maximum lengths for stored text fields
+    pub fn with_limits(mut self, limits: TextLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// An app access token, from the client credentials flow
+    async fn token(&self, client_id: &str, client_secret: &str) -> Result<String, ClioError> {
+        let url = format!("{}/token", self.auth_url);
+        self.check_url(&url)?;
+        let response = self
+            .client
+            .post(&url)
+            .form(&[
+                ("client_id", client_id),
+                ("client_secret", client_secret),
+                ("grant_type", "client_credentials"),
+            ])
+            .send()
+            .await
+            .map_err(|e| http::request_failed("Failed to get a Twitch access token", e))?;
+        if !response.status().is_success() {
+            return Err(ClioError::request(
+                ErrorKind::Http(response.status().as_u16()),
+                format!(
+                    "HTTP {} getting a Twitch access token; check TWITCH_CLIENT_ID and \
+                 TWITCH_CLIENT_SECRET",
+                    response.status()
+                ),
+            ));
+        }
+        let token: Token = response
+            .json()
+            .await
+            .clio_parse_err("Invalid Twitch token response")?;
+        Ok(token.access_token)
+    }
+
+    fn check_url(&self, url: &str) -> Result<(), ClioError> {
+        let url = Url::parse(url).clio_network_err(format!("Invalid URL {url}"))?;
+        match self.client.guard() {
+            Some(guard) => guard.check_url(&url),
+            None => Ok(()),
+        }
+    }
+
+    fn item(&self, stream: Stream, now: DateTime<Utc>) -> Item {
+        let title = match stream.title.trim() {
+            "" => format!("{} is live", stream.user_name),
+            title => format!("{} is live: {title}", stream.user_name),
+        };
+        Item {
+            id: Uuid::new_v4().to_string(),
+            source_name: self.name.clone(),
+            title: self.limits.title(&title),
+            // The stream ID tells one broadcast of a channel from the next
+            link: format!("https://www.twitch.tv/{}#{}", stream.user_login, stream.id),
+            summary: (!stream.game_name.is_empty()).then(|| {
+                self.limits
+                    .summary(&format!("Streaming {}", stream.game_name))
+            }),
+            pub_date: stream.started_at,
+            expires_at: Some(now + self.live_ttl),
+            thumbnail: (!stream.thumbnail_url.is_empty()).then(|| {
+                stream
+                    .thumbnail_url
+                    .replace("{width}", "640")
+                    .replace("{height}", "360")
+            }),
+            tags: vec![LIVE_TAG.to_string()],
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&config::TwitchSource> for TwitchSource {
+    fn from(source: &config::TwitchSource) -> Self {
+        let credentials = env::var("TWITCH_CLIENT_ID")
+            .ok()
+            .zip(env::var("TWITCH_CLIENT_SECRET").ok());
+        Self {
+            name: source.name.clone(),
+            channels: source
+                .channels
+                .iter()
+                .map(|channel| channel.trim().to_lowercase())
+                .collect(),
+            live_ttl: Duration::minutes(i64::from(source.live_ttl_minutes)),
+            credentials,
+            client: FeedClient::default(),
+            limits: TextLimits::default(),
+            api_url: API_URL.to_string(),
+            auth_url: AUTH_URL.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Matcher;
+    use serde_json::json;
+
+    fn source(server: &mockito::Server, credentials: Option<(&str, &str)>) -> TwitchSource {
+        TwitchSource {
+            credentials: credentials.map(|(id, secret)| (id.to_string(), secret.to_string())),
+            api_url: server.url(),
+            auth_url: server.url(),
+            ..TwitchSource::from(&config::TwitchSource {
+                name: "Streams".to_string(),
+                channels: vec!["RustLang".to_string(), "jonhoo".to_string()],
+                live_ttl_minutes: 90,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_live_streams() {
+        let mut server = mockito::Server::new_async().await;
+        let token = server
+            .mock("POST", "/token")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("client_id".into(), "id".into()),
+                Matcher::UrlEncoded("client_secret".into(), "secret".into()),
+                Matcher::UrlEncoded("grant_type".into(), "client_credentials".into()),
+            ]))
+            .with_body(json!({"access_token": "tok", "expires_in": 5000000}).to_string())
+            .create_async()
+            .await;
+        let streams = server
+            .mock("GET", "/streams")
+            .match_query(Matcher::Regex("user_login=rustlang&user_login=jonhoo".into()))
+            .match_header("Client-Id", "id")
+            .match_header("Authorization", "Bearer tok")
+            .with_body(
+                json!({"data": [{
+                    "id": "40952121085",
+                    "user_login": "jonhoo",
+                    "user_name": "Jonhoo",
+                    "game_name": "Software and Game Development",
+                    "type": "live",
+                    "title": "Crust of Rust: async/await",
+                    "started_at": "2024-05-01T16:00:00Z",
+                    "thumbnail_url": "https://static-cdn.jtvnw.net/previews-ttv/live_user_jonhoo-{width}x{height}.jpg"
+                }]})
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let before = Utc::now();
+        let items = source(&server, Some(("id", "secret")))
+            .fetch()
+            .await
+            .unwrap();
+
+        token.assert_async().await;
+        streams.assert_async().await;
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].title, "Jonhoo is live: Crust of Rust: async/await");
+        assert_eq!(items[0].link, "https://www.twitch.tv/jonhoo#40952121085");
+        assert_eq!(
+            items[0].summary.as_deref(),
+            Some("Streaming Software and Game Development")
+        );
+        assert_eq!(
+            items[0].thumbnail.as_deref(),
+            Some("https://static-cdn.jtvnw.net/previews-ttv/live_user_jonhoo-640x360.jpg")
+        );
+        assert_eq!(items[0].tags, ["live"]);
+        assert!(items[0].expires_at.unwrap() >= before + Duration::minutes(90));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_needs_credentials() {
+        let server = mockito::Server::new_async().await;
+        let err = source(&server, None).fetch().await.unwrap_err();
+        assert!(err.to_string().contains("TWITCH_CLIENT_ID"));
+    }
+}
@@ -0,0 +1,336 @@
+use crate::config::{self, WeatherProvider};
+use crate::error::{ClioError, ErrorContext, ErrorKind};
+use crate::http::{self, FeedClient};
+use crate::sanitize::{TextLimits, strip_control};
+use crate::source::{Item, Severity, Source, WeatherAlert};
+use async_trait::async_trait;
+use atom_syndication::Feed;
+use atom_syndication::extension::ExtensionMap;
+use chrono::{DateTime, Utc};
+use url::Url;
+use uuid::Uuid;
+
+const NOAA_URL: &str = "https://api.weather.gov/alerts/active.atom";
+
+const METEOALARM_URL: &str = "https://feeds.meteoalarm.org/feeds/meteoalarm-legacy-atom-";
+
+/// Tag on every warning
+const WEATHER_TAG: &str = "weather";
+
+/// Source of official weather warnings from a CAP Atom feed
+///
+/// NOAA and MeteoAlarm both publish each warning as an Atom entry carrying
+/// the CAP fields (`cap:event`, `cap:severity`, `cap:areaDesc`, ...), which
+/// become the item's [`WeatherAlert`]. Warnings are tagged "weather" and
+/// their severity, and expire when the warning does.
+#[derive(Debug, Clone)]
+pub struct WeatherSource {
+    name: String,
+    url: String,
+    client: FeedClient,
+    limits: TextLimits,
+}
+
+/// Address of the CAP Atom feed with the warnings `source` asks for
+pub fn feed_url(source: &config::WeatherSource) -> String {
+    let area = source.area.trim();
+    match source.provider {
+        WeatherProvider::Noaa => format!("{NOAA_URL}?area={}", area.to_uppercase()),
+        WeatherProvider::Meteoalarm => {
+            format!("{METEOALARM_URL}{}", area.to_lowercase().replace(' ', "-"))
+        }
+    }
+}
+
+#[async_trait]
+impl Source for WeatherSource {
+    async fn fetch(&self) -> Result<Vec<Item>, ClioError> {
+        let url = Url::parse(&self.url).clio_network_err(format!("Invalid URL {}", self.url))?;
+        if let Some(guard) = self.client.guard() {
+            guard.check_url(&url)?;
+        }
+        let response = self
+            .client
+            .get(&self.url)
+            .header("Accept", "application/atom+xml")
+            .send()
+            .await
+            .map_err(|e| {
+                http::request_failed(
+                    format!("Failed to fetch weather warnings for {}", self.name),
+                    e,
+                )
+            })?;
+        if !response.status().is_success() {
+            return Err(ClioError::request(
+                ErrorKind::Http(response.status().as_u16()),
+                format!(
+                    "HTTP {} fetching weather warnings for {}",
+                    response.status(),
+                    self.name
+                ),
+            ));
+        }
+        let text = response
+            .text()
+            .await
+            .clio_network_err(format!("Failed to read weather warnings for {}", self.name))?;
+        let feed = text.parse::<Feed>()?;
+
+        let mut items: Vec<Item> = feed
+            .entries()
+            .iter()
+            .filter_map(|entry| {
+                let link = entry
+                    .links()
+                    .iter()
+                    .find(|link| link.rel() == "alternate")
+                    .or_else(|| entry.links().first())
+                    .map(|link| link.href())
+                    .unwrap_or(entry.id());
+                let link = strip_control(link.trim());
+                if link.is_empty() {
+                    return None;
+                }
+                let cap = Cap(entry.extensions());
+                let event = cap.text("event").unwrap_or(entry.title().as_str());
+                let severity = Severity::parse(cap.text("severity").unwrap_or_default());
+                let alert = WeatherAlert {
+                    event: self.limits.title(event),
+                    severity,
+                    area: self
+                        .limits
+                        .summary(cap.text("areaDesc").unwrap_or_default()),
+                    onset: cap.date("onset").or_else(|| cap.date("effective")),
+                };
+
+                let mut tags = vec![WEATHER_TAG.to_string()];
+                if severity != Severity::Unknown {
+                    tags.push(severity.as_str().to_string());
+                }
+                let summary = entry
+                    .summary()
+                    .map(|summary| summary.as_str())
+                    .filter(|summary| !summary.trim().is_empty());
+                Some(Item {
+                    id: Uuid::new_v4().to_string(),
+                    source_name: self.name.clone(),
+                    title: self.limits.title(entry.title()),
+                    link,
+                    summary: summary.map(|summary| self.limits.summary(summary)),
+                    pub_date: cap
+                        .date("sent")
+                        .or_else(|| entry.published().map(|date| date.to_utc()))
+                        .or(Some(entry.updated().to_utc())),
+                    expires_at: cap.date("expires"),
+                    tags,
+                    alert: Some(alert),
+                    ..Default::default()
+                })
+            })
+            .collect();
+        // Most dangerous first; the sort is stable, so feed order breaks ties
+        items.sort_by_key(|item| item.alert.as_ref().map(|alert| alert.severity));
+        Ok(items)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl WeatherSource {
+    /// Send requests through a client shared with other sources
+    pub fn with_client(mut self, client: FeedClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Use custom maximum lengths for stored text fields
+    pub fn with_limits(mut self, limits: TextLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+}
+
+impl From<&config::WeatherSource> for WeatherSource {
+    fn from(source: &config::WeatherSource) -> Self {
+        Self {
+            name: source.name.clone(),
+            url: feed_url(source),
+            client: FeedClient::default(),
+            limits: TextLimits::default(),
+        }
+    }
+}
+
+/// The `cap:` elements of an Atom entry
+struct Cap<'a>(&'a ExtensionMap);
+
+impl Cap<'_> {
+    fn text(&self, name: &str) -> Option<&str> {
+        self.0
+            .get("cap")?
+            .get(name)?
+            .first()?
+            .value()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    }
+
+    fn date(&self, name: &str) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(self.text(name)?)
+            .ok()
+            .map(|date| date.to_utc())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const NOAA_FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom" xmlns:cap="urn:oasis:names:tc:emergency:cap:1.2">
+  <id>https://api.weather.gov/alerts/active.atom?area=CA</id>
+  <title>Current watches, warnings, and advisories for California</title>
+  <updated>2024-01-10T12:00:00-08:00</updated>
+  <entry>
+    <id>urn:oid:2.49.0.1.840.0.aaa.001.1</id>
+    <title>Wind Advisory issued January 10 at 3:00AM PST until January 10 at 6:00PM PST by NWS San Francisco CA</title>
+    <link rel="alternate" href="https://api.weather.gov/alerts/urn:oid:2.49.0.1.840.0.aaa.001.1"/>
+    <updated>2024-01-10T03:00:00-08:00</updated>
+    <summary>South winds 20 to 30 mph with gusts up to 50 mph.</summary>
+    <cap:event>Wind Advisory</cap:event>
+    <cap:sent>2024-01-10T03:00:00-08:00</cap:sent>
+    <cap:effective>2024-01-10T03:00:00-08:00</cap:effective>
+    <cap:expires>2024-01-10T18:00:00-08:00</cap:expires>
+    <cap:severity>Moderate</cap:severity>
+    <cap:areaDesc>San Francisco; North Bay Interior Mountains</cap:areaDesc>
+  </entry>
+  <entry>
+    <id>urn:oid:2.49.0.1.840.0.bbb.002.1</id>
+    <title>Blizzard Warning issued January 10 at 4:00AM PST by NWS Sacramento CA</title>
+    <link rel="alternate" href="https://api.weather.gov/alerts/urn:oid:2.49.0.1.840.0.bbb.002.1"/>
+    <updated>2024-01-10T04:00:00-08:00</updated>
+    <summary>Heavy snow and whiteout conditions.</summary>
+    <cap:event>Blizzard Warning</cap:event>
+    <cap:sent>2024-01-10T04:00:00-08:00</cap:sent>
+    <cap:effective>2024-01-10T04:00:00-08:00</cap:effective>
+    <cap:onset>2024-01-10T22:00:00-08:00</cap:onset>
+    <cap:expires>2024-01-12T04:00:00-08:00</cap:expires>
+    <cap:severity>Extreme</cap:severity>
+    <cap:areaDesc>West Slope Northern Sierra Nevada</cap:areaDesc>
+  </entry>
+</feed>"#;
+
+    #[test]
+    fn test_noaa_and_meteoalarm_urls() {
+        let source = |provider, area: &str| {
+            WeatherSource::from(&config::WeatherSource {
+                name: "Weather".to_string(),
+                provider,
+                area: area.to_string(),
+            })
+        };
+        assert_eq!(
+            source(WeatherProvider::Noaa, "ca").url,
+            "https://api.weather.gov/alerts/active.atom?area=CA"
+        );
+        assert_eq!(
+            source(WeatherProvider::Meteoalarm, "United Kingdom").url,
+            "https://feeds.meteoalarm.org/feeds/meteoalarm-legacy-atom-united-kingdom"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_cap_warnings() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/alerts/active.atom?area=CA")
+            .with_header("content-type", "application/atom+xml")
+            .with_body(NOAA_FEED)
+            .create_async()
+            .await;
+        let source = WeatherSource {
+            url: format!("{}/alerts/active.atom?area=CA", server.url()),
+            ..WeatherSource::from(&config::WeatherSource {
+                name: "California".to_string(),
+                provider: WeatherProvider::Noaa,
+                area: "CA".to_string(),
+            })
+        };
+
+        let items = source.fetch().await.unwrap();
+
+        assert_eq!(items.len(), 2);
+        let blizzard = items[0].alert.as_ref().unwrap();
+        assert_eq!(blizzard.event, "Blizzard Warning");
+        assert_eq!(blizzard.severity, Severity::Extreme);
+        assert_eq!(blizzard.area, "West Slope Northern Sierra Nevada");
+        assert_eq!(
+            blizzard.onset,
+            Some(Utc.with_ymd_and_hms(2024, 1, 11, 6, 0, 0).unwrap())
+        );
+        assert_eq!(items[0].tags, ["weather", "extreme"]);
+        assert_eq!(
+            items[0].expires_at,
+            Some(Utc.with_ymd_and_hms(2024, 1, 12, 12, 0, 0).unwrap())
+        );
+
+        let wind = items[1].alert.as_ref().unwrap();
+        assert_eq!(wind.severity, Severity::Moderate);
+        // Without an onset, the warning starts when it takes effect
+        assert_eq!(
+            wind.onset,
+            Some(Utc.with_ymd_and_hms(2024, 1, 10, 11, 0, 0).unwrap())
+        );
+        assert_eq!(
+            items[1].link,
+            "https://api.weather.gov/alerts/urn:oid:2.49.0.1.840.0.aaa.001.1"
+        );
+        assert_eq!(
+            items[1].summary.as_deref(),
+            Some("South winds 20 to 30 mph with gusts up to 50 mph.")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_strips_control_characters() {
+        let mut server = mockito::Server::new_async().await;
+        let feed = NOAA_FEED
+            .replace(
+                "<cap:event>Blizzard Warning",
+                "<cap:event>&#27;]0;pwned&#7;Blizzard &#27;[31mWarning",
+            )
+            .replace("West Slope", "West\u{9b} Slope")
+            .replace(
+                "href=\"https://api.weather.gov/alerts/urn:oid:2.49.0.1.840.0.bbb.002.1\"",
+                "href=\"https://api.weather.gov/alerts/&#27;[2Jbbb\"",
+            );
+        let _mock = server
+            .mock("GET", "/alerts/active.atom?area=CA")
+            .with_body(feed)
+            .create_async()
+            .await;
+        let source = WeatherSource {
+            url: format!("{}/alerts/active.atom?area=CA", server.url()),
+            ..WeatherSource::from(&config::WeatherSource {
+                name: "California".to_string(),
+                provider: WeatherProvider::Noaa,
+                area: "CA".to_string(),
+            })
+        };
+
+        let items = source.fetch().await.unwrap();
+
+        let blizzard = items[0].alert.as_ref().unwrap();
+        assert_eq!(blizzard.event, "Blizzard Warning");
+        assert_eq!(blizzard.area, "West Slope Northern Sierra Nevada");
+        assert_eq!(items[0].link, "https://api.weather.gov/alerts/bbb");
+    }
+}
@@ -0,0 +1,401 @@
+use crate::config::{Config, RssSource};
+use crate::error::ClioError;
+use crate::fetcher::{Fetcher, SourceFetch};
+use crate::http::{FeedClient, RequestGuard};
+use crate::pull::{Pull, PullStore, Pulled};
+use crate::source::{self, Item};
+use chrono::{DateTime, TimeZone, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+/// Posts a [`FakeFeed`] lists unless told otherwise
+const DEFAULT_WINDOW: usize = 5;
+
+/// A feed served by [`FakeFeeds`]
+///
+/// Posts are numbered from 1 and published an hour apart from the start of
+/// 2024, so their links, titles and dates are the same on every run.
+#[derive(Debug, Clone)]
+pub struct FakeFeed {
+    name: String,
+    latency: Duration,
+    window: usize,
+    per_round: usize,
+    status: Option<u16>,
+    rewrites_links: bool,
+    expiring: bool,
+}
+
+impl FakeFeed {
+    /// A feed listing its 5 newest posts, publishing one more each round and
+    /// answering at once
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            latency: Duration::ZERO,
+            window: DEFAULT_WINDOW,
+            per_round: 1,
+            status: None,
+            rewrites_links: false,
+            expiring: false,
+        }
+    }
+
+    /// Answer each request after `latency`
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// List the `window` newest posts
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Publish `count` posts each round; 0 makes a feed that never changes
+    pub fn with_new_per_round(mut self, count: usize) -> Self {
+        self.per_round = count;
+        self
+    }
+
+    /// Give every post a new link on each request, as feeds that put session
+    /// IDs in their links do; [`FakeFeeds::links`] leaves the session out
+    pub fn rewriting_links(mut self) -> Self {
+        self.rewrites_links = true;
+        self
+    }
+
+    /// Have every post expire an hour after it was published, so all of them
+    /// have expired by the time they are pulled
+    pub fn expiring(mut self) -> Self {
+        self.expiring = true;
+        self
+    }
+
+    /// Answer every request with HTTP `status` instead of the feed
+    pub fn failing(mut self, status: u16) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Numbers of the posts listed in `round`, newest first
+    fn posts(&self, round: usize) -> impl Iterator<Item = usize> + use<> {
+        let published = self.window + round * self.per_round;
+        (published - self.window + 1..=published).rev()
+    }
+
+    fn path(&self) -> String {
+        format!("/{}/feed.xml", self.slug())
+    }
+
+    fn slug(&self) -> String {
+        self.name
+            .to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("-")
+    }
+
+    fn link(&self, base_url: &str, post: usize) -> String {
+        format!("{base_url}/{}/posts/{post}", self.slug())
+    }
+
+    fn rss(&self, base_url: &str, round: usize, request: usize) -> String {
+        let name = html_escape::encode_text(&self.name);
+        let session = if self.rewrites_links {
+            format!("?session={request}")
+        } else {
+            String::new()
+        };
+        let items: String = self
+            .posts(round)
+            .map(|post| {
+                let expiry = if self.expiring {
+                    let expires_at = published(post) + chrono::Duration::hours(1);
+                    format!(
+                        "<expirationDate>{}</expirationDate>",
+                        expires_at.to_rfc2822()
+                    )
+                } else {
+                    String::new()
+                };
+                format!(
+                    "<item><title>{name} post {post}</title><link>{}{session}</link>\
+                     <pubDate>{}</pubDate>{expiry}</item>",
+                    self.link(base_url, post),
+                    published(post).to_rfc2822()
+                )
+            })
+            .collect();
+        format!(
+            r#"<?xml version="1.0"?><rss version="2.0"><channel><title>{name}</title><link>{base_url}/{}</link><description>Fake feed</description>{items}</channel></rss>"#,
+            self.slug()
+        )
+    }
+}
+
+/// A fake feed server publishing [`FakeFeed`]s, for tests that drive the
+/// real pull pipeline end to end instead of mocking each part
+///
+/// Every feed starts at round 0; [`advance`](Self::advance) publishes the
+/// next posts of each feed, pushing its oldest out of the listing.
+pub struct FakeFeeds {
+    server: MockServer,
+    feeds: Vec<FakeFeed>,
+    round: Arc<AtomicUsize>,
+}
+
+impl FakeFeeds {
+    /// Serve `feeds` from a new server on a free local port
+    pub async fn start(feeds: Vec<FakeFeed>) -> Self {
+        let server = MockServer::start().await;
+        let round = Arc::new(AtomicUsize::new(0));
+        for feed in &feeds {
+            Mock::given(method("GET"))
+                .and(path(feed.path()))
+                .respond_with(Publish {
+                    feed: feed.clone(),
+                    base_url: server.uri(),
+                    round: round.clone(),
+                    requests: AtomicUsize::new(0),
+                })
+                .mount(&server)
+                .await;
+        }
+        Self {
+            server,
+            feeds,
+            round,
+        }
+    }
+
+    /// Serve `count` feeds named "Feed 1", "Feed 2" and so on, each as
+    /// [`FakeFeed::new`] makes it
+    pub async fn uniform(count: usize) -> Self {
+        Self::start(
+            (1..=count)
+                .map(|n| FakeFeed::new(format!("Feed {n}")))
+                .collect(),
+        )
+        .await
+    }
+
+    pub fn feeds(&self) -> &[FakeFeed] {
+        &self.feeds
+    }
+
+    pub fn round(&self) -> usize {
+        self.round.load(Ordering::SeqCst)
+    }
+
+    /// Publish the next posts of every feed
+    pub fn advance(&self) {
+        self.round.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Address of the feed called `name`
+    ///
+    /// # Panics
+    ///
+    /// When no feed has that name.
+    pub fn url(&self, name: &str) -> String {
+        format!("{}{}", self.server.uri(), self.feed(name).path())
+    }
+
+    /// Links the feed called `name` lists in the current round, newest first
+    ///
+    /// # Panics
+    ///
+    /// When no feed has that name.
+    pub fn links(&self, name: &str) -> Vec<String> {
+        let feed = self.feed(name);
+        feed.posts(self.round())
+            .map(|post| feed.link(&self.server.uri(), post))
+            .collect()
+    }
+
+    /// A configuration subscribed to every feed
+    ///
+    /// Private networks are allowed, since the server listens on the loopback
+    /// address.
+    pub fn config(&self) -> Config {
+        let mut config = Config::default();
+        config.settings.allow_private_networks = true;
+        config.sources.rss = self
+            .feeds
+            .iter()
+            .map(|feed| RssSource::new(feed.name.clone(), self.url(&feed.name)))
+            .collect();
+        config
+    }
+
+    /// Requests the server has answered so far
+    pub async fn requests(&self) -> usize {
+        self.server
+            .received_requests()
+            .await
+            .map_or(0, |requests| requests.len())
+    }
+
+    fn feed(&self, name: &str) -> &FakeFeed {
+        self.feeds
+            .iter()
+            .find(|feed| feed.name == name)
+            .unwrap_or_else(|| panic!("No fake feed named '{name}'"))
+    }
+}
+
+/// Storage holding pulled items in memory, for pulling without a database
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    items: Mutex<Vec<Item>>,
+    fetches: Mutex<Vec<SourceFetch>>,
+    archived: Mutex<HashSet<String>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The items stored so far, in the order they were stored
+    pub fn items(&self) -> Vec<Item> {
+        self.items.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Links of the stored items archived so far
+    pub fn archived(&self) -> HashSet<String> {
+        self.archived
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// The fetch history recorded so far, oldest first
+    pub fn fetches(&self) -> Vec<SourceFetch> {
+        self.fetches
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+impl PullStore for MemoryStore {
+    fn store_items(&self, items: &[Item]) -> Result<Vec<String>, ClioError> {
+        let mut stored = self.items.lock().unwrap_or_else(|e| e.into_inner());
+        let mut links: HashSet<String> = stored.iter().map(|item| item.link.clone()).collect();
+        let mut inserted = Vec::new();
+        for item in items {
+            if links.insert(item.link.clone()) {
+                stored.push(item.clone());
+                inserted.push(item.link.clone());
+            }
+        }
+        Ok(inserted)
+    }
+
+    fn record_fetches(&self, fetches: &[SourceFetch]) -> Result<(), ClioError> {
+        self.fetches
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .extend_from_slice(fetches);
+        Ok(())
+    }
+
+    fn body_hashes(&self) -> Result<HashMap<String, String>, ClioError> {
+        // Later fetches overwrite earlier ones, leaving the latest
+        Ok(self
+            .fetches()
+            .into_iter()
+            .filter(|fetch| fetch.succeeded)
+            .filter_map(|fetch| Some((fetch.source_name, fetch.body_hash?)))
+            .collect())
+    }
+
+    fn drop_stored_fingerprints(
+        &self,
+        source_name: &str,
+        items: Vec<Item>,
+    ) -> Result<Vec<Item>, ClioError> {
+        let stored: HashSet<String> = self
+            .items()
+            .iter()
+            .filter(|item| item.source_name == source_name)
+            .map(Item::fingerprint)
+            .collect();
+        Ok(items
+            .into_iter()
+            .filter(|item| !stored.contains(&item.fingerprint()))
+            .collect())
+    }
+
+    fn archive_expired(&self) -> Result<(), ClioError> {
+        let now = Utc::now();
+        let expired = self
+            .items()
+            .into_iter()
+            .filter(|item| item.expires_at.is_some_and(|expires_at| expires_at <= now))
+            .map(|item| item.link);
+        self.archived
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .extend(expired);
+        Ok(())
+    }
+}
+
+/// Pull every source of `config` into `store` with the pipeline `clio pull`
+/// runs, fetching with `fetcher`
+pub async fn pull(
+    config: &Config,
+    fetcher: Fetcher,
+    store: Arc<MemoryStore>,
+) -> Result<Pulled, ClioError> {
+    let client = FeedClient::new(RequestGuard::new(config.settings.allow_private_networks));
+    let sources = source::from_config(config, &client)?;
+    Pull::new(config, client)
+        .with_fetcher(fetcher.quiet())
+        .with_store(store)
+        .run(sources)
+        .await
+}
+
+/// When post number `post` of a fake feed was published
+fn published(post: usize) -> DateTime<Utc> {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    start + chrono::Duration::hours(post as i64)
+}
+
+/// Answers requests for one feed with the posts of the current round
+struct Publish {
+    feed: FakeFeed,
+    base_url: String,
+    round: Arc<AtomicUsize>,
+    requests: AtomicUsize,
+}
+
+impl Respond for Publish {
+    fn respond(&self, _request: &Request) -> ResponseTemplate {
+        let response = match self.feed.status {
+            Some(status) => ResponseTemplate::new(status),
+            None => ResponseTemplate::new(200)
+                .insert_header("content-type", "application/rss+xml")
+                .set_body_string(self.feed.rss(
+                    &self.base_url,
+                    self.round.load(Ordering::SeqCst),
+                    self.requests.fetch_add(1, Ordering::SeqCst),
+                )),
+        };
+        response.set_delay(self.feed.latency)
+    }
+}
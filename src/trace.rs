@@ -0,0 +1,271 @@
+use crate::error::{ClioError, ErrorContext};
+use crate::redact::redact;
+use crate::writes;
+use chrono::{DateTime, Utc};
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Headers in which CDNs and caching proxies report whether they had the response
+const CACHE_HEADERS: [&str; 3] = ["cf-cache-status", "x-cache-status", "x-cache"];
+
+/// Every HTTP request made during one run, for `clio pull --trace`
+#[derive(Debug)]
+pub struct Trace {
+    started_at: DateTime<Utc>,
+    requests: Mutex<Vec<TracedRequest>>,
+}
+
+/// One request in a [`Trace`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TracedRequest {
+    pub url: String,
+    pub started_at: DateTime<Utc>,
+    /// Time until the whole body was received, or the request failed
+    pub duration_ms: u64,
+    /// HTTP status code, unless the request failed before a response
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// Response body bytes received
+    pub bytes: u64,
+    /// Whether the response came from a cache, when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache: Option<CacheStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Where a response came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheStatus {
+    /// A CDN or proxy answered from its cache
+    Hit,
+    /// A CDN or proxy had to ask the origin server
+    Miss,
+    /// Replayed from a cassette, without the network
+    Replay,
+}
+
+/// A request in progress, recorded when finished or dropped
+///
+/// A span dropped without [`finish`](Self::finish) or [`fail`](Self::fail),
+/// such as when a fetch times out, is recorded as cancelled.
+#[derive(Debug)]
+pub struct Span {
+    trace: Arc<Trace>,
+    url: String,
+    started_at: DateTime<Utc>,
+    timer: Instant,
+    done: bool,
+}
+
+#[derive(Serialize)]
+struct TraceFile<'a> {
+    started_at: DateTime<Utc>,
+    finished_at: DateTime<Utc>,
+    requests: &'a [TracedRequest],
+}
+
+impl Trace {
+    pub fn new() -> Self {
+        Self {
+            started_at: Utc::now(),
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Start timing a request to `url`, recorded with its credentials redacted
+    pub fn start(self: &Arc<Self>, url: &str) -> Span {
+        Span {
+            trace: self.clone(),
+            url: redact(url),
+            started_at: Utc::now(),
+            timer: Instant::now(),
+            done: false,
+        }
+    }
+
+    /// Requests recorded so far, in the order they started
+    pub fn requests(&self) -> Vec<TracedRequest> {
+        let mut requests = self
+            .requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        requests.sort_by_key(|request| request.started_at);
+        requests
+    }
+
+    /// Write the trace to `path` as JSON
+    pub fn save(&self, path: &Path) -> Result<(), ClioError> {
+        writes::ensure_allowed("write the trace")?;
+        let requests = self.requests();
+        let file = TraceFile {
+            started_at: self.started_at,
+            finished_at: Utc::now(),
+            requests: &requests,
+        };
+        let contents =
+            serde_json::to_string_pretty(&file).clio_config_err("Failed to serialize trace")?;
+        fs::write(path, contents)
+            .clio_config_err(format!("Failed to write trace to {}", path.display()))
+    }
+
+    fn record(&self, request: TracedRequest) {
+        self.requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(request);
+    }
+}
+
+impl Default for Trace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CacheStatus {
+    /// The cache status a CDN or proxy reported in its response headers
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let value = CACHE_HEADERS
+            .iter()
+            .find_map(|name| headers.get(*name)?.to_str().ok())?
+            .to_ascii_uppercase();
+        if value.contains("HIT") {
+            Some(CacheStatus::Hit)
+        } else if ["MISS", "EXPIRED", "BYPASS", "DYNAMIC"]
+            .iter()
+            .any(|status| value.contains(status))
+        {
+            Some(CacheStatus::Miss)
+        } else {
+            None
+        }
+    }
+}
+
+impl Span {
+    /// Record a response
+    pub fn finish(mut self, status: u16, bytes: u64, cache: Option<CacheStatus>) {
+        self.record(Some(status), bytes, cache, None);
+    }
+
+    /// Record a request that failed without a usable response
+    pub fn fail(mut self, error: String) {
+        self.record(None, 0, None, Some(redact(&error)));
+    }
+
+    fn record(
+        &mut self,
+        status: Option<u16>,
+        bytes: u64,
+        cache: Option<CacheStatus>,
+        error: Option<String>,
+    ) {
+        self.done = true;
+        self.trace.record(TracedRequest {
+            url: std::mem::take(&mut self.url),
+            started_at: self.started_at,
+            duration_ms: self.timer.elapsed().as_millis() as u64,
+            status,
+            bytes,
+            cache,
+            error,
+        });
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        if !self.done {
+            self.record(None, 0, None, Some("Cancelled".to_string()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_spans_are_recorded() {
+        let trace = Arc::new(Trace::new());
+        trace
+            .start("https://example.com/a.xml")
+            .finish(200, 512, Some(CacheStatus::Hit));
+        trace
+            .start("https://example.com/b.xml")
+            .fail("HTTP 503".to_string());
+        drop(trace.start("https://example.com/slow.xml"));
+
+        let requests = trace.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].status, Some(200));
+        assert_eq!(requests[0].bytes, 512);
+        assert_eq!(requests[1].error.as_deref(), Some("HTTP 503"));
+        assert_eq!(requests[2].url, "https://example.com/slow.xml");
+        assert_eq!(requests[2].error.as_deref(), Some("Cancelled"));
+    }
+
+    #[test]
+    fn test_save() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("trace.json");
+        let trace = Arc::new(Trace::new());
+        trace
+            .start("https://example.com/feed.xml")
+            .finish(200, 42, None);
+
+        trace.save(&path).unwrap();
+        let saved: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let request = &saved["requests"][0];
+        assert_eq!(request["url"], "https://example.com/feed.xml");
+        assert_eq!(request["status"], 200);
+        assert_eq!(request["bytes"], 42);
+        assert!(request.get("cache").is_none());
+        assert!(saved["finished_at"].is_string());
+    }
+
+    #[test]
+    fn test_save_redacts_secrets() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("trace.json");
+        let trace = Arc::new(Trace::new());
+        trace
+            .start("https://example.com/feed.xml?token=hunter2&page=2")
+            .finish(200, 42, None);
+        trace
+            .start("https://example.com/private.xml?api_key=hunter2")
+            .fail("Failed to fetch https://example.com/private.xml?api_key=hunter2".to_string());
+
+        trace.save(&path).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        assert!(!saved.contains("hunter2"));
+        assert!(saved.contains("https://example.com/feed.xml?token=<REDACTED>&page=2"));
+    }
+
+    #[test]
+    fn test_cache_status_from_headers() {
+        let status = |name: &'static str, value: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(name, HeaderValue::from_static(value));
+            CacheStatus::from_headers(&headers)
+        };
+        assert_eq!(status("cf-cache-status", "HIT"), Some(CacheStatus::Hit));
+        assert_eq!(
+            status("x-cache", "Miss from cloudfront"),
+            Some(CacheStatus::Miss)
+        );
+        assert_eq!(status("x-cache-status", "EXPIRED"), Some(CacheStatus::Miss));
+        assert_eq!(status("x-cache", "unknown"), None);
+        assert_eq!(CacheStatus::from_headers(&HeaderMap::new()), None);
+    }
+}
@@ -0,0 +1,1147 @@
+use crate::Item;
+use crate::color::{self, Stream};
+use crate::database::ItemChange;
+use crate::display::{ListRow, relative_time, truncate_columns, wrap};
+use crate::error::ClioError;
+use crate::graphics::{self, GraphicsProtocol};
+use chrono::Utc;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Color, Print, SetAttribute, SetForegroundColor};
+use crossterm::{cursor, execute, queue, terminal};
+use std::collections::{BTreeSet, HashMap};
+use std::io::{self, Write};
+
+/// Lines taken by the header and status bar
+const CHROME_LINES: u16 = 2;
+
+/// Widest thumbnail shown in the reader pane, in columns
+const MAX_IMAGE_COLUMNS: u16 = 40;
+
+/// How the reader pane shows when a weather warning applies
+const ALERT_TIME: &str = "%b %d %H:%M UTC";
+
+const HELP: &str = "j/k move  space mark  v visual  p preview  r read  u unread  s star  \
+                    a archive  o open  A subscribe  q quit";
+
+/// Items shown when previewing a feed to subscribe to
+pub const PREVIEW_ITEMS: usize = 5;
+
+/// Something to do with the selected items
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Change stored state, such as marking read
+    Change(ItemChange),
+    /// Open in the browser
+    Open,
+}
+
+/// A feed found from a URL typed into the list, shown before subscribing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedPreview {
+    /// Name the source is saved under
+    pub name: String,
+    pub url: String,
+    /// Titles of the feed's newest items, up to [`PREVIEW_ITEMS`]
+    pub titles: Vec<String>,
+}
+
+/// Looks up the feed behind a URL typed into the list
+type FeedFinder = Box<dyn FnMut(&str) -> Result<FeedPreview, ClioError>>;
+
+/// Saves a feed subscribed to from the list
+type FeedSaver = Box<dyn FnMut(&FeedPreview) -> Result<(), ClioError>>;
+
+/// Finds the feed behind a URL, and subscribes to it once confirmed
+pub struct Subscriber {
+    find: FeedFinder,
+    add: FeedSaver,
+}
+
+impl Subscriber {
+    /// Look feeds up with `find` and save confirmed ones with `add`
+    pub fn new(
+        find: impl FnMut(&str) -> Result<FeedPreview, ClioError> + 'static,
+        add: impl FnMut(&FeedPreview) -> Result<(), ClioError> + 'static,
+    ) -> Self {
+        Self {
+            find: Box::new(find),
+            add: Box::new(add),
+        }
+    }
+}
+
+/// Downloads an image by URL, returning `None` when it cannot be fetched
+type ImageLoader = Box<dyn FnMut(&str) -> Option<Vec<u8>>>;
+
+/// Thumbnails for the reader pane
+pub struct Previews {
+    protocol: Option<GraphicsProtocol>,
+    load: ImageLoader,
+    /// Pixel size of a terminal cell
+    cell: (u32, u32),
+    /// Downloaded images by URL; `None` records a failed download
+    downloads: HashMap<String, Option<Vec<u8>>>,
+    /// Escape sequences by URL and size in cells
+    encoded: HashMap<(String, u16, u16), Option<String>>,
+}
+
+impl Previews {
+    /// Draw thumbnails with `protocol`, downloading them with `load`
+    pub fn new(
+        protocol: GraphicsProtocol,
+        load: impl FnMut(&str) -> Option<Vec<u8>> + 'static,
+    ) -> Self {
+        Self {
+            protocol: Some(protocol),
+            load: Box::new(load),
+            cell: graphics::cell_size(),
+            downloads: HashMap::new(),
+            encoded: HashMap::new(),
+        }
+    }
+
+    /// Show the reader pane as text only
+    pub fn disabled() -> Self {
+        Self {
+            protocol: None,
+            load: Box::new(|_| None),
+            cell: (0, 0),
+            downloads: HashMap::new(),
+            encoded: HashMap::new(),
+        }
+    }
+
+    /// Escape sequence drawing the image at `url` within `columns` × `rows` cells
+    fn image(&mut self, url: &str, columns: u16, rows: u16) -> Option<&str> {
+        let protocol = self.protocol?;
+        let key = (url.to_string(), columns, rows);
+        if !self.encoded.contains_key(&key) {
+            let data = self
+                .downloads
+                .entry(url.to_string())
+                .or_insert_with(|| (self.load)(url));
+            let escape = data
+                .as_deref()
+                .and_then(|data| protocol.encode(data, columns, rows, self.cell).ok());
+            self.encoded.insert(key.clone(), escape);
+        }
+        self.encoded[&key].as_deref()
+    }
+}
+
+/// Show rows in an interactive list until the user quits
+///
+/// Rows are read from `pages` as the cursor nears the end of those already
+/// read, so a long listing opens as soon as its first page arrives.
+///
+/// `handler` carries out actions on the selected items; when it succeeds the
+/// list is updated to match, and when it fails the error is shown in the
+/// status bar and the list is left as it was. `subscriber` looks up and
+/// saves the feeds subscribed to with `A`.
+pub fn run<F>(
+    mut pages: impl Iterator<Item = Result<Vec<ListRow>, ClioError>>,
+    mut previews: Previews,
+    mut subscriber: Subscriber,
+    mut handler: F,
+) -> io::Result<()>
+where
+    F: FnMut(Action, &[Item]) -> Result<(), ClioError>,
+{
+    let mut view = ListView::new(Vec::new());
+    view.complete = false;
+    let _screen = Screen::enter()?;
+    let mut out = io::stdout();
+
+    loop {
+        let (width, height) = terminal::size()?;
+        view.load(&mut pages, view.list_rows(height).max(1));
+        if let Some(protocol) = previews.protocol {
+            queue!(out, Print(protocol.clear()))?;
+        }
+
+        let pane = view.pane(width, height);
+        let thumbnail = pane
+            .zip(view.rows.get(view.cursor))
+            .and_then(|(pane, row)| Some((pane, row.item.thumbnail.as_deref()?)));
+        let image = thumbnail.and_then(|(pane, url)| {
+            let columns = (width / 3).min(MAX_IMAGE_COLUMNS);
+            let escape = previews.image(url, columns, pane.rows.saturating_sub(1))?;
+            Some((pane, columns, escape))
+        });
+
+        view.render(
+            &mut out,
+            width,
+            height,
+            image.map_or(0, |(_, columns, _)| columns),
+        )?;
+        if let Some((pane, _, escape)) = image {
+            queue!(out, cursor::MoveTo(0, pane.top + 1), Print(escape))?;
+            out.flush()?;
+        }
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        let page = view.list_rows(height).max(1);
+        match view.handle_key(key, page) {
+            Some(Command::Quit) => return Ok(()),
+            Some(Command::Act(action)) => view.perform(action, &mut handler),
+            Some(Command::Find(url)) => {
+                // Lookups take a moment, so say what is happening first
+                view.status = Some(format!("Looking for feeds at {url}…"));
+                view.render(&mut out, width, height, 0)?;
+                view.find_feed(&url, &mut subscriber);
+            }
+            Some(Command::Subscribe(preview)) => view.subscribe(preview, &mut subscriber),
+            None => {}
+        }
+    }
+}
+
+/// What a key press asks the event loop to do
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Command {
+    Quit,
+    Act(Action),
+    /// Look for the feed behind a URL
+    Find(String),
+    Subscribe(FeedPreview),
+}
+
+/// Input taken over from the list while subscribing to a feed
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Prompt {
+    /// The URL typed so far
+    Url(String),
+    /// A feed found, waiting for y or n
+    Confirm(FeedPreview),
+}
+
+/// Screen area below the list holding the reader pane
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Pane {
+    /// Line of the separator above the pane
+    top: u16,
+    /// Lines including the separator
+    rows: u16,
+}
+
+/// Raw mode on the alternate screen, restored on drop (including on panic)
+struct Screen;
+
+impl Screen {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Cursor, scroll position, and selection over the list rows
+#[derive(Debug)]
+struct ListView {
+    rows: Vec<ListRow>,
+    cursor: usize,
+    /// First row on screen
+    offset: usize,
+    /// Rows toggled with space or committed from visual mode
+    marks: BTreeSet<usize>,
+    /// Where visual mode started, while it is active
+    anchor: Option<usize>,
+    status: Option<String>,
+    /// Whether the reader pane is open
+    reader: bool,
+    prompt: Option<Prompt>,
+    /// Whether every page of rows has been read
+    complete: bool,
+}
+
+impl ListView {
+    fn new(rows: Vec<ListRow>) -> Self {
+        Self {
+            rows,
+            cursor: 0,
+            offset: 0,
+            marks: BTreeSet::new(),
+            anchor: None,
+            status: None,
+            reader: false,
+            prompt: None,
+            complete: true,
+        }
+    }
+
+    /// Read pages until two screens of rows lie past the cursor, or none are left
+    fn load(
+        &mut self,
+        pages: &mut impl Iterator<Item = Result<Vec<ListRow>, ClioError>>,
+        screen: usize,
+    ) {
+        while !self.complete && self.rows.len() < self.cursor + 2 * screen {
+            match pages.next() {
+                Some(Ok(rows)) => self.rows.extend(rows),
+                Some(Err(e)) => {
+                    self.status = Some(e.to_string());
+                    self.complete = true;
+                }
+                None => self.complete = true,
+            }
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent, page: usize) -> Option<Command> {
+        self.status = None;
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        if self.prompt.is_some() && !(ctrl && key.code == KeyCode::Char('c')) {
+            return self.handle_prompt_key(key);
+        }
+
+        match key.code {
+            KeyCode::Char('c') if ctrl => return Some(Command::Quit),
+            KeyCode::Char('d') if ctrl => self.move_by(page as isize / 2),
+            KeyCode::Char('u') if ctrl => self.move_by(-(page as isize / 2)),
+            KeyCode::Char('q') => return Some(Command::Quit),
+            KeyCode::Char('j') | KeyCode::Down => self.move_by(1),
+            KeyCode::Char('k') | KeyCode::Up => self.move_by(-1),
+            KeyCode::PageDown => self.move_by(page as isize),
+            KeyCode::PageUp => self.move_by(-(page as isize)),
+            KeyCode::Char('g') | KeyCode::Home => self.cursor = 0,
+            KeyCode::Char('G') | KeyCode::End => self.cursor = self.rows.len().saturating_sub(1),
+            KeyCode::Char(' ') => self.toggle_mark(),
+            KeyCode::Char('v') => self.toggle_visual(),
+            KeyCode::Esc => self.cancel(),
+            KeyCode::Char('p') | KeyCode::Tab => self.reader = !self.reader,
+            KeyCode::Char('r') => return Some(Command::Act(Action::Change(ItemChange::MarkRead))),
+            KeyCode::Char('u') => {
+                return Some(Command::Act(Action::Change(ItemChange::MarkUnread)));
+            }
+            KeyCode::Char('s') => return Some(Command::Act(Action::Change(self.star_change()))),
+            KeyCode::Char('a') => return Some(Command::Act(Action::Change(ItemChange::Archive))),
+            KeyCode::Char('o') | KeyCode::Enter => return Some(Command::Act(Action::Open)),
+            KeyCode::Char('A') => self.prompt = Some(Prompt::Url(String::new())),
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_prompt_key(&mut self, key: KeyEvent) -> Option<Command> {
+        match (self.prompt.take()?, key.code) {
+            (Prompt::Url(_), KeyCode::Esc) => {}
+            (Prompt::Url(url), KeyCode::Enter) if !url.trim().is_empty() => {
+                return Some(Command::Find(url.trim().to_string()));
+            }
+            (Prompt::Url(mut url), code) => {
+                match code {
+                    KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        url.push(c)
+                    }
+                    KeyCode::Backspace => {
+                        url.pop();
+                    }
+                    _ => {}
+                }
+                self.prompt = Some(Prompt::Url(url));
+            }
+            (Prompt::Confirm(preview), KeyCode::Char('y') | KeyCode::Enter) => {
+                return Some(Command::Subscribe(preview));
+            }
+            (Prompt::Confirm(_), KeyCode::Char('n' | 'q') | KeyCode::Esc) => {
+                self.status = Some("Not subscribed".to_string());
+            }
+            (prompt @ Prompt::Confirm(_), _) => self.prompt = Some(prompt),
+        }
+        None
+    }
+
+    fn find_feed(&mut self, url: &str, subscriber: &mut Subscriber) {
+        match (subscriber.find)(url) {
+            Ok(preview) => {
+                self.status = None;
+                self.prompt = Some(Prompt::Confirm(preview));
+            }
+            Err(e) => self.status = Some(e.to_string()),
+        }
+    }
+
+    fn subscribe(&mut self, preview: FeedPreview, subscriber: &mut Subscriber) {
+        self.status = Some(match (subscriber.add)(&preview) {
+            Ok(()) => format!(
+                "Subscribed to '{}'; its items arrive with the next pull",
+                preview.name
+            ),
+            Err(e) => e.to_string(),
+        });
+    }
+
+    /// Rows an action applies to: the visual range and marks, or else the cursor row
+    fn selection(&self) -> Vec<usize> {
+        let mut selected = self.marks.clone();
+        if let Some(anchor) = self.anchor {
+            selected.extend(anchor.min(self.cursor)..=anchor.max(self.cursor));
+        }
+        if selected.is_empty() && self.cursor < self.rows.len() {
+            selected.insert(self.cursor);
+        }
+        selected.into_iter().collect()
+    }
+
+    fn is_selected(&self, index: usize) -> bool {
+        self.marks.contains(&index)
+            || self.anchor.is_some_and(|anchor| {
+                (anchor.min(self.cursor)..=anchor.max(self.cursor)).contains(&index)
+            })
+    }
+
+    fn perform<F>(&mut self, action: Action, handler: &mut F)
+    where
+        F: FnMut(Action, &[Item]) -> Result<(), ClioError>,
+    {
+        let selection = self.selection();
+        if selection.is_empty() {
+            return;
+        }
+        let items: Vec<Item> = selection
+            .iter()
+            .map(|&i| self.rows[i].item.clone())
+            .collect();
+
+        match handler(action, &items) {
+            Ok(()) => {
+                self.apply(action, &selection);
+                self.status = Some(describe(action, selection.len()));
+            }
+            Err(e) => self.status = Some(e.to_string()),
+        }
+    }
+
+    /// Update rows to reflect an action that was carried out
+    ///
+    /// The selection is cleared afterwards, since archived rows shift the
+    /// indices of every row below them.
+    fn apply(&mut self, action: Action, selection: &[usize]) {
+        match action {
+            Action::Change(ItemChange::Archive) => {
+                for &i in selection.iter().rev() {
+                    self.rows.remove(i);
+                }
+                let removed_above = selection.iter().filter(|&&i| i < self.cursor).count();
+                self.cursor = (self.cursor - removed_above).min(self.rows.len().saturating_sub(1));
+            }
+            Action::Change(change) => {
+                for &i in selection {
+                    let item = &mut self.rows[i].item;
+                    match change {
+                        ItemChange::MarkRead => item.is_read = true,
+                        ItemChange::MarkUnread => item.is_read = false,
+                        ItemChange::Star => item.is_starred = true,
+                        ItemChange::Unstar => item.is_starred = false,
+                        ItemChange::Archive => {}
+                    }
+                }
+            }
+            Action::Open => {
+                for &i in selection {
+                    self.rows[i].item.is_read = true;
+                }
+            }
+        }
+        self.marks.clear();
+        self.anchor = None;
+    }
+
+    /// Unstar when everything selected is already starred, otherwise star
+    fn star_change(&self) -> ItemChange {
+        let selection = self.selection();
+        if !selection.is_empty() && selection.iter().all(|&i| self.rows[i].item.is_starred) {
+            ItemChange::Unstar
+        } else {
+            ItemChange::Star
+        }
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        let last = self.rows.len().saturating_sub(1);
+        self.cursor = self.cursor.saturating_add_signed(delta).min(last);
+    }
+
+    fn toggle_mark(&mut self) {
+        if self.cursor >= self.rows.len() {
+            return;
+        }
+        if !self.marks.remove(&self.cursor) {
+            self.marks.insert(self.cursor);
+        }
+        self.move_by(1);
+    }
+
+    /// Leave visual mode, or clear the marks when not in it
+    fn cancel(&mut self) {
+        if self.anchor.take().is_none() {
+            self.marks.clear();
+        }
+    }
+
+    fn toggle_visual(&mut self) {
+        match self.anchor.take() {
+            Some(anchor) => self
+                .marks
+                .extend(anchor.min(self.cursor)..=anchor.max(self.cursor)),
+            None if !self.rows.is_empty() => self.anchor = Some(self.cursor),
+            None => {}
+        }
+    }
+
+    /// Keep the cursor row within the visible window
+    fn scroll(&mut self, visible: usize) {
+        if self.cursor < self.offset {
+            self.offset = self.cursor;
+        } else if visible > 0 && self.cursor >= self.offset + visible {
+            self.offset = self.cursor + 1 - visible;
+        }
+    }
+
+    /// Lines available to list rows, leaving the lower half to the reader pane
+    fn list_rows(&self, height: u16) -> usize {
+        let lines = height.saturating_sub(CHROME_LINES);
+        usize::from(if self.reader { lines / 2 } else { lines })
+    }
+
+    fn pane(&self, width: u16, height: u16) -> Option<Pane> {
+        if !self.reader || width == 0 || matches!(self.prompt, Some(Prompt::Confirm(_))) {
+            return None;
+        }
+        let top = self.list_rows(height) as u16 + 1;
+        let rows = height.saturating_sub(1).saturating_sub(top);
+        (rows > 1).then_some(Pane { top, rows })
+    }
+
+    /// Draw the screen, leaving `image_columns` free on the left of the
+    /// reader pane for a thumbnail
+    fn render(
+        &mut self,
+        out: &mut impl Write,
+        width: u16,
+        height: u16,
+        image_columns: u16,
+    ) -> io::Result<()> {
+        let pane = self.pane(width, height);
+        let width = usize::from(width);
+        let visible = self.list_rows(height);
+        self.scroll(visible);
+        let now = Utc::now();
+
+        queue!(
+            out,
+            terminal::Clear(terminal::ClearType::All),
+            cursor::MoveTo(0, 0)
+        )?;
+
+        let more = if self.complete { "" } else { "+" };
+        let mut header = format!("clio — {}{more} items", self.rows.len());
+        let selected = self.selection().len();
+        if self.anchor.is_some() {
+            header.push_str(&format!("  -- VISUAL -- {selected} selected"));
+        } else if !self.marks.is_empty() {
+            header.push_str(&format!("  {selected} marked"));
+        }
+        queue!(
+            out,
+            SetAttribute(Attribute::Bold),
+            Print(truncate_columns(&header, width)),
+            SetAttribute(Attribute::Reset)
+        )?;
+
+        if let Some(Prompt::Confirm(preview)) = &self.prompt {
+            return self.render_preview(out, preview, width, height);
+        }
+
+        for (line, index) in (self.offset..self.rows.len()).take(visible).enumerate() {
+            let row = &self.rows[index];
+            let pointer = if index == self.cursor { '>' } else { ' ' };
+            let mark = if self.is_selected(index) { '*' } else { ' ' };
+            let star = if row.item.is_starred { '★' } else { ' ' };
+            let mut text = format!(
+                "{pointer}{mark}{star} {}  {}",
+                row.title(),
+                row.item.source_name
+            );
+            for detail in relative_time(&row.item, now).into_iter().chain(row.media()) {
+                text.push_str(&format!(" · {detail}"));
+            }
+
+            queue!(out, cursor::MoveTo(0, line as u16 + 1))?;
+            if self.is_selected(index) {
+                queue!(out, SetAttribute(Attribute::Reverse))?;
+            }
+            if !row.item.is_read {
+                queue!(out, SetAttribute(Attribute::Bold))?;
+            }
+            let severe = row
+                .item
+                .alert
+                .as_ref()
+                .is_some_and(|alert| alert.severity.is_severe());
+            if severe && color::enabled(Stream::Stdout) {
+                queue!(out, SetForegroundColor(Color::Red))?;
+            }
+            queue!(
+                out,
+                Print(truncate_columns(&text, width)),
+                SetAttribute(Attribute::Reset)
+            )?;
+        }
+
+        if self.rows.is_empty() {
+            queue!(
+                out,
+                cursor::MoveTo(0, 1),
+                Print("No items. Run 'clio pull' first to fetch content.")
+            )?;
+        }
+
+        if let (Some(pane), Some(row)) = (pane, self.rows.get(self.cursor)) {
+            self.render_pane(out, pane, width, image_columns, &row.item)?;
+        }
+
+        if let Some(Prompt::Url(url)) = &self.prompt {
+            queue!(
+                out,
+                cursor::MoveTo(0, height.saturating_sub(1)),
+                Print(truncate_columns(
+                    &format!("Subscribe to (site or feed URL): {url}_"),
+                    width
+                ))
+            )?;
+            return out.flush();
+        }
+        self.render_status(out, width, height, HELP)
+    }
+
+    fn render_status(
+        &self,
+        out: &mut impl Write,
+        width: usize,
+        height: u16,
+        help: &str,
+    ) -> io::Result<()> {
+        let status = self.status.as_deref().unwrap_or(help);
+        queue!(
+            out,
+            cursor::MoveTo(0, height.saturating_sub(1)),
+            SetAttribute(Attribute::Dim),
+            Print(truncate_columns(status, width)),
+            SetAttribute(Attribute::Reset)
+        )?;
+        out.flush()
+    }
+
+    /// Draw a feed found to subscribe to in place of the list
+    fn render_preview(
+        &self,
+        out: &mut impl Write,
+        preview: &FeedPreview,
+        width: usize,
+        height: u16,
+    ) -> io::Result<()> {
+        let mut lines = vec![
+            (Attribute::Bold, format!("Subscribe to '{}'?", preview.name)),
+            (Attribute::Underlined, preview.url.clone()),
+            (Attribute::Reset, String::new()),
+        ];
+        if preview.titles.is_empty() {
+            lines.push((Attribute::Dim, "The feed has no items yet".to_string()));
+        }
+        lines.extend(
+            preview
+                .titles
+                .iter()
+                .map(|title| (Attribute::Reset, format!("  - {title}"))),
+        );
+
+        let last = height.saturating_sub(1);
+        for (line, (attribute, text)) in (1..last).zip(lines) {
+            queue!(
+                out,
+                cursor::MoveTo(0, line),
+                SetAttribute(attribute),
+                Print(truncate_columns(&text, width)),
+                SetAttribute(Attribute::Reset)
+            )?;
+        }
+        self.render_status(out, width, height, "y subscribe  n cancel")
+    }
+
+    fn render_pane(
+        &self,
+        out: &mut impl Write,
+        pane: Pane,
+        width: usize,
+        image_columns: u16,
+        item: &Item,
+    ) -> io::Result<()> {
+        queue!(
+            out,
+            cursor::MoveTo(0, pane.top),
+            SetAttribute(Attribute::Dim),
+            Print("─".repeat(width)),
+            SetAttribute(Attribute::Reset)
+        )?;
+
+        let left = if image_columns > 0 {
+            image_columns + 1
+        } else {
+            0
+        };
+        let text_width = width.saturating_sub(usize::from(left));
+        let mut byline = item.source_name.clone();
+        if let Some(journal) = item.paper.as_ref().and_then(|paper| paper.journal.as_ref()) {
+            byline.push_str(&format!(" · {journal}"));
+        }
+        if let Some(age) = relative_time(item, Utc::now()) {
+            byline.push_str(&format!(" · {age}"));
+        }
+
+        let mut lines = vec![
+            (Attribute::Bold, truncate_columns(&item.title, text_width)),
+            (Attribute::Dim, truncate_columns(&byline, text_width)),
+            (
+                Attribute::Underlined,
+                truncate_columns(&item.link, text_width),
+            ),
+            (Attribute::Reset, String::new()),
+        ];
+        // Where and when a weather warning applies, ahead of its description
+        if let Some(alert) = &item.alert {
+            let onset = alert
+                .onset
+                .map(|onset| format!("from {}", onset.format(ALERT_TIME)));
+            let until = item
+                .expires_at
+                .map(|end| format!("until {}", end.format(ALERT_TIME)));
+            let parts: Vec<String> = [Some(alert.area.clone()), onset, until]
+                .into_iter()
+                .flatten()
+                .filter(|part| !part.is_empty())
+                .collect();
+            lines.insert(
+                3,
+                (
+                    Attribute::Bold,
+                    truncate_columns(&parts.join(" · "), text_width),
+                ),
+            );
+        }
+        // An enriched item's abstract says more than the feed's summary
+        let text = item
+            .paper
+            .as_ref()
+            .and_then(|paper| paper.abstract_text.as_ref())
+            .or(item.summary.as_ref());
+        if let Some(summary) = text {
+            lines.extend(
+                wrap(summary, text_width)
+                    .into_iter()
+                    .map(|line| (Attribute::Reset, line)),
+            );
+        }
+
+        for (line, (attribute, text)) in (pane.top + 1..pane.top + pane.rows).zip(lines) {
+            queue!(
+                out,
+                cursor::MoveTo(left, line),
+                SetAttribute(attribute),
+                Print(text),
+                SetAttribute(Attribute::Reset)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn describe(action: Action, count: usize) -> String {
+    let what = match action {
+        Action::Change(ItemChange::MarkRead) => "Marked read",
+        Action::Change(ItemChange::MarkUnread) => "Marked unread",
+        Action::Change(ItemChange::Star) => "Starred",
+        Action::Change(ItemChange::Unstar) => "Unstarred",
+        Action::Change(ItemChange::Archive) => "Archived",
+        Action::Open => "Opened",
+    };
+    match count {
+        1 => format!("{what} 1 item"),
+        n => format!("{what} {n} items"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::{Enclosure, Paper, Severity, WeatherAlert};
+
+    fn list(count: usize) -> ListView {
+        ListView::new(
+            (0..count)
+                .map(|i| ListRow {
+                    item: Item {
+                        id: format!("id-{i}"),
+                        title: format!("Item {i}"),
+                        ..Default::default()
+                    },
+                    count: 1,
+                })
+                .collect(),
+        )
+    }
+
+    fn press(view: &mut ListView, code: KeyCode) -> Option<Command> {
+        view.handle_key(KeyEvent::new(code, KeyModifiers::NONE), 10)
+    }
+
+    fn ids(items: &[Item]) -> Vec<&str> {
+        items.iter().map(|item| item.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_selection_defaults_to_cursor() {
+        let mut view = list(3);
+        press(&mut view, KeyCode::Char('j'));
+        assert_eq!(view.selection(), [1]);
+
+        assert_eq!(list(0).selection(), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_space_toggles_marks() {
+        let mut view = list(4);
+        press(&mut view, KeyCode::Char(' '));
+        press(&mut view, KeyCode::Char('j'));
+        press(&mut view, KeyCode::Char(' '));
+        assert_eq!(view.selection(), [0, 2]);
+        assert_eq!(view.cursor, 3);
+
+        press(&mut view, KeyCode::Char('k'));
+        press(&mut view, KeyCode::Char(' '));
+        assert_eq!(view.selection(), [0]);
+
+        press(&mut view, KeyCode::Esc);
+        assert_eq!(view.selection(), [3]);
+    }
+
+    #[test]
+    fn test_visual_mode_selects_range() {
+        let mut view = list(6);
+        press(&mut view, KeyCode::Char('j'));
+        press(&mut view, KeyCode::Char('v'));
+        press(&mut view, KeyCode::Char('j'));
+        press(&mut view, KeyCode::Char('j'));
+        assert_eq!(view.selection(), [1, 2, 3]);
+
+        // Leaving visual mode with v keeps the range marked
+        press(&mut view, KeyCode::Char('v'));
+        press(&mut view, KeyCode::Char('G'));
+        assert_eq!(view.selection(), [1, 2, 3]);
+
+        // Ranges extend upwards too, and combine with marks
+        press(&mut view, KeyCode::Char('v'));
+        press(&mut view, KeyCode::Char('k'));
+        assert_eq!(view.selection(), [1, 2, 3, 4, 5]);
+
+        // Esc cancels visual mode but keeps earlier marks
+        press(&mut view, KeyCode::Esc);
+        assert_eq!(view.selection(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_bulk_archive_refreshes_list() {
+        let mut view = list(5);
+        for _ in 0..3 {
+            press(&mut view, KeyCode::Char('j'));
+        }
+        press(&mut view, KeyCode::Char('v'));
+        press(&mut view, KeyCode::Char('j'));
+
+        let mut seen = Vec::new();
+        let action = press(&mut view, KeyCode::Char('a'));
+        assert_eq!(
+            action,
+            Some(Command::Act(Action::Change(ItemChange::Archive)))
+        );
+        view.perform(Action::Change(ItemChange::Archive), &mut |action, items| {
+            seen.push((action, ids(items).join(",")));
+            Ok(())
+        });
+
+        assert_eq!(
+            seen,
+            [(Action::Change(ItemChange::Archive), "id-3,id-4".to_string())]
+        );
+        let remaining: Vec<&str> = view.rows.iter().map(|r| r.item.id.as_str()).collect();
+        assert_eq!(remaining, ["id-0", "id-1", "id-2"]);
+        assert_eq!(view.cursor, 2);
+        assert!(view.anchor.is_none() && view.marks.is_empty());
+        assert_eq!(view.status.as_deref(), Some("Archived 2 items"));
+    }
+
+    #[test]
+    fn test_bulk_mark_read_and_star() {
+        let mut view = list(3);
+        press(&mut view, KeyCode::Char(' '));
+        press(&mut view, KeyCode::Char(' '));
+
+        view.perform(Action::Change(ItemChange::MarkRead), &mut |_, _| Ok(()));
+        assert!(view.rows[0].item.is_read && view.rows[1].item.is_read);
+        assert!(!view.rows[2].item.is_read);
+
+        // s stars, then unstars once everything selected is starred
+        press(&mut view, KeyCode::Char('g'));
+        assert_eq!(view.star_change(), ItemChange::Star);
+        view.perform(Action::Change(ItemChange::Star), &mut |_, _| Ok(()));
+        assert!(view.rows[0].item.is_starred);
+        assert_eq!(
+            press(&mut view, KeyCode::Char('s')),
+            Some(Command::Act(Action::Change(ItemChange::Unstar)))
+        );
+    }
+
+    #[test]
+    fn test_failed_action_keeps_selection() {
+        let mut view = list(3);
+        press(&mut view, KeyCode::Char(' '));
+        view.perform(Action::Change(ItemChange::Archive), &mut |_, _| {
+            Err(ClioError::Database("offline".to_string()))
+        });
+
+        assert_eq!(view.rows.len(), 3);
+        assert_eq!(view.selection(), [0]);
+        assert_eq!(view.status.as_deref(), Some("Database error: offline"));
+    }
+
+    #[test]
+    fn test_subscribe_from_list() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let added = Rc::new(RefCell::new(Vec::new()));
+        let saved = added.clone();
+        let mut subscriber = Subscriber::new(
+            |url| match url {
+                "example.com" => Ok(FeedPreview {
+                    name: "Example".to_string(),
+                    url: "https://example.com/feed.xml".to_string(),
+                    titles: vec!["First post".to_string()],
+                }),
+                _ => Err(ClioError::Network(format!("No feed found at {url}"))),
+            },
+            move |preview| {
+                saved.borrow_mut().push(preview.url.clone());
+                Ok(())
+            },
+        );
+        let mut view = list(3);
+
+        // Typing the URL does not trigger list keys such as q or a
+        press(&mut view, KeyCode::Char('A'));
+        for c in "exq".chars() {
+            assert_eq!(press(&mut view, KeyCode::Char(c)), None);
+        }
+        press(&mut view, KeyCode::Backspace);
+        for c in "ample.com".chars() {
+            press(&mut view, KeyCode::Char(c));
+        }
+        assert_eq!(view.rows.len(), 3);
+        let Some(Command::Find(url)) = press(&mut view, KeyCode::Enter) else {
+            panic!("Expected a lookup");
+        };
+        assert_eq!(url, "example.com");
+
+        view.find_feed(&url, &mut subscriber);
+        let mut out = Vec::new();
+        view.render(&mut out, 60, 20, 0).unwrap();
+        let screen = String::from_utf8(out).unwrap();
+        assert!(screen.contains("Subscribe to 'Example'?"));
+        assert!(screen.contains("First post"));
+        assert!(!screen.contains("Item 0"));
+
+        let Some(Command::Subscribe(preview)) = press(&mut view, KeyCode::Char('y')) else {
+            panic!("Expected a subscription");
+        };
+        view.subscribe(preview, &mut subscriber);
+        assert_eq!(*added.borrow(), ["https://example.com/feed.xml"]);
+        assert_eq!(
+            view.status.as_deref(),
+            Some("Subscribed to 'Example'; its items arrive with the next pull")
+        );
+        assert_eq!(view.prompt, None);
+
+        // A failed lookup is reported, and n or Esc back out
+        view.find_feed("nothing.example", &mut subscriber);
+        assert_eq!(
+            view.status.as_deref(),
+            Some("Network error: No feed found at nothing.example")
+        );
+        press(&mut view, KeyCode::Char('A'));
+        press(&mut view, KeyCode::Esc);
+        assert_eq!(view.prompt, None);
+        view.find_feed("example.com", &mut subscriber);
+        press(&mut view, KeyCode::Char('n'));
+        assert_eq!(view.prompt, None);
+        assert_eq!(view.status.as_deref(), Some("Not subscribed"));
+        assert_eq!(added.borrow().len(), 1);
+    }
+
+    #[test]
+    fn test_rows_load_as_cursor_nears_end() {
+        let mut pages = (0..5).map(|page| {
+            let rows = list(10).rows.into_iter().map(move |mut row| {
+                row.item.id = format!("{page}-{}", row.item.id);
+                row
+            });
+            Ok(rows.collect())
+        });
+        let mut view = ListView::new(Vec::new());
+        view.complete = false;
+
+        view.load(&mut pages, 8);
+        assert_eq!(view.rows.len(), 20);
+        view.cursor = 19;
+        view.load(&mut pages, 8);
+        assert_eq!(view.rows.len(), 40);
+        assert!(!view.complete);
+
+        let mut out = Vec::new();
+        view.render(&mut out, 60, 10, 0).unwrap();
+        assert!(String::from_utf8(out).unwrap().contains("clio — 40+ items"));
+
+        view.cursor = 39;
+        view.load(&mut pages, 8);
+        assert_eq!(view.rows.len(), 50);
+        assert!(view.complete);
+
+        // A page that fails to load ends the list with the error shown
+        let mut view = ListView::new(Vec::new());
+        view.complete = false;
+        view.load(
+            &mut std::iter::once(Err(ClioError::Database("timeout".to_string()))),
+            8,
+        );
+        assert!(view.complete);
+        assert_eq!(view.status.as_deref(), Some("Database error: timeout"));
+    }
+
+    #[test]
+    fn test_scroll_follows_cursor() {
+        let mut view = list(50);
+        view.cursor = 30;
+        view.scroll(10);
+        assert_eq!(view.offset, 21);
+        view.cursor = 5;
+        view.scroll(10);
+        assert_eq!(view.offset, 5);
+    }
+
+    #[test]
+    fn test_reader_pane_takes_lower_half() {
+        let mut view = list(50);
+        assert_eq!(view.list_rows(24), 22);
+        assert_eq!(view.pane(80, 24), None);
+
+        press(&mut view, KeyCode::Char('p'));
+        assert_eq!(view.list_rows(24), 11);
+        assert_eq!(view.pane(80, 24), Some(Pane { top: 12, rows: 11 }));
+
+        // Too short to show anything below the separator
+        assert_eq!(view.pane(80, 4), None);
+
+        press(&mut view, KeyCode::Tab);
+        assert_eq!(view.pane(80, 24), None);
+    }
+
+    #[test]
+    fn test_reader_pane_shows_item() {
+        let mut view = list(3);
+        view.rows[1].item.summary = Some("A summary that wraps".to_string());
+        view.rows[1].item.link = "https://example.com/1".to_string();
+        press(&mut view, KeyCode::Char('j'));
+        press(&mut view, KeyCode::Char('p'));
+
+        let mut out = Vec::new();
+        view.render(&mut out, 40, 20, 0).unwrap();
+        let screen = String::from_utf8(out).unwrap();
+        assert!(screen.contains("https://example.com/1"));
+        assert!(screen.contains("A summary that wraps"));
+
+        view.rows[1].item.paper = Some(Paper {
+            journal: Some("J. Tests".to_string()),
+            abstract_text: Some("The abstract".to_string()),
+            ..Default::default()
+        });
+        let mut out = Vec::new();
+        view.render(&mut out, 40, 20, 0).unwrap();
+        let screen = String::from_utf8(out).unwrap();
+        assert!(screen.contains("J. Tests"));
+        assert!(screen.contains("The abstract"));
+        assert!(!screen.contains("A summary that wraps"));
+
+        view.rows[1].item.alert = Some(WeatherAlert {
+            event: "Flood Watch".to_string(),
+            severity: Severity::Severe,
+            area: "Coast".to_string(),
+            onset: Some(Utc::now()),
+        });
+        let mut out = Vec::new();
+        view.render(&mut out, 60, 20, 0).unwrap();
+        let screen = String::from_utf8(out).unwrap();
+        assert!(screen.contains("[SEVERE ALERT]"));
+        assert!(screen.contains("Coast · from "));
+
+        view.rows[0].item.enclosure = Some(Enclosure {
+            url: "https://example.com/episode.mp3".to_string(),
+            length: Some(39_845_888),
+            duration: Some(std::time::Duration::from_secs(42 * 60)),
+            ..Default::default()
+        });
+        let mut out = Vec::new();
+        view.render(&mut out, 60, 20, 0).unwrap();
+        assert!(
+            String::from_utf8(out)
+                .unwrap()
+                .contains("Item 0   · 42 min, 38 MB")
+        );
+    }
+
+    #[test]
+    fn test_previews_cache_downloads() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let loads = Arc::new(AtomicUsize::new(0));
+        let counter = loads.clone();
+        let mut previews = Previews {
+            cell: (8, 16),
+            ..Previews::new(GraphicsProtocol::Sixel, move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                None
+            })
+        };
+
+        assert_eq!(previews.image("https://example.com/a.png", 10, 5), None);
+        assert_eq!(previews.image("https://example.com/a.png", 12, 5), None);
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            Previews::disabled().image("https://example.com/a.png", 10, 5),
+            None
+        );
+    }
+}
@@ -0,0 +1,233 @@
+use crate::config::Config;
+use crate::error::{ClioError, ErrorContext};
+use crate::writes;
+use chrono::{DateTime, Datelike, Days, NaiveDate, TimeZone, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+
+/// Number of weeks shown in the reading history
+pub const WEEKS: usize = 8;
+
+/// Something worth counting about how clio is used
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Activity {
+    /// A `clio pull` run
+    Pull,
+    /// Items marked read or opened
+    Read,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageEvent {
+    pub at: DateTime<Utc>,
+    pub activity: Activity,
+    pub count: u64,
+}
+
+/// Local record of clio usage, one JSON object per line
+///
+/// Lives at `~/.clio/usage.log` and is only ever read by `clio stats --me`;
+/// nothing in it is sent anywhere.
+#[derive(Debug, Clone)]
+pub struct UsageLog {
+    path: PathBuf,
+}
+
+/// Usage totals for `clio stats --me`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UsageSummary {
+    pub pulls: u64,
+    /// Pulls in the last 7 days
+    pub recent_pulls: u64,
+    pub items_read: u64,
+    /// Items read in each of the last [`WEEKS`] weeks, keyed by the Monday
+    /// starting the week, oldest first
+    pub reads_per_week: Vec<(NaiveDate, u64)>,
+    /// Items read by hour of the day, in the summary's time zone
+    pub reads_by_hour: [u64; 24],
+}
+
+impl UsageLog {
+    /// The usage log in the clio configuration directory
+    ///
+    /// Nothing is created until the first event is recorded.
+    pub fn open() -> Result<Self, ClioError> {
+        Ok(Self::at(Config::config_dir()?.join("usage.log")))
+    }
+
+    /// A usage log at a specific path
+    pub fn at(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Count `count` occurrences of `activity`, now
+    ///
+    /// Nothing is recorded when filesystem writes are disabled.
+    pub fn record(&self, activity: Activity, count: u64) -> Result<(), ClioError> {
+        if writes::read_only().is_some() {
+            return Ok(());
+        }
+        let event = UsageEvent {
+            at: Utc::now(),
+            activity,
+            count,
+        };
+        let mut line =
+            serde_json::to_string(&event).clio_config_err("Failed to serialize usage event")?;
+        line.push('\n');
+
+        if let Some(dir) = self.path.parent() {
+            Config::ensure_private_dir(dir)?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .mode(0o600)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .clio_config_err(format!(
+                "Failed to write usage log at {}",
+                self.path.display()
+            ))
+    }
+
+    /// Every recorded event, skipping lines that cannot be parsed
+    pub fn events(&self) -> Result<Vec<UsageEvent>, ClioError> {
+        match fs::read_to_string(&self.path) {
+            Ok(contents) => Ok(contents
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(ClioError::Config(format!(
+                "Failed to read usage log at {}: {e}",
+                self.path.display()
+            ))),
+        }
+    }
+}
+
+impl UsageSummary {
+    /// Summarize `events` as of `now`, bucketing by week and hour in `now`'s time zone
+    pub fn new<Tz: TimeZone>(events: &[UsageEvent], now: DateTime<Tz>) -> Self {
+        let zone = now.timezone();
+        let this_week = week_start(now.date_naive());
+        let weeks: Vec<NaiveDate> = (0..WEEKS as u64)
+            .rev()
+            .filter_map(|ago| this_week.checked_sub_days(Days::new(7 * ago)))
+            .collect();
+
+        let mut summary = Self {
+            pulls: 0,
+            recent_pulls: 0,
+            items_read: 0,
+            reads_per_week: weeks.iter().map(|&week| (week, 0)).collect(),
+            reads_by_hour: [0; 24],
+        };
+        let week_ago = now.with_timezone(&Utc) - chrono::Duration::days(7);
+
+        for event in events {
+            match event.activity {
+                Activity::Pull => {
+                    summary.pulls += event.count;
+                    if event.at > week_ago {
+                        summary.recent_pulls += event.count;
+                    }
+                }
+                Activity::Read => {
+                    let local = event.at.with_timezone(&zone);
+                    summary.items_read += event.count;
+                    summary.reads_by_hour[local.hour() as usize] += event.count;
+
+                    let week = week_start(local.date_naive());
+                    if let Some((_, count)) = summary
+                        .reads_per_week
+                        .iter_mut()
+                        .find(|(start, _)| *start == week)
+                    {
+                        *count += event.count;
+                    }
+                }
+            }
+        }
+        summary
+    }
+}
+
+/// The Monday starting the week containing `date`
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Days::new(u64::from(date.weekday().num_days_from_monday()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn event(at: &str, activity: Activity, count: u64) -> UsageEvent {
+        UsageEvent {
+            at: DateTime::parse_from_rfc3339(at)
+                .unwrap()
+                .with_timezone(&Utc),
+            activity,
+            count,
+        }
+    }
+
+    #[test]
+    fn test_record_and_read_events() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("usage.log");
+        let log = UsageLog::at(path.clone());
+        assert!(log.events().unwrap().is_empty());
+
+        log.record(Activity::Pull, 1).unwrap();
+        fs::write(&path, fs::read_to_string(&path).unwrap() + "not json\n").unwrap();
+        log.record(Activity::Read, 3).unwrap();
+
+        let events = log.events().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].activity, Activity::Pull);
+        assert_eq!((events[1].activity, events[1].count), (Activity::Read, 3));
+    }
+
+    #[test]
+    fn test_summary() {
+        // Thursday
+        let now = DateTime::parse_from_rfc3339("2024-05-02T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let events = [
+            event("2024-05-01T08:15:00Z", Activity::Pull, 1),
+            event("2024-04-01T08:15:00Z", Activity::Pull, 1),
+            event("2024-05-01T21:30:00Z", Activity::Read, 4),
+            event("2024-04-29T00:05:00Z", Activity::Read, 1),
+            event("2024-04-28T21:00:00Z", Activity::Read, 2),
+            // Older than the weeks shown, but still counted in the totals
+            event("2023-01-01T21:00:00Z", Activity::Read, 5),
+        ];
+
+        let summary = UsageSummary::new(&events, now);
+        assert_eq!((summary.pulls, summary.recent_pulls), (2, 1));
+        assert_eq!(summary.items_read, 12);
+        assert_eq!(summary.reads_per_week.len(), WEEKS);
+
+        let this_week = NaiveDate::from_ymd_opt(2024, 4, 29).unwrap();
+        assert_eq!(summary.reads_per_week[WEEKS - 1], (this_week, 5));
+        assert_eq!(summary.reads_per_week[WEEKS - 2].1, 2);
+        assert_eq!(summary.reads_by_hour[21], 11);
+        assert_eq!(summary.reads_by_hour[0], 1);
+    }
+
+    #[test]
+    fn test_week_start() {
+        let sunday = NaiveDate::from_ymd_opt(2024, 5, 5).unwrap();
+        let monday = NaiveDate::from_ymd_opt(2024, 4, 29).unwrap();
+        assert_eq!(week_start(sunday), monday);
+        assert_eq!(week_start(monday), monday);
+    }
+}
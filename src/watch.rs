@@ -0,0 +1,87 @@
+use crate::Item;
+use std::collections::HashSet;
+
+/// Links of the items `clio watch` has already come across
+///
+/// Feeds return the same items on every poll, so each link is only
+/// reported once per run. Items of fingerprinted feeds are keyed by title and
+/// date instead, since those feeds change their links between polls.
+#[derive(Debug, Default)]
+pub struct Seen {
+    keys: HashSet<String>,
+    fingerprinted: HashSet<String>,
+}
+
+impl Seen {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Match the items of the sources named in `fingerprinted` by
+    /// [`Item::fingerprint`] rather than link
+    pub fn with_fingerprints(mut self, fingerprinted: HashSet<String>) -> Self {
+        self.fingerprinted = fingerprinted;
+        self
+    }
+
+    /// Keep the first copy of each item that has not been seen, and remember
+    /// it
+    pub fn unseen(&mut self, items: Vec<Item>) -> Vec<Item> {
+        items
+            .into_iter()
+            .filter(|item| {
+                let key = if self.fingerprinted.contains(&item.source_name) {
+                    format!("{}\n{}", item.source_name, item.fingerprint())
+                } else {
+                    item.link.clone()
+                };
+                self.keys.insert(key)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(link: &str) -> Item {
+        Item {
+            link: link.to_string(),
+            title: link.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_unseen_reports_each_link_once() {
+        let mut seen = Seen::new();
+        let first = seen.unseen(vec![item("a"), item("b"), item("a")]);
+        assert_eq!(
+            first.iter().map(|i| i.link.as_str()).collect::<Vec<_>>(),
+            vec!["a", "b"]
+        );
+
+        let second = seen.unseen(vec![item("b"), item("c")]);
+        assert_eq!(
+            second.iter().map(|i| i.link.as_str()).collect::<Vec<_>>(),
+            vec!["c"]
+        );
+        assert!(seen.unseen(vec![item("a"), item("c")]).is_empty());
+    }
+
+    #[test]
+    fn test_unseen_matches_fingerprinted_items_by_title() {
+        let mut seen = Seen::new().with_fingerprints(HashSet::from(["Rewriter".to_string()]));
+        let rewritten = |link: &str| Item {
+            source_name: "Rewriter".to_string(),
+            title: "Same post".to_string(),
+            link: link.to_string(),
+            ..Default::default()
+        };
+        assert_eq!(seen.unseen(vec![rewritten("a?session=1")]).len(), 1);
+        assert!(seen.unseen(vec![rewritten("a?session=2")]).is_empty());
+        // Other feeds are still matched by link
+        assert_eq!(seen.unseen(vec![item("b"), item("c")]).len(), 2);
+    }
+}
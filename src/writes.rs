@@ -0,0 +1,93 @@
+use crate::error::ClioError;
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Why filesystem writes are turned off for this run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadOnly {
+    /// `--no-write` was given
+    Requested,
+    /// The home directory, or `~/.clio`, cannot be written to
+    Home,
+}
+
+static READ_ONLY: OnceLock<ReadOnly> = OnceLock::new();
+
+/// Turn off filesystem writes for the rest of the process
+///
+/// Incidental writes such as the audit and usage logs are skipped; writes a
+/// command exists to make, such as saving the configuration, fail instead.
+/// The first reason given is the one reported.
+pub fn disable(reason: ReadOnly) {
+    let _ = READ_ONLY.set(reason);
+}
+
+/// Why writes are turned off, if they are
+pub fn read_only() -> Option<ReadOnly> {
+    READ_ONLY.get().copied()
+}
+
+/// Fail if writes are turned off, naming what could not be done
+pub fn ensure_allowed(action: &str) -> Result<(), ClioError> {
+    match read_only() {
+        Some(reason) => Err(ClioError::Config(refusal(action, reason))),
+        None => Ok(()),
+    }
+}
+
+/// Whether clio cannot write under the home directory
+///
+/// Checks `~/.clio` when it exists, since that is where clio writes, and the
+/// home directory itself otherwise. Nothing is written to find out.
+pub fn home_is_read_only() -> bool {
+    let Some(home) = dirs::home_dir() else {
+        return false;
+    };
+    let clio_dir = home.join(".clio");
+    let dir = if clio_dir.is_dir() { clio_dir } else { home };
+    dir.is_dir() && !is_writable(&dir)
+}
+
+fn refusal(action: &str, reason: ReadOnly) -> String {
+    match reason {
+        ReadOnly::Requested => {
+            format!("Cannot {action}: filesystem writes are disabled by --no-write")
+        }
+        ReadOnly::Home => format!(
+            "Cannot {action}: the home directory is read-only, so filesystem writes are disabled"
+        ),
+    }
+}
+
+/// Whether the current user may write to `path`, including on read-only mounts
+fn is_writable(path: &Path) -> bool {
+    let Ok(path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: `path` is a valid NUL-terminated string that outlives the call
+    unsafe { libc::access(path.as_ptr(), libc::W_OK) == 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_is_writable() {
+        let dir = TempDir::new().unwrap();
+        assert!(is_writable(dir.path()));
+        assert!(!is_writable(&dir.path().join("missing")));
+    }
+
+    #[test]
+    fn test_refusal() {
+        assert_eq!(
+            refusal("save the configuration", ReadOnly::Requested),
+            "Cannot save the configuration: filesystem writes are disabled by --no-write"
+        );
+        assert!(refusal("write the trace", ReadOnly::Home).contains("home directory is read-only"));
+    }
+}
@@ -1,9 +1,24 @@
 use assert_cmd::Command;
 use predicates::prelude::*;
-use std::sync::Mutex;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
 
-// Use a mutex to ensure tests that manipulate ~/.clio don't run in parallel
-static CONFIG_MUTEX: Mutex<()> = Mutex::new(());
+/// Create a temporary home directory containing the given clio configuration
+fn home_with_config(config: &str) -> TempDir {
+    let home = TempDir::new().unwrap();
+    let config_dir = home.path().join(".clio");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(config_dir.join("config.toml"), config).unwrap();
+    home
+}
+
+/// Run clio with HOME pointed at a test directory
+fn clio_in(home: &Path) -> Command {
+    let mut cmd = Command::cargo_bin("clio").unwrap();
+    cmd.env("HOME", home);
+    cmd
+}
 
 #[test]
 fn test_clio_without_args_shows_help() {
@@ -37,80 +52,263 @@ fn test_clio_version_flag() {
 }
 
 #[test]
-fn test_pull_command() {
-    let _guard = CONFIG_MUTEX.lock().unwrap();
+fn test_trivial_commands_leave_home_untouched() {
+    let home = TempDir::new().unwrap();
+    for args in [
+        &["--help"][..],
+        &["--version"],
+        &["pull", "--help"],
+        &["config", "schema"],
+        &["audit", "tail"],
+        &["stats", "--me"],
+    ] {
+        clio_in(home.path()).args(args).assert().success();
+    }
+    assert!(!home.path().join(".clio").exists());
+}
+
+#[test]
+fn test_pull_requires_config() {
+    let home = TempDir::new().unwrap();
+    clio_in(home.path())
+        .arg("pull")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No configuration found"))
+        .stderr(predicate::str::contains("clio init"));
 
-    // Clean up any existing config
-    let config_dir = dirs::home_dir().unwrap().join(".clio");
-    let _ = std::fs::remove_dir_all(&config_dir);
+    // Nothing is written as a side effect
+    assert!(!home.path().join(".clio").exists());
+}
 
-    // Pull command should create config silently and work with default config
-    let mut cmd = Command::cargo_bin("clio").unwrap();
-    cmd.arg("pull")
+#[test]
+fn test_init_command() {
+    let home = TempDir::new().unwrap();
+    let config_path = home.path().join(".clio").join("config.toml");
+
+    clio_in(home.path())
+        .arg("--quiet")
+        .arg("init")
         .assert()
         .success()
-        .stdout(predicate::str::contains(
-            "Fetching content from 2 configured sources",
-        ));
+        .stderr(predicate::str::contains("Created"));
+    assert!(config_path.exists());
+
+    clio_in(home.path())
+        .args(["source", "list"])
+        .env_remove("SUPABASE_URL")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Julia Evans"));
 
-    // Verify config was created
-    assert!(config_dir.join("config.toml").exists());
+    // An existing configuration is kept unless forced
+    fs::write(&config_path, "[sources]\nrss = []\n").unwrap();
+    clio_in(home.path())
+        .arg("init")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--force"));
+    assert_eq!(
+        fs::read_to_string(&config_path).unwrap(),
+        "[sources]\nrss = []\n"
+    );
 
-    // Clean up after test
-    let _ = std::fs::remove_dir_all(&config_dir);
+    clio_in(home.path())
+        .args(["init", "--force"])
+        .assert()
+        .success();
+    assert!(
+        fs::read_to_string(&config_path)
+            .unwrap()
+            .contains("Julia Evans")
+    );
 }
 
 #[test]
-fn test_pull_command_with_quiet() {
-    let _guard = CONFIG_MUTEX.lock().unwrap();
+fn test_config_flag_and_env_choose_the_file() {
+    let home = TempDir::new().unwrap();
+    let work = home.path().join("profiles").join("work.toml");
 
-    // Clean up any existing config
-    let config_dir = dirs::home_dir().unwrap().join(".clio");
-    let _ = std::fs::remove_dir_all(&config_dir);
-
-    // Pull command should create config silently and work even with --quiet
-    let mut cmd = Command::cargo_bin("clio").unwrap();
-    cmd.arg("--quiet")
-        .arg("pull")
+    clio_in(home.path())
+        .args(["--config", work.to_str().unwrap(), "init"])
         .assert()
         .success()
-        .stdout(predicate::str::contains(
-            "Fetching content from 2 configured sources",
-        ));
+        .stderr(predicate::str::contains(work.display().to_string()));
+    assert!(work.exists());
+    assert!(!home.path().join(".clio").join("config.toml").exists());
 
-    // Verify config was created
-    assert!(config_dir.join("config.toml").exists());
+    clio_in(home.path())
+        .args(["source", "add", "Work", "https://work.example.com/feed.xml"])
+        .env("CLIO_CONFIG", &work)
+        .assert()
+        .success();
+    assert!(
+        fs::read_to_string(&work)
+            .unwrap()
+            .contains("https://work.example.com/feed.xml")
+    );
 
-    // Clean up after test
-    let _ = std::fs::remove_dir_all(&config_dir);
+    // The flag wins over the environment
+    clio_in(home.path())
+        .args(["config", "validate", "--config", "missing.toml"])
+        .env("CLIO_CONFIG", &work)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "No configuration found at missing.toml",
+        ));
 }
 
 #[test]
-fn test_list_command() {
+fn test_list_command_requires_storage() {
     let mut cmd = Command::cargo_bin("clio").unwrap();
-    cmd.arg("list")
+    cmd.env_remove("SUPABASE_URL")
+        .arg("list")
         .assert()
-        .success()
-        .stdout(predicate::str::contains("Listing items"));
+        .failure()
+        .stderr(predicate::str::contains("SUPABASE_URL"));
 }
 
 #[test]
-fn test_open_command_without_id() {
+fn test_read_and_unread_commands() {
     let mut cmd = Command::cargo_bin("clio").unwrap();
-    cmd.arg("open")
+    cmd.arg("read")
         .assert()
         .failure()
         .stderr(predicate::str::contains("required"));
+
+    let mut cmd = Command::cargo_bin("clio").unwrap();
+    cmd.env_remove("SUPABASE_URL")
+        .args(["unread", "6f1c2a9e", "0b5d"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("SUPABASE_URL"));
 }
 
 #[test]
-fn test_open_command_with_id() {
+fn test_star_requires_storage() {
     let mut cmd = Command::cargo_bin("clio").unwrap();
-    cmd.arg("open")
-        .arg("test-id")
+    cmd.env_remove("SUPABASE_URL")
+        .args(["star", "6f1c2a9e"])
         .assert()
-        .success()
-        .stdout(predicate::str::contains("Opening item test-id"));
+        .failure()
+        .stderr(predicate::str::contains("SUPABASE_URL"));
+}
+
+#[test]
+fn test_status_requires_storage() {
+    let home = home_with_config("[sources]\nrss = []\n");
+    clio_in(home.path())
+        .env_remove("SUPABASE_URL")
+        .args(["status", "--total"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("SUPABASE_URL"));
+}
+
+#[test]
+fn test_open_command_without_id_picks_from_storage() {
+    let mut cmd = Command::cargo_bin("clio").unwrap();
+    cmd.env_remove("SUPABASE_URL")
+        .arg("open")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("SUPABASE_URL"));
+}
+
+#[test]
+fn test_open_command_requires_storage() {
+    let mut cmd = Command::cargo_bin("clio").unwrap();
+    cmd.env_remove("SUPABASE_URL")
+        .arg("open")
+        .arg("6f1c2a9e")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("SUPABASE_URL"));
+}
+
+#[test]
+fn test_open_unread_requires_storage() {
+    let mut cmd = Command::cargo_bin("clio").unwrap();
+    cmd.env_remove("SUPABASE_URL")
+        .args(["open", "--unread", "--source", "HN", "--limit", "5"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("SUPABASE_URL"));
+}
+
+#[test]
+fn test_copy_requires_storage() {
+    let mut cmd = Command::cargo_bin("clio").unwrap();
+    cmd.env_remove("SUPABASE_URL")
+        .args(["copy", "item-123", "--markdown"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("SUPABASE_URL"));
+}
+
+#[test]
+fn test_save_requires_destination() {
+    let home = home_with_config(
+        r#"
+[[sources.rss]]
+name = "Hacker News"
+url = "https://hnrss.org/frontpage"
+"#,
+    );
+    clio_in(home.path())
+        .env_remove("SUPABASE_URL")
+        .args(["save", "item-123"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "No read-later destination configured",
+        ));
+
+    fs::write(
+        home.path().join(".clio/config.toml"),
+        r#"
+[[sources.rss]]
+name = "Hacker News"
+url = "https://hnrss.org/frontpage"
+
+[[read_later]]
+name = "notes"
+file = "later.md"
+"#,
+    )
+    .unwrap();
+    clio_in(home.path())
+        .env_remove("SUPABASE_URL")
+        .args(["save", "item-123", "--to", "notes"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("SUPABASE_URL"));
+}
+
+#[test]
+fn test_color_control() {
+    let mut cmd = Command::cargo_bin("clio").unwrap();
+    cmd.env_remove("SUPABASE_URL")
+        .args(["show", "item-123", "--color", "always"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("\u{1b}["))
+        .stderr(predicate::str::contains("SUPABASE_URL"));
+
+    // Output that is not a terminal is left plain, as is any with --color=never
+    for args in [
+        &["show", "item-123"][..],
+        &["show", "item-123", "--color", "never"],
+    ] {
+        let mut cmd = Command::cargo_bin("clio").unwrap();
+        cmd.env_remove("SUPABASE_URL")
+            .args(args)
+            .assert()
+            .failure()
+            .stderr(predicate::str::starts_with("Error: "))
+            .stderr(predicate::str::contains("\u{1b}[").not());
+    }
 }
 
 #[test]
@@ -155,11 +353,1437 @@ fn test_invalid_command() {
 #[test]
 fn test_global_flags_order() {
     // Global flags should work before the subcommand
-    // Using 'list' instead of 'pull' since pull requires config
+    // Using 'config schema' since it needs neither config nor storage
     let mut cmd = Command::cargo_bin("clio").unwrap();
-    cmd.arg("--quiet").arg("list").assert().success();
+    cmd.args(["--quiet", "config", "schema"]).assert().success();
 
     // Global flags should also work after the subcommand
     let mut cmd = Command::cargo_bin("clio").unwrap();
-    cmd.arg("list").arg("--quiet").assert().success();
+    cmd.args(["config", "schema", "--quiet"]).assert().success();
+
+    let mut cmd = Command::cargo_bin("clio").unwrap();
+    cmd.args(["--plain", "config", "schema"]).assert().success();
+}
+
+#[test]
+fn test_auth_rotate_rejects_invalid_key() {
+    let mut cmd = Command::cargo_bin("clio").unwrap();
+    cmd.env("SUPABASE_URL", "https://test.supabase.co")
+        .args(["auth", "rotate", "--new-key", "not-a-secret-key"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("must start with 'sb_secret_'"))
+        .stderr(predicate::str::contains("not-a-secret-key").not());
+}
+
+#[test]
+fn test_auth_rotate_requires_supabase_url() {
+    let mut cmd = Command::cargo_bin("clio").unwrap();
+    cmd.env_remove("SUPABASE_URL")
+        .args(["auth", "rotate", "--new-key", "sb_secret_new123"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Missing SUPABASE_URL"));
+}
+
+#[test]
+fn test_db_migrate_requires_supabase_url() {
+    let mut cmd = Command::cargo_bin("clio").unwrap();
+    cmd.env_remove("SUPABASE_URL")
+        .args(["db", "migrate", "--dry-run"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Missing SUPABASE_URL"));
+}
+
+#[test]
+fn test_db_reset_needs_confirmation() {
+    let mut cmd = Command::cargo_bin("clio").unwrap();
+    cmd.env("SUPABASE_URL", "http://127.0.0.1:9")
+        .args(["db", "reset"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("pass --yes to confirm"));
+
+    let mut cmd = Command::cargo_bin("clio").unwrap();
+    cmd.env_remove("SUPABASE_URL")
+        .args(["db", "reset", "--yes"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Missing SUPABASE_URL"));
+}
+
+#[test]
+fn test_db_verify_requires_storage() {
+    let mut cmd = Command::cargo_bin("clio").unwrap();
+    cmd.env_remove("SUPABASE_URL")
+        .args(["db", "verify", "--repair", "--yes"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Missing SUPABASE_URL"));
+}
+
+#[test]
+fn test_purge_all_needs_confirmation_and_storage() {
+    let home = home_with_config("[sources]\nrss = []\n");
+    let config_path = home.path().join(".clio").join("config.toml");
+
+    clio_in(home.path())
+        .args(["purge", "--all", "--with-config"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("pass --yes to confirm"));
+
+    // Nothing is deleted when storage cannot be reached
+    clio_in(home.path())
+        .env_remove("SUPABASE_URL")
+        .args(["purge", "--all", "--with-history", "--with-config", "--yes"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Missing SUPABASE_URL"));
+    assert!(config_path.exists());
+}
+
+#[test]
+fn test_source_rename_without_storage() {
+    let config = "[[sources.rss]]\nname = \"HN\"\nurl = \"https://news.ycombinator.com/rss\"\n";
+    let home = home_with_config(config);
+    let config_path = home.path().join(".clio").join("config.toml");
+
+    clio_in(home.path())
+        .args(["sources", "rename", "Missing", "Other"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No source named 'Missing'"));
+
+    // Without storage there are no stored items to move
+    clio_in(home.path())
+        .env_remove("SUPABASE_URL")
+        .args(["sources", "rename", "HN", "Hacker News"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Storage unavailable"))
+        .stderr(predicate::str::contains("Renamed 'HN' to 'Hacker News'"));
+    assert_eq!(
+        fs::read_to_string(&config_path).unwrap(),
+        config.replace("\"HN\"", "\"Hacker News\"")
+    );
+}
+
+#[test]
+fn test_source_pause_and_resume() {
+    let home = home_with_config(
+        r#"
+# Read every morning
+[[sources.rss]]
+name = "Feed A"
+url = "https://example.com/a.xml"
+
+[[sources.rss]]
+name = "Feed B"
+url = "https://example.com/b.xml"
+"#,
+    );
+
+    clio_in(home.path())
+        .args(["sources", "pause", "Feed A"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Paused source 'Feed A'"));
+
+    let saved = fs::read_to_string(home.path().join(".clio/config.toml")).unwrap();
+    assert!(saved.contains("paused = true"));
+    // The rest of the file is kept as written
+    assert!(saved.contains("# Read every morning"));
+
+    clio_in(home.path())
+        .arg("pull")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Fetching content from 1 configured sources",
+        ))
+        .stderr(predicate::str::contains("Feed A").not())
+        .stderr(predicate::str::contains("Skipping 1 paused source(s)"));
+
+    clio_in(home.path())
+        .args(["sources", "resume", "Feed A"])
+        .assert()
+        .success();
+
+    clio_in(home.path())
+        .arg("pull")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Fetching content from 2 configured sources",
+        ));
+}
+
+#[test]
+fn test_source_import_resolves_conflicts() {
+    let home = home_with_config(
+        r#"
+# Read every morning
+[[sources.rss]]
+name = "Feed A"
+url = "https://example.com/a.xml"
+tags = ["news"]
+"#,
+    );
+    let opml = home.path().join("feeds.opml");
+    fs::write(
+        &opml,
+        r#"<opml version="2.0"><body>
+  <outline text="Tech">
+    <outline type="rss" text="Renamed A" xmlUrl="https://example.com/a.xml"/>
+    <outline type="rss" text="Feed B" xmlUrl="https://example.com/b.xml"/>
+  </outline>
+</body></opml>"#,
+    )
+    .unwrap();
+
+    // Without a terminal, a conflict needs a policy
+    clio_in(home.path())
+        .args(["sources", "import"])
+        .arg(&opml)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--on-conflict"));
+    let config = home.path().join(".clio/config.toml");
+    assert!(!fs::read_to_string(&config).unwrap().contains("Feed B"));
+
+    clio_in(home.path())
+        .args(["sources", "import", "--on-conflict", "merge"])
+        .arg(&opml)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Added 1 sources, updated 1, kept 0 unchanged; 0 already subscribed",
+        ));
+    let saved = fs::read_to_string(&config).unwrap();
+    assert!(saved.contains("# Read every morning"));
+    assert!(saved.contains("name = \"Feed A\""));
+    assert!(saved.contains("tags = [\"news\", \"Tech\"]"));
+    assert!(saved.contains("name = \"Feed B\""));
+
+    clio_in(home.path())
+        .args(["sources", "import", "--on-conflict", "keep"])
+        .arg(&opml)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Added 0 sources, updated 0, kept 1 unchanged; 1 already subscribed",
+        ));
+}
+
+#[test]
+fn test_watch_needs_sources() {
+    let home = home_with_config("[sources]\n");
+
+    clio_in(home.path())
+        .arg("watch")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No active sources to watch"));
+
+    let home = home_with_config(
+        r#"
+[[sources.rss]]
+name = "Feed A"
+url = "https://example.com/a.xml"
+"#,
+    );
+    clio_in(home.path())
+        .args(["watch", "--source", "Missing*"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "No active source matches 'Missing*'",
+        ));
+}
+
+#[test]
+fn test_source_remove_requires_confirmation() {
+    let home = home_with_config(
+        r#"
+[[sources.rss]]
+name = "Feed A"
+url = "https://example.com/a.xml"
+
+[[sources.rss]]
+name = "Feed B"
+url = "https://example.com/b.xml"
+"#,
+    );
+
+    // Not a terminal, so there is nobody to ask
+    clio_in(home.path())
+        .args(["sources", "remove", "Feed A"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--yes"));
+    let saved = fs::read_to_string(home.path().join(".clio/config.toml")).unwrap();
+    assert!(saved.contains("Feed A"));
+
+    clio_in(home.path())
+        .args(["sources", "remove", "Feed A", "--yes"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Removed source 'Feed A'"));
+    let saved = fs::read_to_string(home.path().join(".clio/config.toml")).unwrap();
+    assert!(!saved.contains("Feed A"));
+    assert!(saved.contains("Feed B"));
+
+    clio_in(home.path())
+        .args(["sources", "remove", "Feed A", "--yes"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No source named 'Feed A'"));
+
+    clio_in(home.path())
+        .args(["audit", "tail", "--plain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "sources remove | remove source (1) | Feed A",
+        ));
+}
+
+#[test]
+fn test_audit_tail_when_empty() {
+    let home = home_with_config("[sources]\nrss = []\n");
+
+    clio_in(home.path())
+        .args(["audit", "tail"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("No changes recorded yet"));
+}
+
+#[test]
+fn test_source_pause_unknown_name() {
+    let home = home_with_config(
+        r#"
+[[sources.rss]]
+name = "Feed A"
+url = "https://example.com/a.xml"
+"#,
+    );
+
+    clio_in(home.path())
+        .args(["sources", "pause", "Nope"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No source named 'Nope'"));
+}
+
+#[test]
+fn test_import_reports_unreadable_export() {
+    let home = home_with_config("[sources]\nrss = []\n");
+
+    clio_in(home.path())
+        .args(["import", "miniflux"])
+        .arg(home.path().join("missing.json"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to read"));
+}
+
+#[test]
+fn test_fetch_enclosures_unknown_source() {
+    let home = home_with_config(
+        r#"
+[[sources.rss]]
+name = "Feed A"
+url = "https://example.com/a.xml"
+"#,
+    );
+
+    clio_in(home.path())
+        .args(["fetch-enclosures", "--source", "Nope"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No active source named 'Nope'"));
+}
+
+#[test]
+fn test_export_rejects_invalid_filter() {
+    let home = home_with_config("[sources]\nrss = []\n");
+
+    clio_in(home.path())
+        .args(["export", "--ics", "--filter", "events"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expected tag:NAME or source:NAME"));
+}
+
+#[test]
+fn test_export_bibtex_output_given_once() {
+    let home = home_with_config("[sources]\nrss = []\n");
+
+    clio_in(home.path())
+        .args(["export", "--bibtex", "papers.bib", "-o", "other.bib"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "either after the format or with --output",
+        ));
+
+    clio_in(home.path())
+        .env_remove("SUPABASE_URL")
+        .args(["export", "--bibtex", "--starred"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("SUPABASE_URL"));
+}
+
+#[test]
+fn test_top_with_no_recent_items() {
+    let home = home_with_config("[sources]\nrss = []\n");
+
+    clio_in(home.path())
+        .args(["top", "--hours", "48"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Nothing published in the last 48h",
+        ))
+        .stdout(predicate::str::is_empty());
+}
+
+#[test]
+fn test_pull_lists_synthetic_sources() {
+    let home = home_with_config(
+        r#"
+[[sources.synthetic]]
+name = "Demo"
+items = 50
+"#,
+    );
+
+    clio_in(home.path())
+        .arg("pull")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Fetching content from 1 configured sources",
+        ))
+        .stderr(predicate::str::contains("Demo (synthetic, 50 items)"))
+        .stderr(predicate::str::contains("Fetched 50 items from 1 of 1 sources"))
+        // Progress and summaries stay out of piped output
+        .stdout(predicate::str::is_empty());
+}
+
+#[test]
+fn test_pull_selected_sources() {
+    let home = home_with_config(
+        r#"
+[[sources.synthetic]]
+name = "Demo One"
+items = 3
+
+[[sources.synthetic]]
+name = "Demo Two"
+items = 4
+
+[[sources.synthetic]]
+name = "Other"
+items = 5
+"#,
+    );
+
+    clio_in(home.path())
+        .args(["pull", "--source", "Demo*"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Fetching 2 of 3 active sources, matching Demo*",
+        ))
+        .stderr(predicate::str::contains(
+            "Fetched 7 items from 2 of 2 sources",
+        ))
+        .stderr(predicate::str::contains("Other").not());
+
+    clio_in(home.path())
+        .args(["pull", "--source", "Other", "--source", "Misspelled"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "No active source matches 'Misspelled'",
+        ));
+}
+
+#[test]
+fn test_no_write_mode() {
+    let home = TempDir::new().unwrap();
+    clio_in(home.path())
+        .args(["--no-write", "init"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Cannot create the configuration: filesystem writes are disabled by --no-write",
+        ));
+    assert!(!home.path().join(".clio").exists());
+
+    let home = home_with_config(
+        r#"
+[[sources.synthetic]]
+name = "Demo"
+items = 5
+"#,
+    );
+    clio_in(home.path())
+        .args(["pull", "--no-write", "--trace", "trace.json"])
+        .current_dir(home.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Cannot write the trace"));
+    assert!(!home.path().join("trace.json").exists());
+
+    clio_in(home.path())
+        .args(["pull", "--no-write"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Demo (synthetic, 5 items)"));
+    let written: Vec<_> = fs::read_dir(home.path().join(".clio"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(written, ["config.toml"]);
+}
+
+#[test]
+fn test_prune_requires_storage() {
+    let home = TempDir::new().unwrap();
+    clio_in(home.path())
+        .env_remove("SUPABASE_URL")
+        .args(["prune", "--older-than", "30d", "--keep-starred", "--yes"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("SUPABASE_URL"));
+
+    clio_in(home.path())
+        .args(["prune", "--keep-starred"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--older-than"));
+}
+
+#[test]
+fn test_prune_configured_retention() {
+    let home = home_with_config("[sources]\n");
+    clio_in(home.path())
+        .args(["prune", "--dry-run"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No retention rules configured"));
+
+    let home = home_with_config(
+        r#"
+        [[sources.rss]]
+        name = "Wire"
+        url = "https://example.com/wire.xml"
+        tags = ["news"]
+
+        [[retention]]
+        filter = "tag:news"
+        keep_days = 7
+        "#,
+    );
+    clio_in(home.path())
+        .env_remove("SUPABASE_URL")
+        .args(["prune", "--dry-run"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("SUPABASE_URL"));
+}
+
+#[test]
+fn test_serve_requires_storage() {
+    let home = home_with_config("[sources]\n");
+    clio_in(home.path())
+        .env_remove("SUPABASE_URL")
+        .args(["serve", "--port", "0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("SUPABASE_URL"));
+}
+
+#[test]
+fn test_configuration_from_environment() {
+    let home = TempDir::new().unwrap();
+    clio_in(home.path())
+        .env_remove("SUPABASE_URL")
+        .env(
+            "CLIO_SOURCES",
+            r#"[{"name": "HN", "url": "https://news.ycombinator.com/rss"}]"#,
+        )
+        .env("CLIO_SETTINGS_ARCHIVE_EXPIRED", "true")
+        .args(["source", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("HN"));
+    assert!(!home.path().join(".clio").exists());
+
+    // The environment wins over the file
+    let home = home_with_config(
+        r#"
+[[sources.synthetic]]
+name = "Demo"
+items = 5
+"#,
+    );
+    clio_in(home.path())
+        .env("CLIO_SETTINGS_ARCHIVE_EXPIRED", "true")
+        .arg("pull")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("will be archived"));
+
+    clio_in(home.path())
+        .env("CLIO_SETTINGS_TIMEOUT", "20")
+        .arg("pull")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Fetched 5 items"));
+
+    // Sources of other kinds need no file either
+    let home = TempDir::new().unwrap();
+    clio_in(home.path())
+        .env_remove("SUPABASE_URL")
+        .env(
+            "CLIO_SOURCES_SYNTHETIC",
+            r#"[{"name": "Demo", "items": 5}]"#,
+        )
+        .env("CLIO_MUTES", r#"[{"keyword": "demo"}]"#)
+        .args(["source", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Demo"));
+    assert!(!home.path().join(".clio").exists());
+}
+
+#[test]
+fn test_health_fails_without_storage() {
+    let home = TempDir::new().unwrap();
+    clio_in(home.path())
+        .env_remove("SUPABASE_URL")
+        .args(["health", "--plain"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("FAILED | storage | "))
+        .stdout(predicate::str::contains("FAILED | last pull | "))
+        .stderr(predicate::str::contains("clio is unhealthy"));
+}
+
+#[test]
+fn test_config_validate() {
+    let home = home_with_config(
+        r#"
+[[sources.rss]]
+name = "A"
+url = "https://example.com/feed.xml"
+
+[[sources.rss]]
+name = "B"
+url = "https://example.com/feed.xml"
+"#,
+    );
+    clio_in(home.path())
+        .args(["config", "validate", "--plain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "WARNING | B | Duplicate URL https://example.com/feed.xml, also used by 'A'",
+        ))
+        .stderr(predicate::str::contains(
+            "Configuration is valid: 2 source(s)",
+        ));
+
+    let home = home_with_config(
+        r#"
+[[sources.rss]]
+name = "A"
+url = "ftp://example.com/feed.xml"
+
+[[sources.rss]]
+name = "A"
+url = "https://example.com/other.xml"
+"#,
+    );
+    clio_in(home.path())
+        .args(["config", "validate"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("✗ A: Invalid URL scheme 'ftp'"))
+        .stdout(predicate::str::contains("✗ A: Duplicate source name: A"))
+        .stderr(predicate::str::contains(
+            "Configuration has 2 error(s) and 0 warning(s)",
+        ));
+}
+
+/// Run `clio config edit` with `editor` standing in for the user
+fn config_edit(home: &Path, editor: &str) -> assert_cmd::assert::Assert {
+    clio_in(home)
+        .env_remove("VISUAL")
+        .env("EDITOR", editor)
+        .args(["config", "edit"])
+        .assert()
+}
+
+#[test]
+fn test_config_edit_reports_source_changes() {
+    let home = home_with_config(
+        r#"
+[[sources.rss]]
+name = "Feed A"
+url = "https://example.com/a.xml"
+"#,
+    );
+
+    config_edit(home.path(), "sed -i 's/Feed A/Feed B/'")
+        .success()
+        .stderr(predicate::str::contains("+ Feed B"))
+        .stderr(predicate::str::contains("- Feed A"))
+        .stderr(predicate::str::contains(
+            "Configuration saved: 1 source(s) added, 1 removed",
+        ));
+    let config = fs::read_to_string(home.path().join(".clio/config.toml")).unwrap();
+    assert!(config.contains("Feed B"));
+}
+
+#[test]
+fn test_config_edit_creates_from_example() {
+    let home = TempDir::new().unwrap();
+
+    config_edit(home.path(), "true")
+        .success()
+        .stderr(predicate::str::contains("from the example configuration"))
+        .stderr(predicate::str::contains("0 source(s) added, 0 removed"));
+    assert!(home.path().join(".clio/config.toml").exists());
+}
+
+#[test]
+fn test_config_edit_reports_errors() {
+    let home = home_with_config(
+        r#"
+[[sources.rss]]
+name = "Feed A"
+url = "https://example.com/a.xml"
+"#,
+    );
+
+    config_edit(home.path(), "sed -i 's/https/ftp/'")
+        .failure()
+        .stdout(predicate::str::contains(
+            "✗ Feed A: Invalid URL scheme 'ftp'",
+        ))
+        .stderr(predicate::str::contains("saved with 1 error(s)"));
+    config_edit(home.path(), "false")
+        .failure()
+        .stderr(predicate::str::contains("Editor 'false' exited"));
+}
+
+#[test]
+fn test_config_validate_network() {
+    let mut server = mockito::Server::new();
+    let _missing = server.mock("GET", "/missing.xml").with_status(404).create();
+    let home = home_with_config(&format!(
+        r#"
+[settings]
+allow_private_networks = true
+
+[[sources.rss]]
+name = "Gone"
+url = "{}/missing.xml"
+"#,
+        server.url()
+    ));
+
+    clio_in(home.path())
+        .args(["config", "validate", "--plain"])
+        .assert()
+        .success();
+    clio_in(home.path())
+        .args(["config", "validate", "--network", "--plain"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("ERROR | Gone | "))
+        .stdout(predicate::str::contains("404"));
+}
+
+#[test]
+fn test_preview_prints_feed_without_subscribing() {
+    let mut server = mockito::Server::new();
+    let _feed = server
+        .mock("GET", "/feed.xml")
+        .with_header("content-type", "application/rss+xml")
+        .with_body(
+            r#"<?xml version="1.0"?>
+<rss version="2.0"><channel><title>Example Blog</title><link>https://example.com</link>
+<item><title>First post</title><link>https://example.com/1</link></item>
+<item><title>Second post</title><link>https://example.com/2</link></item>
+<item><title>Draft without a link</title></item>
+</channel></rss>"#,
+        )
+        .create();
+    let config = "[settings]\nallow_private_networks = true\n\n[sources]\n";
+    let home = home_with_config(config);
+
+    clio_in(home.path())
+        .args(["preview", &format!("{}/feed.xml", server.url()), "-n", "1"])
+        .args(["--plain"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("plain http"))
+        .stderr(predicate::str::contains(
+            "1 entries were left out for lacking a title or link",
+        ))
+        .stdout(predicate::str::contains("FEED | Example Blog | "))
+        .stdout(predicate::str::contains("| 2 items"))
+        .stdout(predicate::str::contains("ITEM | First post | "))
+        .stdout(predicate::str::contains("Second post").not());
+    assert_eq!(
+        fs::read_to_string(home.path().join(".clio/config.toml")).unwrap(),
+        config
+    );
+}
+
+#[test]
+fn test_discover_lists_and_adds_advertised_feeds() {
+    let mut server = mockito::Server::new();
+    let _page = server
+        .mock("GET", "/")
+        .with_header("content-type", "text/html")
+        .with_body(
+            r#"<html><head>
+<link rel="alternate" type="application/rss+xml" title="Posts" href="/posts.xml">
+<link rel="alternate" type="application/atom+xml" title="Comments" href="/comments.atom">
+</head></html>"#,
+        )
+        .create();
+    let config = "[settings]\nallow_private_networks = true\n\n[sources]\n";
+    let home = home_with_config(config);
+
+    clio_in(home.path())
+        .args(["discover", &server.url(), "--plain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("FEED | 1 | Posts | "))
+        .stdout(predicate::str::contains("FEED | 2 | Comments | "));
+    assert_eq!(
+        fs::read_to_string(home.path().join(".clio/config.toml")).unwrap(),
+        config
+    );
+
+    clio_in(home.path())
+        .args(["discover", &server.url(), "--add"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("pass --feed N to choose"));
+    clio_in(home.path())
+        .args(["discover", &server.url(), "--add", "--feed", "3"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No feed number 3"));
+
+    clio_in(home.path())
+        .args(["discover", &server.url(), "--add", "--feed", "2"])
+        .args(["--name", "Blog comments"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Added source 'Blog comments'"));
+    let saved = fs::read_to_string(home.path().join(".clio/config.toml")).unwrap();
+    assert!(saved.contains("name = \"Blog comments\""));
+    assert!(saved.contains(&format!("url = \"{}/comments.atom\"", server.url())));
+}
+
+#[test]
+fn test_stats_requires_storage() {
+    let home = home_with_config("[sources]\n");
+    clio_in(home.path())
+        .env_remove("SUPABASE_URL")
+        .arg("stats")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("SUPABASE_URL"));
+}
+
+#[test]
+fn test_search_requires_storage() {
+    let home = home_with_config("[sources]\nrss = []\n");
+
+    clio_in(home.path())
+        .env_remove("SUPABASE_URL")
+        .args(["search", "rust", "--unread", "--since", "7d"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("SUPABASE_URL"));
+}
+
+#[test]
+fn test_search_all_profiles_reads_each_profile() {
+    let home = home_with_config("[sources]\nrss = []\n");
+    let work = home.path().join(".clio/work.toml");
+    fs::write(&work, "[sources]\nrss = []\n").unwrap();
+
+    clio_in(home.path())
+        .env_remove("SUPABASE_URL")
+        .args(["search", "rust", "--all-profiles"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("SUPABASE_URL"));
+
+    fs::write(&work, "[sources\n").unwrap();
+    clio_in(home.path())
+        .env_remove("SUPABASE_URL")
+        .args(["search", "rust", "--all-profiles"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Profile 'work'"));
+}
+
+#[test]
+fn test_db_rotate_needs_an_age() {
+    let home = home_with_config("[sources]\n");
+    clio_in(home.path())
+        .args(["db", "rotate"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--older-than DAYS"));
+
+    let home = home_with_config("[settings]\ncold_storage_after_days = 365\n\n[sources]\n");
+    clio_in(home.path())
+        .env_remove("SUPABASE_URL")
+        .args(["db", "rotate"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("SUPABASE_URL"));
+}
+
+#[test]
+fn test_search_rejects_invalid_since() {
+    clio_in(TempDir::new().unwrap().path())
+        .args(["search", "rust", "--since", "yesterday"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expected a date like 2024-05-01"));
+}
+
+#[test]
+fn test_top_replays_cassette() {
+    let home = home_with_config(
+        r#"
+[[sources.rss]]
+name = "Hacker News"
+url = "https://hnrss.org/frontpage"
+"#,
+    );
+    let cassette = home.path().join("cassette.json");
+    let feed = r#"<?xml version="1.0"?>
+<rss version="2.0"><channel><title>HN</title>
+<item><title>Replayed story</title><link>https://example.com/story</link>
+<description>Points: 42</description><pubDate>Wed, 01 May 2024 12:00:00 +0000</pubDate></item>
+</channel></rss>"#;
+    let contents = serde_json::json!({
+        "interactions": [{"url": "https://hnrss.org/frontpage", "status": 200, "body": feed}]
+    });
+    fs::write(&cassette, contents.to_string()).unwrap();
+
+    clio_in(home.path())
+        .env("CLIO_REPLAY", "1")
+        .env("CLIO_CASSETTE", &cassette)
+        .args(["top", "--hours", "1000000", "--plain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("RANK 1 | Replayed story"))
+        .stdout(predicate::str::contains("42 points"));
+
+    clio_in(home.path())
+        .env("CLIO_REPLAY", "1")
+        .env("CLIO_RECORD", "1")
+        .args(["top"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be set at the same time"));
+}
+
+#[test]
+fn test_mute_drops_items_at_pull() {
+    let home = home_with_config(
+        r#"
+[[sources.rss]]
+name = "Hacker News"
+url = "https://hnrss.org/frontpage"
+"#,
+    );
+    let cassette = home.path().join("cassette.json");
+    let feed = r#"<?xml version="1.0"?>
+<rss version="2.0"><channel><title>HN</title>
+<item><title>Crypto exchange collapses</title><link>https://example.com/crypto</link></item>
+<item><title>Rust 2.0 announced</title><link>https://example.com/rust</link></item>
+</channel></rss>"#;
+    let contents = serde_json::json!({
+        "interactions": [{"url": "https://hnrss.org/frontpage", "status": 200, "body": feed}]
+    });
+    fs::write(&cassette, contents.to_string()).unwrap();
+
+    clio_in(home.path())
+        .args(["mute", "add", "crypto"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Muted 'crypto'"));
+    clio_in(home.path())
+        .args(["mute", "add", "CRYPTO"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("already muted"));
+    clio_in(home.path())
+        .args(["mute", "list"])
+        .assert()
+        .success()
+        .stdout("crypto\n");
+
+    clio_in(home.path())
+        .env("CLIO_REPLAY", "1")
+        .env("CLIO_CASSETTE", &cassette)
+        .args(["pull", "--json"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Muted 1 item(s)"))
+        .stdout(predicate::str::contains("Rust 2.0 announced"))
+        .stdout(predicate::str::contains("Crypto exchange").not());
+
+    clio_in(home.path())
+        .args(["mute", "remove", "Crypto"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Unmuted 'crypto'"));
+    clio_in(home.path())
+        .args(["mute", "remove", "crypto"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No mute rule for 'crypto'"));
+}
+
+#[test]
+fn test_diff_config_replays_both_configs() {
+    let home = home_with_config(
+        r#"
+[[sources.rss]]
+name = "Hacker News"
+url = "https://hnrss.org/frontpage"
+"#,
+    );
+    let proposed = home.path().join("new-config.toml");
+    fs::write(
+        &proposed,
+        r#"
+[[sources.rss]]
+name = "Hacker News"
+url = "https://hnrss.org/frontpage"
+
+[[sources.rss]]
+name = "Lobsters"
+url = "https://lobste.rs/rss"
+
+[[alerts]]
+keywords = ["replayed"]
+"#,
+    )
+    .unwrap();
+
+    clio_in(home.path())
+        .args(["diff-config", proposed.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No recorded feeds"));
+
+    let cassette = home.path().join(".clio").join("cassette.json");
+    let feed = r#"<?xml version="1.0"?>
+<rss version="2.0"><channel><title>HN</title>
+<item><title>Replayed story</title><link>https://example.com/story</link></item>
+<item><title>Another story</title><link>https://example.com/another</link></item>
+</channel></rss>"#;
+    let contents = serde_json::json!({
+        "interactions": [{"url": "https://hnrss.org/frontpage", "status": 200, "body": feed}]
+    });
+    fs::write(&cassette, contents.to_string()).unwrap();
+
+    clio_in(home.path())
+        .args(["diff-config", proposed.to_str().unwrap(), "--plain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ITEMS | 2 | 2\n"))
+        .stdout(predicate::str::contains("SOURCE | Lobsters | - | 0 | "))
+        .stdout(predicate::str::contains(
+            "No response for https://lobste.rs/rss",
+        ))
+        .stdout(predicate::str::contains(
+            "RULE | alerts: replayed | - | 1\n",
+        ))
+        .stdout(predicate::str::contains("Hacker News").not());
+}
+
+#[test]
+fn test_pull_writes_trace() {
+    let home = home_with_config(
+        r#"
+[[sources.rss]]
+name = "Blog"
+url = "https://example.com/feed.xml"
+"#,
+    );
+    let cassette = home.path().join("cassette.json");
+    let contents = serde_json::json!({
+        "interactions": [{
+            "url": "https://example.com/feed.xml",
+            "status": 200,
+            "body": "<rss version=\"2.0\"><channel><title>Blog</title></channel></rss>"
+        }]
+    });
+    fs::write(&cassette, contents.to_string()).unwrap();
+    let trace = home.path().join("trace.json");
+
+    clio_in(home.path())
+        .env("CLIO_REPLAY", "1")
+        .env("CLIO_CASSETTE", &cassette)
+        .arg("pull")
+        .arg("--trace")
+        .arg(&trace)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Wrote a trace of 1 requests"));
+
+    let saved: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&trace).unwrap()).unwrap();
+    let request = &saved["requests"][0];
+    assert_eq!(request["url"], "https://example.com/feed.xml");
+    assert_eq!(request["status"], 200);
+    assert_eq!(request["cache"], "replay");
+}
+
+#[test]
+fn test_pull_sends_grouped_alert() {
+    let home = home_with_config(
+        r#"
+[[sources.rss]]
+name = "Advisories"
+url = "https://example.com/feed.xml"
+
+[[alerts]]
+keywords = ["CVE", "security advisory"]
+channel = "terminal"
+"#,
+    );
+    let items: String = [
+        "CVE-2024-0001 in libfoo",
+        "Weekly notes",
+        "Security advisory: libbar",
+        "CVE-2024-0002 in libbaz",
+    ]
+    .iter()
+    .enumerate()
+    .map(|(i, title)| {
+        format!("<item><title>{title}</title><link>https://example.com/{i}</link></item>")
+    })
+    .collect();
+    let cassette = home.path().join("cassette.json");
+    let contents = serde_json::json!({
+        "interactions": [{
+            "url": "https://example.com/feed.xml",
+            "status": 200,
+            "body": format!("<rss version=\"2.0\"><channel><title>Feed</title>{items}</channel></rss>")
+        }]
+    });
+    fs::write(&cassette, contents.to_string()).unwrap();
+
+    let pull = || {
+        clio_in(home.path())
+            .env("CLIO_REPLAY", "1")
+            .env("CLIO_CASSETTE", &cassette)
+            .arg("pull")
+            .assert()
+            .success()
+    };
+    pull().stderr(
+        predicate::str::contains("3 new items matching CVE, security advisory")
+            .and(predicate::str::contains(
+                "Advisories: Security advisory: libbar",
+            ))
+            .and(predicate::str::contains("Weekly notes").not()),
+    );
+    assert!(home.path().join(".clio/alerts.json").exists());
+
+    // The same items do not alert twice
+    pull().stderr(predicate::str::contains("matching").not());
+}
+
+#[test]
+fn test_source_add() {
+    let home = home_with_config(
+        r#"
+[[sources.rss]]
+name = "Feed A"
+url = "https://example.com/a.xml"
+"#,
+    );
+
+    clio_in(home.path())
+        .args(["sources", "add", "Feed B", "https://example.com/b.xml"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Added source 'Feed B'"));
+    let saved = fs::read_to_string(home.path().join(".clio/config.toml")).unwrap();
+    assert!(saved.contains("https://example.com/b.xml"));
+
+    clio_in(home.path())
+        .args(["sources", "add", "Feed B", "https://example.com/c.xml"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Duplicate source name: Feed B"));
+
+    clio_in(home.path())
+        .args(["sources", "add", "Feed C", "file:///etc/passwd"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid URL scheme 'file'"));
+
+    // A loopback feed is refused by the network guard, so nothing is saved
+    clio_in(home.path())
+        .args([
+            "sources",
+            "add",
+            "Local",
+            "http://127.0.0.1:9/feed.xml",
+            "--fetch-now",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Not adding source 'Local'"));
+    let saved = fs::read_to_string(home.path().join(".clio/config.toml")).unwrap();
+    assert!(!saved.contains("Local"));
+}
+
+#[test]
+fn test_source_remove_purge_items_requires_storage() {
+    let home = home_with_config(
+        r#"
+[[sources.rss]]
+name = "Feed A"
+url = "https://example.com/a.xml"
+"#,
+    );
+
+    clio_in(home.path())
+        .env_remove("SUPABASE_URL")
+        .args(["sources", "remove", "Feed A", "--purge-items", "--yes"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("SUPABASE_URL"));
+
+    // The source stays configured when its items could not be deleted
+    let saved = fs::read_to_string(home.path().join(".clio/config.toml")).unwrap();
+    assert!(saved.contains("Feed A"));
+}
+
+#[test]
+fn test_source_list_without_storage() {
+    let home = home_with_config(
+        r#"
+[[sources.rss]]
+name = "Feed A"
+url = "https://example.com/a.xml"
+paused = true
+
+[[sources.synthetic]]
+name = "Demo"
+"#,
+    );
+
+    clio_in(home.path())
+        .env_remove("SUPABASE_URL")
+        .args(["sources", "list", "--plain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Feed A | https://example.com/a.xml | paused",
+        ))
+        .stdout(predicate::str::contains("Demo | synthetic, 20 items"))
+        .stderr(predicate::str::contains("Source health unavailable"));
+
+    // Paused sources are greyed out when colored
+    clio_in(home.path())
+        .env_remove("SUPABASE_URL")
+        .args(["sources", "list", "--color", "always"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\u{1b}[2mFeed A (https://example.com/a.xml)",
+        ))
+        .stdout(predicate::str::contains("\u{1b}[2m  paused"))
+        .stdout(predicate::str::contains("\nDemo (synthetic, 20 items)\n"));
+}
+
+#[test]
+fn test_source_show_without_storage() {
+    let home = home_with_config(
+        r#"
+[[sources.rss]]
+name = "Feed A"
+url = "https://example.com/a.xml"
+"#,
+    );
+
+    clio_in(home.path())
+        .env_remove("SUPABASE_URL")
+        .args(["sources", "show", "Feed A", "--plain"])
+        .assert()
+        .success()
+        .stdout("Feed A | https://example.com/a.xml\n")
+        .stderr(predicate::str::contains("Fetch history unavailable"));
+
+    clio_in(home.path())
+        .env_remove("SUPABASE_URL")
+        .args(["sources", "show", "Feed B"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No source named 'Feed B'"));
+}
+
+#[test]
+fn test_rules_export_import_and_test() {
+    let home = home_with_config(
+        r#"
+[[sources.rss]]
+name = "Feed A"
+url = "https://example.com/a.xml"
+
+[[alerts]]
+keywords = ["outage"]
+"#,
+    );
+    let rules = home.path().join("rules.toml");
+
+    clio_in(home.path())
+        .args(["rules", "export", rules.to_str().unwrap()])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Wrote 1 rules"));
+    assert_eq!(
+        fs::read_to_string(&rules).unwrap(),
+        "[[alerts]]\nkeywords = [\"outage\"]\nchannel = \"desktop\"\n"
+    );
+
+    // Importing the same rules again adds nothing
+    fs::write(
+        &rules,
+        "[[alerts]]\nkeywords = [\"outage\"]\n\n[[authors]]\nname = \"Julia Evans\"\nstar = true\n",
+    )
+    .unwrap();
+    clio_in(home.path())
+        .args(["rules", "import", rules.to_str().unwrap()])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Added 1 rules; 1 already configured",
+        ));
+    clio_in(home.path())
+        .args(["rules", "export"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("name = \"Julia Evans\""));
+
+    clio_in(home.path())
+        .args(["rules", "test"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Nothing has been pulled yet"));
+
+    fs::write(
+        home.path().join(".clio").join("last_pull.json"),
+        r#"{"pulled_at": "2025-03-01T08:00:00Z", "items": [
+            {"source_name": "Feed A", "title": "Outage in us-east", "link": "https://example.com/1"},
+            {"source_name": "Feed A", "title": "Release notes", "link": "https://example.com/2",
+             "authors": ["Julia Evans"]}
+        ]}"#,
+    )
+    .unwrap();
+    clio_in(home.path())
+        .args(["rules", "test", "--against", "last-pull", "--plain"])
+        .assert()
+        .success()
+        .stdout(
+            "RULE | [[authors]] #1 | Julia Evans | 1\n\
+             MATCH | [[authors]] #1 | Feed A | Release notes | https://example.com/2\n\
+             RULE | [[alerts]] #1 | outage | 1\n\
+             MATCH | [[alerts]] #1 | Feed A | Outage in us-east | https://example.com/1\n",
+        );
+}
+
+#[test]
+fn test_rules_stats_reads_the_hit_log() {
+    let home = home_with_config(
+        r#"
+[[sources.rss]]
+name = "Feed A"
+url = "https://example.com/a.xml"
+
+[[alerts]]
+keywords = ["outage"]
+
+[[alerts]]
+keywords = ["the"]
+"#,
+    );
+
+    clio_in(home.path())
+        .args(["rules", "stats", "--plain"])
+        .assert()
+        .success()
+        .stdout(
+            "[[alerts]] #1 | outage | 0 | 0 | 0 | never\n[[alerts]] #2 | the | 0 | 0 | 0 | never\n",
+        );
+
+    let now = chrono::Utc::now().to_rfc3339();
+    fs::write(
+        home.path().join(".clio").join("rule_hits.log"),
+        format!(
+            "{{\"at\":\"{now}\",\"items\":10,\"hits\":{{\"alerts: outage\":0,\"alerts: the\":9}}}}\n"
+        ),
+    )
+    .unwrap();
+    clio_in(home.path())
+        .args(["rules", "stats"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[[alerts]] #1 (outage)\n  0 of 10 items in 1 pulls · last matched never · never matched",
+        ))
+        .stdout(predicate::str::contains("9 of 10 items in 1 pulls"))
+        .stdout(predicate::str::contains("matches most items"));
+}
+
+#[test]
+fn test_stats_me_counts_pulls() {
+    let home = home_with_config("[sources]\nrss = []\n");
+
+    clio_in(home.path())
+        .args(["stats", "--me"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No usage recorded yet"));
+
+    clio_in(home.path()).arg("pull").assert().success();
+    clio_in(home.path()).arg("pull").assert().success();
+
+    clio_in(home.path())
+        .args(["stats", "--me", "--plain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "2 pulls (2 in the last 7 days), 0 items read",
+        ))
+        .stdout(predicate::str::contains("0 read"));
 }
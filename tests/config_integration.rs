@@ -115,7 +115,11 @@ fn test_config_with_100_sources() {
     }
 
     let config = Config {
-        sources: Sources { rss: sources },
+        sources: Sources {
+            rss: sources,
+            ..Default::default()
+        },
+        ..Default::default()
     };
 
     assert!(config.validate().is_ok());
@@ -148,7 +152,9 @@ fn test_config_with_various_url_formats() {
                     "https://example.com/feed?format=rss".to_string(),
                 ),
             ],
+            ..Default::default()
         },
+        ..Default::default()
     };
 
     assert!(config.validate().is_ok());
@@ -167,7 +173,9 @@ fn test_config_with_invalid_schemes() {
         let config = Config {
             sources: Sources {
                 rss: vec![RssSource::new(name.to_string(), url.to_string())],
+                ..Default::default()
             },
+            ..Default::default()
         };
 
         let result = config.validate();
@@ -195,7 +203,9 @@ fn test_config_serialization_roundtrip() {
                     "https://example.com/feed2.xml".to_string(),
                 ),
             ],
+            ..Default::default()
         },
+        ..Default::default()
     };
 
     let serialized = toml::to_string(&original).unwrap();
@@ -226,7 +236,9 @@ fn test_config_with_unicode_names() {
                     "https://example.com/feed.xml".to_string(),
                 ),
             ],
+            ..Default::default()
         },
+        ..Default::default()
     };
 
     assert!(config.validate().is_ok());
@@ -251,7 +263,9 @@ fn test_config_with_whitespace_names() {
                     "https://example.com/feed3.xml".to_string(),
                 ),
             ],
+            ..Default::default()
         },
+        ..Default::default()
     };
 
     assert!(config.validate().is_ok());
@@ -2,7 +2,7 @@ use std::env;
 use std::sync::Arc;
 
 use clio::config::SupabaseConfig;
-use clio::database::{Database, SupabaseClient};
+use clio::database::{Database, MIGRATIONS, SupabaseClient};
 use clio::error::ClioError;
 use serial_test::serial;
 
@@ -13,6 +13,8 @@ struct IntegrationMockClient {
     queries: std::sync::Mutex<Vec<String>>,
     table_exists_count: std::sync::Mutex<usize>,
     error_on_query: Option<String>,
+    /// Newest migration recorded in the schema
+    schema_version: u32,
 }
 
 impl IntegrationMockClient {
@@ -22,6 +24,14 @@ impl IntegrationMockClient {
             queries: std::sync::Mutex::new(Vec::new()),
             table_exists_count: std::sync::Mutex::new(0),
             error_on_query: None,
+            schema_version: 0,
+        }
+    }
+
+    fn at_version(url: String, schema_version: u32) -> Self {
+        Self {
+            schema_version,
+            ..Self::new(url)
         }
     }
 
@@ -31,6 +41,7 @@ impl IntegrationMockClient {
             queries: std::sync::Mutex::new(Vec::new()),
             table_exists_count: std::sync::Mutex::new(0),
             error_on_query: Some(error_msg),
+            schema_version: 0,
         }
     }
 
@@ -42,21 +53,29 @@ impl IntegrationMockClient {
 
 impl SupabaseClient for IntegrationMockClient {
     fn execute(&self, query: &str) -> Result<(), ClioError> {
-        if let Some(ref error_msg) = self.error_on_query {
-            if query.contains("CREATE TABLE") || query.contains("SELECT 1") {
-                return Err(ClioError::Database(error_msg.clone()));
-            }
+        if let Some(ref error_msg) = self.error_on_query
+            && (query.contains("CREATE TABLE") || query.contains("SELECT 1"))
+        {
+            return Err(ClioError::Database(error_msg.clone()));
         }
 
         self.queries.lock().unwrap().push(query.to_string());
         Ok(())
     }
 
+    fn query(&self, query: &str) -> Result<Vec<serde_json::Value>, ClioError> {
+        self.execute(query)?;
+        if query.contains("FROM schema_migrations") {
+            return Ok(vec![serde_json::json!({"version": self.schema_version})]);
+        }
+        Ok(Vec::new())
+    }
+
     fn table_exists(&self, _table_name: &str) -> Result<bool, ClioError> {
-        if let Some(ref error_msg) = self.error_on_query {
-            if error_msg.contains("table_check") {
-                return Err(ClioError::Database(error_msg.clone()));
-            }
+        if let Some(ref error_msg) = self.error_on_query
+            && error_msg.contains("table_check")
+        {
+            return Err(ClioError::Database(error_msg.clone()));
         }
 
         let mut count = self.table_exists_count.lock().unwrap();
@@ -90,7 +109,11 @@ fn test_database_integration_with_mock_client() {
     let queries = mock_client.get_queries();
     assert!(!queries.is_empty(), "Should have executed queries");
     assert!(
-        queries[0].contains("CREATE TABLE IF NOT EXISTS items"),
+        queries[0].contains("CREATE TABLE IF NOT EXISTS schema_migrations"),
+        "Should create the table recording migrations"
+    );
+    assert!(
+        queries[1].contains("CREATE TABLE IF NOT EXISTS items"),
         "Should create items table"
     );
 }
@@ -146,22 +169,24 @@ fn test_database_integration_schema_already_exists() {
         secret_key: "sb_secret_test123".to_string(),
     };
 
-    let mock_client = Arc::new(IntegrationMockClient::new(
+    let mock_client = Arc::new(IntegrationMockClient::at_version(
         "https://test.supabase.co".to_string(),
+        MIGRATIONS.len() as u32,
     ));
 
     // Call table_exists once to make it return true on next call
-    let _ = mock_client.table_exists("items");
+    let _ = mock_client.table_exists("schema_migrations");
 
     let db = Database::with_client(config, mock_client.clone());
 
-    // Initialize schema when table already exists
+    // Initialize schema when every migration has been applied
     let result = db.init_schema();
     assert!(result.is_ok(), "Should succeed even if table exists");
 
-    // No CREATE TABLE query should be executed
+    // Only the schema version is read; no CREATE TABLE query should be executed
     let queries = mock_client.get_queries();
-    assert_eq!(queries.len(), 0, "Should not create table if it exists");
+    assert_eq!(queries.len(), 1, "Should only read the schema version");
+    assert!(!queries[0].contains("CREATE TABLE"));
 }
 
 #[test]
@@ -190,7 +215,12 @@ fn test_database_integration_environment_variables() {
     }
     let result = Database::new();
     assert!(result.is_err());
-    assert!(result.unwrap_err().to_string().contains("SUPABASE_SECRET_KEY"));
+    assert!(
+        result
+            .unwrap_err()
+            .to_string()
+            .contains("SUPABASE_SECRET_KEY")
+    );
 
     // Test invalid URL scheme
     unsafe {
@@ -231,7 +261,7 @@ fn test_database_integration_table_structure() {
 
     // Verify table structure
     let queries = mock_client.get_queries();
-    let create_table_query = &queries[0];
+    let create_table_query = &queries[1];
 
     // Check all required columns are present
     assert!(create_table_query.contains("id UUID PRIMARY KEY"));
@@ -245,10 +275,10 @@ fn test_database_integration_table_structure() {
     assert!(create_table_query.contains("updated_at TIMESTAMPTZ DEFAULT NOW()"));
 
     // Check indexes are created
-    assert!(queries.len() >= 4, "Should create table and 3 indexes");
-    assert!(queries[1].contains("idx_items_pub_date"));
-    assert!(queries[2].contains("idx_items_created_at"));
-    assert!(queries[3].contains("idx_items_is_read"));
+    assert!(queries.len() >= 5, "Should create table and 3 indexes");
+    assert!(queries[2].contains("idx_items_pub_date"));
+    assert!(queries[3].contains("idx_items_created_at"));
+    assert!(queries[4].contains("idx_items_is_read"));
 }
 
 #[test]
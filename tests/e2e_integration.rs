@@ -0,0 +1,191 @@
+use clio::Fetcher;
+use clio::config::{Dedup, MuteRule};
+use clio::testing::{self, FakeFeed, FakeFeeds, MemoryStore};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_pull_stores_every_feed() {
+    let feeds = FakeFeeds::uniform(8).await;
+    let store = Arc::new(MemoryStore::new());
+
+    let pulled = testing::pull(&feeds.config(), Fetcher::new(), store.clone())
+        .await
+        .unwrap();
+    assert_eq!(pulled.stats.successful_sources, 8);
+    assert_eq!(pulled.items.len(), 8 * 5);
+    assert_eq!(pulled.last_pull.items.len(), 8 * 5);
+
+    let links: Vec<String> = store
+        .items()
+        .into_iter()
+        .filter(|item| item.source_name == "Feed 3")
+        .map(|item| item.link)
+        .collect();
+    assert_eq!(links, feeds.links("Feed 3"));
+}
+
+#[tokio::test]
+async fn test_rotating_items_are_stored_once() {
+    let feeds = FakeFeeds::start(vec![
+        FakeFeed::new("Daily").with_new_per_round(2),
+        FakeFeed::new("Quiet").with_new_per_round(0),
+    ])
+    .await;
+    let store = Arc::new(MemoryStore::new());
+    let config = feeds.config();
+
+    let pulled = testing::pull(&config, Fetcher::new(), store.clone())
+        .await
+        .unwrap();
+    assert_eq!(pulled.items.len(), 10);
+
+    let oldest = feeds.links("Daily").pop().unwrap();
+    feeds.advance();
+    assert!(!feeds.links("Daily").contains(&oldest));
+    let pulled = testing::pull(&config, Fetcher::new(), store.clone())
+        .await
+        .unwrap();
+    // Only the changed feed is parsed, and only its new posts are kept
+    assert_eq!(pulled.last_pull.items.len(), 5);
+    let titles: Vec<&str> = pulled
+        .items
+        .iter()
+        .map(|item| item.title.as_str())
+        .collect();
+    assert_eq!(titles, ["Daily post 7", "Daily post 6"]);
+
+    // The feed that did not change was fetched but not parsed again
+    assert_eq!(pulled.stats.not_modified(), 1);
+    let quiet = pulled
+        .stats
+        .fetches
+        .iter()
+        .find(|fetch| fetch.source_name == "Quiet")
+        .unwrap();
+    assert!(quiet.not_modified && quiet.succeeded);
+
+    // Posts that rotated out of the feed stay stored
+    let stored = store.items();
+    assert_eq!(stored.len(), 12);
+    assert!(stored.iter().any(|item| item.link == oldest));
+    assert_eq!(feeds.requests().await, 4);
+}
+
+#[tokio::test]
+async fn test_slow_and_failing_feeds_fail_alone() {
+    let feeds = FakeFeeds::start(vec![
+        FakeFeed::new("Fast"),
+        FakeFeed::new("Slow").with_latency(Duration::from_secs(3)),
+        FakeFeed::new("Broken").failing(503),
+    ])
+    .await;
+    let store = Arc::new(MemoryStore::new());
+
+    let pulled = testing::pull(&feeds.config(), Fetcher::with_timeout(1), store.clone())
+        .await
+        .unwrap();
+    assert_eq!(pulled.stats.successful_sources, 1);
+    assert_eq!(pulled.stats.failed_sources, 2);
+    // Failures land in the fetch history along with successes
+    let mut history: Vec<(String, bool)> = store
+        .fetches()
+        .into_iter()
+        .map(|fetch| (fetch.source_name, fetch.succeeded))
+        .collect();
+    history.sort();
+    assert_eq!(
+        history,
+        [
+            ("Broken".to_string(), false),
+            ("Fast".to_string(), true),
+            ("Slow".to_string(), false)
+        ]
+    );
+    assert_eq!(store.items().len(), 5);
+    assert!(store.items().iter().all(|item| item.source_name == "Fast"));
+}
+
+#[tokio::test]
+async fn test_pull_applies_mute_rules() {
+    let feeds = FakeFeeds::start(vec![FakeFeed::new("Noisy"), FakeFeed::new("Calm")]).await;
+    let mut config = feeds.config();
+    config.mutes.push(MuteRule {
+        keyword: "noisy".to_string(),
+    });
+    let store = Arc::new(MemoryStore::new());
+
+    let pulled = testing::pull(&config, Fetcher::new(), store.clone())
+        .await
+        .unwrap();
+    assert_eq!(pulled.muted, 5);
+    // Muted items are never stored, though rules test still sees them
+    assert_eq!(pulled.last_pull.items.len(), 10);
+    assert_eq!(store.items().len(), 5);
+    assert!(
+        store
+            .items()
+            .iter()
+            .all(|item| item.title.starts_with("Calm post"))
+    );
+}
+
+#[tokio::test]
+async fn test_fingerprinted_feeds_are_stored_once() {
+    let feeds = FakeFeeds::start(vec![
+        FakeFeed::new("Rewriter")
+            .rewriting_links()
+            .with_new_per_round(0),
+    ])
+    .await;
+    let mut config = feeds.config();
+    config.sources.rss[0].dedup = Dedup::Fingerprint;
+    let store = Arc::new(MemoryStore::new());
+
+    let pulled = testing::pull(&config, Fetcher::new(), store.clone())
+        .await
+        .unwrap();
+    assert_eq!(pulled.items.len(), 5);
+
+    // Every link differs from the first pull, so only the fingerprint matches
+    let pulled = testing::pull(&config, Fetcher::new(), store.clone())
+        .await
+        .unwrap();
+    assert_eq!(pulled.last_pull.items.len(), 5);
+    assert!(pulled.items.is_empty());
+    assert_eq!(store.items().len(), 5);
+}
+
+#[tokio::test]
+async fn test_pull_archives_expired_items_when_asked() {
+    let feeds = FakeFeeds::start(vec![
+        FakeFeed::new("Deals").expiring(),
+        FakeFeed::new("Blog"),
+    ])
+    .await;
+    let mut config = feeds.config();
+    let store = Arc::new(MemoryStore::new());
+
+    testing::pull(&config, Fetcher::new(), store.clone())
+        .await
+        .unwrap();
+    assert!(store.archived().is_empty());
+
+    config.settings.archive_expired = true;
+    feeds.advance();
+    testing::pull(&config, Fetcher::new(), store.clone())
+        .await
+        .unwrap();
+    // Expired items stored by earlier pulls are archived along with new ones
+    let mut archived: Vec<String> = store.archived().into_iter().collect();
+    archived.sort();
+    let mut deals: Vec<String> = store
+        .items()
+        .into_iter()
+        .filter(|item| item.source_name == "Deals")
+        .map(|item| item.link)
+        .collect();
+    deals.sort();
+    assert_eq!(deals.len(), 6);
+    assert_eq!(archived, deals);
+}
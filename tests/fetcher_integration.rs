@@ -55,6 +55,7 @@ fn create_test_item(id: &str, source_name: &str) -> Item {
         link: format!("https://example.com/article/{id}"),
         summary: Some(format!("This is a test summary for article {id}")),
         pub_date: Some(Utc::now()),
+        ..Default::default()
     }
 }
 
@@ -100,6 +101,43 @@ async fn test_fetcher_with_real_network_mocks() {
     assert_eq!(items[0].title, "Article 1");
 }
 
+#[tokio::test]
+async fn test_fetcher_reports_connection_reuse() {
+    use clio::http::{FeedClient, RequestGuard};
+    use clio::source::rss::RssSource;
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(
+            r#"<rss version="2.0"><channel><title>t</title><link>https://example.com</link><description>d</description></channel></rss>"#,
+        ))
+        .mount(&mock_server)
+        .await;
+
+    // A host name, so new connections go through the resolver and are counted
+    let port = mock_server.address().port();
+    let client = FeedClient::new(RequestGuard::new(true));
+    let sources: Vec<Arc<dyn Source>> = (0..4)
+        .map(|i| {
+            Arc::new(
+                RssSource::new(
+                    format!("Feed {i}"),
+                    format!("http://localhost:{port}/feed{i}.rss"),
+                )
+                .with_client(client.clone()),
+            ) as Arc<dyn Source>
+        })
+        .collect();
+
+    let fetcher = Fetcher::new().with_client(client);
+    let (_, stats) = fetcher.fetch_all(sources).await;
+
+    assert_eq!(stats.successful_sources, 4);
+    let connections = stats.connections.unwrap();
+    assert_eq!(connections.requests, 4);
+    assert!((1..=4).contains(&connections.connections));
+}
+
 #[tokio::test]
 async fn test_parallel_fetch_with_varying_delays() {
     let sources: Vec<Arc<dyn Source>> = (0..10)
@@ -287,6 +325,7 @@ async fn test_fetch_result_processing() {
     stats.process_result(&clio::FetchResult::Error {
         source_name: "Source2".to_string(),
         error: "Network error".to_string(),
+        kind: clio::error::ErrorKind::Network,
     });
 
     stats.process_result(&clio::FetchResult::Success {